@@ -0,0 +1,70 @@
+//! A bot with a command of its own next to the built-in ones.
+//!
+//! `!greet @someone [message...]` greets a member. Its [`Param`]s give it a usage
+//! string, a matching slash command definition and an error when the user is
+//! left out.
+//!
+//! ```sh
+//! DISCORD_TOKEN=... cargo run --example custom_command
+//! ```
+
+use async_trait::async_trait;
+
+use rust_discord_bot_hander::bot::{load_config, load_token, Bot};
+use rust_discord_bot_hander::commands;
+use rust_discord_bot_hander::framework::command_handler::{Command, CommandContext, CommandResult};
+use rust_discord_bot_hander::framework::{Param, ParamKind};
+use rust_discord_bot_hander::utils::helpers::{parse_user, send_info};
+use rust_discord_bot_hander::utils::logging;
+
+/// Greets a member, with an optional message.
+struct GreetCommand;
+
+#[async_trait]
+impl Command for GreetCommand {
+    fn name(&self) -> &str {
+        "greet"
+    }
+
+    fn description(&self) -> &str {
+        "Say hello to someone"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            Param::required("user", ParamKind::User, "Who to greet"),
+            Param::optional("message", ParamKind::Text, "What to tell them").length(1, 200),
+        ]
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        // The handler already checked that the first argument is a user
+        let user_id = parse_user(&ctx.args[0]).ok_or("Expected a user")?;
+        let message = match ctx.args[1..].join(" ") {
+            message if message.is_empty() => "Welcome!".to_string(),
+            message => message,
+        };
+
+        send_info(
+            ctx.ctx,
+            ctx.msg,
+            "👋 Hello",
+            format!("<@{}>, {}", user_id, message),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let config = load_config()?;
+    logging::init(&config.logging);
+
+    let token = load_token()?;
+    Bot::new(token, config)
+        .with_commands(commands::register_commands)
+        .register_command(GreetCommand)
+        .start()
+        .await
+}
@@ -0,0 +1,70 @@
+//! A bot that reacts to events with its own handler.
+//!
+//! Event handlers are registered by plugins, so this one comes with a plugin that
+//! only registers the handler. It waves at messages that say hello.
+//!
+//! ```sh
+//! DISCORD_TOKEN=... cargo run --example custom_event_handler
+//! ```
+
+use async_trait::async_trait;
+use serenity::model::channel::{Message, ReactionType};
+use serenity::prelude::*;
+use tracing::debug;
+
+use rust_discord_bot_hander::bot::{load_config, load_token, Bot};
+use rust_discord_bot_hander::commands;
+use rust_discord_bot_hander::framework::event_handler::{EventDispatcher, EventHandler};
+use rust_discord_bot_hander::framework::Plugin;
+use rust_discord_bot_hander::utils::logging;
+
+/// Reacts with a wave to messages that start with "hello".
+struct WaveHandler;
+
+#[async_trait]
+impl EventHandler for WaveHandler {
+    fn event_type(&self) -> &'static str {
+        "message"
+    }
+
+    async fn on_message(&self, ctx: Context, msg: &Message) {
+        if msg.author.bot || !msg.content.to_lowercase().starts_with("hello") {
+            return;
+        }
+        let wave = ReactionType::Unicode("👋".to_string());
+        if let Err(e) = msg.react(&ctx.http, wave).await {
+            debug!("Couldn't wave at message {}: {}", msg.id, e);
+        }
+    }
+}
+
+/// Registers [`WaveHandler`]. Server admins can turn it off with `modules`.
+struct WavePlugin;
+
+#[async_trait]
+impl Plugin for WavePlugin {
+    fn name(&self) -> &'static str {
+        "wave"
+    }
+
+    fn description(&self) -> &'static str {
+        "Waves at people who say hello"
+    }
+
+    fn register_events(&self, dispatcher: &mut EventDispatcher) {
+        dispatcher.register_handler(WaveHandler);
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let config = load_config()?;
+    logging::init(&config.logging);
+
+    let token = load_token()?;
+    Bot::new(token, config)
+        .with_commands(commands::register_commands)
+        .with_plugin(WavePlugin)
+        .start()
+        .await
+}
@@ -0,0 +1,169 @@
+//! A bot with a plugin that brings its own store, command and event handler.
+//!
+//! The plugin counts how many messages each member sends and `!messages [user]`
+//! shows the count. The store is opened in [`Plugin::init`] and put into the
+//! shared state, where the command gets it with [`Inject`] and the handler reads
+//! it from the client data.
+//!
+//! ```sh
+//! DISCORD_TOKEN=... cargo run --example custom_plugin
+//! ```
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+use tracing::warn;
+
+use rust_discord_bot_hander::bot::{load_config, load_token, Bot};
+use rust_discord_bot_hander::commands;
+use rust_discord_bot_hander::framework::command_handler::{
+    Command, CommandContext, CommandHandler, CommandResult,
+};
+use rust_discord_bot_hander::framework::event_handler::{EventDispatcher, EventHandler};
+use rust_discord_bot_hander::framework::{Inject, Param, ParamKind, Plugin, PluginContext};
+use rust_discord_bot_hander::storage::JsonStore;
+use rust_discord_bot_hander::utils::helpers::{parse_user, send_info};
+use rust_discord_bot_hander::utils::logging;
+
+/// Messages sent by each user, by user ID.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct MessageCounts {
+    #[serde(default)]
+    users: HashMap<u64, u64>,
+}
+
+/// Key for the message counts in the shared state.
+struct MessageCountsKey;
+
+impl TypeMapKey for MessageCountsKey {
+    type Value = Arc<JsonStore<MessageCounts>>;
+}
+
+/// Shows how many messages a member has sent.
+struct MessagesCommand {
+    store: Arc<JsonStore<MessageCounts>>,
+}
+
+impl MessagesCommand {
+    fn new(Inject(store): Inject<MessageCountsKey>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Command for MessagesCommand {
+    fn name(&self) -> &str {
+        "messages"
+    }
+
+    fn description(&self) -> &str {
+        "Show how many messages someone has sent"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![Param::optional(
+            "user",
+            ParamKind::User,
+            "Whose messages to count, yourself by default",
+        )]
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let user_id = ctx
+            .args
+            .first()
+            .and_then(|arg| parse_user(arg))
+            .unwrap_or(ctx.msg.author.id);
+        let count = self
+            .store
+            .read()
+            .await
+            .users
+            .get(&user_id.0)
+            .copied()
+            .unwrap_or(0);
+
+        send_info(
+            ctx.ctx,
+            ctx.msg,
+            "💬 Messages",
+            format!("<@{}> has sent {} messages.", user_id, count),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Counts every message that isn't from a bot.
+struct CountHandler;
+
+#[async_trait]
+impl EventHandler for CountHandler {
+    fn event_type(&self) -> &'static str {
+        "message"
+    }
+
+    async fn on_message(&self, ctx: Context, msg: &Message) {
+        if msg.author.bot {
+            return;
+        }
+        let store = {
+            let data = ctx.data.read().await;
+            data.get::<MessageCountsKey>().cloned()
+        };
+        if let Some(store) = store {
+            let user_id = msg.author.id.0;
+            let counted = store
+                .update(|counts| *counts.users.entry(user_id).or_default() += 1)
+                .await;
+            if let Err(e) = counted {
+                warn!("Couldn't save the message count: {}", e);
+            }
+        }
+    }
+}
+
+/// Message counting, which server admins can turn off with `modules`.
+struct MessageCountPlugin;
+
+#[async_trait]
+impl Plugin for MessageCountPlugin {
+    fn name(&self) -> &'static str {
+        "message_count"
+    }
+
+    fn description(&self) -> &'static str {
+        "Counts the messages each member sends"
+    }
+
+    async fn init(&self, ctx: PluginContext<'_>) -> io::Result<()> {
+        let counts = Arc::new(ctx.storage.open("message_counts").await?);
+        ctx.state.insert::<MessageCountsKey>(counts);
+        Ok(())
+    }
+
+    fn register_commands(&self, handler: &mut CommandHandler) {
+        handler.register_with_state(MessagesCommand::new);
+    }
+
+    fn register_events(&self, dispatcher: &mut EventDispatcher) {
+        dispatcher.register_handler(CountHandler);
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let config = load_config()?;
+    logging::init(&config.logging);
+
+    let token = load_token()?;
+    Bot::new(token, config)
+        .with_commands(commands::register_commands)
+        .with_plugin(MessageCountPlugin)
+        .start()
+        .await
+}
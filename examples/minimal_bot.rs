@@ -0,0 +1,22 @@
+//! The smallest useful bot: the built-in commands, configured from
+//! `config/config.toml` and logged in with `DISCORD_TOKEN`.
+//!
+//! ```sh
+//! DISCORD_TOKEN=... cargo run --example minimal_bot
+//! ```
+
+use rust_discord_bot_hander::bot::{load_config, load_token, Bot};
+use rust_discord_bot_hander::commands;
+use rust_discord_bot_hander::utils::logging;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let config = load_config()?;
+    logging::init(&config.logging);
+
+    let token = load_token()?;
+    Bot::new(token, config)
+        .with_commands(commands::register_commands)
+        .start()
+        .await
+}
@@ -1,17 +1,57 @@
 //! The main bot implementation.
 
+use serenity::client::bridge::gateway::event::ShardStageUpdateEvent;
+use serenity::http::ratelimiting::RatelimitInfo;
+use serenity::http::Http;
+use serenity::model::application::interaction::Interaction;
 use serenity::model::channel::Message;
+use serenity::model::event::{ChannelPinsUpdateEvent, Event, MessageUpdateEvent, ResumedEvent};
 use serenity::model::gateway::Ready;
+use serenity::model::guild::{Guild, Member};
+use serenity::model::id::{ChannelId, GuildId, MessageId};
+use serenity::model::user::User;
+use serenity::model::voice::VoiceState;
 use serenity::prelude::*;
 use std::env;
 use std::sync::Arc;
-use tracing::{error, info};
+use std::time::Duration;
+use tracing::{error, info, warn};
 
-use crate::events::{MessageHandler, ReadyHandler};
-use crate::framework::command_handler::CommandHandler;
+use crate::events::CommandSyncHandler;
+use crate::framework::app_commands::{self, SyncScope};
+use crate::framework::break_glass::{BreakGlass, BreakGlassKey};
+use crate::framework::command_handler::{CommandHandler, CommandNamesKey};
 use crate::framework::event_handler::EventDispatcher;
-use crate::models::BotConfig;
+use crate::framework::intents;
+use crate::framework::plugin::{Modules, ModulesKey, Plugin, PluginContext};
+use crate::framework::scripts::{Scripts, ScriptsKey};
+use crate::models::{
+    Analytics, AnalyticsKey, AuditKey, AutoResponseKey, BotConfig, EventKey, FeedKey, GrowthKey,
+    GuildConfigKey, KnowledgeBaseKey, LfgKey, MaintenanceKey, MessageCache, MessageCacheKey,
+    MirrorKey, ModerationKey, ModmailKey, NotificationKey, PinArchiveKey, PinVoteKey, PremiumKey,
+    QuoteKey, RolePersistenceKey, ShardHealth, ShardHealthKey, TodoKey, VotesKey,
+};
+use crate::storage::{migrations, KvKey, KvStore, Retention, Storage};
+use crate::utils::bot_lists::StatsPoster;
+use crate::utils::devlookup::{DevLookup, DevLookupKey};
+use crate::utils::diagnostics::{Diagnostics, DiagnosticsKey};
+use crate::utils::dms::{DmService, DmServiceKey};
+use crate::utils::entitlements::{EntitlementSync, EntitlementSyncKey};
+use crate::utils::exchange::{Exchange, ExchangeKey};
+use crate::utils::feeds::FeedWatcher;
+use crate::utils::heartbeat::Heartbeat;
 use crate::utils::helpers::BotConfigKey;
+use crate::utils::lfg::LfgSweeper;
+use crate::utils::limits::{Limits, LimitsKey};
+use crate::utils::piston::{Piston, PistonKey};
+use crate::utils::reddit::{Reddit, RedditKey};
+use crate::utils::reminders::Reminders;
+use crate::utils::rest::{RestPolicy, RestPolicyKey};
+use crate::utils::rest_usage::RestUsage;
+use crate::utils::steam::{Steam, SteamKey};
+use crate::utils::webhooks::{Webhooks, WebhooksKey};
+use crate::web::votes::VoteWebhook;
+use crate::web::WebServer;
 
 /// The main bot structure.
 pub struct Bot {
@@ -21,21 +61,49 @@ pub struct Bot {
     config: BotConfig,
     /// The command handler for processing commands.
     command_handler: CommandHandler,
+    /// State inserted into the client data before startup.
+    ///
+    /// Built-in stores are added when the bot starts and replace user state with the same key.
+    state: TypeMap,
+    /// Optional feature areas, set up when the bot starts.
+    plugins: Vec<Arc<dyn Plugin>>,
 }
 
 impl Bot {
     /// Create a new Bot instance.
     pub fn new(token: String, config: BotConfig) -> Self {
         // Create command handler with the configured prefix
-        let command_handler = CommandHandler::new().with_prefix(config.prefix.clone());
+        let mut command_handler = CommandHandler::new()
+            .with_prefix(config.prefix.clone())
+            .with_cooldown(Duration::from_secs(config.commands.cooldown))
+            .with_timeout(Duration::from_secs(config.commands.timeout))
+            .with_max_concurrent(config.commands.max_concurrent);
+        if let Some(prefix) = config.dev_prefix() {
+            command_handler = command_handler.with_dev_prefix(prefix);
+        }
 
         Self {
             token,
             config,
             command_handler,
+            state: TypeMap::new(),
+            plugins: crate::plugins::builtin(),
         }
     }
 
+    /// Add shared state that commands and handlers can read through
+    /// [`crate::framework::State`].
+    pub fn with_state<K: TypeMapKey>(mut self, value: K::Value) -> Self {
+        self.state.insert::<K>(value);
+        self
+    }
+
+    /// Add a plugin on top of the ones compiled in with Cargo features.
+    pub fn with_plugin(mut self, plugin: impl Plugin + 'static) -> Self {
+        self.plugins.push(Arc::new(plugin));
+        self
+    }
+
     /// Register a command with the bot.
     pub fn register_command(
         mut self,
@@ -45,31 +113,307 @@ impl Bot {
         self
     }
 
+    /// Register commands using a registration function, such as
+    /// [`crate::commands::register_commands`].
+    pub fn with_commands(mut self, register: impl FnOnce(&mut CommandHandler)) -> Self {
+        register(&mut self.command_handler);
+        self
+    }
+
     /// Start the bot.
-    pub async fn start(self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    pub async fn start(mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if self.config.dev.enabled {
+            info!(
+                "Development mode is on, syncing commands to {} guilds",
+                self.config.dev.guilds.len()
+            );
+        }
+
+        // Open persistent stores
+        let storage = Storage::from_config(&self.config).await?;
+        info!("Storing data in {}", storage.backend().describe());
+        let applied = migrations::migrate(&storage).await?;
+        if !applied.is_empty() {
+            info!("Applied {} data migrations", applied.len());
+        }
+        let guild_configs = Arc::new(storage.open("guild_config").await?);
+        let moderation = Arc::new(storage.open_durable("moderation").await?);
+        let modmail = Arc::new(storage.open("modmail").await?);
+        let maintenance = Arc::new(storage.open("maintenance").await?);
+        let audit = Arc::new(storage.open("audit").await?);
+        let pin_archive = Arc::new(storage.open("pin_archive").await?);
+        let auto_responses = Arc::new(storage.open("auto_responses").await?);
+        let role_persistence = Arc::new(storage.open("role_persistence").await?);
+        let premium = Arc::new(storage.open_durable("premium").await?);
+        let votes = Arc::new(storage.open("votes").await?);
+        let mirrors = Arc::new(storage.open("mirrors").await?);
+        let growth = Arc::new(storage.open("growth").await?);
+        let pin_votes = Arc::new(storage.open("pin_votes").await?);
+        let feeds = Arc::new(storage.open("feeds").await?);
+        let quotes = Arc::new(storage.open("quotes").await?);
+        let todos = Arc::new(storage.open("todos").await?);
+        let knowledge_base = Arc::new(storage.open("knowledge_base").await?);
+        let events = Arc::new(storage.open("events").await?);
+        let lfg = Arc::new(storage.open("lfg").await?);
+        let notifications = Arc::new(storage.open("notifications").await?);
+        let analytics = Arc::new(Analytics::new(
+            self.config.analytics.clone(),
+            Arc::new(storage.open("analytics").await?),
+        ));
+        let kv = Arc::new(KvStore::new(storage.open("kv").await?));
+        kv.purge_expired().await?;
+
+        // Save changes kept in memory once an unreachable backend is back
+        storage.spawn_recovery();
+
+        // Purge data past its retention period in the background
+        Retention::new(
+            self.config.retention.clone(),
+            guild_configs.clone(),
+            moderation.clone(),
+            modmail.clone(),
+        )
+        .spawn();
+
+        // Save emoji and channel activity counts in batches
+        analytics.clone().spawn();
+
+        let exchange = Arc::new(Exchange::new(self.config.exchange.clone()));
+        let piston = Arc::new(Piston::new(self.config.run.clone()));
+        let dev_lookup = Arc::new(DevLookup::new());
+        let steam = Arc::new(Steam::new());
+        let reddit = Arc::new(Reddit::new());
+
+        // Resolve premium limits and keep entitlements in sync in the background
+        let limits = Arc::new(Limits::new(self.config.premium.clone(), premium.clone()));
+        let dms = Arc::new(DmService::new(notifications.clone()));
+        let entitlements = Arc::new(EntitlementSync::new(
+            self.token.clone(),
+            &self.config.premium,
+            premium.clone(),
+        ));
+        entitlements.clone().spawn();
+
+        // Receive webhooks such as bot list votes
+        if let Some(bind) = &self.config.web.bind {
+            let webhook =
+                VoteWebhook::new(self.config.votes.clone(), votes.clone(), premium.clone());
+            WebServer::new(bind, webhook)?.spawn()?;
+        }
+
+        let bot_lists = self.config.bot_lists.clone();
+        let monitoring = self.config.monitoring.clone();
+        let feeds_config = self.config.feeds.clone();
+        let intents_config = self.config.intents.clone();
+        let run_diagnostics = self.config.diagnostics.on_startup;
+
+        // Create in-memory caches
+        let message_cache = Arc::new(MessageCache::new(
+            self.config.message_cache.capacity,
+            Duration::from_secs(self.config.message_cache.ttl),
+        ));
+
+        let shard_health = Arc::new(ShardHealth::new(
+            self.config.shard_health.storm_threshold,
+            Duration::from_secs(self.config.shard_health.storm_window),
+        ));
+
+        // Load the owner-defined script commands
+        let scripts = Arc::new(Scripts::new(self.config.scripts.clone()));
+        scripts.load();
+
+        // Check the setup again at startup and with `diagnose`
+        let diagnostics = Arc::new(Diagnostics::new(
+            storage.clone(),
+            guild_configs.clone(),
+            message_cache.clone(),
+        ));
+
+        // Share the bot's webhooks between the features that send through them
+        let webhooks = Arc::new(Webhooks::new());
+
+        // Share one circuit breaker between all REST calls
+        let rest_policy = Arc::new(RestPolicy::new(self.config.rest.clone()));
+        let rest_usage = rest_policy.usage();
+
+        // Set up the plugins, which open their own stores and register their commands
+        for plugin in &self.plugins {
+            let applied =
+                migrations::migrate_plugin(&storage, plugin.name(), plugin.migrations()).await?;
+            if !applied.is_empty() {
+                info!(
+                    "Applied {} data migrations for {}",
+                    applied.len(),
+                    plugin.name()
+                );
+            }
+            plugin
+                .init(PluginContext {
+                    config: &self.config,
+                    storage: &storage,
+                    state: &mut self.state,
+                })
+                .await?;
+            self.command_handler.register_plugin(plugin.as_ref());
+            info!("Loaded plugin {}", plugin.name());
+        }
+
+        // Track which guilds turned which plugins off
+        let modules = Arc::new(Modules::new(self.plugins.clone()));
+        modules.load(&*guild_configs.read().await);
+        self.command_handler.set_modules(modules.clone());
+
+        // Add the configuration and stores to the shared state
+        let max_in_flight = self.config.dispatch.max_in_flight;
+        let panic_threshold = self.config.dispatch.panic_threshold;
+        let command_sync = self.config.commands.sync_on_startup.then(|| {
+            CommandSyncHandler::new(
+                app_commands::local_commands(&self.plugins),
+                SyncScope::for_config(&self.config),
+            )
+        });
+        self.state.insert::<BotConfigKey>(self.config);
+        self.state.insert::<GuildConfigKey>(guild_configs);
+        self.state.insert::<ModerationKey>(moderation);
+        self.state.insert::<ModmailKey>(modmail);
+        self.state.insert::<MaintenanceKey>(maintenance);
+        self.state.insert::<AuditKey>(audit);
+        self.state.insert::<PinArchiveKey>(pin_archive);
+        self.state.insert::<AutoResponseKey>(auto_responses);
+        self.state.insert::<RolePersistenceKey>(role_persistence);
+        self.state.insert::<PremiumKey>(premium);
+        self.state.insert::<VotesKey>(votes);
+        self.state.insert::<MirrorKey>(mirrors);
+        self.state.insert::<AnalyticsKey>(analytics);
+        self.state.insert::<GrowthKey>(growth);
+        self.state.insert::<PinVoteKey>(pin_votes);
+        self.state.insert::<ExchangeKey>(exchange);
+        self.state.insert::<PistonKey>(piston);
+        self.state.insert::<DevLookupKey>(dev_lookup);
+        self.state.insert::<SteamKey>(steam);
+        self.state.insert::<RedditKey>(reddit.clone());
+        self.state.insert::<FeedKey>(feeds.clone());
+        self.state.insert::<QuoteKey>(quotes);
+        self.state.insert::<TodoKey>(todos.clone());
+        self.state.insert::<KnowledgeBaseKey>(knowledge_base);
+        self.state.insert::<EventKey>(events.clone());
+        self.state.insert::<LfgKey>(lfg.clone());
+        self.state.insert::<NotificationKey>(notifications);
+        self.state.insert::<DmServiceKey>(dms.clone());
+        self.state.insert::<LimitsKey>(limits);
+        self.state.insert::<EntitlementSyncKey>(entitlements);
+        self.state.insert::<KvKey>(kv);
+        self.state.insert::<MessageCacheKey>(message_cache);
+        self.state
+            .insert::<BreakGlassKey>(Arc::new(BreakGlass::default()));
+        self.state.insert::<ShardHealthKey>(shard_health.clone());
+        self.state.insert::<RestPolicyKey>(rest_policy.clone());
+        self.state.insert::<WebhooksKey>(webhooks);
+        self.state.insert::<ScriptsKey>(scripts);
+        self.state.insert::<ModulesKey>(modules.clone());
+        self.state.insert::<DiagnosticsKey>(diagnostics.clone());
+
+        // Build commands that depend on the shared state
+        self.command_handler.resolve_state(&self.state)?;
+        let command_names = Arc::new(self.command_handler.reserved_names());
+        self.state.insert::<CommandNamesKey>(command_names);
+
         // Create the event handler
-        let mut event_dispatcher = EventDispatcher::new();
+        let mut event_dispatcher = EventDispatcher::with_max_in_flight(max_in_flight);
+        event_dispatcher.set_modules(modules);
+        event_dispatcher.set_panic_threshold(panic_threshold);
 
         // Register event handlers
-        event_dispatcher.register_handler(ReadyHandler);
-        event_dispatcher.register_handler(MessageHandler::new(self.command_handler));
+        crate::events::register_events(&mut event_dispatcher, self.command_handler);
+        for plugin in &self.plugins {
+            event_dispatcher.register_plugin(plugin.as_ref());
+        }
+        if let Some(handler) = command_sync {
+            event_dispatcher.register_handler(handler);
+        }
+
+        // Ask only for the events something listens to
+        let derived = self
+            .plugins
+            .iter()
+            .fold(event_dispatcher.intents(), |intents, plugin| {
+                intents | plugin.intents()
+            });
+        let intents = intents::resolve(derived, &intents_config)?;
+        info!("Connecting with gateway intents: {:?}", intents);
+        let privileged = intents & GatewayIntents::privileged();
+        if !privileged.is_empty() {
+            warn!(
+                "Requesting privileged intents {:?}, which must be enabled for the bot in the Discord developer portal",
+                privileged
+            );
+        }
 
-        // Set up the client with the token from environment
-        let intents = GatewayIntents::GUILD_MESSAGES
-            | GatewayIntents::DIRECT_MESSAGES
-            | GatewayIntents::MESSAGE_CONTENT
-            | GatewayIntents::GUILDS;
+        diagnostics.set_intents(intents);
 
-        let mut client = Client::builder(&self.token, intents)
+        // Voice connections go through the music plugin's songbird instance
+        #[cfg(feature = "music")]
+        let voice = self.state.get::<songbird::SongbirdKey>().cloned();
+
+        let dispatcher = Arc::new(event_dispatcher);
+        let builder = Client::builder(&self.token, intents)
+            .type_map(self.state)
             .event_handler_arc(Arc::new(BotEventHandler {
-                dispatcher: event_dispatcher,
+                dispatcher: dispatcher.clone(),
+                rest_usage: rest_usage.clone(),
             }))
-            .await?;
+            .raw_event_handler(BotRawEventHandler { dispatcher });
+        #[cfg(feature = "music")]
+        let builder = match voice {
+            Some(voice) => builder.voice_manager_arc(voice),
+            None => builder,
+        };
+        let mut client = builder.await?;
+
+        // Post server counts to bot lists with an API key set
+        for poster in StatsPoster::from_secrets(
+            &bot_lists,
+            client.cache_and_http.cache.clone(),
+            rest_usage.clone(),
+        ) {
+            poster.spawn();
+        }
+
+        // Let an outside monitor know the bot is alive
+        if let Some(heartbeat) = Heartbeat::new(
+            &monitoring,
+            shard_health,
+            client.cache_and_http.cache.clone(),
+        ) {
+            heartbeat.spawn();
+        }
+
+        // Announce new posts from subreddit feeds
+        FeedWatcher::new(
+            &feeds_config,
+            reddit,
+            feeds,
+            client.cache_and_http.http.clone(),
+            client.cache_and_http.cache.clone(),
+            rest_policy,
+        )
+        .spawn();
 
-        // Add the configuration to the client data
-        {
-            let mut data = client.data.write().await;
-            data.insert::<BotConfigKey>(self.config);
+        // Remind members about todo items when they're due and events before they start
+        Reminders::new(todos, events, dms, client.cache_and_http.http.clone()).spawn();
+
+        // Expire stale LFG posts and clean up after finished groups
+        LfgSweeper::new(
+            lfg,
+            client.cache_and_http.http.clone(),
+            client.cache_and_http.cache.clone(),
+            rest_usage,
+        )
+        .spawn();
+
+        // Report setup problems before connecting
+        if run_diagnostics {
+            diagnostics.run(&client.cache_and_http.http).await.log();
         }
 
         info!("Starting bot...");
@@ -84,11 +428,30 @@ impl Bot {
 /// Serenity event handler that dispatches events to our custom handlers.
 struct BotEventHandler {
     /// The event dispatcher.
-    dispatcher: EventDispatcher,
+    dispatcher: Arc<EventDispatcher>,
+    /// Told about rate limits, see [`RestUsage`].
+    rest_usage: Arc<RestUsage>,
+}
+
+/// Serenity raw event handler that feeds the dispatcher's `raw` handlers.
+struct BotRawEventHandler {
+    /// The event dispatcher.
+    dispatcher: Arc<EventDispatcher>,
+}
+
+#[serenity::async_trait]
+impl RawEventHandler for BotRawEventHandler {
+    async fn raw_event(&self, ctx: Context, event: Event) {
+        self.dispatcher.dispatch_raw(ctx, &event).await;
+    }
 }
 
 #[serenity::async_trait]
 impl EventHandler for BotEventHandler {
+    async fn ratelimit(&self, info: RatelimitInfo) {
+        self.rest_usage.record_rate_limit(info.global);
+    }
+
     async fn ready(&self, ctx: Context, ready: Ready) {
         self.dispatcher.dispatch_ready(ctx, &ready).await;
     }
@@ -97,9 +460,198 @@ impl EventHandler for BotEventHandler {
         self.dispatcher.dispatch_message(ctx, &msg).await;
     }
 
+    async fn message_update(
+        &self,
+        ctx: Context,
+        _old_if_available: Option<Message>,
+        _new: Option<Message>,
+        event: MessageUpdateEvent,
+    ) {
+        self.dispatcher.dispatch_message_update(ctx, &event).await;
+    }
+
+    async fn message_delete(
+        &self,
+        ctx: Context,
+        channel_id: ChannelId,
+        deleted_message_id: MessageId,
+        guild_id: Option<GuildId>,
+    ) {
+        self.dispatcher
+            .dispatch_message_delete(ctx, channel_id, deleted_message_id, guild_id)
+            .await;
+    }
+
+    async fn message_delete_bulk(
+        &self,
+        ctx: Context,
+        channel_id: ChannelId,
+        multiple_deleted_messages_ids: Vec<MessageId>,
+        guild_id: Option<GuildId>,
+    ) {
+        for message_id in multiple_deleted_messages_ids {
+            self.dispatcher
+                .dispatch_message_delete(ctx.clone(), channel_id, message_id, guild_id)
+                .await;
+        }
+    }
+
+    async fn guild_member_addition(&self, ctx: Context, new_member: Member) {
+        self.dispatcher
+            .dispatch_guild_member_add(ctx, new_member.guild_id, &new_member)
+            .await;
+    }
+
+    async fn guild_member_update(&self, ctx: Context, old: Option<Member>, new: Member) {
+        self.dispatcher
+            .dispatch_guild_member_update(ctx, old.as_ref(), &new)
+            .await;
+    }
+
+    async fn guild_member_removal(
+        &self,
+        ctx: Context,
+        guild_id: GuildId,
+        user: User,
+        member_data_if_available: Option<Member>,
+    ) {
+        self.dispatcher
+            .dispatch_guild_member_remove(ctx, guild_id, &user, member_data_if_available.as_ref())
+            .await;
+    }
+
+    async fn guild_ban_addition(&self, ctx: Context, guild_id: GuildId, banned_user: User) {
+        self.dispatcher
+            .dispatch_guild_ban_add(ctx, guild_id, &banned_user)
+            .await;
+    }
+
+    async fn guild_ban_removal(&self, ctx: Context, guild_id: GuildId, unbanned_user: User) {
+        self.dispatcher
+            .dispatch_guild_ban_remove(ctx, guild_id, &unbanned_user)
+            .await;
+    }
+
+    async fn guild_create(&self, ctx: Context, guild: Guild, is_new: bool) {
+        self.dispatcher
+            .dispatch_guild_create(ctx, &guild, is_new)
+            .await;
+    }
+
+    async fn channel_pins_update(&self, ctx: Context, pin: ChannelPinsUpdateEvent) {
+        self.dispatcher
+            .dispatch_channel_pins_update(ctx, &pin)
+            .await;
+    }
+
+    async fn voice_state_update(&self, ctx: Context, old: Option<VoiceState>, new: VoiceState) {
+        self.dispatcher
+            .dispatch_voice_state_update(ctx, old.as_ref(), &new)
+            .await;
+    }
+
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        self.dispatcher
+            .dispatch_interaction(ctx, &interaction)
+            .await;
+    }
+
+    async fn shard_stage_update(&self, ctx: Context, event: ShardStageUpdateEvent) {
+        self.dispatcher
+            .dispatch_shard_stage_update(ctx, &event)
+            .await;
+    }
+
+    async fn resume(&self, ctx: Context, event: ResumedEvent) {
+        self.dispatcher.dispatch_resume(ctx, &event).await;
+    }
+
     // Add more event handlers as needed
 }
 
+/// Run the `migrate` subcommand.
+///
+/// `migrate` applies pending data migrations and `migrate status` only reports the
+/// schema version. Neither connects to Discord.
+pub async fn run_migrate_command(
+    config: &BotConfig,
+    args: &[String],
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let storage = Storage::from_config(config).await?;
+
+    match args.first().map(String::as_str) {
+        None => {
+            let applied = migrations::migrate(&storage).await?;
+            info!(
+                "Applied {} migrations, data is at version {}",
+                applied.len(),
+                migrations::current_version()
+            );
+            for plugin in crate::plugins::builtin() {
+                let applied =
+                    migrations::migrate_plugin(&storage, plugin.name(), plugin.migrations())
+                        .await?;
+                info!("Applied {} migrations for {}", applied.len(), plugin.name());
+            }
+        }
+        Some("status") => {
+            let status = migrations::status(&storage).await?;
+            info!(
+                "Data is at version {} of {} ({} pending)",
+                status.version,
+                status.latest,
+                status.pending()
+            );
+            for plugin in crate::plugins::builtin() {
+                let version = migrations::plugin_version(&storage, plugin.name()).await?;
+                let latest = plugin.migrations().last().map_or(0, |m| m.version);
+                info!(
+                    "{} data is at version {} of {}",
+                    plugin.name(),
+                    version,
+                    latest
+                );
+            }
+        }
+        Some(other) => return Err(format!("Unknown migrate option `{}`", other).into()),
+    }
+
+    Ok(())
+}
+
+/// Run the `register-commands` command line mode.
+///
+/// Syncs the slash and context menu commands with Discord without starting the
+/// bot. With `--dry-run` the changes are only listed.
+pub async fn run_register_commands(
+    token: &str,
+    config: &BotConfig,
+    args: &[String],
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let dry_run = match args.first().map(String::as_str) {
+        None => false,
+        Some("--dry-run") => true,
+        Some(other) => return Err(format!("Unknown register-commands option `{}`", other).into()),
+    };
+
+    let http = Http::new(token);
+    let application = http.get_current_application_info().await?;
+    http.set_application_id(application.id.0);
+
+    let commands = app_commands::local_commands(&crate::plugins::builtin());
+    for scope in SyncScope::for_config(config) {
+        let changes = app_commands::sync(&http, scope, &commands, dry_run).await?;
+        info!(
+            "{} {} changes to the {} commands",
+            if dry_run { "Found" } else { "Applied" },
+            changes.len(),
+            scope
+        );
+    }
+
+    Ok(())
+}
+
 /// Load the bot token from the environment or a file.
 pub fn load_token() -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
     // Try to load from environment variable first
@@ -120,14 +672,17 @@ pub fn load_config() -> Result<BotConfig, Box<dyn std::error::Error + Send + Syn
     let config_path = "config/config.toml";
 
     match BotConfig::load(config_path) {
-        Ok(config) => {
+        Ok(mut config) => {
             info!("Loaded configuration from {}", config_path);
+            config.apply_env();
             Ok(config)
         }
         Err(e) => {
             error!("Failed to load configuration: {}", e);
             error!("Using default configuration");
-            Ok(BotConfig::default())
+            let mut config = BotConfig::default();
+            config.apply_env();
+            Ok(config)
         }
     }
 }
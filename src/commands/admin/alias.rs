@@ -0,0 +1,198 @@
+//! Alias command for giving commands extra names in a server.
+
+use async_trait::async_trait;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandNamesKey, CommandResult};
+use crate::framework::params::{usage_of, Param, ParamKind};
+use crate::framework::scripts::ScriptsKey;
+use crate::framework::state::Inject;
+use crate::models::aliases::{self, MAX_ALIASES, MAX_EXPANSION_LENGTH};
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::guild_config::{GuildConfigKey, GuildConfigs};
+use crate::storage::JsonStore;
+use crate::utils::helpers::{send_error, send_info, send_success, truncate};
+
+/// Adds, removes and lists the server's command aliases.
+pub struct AliasCommand {
+    store: Arc<JsonStore<GuildConfigs>>,
+}
+
+impl AliasCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<GuildConfigKey>) -> Self {
+        Self { store }
+    }
+
+    /// Why an alias can't be called `name` or stand for `command`, if it can't.
+    async fn collision(ctx: &CommandContext<'_>, name: &str, command: &str) -> Option<String> {
+        let (names, scripts) = {
+            let data = ctx.ctx.data.read().await;
+            (
+                data.get::<CommandNamesKey>().cloned(),
+                data.get::<ScriptsKey>().cloned(),
+            )
+        };
+        let is_script = |name: &str| scripts.as_ref().is_some_and(|s| s.get(name).is_some());
+        let is_command = |name: &str| names.as_ref().is_some_and(|n| n.contains(name));
+
+        if is_command(name) || is_script(name) {
+            return Some(format!("`{}` is already a command.", name));
+        }
+        if !is_command(command) && !is_script(command) {
+            return Some(format!(
+                "`{}` isn't a command. Aliases can't stand for other aliases.",
+                command
+            ));
+        }
+        None
+    }
+}
+
+#[async_trait]
+impl Command for AliasCommand {
+    fn name(&self) -> &str {
+        "alias"
+    }
+
+    fn description(&self) -> &str {
+        "Give a command another name in this server, optionally with arguments"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            Param::optional(
+                "action",
+                ParamKind::Word,
+                "add, remove or list, list if left out",
+            )
+            .choices(&["add", "remove", "list"]),
+            Param::optional("name", ParamKind::Word, "The alias to add or remove"),
+            Param::optional(
+                "command",
+                ParamKind::Text,
+                "The command the alias runs, with any arguments",
+            ),
+        ]
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["aliases"]
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Aliases can only be used in a server")?;
+
+        let action = ctx.args.first().map(|arg| arg.to_lowercase());
+        let name = ctx.args.get(1).map(|arg| arg.to_lowercase());
+        let (action, name) = match (action.as_deref(), name) {
+            (None | Some("list"), _) => {
+                let aliases = self.store.read().await.get(guild_id).aliases;
+                let body = if aliases.is_empty() {
+                    format!("No aliases yet. Add one with `{}`.", usage_of(self))
+                } else {
+                    aliases
+                        .iter()
+                        .map(|(name, expansion)| format!("`{}` → `{}`", name, expansion))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                };
+                send_info(ctx.ctx, ctx.msg, "🔀 Aliases", truncate(&body, 4000)).await?;
+                return Ok(());
+            }
+            (Some(action @ ("add" | "remove")), Some(name)) => (action, name),
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", usage_of(self))).await?;
+                return Ok(());
+            }
+        };
+
+        let (event, reply) = if action == "remove" {
+            let removed = self
+                .store
+                .update(|configs| configs.entry(guild_id).aliases.remove(&name).is_some())
+                .await?;
+            if !removed {
+                send_error(ctx.ctx, ctx.msg, format!("There's no alias `{}`.", name)).await?;
+                return Ok(());
+            }
+            (
+                format!("Remove alias {}", name),
+                format!("Removed the alias `{}`.", name),
+            )
+        } else {
+            if let Err(e) = aliases::check_name(&name) {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+            let expansion = ctx.args[2..].join(" ");
+            let command = match aliases::expand(&expansion) {
+                Some((command, _)) => command,
+                None => {
+                    send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", usage_of(self))).await?;
+                    return Ok(());
+                }
+            };
+            if expansion.chars().count() > MAX_EXPANSION_LENGTH {
+                let e = format!(
+                    "Aliases can stand for at most {} characters.",
+                    MAX_EXPANSION_LENGTH
+                );
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+            if let Some(e) = Self::collision(&ctx, &name, &command).await {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+
+            let added = self
+                .store
+                .update(|configs| {
+                    let aliases = &mut configs.entry(guild_id).aliases;
+                    if aliases.len() >= MAX_ALIASES && !aliases.contains_key(&name) {
+                        return false;
+                    }
+                    aliases.insert(name.clone(), expansion.clone());
+                    true
+                })
+                .await?;
+            if !added {
+                let e = format!("This server already has {} aliases.", MAX_ALIASES);
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+            (
+                format!("Add alias {} for {}", name, expansion),
+                format!("`{}` now runs `{}`.", name, expansion),
+            )
+        };
+
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action: event,
+                reason: None,
+            },
+        )
+        .await;
+
+        send_success(ctx.ctx, ctx.msg, reply).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,185 @@
+//! Antinuke command for turning anti-nuke protection on and trusting admins.
+
+use async_trait::async_trait;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::params::{usage_of, Param, ParamKind};
+use crate::framework::state::Inject;
+use crate::models::antinuke::AntinukeSettings;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::guild_config::{GuildConfigKey, GuildConfigs};
+use crate::storage::JsonStore;
+use crate::utils::helpers::{parse_user, send_error, send_info, send_success, BotConfigKey};
+
+/// Most trusted users a guild can have.
+const MAX_TRUSTED: usize = 25;
+
+/// Shows or changes the server's anti-nuke protection. Only the owner can change
+/// it, so a compromised admin can't trust themselves.
+pub struct AntinukeCommand {
+    store: Arc<JsonStore<GuildConfigs>>,
+}
+
+impl AntinukeCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<GuildConfigKey>) -> Self {
+        Self { store }
+    }
+
+    /// Describe the guild's protection and the limits it uses.
+    async fn status(&self, ctx: &CommandContext<'_>, settings: &AntinukeSettings) -> CommandResult {
+        let limits = {
+            let data = ctx.ctx.data.read().await;
+            data.get::<BotConfigKey>()
+                .map(|config| config.antinuke.clone())
+        }
+        .unwrap_or_default();
+        let trusted = if settings.trusted.is_empty() {
+            "nobody besides the owner".to_string()
+        } else {
+            settings
+                .trusted
+                .iter()
+                .map(|id| format!("<@{}>", id))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let description = format!(
+            "Protection is **{}**.\nTrusted: {}\n\nAnyone else who does one of these within \
+             {} seconds loses their roles, and the owner and staff are alerted:\n\
+             • delete {} channels\n• ban {} members\n• create {} webhooks\n\
+             • give {} roles dangerous permissions\n\nI need View Audit Log and Manage Roles, \
+             and my role has to be above theirs.",
+            if settings.enabled { "on" } else { "off" },
+            trusted,
+            limits.window,
+            limits.channel_deletes,
+            limits.bans,
+            limits.webhook_creates,
+            limits.role_escalations
+        );
+        send_info(ctx.ctx, ctx.msg, "🛡️ Anti-nuke", description).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Command for AntinukeCommand {
+    fn name(&self) -> &str {
+        "antinuke"
+    }
+
+    fn description(&self) -> &str {
+        "Strip the roles of anyone mass deleting channels, banning or escalating permissions"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            Param::optional(
+                "action",
+                ParamKind::Word,
+                "on, off, trust or untrust, the status if left out",
+            )
+            .choices(&["on", "enable", "off", "disable", "trust", "untrust"]),
+            Param::optional("user", ParamKind::User, "The user to trust or untrust"),
+        ]
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::ADMINISTRATOR
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Anti-nuke can only be set up in a server")?;
+        let action = ctx.args.first().map(|arg| arg.to_lowercase());
+        let settings = self.store.read().await.get(guild_id).antinuke;
+        if action.is_none() {
+            return self.status(&ctx, &settings).await;
+        }
+
+        let owner_id = ctx.ctx.cache.guild_field(guild_id, |guild| guild.owner_id);
+        if owner_id != Some(ctx.msg.author.id) {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                "Only the server owner can change anti-nuke protection.",
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let target = ctx.args.get(1).and_then(|arg| parse_user(arg));
+        let (audit_action, reply) = match (action.as_deref(), target) {
+            (Some("on" | "enable"), _) => (
+                "Turn anti-nuke on".to_string(),
+                "Anti-nuke protection is on.".to_string(),
+            ),
+            (Some("off" | "disable"), _) => (
+                "Turn anti-nuke off".to_string(),
+                "Anti-nuke protection is off.".to_string(),
+            ),
+            (Some("trust"), Some(user_id)) => {
+                if !settings.trusted.contains(&user_id.0) && settings.trusted.len() >= MAX_TRUSTED {
+                    send_error(
+                        ctx.ctx,
+                        ctx.msg,
+                        format!("Up to {} users can be trusted.", MAX_TRUSTED),
+                    )
+                    .await?;
+                    return Ok(());
+                }
+                (
+                    format!("Trust {} with anti-nuke", user_id),
+                    format!("Anti-nuke won't stop <@{}>.", user_id),
+                )
+            }
+            (Some("untrust"), Some(user_id)) => (
+                format!("Stop trusting {} with anti-nuke", user_id),
+                format!("Anti-nuke will stop <@{}> too.", user_id),
+            ),
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", usage_of(self))).await?;
+                return Ok(());
+            }
+        };
+
+        self.store
+            .update(|configs| {
+                let settings = &mut configs.entry(guild_id).antinuke;
+                match (action.as_deref(), target) {
+                    (Some("on" | "enable"), _) => settings.enabled = true,
+                    (Some("off" | "disable"), _) => settings.enabled = false,
+                    (Some("trust"), Some(user_id)) if !settings.trusted.contains(&user_id.0) => {
+                        settings.trusted.push(user_id.0)
+                    }
+                    (Some("trust"), _) => {}
+                    (_, Some(user_id)) => settings.trusted.retain(|id| *id != user_id.0),
+                    _ => {}
+                }
+            })
+            .await?;
+
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action: audit_action,
+                reason: None,
+            },
+        )
+        .await;
+        send_success(ctx.ctx, ctx.msg, reply).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+//! AutoPublish command for toggling automatic publishing in announcement channels.
+
+use async_trait::async_trait;
+use serenity::model::channel::ChannelType;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::guild_config::{AutoPublish, GuildConfigKey, GuildConfigs};
+use crate::storage::JsonStore;
+use crate::utils::helpers::{parse_channel, send_error, send_success};
+
+/// Turns auto-publishing on or off for an announcement channel.
+pub struct AutoPublishCommand {
+    store: Arc<JsonStore<GuildConfigs>>,
+}
+
+impl AutoPublishCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<GuildConfigKey>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Command for AutoPublishCommand {
+    fn name(&self) -> &str {
+        "autopublish"
+    }
+
+    fn description(&self) -> &str {
+        "Automatically publish messages posted in an announcement channel"
+    }
+
+    fn usage(&self) -> &str {
+        "autopublish <channel> <bot|everyone|off>"
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::MANAGE_CHANNELS
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Auto-publish can only be used in a server")?;
+
+        let channel_id = ctx.args.first().and_then(|arg| parse_channel(arg));
+        let mode = ctx.args.get(1).map(|arg| arg.to_lowercase());
+        let (channel_id, mode) = match (channel_id, mode.as_deref()) {
+            (Some(channel_id), Some("bot")) => (channel_id, Some(AutoPublish::Bot)),
+            (Some(channel_id), Some("everyone")) => (channel_id, Some(AutoPublish::Everyone)),
+            (Some(channel_id), Some("off")) => (channel_id, None),
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", self.usage())).await?;
+                return Ok(());
+            }
+        };
+
+        if mode.is_some() {
+            let is_news = ctx
+                .ctx
+                .cache
+                .guild_channel(channel_id)
+                .is_some_and(|channel| {
+                    channel.guild_id == guild_id && channel.kind == ChannelType::News
+                });
+            if !is_news {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    "Only announcement channels in this server can be auto-published.",
+                )
+                .await?;
+                return Ok(());
+            }
+        }
+
+        self.store
+            .update(|configs| {
+                let auto_publish = &mut configs.entry(guild_id).auto_publish;
+                match mode {
+                    Some(mode) => auto_publish.insert(channel_id.0, mode),
+                    None => auto_publish.remove(&channel_id.0),
+                }
+            })
+            .await?;
+
+        let (action, reply) = match mode {
+            Some(AutoPublish::Bot) => (
+                format!("Enable auto-publish of bot messages in <#{}>", channel_id),
+                format!("My messages in <#{}> will be published.", channel_id),
+            ),
+            Some(AutoPublish::Everyone) => (
+                format!("Enable auto-publish of all messages in <#{}>", channel_id),
+                format!("Every message in <#{}> will be published.", channel_id),
+            ),
+            None => (
+                format!("Disable auto-publish in <#{}>", channel_id),
+                format!("Messages in <#{}> won't be published.", channel_id),
+            ),
+        };
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action,
+                reason: None,
+            },
+        )
+        .await;
+
+        send_success(ctx.ctx, ctx.msg, reply).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,399 @@
+//! AutoResponse command for managing the server's auto-response rules.
+
+use async_trait::async_trait;
+use serenity::model::channel::ReactionType;
+use serenity::model::id::GuildId;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::auto_response::{
+    AutoResponse, AutoResponseData, AutoResponseKey, Response, TriggerKind,
+};
+use crate::storage::JsonStore;
+use crate::utils::constants::PAGINATION_MAX_ITEMS;
+use crate::utils::duration::{self, format_compact};
+use crate::utils::helpers::{
+    compile_regex, parse_channel, send_error, send_info, send_success, truncate,
+};
+use crate::utils::limits::{Limit, Limits, LimitsKey};
+
+const USAGE: &str = "autoresponse [page] | autoresponse add <exact|contains|regex> <trigger> => <response> | autoresponse remove <id> | autoresponse channels <id> <channels...|all> | autoresponse chance <id> <1-100> | autoresponse cooldown <id> <duration|off>";
+
+/// Manages rules that answer messages matching a trigger.
+///
+/// Responses starting with `react:` add a reaction and ones starting with `embed:` are
+/// sent as an embed; anything else is sent as a message.
+pub struct AutoResponseCommand {
+    store: Arc<JsonStore<AutoResponseData>>,
+    limits: Arc<Limits>,
+}
+
+impl AutoResponseCommand {
+    /// Create the command with its store and the premium limits.
+    pub fn new(
+        (Inject(store), Inject(limits)): (Inject<AutoResponseKey>, Inject<LimitsKey>),
+    ) -> Self {
+        Self { store, limits }
+    }
+}
+
+#[async_trait]
+impl Command for AutoResponseCommand {
+    fn name(&self) -> &str {
+        "autoresponse"
+    }
+
+    fn description(&self) -> &str {
+        "Reply to messages matching a trigger with a message, embed or reaction"
+    }
+
+    fn usage(&self) -> &str {
+        USAGE
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["ar"]
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Auto-responses can only be used in a server")?;
+
+        let action = ctx.args.first().map(|arg| arg.to_lowercase());
+        let id = ctx.args.get(1).and_then(|arg| arg.parse::<u64>().ok());
+
+        match (action.as_deref(), id) {
+            (None, _) => self.list(&ctx, guild_id, 1).await,
+            (Some(page), _) if page.parse::<usize>().is_ok() => {
+                let page = page.parse::<usize>().unwrap_or(1).max(1);
+                self.list(&ctx, guild_id, page).await
+            }
+            (Some("add"), _) => self.add(&ctx, guild_id).await,
+            (Some("remove"), Some(id)) => {
+                let removed = self
+                    .store
+                    .update(|data| data.guild_mut(guild_id).remove(id))
+                    .await?;
+                if !removed {
+                    send_error(
+                        ctx.ctx,
+                        ctx.msg,
+                        format!("There's no auto-response #{}.", id),
+                    )
+                    .await?;
+                    return Ok(());
+                }
+                self.audit(&ctx, guild_id, format!("Remove auto-response #{}", id))
+                    .await;
+                send_success(ctx.ctx, ctx.msg, format!("Removed auto-response #{}.", id)).await?;
+                Ok(())
+            }
+            (Some("channels"), Some(id)) => {
+                let args = &ctx.args[2..];
+                let channels = if args.len() == 1 && args[0].eq_ignore_ascii_case("all") {
+                    Some(Vec::new())
+                } else {
+                    args.iter()
+                        .map(|arg| parse_channel(arg).map(|channel| channel.0))
+                        .collect::<Option<Vec<u64>>>()
+                        .filter(|channels| !channels.is_empty())
+                };
+                let channels = match channels {
+                    Some(channels) => channels,
+                    None => {
+                        send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", self.usage())).await?;
+                        return Ok(());
+                    }
+                };
+                let summary = if channels.is_empty() {
+                    "in every channel".to_string()
+                } else {
+                    format!("only in {}", mention_channels(&channels))
+                };
+                self.update_rule(&ctx, guild_id, id, summary, |rule| rule.channels = channels)
+                    .await
+            }
+            (Some("chance"), Some(id)) => {
+                let chance = ctx
+                    .args
+                    .get(2)
+                    .and_then(|arg| arg.trim_end_matches('%').parse::<u8>().ok())
+                    .filter(|chance| (1..=100).contains(chance));
+                let chance = match chance {
+                    Some(chance) => chance,
+                    None => {
+                        send_error(ctx.ctx, ctx.msg, "The chance must be between 1 and 100.")
+                            .await?;
+                        return Ok(());
+                    }
+                };
+                self.update_rule(
+                    &ctx,
+                    guild_id,
+                    id,
+                    format!("{}% of the time", chance),
+                    |rule| rule.chance = chance,
+                )
+                .await
+            }
+            (Some("cooldown"), Some(id)) if ctx.args.len() > 2 => {
+                let input = ctx.args[2..].join(" ");
+                let cooldown = if input.eq_ignore_ascii_case("off") {
+                    0
+                } else {
+                    match duration::parse(&input) {
+                        Ok(cooldown) => cooldown.as_secs(),
+                        Err(e) => {
+                            send_error(ctx.ctx, ctx.msg, e.to_string()).await?;
+                            return Ok(());
+                        }
+                    }
+                };
+                let summary = if cooldown == 0 {
+                    "without a cooldown".to_string()
+                } else {
+                    format!(
+                        "at most once every {}",
+                        format_compact(std::time::Duration::from_secs(cooldown))
+                    )
+                };
+                self.update_rule(&ctx, guild_id, id, summary, |rule| rule.cooldown = cooldown)
+                    .await
+            }
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", self.usage())).await?;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl AutoResponseCommand {
+    /// Add a rule from `add <kind> <trigger> => <response>`.
+    async fn add(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let kind = ctx.args.get(1).map(|arg| arg.parse::<TriggerKind>());
+        let rest = ctx.args.get(2..).unwrap_or_default().join(" ");
+        let (kind, (pattern, response)) = match (kind, rest.split_once("=>")) {
+            (Some(Ok(kind)), Some(parts)) => (kind, parts),
+            (Some(Err(e)), _) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", self.usage())).await?;
+                return Ok(());
+            }
+        };
+
+        let pattern = pattern.trim().to_string();
+        if pattern.is_empty() {
+            send_error(ctx.ctx, ctx.msg, "The trigger can't be empty.").await?;
+            return Ok(());
+        }
+        if kind == TriggerKind::Regex {
+            if let Err(e) = compile_regex(&pattern) {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+        }
+        let response = match Response::parse(response) {
+            Ok(response) => response,
+            Err(e) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+        };
+        if let Response::Reaction(emoji) = &response {
+            if ReactionType::try_from(emoji.as_str()).is_err() {
+                send_error(ctx.ctx, ctx.msg, format!("`{}` isn't an emoji.", emoji)).await?;
+                return Ok(());
+            }
+        }
+
+        let max_rules = self.limits.for_guild(guild_id, Limit::AutoResponses).await;
+        let added = self
+            .store
+            .update(|data| {
+                let guild = data.guild_mut(guild_id);
+                if guild.rules.len() >= max_rules {
+                    return None;
+                }
+                Some(guild.add(kind, pattern.clone(), response, ctx.msg.author.id.0))
+            })
+            .await?;
+        let id = match added {
+            Some(id) => id,
+            None => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!(
+                        "This server already has {} auto-responses. Remove one first.",
+                        max_rules
+                    ),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        self.audit(
+            ctx,
+            guild_id,
+            format!("Add auto-response #{} for {} `{}`", id, kind, pattern),
+        )
+        .await;
+        send_success(ctx.ctx, ctx.msg, format!("Added auto-response #{}.", id)).await?;
+        Ok(())
+    }
+
+    /// Change a rule and confirm the change, or report that it doesn't exist.
+    async fn update_rule(
+        &self,
+        ctx: &CommandContext<'_>,
+        guild_id: GuildId,
+        id: u64,
+        summary: String,
+        change: impl FnOnce(&mut AutoResponse),
+    ) -> CommandResult {
+        let updated = self
+            .store
+            .update(|data| data.guild_mut(guild_id).get_mut(id).map(change).is_some())
+            .await?;
+        if !updated {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!("There's no auto-response #{}.", id),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        self.audit(
+            ctx,
+            guild_id,
+            format!("Set auto-response #{} to respond {}", id, summary),
+        )
+        .await;
+        send_success(
+            ctx.ctx,
+            ctx.msg,
+            format!("Auto-response #{} will respond {}.", id, summary),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Show a page of the guild's rules.
+    async fn list(
+        &self,
+        ctx: &CommandContext<'_>,
+        guild_id: GuildId,
+        page: usize,
+    ) -> CommandResult {
+        let (lines, total) = {
+            let data = self.store.read().await;
+            let rules = data
+                .guild(guild_id)
+                .map(|guild| guild.rules.as_slice())
+                .unwrap_or_default();
+
+            let lines: Vec<String> = rules
+                .iter()
+                .skip((page - 1) * PAGINATION_MAX_ITEMS)
+                .take(PAGINATION_MAX_ITEMS)
+                .map(describe_rule)
+                .collect();
+            (lines, rules.len())
+        };
+
+        if total == 0 {
+            send_info(
+                ctx.ctx,
+                ctx.msg,
+                "Auto-responses",
+                "This server has no auto-responses.",
+            )
+            .await?;
+        } else if lines.is_empty() {
+            send_error(ctx.ctx, ctx.msg, format!("Page {} doesn't exist.", page)).await?;
+        } else {
+            let pages = total.div_ceil(PAGINATION_MAX_ITEMS);
+            send_info(
+                ctx.ctx,
+                ctx.msg,
+                format!("Auto-responses (page {}/{})", page, pages),
+                lines.join("\n\n"),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Record a rule change in the audit log.
+    async fn audit(&self, ctx: &CommandContext<'_>, guild_id: GuildId, action: String) {
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action,
+                reason: None,
+            },
+        )
+        .await;
+    }
+}
+
+/// Describe a rule for the list.
+fn describe_rule(rule: &AutoResponse) -> String {
+    let mut details = Vec::new();
+    if !rule.channels.is_empty() {
+        details.push(format!("in {}", mention_channels(&rule.channels)));
+    }
+    if rule.chance < 100 {
+        details.push(format!("{}% chance", rule.chance));
+    }
+    if rule.cooldown > 0 {
+        details.push(format!(
+            "{} cooldown",
+            format_compact(std::time::Duration::from_secs(rule.cooldown))
+        ));
+    }
+
+    let mut line = format!(
+        "**#{}** {} `{}`\n→ {}",
+        rule.id,
+        rule.trigger,
+        truncate(&rule.pattern, 100),
+        truncate(&rule.response.to_string(), 200)
+    );
+    if !details.is_empty() {
+        line.push_str(&format!("\n{}", details.join(", ")));
+    }
+    line
+}
+
+/// Mention a list of channels.
+fn mention_channels(channels: &[u64]) -> String {
+    channels
+        .iter()
+        .map(|channel| format!("<#{}>", channel))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
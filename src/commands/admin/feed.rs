@@ -0,0 +1,294 @@
+//! Feed command for announcing new subreddit posts in a channel.
+
+use async_trait::async_trait;
+use serenity::model::id::{ChannelId, GuildId};
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::feeds::{Feed, FeedData, FeedKey};
+use crate::storage::JsonStore;
+use crate::utils::helpers::{
+    is_nsfw_channel, parse_channel, send_error, send_info, send_success, truncate, unix_timestamp,
+    BotConfigKey,
+};
+use crate::utils::reddit::{normalize_subreddit, Reddit, RedditKey};
+
+/// Adds, removes and lists subreddit feeds.
+pub struct FeedCommand {
+    max_per_guild: usize,
+    feeds: Arc<JsonStore<FeedData>>,
+    reddit: Arc<Reddit>,
+}
+
+impl FeedCommand {
+    /// Create the command with the feed limit, store and Reddit client.
+    pub fn new(
+        (Inject(config), Inject(feeds), Inject(reddit)): (
+            Inject<BotConfigKey>,
+            Inject<FeedKey>,
+            Inject<RedditKey>,
+        ),
+    ) -> Self {
+        Self {
+            max_per_guild: config.feeds.max_per_guild,
+            feeds,
+            reddit,
+        }
+    }
+
+    async fn list(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let lines: Vec<String> = self
+            .feeds
+            .read()
+            .await
+            .list(guild_id)
+            .iter()
+            .map(|feed| format!("r/{} → <#{}>", feed.subreddit, feed.channel_id))
+            .collect();
+        let body = if lines.is_empty() {
+            "No feeds yet. Add one with `feed add <subreddit> [channel]`.".to_string()
+        } else {
+            lines.join("\n")
+        };
+        send_info(ctx.ctx, ctx.msg, "📰 Subreddit feeds", body).await?;
+        Ok(())
+    }
+
+    async fn add(
+        &self,
+        ctx: &CommandContext<'_>,
+        guild_id: GuildId,
+        subreddit: String,
+        channel_id: ChannelId,
+    ) -> CommandResult {
+        if self.feeds.read().await.list(guild_id).len() >= self.max_per_guild {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!(
+                    "This server already has {} feeds, the most it can have.",
+                    self.max_per_guild
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let about = match self.reddit.subreddit(&subreddit).await? {
+            Some(about) => about,
+            None => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!(
+                        "I can't read r/{}. It may not exist or be private.",
+                        subreddit
+                    ),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+        if about.over18 && !is_nsfw_channel(ctx.ctx, channel_id) {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!(
+                    "r/{} is age-restricted, so its feed needs an age-restricted channel.",
+                    subreddit
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let now = unix_timestamp();
+        let feed = Feed {
+            subreddit: subreddit.clone(),
+            channel_id: channel_id.0,
+            added_by: ctx.msg.author.id.0,
+            added_at: now,
+            last_created: now as f64,
+        };
+        if !self.feeds.update(|data| data.add(guild_id, feed)).await? {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!("<#{}> already has a feed for r/{}.", channel_id, subreddit),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        record(
+            ctx,
+            guild_id,
+            format!("Add r/{} feed in <#{}>", subreddit, channel_id),
+        )
+        .await;
+        send_success(
+            ctx.ctx,
+            ctx.msg,
+            format!(
+                "New posts from r/{} will be announced in <#{}>.",
+                about.display_name, channel_id
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn remove(
+        &self,
+        ctx: &CommandContext<'_>,
+        guild_id: GuildId,
+        subreddit: String,
+        channel_id: ChannelId,
+    ) -> CommandResult {
+        let removed = self
+            .feeds
+            .update(|data| data.remove(guild_id, &subreddit, channel_id))
+            .await?;
+        if !removed {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!("<#{}> has no feed for r/{}.", channel_id, subreddit),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        record(
+            ctx,
+            guild_id,
+            format!("Remove r/{} feed from <#{}>", subreddit, channel_id),
+        )
+        .await;
+        send_success(
+            ctx.ctx,
+            ctx.msg,
+            format!(
+                "r/{} won't be announced in <#{}> anymore.",
+                subreddit, channel_id
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Record a feed change in the audit log.
+async fn record(ctx: &CommandContext<'_>, guild_id: GuildId, action: String) {
+    audit::record(
+        ctx.ctx,
+        AuditEvent {
+            guild_id: Some(guild_id),
+            actor_id: Some(ctx.msg.author.id),
+            source: AuditSource::Command,
+            action,
+            reason: None,
+        },
+    )
+    .await;
+}
+
+#[async_trait]
+impl Command for FeedCommand {
+    fn name(&self) -> &str {
+        "feed"
+    }
+
+    fn description(&self) -> &str {
+        "Announce new posts from a subreddit in a channel"
+    }
+
+    fn usage(&self) -> &str {
+        "feed [add|remove <subreddit> [channel]]"
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["feeds"]
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::EMBED_LINKS
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Feeds can only be used in a server")?;
+
+        let action = ctx.args.first().map(|arg| arg.to_lowercase());
+        let action = match action.as_deref() {
+            None | Some("list") => return self.list(&ctx, guild_id).await,
+            Some(action @ ("add" | "remove")) => action,
+            Some(_) => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", self.usage())).await?;
+                return Ok(());
+            }
+        };
+
+        let subreddit = match ctx.args.get(1) {
+            Some(arg) => match normalize_subreddit(arg) {
+                Some(subreddit) => subreddit,
+                None => {
+                    send_error(
+                        ctx.ctx,
+                        ctx.msg,
+                        format!("`{}` isn't a subreddit name.", truncate(arg, 64)),
+                    )
+                    .await?;
+                    return Ok(());
+                }
+            },
+            None => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", self.usage())).await?;
+                return Ok(());
+            }
+        };
+        let channel_id = match ctx.args.get(2) {
+            Some(arg) => match parse_channel(arg) {
+                Some(channel_id) => channel_id,
+                None => {
+                    send_error(ctx.ctx, ctx.msg, "That isn't a channel.").await?;
+                    return Ok(());
+                }
+            },
+            None => ctx.msg.channel_id,
+        };
+        let in_guild = ctx
+            .ctx
+            .cache
+            .guild_channel(channel_id)
+            .is_some_and(|channel| channel.guild_id == guild_id);
+        if !in_guild {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                "Feeds can only post in this server's channels.",
+            )
+            .await?;
+            return Ok(());
+        }
+
+        if action == "add" {
+            self.add(&ctx, guild_id, subreddit, channel_id).await
+        } else {
+            self.remove(&ctx, guild_id, subreddit, channel_id).await
+        }
+    }
+}
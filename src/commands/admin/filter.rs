@@ -0,0 +1,604 @@
+//! Filter command for managing the server's word filter.
+
+use async_trait::async_trait;
+use serenity::builder::CreateEmbed;
+use serenity::model::id::{ChannelId, GuildId, RoleId};
+use serenity::model::permissions::Permissions;
+use serenity::prelude::*;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::word_filter::{
+    FilterAction, FilterPattern, GuildFilter, PatternKind, Severity, WordFilterData, WordFilterKey,
+};
+use crate::storage::JsonStore;
+use crate::utils::constants::{
+    DEFAULT_COLOR, FILTER_IMPORT_MAX_SIZE, MAX_TIMEOUT, PAGINATION_MAX_ITEMS,
+};
+use crate::utils::duration::{self, format_compact};
+use crate::utils::files::{send_file, OutgoingFile};
+use crate::utils::helpers::{send_error, send_info, send_success, truncate};
+use crate::utils::limits::{Limit, Limits, LimitsKey};
+
+const USAGE: &str = "filter [page] | filter add <wildcard|regex> <low|medium|high> <pattern> | filter remove <id> | filter actions <severity> <delete,warn,timeout|none> | filter timeout <duration> | filter whitelist <add|remove> <role|channel> | filter export | filter import (attach a JSON file)";
+
+/// A role or channel that can be whitelisted.
+enum WhitelistTarget {
+    Role(RoleId),
+    Channel(ChannelId),
+}
+
+/// Manages the word filter: patterns with a severity, the actions for each severity
+/// and the roles and channels that aren't filtered.
+pub struct FilterCommand {
+    store: Arc<JsonStore<WordFilterData>>,
+    limits: Arc<Limits>,
+}
+
+impl FilterCommand {
+    /// Create the command with its store and the premium limits.
+    pub fn new(
+        (Inject(store), Inject(limits)): (Inject<WordFilterKey>, Inject<LimitsKey>),
+    ) -> Self {
+        Self { store, limits }
+    }
+}
+
+#[async_trait]
+impl Command for FilterCommand {
+    fn name(&self) -> &str {
+        "filter"
+    }
+
+    fn description(&self) -> &str {
+        "Delete, warn or time out for messages containing filtered words"
+    }
+
+    fn usage(&self) -> &str {
+        USAGE
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("The word filter can only be used in a server")?;
+
+        let action = ctx.args.first().map(|arg| arg.to_lowercase());
+        match action.as_deref() {
+            None => self.list(&ctx, guild_id, 1).await,
+            Some(page) if page.parse::<usize>().is_ok() => {
+                let page = page.parse::<usize>().unwrap_or(1).max(1);
+                self.list(&ctx, guild_id, page).await
+            }
+            Some("add") => self.add(&ctx, guild_id).await,
+            Some("remove") => self.remove(&ctx, guild_id).await,
+            Some("actions") => self.set_actions(&ctx, guild_id).await,
+            Some("timeout") => self.set_timeout(&ctx, guild_id).await,
+            Some("whitelist") => self.whitelist(&ctx, guild_id).await,
+            Some("export") => self.export(&ctx, guild_id).await,
+            Some("import") => self.import(&ctx, guild_id).await,
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", self.usage())).await?;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl FilterCommand {
+    /// Add a pattern from `add <kind> <severity> <pattern>`.
+    async fn add(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let kind = ctx.args.get(1).map(|arg| arg.parse::<PatternKind>());
+        let severity = ctx.args.get(2).map(|arg| arg.parse::<Severity>());
+        let pattern = ctx.args.get(3..).unwrap_or_default().join(" ");
+        let pattern = match (kind, severity) {
+            (Some(Ok(kind)), Some(Ok(severity))) if !pattern.is_empty() => FilterPattern {
+                kind,
+                pattern,
+                severity,
+            },
+            (Some(Err(e)), _) | (_, Some(Err(e))) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", self.usage())).await?;
+                return Ok(());
+            }
+        };
+        if let Err(e) = pattern.compile() {
+            send_error(ctx.ctx, ctx.msg, e).await?;
+            return Ok(());
+        }
+
+        let description = format!("{} `{}`", pattern.kind, pattern.pattern);
+        let max_rules = self.limits.for_guild(guild_id, Limit::FilterRules).await;
+        let added = self
+            .store
+            .update(|data| {
+                let filter = data.guild_mut(guild_id);
+                if filter.rules.len() >= max_rules {
+                    return Err(format!(
+                        "The filter already has {} patterns. Remove one first.",
+                        max_rules
+                    ));
+                }
+                filter
+                    .add(pattern, ctx.msg.author.id.0)
+                    .ok_or_else(|| "The filter already has that pattern.".to_string())
+            })
+            .await?;
+        let id = match added {
+            Ok(id) => id,
+            Err(e) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+        };
+
+        self.audit(
+            ctx,
+            guild_id,
+            format!("Add word filter rule #{}: {}", id, description),
+        )
+        .await;
+        send_success(ctx.ctx, ctx.msg, format!("Added filter rule #{}.", id)).await?;
+        Ok(())
+    }
+
+    /// Remove a pattern by ID.
+    async fn remove(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let id = match ctx.args.get(1).and_then(|arg| arg.parse::<u64>().ok()) {
+            Some(id) => id,
+            None => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", self.usage())).await?;
+                return Ok(());
+            }
+        };
+
+        let removed = self
+            .store
+            .update(|data| data.guild_mut(guild_id).remove(id))
+            .await?;
+        if !removed {
+            send_error(ctx.ctx, ctx.msg, format!("There's no filter rule #{}.", id)).await?;
+            return Ok(());
+        }
+
+        self.audit(ctx, guild_id, format!("Remove word filter rule #{}", id))
+            .await;
+        send_success(ctx.ctx, ctx.msg, format!("Removed filter rule #{}.", id)).await?;
+        Ok(())
+    }
+
+    /// Set the actions for a severity from `actions <severity> <a,b,...|none>`.
+    async fn set_actions(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let severity = ctx.args.get(1).map(|arg| arg.parse::<Severity>());
+        let actions = ctx.args.get(2..).unwrap_or_default().join(",");
+        let actions: Result<Vec<FilterAction>, String> = if actions.eq_ignore_ascii_case("none") {
+            Ok(Vec::new())
+        } else {
+            actions
+                .split(',')
+                .map(str::trim)
+                .filter(|action| !action.is_empty())
+                .map(str::parse)
+                .collect()
+        };
+
+        let (severity, mut actions) = match (severity, actions) {
+            (Some(Ok(severity)), Ok(actions))
+                if !ctx.args.get(2..).unwrap_or_default().is_empty() =>
+            {
+                (severity, actions)
+            }
+            (Some(Err(e)), _) | (Some(Ok(_)), Err(e)) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", self.usage())).await?;
+                return Ok(());
+            }
+        };
+        actions.sort();
+        actions.dedup();
+
+        let summary = describe_actions(&actions);
+        self.store
+            .update(|data| {
+                data.guild_mut(guild_id).actions.insert(severity, actions);
+            })
+            .await?;
+
+        self.audit(
+            ctx,
+            guild_id,
+            format!(
+                "Set word filter {} severity actions to {}",
+                severity, summary
+            ),
+        )
+        .await;
+        send_success(
+            ctx.ctx,
+            ctx.msg,
+            format!(
+                "Messages matching {} severity rules: {}.",
+                severity, summary
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Set how long the timeout action lasts.
+    async fn set_timeout(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let input = ctx.args.get(1..).unwrap_or_default().join(" ");
+        let timeout = match duration::parse(&input) {
+            Ok(timeout) if timeout.is_zero() || timeout.as_secs() > MAX_TIMEOUT => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    "The timeout must be between 1 second and 28 days.",
+                )
+                .await?;
+                return Ok(());
+            }
+            Ok(timeout) => timeout.as_secs(),
+            Err(e) => {
+                send_error(ctx.ctx, ctx.msg, e.to_string()).await?;
+                return Ok(());
+            }
+        };
+
+        self.store
+            .update(|data| data.guild_mut(guild_id).timeout = timeout)
+            .await?;
+
+        let timeout = format_compact(Duration::from_secs(timeout));
+        self.audit(
+            ctx,
+            guild_id,
+            format!("Set the word filter timeout to {}", timeout),
+        )
+        .await;
+        send_success(
+            ctx.ctx,
+            ctx.msg,
+            format!("The filter's timeouts now last {}.", timeout),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Add or remove a whitelisted role or channel.
+    async fn whitelist(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let add = match ctx.args.get(1).map(|arg| arg.to_lowercase()).as_deref() {
+            Some("add") => true,
+            Some("remove") => false,
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", self.usage())).await?;
+                return Ok(());
+            }
+        };
+        let target = match ctx
+            .args
+            .get(2)
+            .and_then(|arg| parse_target(ctx.ctx, guild_id, arg))
+        {
+            Some(target) => target,
+            None => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    "I can't find that role or channel in this server.",
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let (id, mention) = match target {
+            WhitelistTarget::Role(role) => (role.0, format!("<@&{}>", role)),
+            WhitelistTarget::Channel(channel) => (channel.0, format!("<#{}>", channel)),
+        };
+        let changed = self
+            .store
+            .update(|data| {
+                let filter = data.guild_mut(guild_id);
+                let list = match target {
+                    WhitelistTarget::Role(_) => &mut filter.whitelisted_roles,
+                    WhitelistTarget::Channel(_) => &mut filter.whitelisted_channels,
+                };
+                let listed = list.contains(&id);
+                if add && !listed {
+                    list.push(id);
+                } else if !add && listed {
+                    list.retain(|&listed| listed != id);
+                }
+                add != listed
+            })
+            .await?;
+
+        if !changed {
+            let reply = if add {
+                format!("{} is already whitelisted.", mention)
+            } else {
+                format!("{} isn't whitelisted.", mention)
+            };
+            send_error(ctx.ctx, ctx.msg, reply).await?;
+            return Ok(());
+        }
+
+        let (action, reply) = if add {
+            (
+                format!("Whitelist {} from the word filter", mention),
+                format!("{} is no longer filtered.", mention),
+            )
+        } else {
+            (
+                format!("Remove {} from the word filter whitelist", mention),
+                format!("{} is filtered again.", mention),
+            )
+        };
+        self.audit(ctx, guild_id, action).await;
+        send_success(ctx.ctx, ctx.msg, reply).await?;
+        Ok(())
+    }
+
+    /// Send the guild's patterns as a JSON file.
+    async fn export(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let patterns = self
+            .store
+            .read()
+            .await
+            .guild(guild_id)
+            .map(GuildFilter::export)
+            .unwrap_or_default();
+        if patterns.is_empty() {
+            send_error(ctx.ctx, ctx.msg, "The filter has no patterns to export.").await?;
+            return Ok(());
+        }
+
+        let json = serde_json::to_vec_pretty(&patterns)?;
+        let mut embed = CreateEmbed::default();
+        embed
+            .title("Word filter")
+            .color(DEFAULT_COLOR)
+            .description(format!(
+                "{} patterns. Import them with `filter import`.",
+                patterns.len()
+            ));
+        send_file(
+            ctx.ctx,
+            ctx.msg.channel_id,
+            Some(guild_id),
+            OutgoingFile::new(format!("filter-{}.json", guild_id), json),
+            Some(embed),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Add the patterns from an attached JSON file, skipping ones the filter has.
+    async fn import(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let attachment = match ctx.msg.attachments.first() {
+            Some(attachment) => attachment,
+            None => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    "Attach a JSON file exported with `filter export`.",
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+        if attachment.size > FILTER_IMPORT_MAX_SIZE {
+            send_error(ctx.ctx, ctx.msg, "That file is too large to import.").await?;
+            return Ok(());
+        }
+
+        let data = attachment.download().await?;
+        let patterns: Vec<FilterPattern> = match serde_json::from_slice(&data) {
+            Ok(patterns) => patterns,
+            Err(e) => {
+                send_error(ctx.ctx, ctx.msg, format!("That isn't a filter list: {}", e)).await?;
+                return Ok(());
+            }
+        };
+        if let Some((pattern, e)) = patterns
+            .iter()
+            .find_map(|pattern| pattern.compile().err().map(|e| (pattern, e)))
+        {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!("`{}` is invalid: {}", truncate(&pattern.pattern, 100), e),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let total = patterns.len();
+        let author = ctx.msg.author.id.0;
+        let max_rules = self.limits.for_guild(guild_id, Limit::FilterRules).await;
+        let added = self
+            .store
+            .update(|data| {
+                let filter = data.guild_mut(guild_id);
+                let mut added = 0;
+                for pattern in patterns {
+                    if filter.rules.len() >= max_rules {
+                        break;
+                    }
+                    if filter.add(pattern, author).is_some() {
+                        added += 1;
+                    }
+                }
+                added
+            })
+            .await?;
+
+        self.audit(
+            ctx,
+            guild_id,
+            format!("Import {} word filter patterns", added),
+        )
+        .await;
+        let mut reply = format!("Imported {} of {} patterns.", added, total);
+        if added < total {
+            reply.push_str(&format!(
+                " The rest were already in the filter or over the {} pattern limit.",
+                max_rules
+            ));
+        }
+        send_success(ctx.ctx, ctx.msg, reply).await?;
+        Ok(())
+    }
+
+    /// Show the filter's settings and a page of its patterns.
+    async fn list(
+        &self,
+        ctx: &CommandContext<'_>,
+        guild_id: GuildId,
+        page: usize,
+    ) -> CommandResult {
+        let (description, total) = {
+            let data = self.store.read().await;
+            let default = GuildFilter::default();
+            let filter = data.guild(guild_id).unwrap_or(&default);
+
+            let mut lines: Vec<String> = Severity::ALL
+                .iter()
+                .map(|&severity| {
+                    format!(
+                        "**{}:** {}",
+                        severity,
+                        describe_actions(filter.actions_for(severity))
+                    )
+                })
+                .collect();
+            lines.push(format!(
+                "**Timeout:** {}",
+                format_compact(Duration::from_secs(filter.timeout))
+            ));
+            let whitelist: Vec<String> = filter
+                .whitelisted_roles
+                .iter()
+                .map(|role| format!("<@&{}>", role))
+                .chain(
+                    filter
+                        .whitelisted_channels
+                        .iter()
+                        .map(|channel| format!("<#{}>", channel)),
+                )
+                .collect();
+            if !whitelist.is_empty() {
+                lines.push(format!("**Whitelisted:** {}", whitelist.join(", ")));
+            }
+            lines.push(String::new());
+
+            let rules: Vec<String> = filter
+                .rules
+                .iter()
+                .skip((page - 1) * PAGINATION_MAX_ITEMS)
+                .take(PAGINATION_MAX_ITEMS)
+                .map(|rule| {
+                    format!(
+                        "**#{}** {} `{}` ({})",
+                        rule.id,
+                        rule.pattern.kind,
+                        truncate(&rule.pattern.pattern, 100),
+                        rule.pattern.severity
+                    )
+                })
+                .collect();
+            if filter.rules.is_empty() {
+                lines.push("The filter has no patterns.".to_string());
+            } else if rules.is_empty() {
+                drop(data);
+                send_error(ctx.ctx, ctx.msg, format!("Page {} doesn't exist.", page)).await?;
+                return Ok(());
+            }
+            lines.extend(rules);
+            (lines.join("\n"), filter.rules.len())
+        };
+
+        let pages = total.div_ceil(PAGINATION_MAX_ITEMS).max(1);
+        send_info(
+            ctx.ctx,
+            ctx.msg,
+            format!("Word filter (page {}/{})", page, pages),
+            description,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Record a filter change in the audit log.
+    async fn audit(&self, ctx: &CommandContext<'_>, guild_id: GuildId, action: String) {
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action,
+                reason: None,
+            },
+        )
+        .await;
+    }
+}
+
+/// Describe a severity's actions.
+fn describe_actions(actions: &[FilterAction]) -> String {
+    if actions.is_empty() {
+        return "nothing".to_string();
+    }
+    actions
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Parse a role or channel in the guild from a mention or raw ID.
+fn parse_target(ctx: &Context, guild_id: GuildId, arg: &str) -> Option<WhitelistTarget> {
+    let is_role = |id: u64| ctx.cache.role(guild_id, RoleId(id)).is_some();
+    let is_channel = |id: u64| {
+        ctx.cache
+            .guild_channel(ChannelId(id))
+            .is_some_and(|channel| channel.guild_id == guild_id)
+    };
+
+    if let Some(id) = serenity::utils::parse_role(arg) {
+        return is_role(id).then_some(WhitelistTarget::Role(RoleId(id)));
+    }
+    if let Some(id) = serenity::utils::parse_channel(arg) {
+        return is_channel(id).then_some(WhitelistTarget::Channel(ChannelId(id)));
+    }
+
+    let id: u64 = arg.parse().ok()?;
+    if is_role(id) {
+        Some(WhitelistTarget::Role(RoleId(id)))
+    } else if is_channel(id) {
+        Some(WhitelistTarget::Channel(ChannelId(id)))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,259 @@
+//! Games command for setting up counting and word chain channels.
+
+use async_trait::async_trait;
+use serenity::model::id::{ChannelId, GuildId};
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::games::{GameChannel, GameKind, GamesData, GamesKey};
+use crate::storage::JsonStore;
+use crate::utils::helpers::{parse_channel, send_error, send_info, send_success};
+
+const USAGE: &str = "games | games add <channel> <counting|wordchain> [reset] | games remove <channel> | games restart <channel>";
+
+/// Manages the server's game channels.
+///
+/// Games with `reset` start over when someone makes a mistake; otherwise mistakes
+/// are only deleted.
+pub struct GamesCommand {
+    store: Arc<JsonStore<GamesData>>,
+}
+
+impl GamesCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<GamesKey>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Command for GamesCommand {
+    fn name(&self) -> &str {
+        "games"
+    }
+
+    fn description(&self) -> &str {
+        "Set up counting and word chain channels"
+    }
+
+    fn usage(&self) -> &str {
+        USAGE
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::MANAGE_CHANNELS
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Games can only be set up in a server")?;
+
+        let action = ctx.args.first().map(|arg| arg.to_lowercase());
+        let channel_id = ctx.args.get(1).and_then(|arg| parse_channel(arg));
+
+        match (action.as_deref(), channel_id) {
+            (None | Some("list"), _) => self.list(&ctx, guild_id).await,
+            (Some("add"), Some(channel_id)) => self.add(&ctx, guild_id, channel_id).await,
+            (Some("remove"), Some(channel_id)) => {
+                let removed = self
+                    .store
+                    .update(|data| {
+                        data.guilds
+                            .get_mut(&guild_id.0)
+                            .and_then(|guild| guild.channels.remove(&channel_id.0))
+                    })
+                    .await?;
+                if removed.is_none() {
+                    send_error(ctx.ctx, ctx.msg, format!("<#{}> has no game.", channel_id)).await?;
+                    return Ok(());
+                }
+                self.audit(
+                    &ctx,
+                    guild_id,
+                    format!("Remove the game in <#{}>", channel_id),
+                )
+                .await;
+                send_success(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!("<#{}> is no longer a game channel.", channel_id),
+                )
+                .await?;
+                Ok(())
+            }
+            (Some("restart"), Some(channel_id)) => {
+                let hint = self
+                    .store
+                    .update(|data| {
+                        let game = data
+                            .guilds
+                            .get_mut(&guild_id.0)?
+                            .channels
+                            .get_mut(&channel_id.0)?;
+                        game.restart();
+                        Some(game.next_hint())
+                    })
+                    .await?;
+                let hint = match hint {
+                    Some(hint) => hint,
+                    None => {
+                        send_error(ctx.ctx, ctx.msg, format!("<#{}> has no game.", channel_id))
+                            .await?;
+                        return Ok(());
+                    }
+                };
+                self.audit(
+                    &ctx,
+                    guild_id,
+                    format!("Restart the game in <#{}>", channel_id),
+                )
+                .await;
+                send_success(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!("Restarted the game in <#{}>; {}.", channel_id, hint),
+                )
+                .await?;
+                Ok(())
+            }
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", USAGE)).await?;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl GamesCommand {
+    /// List the game channels and the server's records.
+    async fn list(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let description =
+            {
+                let data = self.store.read().await;
+                match data.guilds.get(&guild_id.0) {
+                    Some(guild) if !guild.channels.is_empty() => {
+                        let mut channels: Vec<_> = guild.channels.iter().collect();
+                        channels.sort_by_key(|(channel_id, _)| **channel_id);
+
+                        let mut lines: Vec<String> = channels
+                            .iter()
+                            .map(|(channel_id, game)| {
+                                let reset = if game.reset_on_fail {
+                                    ", resets on mistakes"
+                                } else {
+                                    ""
+                                };
+                                format!(
+                                    "<#{}>: {}{}, streak **{}**",
+                                    channel_id, game.kind, reset, game.streak
+                                )
+                            })
+                            .collect();
+                        if !guild.records.is_empty() {
+                            lines.push(String::new());
+                            lines.extend(guild.records.iter().map(|(kind, record)| {
+                                format!("🏆 {} record: **{}**", kind, record)
+                            }));
+                        }
+                        lines.join("\n")
+                    }
+                    _ => "No game channels. Add one with `games add`.".to_string(),
+                }
+            };
+
+        send_info(ctx.ctx, ctx.msg, "Game channels", description).await?;
+        Ok(())
+    }
+
+    /// Start a game in a channel, replacing any game it had.
+    async fn add(
+        &self,
+        ctx: &CommandContext<'_>,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+    ) -> CommandResult {
+        let kind = match ctx.args.get(2).map(|arg| arg.parse::<GameKind>()) {
+            Some(Ok(kind)) => kind,
+            Some(Err(e)) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+            None => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", USAGE)).await?;
+                return Ok(());
+            }
+        };
+        let reset_on_fail = ctx
+            .args
+            .get(3)
+            .is_some_and(|arg| arg.eq_ignore_ascii_case("reset"));
+
+        let in_guild = ctx
+            .ctx
+            .cache
+            .guild_channel(channel_id)
+            .is_some_and(|channel| channel.guild_id == guild_id);
+        if !in_guild {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                "I can't find that channel in this server.",
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let game = GameChannel::new(kind, reset_on_fail);
+        let hint = game.next_hint();
+        self.store
+            .update(|data| {
+                data.guilds
+                    .entry(guild_id.0)
+                    .or_default()
+                    .channels
+                    .insert(channel_id.0, game)
+            })
+            .await?;
+
+        self.audit(
+            ctx,
+            guild_id,
+            format!("Start a {} game in <#{}>", kind, channel_id),
+        )
+        .await;
+        send_success(
+            ctx.ctx,
+            ctx.msg,
+            format!(
+                "<#{}> is now a {} channel; {}. I need Manage Messages there to remove mistakes.",
+                channel_id, kind, hint
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Record a change to the game channels in the audit log.
+    async fn audit(&self, ctx: &CommandContext<'_>, guild_id: GuildId, action: String) {
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action,
+                reason: None,
+            },
+        )
+        .await;
+    }
+}
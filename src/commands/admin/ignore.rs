@@ -0,0 +1,139 @@
+//! Ignore command for making the bot ignore channels, roles or users.
+
+use async_trait::async_trait;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::guild_config::{GuildConfigKey, GuildConfigs};
+use crate::models::ignore::IgnoreScope;
+use crate::storage::JsonStore;
+use crate::utils::helpers::{
+    parse_channel, parse_role, parse_user, send_error, send_info, send_success,
+};
+
+/// Adds, removes and lists the server's ignore entries.
+pub struct IgnoreCommand {
+    store: Arc<JsonStore<GuildConfigs>>,
+}
+
+impl IgnoreCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<GuildConfigKey>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Command for IgnoreCommand {
+    fn name(&self) -> &str {
+        "ignore"
+    }
+
+    fn description(&self) -> &str {
+        "Ignore a channel, role or user for commands, passive features, or both"
+    }
+
+    fn usage(&self) -> &str {
+        "ignore <channel|role|user> <target> [commands|passive|all|off] | ignore list"
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Ignore lists can only be used in a server")?;
+
+        let kind = ctx.args.first().map(|arg| arg.to_lowercase());
+        if kind.as_deref() == Some("list") {
+            let list = self.store.read().await.get(guild_id).ignore;
+            send_info(ctx.ctx, ctx.msg, "🙈 Ignored", list.describe()).await?;
+            return Ok(());
+        }
+
+        let target = ctx.args.get(1).map(String::as_str).unwrap_or_default();
+        let (kind, id, mention) = match kind.as_deref() {
+            Some("channel") => match parse_channel(target) {
+                Some(id) => ("channel", id.0, format!("<#{}>", id)),
+                None => return usage(&ctx, self.usage()).await,
+            },
+            Some("role") => match parse_role(target) {
+                Some(id) => ("role", id.0, format!("<@&{}>", id)),
+                None => return usage(&ctx, self.usage()).await,
+            },
+            Some("user") => match parse_user(target) {
+                Some(id) => ("user", id.0, format!("<@{}>", id)),
+                None => return usage(&ctx, self.usage()).await,
+            },
+            _ => return usage(&ctx, self.usage()).await,
+        };
+
+        let scope = match ctx.args.get(2).map(|arg| arg.to_lowercase()).as_deref() {
+            None => Some(IgnoreScope::All),
+            Some("off") => None,
+            Some(scope) => match scope.parse::<IgnoreScope>() {
+                Ok(scope) => Some(scope),
+                Err(e) => {
+                    send_error(ctx.ctx, ctx.msg, e).await?;
+                    return Ok(());
+                }
+            },
+        };
+
+        self.store
+            .update(|configs| {
+                let list = &mut configs.entry(guild_id).ignore;
+                let entries = match kind {
+                    "channel" => &mut list.channels,
+                    "role" => &mut list.roles,
+                    _ => &mut list.users,
+                };
+                match scope {
+                    Some(scope) => entries.insert(id, scope),
+                    None => entries.remove(&id),
+                }
+            })
+            .await?;
+
+        let (action, reply) = match scope {
+            Some(scope) => (
+                format!("Ignore {} {} for {}", kind, id, scope),
+                format!("I'll ignore {} ({}).", mention, scope),
+            ),
+            None => (
+                format!("Stop ignoring {} {}", kind, id),
+                format!("I'll stop ignoring {}.", mention),
+            ),
+        };
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action,
+                reason: None,
+            },
+        )
+        .await;
+
+        send_success(ctx.ctx, ctx.msg, reply).await?;
+        Ok(())
+    }
+}
+
+/// Reply with the command's usage.
+async fn usage(ctx: &CommandContext<'_>, usage: &str) -> CommandResult {
+    send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", usage)).await?;
+    Ok(())
+}
@@ -0,0 +1,286 @@
+//! Mirror command for relaying another channel's messages into this server.
+
+use async_trait::async_trait;
+use serenity::model::id::GuildId;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::mirror::{MirrorData, MirrorFilter, MirrorKey, MAX_MIRRORS_PER_GUILD};
+use crate::storage::JsonStore;
+use crate::utils::helpers::{parse_channel, permissions_in, send_error, send_info, send_success};
+
+const USAGE: &str = "mirror | mirror add <source channel> [target channel] [bots] [no-attachments] | mirror filter <id> <keywords...|none> | mirror remove <id>";
+
+/// Sets up mirrors that relay a source channel into a channel of this server.
+pub struct MirrorCommand {
+    store: Arc<JsonStore<MirrorData>>,
+}
+
+impl MirrorCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<MirrorKey>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Command for MirrorCommand {
+    fn name(&self) -> &str {
+        "mirror"
+    }
+
+    fn description(&self) -> &str {
+        "Relay messages from a channel, even in another server, into this server"
+    }
+
+    fn usage(&self) -> &str {
+        USAGE
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::MANAGE_WEBHOOKS
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::MANAGE_WEBHOOKS
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Mirrors can only be used in a server")?;
+
+        let action = ctx.args.first().map(|arg| arg.to_lowercase());
+        let id = ctx.args.get(1).and_then(|arg| arg.parse::<u64>().ok());
+        match (action.as_deref(), id) {
+            (None, _) => self.list(&ctx, guild_id).await,
+            (Some("add"), _) => self.add(&ctx, guild_id).await,
+            (Some("filter"), Some(id)) if ctx.args.len() > 2 => {
+                let args = &ctx.args[2..];
+                let keywords = if args.len() == 1 && args[0].eq_ignore_ascii_case("none") {
+                    Vec::new()
+                } else {
+                    args.to_vec()
+                };
+                let summary = if keywords.is_empty() {
+                    "every message".to_string()
+                } else {
+                    format!("messages containing {}", keywords.join(", "))
+                };
+                let updated = self
+                    .store
+                    .update(|data| match data.get_mut(guild_id, id) {
+                        Some(mirror) => {
+                            mirror.filter.keywords = keywords;
+                            true
+                        }
+                        None => false,
+                    })
+                    .await?;
+                if !updated {
+                    send_error(ctx.ctx, ctx.msg, format!("There's no mirror #{}.", id)).await?;
+                    return Ok(());
+                }
+                self.audit(
+                    &ctx,
+                    guild_id,
+                    format!("Filter mirror #{} to {}", id, summary),
+                )
+                .await;
+                send_success(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!("Mirror #{} now relays {}.", id, summary),
+                )
+                .await?;
+                Ok(())
+            }
+            (Some("remove"), Some(id)) => {
+                let removed = self.store.update(|data| data.remove(guild_id, id)).await?;
+                if !removed {
+                    send_error(ctx.ctx, ctx.msg, format!("There's no mirror #{}.", id)).await?;
+                    return Ok(());
+                }
+                self.audit(&ctx, guild_id, format!("Remove mirror #{}", id))
+                    .await;
+                send_success(ctx.ctx, ctx.msg, format!("Removed mirror #{}.", id)).await?;
+                Ok(())
+            }
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", USAGE)).await?;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl MirrorCommand {
+    /// List the mirrors relaying into the server.
+    async fn list(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let lines: Vec<String> = self
+            .store
+            .read()
+            .await
+            .into_guild(guild_id)
+            .into_iter()
+            .map(|mirror| {
+                let mut line = format!(
+                    "**#{}** <#{}> → <#{}>",
+                    mirror.id, mirror.source_channel, mirror.target_channel
+                );
+                if !mirror.filter.keywords.is_empty() {
+                    line.push_str(&format!(" (only {})", mirror.filter.keywords.join(", ")));
+                }
+                if mirror.filter.bots {
+                    line.push_str(", with bots");
+                }
+                if !mirror.filter.attachments {
+                    line.push_str(", without attachments");
+                }
+                line
+            })
+            .collect();
+
+        let description = if lines.is_empty() {
+            "No channels are mirrored into this server.".to_string()
+        } else {
+            lines.join("\n")
+        };
+        send_info(ctx.ctx, ctx.msg, "🪞 Mirrors", description).await?;
+        Ok(())
+    }
+
+    /// Add a mirror from `add <source> [target] [bots] [no-attachments]`.
+    async fn add(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let source = match ctx.args.get(1).and_then(|arg| parse_channel(arg)) {
+            Some(source) => source,
+            None => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", USAGE)).await?;
+                return Ok(());
+            }
+        };
+        let mut target = ctx.msg.channel_id;
+        let mut filter = MirrorFilter::default();
+        for arg in &ctx.args[2..] {
+            match arg.to_lowercase().as_str() {
+                "bots" => filter.bots = true,
+                "no-attachments" => filter.attachments = false,
+                _ => match parse_channel(arg) {
+                    Some(channel) => target = channel,
+                    None => {
+                        send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", USAGE)).await?;
+                        return Ok(());
+                    }
+                },
+            }
+        }
+
+        let source_guild = match ctx.ctx.cache.guild_channel(source) {
+            Some(channel) => channel.guild_id,
+            None => {
+                send_error(ctx.ctx, ctx.msg, "I can't see that source channel.").await?;
+                return Ok(());
+            }
+        };
+        if source == target {
+            send_error(ctx.ctx, ctx.msg, "A channel can't mirror itself.").await?;
+            return Ok(());
+        }
+        let in_guild = ctx
+            .ctx
+            .cache
+            .guild_channel(target)
+            .is_some_and(|channel| channel.guild_id == guild_id);
+        if !in_guild {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                "The target must be a channel in this server.",
+            )
+            .await?;
+            return Ok(());
+        }
+
+        // The source server has to agree to being relayed
+        let allowed = permissions_in(ctx.ctx, source_guild, source, ctx.msg.author.id)
+            .await
+            .is_some_and(|permissions| {
+                permissions.contains(Permissions::VIEW_CHANNEL | Permissions::MANAGE_GUILD)
+            });
+        if !allowed {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                "You need Manage Server in the source channel's server to mirror it.",
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let author = ctx.msg.author.id.0;
+        let id = self
+            .store
+            .update(|data| {
+                if data.into_guild(guild_id).len() >= MAX_MIRRORS_PER_GUILD {
+                    return None;
+                }
+                Some(data.add((source_guild, source), (guild_id, target), author, filter))
+            })
+            .await?;
+        let id = match id {
+            Some(id) => id,
+            None => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!(
+                        "A server can have at most {} mirrors.",
+                        MAX_MIRRORS_PER_GUILD
+                    ),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        self.audit(
+            ctx,
+            guild_id,
+            format!("Mirror <#{}> into <#{}> (#{})", source, target, id),
+        )
+        .await;
+        send_success(
+            ctx.ctx,
+            ctx.msg,
+            format!(
+                "Messages in <#{}> will be relayed to <#{}> (mirror #{}).",
+                source, target, id
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Record a change to the server's mirrors in the audit log.
+    async fn audit(&self, ctx: &CommandContext<'_>, guild_id: GuildId, action: String) {
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action,
+                reason: None,
+            },
+        )
+        .await;
+    }
+}
@@ -0,0 +1,48 @@
+//! Administration commands for server managers.
+
+pub mod alias;
+#[cfg(feature = "antinuke")]
+pub mod antinuke;
+pub mod autopublish;
+pub mod autoresponse;
+pub mod feed;
+#[cfg(feature = "automod")]
+pub mod filter;
+#[cfg(feature = "games")]
+pub mod games;
+pub mod ignore;
+pub mod mirror;
+pub mod modules;
+#[cfg(feature = "music")]
+pub mod music;
+pub mod onboarding;
+pub mod permaudit;
+pub mod permsync;
+pub mod pinarchive;
+pub mod pinvote;
+pub mod serverdata;
+pub mod settings;
+pub mod setup;
+pub mod voicetext;
+
+use crate::framework::command_handler::CommandHandler;
+
+/// Register all admin commands with the command handler.
+pub fn register_commands(handler: &mut CommandHandler) {
+    handler.register_with_state(settings::SettingsCommand::new);
+    handler.register_with_state(setup::SetupCommand::new);
+    handler.register_with_state(permaudit::PermAuditCommand::new);
+    handler.register_with_state(permsync::PermSyncCommand::new);
+    handler.register_with_state(serverdata::ServerDataCommand::new);
+    handler.register_command(pinarchive::PinArchiveCommand);
+    handler.register_with_state(pinvote::PinVoteCommand::new);
+    handler.register_with_state(autopublish::AutoPublishCommand::new);
+    handler.register_with_state(autoresponse::AutoResponseCommand::new);
+    handler.register_with_state(modules::ModulesCommand::new);
+    handler.register_with_state(ignore::IgnoreCommand::new);
+    handler.register_with_state(alias::AliasCommand::new);
+    handler.register_with_state(onboarding::OnboardingCommand::new);
+    handler.register_with_state(mirror::MirrorCommand::new);
+    handler.register_with_state(feed::FeedCommand::new);
+    handler.register_with_state(voicetext::VoiceTextCommand::new);
+}
@@ -0,0 +1,194 @@
+//! Modules command for turning plugins on and off in a server.
+
+use async_trait::async_trait;
+use serenity::model::id::GuildId;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::plugin::{Modules, ModulesKey};
+use crate::framework::state::Inject;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::guild_config::{GuildConfigKey, GuildConfigs};
+use crate::storage::JsonStore;
+use crate::utils::helpers::{send_error, send_info, send_success};
+
+const USAGE: &str = "modules [enable <module> | disable <module>]";
+
+/// Lists the plugins compiled into the bot and turns them on or off for the server.
+pub struct ModulesCommand {
+    modules: Arc<Modules>,
+    store: Arc<JsonStore<GuildConfigs>>,
+}
+
+impl ModulesCommand {
+    /// Create the command with the plugin modules and the guild configuration store.
+    pub fn new(
+        (Inject(modules), Inject(store)): (Inject<ModulesKey>, Inject<GuildConfigKey>),
+    ) -> Self {
+        Self { modules, store }
+    }
+}
+
+#[async_trait]
+impl Command for ModulesCommand {
+    fn name(&self) -> &str {
+        "modules"
+    }
+
+    fn description(&self) -> &str {
+        "Turn bot modules on or off in this server"
+    }
+
+    fn usage(&self) -> &str {
+        USAGE
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["plugins"]
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Modules can only be changed in a server")?;
+
+        let action = ctx.args.first().map(|arg| arg.to_lowercase());
+        let name = ctx.args.get(1).map(|arg| arg.to_lowercase());
+
+        let enable = match (action.as_deref(), &name) {
+            (None | Some("list"), _) => return self.list(&ctx, guild_id).await,
+            (Some("enable" | "on"), Some(_)) => true,
+            (Some("disable" | "off"), Some(_)) => false,
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", USAGE)).await?;
+                return Ok(());
+            }
+        };
+        let name = name.unwrap_or_default();
+
+        let plugin = match self.modules.get(&name) {
+            Some(plugin) => plugin.name(),
+            None => {
+                let available: Vec<_> = self
+                    .modules
+                    .plugins()
+                    .iter()
+                    .map(|plugin| format!("`{}`", plugin.name()))
+                    .collect();
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!(
+                        "Unknown module `{}`. Available modules: {}.",
+                        name,
+                        if available.is_empty() {
+                            "none".to_string()
+                        } else {
+                            available.join(", ")
+                        }
+                    ),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        if self.modules.is_enabled(guild_id, plugin) == enable {
+            send_info(
+                ctx.ctx,
+                ctx.msg,
+                "Modules",
+                format!(
+                    "`{}` is already {}.",
+                    plugin,
+                    if enable { "on" } else { "off" }
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        self.store
+            .update(|configs| {
+                let disabled = &mut configs.entry(guild_id).disabled_plugins;
+                disabled.retain(|name| name != plugin);
+                if !enable {
+                    disabled.push(plugin.to_string());
+                    disabled.sort();
+                }
+            })
+            .await?;
+        self.modules.set_enabled(guild_id, plugin, enable);
+
+        let verb = if enable { "Enable" } else { "Disable" };
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action: format!("{} the {} module", verb, plugin),
+                reason: None,
+            },
+        )
+        .await;
+
+        send_success(
+            ctx.ctx,
+            ctx.msg,
+            format!(
+                "`{}` is now {} in this server.",
+                plugin,
+                if enable { "on" } else { "off" }
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+impl ModulesCommand {
+    /// List the modules and whether each is on in the server.
+    async fn list(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let plugins = self.modules.plugins();
+        let description = if plugins.is_empty() {
+            "No modules are compiled into this build.".to_string()
+        } else {
+            plugins
+                .iter()
+                .map(|plugin| {
+                    let status = if self.modules.is_enabled(guild_id, plugin.name()) {
+                        "🟢"
+                    } else {
+                        "🔴"
+                    };
+                    let config = plugin
+                        .config_section()
+                        .map(|section| format!(" (config: `[{}]`)", section))
+                        .unwrap_or_default();
+                    format!(
+                        "{} **{}**: {}{}",
+                        status,
+                        plugin.name(),
+                        plugin.description(),
+                        config
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        send_info(ctx.ctx, ctx.msg, "Modules", description).await?;
+        Ok(())
+    }
+}
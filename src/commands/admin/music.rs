@@ -0,0 +1,283 @@
+//! Music command for the DJ role, the server's queue limits, 24/7 presence and
+//! synced lyrics.
+
+use async_trait::async_trait;
+use serenity::model::channel::ChannelType;
+use serenity::model::id::ChannelId;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::params::{usage_of, Param, ParamKind};
+use crate::framework::state::Inject;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::guild_config::{GuildConfigKey, GuildConfigs};
+use crate::models::music::MusicSettings;
+use crate::storage::JsonStore;
+use crate::utils::duration::{self, format_compact};
+use crate::utils::helpers::{
+    parse_channel, parse_role, send_error, send_info, send_success, BotConfigKey,
+};
+use crate::utils::player::{Player, PlayerKey};
+
+/// Shows or changes who controls music playback, how much can be queued, which
+/// voice channel the bot stays in and whether lyrics are shown.
+pub struct MusicCommand {
+    store: Arc<JsonStore<GuildConfigs>>,
+    player: Arc<Player>,
+}
+
+impl MusicCommand {
+    /// Create the command with its store and the player.
+    pub fn new(
+        (Inject(store), Inject(player)): (Inject<GuildConfigKey>, Inject<PlayerKey>),
+    ) -> Self {
+        Self { store, player }
+    }
+
+    /// Describe the guild's settings and the limits they're held to.
+    async fn status(&self, ctx: &CommandContext<'_>, settings: &MusicSettings) -> CommandResult {
+        let config = {
+            let data = ctx.ctx.data.read().await;
+            data.get::<BotConfigKey>()
+                .map(|config| config.music.clone())
+        }
+        .unwrap_or_default();
+        let dj = match settings.dj_role {
+            Some(role) => format!("<@&{}>", role),
+            None => "not set, only Manage Server".to_string(),
+        };
+        let always_on = match settings.always_on {
+            Some(channel) => format!("<#{}>", channel),
+            None => "off".to_string(),
+        };
+        let description = format!(
+            "DJ role: {}\nQueue: up to {} tracks\nTracks: up to {}\n24/7 channel: {}\n\
+             Synced lyrics: {}\n\n\
+             While someone listens alone they control playback. With others in the \
+             channel, skipping, stopping and clearing need the DJ role or Manage \
+             Server, and everyone else votes to skip.\n\nUsage: `{}`",
+            dj,
+            settings.queue_limit(&config),
+            format_compact(settings.track_limit(&config)),
+            always_on,
+            if settings.karaoke { "on" } else { "off" },
+            usage_of(self)
+        );
+        send_info(ctx.ctx, ctx.msg, "🎵 Music", description).await?;
+        Ok(())
+    }
+}
+
+/// A change to the music settings.
+enum Change {
+    DjRole(Option<u64>),
+    MaxQueue(Option<usize>),
+    MaxTrackLength(Option<Duration>),
+    AlwaysOn(Option<u64>),
+    Karaoke(bool),
+}
+
+#[async_trait]
+impl Command for MusicCommand {
+    fn name(&self) -> &str {
+        "music"
+    }
+
+    fn description(&self) -> &str {
+        "Set the DJ role, music queue limits, a 24/7 channel and synced lyrics"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            Param::optional(
+                "setting",
+                ParamKind::Word,
+                "dj, queue, duration, 247 or karaoke, the settings if left out",
+            )
+            .choices(&["dj", "queue", "duration", "247", "karaoke"]),
+            Param::optional(
+                "value",
+                ParamKind::Text,
+                "A role, number of tracks, duration, voice channel, on or off",
+            ),
+        ]
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Music settings can only be changed in a server")?;
+        let settings = self.store.read().await.get(guild_id).music;
+        let action = ctx.args.first().map(|arg| arg.to_lowercase());
+        let value = ctx.args.get(1).map(|arg| arg.to_lowercase());
+
+        let change = match (action.as_deref(), value.as_deref()) {
+            (None, _) => return self.status(&ctx, &settings).await,
+            (Some("dj"), Some("off")) => Change::DjRole(None),
+            (Some("dj"), Some(role)) => match parse_role(role) {
+                Some(role) => Change::DjRole(Some(role.0)),
+                None => {
+                    send_error(ctx.ctx, ctx.msg, "Expected a role or `off`.").await?;
+                    return Ok(());
+                }
+            },
+            (Some("queue"), Some("off")) => Change::MaxQueue(None),
+            (Some("queue"), Some(tracks)) => match tracks.parse() {
+                Ok(tracks) if tracks > 0 => Change::MaxQueue(Some(tracks)),
+                _ => {
+                    send_error(ctx.ctx, ctx.msg, "Expected a number of tracks or `off`.").await?;
+                    return Ok(());
+                }
+            },
+            (Some("duration"), Some("off")) => Change::MaxTrackLength(None),
+            (Some("duration"), Some(_)) => match duration::parse(&ctx.args[1..].join(" ")) {
+                Ok(length) if !length.is_zero() => Change::MaxTrackLength(Some(length)),
+                Ok(_) => {
+                    send_error(ctx.ctx, ctx.msg, "The duration has to be longer than that.")
+                        .await?;
+                    return Ok(());
+                }
+                Err(e) => {
+                    send_error(ctx.ctx, ctx.msg, e.to_string()).await?;
+                    return Ok(());
+                }
+            },
+            (Some("karaoke"), Some("on")) => Change::Karaoke(true),
+            (Some("karaoke"), Some("off")) => Change::Karaoke(false),
+            (Some("247"), Some("off")) => Change::AlwaysOn(None),
+            (Some("247"), Some(channel)) => {
+                let voice = parse_channel(channel).filter(|channel| {
+                    ctx.ctx
+                        .cache
+                        .guild_channel(*channel)
+                        .is_some_and(|channel| {
+                            channel.guild_id == guild_id
+                                && matches!(channel.kind, ChannelType::Voice | ChannelType::Stage)
+                        })
+                });
+                match voice {
+                    Some(channel) => Change::AlwaysOn(Some(channel.0)),
+                    None => {
+                        send_error(
+                            ctx.ctx,
+                            ctx.msg,
+                            "Expected a voice channel in this server or `off`.",
+                        )
+                        .await?;
+                        return Ok(());
+                    }
+                }
+            }
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", usage_of(self))).await?;
+                return Ok(());
+            }
+        };
+
+        let (action, reply) = match &change {
+            Change::DjRole(Some(role)) => (
+                format!("Set the DJ role to <@&{}>", role),
+                format!(
+                    "Members with <@&{}> can skip, stop and clear while others are listening.",
+                    role
+                ),
+            ),
+            Change::DjRole(None) => (
+                "Remove the DJ role".to_string(),
+                "Only members with Manage Server can skip, stop and clear while others are \
+                 listening."
+                    .to_string(),
+            ),
+            Change::MaxQueue(Some(tracks)) => (
+                format!("Limit the music queue to {} tracks", tracks),
+                format!("The queue can hold up to {} tracks.", tracks),
+            ),
+            Change::MaxQueue(None) => (
+                "Remove the music queue limit".to_string(),
+                "The queue can hold as many tracks as the bot allows.".to_string(),
+            ),
+            Change::MaxTrackLength(Some(length)) => (
+                format!("Limit music tracks to {}", format_compact(*length)),
+                format!(
+                    "Tracks up to {} long can be queued.",
+                    format_compact(*length)
+                ),
+            ),
+            Change::MaxTrackLength(None) => (
+                "Remove the music track length limit".to_string(),
+                "Tracks as long as the bot allows can be queued.".to_string(),
+            ),
+            Change::AlwaysOn(Some(channel)) => (
+                format!("Set the 24/7 music channel to <#{}>", channel),
+                format!(
+                    "I'll stay in <#{}> around the clock, and rejoin it after disconnects and \
+                     restarts.",
+                    channel
+                ),
+            ),
+            Change::AlwaysOn(None) => (
+                "Turn off 24/7 music".to_string(),
+                "I'll leave voice channels when the queue ends.".to_string(),
+            ),
+            Change::Karaoke(true) => (
+                "Turn on synced lyrics".to_string(),
+                "The now-playing message will show synced lyrics when a track has them."
+                    .to_string(),
+            ),
+            Change::Karaoke(false) => (
+                "Turn off synced lyrics".to_string(),
+                "The now-playing message won't show lyrics.".to_string(),
+            ),
+        };
+        if let Change::AlwaysOn(home) = &change {
+            let home = home.map(ChannelId);
+            if let Err(e) = self
+                .player
+                .set_always_on(ctx.ctx, guild_id, home, ctx.msg.channel_id)
+                .await
+            {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+        }
+        self.store
+            .update(|configs| {
+                let music = &mut configs.entry(guild_id).music;
+                match change {
+                    Change::DjRole(role) => music.dj_role = role,
+                    Change::MaxQueue(tracks) => music.max_queue = tracks,
+                    Change::MaxTrackLength(length) => {
+                        music.max_track_length = length.map(|length| length.as_secs())
+                    }
+                    Change::AlwaysOn(channel) => music.always_on = channel,
+                    Change::Karaoke(enabled) => music.karaoke = enabled,
+                }
+            })
+            .await?;
+
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action,
+                reason: None,
+            },
+        )
+        .await;
+        send_success(ctx.ctx, ctx.msg, reply).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,317 @@
+//! Onboarding command for defining the flow new members go through.
+
+use async_trait::async_trait;
+use serenity::model::id::GuildId;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+
+use crate::events::send_onboarding_prompt;
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::guild_config::{GuildConfigKey, GuildConfigs};
+use crate::models::onboarding::{Choice, OnboardingFlow, Question, MAX_CHOICES, MAX_QUESTIONS};
+use crate::storage::JsonStore;
+use crate::utils::helpers::{parse_channel, parse_role, send_error, send_info, send_success};
+
+const USAGE: &str = "onboarding | onboarding <on|off> | onboarding rules <text> | onboarding question add <prompt> => <label> <role>, ... | onboarding question multiple <n> <on|off> | onboarding question remove <n> | onboarding access <roles...|none> | onboarding channel <channel|none> | onboarding age <days> | onboarding test";
+
+/// Defines the rules, questions and access roles of the onboarding flow.
+pub struct OnboardingCommand {
+    store: Arc<JsonStore<GuildConfigs>>,
+}
+
+impl OnboardingCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<GuildConfigKey>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Command for OnboardingCommand {
+    fn name(&self) -> &str {
+        "onboarding"
+    }
+
+    fn description(&self) -> &str {
+        "Show new members the rules and ask questions before giving them access"
+    }
+
+    fn usage(&self) -> &str {
+        USAGE
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::MANAGE_ROLES
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Onboarding can only be used in a server")?;
+
+        let action = ctx.args.first().map(|arg| arg.to_lowercase());
+        let change: Result<String, String> = match action.as_deref() {
+            None => {
+                let flow = self.store.read().await.get(guild_id).onboarding;
+                send_info(ctx.ctx, ctx.msg, "🚪 Onboarding", flow.describe()).await?;
+                return Ok(());
+            }
+            Some("test") => {
+                let flow = self.store.read().await.get(guild_id).onboarding;
+                send_onboarding_prompt(ctx.ctx, guild_id, ctx.msg.author.id, &flow).await?;
+                send_success(ctx.ctx, ctx.msg, "Sent you the onboarding prompt.").await?;
+                return Ok(());
+            }
+            Some(toggle @ ("on" | "off")) => {
+                let enabled = toggle == "on";
+                self.update(guild_id, |flow| {
+                    if enabled && flow.access_roles.is_empty() && flow.questions.is_empty() {
+                        return Err("Add access roles or a question first.".to_string());
+                    }
+                    flow.enabled = enabled;
+                    Ok(format!("Onboarding turned {}", toggle))
+                })
+                .await?
+            }
+            Some("rules") if ctx.args.len() > 1 => {
+                let rules = ctx.args[1..].join(" ");
+                self.update(guild_id, |flow| {
+                    flow.rules = rules;
+                    Ok("Updated the onboarding rules".to_string())
+                })
+                .await?
+            }
+            Some("question") => self.question(&ctx, guild_id).await?,
+            Some("access") if ctx.args.len() > 1 => {
+                let args = &ctx.args[1..];
+                let roles = if args.len() == 1 && args[0].eq_ignore_ascii_case("none") {
+                    Some(Vec::new())
+                } else {
+                    args.iter()
+                        .map(|arg| parse_role(arg).map(|role| role.0))
+                        .collect::<Option<Vec<u64>>>()
+                };
+                match roles {
+                    Some(roles) => {
+                        self.update(guild_id, |flow| {
+                            flow.access_roles = roles;
+                            Ok("Updated the onboarding access roles".to_string())
+                        })
+                        .await?
+                    }
+                    None => Err(format!("Usage: `{}`", USAGE)),
+                }
+            }
+            Some("channel") if ctx.args.len() > 1 => {
+                let channel = if ctx.args[1].eq_ignore_ascii_case("none") {
+                    Some(None)
+                } else {
+                    parse_channel(&ctx.args[1]).map(|channel| Some(channel.0))
+                };
+                match channel {
+                    Some(channel) => {
+                        self.update(guild_id, |flow| {
+                            flow.channel = channel;
+                            Ok(match channel {
+                                Some(channel) => {
+                                    format!("Set the onboarding fallback channel to <#{}>", channel)
+                                }
+                                None => "Removed the onboarding fallback channel".to_string(),
+                            })
+                        })
+                        .await?
+                    }
+                    None => Err(format!("Usage: `{}`", USAGE)),
+                }
+            }
+            Some("age") => match ctx.args.get(1).and_then(|arg| arg.parse::<u64>().ok()) {
+                Some(days) => {
+                    self.update(guild_id, |flow| {
+                        flow.min_account_age_days = days;
+                        Ok(format!(
+                            "Set the onboarding minimum account age to {} days",
+                            days
+                        ))
+                    })
+                    .await?
+                }
+                None => Err("Give the minimum account age in days, or 0 for none.".to_string()),
+            },
+            _ => Err(format!("Usage: `{}`", USAGE)),
+        };
+
+        match change {
+            Ok(action) => {
+                audit::record(
+                    ctx.ctx,
+                    AuditEvent {
+                        guild_id: Some(guild_id),
+                        actor_id: Some(ctx.msg.author.id),
+                        source: AuditSource::Command,
+                        action: action.clone(),
+                        reason: None,
+                    },
+                )
+                .await;
+                send_success(ctx.ctx, ctx.msg, format!("{}.", action)).await?;
+            }
+            Err(e) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl OnboardingCommand {
+    /// Change the guild's flow, returning a summary of the change or why it was refused.
+    async fn update(
+        &self,
+        guild_id: GuildId,
+        change: impl FnOnce(&mut OnboardingFlow) -> Result<String, String>,
+    ) -> CommandResult<Result<String, String>> {
+        let result = self
+            .store
+            .update(|configs| change(&mut configs.entry(guild_id).onboarding))
+            .await?;
+        Ok(result)
+    }
+
+    /// Handle `question add`, `question multiple` and `question remove`.
+    async fn question(
+        &self,
+        ctx: &CommandContext<'_>,
+        guild_id: GuildId,
+    ) -> CommandResult<Result<String, String>> {
+        let action = ctx.args.get(1).map(|arg| arg.to_lowercase());
+        let number = ctx
+            .args
+            .get(2)
+            .and_then(|arg| arg.parse::<usize>().ok())
+            .filter(|number| *number > 0);
+
+        match (action.as_deref(), number) {
+            (Some("add"), _) => {
+                let question = match parse_question(&ctx.args[2..].join(" ")) {
+                    Ok(question) => question,
+                    Err(e) => return Ok(Err(e)),
+                };
+                self.update(guild_id, |flow| {
+                    if flow.questions.len() >= MAX_QUESTIONS {
+                        return Err(format!(
+                            "A flow can have at most {} questions.",
+                            MAX_QUESTIONS
+                        ));
+                    }
+                    flow.questions.push(question);
+                    Ok(format!(
+                        "Added onboarding question {}",
+                        flow.questions.len()
+                    ))
+                })
+                .await
+            }
+            (Some("multiple"), Some(number)) => {
+                let multiple = match ctx.args.get(3).map(|arg| arg.to_lowercase()).as_deref() {
+                    Some("on") => true,
+                    Some("off") => false,
+                    _ => return Ok(Err(format!("Usage: `{}`", USAGE))),
+                };
+                self.update(guild_id, |flow| {
+                    let question = flow
+                        .questions
+                        .get_mut(number - 1)
+                        .ok_or(format!("There's no question {}.", number))?;
+                    question.multiple = multiple;
+                    Ok(format!(
+                        "Onboarding question {} now takes {}",
+                        number,
+                        if multiple {
+                            "any number of answers"
+                        } else {
+                            "one answer"
+                        }
+                    ))
+                })
+                .await
+            }
+            (Some("remove"), Some(number)) => {
+                self.update(guild_id, |flow| {
+                    if number > flow.questions.len() {
+                        return Err(format!("There's no question {}.", number));
+                    }
+                    flow.questions.remove(number - 1);
+                    Ok(format!("Removed onboarding question {}", number))
+                })
+                .await
+            }
+            _ => Ok(Err(format!("Usage: `{}`", USAGE))),
+        }
+    }
+}
+
+/// Parse `<prompt> => <label> <role>, <label> <role>, ...`.
+fn parse_question(input: &str) -> Result<Question, String> {
+    let (prompt, choices) = input
+        .split_once("=>")
+        .ok_or("Separate the question and its answers with `=>`.")?;
+    let prompt = prompt.trim();
+    if prompt.is_empty() {
+        return Err("The question can't be empty.".to_string());
+    }
+
+    let choices = choices
+        .split(',')
+        .map(|choice| {
+            let choice = choice.trim();
+            let (label, role) = choice
+                .rsplit_once(' ')
+                .ok_or(format!("Give `{}` a label and a role.", choice))?;
+            let role = parse_role(role).ok_or(format!("`{}` isn't a role.", role))?;
+            Ok(Choice {
+                label: label.trim().to_string(),
+                role: role.0,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    if choices.len() > MAX_CHOICES {
+        return Err(format!(
+            "A question can have at most {} answers.",
+            MAX_CHOICES
+        ));
+    }
+
+    Ok(Question {
+        prompt: prompt.to_string(),
+        choices,
+        multiple: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_questions_with_role_answers() {
+        let question = parse_question("Your pronouns? => she/her <@&1>, they/them 2").unwrap();
+        assert_eq!(question.prompt, "Your pronouns?");
+        assert_eq!(question.choices.len(), 2);
+        assert_eq!(question.choices[0].label, "she/her");
+        assert_eq!(question.choices[1].role, 2);
+
+        assert!(parse_question("No answers").is_err());
+        assert!(parse_question("Pick => only-a-label").is_err());
+    }
+}
@@ -0,0 +1,137 @@
+//! Permissions audit command for spotting risky role and channel setups.
+
+use async_trait::async_trait;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::params::{usage_of, Param, ParamKind};
+use crate::framework::plugin::{Modules, ModulesKey};
+use crate::framework::state::Inject;
+use crate::models::guild_config::{GuildConfigKey, GuildConfigs};
+use crate::storage::JsonStore;
+use crate::utils::constants::PAGINATION_MAX_ITEMS;
+use crate::utils::helpers::{send_error, send_info, send_success};
+use crate::utils::permaudit::{self, Severity, Snapshot};
+
+/// Reports risky permission setups in the server, most severe first.
+pub struct PermAuditCommand {
+    modules: Arc<Modules>,
+    store: Arc<JsonStore<GuildConfigs>>,
+}
+
+impl PermAuditCommand {
+    /// Create the command with the plugin modules and the guild configuration store.
+    pub fn new(
+        (Inject(modules), Inject(store)): (Inject<ModulesKey>, Inject<GuildConfigKey>),
+    ) -> Self {
+        Self { modules, store }
+    }
+}
+
+#[async_trait]
+impl Command for PermAuditCommand {
+    fn name(&self) -> &str {
+        "permaudit"
+    }
+
+    fn description(&self) -> &str {
+        "Check the server for risky roles, channel overwrites and permissions I'm missing"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![Param::optional(
+            "page",
+            ParamKind::Integer,
+            "Which page of the findings to show",
+        )
+        .range(1, 1000)]
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Permissions can only be audited in a server")?;
+        let page = match ctx.args.first() {
+            Some(arg) => match arg.parse::<usize>() {
+                Ok(page) => page.max(1),
+                Err(_) => {
+                    send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", usage_of(self))).await?;
+                    return Ok(());
+                }
+            },
+            None => 1,
+        };
+
+        let bot_id = ctx.ctx.cache.current_user_id();
+        let snapshot = match ctx.ctx.cache.guild(guild_id) {
+            Some(guild) => Snapshot::of(&guild, bot_id),
+            None => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    "This server isn't cached yet, try again soon.",
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let config = self.store.read().await.get(guild_id);
+        let mut needs = permaudit::feature_permissions(&config);
+        for plugin in self.modules.plugins() {
+            let permissions = plugin.required_bot_permissions();
+            if !permissions.is_empty() && self.modules.is_enabled(guild_id, plugin.name()) {
+                needs.push((plugin.name(), permissions));
+            }
+        }
+        let findings = permaudit::audit(&snapshot, &needs);
+
+        if findings.is_empty() {
+            send_success(ctx.ctx, ctx.msg, "No risky permissions found.").await?;
+            return Ok(());
+        }
+        let pages = findings.len().div_ceil(PAGINATION_MAX_ITEMS);
+        if page > pages {
+            send_error(ctx.ctx, ctx.msg, format!("Page {} doesn't exist.", page)).await?;
+            return Ok(());
+        }
+
+        let count = |severity: Severity| {
+            findings
+                .iter()
+                .filter(|finding| finding.severity == severity)
+                .count()
+        };
+        let mut lines = vec![format!(
+            "{} critical, {} warnings, {} notes\n",
+            count(Severity::Critical),
+            count(Severity::Warning),
+            count(Severity::Info)
+        )];
+        lines.extend(
+            findings
+                .iter()
+                .skip((page - 1) * PAGINATION_MAX_ITEMS)
+                .take(PAGINATION_MAX_ITEMS)
+                .map(ToString::to_string),
+        );
+        send_info(
+            ctx.ctx,
+            ctx.msg,
+            format!("Permissions audit (page {}/{})", page, pages),
+            lines.join("\n"),
+        )
+        .await?;
+        Ok(())
+    }
+}
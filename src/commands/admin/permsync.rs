@@ -0,0 +1,508 @@
+//! Permsync command for syncing, copying and templating channel permissions.
+
+use async_trait::async_trait;
+use serenity::model::channel::GuildChannel;
+use serenity::model::id::{ChannelId, GuildId, UserId};
+use serenity::model::permissions::Permissions;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::params::{usage_of, Param, ParamKind};
+use crate::framework::state::Inject;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::guild_config::{GuildConfigKey, GuildConfigs};
+use crate::models::permission_templates::{
+    self, PermissionTemplate, TemplateOverwrite, MAX_TEMPLATES,
+};
+use crate::storage::JsonStore;
+use crate::utils::actions;
+use crate::utils::helpers::{
+    parse_channel, send_error, send_info, send_success, send_warning, truncate,
+};
+
+/// How long a previewed change waits for `permsync confirm`.
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Most channels changed at once.
+const MAX_CHANNELS: usize = 25;
+
+/// The overwrites one channel would end up with.
+struct ChannelChange {
+    channel_id: ChannelId,
+    current: Vec<TemplateOverwrite>,
+    target: Vec<TemplateOverwrite>,
+}
+
+/// A previewed change, waiting for confirmation.
+struct Plan {
+    /// What the change does, for the audit log.
+    action: String,
+    changes: Vec<ChannelChange>,
+}
+
+/// Replaces channels' permission overwrites with their category's, another
+/// channel's or a saved template's, previewing the changes first.
+pub struct PermSyncCommand {
+    store: Arc<JsonStore<GuildConfigs>>,
+    /// Changes waiting for confirmation, by guild and the member who previewed them.
+    pending: Mutex<HashMap<(GuildId, UserId), (Plan, Instant)>>,
+}
+
+impl PermSyncCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<GuildConfigKey>) -> Self {
+        Self {
+            store,
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Show what a change would do and wait for `permsync confirm`.
+    async fn preview(
+        &self,
+        ctx: &CommandContext<'_>,
+        guild_id: GuildId,
+        plan: Plan,
+    ) -> CommandResult {
+        let mut sections = Vec::new();
+        for change in &plan.changes {
+            let lines = permission_templates::diff(&change.current, &change.target, guild_id.0);
+            if !lines.is_empty() {
+                sections.push(format!("<#{}>\n{}", change.channel_id, lines.join("\n")));
+            }
+        }
+        if sections.is_empty() {
+            send_info(
+                ctx.ctx,
+                ctx.msg,
+                "Permission sync",
+                "Those channels already have these permissions.",
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let mut preview = truncate(
+            &format!(
+                "This changes {} channels:\n\n{}",
+                sections.len(),
+                sections.join("\n\n")
+            ),
+            3800,
+        );
+        preview.push_str(&format!(
+            "\n\nRun `permsync confirm` within {} minutes to apply it.",
+            CONFIRM_TIMEOUT.as_secs() / 60
+        ));
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert((guild_id, ctx.msg.author.id), (plan, Instant::now()));
+        send_warning(ctx.ctx, ctx.msg, preview).await?;
+        Ok(())
+    }
+
+    /// Apply the member's last preview.
+    async fn confirm(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let pending = self
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&(guild_id, ctx.msg.author.id))
+            .filter(|(_, at)| at.elapsed() < CONFIRM_TIMEOUT);
+        let plan = match pending {
+            Some((plan, _)) => plan,
+            None => {
+                send_error(ctx.ctx, ctx.msg, "There's no permission change to confirm.").await?;
+                return Ok(());
+            }
+        };
+
+        let mut changed = 0;
+        let mut failed = 0;
+        for change in &plan.changes {
+            let channel_id = change.channel_id;
+            let mut ok = true;
+            for overwrite in &change.target {
+                if change.current.contains(overwrite) {
+                    continue;
+                }
+                let overwrite = overwrite.to_overwrite();
+                if let Err(e) = actions::set_overwrite(ctx, channel_id, &overwrite).await {
+                    warn!("Failed to set overwrite in {}: {}", channel_id, e);
+                    ok = false;
+                }
+            }
+            for old in &change.current {
+                if change
+                    .target
+                    .iter()
+                    .any(|overwrite| overwrite.target == old.target)
+                {
+                    continue;
+                }
+                let kind = old.to_overwrite().kind;
+                if let Err(e) = actions::delete_overwrite(ctx, channel_id, kind).await {
+                    warn!("Failed to remove overwrite in {}: {}", channel_id, e);
+                    ok = false;
+                }
+            }
+            if ok {
+                changed += 1;
+            } else {
+                failed += 1;
+            }
+        }
+
+        if ctx.dry_run.is_enabled() {
+            return Ok(());
+        }
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action: format!("{} ({} channels)", plan.action, changed + failed),
+                reason: None,
+            },
+        )
+        .await;
+        if failed == 0 {
+            send_success(
+                ctx.ctx,
+                ctx.msg,
+                format!("Updated the permissions of {} channels.", changed),
+            )
+            .await?;
+        } else {
+            send_warning(
+                ctx.ctx,
+                ctx.msg,
+                format!(
+                    "Updated {} channels, but {} couldn't be fully changed. Check that my role is above the roles involved and that I can manage those channels.",
+                    changed, failed
+                ),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Save a channel's overwrites as a template, or delete a template.
+    async fn template(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let action = ctx.args.get(1).map(|arg| arg.to_lowercase());
+        let name = ctx.args.get(2).map(|arg| arg.to_lowercase());
+        match (action.as_deref(), name) {
+            (Some("save"), Some(name)) => {
+                if let Err(e) = permission_templates::check_name(&name) {
+                    send_error(ctx.ctx, ctx.msg, e).await?;
+                    return Ok(());
+                }
+                let channel = match ctx.args.get(3).map(|arg| guild_channel(ctx, guild_id, arg)) {
+                    Some(Ok(channel)) => channel,
+                    Some(Err(e)) => {
+                        send_error(ctx.ctx, ctx.msg, e).await?;
+                        return Ok(());
+                    }
+                    None => {
+                        send_error(
+                            ctx.ctx,
+                            ctx.msg,
+                            "Usage: `permsync template save <name> <channel>`",
+                        )
+                        .await?;
+                        return Ok(());
+                    }
+                };
+                let template = PermissionTemplate {
+                    overwrites: permission_templates::from_overwrites(
+                        &channel.permission_overwrites,
+                    ),
+                };
+
+                let config = self.store.read().await.get(guild_id);
+                if !config.permission_templates.contains_key(&name)
+                    && config.permission_templates.len() >= MAX_TEMPLATES
+                {
+                    send_error(
+                        ctx.ctx,
+                        ctx.msg,
+                        format!("A server can have up to {} templates.", MAX_TEMPLATES),
+                    )
+                    .await?;
+                    return Ok(());
+                }
+                let count = template.overwrites.len();
+                self.store
+                    .update(|configs| {
+                        configs
+                            .entry(guild_id)
+                            .permission_templates
+                            .insert(name.clone(), template);
+                    })
+                    .await?;
+                self.audit(
+                    ctx,
+                    guild_id,
+                    format!("Save permission template `{}`", name),
+                )
+                .await;
+                send_success(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!(
+                        "Saved {} overwrites from <#{}> as `{}`.",
+                        count, channel.id, name
+                    ),
+                )
+                .await?;
+            }
+            (Some("delete" | "remove"), Some(name)) => {
+                let mut removed = false;
+                self.store
+                    .update(|configs| {
+                        removed = configs
+                            .entry(guild_id)
+                            .permission_templates
+                            .remove(&name)
+                            .is_some();
+                    })
+                    .await?;
+                if removed {
+                    self.audit(
+                        ctx,
+                        guild_id,
+                        format!("Delete permission template `{}`", name),
+                    )
+                    .await;
+                    send_success(
+                        ctx.ctx,
+                        ctx.msg,
+                        format!("Deleted the `{}` template.", name),
+                    )
+                    .await?;
+                } else {
+                    send_error(ctx.ctx, ctx.msg, format!("There's no `{}` template.", name))
+                        .await?;
+                }
+            }
+            _ => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    "Usage: `permsync template save <name> <channel>` or `permsync template delete <name>`",
+                )
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// List the guild's templates.
+    async fn list(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let config = self.store.read().await.get(guild_id);
+        let description = if config.permission_templates.is_empty() {
+            "This server has no permission templates. Save one with `permsync template save <name> <channel>`.".to_string()
+        } else {
+            config
+                .permission_templates
+                .iter()
+                .map(|(name, template)| {
+                    format!("`{}`: {} overwrites", name, template.overwrites.len())
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        send_info(ctx.ctx, ctx.msg, "Permission templates", description).await?;
+        Ok(())
+    }
+
+    /// Record a template change in the audit log.
+    async fn audit(&self, ctx: &CommandContext<'_>, guild_id: GuildId, action: String) {
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action,
+                reason: None,
+            },
+        )
+        .await;
+    }
+}
+
+/// Look up a channel of this guild from a mention or ID.
+fn guild_channel(
+    ctx: &CommandContext<'_>,
+    guild_id: GuildId,
+    arg: &str,
+) -> Result<GuildChannel, String> {
+    parse_channel(arg)
+        .and_then(|channel_id| ctx.ctx.cache.guild_channel(channel_id))
+        .filter(|channel| channel.guild_id == guild_id)
+        .ok_or_else(|| format!("`{}` isn't a channel in this server.", arg))
+}
+
+/// Look up the channels to change, at most [`MAX_CHANNELS`].
+fn guild_channels(
+    ctx: &CommandContext<'_>,
+    guild_id: GuildId,
+    args: &[String],
+) -> Result<Vec<GuildChannel>, String> {
+    if args.is_empty() {
+        return Err("Name the channels to change.".to_string());
+    }
+    if args.len() > MAX_CHANNELS {
+        return Err(format!(
+            "Up to {} channels can be changed at once.",
+            MAX_CHANNELS
+        ));
+    }
+    let mut channels: Vec<GuildChannel> = Vec::new();
+    for arg in args {
+        let channel = guild_channel(ctx, guild_id, arg)?;
+        if !channels.iter().any(|other| other.id == channel.id) {
+            channels.push(channel);
+        }
+    }
+    Ok(channels)
+}
+
+/// The change that gives each channel `target`'s overwrites.
+fn change_to(channels: &[GuildChannel], target: &[TemplateOverwrite]) -> Vec<ChannelChange> {
+    channels
+        .iter()
+        .map(|channel| ChannelChange {
+            channel_id: channel.id,
+            current: permission_templates::from_overwrites(&channel.permission_overwrites),
+            target: target.to_vec(),
+        })
+        .collect()
+}
+
+#[async_trait]
+impl Command for PermSyncCommand {
+    fn name(&self) -> &str {
+        "permsync"
+    }
+
+    fn description(&self) -> &str {
+        "Sync channel permissions to their category, another channel or a template"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            Param::required(
+                "action",
+                ParamKind::Word,
+                "category, copy, apply, template, templates or confirm",
+            )
+            .choices(&[
+                "category",
+                "copy",
+                "apply",
+                "template",
+                "templates",
+                "confirm",
+            ]),
+            Param::optional(
+                "targets",
+                ParamKind::Text,
+                "The source channel or template, then the channels to change",
+            ),
+        ]
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD | Permissions::MANAGE_CHANNELS | Permissions::MANAGE_ROLES
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::MANAGE_CHANNELS | Permissions::MANAGE_ROLES
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Permissions can only be synced in a server")?;
+        let action = ctx.args.first().map(|arg| arg.to_lowercase());
+
+        let plan = match action.as_deref() {
+            Some("confirm") => return self.confirm(&ctx, guild_id).await,
+            Some("template") => return self.template(&ctx, guild_id).await,
+            Some("templates") => return self.list(&ctx, guild_id).await,
+            Some("category") => {
+                guild_channels(&ctx, guild_id, &ctx.args[1..]).and_then(|channels| {
+                    let changes = channels
+                        .iter()
+                        .map(|channel| {
+                            let parent = channel
+                                .parent_id
+                                .and_then(|parent_id| ctx.ctx.cache.guild_channel(parent_id))
+                                .ok_or_else(|| format!("<#{}> isn't in a category.", channel.id))?;
+                            Ok(ChannelChange {
+                                channel_id: channel.id,
+                                current: permission_templates::from_overwrites(
+                                    &channel.permission_overwrites,
+                                ),
+                                target: permission_templates::from_overwrites(
+                                    &parent.permission_overwrites,
+                                ),
+                            })
+                        })
+                        .collect::<Result<Vec<_>, String>>()?;
+                    Ok(Plan {
+                        action: "Sync channel permissions to their category".to_string(),
+                        changes,
+                    })
+                })
+            }
+            Some("copy") if ctx.args.len() > 1 => guild_channel(&ctx, guild_id, &ctx.args[1])
+                .and_then(|source| {
+                    let target =
+                        permission_templates::from_overwrites(&source.permission_overwrites);
+                    let channels = guild_channels(&ctx, guild_id, &ctx.args[2..])?;
+                    Ok(Plan {
+                        action: format!("Copy channel permissions from #{}", source.name),
+                        changes: change_to(&channels, &target),
+                    })
+                }),
+            Some("apply") if ctx.args.len() > 1 => {
+                let name = ctx.args[1].to_lowercase();
+                let config = self.store.read().await.get(guild_id);
+                match config.permission_templates.get(&name) {
+                    Some(template) => {
+                        guild_channels(&ctx, guild_id, &ctx.args[2..]).map(|channels| Plan {
+                            action: format!("Apply permission template `{}`", name),
+                            changes: change_to(&channels, &template.overwrites),
+                        })
+                    }
+                    None => Err(format!(
+                        "There's no `{}` template. See `permsync templates`.",
+                        name
+                    )),
+                }
+            }
+            _ => Err(format!("Usage: `{}`", usage_of(self))),
+        };
+
+        match plan {
+            Ok(plan) => self.preview(&ctx, guild_id, plan).await,
+            Err(e) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                Ok(())
+            }
+        }
+    }
+}
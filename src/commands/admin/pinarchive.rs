@@ -0,0 +1,112 @@
+//! PinArchive command for moving a channel's existing pins to the archive channel.
+
+use async_trait::async_trait;
+use serenity::model::permissions::Permissions;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::models::guild_config::guild_config;
+use crate::utils::actions;
+use crate::utils::helpers::{archive_pin, parse_channel, send_error, send_success};
+use crate::utils::rest;
+
+/// Archives the pins a channel already has, optionally unpinning them.
+pub struct PinArchiveCommand;
+
+#[async_trait]
+impl Command for PinArchiveCommand {
+    fn name(&self) -> &str {
+        "pinarchive"
+    }
+
+    fn description(&self) -> &str {
+        "Copy a channel's existing pins to the pin archive channel"
+    }
+
+    fn usage(&self) -> &str {
+        "pinarchive [channel] [unpin]"
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::MANAGE_MESSAGES
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Pin archive can only be used in a server")?;
+
+        let mut channel_id = ctx.msg.channel_id;
+        let mut unpin = false;
+        for arg in &ctx.args {
+            if arg.eq_ignore_ascii_case("unpin") {
+                unpin = true;
+            } else if let Some(id) = parse_channel(arg) {
+                channel_id = id;
+            } else {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", self.usage())).await?;
+                return Ok(());
+            }
+        }
+
+        let archive_channel = guild_config(ctx.ctx, guild_id).await.pin_archive_channel;
+        if archive_channel.is_none() {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                "Set an archive channel first with `settings pin_archive_channel <channel>`.",
+            )
+            .await?;
+            return Ok(());
+        }
+        if archive_channel == Some(channel_id.0) {
+            send_error(ctx.ctx, ctx.msg, "That is the archive channel.").await?;
+            return Ok(());
+        }
+        let in_guild = ctx
+            .ctx
+            .cache
+            .guild_channel(channel_id)
+            .is_some_and(|channel| channel.guild_id == guild_id);
+        if !in_guild {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                "I can't find that channel in this server.",
+            )
+            .await?;
+            return Ok(());
+        }
+
+        // Oldest first, so the archive reads in pin order
+        let pins = rest::call(ctx.ctx, "pins", || channel_id.pins(&ctx.ctx.http)).await?;
+        let mut archived = 0;
+        for pin in pins.iter().rev() {
+            let skip = ctx
+                .dry_run
+                .record(format!("Archive pinned message {}", pin.id));
+            if !skip && archive_pin(ctx.ctx, guild_id, pin).await? {
+                archived += 1;
+            }
+            if unpin {
+                actions::unpin(&ctx, channel_id, pin.id).await?;
+            }
+        }
+
+        let mut summary = format!(
+            "Archived {} of {} pins from <#{}>.",
+            archived,
+            pins.len(),
+            channel_id
+        );
+        if unpin {
+            summary.push_str(" The pins were removed.");
+        }
+        send_success(ctx.ctx, ctx.msg, summary).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,203 @@
+//! PinVote command for letting members pin messages with reactions.
+
+use async_trait::async_trait;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::guild_config::{GuildConfigKey, GuildConfigs};
+use crate::models::pin_votes::MAX_PIN_THRESHOLD;
+use crate::storage::JsonStore;
+use crate::utils::helpers::{parse_channel, send_error, send_info, send_success};
+
+const USAGE: &str =
+    "pinvote | pinvote votes <count|off|default> [channel] | pinvote emoji <emoji|default>";
+
+/// Whether an argument looks like an emoji: a custom emoji mention, or text
+/// without letters or digits.
+fn is_emoji(arg: &str) -> bool {
+    if let Some(inner) = arg.strip_prefix('<').and_then(|s| s.strip_suffix('>')) {
+        let parts: Vec<&str> = inner.trim_start_matches('a').split(':').collect();
+        return matches!(parts.as_slice(), ["", name, id]
+            if !name.is_empty() && id.parse::<u64>().is_ok());
+    }
+    !arg.is_empty() && !arg.chars().any(|c| c.is_ascii_alphanumeric())
+}
+
+/// Sets how many reactions pin a message, per channel.
+pub struct PinVoteCommand {
+    store: Arc<JsonStore<GuildConfigs>>,
+}
+
+impl PinVoteCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<GuildConfigKey>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Command for PinVoteCommand {
+    fn name(&self) -> &str {
+        "pinvote"
+    }
+
+    fn description(&self) -> &str {
+        "Let members pin messages by reacting with an emoji"
+    }
+
+    fn usage(&self) -> &str {
+        USAGE
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::MANAGE_MESSAGES | Permissions::READ_MESSAGE_HISTORY
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Pin voting can only be used in a server")?;
+
+        let action = ctx.args.first().map(|arg| arg.to_lowercase());
+        let value = ctx.args.get(1).map(String::as_str);
+        let (change, reply) = match (action.as_deref(), value) {
+            (None, _) => {
+                let settings = self.store.read().await.get(guild_id).pin_votes;
+                send_info(ctx.ctx, ctx.msg, "📌 Pin voting", settings.describe()).await?;
+                return Ok(());
+            }
+            (Some("emoji"), Some(emoji)) => {
+                let emoji = if emoji.eq_ignore_ascii_case("default") {
+                    None
+                } else if is_emoji(emoji) {
+                    Some(emoji.to_string())
+                } else {
+                    send_error(ctx.ctx, ctx.msg, "That doesn't look like an emoji.").await?;
+                    return Ok(());
+                };
+                let settings = self
+                    .store
+                    .update(|configs| {
+                        let settings = &mut configs.entry(guild_id).pin_votes;
+                        settings.emoji = emoji;
+                        settings.clone()
+                    })
+                    .await?;
+                (
+                    format!("Set the pin vote emoji to {}", settings.emoji()),
+                    format!("Members now vote with {}.", settings.emoji()),
+                )
+            }
+            (Some("votes"), Some(value)) => {
+                let channel = match ctx.args.get(2) {
+                    Some(arg) => match parse_channel(arg) {
+                        Some(channel) => Some(channel),
+                        None => {
+                            send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", USAGE)).await?;
+                            return Ok(());
+                        }
+                    },
+                    None => None,
+                };
+                // `default` drops a channel's own threshold
+                let votes = match value.to_lowercase().as_str() {
+                    "off" => Some(0),
+                    "default" if channel.is_some() => None,
+                    value => match value.parse::<u32>() {
+                        Ok(votes) if (1..=MAX_PIN_THRESHOLD).contains(&votes) => Some(votes),
+                        _ => {
+                            send_error(
+                                ctx.ctx,
+                                ctx.msg,
+                                format!(
+                                    "The votes needed must be between 1 and {}, or `off`.",
+                                    MAX_PIN_THRESHOLD
+                                ),
+                            )
+                            .await?;
+                            return Ok(());
+                        }
+                    },
+                };
+                self.store
+                    .update(|configs| {
+                        let settings = &mut configs.entry(guild_id).pin_votes;
+                        match (channel, votes) {
+                            (Some(channel), Some(votes)) => {
+                                settings.channels.insert(channel.0, votes);
+                            }
+                            (Some(channel), None) => {
+                                settings.channels.remove(&channel.0);
+                            }
+                            (None, votes) => settings.threshold = votes.unwrap_or_default(),
+                        }
+                    })
+                    .await?;
+
+                let place = match channel {
+                    Some(channel) => format!("<#{}>", channel),
+                    None => "every channel".to_string(),
+                };
+                match votes {
+                    Some(0) => (
+                        format!("Turn pin voting off in {}", place),
+                        format!("Pin voting is off in {}.", place),
+                    ),
+                    Some(votes) => (
+                        format!("Pin messages with {} votes in {}", votes, place),
+                        format!("Messages in {} are pinned at {} votes.", place, votes),
+                    ),
+                    None => (
+                        format!("Use the default pin votes in {}", place),
+                        format!("{} uses the server's pin votes again.", place),
+                    ),
+                }
+            }
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", USAGE)).await?;
+                return Ok(());
+            }
+        };
+
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action: change,
+                reason: None,
+            },
+        )
+        .await;
+        send_success(ctx.ctx, ctx.msg, reply).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_emojis() {
+        assert!(is_emoji("📌"));
+        assert!(is_emoji("<:pin:123>"));
+        assert!(is_emoji("<a:pin:123>"));
+        assert!(!is_emoji("pin"));
+        assert!(!is_emoji("<:pin:abc>"));
+        assert!(!is_emoji("<#123>"));
+    }
+}
@@ -0,0 +1,106 @@
+//! ServerData command for exporting everything the bot stores about a guild.
+
+use async_trait::async_trait;
+use serenity::builder::CreateEmbed;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::queue::{ConcurrencyScope, MaxConcurrency};
+use crate::framework::state::Inject;
+use crate::models::guild_config::{GuildConfigKey, GuildConfigs};
+use crate::models::moderation::{ModerationData, ModerationKey};
+use crate::models::modmail::{ModmailData, ModmailKey};
+use crate::models::user_data::GuildDataExport;
+use crate::storage::{JsonStore, KvKey, KvStore};
+use crate::utils::constants::DEFAULT_COLOR;
+use crate::utils::files::{send_file, OutgoingFile};
+
+/// Description of the export embed.
+const DESCRIPTION: &str = "Attached is everything the bot stores about this server: settings, \
+                           moderation records, modmail and feature data.";
+
+/// Sends the server's stored data as a JSON file.
+pub struct ServerDataCommand {
+    configs: Arc<JsonStore<GuildConfigs>>,
+    moderation: Arc<JsonStore<ModerationData>>,
+    modmail: Arc<JsonStore<ModmailData>>,
+    kv: Arc<KvStore>,
+}
+
+impl ServerDataCommand {
+    /// Create the command with its stores.
+    pub fn new(
+        (Inject(configs), Inject(moderation), Inject(modmail), Inject(kv)): (
+            Inject<GuildConfigKey>,
+            Inject<ModerationKey>,
+            Inject<ModmailKey>,
+            Inject<KvKey>,
+        ),
+    ) -> Self {
+        Self {
+            configs,
+            moderation,
+            modmail,
+            kv,
+        }
+    }
+}
+
+#[async_trait]
+impl Command for ServerDataCommand {
+    fn name(&self) -> &str {
+        "serverdata"
+    }
+
+    fn description(&self) -> &str {
+        "Export everything the bot stores about this server"
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::ADMINISTRATOR
+    }
+
+    fn max_concurrency(&self) -> Option<MaxConcurrency> {
+        Some(MaxConcurrency::one(ConcurrencyScope::Guild))
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::ATTACH_FILES.union(Permissions::EMBED_LINKS)
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Server data can only be exported in a server")?;
+
+        let config = self.configs.read().await.get(guild_id);
+        let kv = self.kv.guild_values(guild_id).await;
+        let json = {
+            let moderation = self.moderation.read().await;
+            let modmail = self.modmail.read().await;
+            let export = GuildDataExport::collect(guild_id, config, &moderation, &modmail, kv);
+            serde_json::to_string_pretty(&export)?
+        };
+
+        let mut embed = CreateEmbed::default();
+        embed
+            .title("Server data")
+            .color(DEFAULT_COLOR)
+            .description(DESCRIPTION);
+        send_file(
+            ctx.ctx,
+            ctx.msg.channel_id,
+            Some(guild_id),
+            OutgoingFile::new(format!("kurumi-server-{}.json", guild_id), json),
+            Some(embed),
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,329 @@
+//! Settings command for viewing and changing per-guild configuration.
+
+use async_trait::async_trait;
+use serenity::builder::CreateEmbed;
+use serenity::model::id::{GuildId, UserId};
+use serenity::model::permissions::Permissions;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::plugin::{Modules, ModulesKey};
+use crate::framework::state::Inject;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::config_transfer::{self, Format, MAX_IMPORT_SIZE};
+use crate::models::guild_config::{GuildConfig, GuildConfigKey, GuildConfigs};
+use crate::storage::JsonStore;
+use crate::utils::constants::DEFAULT_COLOR;
+use crate::utils::diagnostics::missing_references;
+use crate::utils::files::{send_file, OutgoingFile};
+use crate::utils::helpers::{send_error, send_info, send_success, send_warning, truncate};
+
+/// How long a previewed import waits for `settings import confirm`.
+const IMPORT_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Shows or changes the server's settings.
+pub struct SettingsCommand {
+    store: Arc<JsonStore<GuildConfigs>>,
+    modules: Arc<Modules>,
+    /// Imports waiting for confirmation, by guild and the member who previewed them.
+    pending: Mutex<HashMap<(GuildId, UserId), (GuildConfig, Instant)>>,
+}
+
+impl SettingsCommand {
+    /// Create the command with its store.
+    pub fn new(
+        (Inject(store), Inject(modules)): (Inject<GuildConfigKey>, Inject<ModulesKey>),
+    ) -> Self {
+        Self {
+            store,
+            modules,
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Send the server's settings as a file.
+    async fn export(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let format = match ctx.args.get(1).map(|arg| arg.parse::<Format>()) {
+            None => Format::default(),
+            Some(Ok(format)) => format,
+            Some(Err(e)) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+        };
+
+        let config = self.store.read().await.get(guild_id);
+        let content = config_transfer::export(&config, format)?;
+        let mut embed = CreateEmbed::default();
+        embed
+            .title("Server settings")
+            .color(DEFAULT_COLOR)
+            .description("Import them in any server with `settings import`.");
+        send_file(
+            ctx.ctx,
+            ctx.msg.channel_id,
+            Some(guild_id),
+            OutgoingFile::new(
+                format!("settings-{}.{}", guild_id, format.extension()),
+                content,
+            ),
+            Some(embed),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Preview the settings in an attached file, or apply the last preview.
+    async fn import(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let key = (guild_id, ctx.msg.author.id);
+        let confirmed = ctx
+            .args
+            .get(1)
+            .is_some_and(|arg| arg.eq_ignore_ascii_case("confirm"));
+        if confirmed {
+            let pending = self
+                .pending
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(&key)
+                .filter(|(_, at)| at.elapsed() < IMPORT_TIMEOUT);
+            return match pending {
+                Some((config, _)) => self.apply(ctx, guild_id, config).await,
+                None => {
+                    send_error(
+                        ctx.ctx,
+                        ctx.msg,
+                        "There's no import to confirm. Attach a file to `settings import` first.",
+                    )
+                    .await?;
+                    Ok(())
+                }
+            };
+        }
+
+        let attachment = match ctx.msg.attachments.first() {
+            Some(attachment) => attachment,
+            None => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    "Attach a JSON or TOML file exported with `settings export`.",
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+        if attachment.size > MAX_IMPORT_SIZE {
+            send_error(ctx.ctx, ctx.msg, "That file is too large to import.").await?;
+            return Ok(());
+        }
+        let format = match Format::from_file_name(&attachment.filename) {
+            Some(format) => format,
+            None => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    "Only `.json` and `.toml` files can be imported.",
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let data = attachment.download().await?;
+        let mut imported = match String::from_utf8(data)
+            .map_err(|_| "That file isn't text.".to_string())
+            .and_then(|content| config_transfer::import(&content, format))
+        {
+            Ok(imported) => imported,
+            Err(e) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+        };
+
+        let current = self.store.read().await.get(guild_id);
+        let owner = is_guild_owner(ctx, guild_id);
+        if !owner {
+            config_transfer::keep_owner_settings(&mut imported, &current);
+        }
+        let changes = config_transfer::diff(&current, &imported);
+        if changes.is_empty() {
+            send_info(
+                ctx.ctx,
+                ctx.msg,
+                "Import",
+                "That file matches the current settings.",
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let mut preview = format!(
+            "Importing this file changes {} settings:\n{}",
+            changes.len(),
+            changes.join("\n")
+        );
+        let references = ctx.ctx.cache.guild_field(guild_id, |guild| {
+            let channels: HashSet<u64> = guild.channels.keys().map(|id| id.0).collect();
+            let roles: HashSet<u64> = guild.roles.keys().map(|id| id.0).collect();
+            (channels, roles)
+        });
+        if let Some((channels, roles)) = references {
+            let missing = missing_references(&imported, &channels, &roles);
+            if !missing.is_empty() {
+                preview.push_str(&format!(
+                    "\n\nThese don't exist in this server and won't work until changed:\n{}",
+                    missing.join("\n")
+                ));
+            }
+        }
+        if !owner {
+            preview.push_str(
+                "\n\nAnti-nuke settings are kept, only the server owner can import them.",
+            );
+        }
+        preview = truncate(&preview, 3800);
+        preview.push_str(&format!(
+            "\n\nRun `settings import confirm` within {} minutes to apply it.",
+            IMPORT_TIMEOUT.as_secs() / 60
+        ));
+
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key, (imported, Instant::now()));
+        send_warning(ctx.ctx, ctx.msg, preview).await?;
+        Ok(())
+    }
+
+    /// Replace the server's settings with imported ones.
+    async fn apply(
+        &self,
+        ctx: &CommandContext<'_>,
+        guild_id: GuildId,
+        mut config: GuildConfig,
+    ) -> CommandResult {
+        let owner = is_guild_owner(ctx, guild_id);
+        let changes = self
+            .store
+            .update(|configs| {
+                let current = configs.get(guild_id);
+                if !owner {
+                    config_transfer::keep_owner_settings(&mut config, &current);
+                }
+                let changes = config_transfer::diff(&current, &config).len();
+                configs.guilds.insert(guild_id.0, config);
+                changes
+            })
+            .await?;
+        self.modules.load(&*self.store.read().await);
+
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action: format!("Import settings ({} changes)", changes),
+                reason: None,
+            },
+        )
+        .await;
+        send_success(
+            ctx.ctx,
+            ctx.msg,
+            format!("Imported the settings, {} changed.", changes),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Whether the invoker owns the server, and so can import its anti-nuke settings.
+fn is_guild_owner(ctx: &CommandContext<'_>, guild_id: GuildId) -> bool {
+    ctx.ctx.cache.guild_field(guild_id, |guild| guild.owner_id) == Some(ctx.msg.author.id)
+}
+
+#[async_trait]
+impl Command for SettingsCommand {
+    fn name(&self) -> &str {
+        "settings"
+    }
+
+    fn description(&self) -> &str {
+        "View or change this server's bot settings"
+    }
+
+    fn usage(&self) -> &str {
+        "settings [<key> <value|none>] | settings export [json|toml] | settings import [confirm] (attach a file)"
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["config"]
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Settings can only be used in a server")?;
+        let store = &self.store;
+
+        match ctx.args.first().map(|arg| arg.to_lowercase()).as_deref() {
+            Some("export") => return self.export(&ctx, guild_id).await,
+            Some("import") => return self.import(&ctx, guild_id).await,
+            _ => {}
+        }
+
+        let (key, value) = match (ctx.args.first(), ctx.args.get(1)) {
+            (Some(key), Some(value)) => (key.to_lowercase(), value.as_str()),
+            (None, _) => {
+                let config = store.read().await.get(guild_id);
+                send_info(ctx.ctx, ctx.msg, "Server settings", config.describe()).await?;
+                return Ok(());
+            }
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", self.usage())).await?;
+                return Ok(());
+            }
+        };
+
+        // Validate against a copy so a bad value doesn't touch the store
+        let mut config = store.read().await.get(guild_id);
+        if let Err(e) = config.set(&key, value) {
+            send_error(ctx.ctx, ctx.msg, e).await?;
+            return Ok(());
+        }
+
+        store
+            .update(|configs| {
+                configs.guilds.insert(guild_id.0, config);
+            })
+            .await?;
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action: format!("Set `{}` to `{}`", key, value),
+                reason: None,
+            },
+        )
+        .await;
+        send_success(ctx.ctx, ctx.msg, format!("Updated `{}`.", key)).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+//! Setup command for configuring a new server in a few clicks.
+
+use async_trait::async_trait;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+
+use crate::events::setup_panel;
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::guild_config::{GuildConfigKey, GuildConfigs};
+use crate::storage::JsonStore;
+use crate::utils::rest;
+
+/// Posts the setup panel, see [`crate::events::SetupHandler`].
+pub struct SetupCommand {
+    store: Arc<JsonStore<GuildConfigs>>,
+}
+
+impl SetupCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<GuildConfigKey>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Command for SetupCommand {
+    fn name(&self) -> &str {
+        "setup"
+    }
+
+    fn description(&self) -> &str {
+        "Set up the staff channel, welcome channel, staff role and phishing protection"
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::EMBED_LINKS
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Setup can only be used in a server")?;
+
+        let config = self.store.read().await.get(guild_id);
+        let (embed, components) = setup_panel(ctx.ctx, guild_id, ctx.msg.author.id, &config);
+        let (embed, components) = (&embed, &components);
+        rest::call(ctx.ctx, "send_message", || {
+            ctx.msg.channel_id.send_message(&ctx.ctx.http, |m| {
+                m.set_embed(embed.clone())
+                    .set_components(components.clone())
+            })
+        })
+        .await?;
+        Ok(())
+    }
+}
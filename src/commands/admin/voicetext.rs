@@ -0,0 +1,228 @@
+//! VoiceText command for linking voice channels to text channels.
+
+use async_trait::async_trait;
+use serenity::model::channel::ChannelType;
+use serenity::model::id::{ChannelId, GuildId};
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+
+use crate::events::{delete_temporary, revoke_connected};
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::params::{usage_of, Param, ParamKind};
+use crate::framework::state::Inject;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::guild_config::{GuildConfigKey, GuildConfigs};
+use crate::models::voice_text::{VoiceText, MAX_LINKS};
+use crate::storage::JsonStore;
+use crate::utils::helpers::{parse_channel, send_error, send_info, send_success};
+
+/// Links a voice channel to a text channel that members get access to while
+/// they're connected, or to a temporary one that exists while anyone is.
+pub struct VoiceTextCommand {
+    store: Arc<JsonStore<GuildConfigs>>,
+}
+
+impl VoiceTextCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<GuildConfigKey>) -> Self {
+        Self { store }
+    }
+
+    /// Whether a channel is a channel of a kind in the guild.
+    fn is_kind(
+        ctx: &CommandContext<'_>,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        kinds: &[ChannelType],
+    ) -> bool {
+        ctx.ctx
+            .cache
+            .guild_channel(channel_id)
+            .is_some_and(|channel| channel.guild_id == guild_id && kinds.contains(&channel.kind))
+    }
+
+    /// List the guild's links.
+    async fn list(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let settings = self.store.read().await.get(guild_id).voice_text;
+        let mut links: Vec<String> = settings
+            .links
+            .iter()
+            .map(|(voice, link)| match link {
+                VoiceText::Channel(text) => format!("<#{}> → <#{}>", voice, text),
+                VoiceText::Temporary => match settings.temporary.get(voice) {
+                    Some(text) => format!("<#{}> → temporary, now <#{}>", voice, text),
+                    None => format!("<#{}> → temporary", voice),
+                },
+            })
+            .collect();
+        links.sort();
+        let description = if links.is_empty() {
+            format!("No voice channels are linked. Usage: `{}`", usage_of(self))
+        } else {
+            links.join("\n")
+        };
+        send_info(ctx.ctx, ctx.msg, "🔊 Voice text channels", description).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Command for VoiceTextCommand {
+    fn name(&self) -> &str {
+        "voicetext"
+    }
+
+    fn description(&self) -> &str {
+        "Give members in a voice channel access to a text channel while they're connected"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            Param::optional(
+                "voice",
+                ParamKind::Channel,
+                "The voice channel to link, the links if left out",
+            ),
+            Param::optional("text", ParamKind::Word, "A text channel, temp or off"),
+        ]
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::MANAGE_CHANNELS
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::MANAGE_CHANNELS | Permissions::MANAGE_ROLES
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Voice text channels can only be set up in a server")?;
+        if ctx.args.is_empty() {
+            return self.list(&ctx, guild_id).await;
+        }
+
+        let voice_channel = ctx.args.first().and_then(|arg| parse_channel(arg));
+        let target = ctx.args.get(1).map(|arg| arg.to_lowercase());
+        let (voice_channel, link) = match (voice_channel, target.as_deref()) {
+            (Some(voice), Some("temp" | "temporary")) => (voice, Some(VoiceText::Temporary)),
+            (Some(voice), Some("off")) => (voice, None),
+            (Some(voice), Some(text)) => match parse_channel(text) {
+                Some(text) if Self::is_kind(&ctx, guild_id, text, &[ChannelType::Text]) => {
+                    (voice, Some(VoiceText::Channel(text.0)))
+                }
+                _ => {
+                    send_error(
+                        ctx.ctx,
+                        ctx.msg,
+                        "Expected a text channel in this server, `temp` or `off`.",
+                    )
+                    .await?;
+                    return Ok(());
+                }
+            },
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", usage_of(self))).await?;
+                return Ok(());
+            }
+        };
+        if link.is_some()
+            && !Self::is_kind(
+                &ctx,
+                guild_id,
+                voice_channel,
+                &[ChannelType::Voice, ChannelType::Stage],
+            )
+        {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                "Only voice channels in this server can be linked.",
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let previous = self
+            .store
+            .update(|configs| {
+                let links = &mut configs.entry(guild_id).voice_text.links;
+                match link {
+                    Some(_)
+                        if !links.contains_key(&voice_channel.0) && links.len() >= MAX_LINKS =>
+                    {
+                        Err(())
+                    }
+                    Some(link) => Ok(links.insert(voice_channel.0, link)),
+                    None => Ok(links.remove(&voice_channel.0)),
+                }
+            })
+            .await?;
+        let previous = match previous {
+            Ok(previous) => previous,
+            Err(()) => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!("Up to {} voice channels can be linked.", MAX_LINKS),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        // Members connected through the old link keep no access it gave them
+        match previous {
+            Some(VoiceText::Temporary) if link != previous => {
+                delete_temporary(ctx.ctx, guild_id, voice_channel).await;
+            }
+            Some(VoiceText::Channel(text)) if link != previous => {
+                revoke_connected(ctx.ctx, guild_id, voice_channel, ChannelId(text)).await;
+            }
+            _ => {}
+        }
+
+        let (action, reply) = match link {
+            Some(VoiceText::Channel(text)) => (
+                format!("Link voice channel <#{}> to <#{}>", voice_channel, text),
+                format!(
+                    "Members in <#{}> will get access to <#{}> while they're connected.",
+                    voice_channel, text
+                ),
+            ),
+            Some(VoiceText::Temporary) => (
+                format!(
+                    "Link voice channel <#{}> to a temporary text channel",
+                    voice_channel
+                ),
+                format!(
+                    "A text channel will be created for <#{}> while anyone is connected.",
+                    voice_channel
+                ),
+            ),
+            None => (
+                format!("Unlink voice channel <#{}>", voice_channel),
+                format!("<#{}> no longer has a text channel.", voice_channel),
+            ),
+        };
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action,
+                reason: None,
+            },
+        )
+        .await;
+        send_success(ctx.ctx, ctx.msg, reply).await?;
+        Ok(())
+    }
+}
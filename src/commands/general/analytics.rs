@@ -0,0 +1,321 @@
+//! Emoji usage and channel activity stats over a chosen number of days.
+
+use async_trait::async_trait;
+use serenity::builder::CreateEmbed;
+use std::sync::Arc;
+use tracing::debug;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::analytics::{today, Analytics, AnalyticsKey};
+use crate::utils::charts::{self, CHART_URL};
+use crate::utils::constants::DEFAULT_COLOR;
+use crate::utils::duration;
+use crate::utils::files::send_file;
+use crate::utils::helpers::{send_error, send_info, truncate};
+
+/// Days covered when no range is given.
+const DEFAULT_DAYS: u64 = 7;
+
+/// Rows shown in a chart.
+const MAX_ROWS: usize = 15;
+
+/// Width of the longest bar, in characters.
+const BAR_WIDTH: usize = 20;
+
+/// Parse a range such as `7d`, `2w` or `all` into a number of days, capped at the
+/// days of counts kept.
+pub(super) fn parse_range(arg: Option<&str>, retention_days: u64) -> Result<u64, String> {
+    let days = match arg {
+        None => DEFAULT_DAYS,
+        Some(arg) if arg.eq_ignore_ascii_case("all") => retention_days,
+        Some(arg) => {
+            let seconds = duration::parse(arg).map_err(|e| e.to_string())?.as_secs();
+            seconds.div_ceil(86_400)
+        }
+    };
+    if days == 0 {
+        return Err("The range has to be at least a day.".to_string());
+    }
+    Ok(days.min(retention_days.max(1)))
+}
+
+/// A bar of [`BAR_WIDTH`] characters, filled in proportion to `count` of `max`.
+fn bar(count: u64, max: u64) -> String {
+    let filled = (count as usize * BAR_WIDTH).div_ceil(max.max(1) as usize);
+    format!("{}{}", "█".repeat(filled), "░".repeat(BAR_WIDTH - filled))
+}
+
+/// Render labelled counts as horizontal bars in a code block.
+fn bar_chart(rows: &[(String, u64)]) -> String {
+    let max = rows.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    let label_width = rows
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let lines: Vec<String> = rows
+        .iter()
+        .map(|(label, count)| {
+            format!(
+                "{:<width$} {} {}",
+                label,
+                bar(*count, max),
+                count,
+                width = label_width
+            )
+        })
+        .collect();
+    format!("```\n{}\n```", lines.join("\n"))
+}
+
+/// Render daily counts as a one-line sparkline.
+pub(super) fn sparkline(counts: &[u64]) -> String {
+    const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = counts.iter().copied().max().unwrap_or(0).max(1);
+    counts
+        .iter()
+        .map(|count| LEVELS[(*count * 7).div_ceil(max) as usize])
+        .collect()
+}
+
+/// A day number as a short date, such as `Mar 04`.
+pub(super) fn day_label(day: u64) -> String {
+    chrono::DateTime::from_timestamp((day * 86_400) as i64, 0)
+        .map(|date| date.format("%b %d").to_string())
+        .unwrap_or_default()
+}
+
+/// A range in days, for titles.
+pub(super) fn describe_days(days: u64) -> String {
+    if days == 1 {
+        "the last day".to_string()
+    } else {
+        format!("the last {} days", days)
+    }
+}
+
+/// Shows the server's most used emojis, in messages and as reactions.
+pub struct EmojiStatsCommand {
+    analytics: Arc<Analytics>,
+}
+
+impl EmojiStatsCommand {
+    /// Create the command with the activity counter.
+    pub fn new(Inject(analytics): Inject<AnalyticsKey>) -> Self {
+        Self { analytics }
+    }
+}
+
+#[async_trait]
+impl Command for EmojiStatsCommand {
+    fn name(&self) -> &str {
+        "emojistats"
+    }
+
+    fn description(&self) -> &str {
+        "Show the server's most used emojis"
+    }
+
+    fn usage(&self) -> &str {
+        "emojistats [range, like 7d, 4w or all]"
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx.msg.guild_id.ok_or("Emoji stats are per server")?;
+        let days = match parse_range(
+            ctx.args.first().map(String::as_str),
+            self.analytics.retention_days(),
+        ) {
+            Ok(days) => days,
+            Err(e) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+        };
+
+        let since = today() + 1 - days;
+        let emojis = self
+            .analytics
+            .read(|data| data.emoji_totals(guild_id, since))
+            .await;
+        let title = format!("😀 Emojis in {}", describe_days(days));
+        if emojis.is_empty() {
+            send_info(ctx.ctx, ctx.msg, title, "No emojis have been used yet.").await?;
+            return Ok(());
+        }
+
+        // Custom emojis don't render in code blocks, so each bar gets its own line
+        let max = emojis[0].1.total();
+        let lines: Vec<String> = emojis
+            .iter()
+            .take(MAX_ROWS)
+            .enumerate()
+            .map(|(i, (emoji, count))| {
+                format!(
+                    "**{}.** {} `{}` {} ({} in messages, {} reactions)",
+                    i + 1,
+                    emoji,
+                    bar(count.total(), max),
+                    count.total(),
+                    count.messages,
+                    count.reactions
+                )
+            })
+            .collect();
+        send_info(ctx.ctx, ctx.msg, title, truncate(&lines.join("\n"), 4096)).await?;
+        Ok(())
+    }
+}
+
+/// Shows message counts per channel and per day.
+pub struct ActivityCommand {
+    analytics: Arc<Analytics>,
+}
+
+impl ActivityCommand {
+    /// Create the command with the activity counter.
+    pub fn new(Inject(analytics): Inject<AnalyticsKey>) -> Self {
+        Self { analytics }
+    }
+}
+
+#[async_trait]
+impl Command for ActivityCommand {
+    fn name(&self) -> &str {
+        "activity"
+    }
+
+    fn description(&self) -> &str {
+        "Show the busiest channels and messages per day"
+    }
+
+    fn usage(&self) -> &str {
+        "activity [range, like 7d, 4w or all]"
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx.msg.guild_id.ok_or("Activity stats are per server")?;
+        let days = match parse_range(
+            ctx.args.first().map(String::as_str),
+            self.analytics.retention_days(),
+        ) {
+            Ok(days) => days,
+            Err(e) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+        };
+
+        let until = today();
+        let since = until + 1 - days;
+        let (channels, daily) = self
+            .analytics
+            .read(|data| {
+                (
+                    data.channel_totals(guild_id, since),
+                    data.daily_messages(guild_id, since, until),
+                )
+            })
+            .await;
+        let title = format!("📊 Activity in {}", describe_days(days));
+        if channels.is_empty() {
+            send_info(
+                ctx.ctx,
+                ctx.msg,
+                title,
+                "No messages have been counted yet.",
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let rows: Vec<(String, u64)> = channels
+            .iter()
+            .take(MAX_ROWS)
+            .map(|(channel_id, count)| {
+                let name = ctx
+                    .ctx
+                    .cache
+                    .guild_channel(*channel_id)
+                    .map(|channel| channel.name)
+                    .unwrap_or_else(|| "deleted-channel".to_string());
+                (format!("#{}", truncate(&name, 24)), *count)
+            })
+            .collect();
+        let total: u64 = daily.iter().map(|(_, count)| count).sum();
+        let summary = format!(
+            "**{}** messages, **{}** a day on average\n{}",
+            total,
+            total / days,
+            bar_chart(&rows)
+        );
+
+        // Show messages per day as a chart, or as a sparkline if it can't be drawn
+        let points: Vec<(String, u64)> = daily
+            .iter()
+            .map(|(day, count)| (day_label(*day), *count))
+            .collect();
+        match charts::line("Messages per day", &points) {
+            Ok(chart) => {
+                let mut embed = CreateEmbed::default();
+                embed
+                    .title(title)
+                    .description(summary)
+                    .image(CHART_URL)
+                    .color(DEFAULT_COLOR);
+                send_file(
+                    ctx.ctx,
+                    ctx.msg.channel_id,
+                    Some(guild_id),
+                    chart,
+                    Some(embed),
+                )
+                .await?;
+            }
+            Err(e) => {
+                debug!("Sending activity without a chart: {}", e);
+                let counts: Vec<u64> = daily.iter().map(|(_, count)| *count).collect();
+                let description = format!("{}\n**Per day**\n`{}`", summary, sparkline(&counts));
+                send_info(ctx.ctx, ctx.msg, title, description).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_caps_ranges() {
+        assert_eq!(parse_range(None, 90), Ok(DEFAULT_DAYS));
+        assert_eq!(parse_range(Some("2w"), 90), Ok(14));
+        assert_eq!(parse_range(Some("36h"), 90), Ok(2));
+        assert!(parse_range(Some("1y"), 90).is_err());
+        assert_eq!(parse_range(Some("all"), 30), Ok(30));
+        assert_eq!(parse_range(Some("365d"), 30), Ok(30));
+    }
+
+    #[test]
+    fn scales_bars_to_the_largest_count() {
+        let chart = bar_chart(&[("#general".to_string(), 10), ("#memes".to_string(), 5)]);
+        assert!(chart.contains(&format!("#general {} 10", "█".repeat(BAR_WIDTH))));
+        assert!(chart.contains(&format!(
+            "#memes   {}{} 5",
+            "█".repeat(BAR_WIDTH / 2),
+            "░".repeat(BAR_WIDTH / 2)
+        )));
+        assert_eq!(sparkline(&[0, 7, 14]), "▁▅█");
+        assert_eq!(day_label(19_000), "Jan 08");
+    }
+}
@@ -0,0 +1,183 @@
+//! Audiofilter command for the equalizer, speed and pitch music is played with.
+
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::params::{usage_of, Param, ParamKind};
+use crate::framework::state::Inject;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::guild_config::{GuildConfigKey, GuildConfigs};
+use crate::models::music::{AudioFilters, Equalizer, PITCH_RANGE, SPEED_RANGE};
+use crate::storage::JsonStore;
+use crate::utils::helpers::{send_error, send_info, send_success};
+use crate::utils::voice;
+
+/// Shows or changes the filters the server's music is played through. They
+/// apply from the next track on and stay on until turned off. Changing them
+/// needs the DJ role or Manage Server.
+pub struct AudioFilterCommand {
+    store: Arc<JsonStore<GuildConfigs>>,
+}
+
+impl AudioFilterCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<GuildConfigKey>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Command for AudioFilterCommand {
+    fn name(&self) -> &str {
+        "audiofilter"
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["af"]
+    }
+
+    fn description(&self) -> &str {
+        "Set the equalizer, speed and pitch the server's music is played with"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            Param::optional(
+                "filter",
+                ParamKind::Word,
+                "An equalizer preset, nightcore, vaporwave, speed, pitch or off",
+            ),
+            Param::optional(
+                "value",
+                ParamKind::Integer,
+                "The speed from 50 to 200%, or the pitch from -12 to 12 semitones",
+            ),
+        ]
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Audio filters can only be set in a server")?;
+        let settings = self.store.read().await.get(guild_id).music;
+        let action = match ctx.args.first() {
+            Some(action) => action.to_lowercase(),
+            None => {
+                let description = format!(
+                    "Filters: {}\n\nUsage: `{}`",
+                    settings.filters.describe(),
+                    usage_of(self)
+                );
+                send_info(ctx.ctx, ctx.msg, "🎛️ Audio filters", description).await?;
+                return Ok(());
+            }
+        };
+
+        if !voice::is_dj(ctx.ctx, ctx.msg, &settings).await {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                "You need the DJ role or Manage Server to change the audio filters.",
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let mut filters = settings.filters;
+        let value = ctx.args.get(1);
+        match (action.as_str(), value) {
+            ("off" | "clear" | "reset", _) => filters = AudioFilters::default(),
+            ("nightcore", _) => {
+                filters.speed = AudioFilters::NIGHTCORE.speed;
+                filters.pitch = AudioFilters::NIGHTCORE.pitch;
+            }
+            ("vaporwave", _) => {
+                filters.speed = AudioFilters::VAPORWAVE.speed;
+                filters.pitch = AudioFilters::VAPORWAVE.pitch;
+            }
+            ("speed", Some(value)) => match value.trim_end_matches('%').parse::<u32>() {
+                Ok(speed) if SPEED_RANGE.contains(&speed) => {
+                    filters.speed = Some(speed).filter(|speed| *speed != 100);
+                }
+                _ => {
+                    send_error(
+                        ctx.ctx,
+                        ctx.msg,
+                        format!(
+                            "The speed has to be from {}% to {}%.",
+                            SPEED_RANGE.start(),
+                            SPEED_RANGE.end()
+                        ),
+                    )
+                    .await?;
+                    return Ok(());
+                }
+            },
+            ("pitch", Some(value)) => match value.trim_start_matches('+').parse::<i32>() {
+                Ok(pitch) if PITCH_RANGE.contains(&pitch) => {
+                    filters.pitch = Some(pitch).filter(|pitch| *pitch != 0);
+                }
+                _ => {
+                    send_error(
+                        ctx.ctx,
+                        ctx.msg,
+                        format!(
+                            "The pitch has to be from {} to +{} semitones.",
+                            PITCH_RANGE.start(),
+                            PITCH_RANGE.end()
+                        ),
+                    )
+                    .await?;
+                    return Ok(());
+                }
+            },
+            (name, None) => match Equalizer::ALL.iter().find(|(preset, _)| *preset == name) {
+                // Picking the preset that's on turns it off
+                Some((_, equalizer)) if filters.equalizer == Some(*equalizer) => {
+                    filters.equalizer = None;
+                }
+                Some((_, equalizer)) => filters.equalizer = Some(*equalizer),
+                None => {
+                    send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", usage_of(self))).await?;
+                    return Ok(());
+                }
+            },
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", usage_of(self))).await?;
+                return Ok(());
+            }
+        }
+
+        self.store
+            .update(|configs| configs.entry(guild_id).music.filters = filters)
+            .await?;
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action: format!("Set the audio filters to {}", filters.describe()),
+                reason: None,
+            },
+        )
+        .await;
+        let reply = if filters.is_empty() {
+            "Audio filters are off.".to_string()
+        } else {
+            format!(
+                "Audio filters: {}. They apply from the next track and stay on until you \
+                 turn them off.",
+                filters.describe()
+            )
+        };
+        send_success(ctx.ctx, ctx.msg, reply).await?;
+        Ok(())
+    }
+}
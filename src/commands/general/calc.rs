@@ -0,0 +1,90 @@
+//! Calc command for evaluating arithmetic expressions.
+
+use async_trait::async_trait;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::params::{usage_of, Param, ParamKind};
+use crate::utils::calc::{evaluate, MAX_EXACT};
+use crate::utils::helpers::{send_error, send_info, truncate};
+use crate::utils::units::format_number;
+
+/// Format a result, marking it as approximate once it's too large to be exact.
+fn format_result(value: f64) -> String {
+    let formatted = format_number(value, 10);
+    if value.abs() >= MAX_EXACT {
+        format!("≈ {}", formatted)
+    } else {
+        formatted
+    }
+}
+
+/// Evaluates an arithmetic expression.
+pub struct CalcCommand;
+
+#[async_trait]
+impl Command for CalcCommand {
+    fn name(&self) -> &str {
+        "calc"
+    }
+
+    fn description(&self) -> &str {
+        "Work out a math expression, like 2 * (3 + sqrt(16))"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![Param::required(
+            "expression",
+            ParamKind::Text,
+            "The math to work out",
+        )]
+    }
+
+    fn wizard(&self) -> bool {
+        true
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["math", "calculate"]
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let expression = ctx.args.join(" ");
+        let expression = expression.trim().trim_matches('`').trim();
+        if expression.is_empty() {
+            send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", usage_of(self))).await?;
+            return Ok(());
+        }
+
+        match evaluate(expression) {
+            Ok(value) => {
+                send_info(
+                    ctx.ctx,
+                    ctx.msg,
+                    "🧮 Calculator",
+                    format!(
+                        "`{}`\n= **{}**",
+                        truncate(&expression.replace('`', "'"), 1000),
+                        format_result(value)
+                    ),
+                )
+                .await?;
+            }
+            Err(e) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marks_inexact_results() {
+        assert_eq!(format_result(1234.5), "1,234.5");
+        assert_eq!(format_result(1.0 / 3.0), "0.3333333333");
+        assert!(format_result(2f64.powi(60)).starts_with("≈ "));
+    }
+}
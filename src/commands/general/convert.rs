@@ -0,0 +1,302 @@
+//! Convert and crypto commands for units, currencies and coin prices.
+
+use async_trait::async_trait;
+use serenity::builder::CreateEmbed;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::utils::constants::DEFAULT_COLOR;
+use crate::utils::duration::relative;
+use crate::utils::exchange::{coin_id, coin_symbol, Exchange, ExchangeKey};
+use crate::utils::helpers::{apply_mentions, mention_policy, send_error};
+use crate::utils::rest;
+use crate::utils::units::{convert, find_unit, format_number, parse_query};
+
+/// Decimals to show for an amount of money, so small prices keep their digits.
+fn money_decimals(value: f64) -> usize {
+    if value.abs() >= 1.0 {
+        2
+    } else {
+        6
+    }
+}
+
+/// Format a 24 hour change, like `📈 +2.31%`.
+fn format_change(change: f64) -> String {
+    if change < 0.0 {
+        format!("📉 {:.2}%", change)
+    } else {
+        format!("📈 +{:.2}%", change)
+    }
+}
+
+/// Send an embed to the channel a command was used in.
+async fn send_embed(ctx: &CommandContext<'_>, embed: CreateEmbed) -> CommandResult {
+    let policy = &mention_policy(ctx.ctx).await;
+    rest::call(ctx.ctx, "send_message", || {
+        let embed = embed.clone();
+        ctx.msg.channel_id.send_message(&ctx.ctx.http, move |m| {
+            m.allowed_mentions(|am| apply_mentions(am, policy, &[]))
+                .set_embed(embed)
+        })
+    })
+    .await?;
+    Ok(())
+}
+
+/// A converted amount of money and when its rate was last updated.
+struct Converted {
+    value: f64,
+    updated_at: u64,
+    source: &'static str,
+}
+
+/// Converts between units, currencies and coins.
+pub struct ConvertCommand {
+    exchange: Arc<Exchange>,
+}
+
+impl ConvertCommand {
+    /// Create the command with the exchange rate provider.
+    pub fn new(Inject(exchange): Inject<ExchangeKey>) -> Self {
+        Self { exchange }
+    }
+
+    /// Convert money between currencies and known coins, or `None` if either side
+    /// isn't a currency or coin.
+    async fn convert_money(
+        &self,
+        amount: f64,
+        from: &str,
+        to: &str,
+    ) -> CommandResult<Option<Converted>> {
+        let converted = match (coin_id(from), coin_id(to)) {
+            (Some(coin), None) => self.exchange.price(coin, to).await?.map(|price| Converted {
+                value: amount * price.price,
+                updated_at: price.updated_at,
+                source: "CoinGecko",
+            }),
+            (None, Some(coin)) => self
+                .exchange
+                .price(coin, from)
+                .await?
+                .map(|price| Converted {
+                    value: amount / price.price,
+                    updated_at: price.updated_at,
+                    source: "CoinGecko",
+                }),
+            (Some(from), Some(to)) => {
+                let from = self.exchange.price(from, "usd").await?;
+                let to = self.exchange.price(to, "usd").await?;
+                from.zip(to).map(|(from, to)| Converted {
+                    value: amount * from.price / to.price,
+                    updated_at: from.updated_at.min(to.updated_at),
+                    source: "CoinGecko",
+                })
+            }
+            (None, None) => {
+                let rates = self.exchange.rates().await?;
+                rates.convert(amount, from, to).map(|value| Converted {
+                    value,
+                    updated_at: rates.updated_at,
+                    source: "ExchangeRate-API",
+                })
+            }
+        };
+        Ok(converted.filter(|converted| converted.value.is_finite()))
+    }
+}
+
+#[async_trait]
+impl Command for ConvertCommand {
+    fn name(&self) -> &str {
+        "convert"
+    }
+
+    fn description(&self) -> &str {
+        "Convert between units, currencies and crypto"
+    }
+
+    fn usage(&self) -> &str {
+        "convert <amount> <from> to <to>"
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["conv"]
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::EMBED_LINKS
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let query = match parse_query(&ctx.args.join(" ")) {
+            Ok(query) => query,
+            Err(e) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+        };
+
+        let mut embed = CreateEmbed::default();
+        embed.color(DEFAULT_COLOR);
+        if let (Some(from), Some(to)) = (find_unit(&query.from), find_unit(&query.to)) {
+            let value = match convert(query.amount, from, to) {
+                Some(value) => value,
+                None => {
+                    send_error(
+                        ctx.ctx,
+                        ctx.msg,
+                        format!(
+                            "`{}` is a {} and `{}` is a {}, so they can't be converted.",
+                            from.symbol, from.dimension, to.symbol, to.dimension
+                        ),
+                    )
+                    .await?;
+                    return Ok(());
+                }
+            };
+            embed.title("📏 Conversion").description(format!(
+                "**{} {}** = **{} {}**",
+                format_number(query.amount, 4),
+                from.symbol,
+                format_number(value, 4),
+                to.symbol
+            ));
+            return send_embed(&ctx, embed).await;
+        }
+
+        let (from, to) = (query.from.to_uppercase(), query.to.to_uppercase());
+        let converted = match self.convert_money(query.amount, &from, &to).await? {
+            Some(converted) => converted,
+            None => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!(
+                        "I don't know how to convert `{}` to `{}`. Try units like `km`, currency codes like `EUR` or coins like `BTC`.",
+                        query.from, query.to
+                    ),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+        embed
+            .title("💱 Conversion")
+            .description(format!(
+                "**{} {}** = **{} {}**",
+                format_number(query.amount, money_decimals(query.amount)),
+                from,
+                format_number(converted.value, money_decimals(converted.value)),
+                to
+            ))
+            .field("Updated", relative(converted.updated_at), true)
+            .footer(|f| f.text(format!("Rates from {}", converted.source)));
+        send_embed(&ctx, embed).await
+    }
+}
+
+/// Shows the price of a coin.
+pub struct CryptoCommand {
+    exchange: Arc<Exchange>,
+}
+
+impl CryptoCommand {
+    /// Create the command with the exchange rate provider.
+    pub fn new(Inject(exchange): Inject<ExchangeKey>) -> Self {
+        Self { exchange }
+    }
+}
+
+#[async_trait]
+impl Command for CryptoCommand {
+    fn name(&self) -> &str {
+        "crypto"
+    }
+
+    fn description(&self) -> &str {
+        "Show the price of a cryptocurrency"
+    }
+
+    fn usage(&self) -> &str {
+        "crypto <coin> [currency]"
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["coin", "price"]
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::EMBED_LINKS
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let coin = match ctx.args.first() {
+            Some(coin) => coin.to_lowercase(),
+            None => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", self.usage())).await?;
+                return Ok(());
+            }
+        };
+        let currency = ctx
+            .args
+            .get(1)
+            .map(|currency| currency.to_lowercase())
+            .unwrap_or_else(|| "usd".to_string());
+
+        // Unknown symbols are tried as CoinGecko IDs, like `pepe` or `render-token`
+        let id = coin_id(&coin).unwrap_or(&coin);
+        let price = match self.exchange.price(id, &currency).await? {
+            Some(price) => price,
+            None => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!(
+                        "I couldn't find a price for `{}` in `{}`.",
+                        coin,
+                        currency.to_uppercase()
+                    ),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let name = match coin_symbol(&price.id) {
+            Some(symbol) => format!("{} ({})", price.id, symbol.to_uppercase()),
+            None => price.id.clone(),
+        };
+        let mut embed = CreateEmbed::default();
+        embed
+            .title(format!("🪙 {}", name))
+            .color(DEFAULT_COLOR)
+            .description(format!(
+                "**{} {}**",
+                format_number(price.price, money_decimals(price.price)),
+                price.currency.to_uppercase()
+            ))
+            .field("Updated", relative(price.updated_at), true)
+            .footer(|f| f.text("Prices from CoinGecko"));
+        if let Some(change) = price.change_24h {
+            embed.field("24h", format_change(change), true);
+        }
+        send_embed(&ctx, embed).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_money_and_changes() {
+        assert_eq!(money_decimals(1234.5), 2);
+        assert_eq!(money_decimals(0.000123), 6);
+        assert_eq!(format_change(2.314), "📈 +2.31%");
+        assert_eq!(format_change(-0.5), "📉 -0.50%");
+    }
+}
@@ -0,0 +1,270 @@
+//! Crate, repo and docs commands for looking up Rust crates and GitHub projects.
+
+use async_trait::async_trait;
+use serenity::builder::CreateEmbed;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::params::{usage_of, Param, ParamKind};
+use crate::framework::state::Inject;
+use crate::utils::constants::DEFAULT_COLOR;
+use crate::utils::devlookup::{
+    choices_menu, crate_embed, docs_embed, is_std, repo_embed, split_path, std_docs_embed,
+    DevLookup, DevLookupKey,
+};
+use crate::utils::helpers::{apply_mentions, mention_policy, send_error, truncate};
+use crate::utils::rest;
+
+/// The longest item path carried in a `docs` select menu's component ID.
+const MAX_MENU_PATH: usize = 64;
+
+/// Send a lookup result.
+async fn send_embed(ctx: &CommandContext<'_>, embed: CreateEmbed) -> CommandResult {
+    let policy = &mention_policy(ctx.ctx).await;
+    rest::call(ctx.ctx, "send_message", || {
+        let embed = embed.clone();
+        ctx.msg.channel_id.send_message(&ctx.ctx.http, move |m| {
+            m.allowed_mentions(|am| apply_mentions(am, policy, &[]))
+                .set_embed(embed)
+        })
+    })
+    .await?;
+    Ok(())
+}
+
+/// Offer search results when nothing matched exactly.
+async fn send_choices(
+    ctx: &CommandContext<'_>,
+    kind: &str,
+    description: String,
+    path: Option<&str>,
+    choices: &[String],
+) -> CommandResult {
+    let policy = &mention_policy(ctx.ctx).await;
+    let description = &description;
+    rest::call(ctx.ctx, "send_message", || {
+        ctx.msg.channel_id.send_message(&ctx.ctx.http, move |m| {
+            m.allowed_mentions(|am| apply_mentions(am, policy, &[]))
+                .embed(|e| {
+                    e.title("🔎 Did you mean...")
+                        .description(description)
+                        .color(DEFAULT_COLOR)
+                })
+                .components(|c| {
+                    choices_menu(c, kind, ctx.msg.author.id, path, choices);
+                    c
+                })
+        })
+    })
+    .await?;
+    Ok(())
+}
+
+/// Shows a crate from crates.io.
+pub struct CrateCommand {
+    lookup: Arc<DevLookup>,
+}
+
+impl CrateCommand {
+    /// Create the command with the lookup client.
+    pub fn new(Inject(lookup): Inject<DevLookupKey>) -> Self {
+        Self { lookup }
+    }
+}
+
+#[async_trait]
+impl Command for CrateCommand {
+    fn name(&self) -> &str {
+        "crate"
+    }
+
+    fn description(&self) -> &str {
+        "Look up a Rust crate on crates.io"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![Param::required(
+            "name",
+            ParamKind::Word,
+            "The crate's name or a search term",
+        )]
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["crates", "cratesio"]
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::EMBED_LINKS
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let name = ctx.args[0].trim_matches('`').to_string();
+
+        if let Some(info) = self.lookup.krate(&name).await? {
+            return send_embed(&ctx, crate_embed(&info)).await;
+        }
+        let choices = self.lookup.search_crates(&name).await?;
+        if choices.is_empty() {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!("There's no crate called `{}`.", truncate(&name, 64)),
+            )
+            .await?;
+            return Ok(());
+        }
+        let description = format!(
+            "There's no crate called `{}`. Pick one of these instead.",
+            truncate(&name, 64)
+        );
+        send_choices(&ctx, "crate", description, None, &choices).await
+    }
+}
+
+/// Shows a repository from GitHub.
+pub struct RepoCommand {
+    lookup: Arc<DevLookup>,
+}
+
+impl RepoCommand {
+    /// Create the command with the lookup client.
+    pub fn new(Inject(lookup): Inject<DevLookupKey>) -> Self {
+        Self { lookup }
+    }
+}
+
+#[async_trait]
+impl Command for RepoCommand {
+    fn name(&self) -> &str {
+        "repo"
+    }
+
+    fn description(&self) -> &str {
+        "Look up a repository on GitHub"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![Param::required(
+            "repo",
+            ParamKind::Word,
+            "The repository, like owner/name",
+        )]
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["github", "gh"]
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::EMBED_LINKS
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let name = ctx.args[0]
+            .trim_start_matches("https://")
+            .trim_start_matches("github.com/")
+            .trim_matches(|c| c == '/' || c == '<' || c == '>')
+            .to_string();
+
+        // Without an owner, or if nothing matches, search for it
+        let valid = name.split('/').count() == 2
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'));
+        if valid {
+            if let Some(info) = self.lookup.repo(&name).await? {
+                return send_embed(&ctx, repo_embed(&info)).await;
+            }
+        }
+        let choices = self.lookup.search_repos(&name).await?;
+        if choices.is_empty() {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!("There's no repository called `{}`.", truncate(&name, 64)),
+            )
+            .await?;
+            return Ok(());
+        }
+        let description = format!(
+            "I couldn't find `{}`. Pick one of these instead.",
+            truncate(&name, 64)
+        );
+        send_choices(&ctx, "repo", description, None, &choices).await
+    }
+}
+
+/// Links to an item in a crate's docs.
+pub struct DocsCommand {
+    lookup: Arc<DevLookup>,
+}
+
+impl DocsCommand {
+    /// Create the command with the lookup client.
+    pub fn new(Inject(lookup): Inject<DevLookupKey>) -> Self {
+        Self { lookup }
+    }
+}
+
+#[async_trait]
+impl Command for DocsCommand {
+    fn name(&self) -> &str {
+        "docs"
+    }
+
+    fn description(&self) -> &str {
+        "Find an item in a crate's docs on docs.rs"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![Param::required(
+            "path",
+            ParamKind::Text,
+            "The item, like serde::Deserialize",
+        )]
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["docsrs", "rustdoc"]
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::EMBED_LINKS
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let (krate, item) = split_path(&ctx.args.join(""));
+        if krate.is_empty() {
+            send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", usage_of(self))).await?;
+            return Ok(());
+        }
+
+        if is_std(&krate) {
+            return send_embed(&ctx, std_docs_embed(&krate, item.as_deref())).await;
+        }
+        if let Some(info) = self.lookup.krate(&krate).await? {
+            return send_embed(&ctx, docs_embed(&info, item.as_deref())).await;
+        }
+
+        let choices = self.lookup.search_crates(&krate).await?;
+        let fits = item
+            .as_deref()
+            .is_none_or(|item| item.chars().count() <= MAX_MENU_PATH);
+        if choices.is_empty() || !fits {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!("There's no crate called `{}`.", truncate(&krate, 64)),
+            )
+            .await?;
+            return Ok(());
+        }
+        let description = format!(
+            "There's no crate called `{}`. Pick the one you meant.",
+            truncate(&krate, 64)
+        );
+        send_choices(&ctx, "docs", description, item.as_deref(), &choices).await
+    }
+}
@@ -0,0 +1,231 @@
+//! Event command for scheduling events members can RSVP to.
+//!
+//! Creating an event opens a form through a button, since only interactions
+//! can open modals. See the event interaction handler for the rest of the flow.
+
+use async_trait::async_trait;
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::id::{ChannelId, MessageId, ScheduledEventId};
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::guild_events::{EventData, EventKey, Rsvp};
+use crate::storage::JsonStore;
+use crate::utils::actions;
+use crate::utils::constants::DEFAULT_COLOR;
+use crate::utils::duration::relative;
+use crate::utils::helpers::{
+    permissions_in, send_error, send_info, send_success, truncate, unix_timestamp,
+};
+use crate::utils::rest;
+
+const USAGE: &str = "event [list | create [discord] | cancel <id>]";
+
+/// Schedules events with RSVP buttons.
+pub struct EventCommand {
+    store: Arc<JsonStore<EventData>>,
+}
+
+impl EventCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<EventKey>) -> Self {
+        Self { store }
+    }
+
+    /// Whether the author can manage events here, telling them if they can't.
+    async fn check_manager(&self, ctx: &CommandContext<'_>) -> CommandResult<bool> {
+        let guild_id = ctx.msg.guild_id.ok_or("Not in a guild")?;
+        let allowed = permissions_in(ctx.ctx, guild_id, ctx.msg.channel_id, ctx.msg.author.id)
+            .await
+            .is_some_and(|permissions| permissions.manage_events());
+        if !allowed {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                "You need the Manage Events permission to do that.",
+            )
+            .await?;
+        }
+        Ok(allowed)
+    }
+
+    /// List upcoming events.
+    async fn list(&self, ctx: &CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx.msg.guild_id.ok_or("Not in a guild")?;
+        let now = unix_timestamp();
+        let lines: Vec<String> = {
+            let data = self.store.read().await;
+            data.guild(guild_id)
+                .map(|guild| {
+                    guild
+                        .upcoming(now)
+                        .map(|event| {
+                            let link = match event.message_id {
+                                Some(message_id) => format!(
+                                    " · [RSVP](https://discord.com/channels/{}/{}/{})",
+                                    guild_id, event.channel_id, message_id
+                                ),
+                                None => String::new(),
+                            };
+                            format!(
+                                "`#{}` **{}** {} · {} going{}",
+                                event.id,
+                                truncate(&event.title, 80),
+                                relative(event.start),
+                                event.attendees(Rsvp::Going).len(),
+                                link
+                            )
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let body = if lines.is_empty() {
+            "No upcoming events. Create one with `event create`.".to_string()
+        } else {
+            truncate(&lines.join("\n"), 4000)
+        };
+        send_info(ctx.ctx, ctx.msg, "📅 Upcoming events", body).await?;
+        Ok(())
+    }
+
+    /// Send the button that opens the event form.
+    async fn create(&self, ctx: &CommandContext<'_>, sync: bool) -> CommandResult {
+        if !self.check_manager(ctx).await? {
+            return Ok(());
+        }
+
+        let description = if sync {
+            "Fill in the form to post the event here. It will also be added to the server's events."
+        } else {
+            "Fill in the form to post the event here."
+        };
+        let channel_id = ctx.msg.channel_id;
+        rest::call(ctx.ctx, "send_message", || {
+            channel_id.send_message(&ctx.ctx.http, |m| {
+                m.embed(|e| {
+                    e.title("📅 New event")
+                        .description(description)
+                        .color(DEFAULT_COLOR)
+                })
+                .components(|c| {
+                    c.create_action_row(|row| {
+                        row.create_button(|b| {
+                            b.custom_id(format!(
+                                "event:start:{}:{}",
+                                ctx.msg.author.id,
+                                u8::from(sync)
+                            ))
+                            .label("Create event")
+                            .style(ButtonStyle::Primary)
+                        })
+                    })
+                })
+            })
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Cancel an event, removing its post and Discord event.
+    async fn cancel(&self, ctx: &CommandContext<'_>, id: Option<&String>) -> CommandResult {
+        let guild_id = ctx.msg.guild_id.ok_or("Not in a guild")?;
+        let id: u64 = match id.and_then(|id| id.trim_start_matches('#').parse().ok()) {
+            Some(id) => id,
+            None => {
+                send_error(ctx.ctx, ctx.msg, "Usage: `event cancel <id>`").await?;
+                return Ok(());
+            }
+        };
+        if !self.check_manager(ctx).await? {
+            return Ok(());
+        }
+
+        let event = self
+            .store
+            .update(|data| data.guild_mut(guild_id).events.remove(&id))
+            .await?;
+        let event = match event {
+            Some(event) => event,
+            None => {
+                send_error(ctx.ctx, ctx.msg, format!("There's no event #{}.", id)).await?;
+                return Ok(());
+            }
+        };
+
+        // The post or the Discord event may already be gone
+        if let Some(message_id) = event.message_id {
+            let _ =
+                actions::delete_message(ctx, ChannelId(event.channel_id), MessageId(message_id))
+                    .await;
+        }
+        if let Some(scheduled_id) = event.scheduled_event_id {
+            let _ = actions::delete_scheduled_event(ctx, guild_id, ScheduledEventId(scheduled_id))
+                .await;
+        }
+
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action: format!("Cancelled event #{} ({})", event.id, event.title),
+                reason: None,
+            },
+        )
+        .await;
+        send_success(
+            ctx.ctx,
+            ctx.msg,
+            format!("Cancelled **{}**.", truncate(&event.title, 200)),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Command for EventCommand {
+    fn name(&self) -> &str {
+        "event"
+    }
+
+    fn description(&self) -> &str {
+        "Schedule events members can RSVP to"
+    }
+
+    fn usage(&self) -> &str {
+        USAGE
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["events", "rsvp"]
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let subcommand = ctx.args.first().map(|arg| arg.to_lowercase());
+        match subcommand.as_deref() {
+            None | Some("list") => self.list(&ctx).await,
+            Some("create" | "new") => {
+                let sync = ctx
+                    .args
+                    .get(1)
+                    .is_some_and(|arg| arg.eq_ignore_ascii_case("discord"));
+                self.create(&ctx, sync).await
+            }
+            Some("cancel" | "delete") => self.cancel(&ctx, ctx.args.get(1)).await,
+            Some(_) => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", USAGE)).await?;
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Growth command for showing the server's member count over time.
+
+use async_trait::async_trait;
+use serenity::builder::CreateEmbed;
+use std::sync::Arc;
+use tracing::debug;
+
+use super::analytics::{day_label, describe_days, parse_range, sparkline};
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::analytics::today;
+use crate::models::growth::{GrowthData, GrowthKey};
+use crate::models::guild_config::guild_config;
+use crate::storage::JsonStore;
+use crate::utils::charts::{self, CHART_URL};
+use crate::utils::constants::DEFAULT_COLOR;
+use crate::utils::files::send_file;
+use crate::utils::helpers::{format_count, send_error, send_info};
+
+/// Shows the member count over a range of days and the next milestone.
+pub struct GrowthCommand {
+    growth: Arc<JsonStore<GrowthData>>,
+}
+
+impl GrowthCommand {
+    /// Create the command with the member count store.
+    pub fn new(Inject(growth): Inject<GrowthKey>) -> Self {
+        Self { growth }
+    }
+}
+
+#[async_trait]
+impl Command for GrowthCommand {
+    fn name(&self) -> &str {
+        "growth"
+    }
+
+    fn description(&self) -> &str {
+        "Show how the server's member count has changed"
+    }
+
+    fn usage(&self) -> &str {
+        "growth [range, like 30d, 12w or all]"
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx.msg.guild_id.ok_or("Growth is per server")?;
+        let until = today();
+        let growth = self
+            .growth
+            .read()
+            .await
+            .guilds
+            .get(&guild_id.0)
+            .cloned()
+            .unwrap_or_default();
+        let first = match growth.counts.keys().next() {
+            Some(first) => *first,
+            None => {
+                send_info(
+                    ctx.ctx,
+                    ctx.msg,
+                    "📈 Member growth",
+                    "No member counts have been saved yet. They're saved when members join or leave.",
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        // `all` goes back to the first saved count
+        let arg = ctx.args.first().map(String::as_str).or(Some("30d"));
+        let days = match parse_range(arg, until + 1 - first.min(until)) {
+            Ok(days) => days,
+            Err(e) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+        };
+        let series = growth.series(until + 1 - days, until);
+        let (start, now) = match (series.first(), series.last()) {
+            (Some(start), Some(now)) => (start.1, now.1),
+            _ => return Ok(()),
+        };
+
+        let change = now as i64 - start as i64;
+        let mut summary = format!(
+            "**{}** members, **{}{}** in {}",
+            format_count(now),
+            if change < 0 { "-" } else { "+" },
+            format_count(change.unsigned_abs()),
+            describe_days(days)
+        );
+        let config = guild_config(ctx.ctx, guild_id).await;
+        if let Some(next) = config.milestones().iter().find(|m| **m > now) {
+            summary.push_str(&format!(
+                "\nNext milestone: **{}** ({} to go)",
+                format_count(*next),
+                format_count(next - now)
+            ));
+        }
+
+        let title = format!("📈 Member growth in {}", describe_days(days));
+        let points: Vec<(String, u64)> = series
+            .iter()
+            .map(|(day, count)| (day_label(*day), *count))
+            .collect();
+        match charts::line("Members", &points) {
+            Ok(chart) => {
+                let mut embed = CreateEmbed::default();
+                embed
+                    .title(title)
+                    .description(summary)
+                    .image(CHART_URL)
+                    .color(DEFAULT_COLOR);
+                send_file(
+                    ctx.ctx,
+                    ctx.msg.channel_id,
+                    Some(guild_id),
+                    chart,
+                    Some(embed),
+                )
+                .await?;
+            }
+            Err(e) => {
+                debug!("Sending growth without a chart: {}", e);
+                let counts: Vec<u64> = series.iter().map(|(_, count)| *count).collect();
+                let description = format!("{}\n`{}`", summary, sparkline(&counts));
+                send_info(ctx.ctx, ctx.msg, title, description).await?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,606 @@
+//! Knowledge base command for the server's wiki-style articles.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serenity::builder::CreateEmbed;
+use serenity::model::id::GuildId;
+use serenity::model::permissions::Permissions;
+use serenity::model::timestamp::Timestamp;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::knowledge_base::{
+    topic_key, Article, KnowledgeBaseData, KnowledgeBaseKey, Lookup, Revision, SaveError,
+    MAX_ARTICLES,
+};
+use crate::storage::JsonStore;
+use crate::utils::constants::{DEFAULT_COLOR, FILTER_IMPORT_MAX_SIZE};
+use crate::utils::duration::relative;
+use crate::utils::files::{send_file, OutgoingFile};
+use crate::utils::helpers::{
+    apply_mentions, mention_policy, permissions_in, raw_args, send_error, send_info, send_success,
+    truncate, unix_timestamp,
+};
+use crate::utils::rest;
+
+/// Longest article shown as a plain message, Discord's message limit.
+const MAX_TEXT_LENGTH: usize = 2000;
+
+/// Longest article shown as an embed, Discord's embed description limit.
+const MAX_EMBED_LENGTH: usize = 4096;
+
+/// Words that can't be topics, since they're subcommands.
+const RESERVED: [&str; 8] = [
+    "set", "list", "history", "revert", "remove", "delete", "export", "import",
+];
+
+const USAGE: &str = "kb <topic> | kb set <topic> [embed:] <content> | kb list | kb history <topic> | kb revert <topic> <revision> | kb remove <topic> | kb export | kb import";
+
+/// An article in an export file.
+#[derive(Serialize, Deserialize)]
+struct ExportedArticle {
+    topic: String,
+    #[serde(default)]
+    last_revision: u64,
+    revisions: Vec<Revision>,
+}
+
+/// Build the message for an article's current revision: the embed for embed
+/// articles, otherwise the text.
+fn render(article: &Article, revision: &Revision) -> (Option<String>, Option<CreateEmbed>) {
+    if !revision.embed {
+        return (Some(revision.content.clone()), None);
+    }
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(&article.topic)
+        .description(&revision.content)
+        .color(DEFAULT_COLOR)
+        .footer(|f| f.text(format!("Revision {}", article.last_revision)))
+        .timestamp(
+            Timestamp::from_unix_timestamp(revision.edited_at as i64)
+                .unwrap_or_else(|_| Timestamp::now()),
+        );
+    (None, Some(embed))
+}
+
+/// Shows and edits the server's knowledge base.
+pub struct KnowledgeBaseCommand {
+    store: Arc<JsonStore<KnowledgeBaseData>>,
+}
+
+impl KnowledgeBaseCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<KnowledgeBaseKey>) -> Self {
+        Self { store }
+    }
+
+    /// Whether the author can edit articles, telling them if not.
+    async fn check_editor(
+        &self,
+        ctx: &CommandContext<'_>,
+        guild_id: GuildId,
+    ) -> CommandResult<bool> {
+        let permissions =
+            permissions_in(ctx.ctx, guild_id, ctx.msg.channel_id, ctx.msg.author.id).await;
+        if permissions.is_some_and(|p| p.manage_messages()) {
+            return Ok(true);
+        }
+        send_error(
+            ctx.ctx,
+            ctx.msg,
+            "You need Manage Messages to edit the knowledge base.",
+        )
+        .await?;
+        Ok(false)
+    }
+
+    /// Record a change in the audit log.
+    async fn audit(&self, ctx: &CommandContext<'_>, guild_id: GuildId, action: String) {
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action,
+                reason: None,
+            },
+        )
+        .await;
+    }
+
+    /// Parse the topic argument at `index`, telling the author if it's invalid.
+    async fn topic(
+        &self,
+        ctx: &CommandContext<'_>,
+        index: usize,
+    ) -> CommandResult<Option<(String, String)>> {
+        let topic = match ctx.args.get(index) {
+            Some(topic) => topic,
+            None => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", USAGE)).await?;
+                return Ok(None);
+            }
+        };
+        match topic_key(topic) {
+            Some(key) if !RESERVED.contains(&key.as_str()) => Ok(Some((key, topic.clone()))),
+            _ => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    "Topics are one word of letters, numbers, `-`, `_` or `.`, and can't be a subcommand name.",
+                )
+                .await?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Show an article, matching the topic fuzzily.
+    async fn show(
+        &self,
+        ctx: &CommandContext<'_>,
+        guild_id: GuildId,
+        query: &str,
+    ) -> CommandResult {
+        let message = {
+            let data = self.store.read().await;
+            match data.guild(guild_id).map(|kb| kb.lookup(query)) {
+                Some(Lookup::Found(article, _)) => match article.current() {
+                    Some(revision) => Ok(render(article, revision)),
+                    None => Err(Vec::new()),
+                },
+                Some(Lookup::NotFound(suggestions)) => {
+                    Err(suggestions.into_iter().map(String::from).collect())
+                }
+                None => Err(Vec::new()),
+            }
+        };
+        let (content, embed) = match message {
+            Ok(message) => message,
+            Err(suggestions) => {
+                let mut reply = format!("There's no article about `{}`.", truncate(query, 64));
+                if !suggestions.is_empty() {
+                    let suggestions: Vec<String> =
+                        suggestions.iter().map(|s| format!("`{}`", s)).collect();
+                    reply.push_str(&format!(" Did you mean {}?", suggestions.join(", ")));
+                }
+                send_error(ctx.ctx, ctx.msg, reply).await?;
+                return Ok(());
+            }
+        };
+
+        let policy = &mention_policy(ctx.ctx).await;
+        rest::call(ctx.ctx, "send_message", || {
+            let (content, embed) = (content.clone(), embed.clone());
+            ctx.msg.channel_id.send_message(&ctx.ctx.http, move |m| {
+                m.allowed_mentions(|am| apply_mentions(am, policy, &[]));
+                if let Some(content) = content {
+                    m.content(content);
+                }
+                if let Some(embed) = embed {
+                    m.set_embed(embed);
+                }
+                m
+            })
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Save a new revision of an article.
+    async fn set(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let (key, topic) = match self.topic(ctx, 1).await? {
+            Some(topic) => topic,
+            None => return Ok(()),
+        };
+        let raw = raw_args(&ctx.msg.content, &ctx.args, 2);
+        let (content, embed) = match raw.strip_prefix("embed:") {
+            Some(content) => (content.trim(), true),
+            None => (raw.trim(), false),
+        };
+        if content.is_empty() {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                "Usage: `kb set <topic> [embed:] <content>`",
+            )
+            .await?;
+            return Ok(());
+        }
+        let limit = if embed {
+            MAX_EMBED_LENGTH
+        } else {
+            MAX_TEXT_LENGTH
+        };
+        if content.chars().count() > limit {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!("That article is longer than {} characters.", limit),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let revision = Revision {
+            content: content.to_string(),
+            embed,
+            edited_by: ctx.msg.author.id.0,
+            edited_at: unix_timestamp(),
+        };
+        let saved = self
+            .store
+            .update(|data| data.guild_mut(guild_id).set(&key, &topic, revision))
+            .await?;
+        let number = match saved {
+            Ok(number) => number,
+            Err(SaveError::Unchanged) => {
+                send_error(ctx.ctx, ctx.msg, "That's already the article's content.").await?;
+                return Ok(());
+            }
+            Err(SaveError::Full) => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!("The knowledge base is full at {} articles.", MAX_ARTICLES),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        self.audit(
+            ctx,
+            guild_id,
+            format!(
+                "Save revision {} of knowledge base article `{}`",
+                number, key
+            ),
+        )
+        .await;
+        let reply = if number == 1 {
+            format!("Created `{}`.", key)
+        } else {
+            format!("Saved revision {} of `{}`.", number, key)
+        };
+        send_success(ctx.ctx, ctx.msg, reply).await?;
+        Ok(())
+    }
+
+    /// List the articles.
+    async fn list(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let topics: Vec<String> = self
+            .store
+            .read()
+            .await
+            .guild(guild_id)
+            .map(|kb| {
+                kb.articles
+                    .values()
+                    .map(|article| format!("`{}`", article.topic))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let body = if topics.is_empty() {
+            "No articles yet. Write one with `kb set <topic> <content>`.".to_string()
+        } else {
+            truncate(&topics.join(", "), 4000)
+        };
+        send_info(ctx.ctx, ctx.msg, "📚 Knowledge base", body).await?;
+        Ok(())
+    }
+
+    /// Show an article's kept revisions.
+    async fn history(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let (key, _) = match self.topic(ctx, 1).await? {
+            Some(topic) => topic,
+            None => return Ok(()),
+        };
+        let lines: Option<Vec<String>> = self
+            .store
+            .read()
+            .await
+            .guild(guild_id)
+            .and_then(|kb| kb.articles.get(&key))
+            .map(|article| {
+                let first = article.first_revision();
+                article
+                    .revisions
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(i, revision)| {
+                        format!(
+                            "**{}** · <@{}> {} · {}",
+                            first + i as u64,
+                            revision.edited_by,
+                            relative(revision.edited_at),
+                            truncate(&revision.content.replace('\n', " "), 60)
+                        )
+                    })
+                    .collect()
+            });
+        match lines {
+            Some(lines) => {
+                send_info(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!("📚 History of {}", key),
+                    truncate(&lines.join("\n"), 4000),
+                )
+                .await?
+            }
+            None => send_error(ctx.ctx, ctx.msg, format!("There's no article `{}`.", key)).await?,
+        };
+        Ok(())
+    }
+
+    /// Restore an old revision as a new one.
+    async fn revert(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let (key, topic) = match self.topic(ctx, 1).await? {
+            Some(topic) => topic,
+            None => return Ok(()),
+        };
+        let number = match ctx.args.get(2).and_then(|arg| arg.parse::<u64>().ok()) {
+            Some(number) => number,
+            None => {
+                send_error(ctx.ctx, ctx.msg, "Usage: `kb revert <topic> <revision>`").await?;
+                return Ok(());
+            }
+        };
+
+        let author = ctx.msg.author.id.0;
+        let saved = self
+            .store
+            .update(|data| {
+                let kb = data.guild_mut(guild_id);
+                let mut revision = kb.articles.get(&key)?.revision(number)?.clone();
+                revision.edited_by = author;
+                revision.edited_at = unix_timestamp();
+                Some(kb.set(&key, &topic, revision))
+            })
+            .await?;
+        let reply = match saved {
+            None => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!(
+                        "`{}` has no revision {}. See `kb history {}`.",
+                        key, number, key
+                    ),
+                )
+                .await?;
+                return Ok(());
+            }
+            Some(Err(_)) => {
+                send_error(ctx.ctx, ctx.msg, "That's already the article's content.").await?;
+                return Ok(());
+            }
+            Some(Ok(new)) => format!(
+                "Restored revision {} of `{}` as revision {}.",
+                number, key, new
+            ),
+        };
+        self.audit(
+            ctx,
+            guild_id,
+            format!(
+                "Revert knowledge base article `{}` to revision {}",
+                key, number
+            ),
+        )
+        .await;
+        send_success(ctx.ctx, ctx.msg, reply).await?;
+        Ok(())
+    }
+
+    /// Delete an article and its history.
+    async fn remove(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let (key, _) = match self.topic(ctx, 1).await? {
+            Some(topic) => topic,
+            None => return Ok(()),
+        };
+        let removed = self
+            .store
+            .update(|data| data.guild_mut(guild_id).remove(&key))
+            .await?;
+        if !removed {
+            send_error(ctx.ctx, ctx.msg, format!("There's no article `{}`.", key)).await?;
+            return Ok(());
+        }
+        self.audit(
+            ctx,
+            guild_id,
+            format!("Remove knowledge base article `{}`", key),
+        )
+        .await;
+        send_success(ctx.ctx, ctx.msg, format!("Removed `{}`.", key)).await?;
+        Ok(())
+    }
+
+    /// Send every article, with its history, as a JSON file.
+    async fn export(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let articles: Vec<ExportedArticle> = self
+            .store
+            .read()
+            .await
+            .guild(guild_id)
+            .map(|kb| {
+                kb.articles
+                    .values()
+                    .map(|article| ExportedArticle {
+                        topic: article.topic.clone(),
+                        last_revision: article.last_revision,
+                        revisions: article.revisions.clone(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        if articles.is_empty() {
+            send_error(ctx.ctx, ctx.msg, "There are no articles to export.").await?;
+            return Ok(());
+        }
+
+        let json = serde_json::to_vec_pretty(&articles)?;
+        let mut embed = CreateEmbed::default();
+        embed
+            .title("Knowledge base")
+            .color(DEFAULT_COLOR)
+            .description(format!(
+                "{} articles. Import them with `kb import`.",
+                articles.len()
+            ));
+        send_file(
+            ctx.ctx,
+            ctx.msg.channel_id,
+            Some(guild_id),
+            OutgoingFile::new(format!("kb-{}.json", guild_id), json),
+            Some(embed),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Restore articles from an attached export. Each imported article's
+    /// current revision is saved as a new revision; new topics keep their history.
+    async fn import(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let attachment = match ctx.msg.attachments.first() {
+            Some(attachment) => attachment,
+            None => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    "Attach a JSON file exported with `kb export`.",
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+        if attachment.size > FILTER_IMPORT_MAX_SIZE {
+            send_error(ctx.ctx, ctx.msg, "That file is too large to import.").await?;
+            return Ok(());
+        }
+
+        let data = attachment.download().await?;
+        let articles: Vec<ExportedArticle> = match serde_json::from_slice(&data) {
+            Ok(articles) => articles,
+            Err(e) => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!("That isn't a knowledge base export: {}", e),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+        let total = articles.len();
+
+        let imported = self
+            .store
+            .update(|data| {
+                let kb = data.guild_mut(guild_id);
+                let mut imported = 0;
+                for article in articles {
+                    let key = match topic_key(&article.topic) {
+                        Some(key) if !RESERVED.contains(&key.as_str()) => key,
+                        _ => continue,
+                    };
+                    let revisions: Vec<Revision> = article
+                        .revisions
+                        .into_iter()
+                        .filter(|revision| !revision.content.trim().is_empty())
+                        .collect();
+                    let saved = match kb.articles.get(&key) {
+                        Some(_) => match revisions.last() {
+                            Some(current) => kb.set(&key, &article.topic, current.clone()).is_ok(),
+                            None => false,
+                        },
+                        None => {
+                            let mut saved = false;
+                            for revision in revisions {
+                                saved |= kb.set(&key, &article.topic, revision).is_ok();
+                            }
+                            saved
+                        }
+                    };
+                    if saved {
+                        imported += 1;
+                    }
+                }
+                imported
+            })
+            .await?;
+
+        self.audit(
+            ctx,
+            guild_id,
+            format!("Import {} knowledge base articles", imported),
+        )
+        .await;
+        let mut reply = format!("Imported {} of {} articles.", imported, total);
+        if imported < total {
+            reply.push_str(" The rest were unchanged, invalid or over the article limit.");
+        }
+        send_success(ctx.ctx, ctx.msg, reply).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Command for KnowledgeBaseCommand {
+    fn name(&self) -> &str {
+        "kb"
+    }
+
+    fn description(&self) -> &str {
+        "Read and write the server's knowledge base"
+    }
+
+    fn usage(&self) -> &str {
+        USAGE
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["wiki", "faq"]
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::EMBED_LINKS | Permissions::ATTACH_FILES
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("The knowledge base can only be used in a server")?;
+
+        let action = ctx.args.first().map(|arg| arg.to_lowercase());
+        let action = match action.as_deref() {
+            None | Some("list") => return self.list(&ctx, guild_id).await,
+            Some("history") => return self.history(&ctx, guild_id).await,
+            Some("export") => return self.export(&ctx, guild_id).await,
+            Some(action @ ("set" | "revert" | "remove" | "delete" | "import")) => action,
+            Some(_) => return self.show(&ctx, guild_id, &ctx.args[0]).await,
+        };
+
+        if !self.check_editor(&ctx, guild_id).await? {
+            return Ok(());
+        }
+        match action {
+            "set" => self.set(&ctx, guild_id).await,
+            "revert" => self.revert(&ctx, guild_id).await,
+            "import" => self.import(&ctx, guild_id).await,
+            _ => self.remove(&ctx, guild_id).await,
+        }
+    }
+}
@@ -0,0 +1,542 @@
+//! LFG command for finding players for a game.
+
+use async_trait::async_trait;
+use serenity::model::id::{ChannelId, GuildId, MessageId, RoleId};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::lfg::{
+    LfgData, LfgKey, LfgPost, Preset, MAX_OPEN_POSTS, MAX_PRESETS, MAX_SLOTS, MIN_SLOTS,
+};
+use crate::storage::JsonStore;
+use crate::utils::actions;
+use crate::utils::duration::{self, relative};
+use crate::utils::helpers::{
+    permissions_in, send_error, send_info, send_success, truncate, unix_timestamp,
+};
+use crate::utils::lfg::{open_expiry, post_buttons, post_embed};
+use crate::utils::rest;
+
+/// Longest game name.
+const MAX_GAME_LENGTH: usize = 60;
+
+/// Furthest ahead a group can plan to start.
+const MAX_LEAD: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+const USAGE: &str = "lfg <game> [slots] [in <time>] | lfg list | lfg cancel <id> | lfg presets [add <game> <slots> | remove <game>]";
+
+/// What `lfg <game> [slots] [in <time>]` asked for.
+#[derive(Debug, PartialEq)]
+struct Request {
+    game: String,
+    slots: Option<u32>,
+    start_in: Option<Duration>,
+}
+
+/// Split the arguments of a new post into the game, the group size and when to start.
+fn parse_request(args: &[String]) -> Result<Request, String> {
+    let mut args = args.to_vec();
+
+    let mut start_in = None;
+    if args.len() > 1 {
+        if let Some(Ok(delay)) = args.last().map(|arg| duration::parse(arg)) {
+            if delay > MAX_LEAD {
+                return Err("Groups can plan at most a week ahead.".to_string());
+            }
+            start_in = Some(delay);
+            args.pop();
+            if args.len() > 1
+                && args
+                    .last()
+                    .is_some_and(|arg| arg.eq_ignore_ascii_case("in"))
+            {
+                args.pop();
+            }
+        }
+    }
+
+    let mut slots = None;
+    if args.len() > 1 {
+        if let Some(Ok(count)) = args.last().map(|arg| arg.parse::<u32>()) {
+            slots = Some(count);
+            args.pop();
+        }
+    }
+
+    let game = args.join(" ");
+    if game.is_empty() {
+        return Err(format!("Usage: `{}`", USAGE));
+    }
+    if game.chars().count() > MAX_GAME_LENGTH {
+        return Err(format!(
+            "Game names can be at most {} characters long.",
+            MAX_GAME_LENGTH
+        ));
+    }
+    Ok(Request {
+        game,
+        slots,
+        start_in,
+    })
+}
+
+/// Check a group size, returning why it's invalid.
+fn check_slots(slots: u32) -> Result<u32, String> {
+    if (MIN_SLOTS..=MAX_SLOTS).contains(&slots) {
+        Ok(slots)
+    } else {
+        Err(format!(
+            "Groups need between {} and {} players, counting you.",
+            MIN_SLOTS, MAX_SLOTS
+        ))
+    }
+}
+
+/// Posts looking for players and manages game presets.
+pub struct LfgCommand {
+    store: Arc<JsonStore<LfgData>>,
+}
+
+impl LfgCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<LfgKey>) -> Self {
+        Self { store }
+    }
+
+    /// Record a change in the audit log.
+    async fn audit(&self, ctx: &CommandContext<'_>, guild_id: GuildId, action: String) {
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action,
+                reason: None,
+            },
+        )
+        .await;
+    }
+
+    /// Post a new group.
+    async fn create(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let request = match parse_request(&ctx.args) {
+            Ok(request) => request,
+            Err(e) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+        };
+        let preset = {
+            let data = self.store.read().await;
+            data.guild(guild_id)
+                .and_then(|guild| guild.preset(&request.game))
+                .cloned()
+        };
+        let game = preset
+            .as_ref()
+            .map_or(request.game.clone(), |preset| preset.game.clone());
+        let slots = match request.slots.or(preset.map(|preset| preset.slots)) {
+            Some(slots) => check_slots(slots),
+            None => Err(format!(
+                "How many players? Try `lfg {} 5`, or ask staff to add a preset for it.",
+                request.game
+            )),
+        };
+        let slots = match slots {
+            Ok(slots) => slots,
+            Err(e) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+        };
+
+        let now = unix_timestamp();
+        let starts_at = request.start_in.map(|delay| now + delay.as_secs());
+        let host = ctx.msg.author.id.0;
+        let post = LfgPost {
+            id: 0,
+            game,
+            slots,
+            host,
+            members: vec![host],
+            channel_id: ctx.msg.channel_id.0,
+            message_id: None,
+            created_at: now,
+            starts_at,
+            expires_at: open_expiry(now, starts_at),
+            filled_at: None,
+            voice_channel_id: None,
+            role_id: None,
+        };
+        let post = self
+            .store
+            .update(|data| {
+                let guild = data.guild_mut(guild_id);
+                if guild.open().count() >= MAX_OPEN_POSTS {
+                    return None;
+                }
+                let id = guild.add(post);
+                guild.posts.get(&id).cloned()
+            })
+            .await?;
+        let post = match post {
+            Some(post) => post,
+            None => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!(
+                        "This server already has {} open groups. Try again once some fill up.",
+                        MAX_OPEN_POSTS
+                    ),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let channel_id = ctx.msg.channel_id;
+        let sent = rest::call(ctx.ctx, "send_message", || {
+            channel_id.send_message(&ctx.ctx.http, |m| {
+                m.set_embed(post_embed(&post, false))
+                    .components(|c| post_buttons(c, &post))
+            })
+        })
+        .await;
+        match sent {
+            Ok(message) => {
+                self.store
+                    .update(|data| {
+                        if let Some(saved) = data.guild_mut(guild_id).posts.get_mut(&post.id) {
+                            saved.message_id = Some(message.id.0);
+                        }
+                    })
+                    .await?;
+            }
+            Err(e) => {
+                self.store
+                    .update(|data| data.guild_mut(guild_id).posts.remove(&post.id))
+                    .await?;
+                return Err(e.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// List open groups.
+    async fn list(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let lines: Vec<String> = {
+            let data = self.store.read().await;
+            data.guild(guild_id)
+                .map(|guild| {
+                    guild
+                        .open()
+                        .map(|post| {
+                            let link = match post.message_id {
+                                Some(message_id) => format!(
+                                    " · [Join](https://discord.com/channels/{}/{}/{})",
+                                    guild_id, post.channel_id, message_id
+                                ),
+                                None => String::new(),
+                            };
+                            format!(
+                                "`#{}` **{}** {}/{} · expires {}{}",
+                                post.id,
+                                post.game,
+                                post.members.len(),
+                                post.slots,
+                                relative(post.expires_at),
+                                link
+                            )
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let body = if lines.is_empty() {
+            "Nobody is looking for a group. Start one with `lfg <game> <slots>`.".to_string()
+        } else {
+            truncate(&lines.join("\n"), 4000)
+        };
+        send_info(ctx.ctx, ctx.msg, "🎮 Open groups", body).await?;
+        Ok(())
+    }
+
+    /// Cancel a group, as its host or with Manage Messages.
+    async fn cancel(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let id: u64 = match ctx
+            .args
+            .get(1)
+            .and_then(|id| id.trim_start_matches('#').parse().ok())
+        {
+            Some(id) => id,
+            None => {
+                send_error(ctx.ctx, ctx.msg, "Usage: `lfg cancel <id>`").await?;
+                return Ok(());
+            }
+        };
+        let host = {
+            let data = self.store.read().await;
+            data.guild(guild_id)
+                .and_then(|guild| guild.posts.get(&id))
+                .map(|post| post.host)
+        };
+        let host = match host {
+            Some(host) => host,
+            None => {
+                send_error(ctx.ctx, ctx.msg, format!("There's no group #{}.", id)).await?;
+                return Ok(());
+            }
+        };
+        if host != ctx.msg.author.id.0 {
+            let permissions =
+                permissions_in(ctx.ctx, guild_id, ctx.msg.channel_id, ctx.msg.author.id).await;
+            if !permissions.is_some_and(|p| p.manage_messages()) {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    "Only the host or someone with Manage Messages can cancel a group.",
+                )
+                .await?;
+                return Ok(());
+            }
+        }
+
+        let post = self
+            .store
+            .update(|data| data.guild_mut(guild_id).posts.remove(&id))
+            .await?
+            .ok_or("Group was removed while cancelling")?;
+        // Any of these may already be gone
+        if let Some(message_id) = post.message_id {
+            let _ = actions::delete_message(ctx, ChannelId(post.channel_id), MessageId(message_id))
+                .await;
+        }
+        if let Some(channel) = post.voice_channel_id {
+            let _ = actions::delete_channel(ctx, ChannelId(channel)).await;
+        }
+        if let Some(role) = post.role_id {
+            let _ = actions::delete_role(ctx, guild_id, RoleId(role)).await;
+        }
+
+        send_success(
+            ctx.ctx,
+            ctx.msg,
+            format!("Cancelled the **{}** group.", post.game),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// List, add or remove game presets.
+    async fn presets(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let action = ctx.args.get(1).map(|arg| arg.to_lowercase());
+        let rest = ctx.args.get(2..).unwrap_or_default();
+        match action.as_deref() {
+            None | Some("list") => {
+                let lines: Vec<String> = {
+                    let data = self.store.read().await;
+                    data.guild(guild_id)
+                        .map(|guild| {
+                            guild
+                                .presets
+                                .values()
+                                .map(|preset| {
+                                    format!("**{}**: {} players", preset.game, preset.slots)
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default()
+                };
+                let body = if lines.is_empty() {
+                    "No presets yet. Add one with `lfg presets add <game> <slots>`.".to_string()
+                } else {
+                    truncate(&lines.join("\n"), 4000)
+                };
+                send_info(ctx.ctx, ctx.msg, "🎮 Game presets", body).await?;
+                return Ok(());
+            }
+            Some("add" | "set" | "remove" | "delete") => {}
+            Some(_) => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", USAGE)).await?;
+                return Ok(());
+            }
+        }
+
+        let permissions =
+            permissions_in(ctx.ctx, guild_id, ctx.msg.channel_id, ctx.msg.author.id).await;
+        if !permissions.is_some_and(|p| p.manage_guild()) {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                "You need Manage Server to change presets.",
+            )
+            .await?;
+            return Ok(());
+        }
+
+        if matches!(action.as_deref(), Some("remove" | "delete")) {
+            let game = rest.join(" ");
+            let removed = self
+                .store
+                .update(|data| {
+                    data.guild_mut(guild_id)
+                        .presets
+                        .remove(&game.trim().to_lowercase())
+                })
+                .await?;
+            match removed {
+                Some(preset) => {
+                    self.audit(ctx, guild_id, format!("Removed LFG preset {}", preset.game))
+                        .await;
+                    send_success(
+                        ctx.ctx,
+                        ctx.msg,
+                        format!("Removed the **{}** preset.", preset.game),
+                    )
+                    .await?;
+                }
+                None => {
+                    send_error(
+                        ctx.ctx,
+                        ctx.msg,
+                        format!("There's no preset for `{}`.", game),
+                    )
+                    .await?;
+                }
+            }
+            return Ok(());
+        }
+
+        let request = match parse_request(rest) {
+            Ok(Request {
+                game,
+                slots: Some(slots),
+                start_in: None,
+            }) => check_slots(slots).map(|slots| (game, slots)),
+            _ => Err("Usage: `lfg presets add <game> <slots>`".to_string()),
+        };
+        let (game, slots) = match request {
+            Ok(preset) => preset,
+            Err(e) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+        };
+        let saved = self
+            .store
+            .update(|data| {
+                let presets = &mut data.guild_mut(guild_id).presets;
+                let key = game.to_lowercase();
+                if !presets.contains_key(&key) && presets.len() >= MAX_PRESETS {
+                    return false;
+                }
+                presets.insert(
+                    key,
+                    Preset {
+                        game: game.clone(),
+                        slots,
+                    },
+                );
+                true
+            })
+            .await?;
+        if !saved {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!("This server already has {} presets.", MAX_PRESETS),
+            )
+            .await?;
+            return Ok(());
+        }
+        self.audit(
+            ctx,
+            guild_id,
+            format!("Set LFG preset {} to {} players", game, slots),
+        )
+        .await;
+        send_success(
+            ctx.ctx,
+            ctx.msg,
+            format!("`lfg {}` now looks for {} players.", game, slots),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Command for LfgCommand {
+    fn name(&self) -> &str {
+        "lfg"
+    }
+
+    fn description(&self) -> &str {
+        "Find players for a game"
+    }
+
+    fn usage(&self) -> &str {
+        USAGE
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["lfp", "group"]
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx.msg.guild_id.ok_or("Not in a guild")?;
+        let subcommand = ctx.args.first().map(|arg| arg.to_lowercase());
+        match subcommand.as_deref() {
+            None => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", USAGE)).await?;
+                Ok(())
+            }
+            Some("list") if ctx.args.len() == 1 => self.list(&ctx, guild_id).await,
+            Some("cancel") if ctx.args.len() == 2 => self.cancel(&ctx, guild_id).await,
+            Some("preset" | "presets") => self.presets(&ctx, guild_id).await,
+            Some(_) => self.create(&ctx, guild_id).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(input: &str) -> Vec<String> {
+        input.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parses_requests() {
+        assert_eq!(
+            parse_request(&args("Rocket League 3 in 1h")),
+            Ok(Request {
+                game: "Rocket League".to_string(),
+                slots: Some(3),
+                start_in: Some(Duration::from_secs(3600)),
+            })
+        );
+        assert_eq!(
+            parse_request(&args("valorant 30m")),
+            Ok(Request {
+                game: "valorant".to_string(),
+                slots: None,
+                start_in: Some(Duration::from_secs(1800)),
+            })
+        );
+        // A lone number is the game's name, not its size
+        assert_eq!(parse_request(&args("2048")).unwrap().game, "2048");
+        assert!(parse_request(&args("minecraft 4 in 2w")).is_err());
+    }
+}
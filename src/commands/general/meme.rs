@@ -0,0 +1,124 @@
+//! Meme command for posting a random image from a subreddit.
+
+use async_trait::async_trait;
+use rand::seq::SliceRandom;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::utils::helpers::{
+    apply_mentions, is_nsfw_channel, mention_policy, send_error, truncate,
+};
+use crate::utils::reddit::{normalize_subreddit, post_embed, Reddit, RedditKey, MEME_SUBREDDITS};
+use crate::utils::rest;
+
+/// Posts a random hot image post from a subreddit.
+pub struct MemeCommand {
+    reddit: Arc<Reddit>,
+}
+
+impl MemeCommand {
+    /// Create the command with the Reddit client.
+    pub fn new(Inject(reddit): Inject<RedditKey>) -> Self {
+        Self { reddit }
+    }
+}
+
+#[async_trait]
+impl Command for MemeCommand {
+    fn name(&self) -> &str {
+        "meme"
+    }
+
+    fn description(&self) -> &str {
+        "Post a random image from a subreddit's hot posts"
+    }
+
+    fn usage(&self) -> &str {
+        "meme [subreddit]"
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["reddit"]
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::EMBED_LINKS
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let subreddit = match ctx.args.first() {
+            Some(arg) => match normalize_subreddit(arg) {
+                Some(subreddit) => subreddit,
+                None => {
+                    send_error(
+                        ctx.ctx,
+                        ctx.msg,
+                        format!("`{}` isn't a subreddit name.", truncate(arg, 64)),
+                    )
+                    .await?;
+                    return Ok(());
+                }
+            },
+            None => MEME_SUBREDDITS
+                .choose(&mut rand::thread_rng())
+                .copied()
+                .unwrap_or("memes")
+                .to_string(),
+        };
+
+        let posts = match self.reddit.hot(&subreddit).await? {
+            Some(posts) => posts,
+            None => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!(
+                        "I can't read r/{}. It may not exist or be private.",
+                        subreddit
+                    ),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let nsfw = is_nsfw_channel(ctx.ctx, ctx.msg.channel_id);
+        let images: Vec<_> = posts
+            .iter()
+            .filter(|post| !post.stickied && post.image_url().is_some())
+            .filter(|post| nsfw || !post.over_18)
+            .collect();
+        let post = images.choose(&mut rand::thread_rng()).copied();
+        let post = match post {
+            Some(post) => post,
+            None => {
+                let reason = if !nsfw && posts.iter().any(|post| post.over_18) {
+                    " that can be shown outside age-restricted channels"
+                } else {
+                    ""
+                };
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!("r/{} has no hot image posts{}.", subreddit, reason),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let embed = post_embed(post);
+        let policy = &mention_policy(ctx.ctx).await;
+        rest::call(ctx.ctx, "send_message", || {
+            let embed = embed.clone();
+            ctx.msg.channel_id.send_message(&ctx.ctx.http, move |m| {
+                m.allowed_mentions(|am| apply_mentions(am, policy, &[]))
+                    .set_embed(embed)
+            })
+        })
+        .await?;
+        Ok(())
+    }
+}
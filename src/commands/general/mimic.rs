@@ -0,0 +1,85 @@
+//! Mimic command for sending a message as another member.
+
+use async_trait::async_trait;
+use serenity::model::permissions::Permissions;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::params::{usage_of, Param, ParamKind};
+use crate::utils::helpers::{parse_user, send_error, truncate};
+use crate::utils::rest;
+use crate::utils::webhooks::{webhooks, Persona};
+
+/// Sends a message under another member's name and avatar, for fun.
+///
+/// The name is marked so nobody mistakes it for the real member, and mentions in the
+/// message don't ping anyone.
+pub struct MimicCommand;
+
+#[async_trait]
+impl Command for MimicCommand {
+    fn name(&self) -> &str {
+        "mimic"
+    }
+
+    fn description(&self) -> &str {
+        "Say something as another member"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            Param::required("user", ParamKind::User, "The member to speak as"),
+            Param::required("message", ParamKind::Text, "What they should say"),
+        ]
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::MANAGE_WEBHOOKS | Permissions::MANAGE_MESSAGES
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Mimic can only be used in a server")?;
+
+        let user_id = ctx.args.first().and_then(|arg| parse_user(arg));
+        let text = ctx.args.get(1..).unwrap_or_default().join(" ");
+        let user_id = match user_id {
+            Some(user_id) => user_id,
+            None => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", usage_of(self))).await?;
+                return Ok(());
+            }
+        };
+        let member = match guild_id.member(ctx.ctx, user_id).await {
+            Ok(member) => member,
+            Err(_) => {
+                send_error(ctx.ctx, ctx.msg, "That user isn't in this server.").await?;
+                return Ok(());
+            }
+        };
+
+        let persona = Persona {
+            name: format!("{} (mimic)", member.display_name()),
+            avatar_url: Some(member.face()),
+        };
+        webhooks(ctx.ctx)
+            .await
+            .send(
+                ctx.ctx,
+                ctx.msg.channel_id,
+                &persona,
+                &truncate(&text, 2000),
+                Vec::new(),
+            )
+            .await?;
+
+        // Remove the command so only the mimicked message is left
+        rest::call(ctx.ctx, "delete_message", || ctx.msg.delete(&ctx.ctx.http)).await?;
+        Ok(())
+    }
+}
@@ -1,6 +1,39 @@
 //! General utility commands for the bot.
 
+pub mod analytics;
+#[cfg(feature = "music")]
+pub mod audiofilter;
+pub mod calc;
+pub mod convert;
+pub mod devlookup;
+pub mod event;
+pub mod growth;
+pub mod kb;
+pub mod lfg;
+pub mod meme;
+pub mod mimic;
+pub mod mydata;
+pub mod notifications;
 pub mod ping;
+#[cfg(feature = "music")]
+pub mod play;
+#[cfg(feature = "music")]
+pub mod queue;
+pub mod quote;
+#[cfg(feature = "music")]
+pub mod radio;
+pub mod run;
+#[cfg(feature = "music")]
+pub mod skip;
+#[cfg(feature = "music")]
+pub mod sound;
+pub mod steam;
+#[cfg(feature = "music")]
+pub mod stop;
+pub mod timestamp;
+pub mod todo;
+pub mod urban;
+pub mod vote;
 
 use crate::framework::command_handler::CommandHandler;
 
@@ -8,6 +41,31 @@ use crate::framework::command_handler::CommandHandler;
 pub fn register_commands(handler: &mut CommandHandler) {
     // Register the ping command
     handler.register_command(ping::PingCommand);
+    handler.register_with_state(mydata::MyDataCommand::new);
+    handler.register_with_state(quote::QuoteCommand::new);
+    handler.register_command(mimic::MimicCommand);
+    handler.register_command(urban::UrbanCommand);
+    handler.register_with_state(vote::VoteCommand::new);
+    handler.register_with_state(analytics::EmojiStatsCommand::new);
+    handler.register_with_state(analytics::ActivityCommand::new);
+    handler.register_with_state(growth::GrowthCommand::new);
+    handler.register_command(calc::CalcCommand);
+    handler.register_with_state(convert::ConvertCommand::new);
+    handler.register_with_state(convert::CryptoCommand::new);
+    handler.register_with_state(run::RunCommand::new);
+    handler.register_with_state(devlookup::CrateCommand::new);
+    handler.register_with_state(devlookup::RepoCommand::new);
+    handler.register_with_state(devlookup::DocsCommand::new);
+    handler.register_with_state(steam::SteamCommand::new);
+    handler.register_with_state(steam::GameInfoCommand::new);
+    handler.register_with_state(steam::GamePriceCommand::new);
+    handler.register_with_state(meme::MemeCommand::new);
+    handler.register_with_state(todo::TodoCommand::new);
+    handler.register_with_state(kb::KnowledgeBaseCommand::new);
+    handler.register_with_state(event::EventCommand::new);
+    handler.register_with_state(lfg::LfgCommand::new);
+    handler.register_command(timestamp::TimestampCommand);
+    handler.register_with_state(notifications::NotificationsCommand::new);
 
     // Add more general commands here as they're implemented
     // handler.register_command(help::HelpCommand);
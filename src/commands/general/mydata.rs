@@ -0,0 +1,283 @@
+//! MyData command for exporting or deleting the data the bot stores about a user.
+
+use async_trait::async_trait;
+use serenity::builder::CreateEmbed;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::queue::{ConcurrencyScope, MaxConcurrency};
+use crate::framework::state::Inject;
+use crate::models::guild_events::{EventData, EventKey};
+use crate::models::knowledge_base::{KnowledgeBaseData, KnowledgeBaseKey};
+use crate::models::lfg::{LfgData, LfgKey};
+use crate::models::message_cache::{MessageCache, MessageCacheKey};
+use crate::models::moderation::{ModerationData, ModerationKey};
+use crate::models::modmail::{ModmailData, ModmailKey};
+use crate::models::notifications::{NotificationData, NotificationKey};
+use crate::models::premium::{PremiumData, PremiumKey};
+use crate::models::quotes::{QuoteData, QuoteKey};
+use crate::models::role_persistence::{RolePersistenceData, RolePersistenceKey};
+use crate::models::todos::{TodoData, TodoKey};
+use crate::models::user_data::{UserDataExport, UserStores};
+use crate::models::votes::{VoteData, VotesKey};
+use crate::storage::JsonStore;
+use crate::utils::constants::DEFAULT_COLOR;
+use crate::utils::dms::{dm_service, DmOutcome};
+use crate::utils::files::OutgoingFile;
+use crate::utils::helpers::{send_error, send_info, send_success, send_warning};
+
+/// What `mydata delete` keeps, and why.
+const KEPT: &str = "Moderation records (notes, cases, appeals, reports and watchlist entries) \
+                    and modmail blocks are kept, since servers need them to moderate. Groups \
+                    you host stay until they expire or you cancel them, and premium you \
+                    bought or were given is kept.";
+
+/// Every store that keeps data about users.
+type Stores = (
+    Inject<ModerationKey>,
+    Inject<ModmailKey>,
+    Inject<MessageCacheKey>,
+    Inject<TodoKey>,
+    Inject<RolePersistenceKey>,
+    Inject<NotificationKey>,
+    Inject<PremiumKey>,
+    Inject<VotesKey>,
+    Inject<QuoteKey>,
+    Inject<EventKey>,
+    Inject<LfgKey>,
+    Inject<KnowledgeBaseKey>,
+);
+
+/// Lets users download or delete everything the bot stores about them.
+pub struct MyDataCommand {
+    moderation: Arc<JsonStore<ModerationData>>,
+    modmail: Arc<JsonStore<ModmailData>>,
+    message_cache: Arc<MessageCache>,
+    todos: Arc<JsonStore<TodoData>>,
+    roles: Arc<JsonStore<RolePersistenceData>>,
+    notifications: Arc<JsonStore<NotificationData>>,
+    premium: Arc<JsonStore<PremiumData>>,
+    votes: Arc<JsonStore<VoteData>>,
+    quotes: Arc<JsonStore<QuoteData>>,
+    events: Arc<JsonStore<EventData>>,
+    lfg: Arc<JsonStore<LfgData>>,
+    knowledge_base: Arc<JsonStore<KnowledgeBaseData>>,
+}
+
+impl MyDataCommand {
+    /// Create the command with its stores.
+    pub fn new(
+        (
+            Inject(moderation),
+            Inject(modmail),
+            Inject(message_cache),
+            Inject(todos),
+            Inject(roles),
+            Inject(notifications),
+            Inject(premium),
+            Inject(votes),
+            Inject(quotes),
+            Inject(events),
+            Inject(lfg),
+            Inject(knowledge_base),
+        ): Stores,
+    ) -> Self {
+        Self {
+            moderation,
+            modmail,
+            message_cache,
+            todos,
+            roles,
+            notifications,
+            premium,
+            votes,
+            quotes,
+            events,
+            lfg,
+            knowledge_base,
+        }
+    }
+
+    /// DM the author a JSON file with their data.
+    async fn export(&self, ctx: &CommandContext<'_>) -> CommandResult {
+        let author = &ctx.msg.author;
+        let export = {
+            let moderation = self.moderation.read().await;
+            let modmail = self.modmail.read().await;
+            let todos = self.todos.read().await;
+            let roles = self.roles.read().await;
+            let notifications = self.notifications.read().await;
+            let premium = self.premium.read().await;
+            let votes = self.votes.read().await;
+            let quotes = self.quotes.read().await;
+            let events = self.events.read().await;
+            let lfg = self.lfg.read().await;
+            let knowledge_base = self.knowledge_base.read().await;
+            let stores = UserStores {
+                moderation: &moderation,
+                modmail: &modmail,
+                todos: &todos,
+                roles: &roles,
+                notifications: &notifications,
+                premium: &premium,
+                votes: &votes,
+                quotes: &quotes,
+                events: &events,
+                lfg: &lfg,
+                knowledge_base: &knowledge_base,
+            };
+            UserDataExport::collect(stores, author.id)
+        };
+        let json = serde_json::to_string_pretty(&export)?;
+        let file = OutgoingFile::new(format!("kurumi-data-{}.json", author.id), json);
+
+        let mut embed = CreateEmbed::default();
+        embed
+            .title("Your data")
+            .color(DEFAULT_COLOR)
+            .description(if export.is_empty() {
+                "The bot doesn't store anything about you."
+            } else {
+                "Attached is everything the bot stores about you: moderator notes, cases, \
+                 appeals, reports, watchlist entries, modmail, saved roles, quotes, event \
+                 answers, groups, knowledge base edits, your todo list, notification settings, \
+                 premium and votes. Use `mydata delete` to remove what can be removed."
+            });
+
+        // The user asked for it, so it isn't held back by their DM settings
+        let sent = match dm_service(ctx.ctx).await {
+            Some(dms) => dms
+                .send_file(ctx.ctx, author.id, None, file, Some(embed))
+                .await
+                .is_ok_and(|outcome| outcome == DmOutcome::Sent),
+            None => false,
+        };
+        if !sent {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                "I couldn't DM you. Allow direct messages from server members and try again.",
+            )
+            .await?;
+            return Ok(());
+        }
+
+        if ctx.msg.guild_id.is_some() {
+            send_success(ctx.ctx, ctx.msg, "Check your DMs for your data.").await?;
+        }
+        Ok(())
+    }
+
+    /// Delete the author's data.
+    async fn delete(&self, ctx: &CommandContext<'_>) -> CommandResult {
+        let user_id = ctx.msg.author.id;
+        if ctx
+            .dry_run
+            .record(format!("delete stored data of {}", user_id))
+        {
+            return Ok(());
+        }
+
+        let records = self
+            .modmail
+            .update(|data| data.remove_user(user_id))
+            .await?
+            + self.todos.update(|data| data.remove_user(user_id)).await?
+            + self.roles.update(|data| data.remove_user(user_id)).await?
+            + self
+                .notifications
+                .update(|data| data.remove_user(user_id))
+                .await?
+            + self
+                .premium
+                .update(|data| data.remove_user(user_id))
+                .await?
+            + self.votes.update(|data| data.remove_user(user_id)).await?
+            + self.quotes.update(|data| data.remove_user(user_id)).await?
+            + self.events.update(|data| data.remove_user(user_id)).await?
+            + self.lfg.update(|data| data.remove_user(user_id)).await?
+            + self
+                .knowledge_base
+                .update(|data| data.remove_user(user_id))
+                .await?;
+        let messages = self.message_cache.forget_author(user_id);
+        info!(
+            "Deleted stored data of {}: {} records, {} cached messages",
+            user_id, records, messages
+        );
+
+        send_success(
+            ctx.ctx,
+            ctx.msg,
+            format!(
+                "Deleted {} stored records and {} cached messages about you.\n\n{}",
+                records, messages, KEPT
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Command for MyDataCommand {
+    fn name(&self) -> &str {
+        "mydata"
+    }
+
+    fn description(&self) -> &str {
+        "Download or delete the data the bot stores about you"
+    }
+
+    fn usage(&self) -> &str {
+        "mydata <export|delete [confirm]>"
+    }
+
+    fn max_concurrency(&self) -> Option<MaxConcurrency> {
+        Some(MaxConcurrency::one(ConcurrencyScope::User))
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let action = ctx.args.first().map(|arg| arg.to_lowercase());
+        let confirmed = ctx
+            .args
+            .get(1)
+            .is_some_and(|arg| arg.eq_ignore_ascii_case("confirm"));
+
+        match action.as_deref() {
+            Some("export") => self.export(&ctx).await,
+            Some("delete") if confirmed => self.delete(&ctx).await,
+            Some("delete") => {
+                send_warning(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!(
+                        "This permanently deletes your open modmail conversation, saved roles, \
+                         quotes of you, event answers, todo list, notification settings, votes \
+                         and voting trial, takes you out of groups you joined, and takes your \
+                         name off quotes you saved and knowledge base edits.\n\n{}\n\n\
+                         Run `mydata delete confirm` to continue.",
+                        KEPT
+                    ),
+                )
+                .await?;
+                Ok(())
+            }
+            _ => {
+                send_info(
+                    ctx.ctx,
+                    ctx.msg,
+                    "Your data",
+                    format!(
+                        "`mydata export` sends you a file with everything the bot stores about \
+                         you.\n`mydata delete` deletes it.\n\nUsage: `{}`",
+                        self.usage()
+                    ),
+                )
+                .await?;
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! Notifications command for choosing which DMs the bot sends.
+
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::notifications::{DmCategory, NotificationData, NotificationKey};
+use crate::storage::JsonStore;
+use crate::utils::helpers::{send_error, send_info, send_success};
+
+const USAGE: &str = "notifications [<category|all> <on|off>]";
+
+/// Shows or changes which DMs the author gets.
+pub struct NotificationsCommand {
+    store: Arc<JsonStore<NotificationData>>,
+}
+
+impl NotificationsCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<NotificationKey>) -> Self {
+        Self { store }
+    }
+
+    /// List each category and whether it's on.
+    async fn show(&self, ctx: &CommandContext<'_>) -> CommandResult {
+        let user_id = ctx.msg.author.id;
+        let lines: Vec<String> = {
+            let data = self.store.read().await;
+            DmCategory::ALL
+                .iter()
+                .map(|category| {
+                    let state = if data.allows(user_id, *category) {
+                        "✅ on"
+                    } else {
+                        "🔕 off"
+                    };
+                    format!("`{}` {}: {}", category, state, category.description())
+                })
+                .collect()
+        };
+        let body = format!(
+            "{}\n\nChange one with `notifications <category> <on|off>`, or use `all`.",
+            lines.join("\n")
+        );
+        send_info(ctx.ctx, ctx.msg, "🔔 DM notifications", body).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Command for NotificationsCommand {
+    fn name(&self) -> &str {
+        "notifications"
+    }
+
+    fn description(&self) -> &str {
+        "Choose which DMs the bot sends you"
+    }
+
+    fn usage(&self) -> &str {
+        USAGE
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["notifs", "dms"]
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let (category, state) = match (ctx.args.first(), ctx.args.get(1)) {
+            (None, _) => return self.show(&ctx).await,
+            (Some(category), Some(state)) => (category, state.to_lowercase()),
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", USAGE)).await?;
+                return Ok(());
+            }
+        };
+
+        let categories: Vec<DmCategory> = if category.eq_ignore_ascii_case("all") {
+            DmCategory::ALL.to_vec()
+        } else {
+            match category.parse() {
+                Ok(category) => vec![category],
+                Err(e) => {
+                    send_error(ctx.ctx, ctx.msg, e).await?;
+                    return Ok(());
+                }
+            }
+        };
+        let enabled = match state.as_str() {
+            "on" | "yes" | "true" => true,
+            "off" | "no" | "false" => false,
+            _ => {
+                send_error(ctx.ctx, ctx.msg, "Expected `on` or `off`.").await?;
+                return Ok(());
+            }
+        };
+
+        let user_id = ctx.msg.author.id;
+        self.store
+            .update(|data| data.set(user_id, &categories, enabled))
+            .await?;
+
+        let names: Vec<String> = categories.iter().map(|c| format!("`{}`", c)).collect();
+        let message = if enabled {
+            format!("You'll get DMs for {} again.", names.join(", "))
+        } else {
+            format!("You won't get DMs for {} anymore.", names.join(", "))
+        };
+        send_success(ctx.ctx, ctx.msg, message).await?;
+        Ok(())
+    }
+}
@@ -43,3 +43,28 @@ impl Command for PingCommand {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{command_context, MockDiscord, TestMessage};
+
+    #[tokio::test]
+    async fn replies_then_edits_in_latency() {
+        let discord = MockDiscord::start().await;
+        let ctx = discord.context();
+        let msg = TestMessage::new("!ping").build();
+
+        PingCommand
+            .execute(command_context(&ctx, &msg))
+            .await
+            .unwrap();
+
+        let sent = discord.sent_messages();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0].content.as_deref(), Some("Pinging..."));
+        assert!(sent[1].edited);
+        assert!(sent[1].has_embed_titled("🏓 Pong!"));
+        assert!(sent[1].contains("Latency:"));
+    }
+}
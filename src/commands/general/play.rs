@@ -0,0 +1,374 @@
+//! Play command for queueing music from searches, links and Spotify or Apple
+//! Music links.
+
+use async_trait::async_trait;
+use serenity::model::channel::Message;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::params::{usage_of, Param, ParamKind};
+use crate::framework::state::Inject;
+use crate::models::guild_config::{GuildConfigKey, GuildConfigs};
+use crate::storage::JsonStore;
+use crate::utils::constants::DEFAULT_COLOR;
+use crate::utils::duration::format_compact;
+use crate::utils::helpers::{
+    apply_mentions, mention_policy, send_error, send_success, send_warning, BotConfigKey,
+};
+use crate::utils::player::{Player, PlayerKey, QueuedTrack};
+use crate::utils::rest;
+use crate::utils::track_links::{parse_link, ResolvedTrack, TrackLink, TrackLinks, TrackLinksKey};
+use crate::utils::voice::{self, voice_channel_of};
+
+/// Links with more tracks than this show progress while they're resolved.
+const PROGRESS_THRESHOLD: usize = 100;
+
+/// Queues music in the author's voice channel. Searches and links are looked
+/// up with yt-dlp, and Spotify and Apple Music links are resolved to tracks
+/// that are searched for when they're about to play.
+pub struct PlayCommand {
+    links: Arc<TrackLinks>,
+    player: Arc<Player>,
+    store: Arc<JsonStore<GuildConfigs>>,
+}
+
+impl PlayCommand {
+    /// Create the command with the link resolver, the player and the guild
+    /// config store.
+    pub fn new(
+        (Inject(links), Inject(player), Inject(store)): (
+            Inject<TrackLinksKey>,
+            Inject<PlayerKey>,
+            Inject<GuildConfigKey>,
+        ),
+    ) -> Self {
+        Self {
+            links,
+            player,
+            store,
+        }
+    }
+
+    /// Resolve a link to at most about `max` tracks, showing progress for large
+    /// ones. Returns the tracks and how many the link has in all, or `None` if
+    /// the link doesn't exist.
+    async fn resolve(
+        &self,
+        ctx: &CommandContext<'_>,
+        link: &TrackLink,
+        max: usize,
+    ) -> CommandResult<Option<(Vec<ResolvedTrack>, usize)>> {
+        if let Some(tracks) = self.links.cached(link).await {
+            let total = tracks.len();
+            return Ok(Some((tracks, total)));
+        }
+
+        let mut tracks = Vec::new();
+        let mut total = 0;
+        let mut offset = 0;
+        let mut progress: Option<Message> = None;
+        let complete = loop {
+            let page = match self.links.page(link, offset).await? {
+                Some(page) => page,
+                None if tracks.is_empty() => return Ok(None),
+                None => break false,
+            };
+            tracks.extend(page.tracks);
+            total = page.total;
+            if page.total > PROGRESS_THRESHOLD {
+                let text = format!(
+                    "Looking up the {}: {}/{} tracks...",
+                    link.describe(),
+                    tracks.len().min(page.total),
+                    page.total
+                );
+                let policy = &mention_policy(ctx.ctx).await;
+                let text = &text;
+                match progress.as_ref() {
+                    Some(message) => {
+                        rest::call(ctx.ctx, "edit_message", || {
+                            message
+                                .channel_id
+                                .edit_message(&ctx.ctx.http, message.id, move |m| {
+                                    m.allowed_mentions(|am| apply_mentions(am, policy, &[]))
+                                        .embed(|e| e.description(text).color(DEFAULT_COLOR))
+                                })
+                        })
+                        .await?;
+                    }
+                    None => {
+                        let message = rest::call(ctx.ctx, "send_message", || {
+                            ctx.msg.channel_id.send_message(&ctx.ctx.http, move |m| {
+                                m.reference_message(ctx.msg)
+                                    .allowed_mentions(|am| apply_mentions(am, policy, &[]))
+                                    .embed(|e| e.description(text).color(DEFAULT_COLOR))
+                            })
+                        })
+                        .await?;
+                        progress = Some(message);
+                    }
+                }
+            }
+            match page.next {
+                Some(next) if tracks.len() < max => offset = next,
+                Some(_) => break false,
+                None => break true,
+            }
+        };
+        if let Some(message) = progress {
+            let _ = rest::call(ctx.ctx, "delete_message", || message.delete(&ctx.ctx.http)).await;
+        }
+        // Only whole links are cached, so a later, larger queue limit isn't cut short
+        if complete {
+            self.links.remember(link, tracks.clone()).await;
+        }
+        Ok(Some((tracks, total)))
+    }
+
+    /// Look up a search or a link with yt-dlp. Replies and returns `None` if
+    /// nothing playable was found.
+    async fn search(
+        &self,
+        ctx: &CommandContext<'_>,
+        query: &str,
+        max_length: Duration,
+    ) -> CommandResult<Option<QueuedTrack>> {
+        let media = match voice::lookup(query).await {
+            Ok(Some(media)) => media,
+            Ok(None) => {
+                send_error(ctx.ctx, ctx.msg, "I couldn't find anything for that.").await?;
+                return Ok(None);
+            }
+            Err(e) => {
+                warn!("Couldn't look up {:?}: {}", query, e);
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    "I couldn't look that up, try again later.",
+                )
+                .await?;
+                return Ok(None);
+            }
+        };
+        if let Some(length) = media.length() {
+            if length > max_length {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!(
+                        "**{}** is longer than {}.",
+                        media.title,
+                        format_compact(max_length)
+                    ),
+                )
+                .await?;
+                return Ok(None);
+            }
+        }
+        Ok(Some(QueuedTrack::from_media(
+            &media,
+            query,
+            ctx.msg.author.id,
+        )))
+    }
+
+    /// Resolve a Spotify or Apple Music link to at most `room` tracks. Returns
+    /// the tracks and notes about the ones left out, or replies and returns
+    /// `None` if the link can't be used.
+    async fn link_tracks(
+        &self,
+        ctx: &CommandContext<'_>,
+        link: &TrackLink,
+        room: usize,
+        max_length: Duration,
+    ) -> CommandResult<Option<(Vec<QueuedTrack>, Vec<String>)>> {
+        if let Err(e) = self.links.supports(link) {
+            send_error(ctx.ctx, ctx.msg, e).await?;
+            return Ok(None);
+        }
+        let (tracks, total) = match self.resolve(ctx, link, room).await? {
+            Some(resolved) => resolved,
+            None => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!("I couldn't find that {}.", link.describe()),
+                )
+                .await?;
+                return Ok(None);
+            }
+        };
+        let found = tracks.len();
+        let mut tracks: Vec<ResolvedTrack> = tracks
+            .into_iter()
+            .filter(|track| track.length() <= max_length)
+            .collect();
+        let too_long = found - tracks.len();
+        // Pages past the queue limit aren't fetched at all
+        let too_many = tracks.len().saturating_sub(room) + total.saturating_sub(found);
+        tracks.truncate(room);
+
+        let mut notes = Vec::new();
+        if too_long > 0 {
+            notes.push(format!(
+                "Left out {} longer than {}.",
+                match too_long {
+                    1 => "1 track".to_string(),
+                    n => format!("{} tracks", n),
+                },
+                format_compact(max_length)
+            ));
+        }
+        if too_many > 0 {
+            notes.push(format!(
+                "The queue was full, so {} more were left out.",
+                too_many
+            ));
+        }
+        let tracks = tracks
+            .into_iter()
+            .map(|track| QueuedTrack::search(track.query(), track.length(), ctx.msg.author.id))
+            .collect();
+        Ok(Some((tracks, notes)))
+    }
+}
+
+#[async_trait]
+impl Command for PlayCommand {
+    fn name(&self) -> &str {
+        "play"
+    }
+
+    fn description(&self) -> &str {
+        "Play music from a search or a Spotify or Apple Music link"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![Param::required(
+            "track",
+            ParamKind::Text,
+            "A search, a link, or a Spotify or Apple Music link",
+        )]
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::CONNECT | Permissions::SPEAK
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Music can only be played in a server")?;
+        if ctx.args.is_empty() {
+            send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", usage_of(self))).await?;
+            return Ok(());
+        }
+        let channel_id = match voice_channel_of(ctx.ctx, guild_id, ctx.msg.author.id) {
+            Some(channel_id) => channel_id,
+            None => {
+                send_error(ctx.ctx, ctx.msg, "Join a voice channel first.").await?;
+                return Ok(());
+            }
+        };
+        if let Some(playing) = self.player.channel(guild_id) {
+            if playing != channel_id && self.player.queued(guild_id) > 0 {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!("I'm already playing in <#{}>.", playing),
+                )
+                .await?;
+                return Ok(());
+            }
+        }
+
+        let config = {
+            let data = ctx.ctx.data.read().await;
+            data.get::<BotConfigKey>()
+                .map(|config| config.music.clone())
+        }
+        .unwrap_or_default();
+        let settings = self.store.read().await.get(guild_id).music;
+        let (max_queue, max_length) =
+            (settings.queue_limit(&config), settings.track_limit(&config));
+        let room = max_queue.saturating_sub(self.player.queued(guild_id));
+        if room == 0 {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!("The queue is full, it holds {} tracks.", max_queue),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let query = ctx.args.join(" ");
+        let (tracks, mut notes) = match parse_link(&query) {
+            Some(link) => match self.link_tracks(&ctx, &link, room, max_length).await? {
+                Some(found) => found,
+                None => return Ok(()),
+            },
+            None => match self.search(&ctx, &query, max_length).await? {
+                Some(track) => (vec![track], Vec::new()),
+                None => return Ok(()),
+            },
+        };
+        if tracks.is_empty() {
+            send_error(ctx.ctx, ctx.msg, notes.join(" ")).await?;
+            return Ok(());
+        }
+
+        let found = tracks.len();
+        let first = tracks[0].title.clone();
+        let (position, count) = match self
+            .player
+            .enqueue(
+                ctx.ctx,
+                guild_id,
+                channel_id,
+                ctx.msg.channel_id,
+                tracks,
+                max_queue,
+            )
+            .await
+        {
+            Ok(queued) => queued,
+            Err(e) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+        };
+        // The queue can fill up while a link is looked up
+        if count < found {
+            notes.push(format!(
+                "The queue filled up in the meantime, so {} more were left out.",
+                found - count
+            ));
+        }
+
+        let mut description = match (count, position) {
+            (1, 0) => format!("Starting **{}**.", first),
+            (1, _) => format!("Queued **{}** at position {}.", first, position),
+            (_, 0) => format!("Queued {} tracks, starting with **{}**.", count, first),
+            _ => format!("Queued {} tracks from position {}.", count, position),
+        };
+        if notes.is_empty() {
+            send_success(ctx.ctx, ctx.msg, description).await?;
+        } else {
+            for note in notes {
+                description.push(' ');
+                description.push_str(&note);
+            }
+            send_warning(ctx.ctx, ctx.msg, description).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+//! Queue command for showing and clearing the server's music queue.
+
+use async_trait::async_trait;
+use serenity::model::id::GuildId;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::params::{usage_of, Param, ParamKind};
+use crate::framework::state::Inject;
+use crate::models::guild_config::{GuildConfigKey, GuildConfigs};
+use crate::storage::JsonStore;
+use crate::utils::constants::PAGINATION_MAX_ITEMS;
+use crate::utils::duration::format_clock;
+use crate::utils::helpers::{send_error, send_info, send_success};
+use crate::utils::player::{Player, PlayerKey};
+use crate::utils::voice::{self, voice_channel_of};
+
+/// Shows what's playing and what's queued, and clears the queue.
+pub struct QueueCommand {
+    player: Arc<Player>,
+    store: Arc<JsonStore<GuildConfigs>>,
+}
+
+impl QueueCommand {
+    /// Create the command with the player and the guild config store.
+    pub fn new(
+        (Inject(player), Inject(store)): (Inject<PlayerKey>, Inject<GuildConfigKey>),
+    ) -> Self {
+        Self { player, store }
+    }
+
+    /// Show a page of the queue.
+    async fn show(
+        &self,
+        ctx: &CommandContext<'_>,
+        guild_id: GuildId,
+        page: usize,
+    ) -> CommandResult {
+        let snapshot = match self.player.snapshot(guild_id).await {
+            Some(snapshot) if snapshot.current.is_some() || !snapshot.upcoming.is_empty() => {
+                snapshot
+            }
+            _ => {
+                send_info(
+                    ctx.ctx,
+                    ctx.msg,
+                    "🎵 Queue",
+                    "Nothing is queued. Add something with `play <search>`.",
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let pages = snapshot
+            .upcoming
+            .len()
+            .div_ceil(PAGINATION_MAX_ITEMS)
+            .max(1);
+        if page == 0 || page > pages {
+            send_error(ctx.ctx, ctx.msg, format!("Page {} doesn't exist.", page)).await?;
+            return Ok(());
+        }
+        let mut description = match &snapshot.current {
+            Some((track, position)) => format!(
+                "**Now playing** in <#{}>: {} [{}]\n",
+                snapshot.channel_id,
+                track.line(),
+                format_clock(*position)
+            ),
+            None => String::new(),
+        };
+        for (i, track) in snapshot
+            .upcoming
+            .iter()
+            .enumerate()
+            .skip((page - 1) * PAGINATION_MAX_ITEMS)
+            .take(PAGINATION_MAX_ITEMS)
+        {
+            description.push_str(&format!("\n`{}.` {}", i + 1, track.line()));
+        }
+        send_info(
+            ctx.ctx,
+            ctx.msg,
+            format!(
+                "🎵 Queue (page {}/{}, {} up next)",
+                page,
+                pages,
+                snapshot.upcoming.len()
+            ),
+            description,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Remove every queued track. With others listening, this needs the DJ role
+    /// or Manage Server.
+    async fn clear(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let channel_id = match self.player.channel(guild_id) {
+            Some(channel_id) => channel_id,
+            None => {
+                send_error(ctx.ctx, ctx.msg, "Nothing is queued.").await?;
+                return Ok(());
+            }
+        };
+        if voice_channel_of(ctx.ctx, guild_id, ctx.msg.author.id) != Some(channel_id) {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!("Join <#{}> to clear the queue.", channel_id),
+            )
+            .await?;
+            return Ok(());
+        }
+        let settings = self.store.read().await.get(guild_id).music;
+        let listeners = voice::listeners(ctx.ctx, guild_id, channel_id).len();
+        if !voice::can_control(ctx.ctx, ctx.msg, &settings, listeners).await {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                "While others are listening, you need the DJ role or Manage Server to clear the queue.",
+            )
+            .await?;
+            return Ok(());
+        }
+        let removed = self.player.clear(guild_id);
+        send_success(
+            ctx.ctx,
+            ctx.msg,
+            format!("Removed {} queued tracks.", removed),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Command for QueueCommand {
+    fn name(&self) -> &str {
+        "queue"
+    }
+
+    fn description(&self) -> &str {
+        "Show or clear the music queue"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![Param::optional(
+            "page",
+            ParamKind::Word,
+            "Which page of the queue to show, or clear to empty it",
+        )
+        .pattern(r"^(\d+|clear)$", "a page number or `clear`")]
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["q"]
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("The queue can only be shown in a server")?;
+        match ctx.args.first().map(String::as_str) {
+            Some("clear") => self.clear(&ctx, guild_id).await,
+            Some(page) => match page.parse() {
+                Ok(page) => self.show(&ctx, guild_id, page).await,
+                Err(_) => {
+                    send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", usage_of(self))).await?;
+                    Ok(())
+                }
+            },
+            None => self.show(&ctx, guild_id, 1).await,
+        }
+    }
+}
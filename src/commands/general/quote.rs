@@ -0,0 +1,493 @@
+//! Quote command for reposting a message as an embed and keeping a server's
+//! quote collection.
+
+use async_trait::async_trait;
+use rand::seq::SliceRandom;
+use serenity::builder::CreateEmbed;
+use serenity::model::id::GuildId;
+use serenity::model::permissions::Permissions;
+use serenity::model::timestamp::Timestamp;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::quotes::{Quote, QuoteData, QuoteKey};
+use crate::storage::JsonStore;
+use crate::utils::constants::{DEFAULT_COLOR, PAGINATION_MAX_ITEMS};
+use crate::utils::helpers::{
+    apply_mentions, is_nsfw_channel, mention_policy, parse_message_ref, parse_user, permissions_in,
+    quote_embed, send_error, send_info, send_success, truncate, unix_timestamp,
+};
+use crate::utils::rest;
+use crate::utils::webhooks::{webhooks, Persona};
+
+/// Permissions needed to read the quoted message's channel.
+const READ_PERMISSIONS: Permissions =
+    Permissions::VIEW_CHANNEL.union(Permissions::READ_MESSAGE_HISTORY);
+
+/// Longest quote that can be saved.
+const MAX_QUOTE_LENGTH: usize = 1000;
+
+const USAGE: &str = "quote [<message link or ID>|#<id>] | quote add [user] <text> | quote search <term> [page] | quote remove <id>";
+
+/// Parse a saved quote ID written as `#12`.
+fn parse_quote_id(arg: &str) -> Option<u64> {
+    arg.strip_prefix('#')?.parse().ok()
+}
+
+/// Split `quote search` arguments into the term and page. A trailing number is
+/// the page, unless it's the whole term.
+fn search_args(args: &[String]) -> (String, usize) {
+    match args.split_last() {
+        Some((last, rest)) if !rest.is_empty() => match last.parse::<usize>() {
+            Ok(page) => (rest.join(" "), page.max(1)),
+            Err(_) => (args.join(" "), 1),
+        },
+        _ => (args.join(" "), 1),
+    }
+}
+
+/// One line of a quote list.
+fn quote_line(quote: &Quote) -> String {
+    let content = truncate(&quote.content.replace('\n', " "), 80);
+    format!("**#{}** “{}” — {}", quote.id, content, quote.author_name)
+}
+
+/// The embed showing a saved quote.
+fn saved_quote_embed(quote: &Quote, guild_id: GuildId) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    let author = match quote.author_id {
+        Some(author_id) => format!("— <@{}>", author_id),
+        None => format!("— {}", quote.author_name),
+    };
+    embed
+        .description(format!("“{}”\n\n{}", quote.content, author))
+        .color(DEFAULT_COLOR)
+        .footer(|f| f.text(format!("Quote #{}", quote.id)))
+        .timestamp(
+            Timestamp::from_unix_timestamp(quote.added_at as i64)
+                .unwrap_or_else(|_| Timestamp::now()),
+        );
+    if let (Some(channel_id), Some(message_id)) = (quote.channel_id, quote.message_id) {
+        embed.field(
+            "Source",
+            format!(
+                "[Jump to message](https://discord.com/channels/{}/{}/{})",
+                guild_id, channel_id, message_id
+            ),
+            false,
+        );
+    }
+    embed
+}
+
+/// Reposts messages as embeds and keeps the server's saved quotes.
+pub struct QuoteCommand {
+    store: Arc<JsonStore<QuoteData>>,
+}
+
+impl QuoteCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<QuoteKey>) -> Self {
+        Self { store }
+    }
+
+    /// Show a saved quote, or a random one.
+    async fn show(
+        &self,
+        ctx: &CommandContext<'_>,
+        guild_id: GuildId,
+        id: Option<u64>,
+    ) -> CommandResult {
+        let quote = {
+            let data = self.store.read().await;
+            let guild = data.guild(guild_id);
+            match id {
+                Some(id) => guild.and_then(|guild| guild.get(id)).cloned(),
+                None => guild
+                    .and_then(|guild| guild.quotes.choose(&mut rand::thread_rng()))
+                    .cloned(),
+            }
+        };
+        let quote = match (quote, id) {
+            (Some(quote), _) => quote,
+            (None, Some(id)) => {
+                send_error(ctx.ctx, ctx.msg, format!("There's no quote #{}.", id)).await?;
+                return Ok(());
+            }
+            (None, None) => {
+                send_info(
+                    ctx.ctx,
+                    ctx.msg,
+                    "💬 Quotes",
+                    "No quotes saved yet. Reply to a message with `quote add` to save one.",
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        // Quotes are shown without pinging the people in them
+        let embed = saved_quote_embed(&quote, guild_id);
+        let policy = &mention_policy(ctx.ctx).await;
+        rest::call(ctx.ctx, "send_message", || {
+            let embed = embed.clone();
+            ctx.msg.channel_id.send_message(&ctx.ctx.http, move |m| {
+                m.allowed_mentions(|am| apply_mentions(am, policy, &[]))
+                    .set_embed(embed)
+            })
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Save the replied-to message, or text, as a quote.
+    async fn add(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let args = ctx.args.get(1..).unwrap_or_default();
+        let quote = if let Some(message) = &ctx.msg.referenced_message {
+            if message.author.bot {
+                send_error(ctx.ctx, ctx.msg, "Bot messages can't be saved as quotes.").await?;
+                return Ok(());
+            }
+            let saved = self
+                .store
+                .read()
+                .await
+                .guild(guild_id)
+                .and_then(|guild| guild.from_message(message.id.0))
+                .map(|quote| quote.id);
+            if let Some(id) = saved {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!("That message is already saved as quote #{}.", id),
+                )
+                .await?;
+                return Ok(());
+            }
+            Quote {
+                id: 0,
+                content: message.content.trim().to_string(),
+                author_id: Some(message.author.id.0),
+                author_name: message.author.name.clone(),
+                added_by: ctx.msg.author.id.0,
+                added_at: unix_timestamp(),
+                channel_id: Some(message.channel_id.0),
+                message_id: Some(message.id.0),
+            }
+        } else {
+            let author_id = args.first().and_then(|arg| parse_user(arg));
+            let text = match author_id {
+                Some(_) => args[1..].join(" "),
+                None => args.join(" "),
+            };
+            let author_name = match author_id {
+                Some(author_id) => match ctx.ctx.cache.user(author_id) {
+                    Some(user) => user.name,
+                    None => match author_id.to_user(ctx.ctx).await {
+                        Ok(user) => user.name,
+                        Err(_) => "Unknown".to_string(),
+                    },
+                },
+                None => "Anonymous".to_string(),
+            };
+            Quote {
+                id: 0,
+                content: text.trim().trim_matches('"').trim().to_string(),
+                author_id: author_id.map(|id| id.0),
+                author_name,
+                added_by: ctx.msg.author.id.0,
+                added_at: unix_timestamp(),
+                channel_id: None,
+                message_id: None,
+            }
+        };
+
+        if quote.content.is_empty() {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                "Reply to a message with `quote add`, or use `quote add [user] <text>`.",
+            )
+            .await?;
+            return Ok(());
+        }
+        if quote.content.chars().count() > MAX_QUOTE_LENGTH {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!(
+                    "Quotes can be at most {} characters long.",
+                    MAX_QUOTE_LENGTH
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let author_name = quote.author_name.clone();
+        let id = self
+            .store
+            .update(|data| data.guild_mut(guild_id).add(quote))
+            .await?;
+        send_success(
+            ctx.ctx,
+            ctx.msg,
+            format!("Saved quote #{} from {}.", id, author_name),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// List the quotes matching a term.
+    async fn search(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let (term, page) = search_args(ctx.args.get(1..).unwrap_or_default());
+        if term.is_empty() {
+            send_error(ctx.ctx, ctx.msg, "Usage: `quote search <term> [page]`").await?;
+            return Ok(());
+        }
+
+        let (lines, total) = {
+            let data = self.store.read().await;
+            let found = data
+                .guild(guild_id)
+                .map(|guild| guild.search(&term))
+                .unwrap_or_default();
+            let lines: Vec<String> = found
+                .iter()
+                .skip((page - 1) * PAGINATION_MAX_ITEMS)
+                .take(PAGINATION_MAX_ITEMS)
+                .map(|quote| quote_line(quote))
+                .collect();
+            (lines, found.len())
+        };
+
+        if total == 0 {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!("No quotes match `{}`.", truncate(&term, 64)),
+            )
+            .await?;
+            return Ok(());
+        }
+        let pages = total.div_ceil(PAGINATION_MAX_ITEMS);
+        if lines.is_empty() {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!("Page {} doesn't exist. There are {} page(s).", page, pages),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        send_info(
+            ctx.ctx,
+            ctx.msg,
+            format!(
+                "💬 Quotes matching “{}” (page {}/{})",
+                truncate(&term, 64),
+                page,
+                pages
+            ),
+            truncate(&lines.join("\n"), 4000),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Remove a quote. Members can remove the quotes they saved, and moderators
+    /// any quote.
+    async fn remove(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let id = match ctx
+            .args
+            .get(1)
+            .and_then(|arg| arg.trim_start_matches('#').parse::<u64>().ok())
+        {
+            Some(id) => id,
+            None => {
+                send_error(ctx.ctx, ctx.msg, "Usage: `quote remove <id>`").await?;
+                return Ok(());
+            }
+        };
+
+        let added_by = self
+            .store
+            .read()
+            .await
+            .guild(guild_id)
+            .and_then(|guild| guild.get(id))
+            .map(|quote| quote.added_by);
+        let added_by = match added_by {
+            Some(added_by) => added_by,
+            None => {
+                send_error(ctx.ctx, ctx.msg, format!("There's no quote #{}.", id)).await?;
+                return Ok(());
+            }
+        };
+        if added_by != ctx.msg.author.id.0 {
+            let permissions =
+                permissions_in(ctx.ctx, guild_id, ctx.msg.channel_id, ctx.msg.author.id).await;
+            if !permissions.is_some_and(|p| p.manage_messages()) {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    "You can only remove quotes you saved, unless you can manage messages.",
+                )
+                .await?;
+                return Ok(());
+            }
+        }
+
+        self.store
+            .update(|data| data.guild_mut(guild_id).remove(id))
+            .await?;
+        send_success(ctx.ctx, ctx.msg, format!("Removed quote #{}.", id)).await?;
+        Ok(())
+    }
+
+    /// Repost a message from this server as an embed.
+    async fn repost(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let target = match ctx.args.first().and_then(|arg| parse_message_ref(arg)) {
+            Some(target) => target,
+            None => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", USAGE)).await?;
+                return Ok(());
+            }
+        };
+        if target.guild_id.is_some_and(|id| id != guild_id) {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                "Only messages from this server can be quoted.",
+            )
+            .await?;
+            return Ok(());
+        }
+        let channel_id = target.channel_id.unwrap_or(ctx.msg.channel_id);
+
+        // The channel must belong to this guild and be readable by the author
+        let in_guild = ctx
+            .ctx
+            .cache
+            .guild_channel(channel_id)
+            .is_some_and(|channel| channel.guild_id == guild_id);
+        if !in_guild {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                "I can't find that channel in this server.",
+            )
+            .await?;
+            return Ok(());
+        }
+        if channel_id != ctx.msg.channel_id {
+            let permissions =
+                permissions_in(ctx.ctx, guild_id, channel_id, ctx.msg.author.id).await;
+            if !permissions.is_some_and(|p| p.contains(READ_PERMISSIONS)) {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    "You can only quote messages from channels you can read.",
+                )
+                .await?;
+                return Ok(());
+            }
+
+            if is_nsfw_channel(ctx.ctx, channel_id) && !is_nsfw_channel(ctx.ctx, ctx.msg.channel_id)
+            {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    "Messages from age-restricted channels can't be quoted here.",
+                )
+                .await?;
+                return Ok(());
+            }
+        }
+
+        let message = match rest::call(ctx.ctx, "get_message", || {
+            channel_id.message(&ctx.ctx.http, target.message_id)
+        })
+        .await
+        {
+            Ok(message) => message,
+            Err(_) => {
+                send_error(ctx.ctx, ctx.msg, "I couldn't find that message.").await?;
+                return Ok(());
+            }
+        };
+
+        // Repost under the quoting member's name, so it's clear who brought it up
+        let embed = quote_embed(&message, guild_id);
+        webhooks(ctx.ctx)
+            .await
+            .send(
+                ctx.ctx,
+                ctx.msg.channel_id,
+                &Persona::of(&ctx.msg.author),
+                "",
+                vec![embed],
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Command for QuoteCommand {
+    fn name(&self) -> &str {
+        "quote"
+    }
+
+    fn description(&self) -> &str {
+        "Repost a message as an embed, or save and show the server's quotes"
+    }
+
+    fn usage(&self) -> &str {
+        USAGE
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::EMBED_LINKS
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Quote can only be used in a server")?;
+
+        let action = ctx.args.first().map(|arg| arg.to_lowercase());
+        match action.as_deref() {
+            None => self.show(&ctx, guild_id, None).await,
+            Some("add") => self.add(&ctx, guild_id).await,
+            Some("search") => self.search(&ctx, guild_id).await,
+            Some("remove") | Some("delete") => self.remove(&ctx, guild_id).await,
+            Some(arg) => match parse_quote_id(arg) {
+                Some(id) => self.show(&ctx, guild_id, Some(id)).await,
+                None => self.repost(&ctx, guild_id).await,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_search_term_and_page() {
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(search_args(&args(&["ship", "it"])), ("ship it".into(), 1));
+        assert_eq!(search_args(&args(&["ship", "2"])), ("ship".into(), 2));
+        assert_eq!(search_args(&args(&["42"])), ("42".into(), 1));
+        assert_eq!(parse_quote_id("#7"), Some(7));
+        assert_eq!(parse_quote_id("7"), None);
+    }
+}
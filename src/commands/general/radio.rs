@@ -0,0 +1,309 @@
+//! Radio command for internet radio presets.
+
+use async_trait::async_trait;
+use serenity::model::id::GuildId;
+use serenity::model::permissions::Permissions;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::params::{usage_of, Param, ParamKind};
+use crate::framework::state::Inject;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::guild_config::{GuildConfigKey, GuildConfigs};
+use crate::models::music::{check_preset_name, check_stream_url, MAX_RADIO_PRESETS};
+use crate::storage::JsonStore;
+use crate::utils::helpers::{
+    author_permissions, send_error, send_info, send_success, truncate, BotConfigKey,
+};
+use crate::utils::player::{Player, PlayerKey, QueuedTrack};
+use crate::utils::voice::voice_channel_of;
+
+/// Plays internet radio presets, from the config or added by the server.
+/// Adding and removing the server's own needs Manage Server.
+pub struct RadioCommand {
+    store: Arc<JsonStore<GuildConfigs>>,
+    player: Arc<Player>,
+}
+
+impl RadioCommand {
+    /// Create the command with its store and the player.
+    pub fn new(
+        (Inject(store), Inject(player)): (Inject<GuildConfigKey>, Inject<PlayerKey>),
+    ) -> Self {
+        Self { store, player }
+    }
+
+    /// The presets every server has, from `[music.radio]`.
+    async fn builtin(ctx: &CommandContext<'_>) -> BTreeMap<String, String> {
+        let data = ctx.ctx.data.read().await;
+        data.get::<BotConfigKey>()
+            .map(|config| config.music.radio.clone())
+            .unwrap_or_default()
+    }
+
+    /// Whether the author can change the server's presets, telling them if not.
+    async fn can_manage(ctx: &CommandContext<'_>) -> CommandResult<bool> {
+        let allowed = author_permissions(ctx.ctx, ctx.msg)
+            .await
+            .is_some_and(|permissions| permissions.manage_guild());
+        if !allowed {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                "You need Manage Server to change the radio presets.",
+            )
+            .await?;
+        }
+        Ok(allowed)
+    }
+
+    /// List the built-in presets and the server's own.
+    async fn list(&self, ctx: &CommandContext<'_>, guild_id: GuildId) -> CommandResult {
+        let builtin = Self::builtin(ctx).await;
+        let custom = self.store.read().await.get(guild_id).music.radio;
+        let mut lines: Vec<String> = builtin
+            .keys()
+            .filter(|name| !custom.contains_key(*name))
+            .map(|name| format!("`{}`", name))
+            .collect();
+        lines.extend(
+            custom
+                .iter()
+                .map(|(name, url)| format!("`{}`: <{}>", name, truncate(url, 80))),
+        );
+        lines.sort();
+        let description = if lines.is_empty() {
+            format!("There are no presets. Usage: `{}`", usage_of(self))
+        } else {
+            lines.join("\n")
+        };
+        send_info(ctx.ctx, ctx.msg, "📻 Radio presets", description).await?;
+        Ok(())
+    }
+
+    /// Add or replace one of the server's presets.
+    async fn add(
+        &self,
+        ctx: &CommandContext<'_>,
+        guild_id: GuildId,
+        name: &str,
+        url: &str,
+    ) -> CommandResult {
+        let checked = check_preset_name(name).and_then(|name| Ok((name, check_stream_url(url)?)));
+        let (name, url) = match checked {
+            Ok(checked) => checked,
+            Err(e) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+        };
+
+        let added = self
+            .store
+            .update(|configs| {
+                let radio = &mut configs.entry(guild_id).music.radio;
+                if !radio.contains_key(&name) && radio.len() >= MAX_RADIO_PRESETS {
+                    return false;
+                }
+                radio.insert(name.clone(), url.clone());
+                true
+            })
+            .await?;
+        if !added {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!("A server can add up to {} presets.", MAX_RADIO_PRESETS),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action: format!("Add radio preset `{}`", name),
+                reason: Some(url),
+            },
+        )
+        .await;
+        send_success(
+            ctx.ctx,
+            ctx.msg,
+            format!("Added `{}`. Play it with `radio {}`.", name, name),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Remove one of the server's presets.
+    async fn remove(
+        &self,
+        ctx: &CommandContext<'_>,
+        guild_id: GuildId,
+        name: &str,
+    ) -> CommandResult {
+        let name = name.to_lowercase();
+        let removed = self
+            .store
+            .update(|configs| configs.entry(guild_id).music.radio.remove(&name))
+            .await?;
+        if removed.is_none() {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!("This server hasn't added a preset named `{}`.", name),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action: format!("Remove radio preset `{}`", name),
+                reason: None,
+            },
+        )
+        .await;
+        send_success(ctx.ctx, ctx.msg, format!("Removed `{}`.", name)).await?;
+        Ok(())
+    }
+
+    /// Queue a preset's stream in the author's voice channel. It plays until
+    /// it's skipped or stopped.
+    async fn play(&self, ctx: &CommandContext<'_>, guild_id: GuildId, name: &str) -> CommandResult {
+        let name = name.to_lowercase();
+        let builtin = Self::builtin(ctx).await;
+        let settings = self.store.read().await.get(guild_id).music;
+        let url = match settings.radio_stream(&builtin, &name) {
+            Some(url) => url,
+            None => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!("There's no preset named `{}`. See `radio list`.", name),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+        let channel_id = match voice_channel_of(ctx.ctx, guild_id, ctx.msg.author.id) {
+            Some(channel_id) => channel_id,
+            None => {
+                send_error(ctx.ctx, ctx.msg, "Join a voice channel first.").await?;
+                return Ok(());
+            }
+        };
+        let config = {
+            let data = ctx.ctx.data.read().await;
+            data.get::<BotConfigKey>()
+                .map(|config| config.music.clone())
+        }
+        .unwrap_or_default();
+        let max_queue = settings.queue_limit(&config);
+
+        let track = QueuedTrack::radio(&name, url, ctx.msg.author.id);
+        let position = match self
+            .player
+            .enqueue(
+                ctx.ctx,
+                guild_id,
+                channel_id,
+                ctx.msg.channel_id,
+                vec![track],
+                max_queue,
+            )
+            .await
+        {
+            Ok((position, _)) => position,
+            Err(e) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+        };
+        let reply = match position {
+            0 => format!(
+                "Tuning in to `{}` in <#{}>. Use `skip` or `stop` to end it.",
+                name, channel_id
+            ),
+            _ => format!("Queued `{}` at position {}.", name, position),
+        };
+        send_success(ctx.ctx, ctx.msg, reply).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Command for RadioCommand {
+    fn name(&self) -> &str {
+        "radio"
+    }
+
+    fn description(&self) -> &str {
+        "Play internet radio presets and manage the server's own"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            Param::optional(
+                "preset",
+                ParamKind::Word,
+                "A preset to play, or list, add or remove",
+            ),
+            Param::optional("name", ParamKind::Word, "The preset to add or remove"),
+            Param::optional("url", ParamKind::Word, "The stream to add"),
+        ]
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::CONNECT | Permissions::SPEAK
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("The radio can only be used in a server")?;
+        let action = ctx.args.first().map(|arg| arg.to_lowercase());
+
+        match (action.as_deref(), ctx.args.get(1), ctx.args.get(2)) {
+            (None | Some("list"), _, _) => self.list(&ctx, guild_id).await,
+            (Some("add"), Some(name), Some(url)) => {
+                if Self::can_manage(&ctx).await? {
+                    self.add(&ctx, guild_id, name, url).await?;
+                }
+                Ok(())
+            }
+            (Some("remove" | "delete"), Some(name), None) => {
+                if Self::can_manage(&ctx).await? {
+                    self.remove(&ctx, guild_id, name).await?;
+                }
+                Ok(())
+            }
+            (Some("add" | "remove" | "delete"), _, _) => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", usage_of(self))).await?;
+                Ok(())
+            }
+            (Some(name), None, None) => {
+                let name = name.to_string();
+                self.play(&ctx, guild_id, &name).await
+            }
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", usage_of(self))).await?;
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,294 @@
+//! Run command for compiling and running code in a sandbox.
+
+use async_trait::async_trait;
+use serenity::builder::CreateEmbed;
+use serenity::model::id::UserId;
+use serenity::model::permissions::Permissions;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::utils::constants::{DEFAULT_COLOR, ERROR_COLOR, SUCCESS_COLOR};
+use crate::utils::duration::format_compact;
+use crate::utils::helpers::{apply_mentions, mention_policy, send_error, truncate};
+use crate::utils::paste::{paste, paste_config};
+use crate::utils::piston::{extract_code, Piston, PistonKey, Stage};
+use crate::utils::rest;
+
+/// The most output shown in an embed field, leaving room for the code fence.
+const FIELD_OUTPUT_LIMIT: usize = 1000;
+
+/// Limits how many runs each user may start in a window.
+struct RunLimiter {
+    runs: usize,
+    window: Duration,
+    starts: Mutex<HashMap<UserId, VecDeque<Instant>>>,
+}
+
+impl RunLimiter {
+    fn new(runs: usize, window: Duration) -> Self {
+        Self {
+            runs,
+            window,
+            starts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Start a run, or return how long until the user may run code again.
+    fn try_start(&self, user: UserId, now: Instant) -> Result<(), Duration> {
+        if self.runs == 0 {
+            return Ok(());
+        }
+
+        let mut starts = self.starts.lock().unwrap_or_else(|e| e.into_inner());
+        starts.retain(|_, times| {
+            times.retain(|start| now.duration_since(*start) < self.window);
+            !times.is_empty()
+        });
+        let times = starts.entry(user).or_default();
+        if times.len() >= self.runs {
+            let oldest = times.front().copied().unwrap_or(now);
+            return Err(self.window - now.duration_since(oldest));
+        }
+        times.push_back(now);
+        Ok(())
+    }
+}
+
+/// Format a stage's output as a code block, cut to fit in an embed field.
+fn output_field(output: &str) -> String {
+    let output = output.replace("```", "`\u{200b}``");
+    let output = if output.trim().is_empty() {
+        "(no output)".to_string()
+    } else {
+        truncate(&output, FIELD_OUTPUT_LIMIT)
+    };
+    format!("```\n{}\n```", output)
+}
+
+/// Describe how a stage ended, like `exit code 1` or `killed by SIGKILL`.
+fn describe_exit(stage: &Stage) -> String {
+    match (&stage.signal, stage.code) {
+        (Some(signal), _) => format!("killed by {}", signal),
+        (None, Some(code)) => format!("exit code {}", code),
+        (None, None) => "no exit code".to_string(),
+    }
+}
+
+/// Compiles and runs code with Piston.
+pub struct RunCommand {
+    piston: Arc<Piston>,
+    limiter: RunLimiter,
+}
+
+impl RunCommand {
+    /// Create the command with the Piston client.
+    pub fn new(Inject(piston): Inject<PistonKey>) -> Self {
+        let config = piston.config();
+        let limiter = RunLimiter::new(config.runs, Duration::from_secs(config.window));
+        Self { piston, limiter }
+    }
+}
+
+#[async_trait]
+impl Command for RunCommand {
+    fn name(&self) -> &str {
+        "run"
+    }
+
+    fn description(&self) -> &str {
+        "Run code in a sandbox"
+    }
+
+    fn usage(&self) -> &str {
+        "run [language] ```code```"
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["exec", "code"]
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::EMBED_LINKS
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let block = match extract_code(&ctx.msg.content) {
+            Some(block) => block,
+            None => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!("Put your code in a code block: `{}`", self.usage()),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+        // The language can be given before the block or as its tag
+        let language = ctx
+            .args
+            .first()
+            .filter(|arg| !arg.starts_with("```"))
+            .map(|arg| arg.to_lowercase())
+            .or(block.language);
+        let language = match language {
+            Some(language) => language,
+            None => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    "Which language is that? Tag the code block, like ```` ```py ````, or use `run py`.",
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+        let runtime = match self.piston.runtime(&language).await? {
+            Some(runtime) => runtime,
+            None => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!("I can't run `{}` code.", truncate(&language, 32)),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        if let Err(wait) = self.limiter.try_start(ctx.msg.author.id, Instant::now()) {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!(
+                    "You're running code too often. Try again in {}.",
+                    format_compact(wait)
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let title = format!("▶️ {} {}", runtime.language, runtime.version);
+        let policy = &mention_policy(ctx.ctx).await;
+        let mut reply = rest::call(ctx.ctx, "send_message", || {
+            ctx.msg.channel_id.send_message(&ctx.ctx.http, |m| {
+                m.reference_message(ctx.msg)
+                    .allowed_mentions(|am| apply_mentions(am, policy, &[]))
+                    .embed(|e| {
+                        e.title(&title)
+                            .description("Running...")
+                            .color(DEFAULT_COLOR)
+                    })
+            })
+        })
+        .await?;
+
+        let execution = match self.piston.execute(&runtime, &block.code).await {
+            Ok(execution) => execution,
+            Err(error) => {
+                reply
+                    .edit(&ctx.ctx.http, |m| {
+                        m.embed(|e| {
+                            e.title(&title)
+                                .description(format!(
+                                    "The code couldn't be run: {}",
+                                    error.user_message()
+                                ))
+                                .color(ERROR_COLOR)
+                        })
+                    })
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let mut embed = CreateEmbed::default();
+        embed.title(&title);
+        let mut full_output = String::new();
+        let mut truncated = false;
+        let compile_failed = match &execution.compile {
+            Some(compile) if !compile.succeeded() || !compile.output.trim().is_empty() => {
+                embed.field(
+                    format!("Compiler ({})", describe_exit(compile)),
+                    output_field(&compile.output),
+                    false,
+                );
+                full_output.push_str(&format!("--- compiler ---\n{}\n", compile.output));
+                truncated |= compile.output.chars().count() > FIELD_OUTPUT_LIMIT;
+                !compile.succeeded()
+            }
+            _ => false,
+        };
+        if !compile_failed {
+            embed.field(
+                format!("Output ({})", describe_exit(&execution.run)),
+                output_field(&execution.run.output),
+                false,
+            );
+            full_output.push_str(&format!("--- output ---\n{}\n", execution.run.output));
+            truncated |= execution.run.output.chars().count() > FIELD_OUTPUT_LIMIT;
+        }
+        let succeeded = !compile_failed && execution.run.succeeded();
+        embed.color(if succeeded {
+            SUCCESS_COLOR
+        } else {
+            ERROR_COLOR
+        });
+
+        // Upload the whole output when some of it didn't fit
+        if truncated {
+            let config = paste_config(ctx.ctx).await;
+            match paste(&config, "output.txt", &full_output).await {
+                Ok(url) => {
+                    embed.description(format!("The output was cut short. [Full output]({})", url));
+                }
+                Err(e) => {
+                    warn!("Failed to paste run output: {}", e);
+                    embed.description("The output was cut short.");
+                }
+            }
+        }
+
+        reply.edit(&ctx.ctx.http, |m| m.set_embed(embed)).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_runs_per_user() {
+        let limiter = RunLimiter::new(2, Duration::from_secs(60));
+        let (alice, bob) = (UserId(1), UserId(2));
+        let start = Instant::now();
+        assert!(limiter.try_start(alice, start).is_ok());
+        assert!(limiter.try_start(alice, start).is_ok());
+        assert_eq!(
+            limiter.try_start(alice, start + Duration::from_secs(10)),
+            Err(Duration::from_secs(50))
+        );
+        assert!(limiter.try_start(bob, start).is_ok());
+        assert!(limiter
+            .try_start(alice, start + Duration::from_secs(60))
+            .is_ok());
+    }
+
+    #[test]
+    fn formats_output() {
+        assert_eq!(output_field(""), "```\n(no output)\n```");
+        assert!(!output_field("a```b").contains("a```b"));
+        let stage = Stage {
+            output: String::new(),
+            code: None,
+            signal: Some("SIGKILL".to_string()),
+        };
+        assert_eq!(describe_exit(&stage), "killed by SIGKILL");
+    }
+}
@@ -0,0 +1,102 @@
+//! Skip command for moving on to the next queued track.
+
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::guild_config::{GuildConfigKey, GuildConfigs};
+use crate::models::music::votes_needed;
+use crate::storage::JsonStore;
+use crate::utils::helpers::{send_error, send_info, send_success};
+use crate::utils::player::{Player, PlayerKey};
+use crate::utils::voice::{self, voice_channel_of};
+
+/// Skips the track playing in the author's voice channel. With others
+/// listening, members without the DJ role or Manage Server vote to skip.
+pub struct SkipCommand {
+    player: Arc<Player>,
+    store: Arc<JsonStore<GuildConfigs>>,
+}
+
+impl SkipCommand {
+    /// Create the command with the player and the guild config store.
+    pub fn new(
+        (Inject(player), Inject(store)): (Inject<PlayerKey>, Inject<GuildConfigKey>),
+    ) -> Self {
+        Self { player, store }
+    }
+}
+
+#[async_trait]
+impl Command for SkipCommand {
+    fn name(&self) -> &str {
+        "skip"
+    }
+
+    fn description(&self) -> &str {
+        "Skip the track that's playing, or vote to skip it"
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Tracks can only be skipped in a server")?;
+        let channel_id = match self.player.channel(guild_id) {
+            Some(channel_id) => channel_id,
+            None => {
+                send_error(ctx.ctx, ctx.msg, "Nothing is playing.").await?;
+                return Ok(());
+            }
+        };
+        if voice_channel_of(ctx.ctx, guild_id, ctx.msg.author.id) != Some(channel_id) {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!("Join <#{}> to skip tracks.", channel_id),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let settings = self.store.read().await.get(guild_id).music;
+        let listeners = voice::listeners(ctx.ctx, guild_id, channel_id);
+        if !voice::can_control(ctx.ctx, ctx.msg, &settings, listeners.len()).await {
+            let needed = votes_needed(listeners.len());
+            let (track, votes) =
+                match self
+                    .player
+                    .vote_skip(guild_id, ctx.msg.author.id, &listeners)
+                {
+                    Some(voted) => voted,
+                    None => {
+                        send_error(ctx.ctx, ctx.msg, "Nothing is playing.").await?;
+                        return Ok(());
+                    }
+                };
+            if votes < needed {
+                send_info(
+                    ctx.ctx,
+                    ctx.msg,
+                    "⏭️ Vote to skip",
+                    format!("{}/{} votes to skip **{}**.", votes, needed, track.title),
+                )
+                .await?;
+                return Ok(());
+            }
+        }
+
+        match self.player.skip(guild_id) {
+            Some(track) => {
+                send_success(ctx.ctx, ctx.msg, format!("Skipped **{}**.", track.title)).await?
+            }
+            None => send_error(ctx.ctx, ctx.msg, "Nothing is playing.").await?,
+        };
+        Ok(())
+    }
+}
@@ -0,0 +1,389 @@
+//! Sound command for the server's soundboard clips.
+
+use async_trait::async_trait;
+use serenity::model::id::GuildId;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::params::{usage_of, Param, ParamKind};
+use crate::framework::state::Inject;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::sounds::{check_name, AddError, Sound, Soundboard, SoundsKey};
+use crate::utils::audio::{self, EXTENSIONS};
+use crate::utils::constants::PAGINATION_MAX_ITEMS;
+use crate::utils::helpers::{
+    author_permissions, send_error, send_info, send_success, BotConfigKey,
+};
+use crate::utils::limits::{Limit, Limits, LimitsKey};
+use crate::utils::player::{Player, PlayerKey};
+use crate::utils::voice::voice_channel_of;
+
+/// Plays, lists and manages the server's soundboard clips. Anyone can play them;
+/// adding and removing them needs Manage Server.
+pub struct SoundCommand {
+    sounds: Arc<Soundboard>,
+    limits: Arc<Limits>,
+    player: Arc<Player>,
+}
+
+impl SoundCommand {
+    /// Create the command with the soundboard, the limits service and the player.
+    pub fn new(
+        (Inject(sounds), Inject(limits), Inject(player)): (
+            Inject<SoundsKey>,
+            Inject<LimitsKey>,
+            Inject<PlayerKey>,
+        ),
+    ) -> Self {
+        Self {
+            sounds,
+            limits,
+            player,
+        }
+    }
+
+    /// Whether the author can change the soundboard, telling them if not.
+    async fn can_manage(&self, ctx: &CommandContext<'_>) -> CommandResult<bool> {
+        let allowed = author_permissions(ctx.ctx, ctx.msg)
+            .await
+            .is_some_and(|permissions| permissions.manage_guild());
+        if !allowed {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                "You need Manage Server to change the soundboard.",
+            )
+            .await?;
+        }
+        Ok(allowed)
+    }
+
+    /// Store the attached clip under a name.
+    async fn add(&self, ctx: &CommandContext<'_>, guild_id: GuildId, name: &str) -> CommandResult {
+        let name = match check_name(name) {
+            Ok(name) => name,
+            Err(e) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+        };
+        let attachment = match ctx.msg.attachments.first() {
+            Some(attachment) => attachment,
+            None => {
+                send_error(ctx.ctx, ctx.msg, "Attach the clip to your message.").await?;
+                return Ok(());
+            }
+        };
+        let extension = attachment
+            .filename
+            .rsplit_once('.')
+            .map(|(_, extension)| extension.to_lowercase())
+            .filter(|extension| EXTENSIONS.contains(&extension.as_str()));
+        let extension = match extension {
+            Some(extension) => extension,
+            None => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!("Clips have to be {} files.", EXTENSIONS.join(", ")),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let config = {
+            let data = ctx.ctx.data.read().await;
+            data.get::<BotConfigKey>()
+                .map(|config| config.music.clone())
+        }
+        .unwrap_or_default();
+        if attachment.size > config.max_sound_size {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!("Clips can be up to {} KB.", config.max_sound_size / 1024),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let data = attachment.download().await?;
+        let probed = {
+            let (data, extension) = (data.clone(), extension.clone());
+            tokio::task::spawn_blocking(move || audio::probe(data, &extension)).await
+        };
+        let length = match probed {
+            Ok(Ok(length)) => length,
+            Ok(Err(e)) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(format!("Couldn't read the clip: {}", e).into()),
+        };
+        if length > Duration::from_secs(config.max_sound_length) {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!(
+                    "Clips can be up to {} seconds long, that one is {:.1}.",
+                    config.max_sound_length,
+                    length.as_secs_f64()
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let max = self.limits.for_guild(guild_id, Limit::Sounds).await;
+        let sound = Sound {
+            file: String::new(),
+            uploaded_by: ctx.msg.author.id.0,
+            length_ms: length.as_millis() as u64,
+            size: data.len() as u64,
+        };
+        match self
+            .sounds
+            .add(guild_id, &name, &extension, &data, sound, max)
+            .await
+        {
+            Ok(()) => {}
+            Err(AddError::Full(max)) => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!(
+                        "This server has {} clips, the most it can have. Remove one first.",
+                        max
+                    ),
+                )
+                .await?;
+                return Ok(());
+            }
+            Err(AddError::Io(e)) => return Err(e.into()),
+        }
+
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action: format!("Add soundboard clip `{}`", name),
+                reason: None,
+            },
+        )
+        .await;
+        send_success(
+            ctx.ctx,
+            ctx.msg,
+            format!(
+                "Added `{}` ({:.1} seconds). Play it with `sound play {}`.",
+                name,
+                length.as_secs_f64(),
+                name
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Delete a clip.
+    async fn remove(
+        &self,
+        ctx: &CommandContext<'_>,
+        guild_id: GuildId,
+        name: &str,
+    ) -> CommandResult {
+        let name = name.to_lowercase();
+        if !self.sounds.remove(guild_id, &name).await? {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!("There's no clip named `{}`.", name),
+            )
+            .await?;
+            return Ok(());
+        }
+        audit::record(
+            ctx.ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.msg.author.id),
+                source: AuditSource::Command,
+                action: format!("Remove soundboard clip `{}`", name),
+                reason: None,
+            },
+        )
+        .await;
+        send_success(ctx.ctx, ctx.msg, format!("Removed `{}`.", name)).await?;
+        Ok(())
+    }
+
+    /// Play a clip in the author's voice channel.
+    async fn play(&self, ctx: &CommandContext<'_>, guild_id: GuildId, name: &str) -> CommandResult {
+        let name = name.to_lowercase();
+        let path = match self.sounds.get(guild_id, &name).await {
+            Some((_, path)) => path,
+            None => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!("There's no clip named `{}`.", name),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+        let channel_id = match voice_channel_of(ctx.ctx, guild_id, ctx.msg.author.id) {
+            Some(channel_id) => channel_id,
+            None => {
+                send_error(ctx.ctx, ctx.msg, "Join a voice channel first.").await?;
+                return Ok(());
+            }
+        };
+        if let Err(e) = self
+            .player
+            .play_clip(ctx.ctx, guild_id, channel_id, ctx.msg.channel_id, &path)
+            .await
+        {
+            send_error(ctx.ctx, ctx.msg, e).await?;
+            return Ok(());
+        }
+        send_success(
+            ctx.ctx,
+            ctx.msg,
+            format!("Playing `{}` in <#{}>.", name, channel_id),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// List a page of clips.
+    async fn list(
+        &self,
+        ctx: &CommandContext<'_>,
+        guild_id: GuildId,
+        page: usize,
+    ) -> CommandResult {
+        let sounds = self.sounds.list(guild_id).await;
+        let max = self.limits.for_guild(guild_id, Limit::Sounds).await;
+        if sounds.is_empty() {
+            send_info(
+                ctx.ctx,
+                ctx.msg,
+                "🔊 Soundboard",
+                "There are no clips yet. Add one with `sound add <name>` and an attached file.",
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let pages = sounds.len().div_ceil(PAGINATION_MAX_ITEMS);
+        if page == 0 || page > pages {
+            send_error(ctx.ctx, ctx.msg, format!("Page {} doesn't exist.", page)).await?;
+            return Ok(());
+        }
+        let lines: Vec<String> = sounds
+            .iter()
+            .skip((page - 1) * PAGINATION_MAX_ITEMS)
+            .take(PAGINATION_MAX_ITEMS)
+            .map(|(name, sound)| {
+                format!(
+                    "`{}` ({:.1}s) by <@{}>",
+                    name,
+                    sound.length_ms as f64 / 1000.0,
+                    sound.uploaded_by
+                )
+            })
+            .collect();
+        send_info(
+            ctx.ctx,
+            ctx.msg,
+            format!(
+                "🔊 Soundboard (page {}/{}, {}/{} clips)",
+                page,
+                pages,
+                sounds.len(),
+                max
+            ),
+            lines.join("\n"),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Command for SoundCommand {
+    fn name(&self) -> &str {
+        "sound"
+    }
+
+    fn description(&self) -> &str {
+        "Play and manage the server's soundboard clips"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            Param::optional(
+                "action",
+                ParamKind::Word,
+                "list, play, add or remove, or a sound to play",
+            ),
+            Param::optional(
+                "name",
+                ParamKind::Word,
+                "The sound, or which page of the list to show",
+            ),
+        ]
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::CONNECT | Permissions::SPEAK
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("The soundboard can only be used in a server")?;
+        let action = ctx.args.first().map(|arg| arg.to_lowercase());
+        let name = ctx.args.get(1);
+
+        match (action.as_deref(), name) {
+            (None | Some("list"), _) => {
+                let page = name.and_then(|page| page.parse().ok()).unwrap_or(1);
+                self.list(&ctx, guild_id, page).await
+            }
+            (Some("play"), Some(name)) => self.play(&ctx, guild_id, name).await,
+            (Some("add"), Some(name)) => {
+                if self.can_manage(&ctx).await? {
+                    self.add(&ctx, guild_id, name).await?;
+                }
+                Ok(())
+            }
+            (Some("remove" | "delete"), Some(name)) => {
+                if self.can_manage(&ctx).await? {
+                    self.remove(&ctx, guild_id, name).await?;
+                }
+                Ok(())
+            }
+            // A bare clip name plays it
+            (Some(_), None) => {
+                let name = ctx.args[0].clone();
+                self.play(&ctx, guild_id, &name).await
+            }
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", usage_of(self))).await?;
+                Ok(())
+            }
+        }
+    }
+}
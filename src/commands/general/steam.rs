@@ -0,0 +1,267 @@
+//! Steam, gameinfo and gameprice commands for looking things up on Steam.
+
+use async_trait::async_trait;
+use serenity::builder::CreateEmbed;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::params::{Param, ParamKind};
+use crate::framework::state::Inject;
+use crate::utils::constants::DEFAULT_COLOR;
+use crate::utils::helpers::{apply_mentions, mention_policy, send_error, truncate};
+use crate::utils::rest;
+use crate::utils::steam::{
+    choices_menu, game_embed, parse_profile, price_embed, profile_embed, App, SearchResult, Steam,
+    SteamKey,
+};
+
+/// Send a lookup result.
+async fn send_embed(ctx: &CommandContext<'_>, embed: CreateEmbed) -> CommandResult {
+    let policy = &mention_policy(ctx.ctx).await;
+    rest::call(ctx.ctx, "send_message", || {
+        let embed = embed.clone();
+        ctx.msg.channel_id.send_message(&ctx.ctx.http, move |m| {
+            m.allowed_mentions(|am| apply_mentions(am, policy, &[]))
+                .set_embed(embed)
+        })
+    })
+    .await?;
+    Ok(())
+}
+
+/// The result to show straight away: the only one, or one whose name matches
+/// the title exactly.
+fn pick(title: &str, results: &[SearchResult]) -> Option<u64> {
+    match results {
+        [only] => Some(only.id),
+        _ => results
+            .iter()
+            .find(|result| result.name.eq_ignore_ascii_case(title))
+            .map(|result| result.id),
+    }
+}
+
+/// Find a game by title and show it, or offer a menu when several match.
+async fn lookup_game(
+    ctx: &CommandContext<'_>,
+    steam: &Steam,
+    kind: &str,
+    render: fn(&App) -> CreateEmbed,
+) -> CommandResult {
+    let title = ctx.args.join(" ");
+
+    let results = steam.search(&title).await?;
+    if results.is_empty() {
+        send_error(
+            ctx.ctx,
+            ctx.msg,
+            format!("No game on Steam matches `{}`.", truncate(&title, 64)),
+        )
+        .await?;
+        return Ok(());
+    }
+    if let Some(id) = pick(&title, &results) {
+        return match steam.app(id).await? {
+            Some(app) => send_embed(ctx, render(&app)).await,
+            None => {
+                send_error(ctx.ctx, ctx.msg, "Steam couldn't find that game's page.").await?;
+                Ok(())
+            }
+        };
+    }
+
+    let menu_kind = if kind == "gameprice" { "price" } else { "game" };
+    let policy = &mention_policy(ctx.ctx).await;
+    let description = format!(
+        "{} games match `{}`. Pick one.",
+        results.len(),
+        truncate(&title, 64)
+    );
+    let (description, results) = (&description, &results);
+    rest::call(ctx.ctx, "send_message", || {
+        ctx.msg.channel_id.send_message(&ctx.ctx.http, move |m| {
+            m.allowed_mentions(|am| apply_mentions(am, policy, &[]))
+                .embed(|e| {
+                    e.title("🔎 Which game?")
+                        .description(description)
+                        .color(DEFAULT_COLOR)
+                })
+                .components(|c| {
+                    choices_menu(c, menu_kind, ctx.msg.author.id, results);
+                    c
+                })
+        })
+    })
+    .await?;
+    Ok(())
+}
+
+/// Shows a Steam Community profile.
+pub struct SteamCommand {
+    steam: Arc<Steam>,
+}
+
+impl SteamCommand {
+    /// Create the command with the Steam client.
+    pub fn new(Inject(steam): Inject<SteamKey>) -> Self {
+        Self { steam }
+    }
+}
+
+#[async_trait]
+impl Command for SteamCommand {
+    fn name(&self) -> &str {
+        "steam"
+    }
+
+    fn description(&self) -> &str {
+        "Show a Steam profile"
+    }
+
+    fn usage(&self) -> &str {
+        "steam <profile link, custom URL name or SteamID64>"
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::EMBED_LINKS
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        if !self.steam.has_api_key() {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                "Steam profiles aren't set up. The bot owner needs to set a Steam Web API key.",
+            )
+            .await?;
+            return Ok(());
+        }
+        let profile = match ctx.args.first().and_then(|arg| parse_profile(arg)) {
+            Some(profile) => profile,
+            None => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", self.usage())).await?;
+                return Ok(());
+            }
+        };
+
+        match self.steam.player(&profile).await? {
+            Some(player) => send_embed(&ctx, profile_embed(&player)).await,
+            None => {
+                send_error(ctx.ctx, ctx.msg, "I couldn't find that Steam profile.").await?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Shows a game's Steam store page.
+pub struct GameInfoCommand {
+    steam: Arc<Steam>,
+}
+
+impl GameInfoCommand {
+    /// Create the command with the Steam client.
+    pub fn new(Inject(steam): Inject<SteamKey>) -> Self {
+        Self { steam }
+    }
+}
+
+#[async_trait]
+impl Command for GameInfoCommand {
+    fn name(&self) -> &str {
+        "gameinfo"
+    }
+
+    fn description(&self) -> &str {
+        "Show a game from the Steam store"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![Param::required(
+            "title",
+            ParamKind::Text,
+            "The game to look up",
+        )]
+    }
+
+    fn wizard(&self) -> bool {
+        true
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["game"]
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::EMBED_LINKS
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        lookup_game(&ctx, &self.steam, "gameinfo", game_embed).await
+    }
+}
+
+/// Shows a game's price on the Steam store.
+pub struct GamePriceCommand {
+    steam: Arc<Steam>,
+}
+
+impl GamePriceCommand {
+    /// Create the command with the Steam client.
+    pub fn new(Inject(steam): Inject<SteamKey>) -> Self {
+        Self { steam }
+    }
+}
+
+#[async_trait]
+impl Command for GamePriceCommand {
+    fn name(&self) -> &str {
+        "gameprice"
+    }
+
+    fn description(&self) -> &str {
+        "Show a game's price on Steam"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![Param::required(
+            "title",
+            ParamKind::Text,
+            "The game to look up",
+        )]
+    }
+
+    fn wizard(&self) -> bool {
+        true
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["steamprice"]
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::EMBED_LINKS
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        lookup_game(&ctx, &self.steam, "gameprice", price_embed).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_single_or_exact_matches() {
+        let result = |id: u64, name: &str| SearchResult {
+            id,
+            name: name.to_string(),
+        };
+        assert_eq!(pick("portal", &[result(1, "Portal 2")]), Some(1));
+        let results = [result(1, "Portal 2"), result(2, "Portal")];
+        assert_eq!(pick("portal", &results), Some(2));
+        assert_eq!(pick("port", &results), None);
+    }
+}
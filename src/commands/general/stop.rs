@@ -0,0 +1,88 @@
+//! Stop command for ending playback and leaving voice.
+
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::guild_config::{GuildConfigKey, GuildConfigs};
+use crate::storage::JsonStore;
+use crate::utils::helpers::{send_error, send_success};
+use crate::utils::player::{Player, PlayerKey};
+use crate::utils::voice::{self, voice_channel_of};
+
+/// Stops playback, empties the queue and leaves the voice channel, or goes back
+/// to the 24/7 channel. With others listening, this needs the DJ role or Manage
+/// Server.
+pub struct StopCommand {
+    player: Arc<Player>,
+    store: Arc<JsonStore<GuildConfigs>>,
+}
+
+impl StopCommand {
+    /// Create the command with the player and the guild config store.
+    pub fn new(
+        (Inject(player), Inject(store)): (Inject<PlayerKey>, Inject<GuildConfigKey>),
+    ) -> Self {
+        Self { player, store }
+    }
+}
+
+#[async_trait]
+impl Command for StopCommand {
+    fn name(&self) -> &str {
+        "stop"
+    }
+
+    fn description(&self) -> &str {
+        "Stop playing, clear the queue and leave the voice channel"
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["leave"]
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Playback can only be stopped in a server")?;
+        let channel_id = match self.player.channel(guild_id) {
+            Some(channel_id) => channel_id,
+            None => {
+                send_error(ctx.ctx, ctx.msg, "I'm not in a voice channel.").await?;
+                return Ok(());
+            }
+        };
+        if voice_channel_of(ctx.ctx, guild_id, ctx.msg.author.id) != Some(channel_id) {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!("Join <#{}> to stop playback.", channel_id),
+            )
+            .await?;
+            return Ok(());
+        }
+        let settings = self.store.read().await.get(guild_id).music;
+        let listeners = voice::listeners(ctx.ctx, guild_id, channel_id).len();
+        if !voice::can_control(ctx.ctx, ctx.msg, &settings, listeners).await {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                "While others are listening, you need the DJ role or Manage Server to stop playback.",
+            )
+            .await?;
+            return Ok(());
+        }
+        let reply = match self.player.stop(guild_id).await {
+            Some(home) => format!("Stopped. I'm staying in <#{}>, the 24/7 channel.", home),
+            None => format!("Stopped and left <#{}>.", channel_id),
+        };
+        send_success(ctx.ctx, ctx.msg, reply).await?;
+        Ok(())
+    }
+}
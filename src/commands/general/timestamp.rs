@@ -0,0 +1,87 @@
+//! Timestamp command for building Discord's `<t:...>` timestamp syntax.
+
+use async_trait::async_trait;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::utils::constants::DEFAULT_COLOR;
+use crate::utils::duration::{timestamp, TimestampStyle};
+use crate::utils::helpers::{apply_mentions, mention_policy, raw_args, send_error, unix_timestamp};
+use crate::utils::rest;
+use crate::utils::timezones::parse_when;
+
+const USAGE: &str = "timestamp [now | in <duration> | <time> [zone] | <YYYY-MM-DD HH:MM> [zone]]";
+
+/// Shows every Discord timestamp style for a point in time, with a menu to copy one.
+pub struct TimestampCommand;
+
+#[async_trait]
+impl Command for TimestampCommand {
+    fn name(&self) -> &str {
+        "timestamp"
+    }
+
+    fn description(&self) -> &str {
+        "Build a timestamp that shows in everyone's own time zone"
+    }
+
+    fn usage(&self) -> &str {
+        USAGE
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["ts", "time"]
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let input = raw_args(&ctx.msg.content, &ctx.args, 0).trim();
+        let input = if input.is_empty() { "now" } else { input };
+        let unix = match parse_when(input, unix_timestamp()) {
+            Ok(unix) => unix,
+            Err(e) => {
+                send_error(ctx.ctx, ctx.msg, format!("{}\nUsage: `{}`", e, USAGE)).await?;
+                return Ok(());
+            }
+        };
+
+        let lines: Vec<String> = TimestampStyle::ALL
+            .iter()
+            .map(|style| {
+                let tag = timestamp(unix, *style);
+                format!("`{}` {}", tag, tag)
+            })
+            .collect();
+        let policy = &mention_policy(ctx.ctx).await;
+        let channel_id = ctx.msg.channel_id;
+        rest::call(ctx.ctx, "send_message", || {
+            channel_id.send_message(&ctx.ctx.http, |m| {
+                m.allowed_mentions(|am| apply_mentions(am, policy, &[]))
+                    .embed(|e| {
+                        e.title("🕒 Timestamp")
+                            .description(lines.join("\n"))
+                            .color(DEFAULT_COLOR)
+                            .footer(|f| f.text("Pick a style below to get it ready to copy"))
+                    })
+                    .components(|c| {
+                        c.create_action_row(|row| {
+                            row.create_select_menu(|menu| {
+                                menu.custom_id(format!("timestamp:{}", unix))
+                                    .placeholder("Copy a style")
+                                    .options(|options| {
+                                        for style in TimestampStyle::ALL {
+                                            options.create_option(|o| {
+                                                o.label(style.name())
+                                                    .description(timestamp(unix, style).to_string())
+                                                    .value(style.flag())
+                                            });
+                                        }
+                                        options
+                                    })
+                            })
+                        })
+                    })
+            })
+        })
+        .await?;
+        Ok(())
+    }
+}
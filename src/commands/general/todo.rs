@@ -0,0 +1,343 @@
+//! Todo command for personal and shared per-channel task lists.
+
+use async_trait::async_trait;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::todos::{TodoData, TodoKey, TodoOwner, MAX_ITEMS};
+use crate::storage::JsonStore;
+use crate::utils::constants::DEFAULT_COLOR;
+use crate::utils::duration::{self, format_compact, relative};
+use crate::utils::helpers::{
+    apply_mentions, mention_policy, permissions_in, send_error, send_info, send_success, truncate,
+    unix_timestamp,
+};
+use crate::utils::rest;
+
+/// Longest item text.
+const MAX_TEXT_LENGTH: usize = 200;
+
+/// Furthest a due date can be.
+const MAX_DUE: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// Most items offered in the done menu, Discord's limit for a select menu.
+const MAX_MENU_ITEMS: usize = 25;
+
+const USAGE: &str =
+    "todo [channel] [list | add <text> [in <duration>] | done [ids...] | clear [all]]";
+
+/// Split `todo add` arguments into the text and an optional due date given as a
+/// trailing `in <duration>`.
+fn split_due(args: &[String]) -> Result<(String, Option<Duration>), String> {
+    match args {
+        [text @ .., keyword, due] if !text.is_empty() && keyword.eq_ignore_ascii_case("in") => {
+            let due = duration::parse(due).map_err(|e| e.to_string())?;
+            if due > MAX_DUE {
+                return Err("Due dates can be at most a year away.".to_string());
+            }
+            Ok((text.join(" "), Some(due)))
+        }
+        _ => Ok((args.join(" "), None)),
+    }
+}
+
+/// Manages todo lists.
+pub struct TodoCommand {
+    store: Arc<JsonStore<TodoData>>,
+}
+
+impl TodoCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<TodoKey>) -> Self {
+        Self { store }
+    }
+
+    /// Show a list.
+    async fn list(&self, ctx: &CommandContext<'_>, owner: TodoOwner) -> CommandResult {
+        let lines: Vec<String> = {
+            let data = self.store.read().await;
+            let items = data.list(owner).map(|list| list.items.as_slice());
+            let (done, open): (Vec<_>, Vec<_>) =
+                items.unwrap_or_default().iter().partition(|item| item.done);
+            open.iter()
+                .chain(done.iter())
+                .map(|item| item.line())
+                .collect()
+        };
+        let title = match owner {
+            TodoOwner::User(_) => "📝 Your todo list".to_string(),
+            TodoOwner::Channel(id) => format!("📝 Todo list for <#{}>", id),
+        };
+        let body = if lines.is_empty() {
+            "Nothing to do. Add something with `todo add <text>`.".to_string()
+        } else {
+            truncate(&lines.join("\n"), 4000)
+        };
+        send_info(ctx.ctx, ctx.msg, title, body).await?;
+        Ok(())
+    }
+
+    /// Add an item.
+    async fn add(
+        &self,
+        ctx: &CommandContext<'_>,
+        owner: TodoOwner,
+        args: &[String],
+    ) -> CommandResult {
+        let (text, due) = match split_due(args) {
+            Ok(parts) => parts,
+            Err(e) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+        };
+        let text = text.trim().to_string();
+        if text.is_empty() {
+            send_error(ctx.ctx, ctx.msg, "Usage: `todo add <text> [in <duration>]`").await?;
+            return Ok(());
+        }
+        if text.chars().count() > MAX_TEXT_LENGTH {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!("Items can be at most {} characters long.", MAX_TEXT_LENGTH),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let now = unix_timestamp();
+        let due_at = due.map(|due| now + due.as_secs());
+        let id = self
+            .store
+            .update(|data| {
+                data.list_mut(owner)
+                    .add(text, ctx.msg.author.id.0, now, due_at)
+            })
+            .await?;
+        let reply = match (id, due, due_at) {
+            (None, _, _) => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!(
+                        "The list is full at {} items. Clear finished ones with `todo clear`.",
+                        MAX_ITEMS
+                    ),
+                )
+                .await?;
+                return Ok(());
+            }
+            (Some(id), Some(due), Some(due_at)) => format!(
+                "Added #{}, due in {} ({}).",
+                id,
+                format_compact(due),
+                relative(due_at)
+            ),
+            (Some(id), _, _) => format!("Added #{}.", id),
+        };
+        send_success(ctx.ctx, ctx.msg, reply).await?;
+        Ok(())
+    }
+
+    /// Mark items done, or offer a menu of open items when no IDs are given.
+    async fn done(
+        &self,
+        ctx: &CommandContext<'_>,
+        owner: TodoOwner,
+        args: &[String],
+    ) -> CommandResult {
+        if !args.is_empty() {
+            let ids: Option<Vec<u64>> = args
+                .iter()
+                .map(|arg| arg.trim_start_matches('#').parse().ok())
+                .collect();
+            let ids = match ids {
+                Some(ids) => ids,
+                None => {
+                    send_error(ctx.ctx, ctx.msg, "Usage: `todo done [ids...]`").await?;
+                    return Ok(());
+                }
+            };
+            let done = self
+                .store
+                .update(|data| data.list_mut(owner).complete(&ids))
+                .await?;
+            if done.is_empty() {
+                send_error(ctx.ctx, ctx.msg, "None of those items are open.").await?;
+            } else {
+                let ids: Vec<String> = done.iter().map(|item| format!("#{}", item.id)).collect();
+                send_success(ctx.ctx, ctx.msg, format!("Marked {} done.", ids.join(", "))).await?;
+            }
+            return Ok(());
+        }
+
+        let items: Vec<(u64, String)> = self
+            .store
+            .read()
+            .await
+            .list(owner)
+            .map(|list| {
+                list.open()
+                    .take(MAX_MENU_ITEMS)
+                    .map(|item| (item.id, item.text.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        if items.is_empty() {
+            send_info(ctx.ctx, ctx.msg, "📝 Todo", "Nothing left to do.").await?;
+            return Ok(());
+        }
+
+        let custom_id = format!("todo:{}:{}", owner, ctx.msg.author.id);
+        let policy = &mention_policy(ctx.ctx).await;
+        let (custom_id, items) = (&custom_id, &items);
+        rest::call(ctx.ctx, "send_message", || {
+            ctx.msg.channel_id.send_message(&ctx.ctx.http, move |m| {
+                m.allowed_mentions(|am| apply_mentions(am, policy, &[]))
+                    .embed(|e| {
+                        e.title("📝 Mark items done")
+                            .description("Pick the items you've finished.")
+                            .color(DEFAULT_COLOR)
+                    })
+                    .components(|c| {
+                        c.create_action_row(|row| {
+                            row.create_select_menu(|menu| {
+                                menu.custom_id(custom_id)
+                                    .placeholder("Finished items")
+                                    .min_values(1)
+                                    .max_values(items.len() as u64)
+                                    .options(|options| {
+                                        for (id, text) in items {
+                                            options.create_option(|o| {
+                                                o.label(truncate(&format!("#{} {}", id, text), 100))
+                                                    .value(id)
+                                            });
+                                        }
+                                        options
+                                    })
+                            })
+                        })
+                    })
+            })
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Remove finished items, or all of them.
+    async fn clear(
+        &self,
+        ctx: &CommandContext<'_>,
+        owner: TodoOwner,
+        args: &[String],
+    ) -> CommandResult {
+        let all = match args.first().map(|arg| arg.to_lowercase()).as_deref() {
+            None => false,
+            Some("all") => true,
+            Some(_) => {
+                send_error(ctx.ctx, ctx.msg, "Usage: `todo clear [all]`").await?;
+                return Ok(());
+            }
+        };
+        if let (TodoOwner::Channel(channel_id), true) = (owner, all) {
+            let guild_id = ctx.msg.guild_id.ok_or("Shared lists are only in servers")?;
+            let permissions =
+                permissions_in(ctx.ctx, guild_id, channel_id.into(), ctx.msg.author.id).await;
+            if !permissions.is_some_and(|p| p.manage_messages()) {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    "You need Manage Messages to clear everything from a shared list.",
+                )
+                .await?;
+                return Ok(());
+            }
+        }
+
+        let removed = self
+            .store
+            .update(|data| data.list_mut(owner).clear(all))
+            .await?;
+        let reply = match (removed, all) {
+            (0, _) => "There was nothing to clear.".to_string(),
+            (removed, true) => format!("Cleared all {} items.", removed),
+            (removed, false) => format!("Cleared {} finished items.", removed),
+        };
+        send_success(ctx.ctx, ctx.msg, reply).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Command for TodoCommand {
+    fn name(&self) -> &str {
+        "todo"
+    }
+
+    fn description(&self) -> &str {
+        "Keep a personal todo list or a shared one for a channel"
+    }
+
+    fn usage(&self) -> &str {
+        USAGE
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["todos", "task", "tasks"]
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::EMBED_LINKS
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let mut args = ctx.args.as_slice();
+        let owner = match args.first().map(|arg| arg.to_lowercase()).as_deref() {
+            Some("channel") | Some("shared") => {
+                if ctx.msg.guild_id.is_none() {
+                    send_error(ctx.ctx, ctx.msg, "Shared lists are only in servers.").await?;
+                    return Ok(());
+                }
+                args = &args[1..];
+                TodoOwner::Channel(ctx.msg.channel_id.0)
+            }
+            _ => TodoOwner::User(ctx.msg.author.id.0),
+        };
+
+        let action = args.first().map(|arg| arg.to_lowercase());
+        let rest = args.get(1..).unwrap_or_default();
+        match action.as_deref() {
+            None | Some("list") => self.list(&ctx, owner).await,
+            Some("add") => self.add(&ctx, owner, rest).await,
+            Some("done") | Some("finish") => self.done(&ctx, owner, rest).await,
+            Some("clear") => self.clear(&ctx, owner, rest).await,
+            Some(_) => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", USAGE)).await?;
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_trailing_due_dates() {
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            split_due(&args(&["write", "docs", "in", "2h"])),
+            Ok(("write docs".to_string(), Some(Duration::from_secs(7200))))
+        );
+        assert_eq!(
+            split_due(&args(&["move", "in"])),
+            Ok(("move in".to_string(), None))
+        );
+        assert!(split_due(&args(&["ship", "in", "soon"])).is_err());
+    }
+}
@@ -0,0 +1,153 @@
+//! Urban command for looking up slang on Urban Dictionary.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serenity::builder::CreateEmbed;
+use serenity::model::permissions::Permissions;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::params::{Param, ParamKind};
+use crate::utils::constants::DEFAULT_COLOR;
+use crate::utils::helpers::{apply_mentions, mention_policy, send_error, truncate};
+use crate::utils::rest;
+
+/// Urban Dictionary's definition API.
+const API_URL: &str = "https://api.urbandictionary.com/v0/define";
+
+/// A response from the definition API.
+#[derive(Deserialize)]
+struct Definitions {
+    list: Vec<Definition>,
+}
+
+/// A single Urban Dictionary definition.
+#[derive(Deserialize)]
+struct Definition {
+    word: String,
+    definition: String,
+    #[serde(default)]
+    example: String,
+    permalink: String,
+    #[serde(default)]
+    thumbs_up: u64,
+    #[serde(default)]
+    thumbs_down: u64,
+}
+
+/// Remove the `[brackets]` Urban Dictionary puts around linked words.
+fn strip_links(text: &str) -> String {
+    text.replace(['[', ']'], "")
+}
+
+/// Looks up the top Urban Dictionary definition of a term.
+///
+/// Definitions are user-written and often explicit, so this only runs in
+/// age-restricted channels.
+pub struct UrbanCommand;
+
+#[async_trait]
+impl Command for UrbanCommand {
+    fn name(&self) -> &str {
+        "urban"
+    }
+
+    fn description(&self) -> &str {
+        "Look up a term on Urban Dictionary"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            Param::required("term", ParamKind::Text, "The word or phrase to look up")
+                .length(1, 100),
+        ]
+    }
+
+    fn wizard(&self) -> bool {
+        true
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["ud"]
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::EMBED_LINKS
+    }
+
+    fn nsfw_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let term = ctx.args.join(" ");
+
+        let response: Definitions = reqwest::Client::new()
+            .get(API_URL)
+            .query(&[("term", term.as_str())])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let definition = match response
+            .list
+            .into_iter()
+            .max_by_key(|definition| definition.thumbs_up)
+        {
+            Some(definition) => definition,
+            None => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!("Urban Dictionary has no definition for `{}`.", term),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let mut embed = CreateEmbed::default();
+        embed
+            .title(&definition.word)
+            .url(&definition.permalink)
+            .color(DEFAULT_COLOR)
+            .description(truncate(&strip_links(&definition.definition), 2048))
+            .footer(|f| {
+                f.text(format!(
+                    "👍 {} · 👎 {}",
+                    definition.thumbs_up, definition.thumbs_down
+                ))
+            });
+        if !definition.example.trim().is_empty() {
+            embed.field(
+                "Example",
+                truncate(&strip_links(&definition.example), 1024),
+                false,
+            );
+        }
+
+        let policy = &mention_policy(ctx.ctx).await;
+        rest::call(ctx.ctx, "send_message", || {
+            let embed = embed.clone();
+            ctx.msg.channel_id.send_message(&ctx.ctx.http, move |m| {
+                m.allowed_mentions(|am| apply_mentions(am, policy, &[]))
+                    .set_embed(embed)
+            })
+        })
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_linked_words() {
+        assert_eq!(
+            strip_links("A [cool] way to say [hello]"),
+            "A cool way to say hello"
+        );
+    }
+}
@@ -0,0 +1,101 @@
+//! Vote command for showing where to vote for the bot and the user's streak.
+
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::config::VotesConfig;
+use crate::models::votes::{VoteData, VoteSite, VotesKey};
+use crate::storage::JsonStore;
+use crate::utils::duration::{format_compact, relative};
+use crate::utils::helpers::{send_info, BotConfigKey};
+
+/// Shows the vote links and the author's voting streak.
+pub struct VoteCommand {
+    config: VotesConfig,
+    votes: Arc<JsonStore<VoteData>>,
+}
+
+impl VoteCommand {
+    /// Create the command with the vote settings and store.
+    pub fn new((Inject(config), Inject(votes)): (Inject<BotConfigKey>, Inject<VotesKey>)) -> Self {
+        Self {
+            config: config.votes,
+            votes,
+        }
+    }
+
+    /// The sites that accept votes, which are the ones with a webhook secret.
+    fn sites(&self) -> Vec<VoteSite> {
+        VoteSite::ALL
+            .into_iter()
+            .filter(|site| match site {
+                VoteSite::Topgg => !self.config.topgg_auth.is_empty(),
+                VoteSite::Dbl => !self.config.dbl_auth.is_empty(),
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Command for VoteCommand {
+    fn name(&self) -> &str {
+        "vote"
+    }
+
+    fn description(&self) -> &str {
+        "Vote for the bot and see your voting streak"
+    }
+
+    fn usage(&self) -> &str {
+        "vote"
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let sites = self.sites();
+        if sites.is_empty() {
+            send_info(
+                ctx.ctx,
+                ctx.msg,
+                "🗳️ Vote",
+                "Voting isn't set up for this bot.",
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let bot_id = ctx.ctx.cache.current_user_id();
+        let mut lines: Vec<String> = sites
+            .iter()
+            .map(|site| format!("[Vote on {}]({})", site, site.vote_url(bot_id)))
+            .collect();
+
+        if self.config.trial_duration > 0 {
+            lines.push(format!(
+                "Each vote gives you {} of **{}** premium.",
+                format_compact(Duration::from_secs(self.config.trial_duration)),
+                self.config.trial_tier
+            ));
+        }
+
+        let data = self.votes.read().await;
+        match data.voter(ctx.msg.author.id) {
+            None => lines.push("\nYou haven't voted yet.".to_string()),
+            Some(voter) => {
+                lines.push(format!(
+                    "\n🔥 Streak: **{}** (best {})\nTotal votes: **{}**",
+                    voter.streak, voter.best_streak, voter.total
+                ));
+                if let Some(last) = voter.last_vote() {
+                    lines.push(format!("Last vote: {}", relative(last)));
+                }
+            }
+        }
+        drop(data);
+
+        send_info(ctx.ctx, ctx.msg, "🗳️ Vote", lines.join("\n")).await?;
+        Ok(())
+    }
+}
@@ -1,6 +1,9 @@
 //! Command modules that implement various bot commands.
 
+pub mod admin;
 pub mod general;
+pub mod moderation;
+pub mod owner;
 
 use crate::framework::command_handler::CommandHandler;
 
@@ -9,7 +12,15 @@ pub fn register_commands(handler: &mut CommandHandler) {
     // Register general commands
     general::register_commands(handler);
 
+    // Register admin commands
+    admin::register_commands(handler);
+
+    // Register moderation commands
+    moderation::register_commands(handler);
+
+    // Register owner commands
+    owner::register_commands(handler);
+
     // You can add more command categories here as they are implemented
-    // admin::register_commands(handler);
     // fun::register_commands(handler);
 }
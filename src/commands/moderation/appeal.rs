@@ -0,0 +1,115 @@
+//! Appeal command for punished users to start an appeal.
+
+use async_trait::async_trait;
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::id::GuildId;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::moderation::{CaseKind, ModerationData, ModerationKey};
+use crate::storage::JsonStore;
+use crate::utils::constants::DEFAULT_COLOR;
+use crate::utils::helpers::{send_info, truncate};
+use crate::utils::rest;
+
+/// Lists the servers where the user can appeal a ban or timeout.
+///
+/// Meant to be used in DMs, since banned users can't reach the server.
+pub struct AppealCommand {
+    store: Arc<JsonStore<ModerationData>>,
+}
+
+impl AppealCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<ModerationKey>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Command for AppealCommand {
+    fn name(&self) -> &str {
+        "appeal"
+    }
+
+    fn description(&self) -> &str {
+        "Appeal a ban or timeout (use this in DMs with the bot)"
+    }
+
+    fn dm_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let user_id = ctx.msg.author.id;
+        let store = &self.store;
+
+        let punishments: Vec<(GuildId, CaseKind)> = {
+            let data = store.read().await;
+            data.guilds
+                .iter()
+                .filter(|(_, guild)| guild.pending_appeal(user_id).is_none())
+                .filter_map(|(guild_id, guild)| {
+                    guild
+                        .active_punishment(user_id)
+                        .map(|case| (GuildId(*guild_id), case.kind))
+                })
+                .take(25)
+                .collect()
+        };
+
+        if punishments.is_empty() {
+            send_info(
+                ctx.ctx,
+                ctx.msg,
+                "Appeals",
+                "You have no bans or timeouts that can be appealed.",
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let buttons: Vec<(String, String)> = punishments
+            .iter()
+            .map(|(guild_id, kind)| {
+                let name = guild_id
+                    .name(&ctx.ctx.cache)
+                    .unwrap_or_else(|| guild_id.to_string());
+                (
+                    format!("appeal:start:{}", guild_id),
+                    truncate(&format!("{} ({})", name, kind), 70),
+                )
+            })
+            .collect();
+
+        let channel_id = ctx.msg.channel_id;
+        rest::call(ctx.ctx, "send_message", || {
+            channel_id.send_message(&ctx.ctx.http, |m| {
+                m.embed(|e| {
+                    e.title("Appeals")
+                        .description("Choose the server whose punishment you want to appeal.")
+                        .color(DEFAULT_COLOR)
+                })
+                .components(|c| {
+                    for row in buttons.chunks(5) {
+                        c.create_action_row(|r| {
+                            for (custom_id, label) in row {
+                                r.create_button(|b| {
+                                    b.custom_id(custom_id)
+                                        .label(label)
+                                        .style(ButtonStyle::Primary)
+                                });
+                            }
+                            r
+                        });
+                    }
+                    c
+                })
+            })
+        })
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,22 @@
+//! Moderation commands for server staff.
+
+pub mod appeal;
+pub mod modmail;
+pub mod note;
+pub mod notes;
+pub mod report;
+pub mod snipe;
+pub mod watchlist;
+
+use crate::framework::command_handler::CommandHandler;
+
+/// Register all moderation commands with the command handler.
+pub fn register_commands(handler: &mut CommandHandler) {
+    handler.register_with_state(appeal::AppealCommand::new);
+    handler.register_with_state(modmail::ModmailCommand::new);
+    handler.register_with_state(note::NoteCommand::new);
+    handler.register_with_state(notes::NotesCommand::new);
+    handler.register_command(report::ReportCommand);
+    handler.register_with_state(snipe::SnipeCommand::new);
+    handler.register_with_state(watchlist::WatchlistCommand::new);
+}
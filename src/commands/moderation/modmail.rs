@@ -0,0 +1,176 @@
+//! Modmail command for closing conversations and blocking users.
+
+use async_trait::async_trait;
+use serenity::builder::CreateEmbed;
+use serenity::model::id::{ChannelId, UserId};
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::guild_config::guild_config;
+use crate::models::modmail::{ModmailData, ModmailKey};
+use crate::storage::JsonStore;
+use crate::utils::constants::{DEFAULT_COLOR, ERROR_COLOR};
+use crate::utils::dms::{dm_service, DmOutcome};
+use crate::utils::files::{send_file, OutgoingFile};
+use crate::utils::helpers::{parse_user, send_error, send_success};
+
+/// Manages modmail conversations from the staff side.
+pub struct ModmailCommand {
+    store: Arc<JsonStore<ModmailData>>,
+}
+
+impl ModmailCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<ModmailKey>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Command for ModmailCommand {
+    fn name(&self) -> &str {
+        "modmail"
+    }
+
+    fn description(&self) -> &str {
+        "Close modmail conversations and block or unblock users"
+    }
+
+    fn usage(&self) -> &str {
+        "modmail close [reason] | modmail block <user> | modmail unblock <user>"
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::MODERATE_MEMBERS
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Modmail can only be managed in a server")?;
+        let store = &self.store;
+
+        let action = ctx.args.first().map(String::as_str);
+        let user_id = ctx.args.get(1).and_then(|arg| parse_user(arg));
+
+        match (action, user_id) {
+            (Some("close"), _) => {
+                let thread = store
+                    .update(|data| {
+                        let user_id = data.thread_for_channel(ctx.msg.channel_id)?.user_id;
+                        data.open.remove(&user_id)
+                    })
+                    .await?;
+                let thread = match thread {
+                    Some(thread) => thread,
+                    None => {
+                        send_error(ctx.ctx, ctx.msg, "This isn't an open modmail thread.").await?;
+                        return Ok(());
+                    }
+                };
+                let reason = Some(ctx.args[1..].join(" ")).filter(|r| !r.is_empty());
+
+                // Tell the user, ignoring closed DMs
+                let guild_name = guild_id
+                    .name(&ctx.ctx.cache)
+                    .unwrap_or_else(|| "the server".to_string());
+                let notified = match dm_service(ctx.ctx).await {
+                    Some(dms) => {
+                        dms.send(&ctx.ctx.http, UserId(thread.user_id), None, |m| {
+                            m.embed(|e| {
+                                e.title("Conversation closed")
+                                    .description(format!(
+                                        "The staff of **{}** closed this conversation.{}",
+                                        guild_name,
+                                        reason
+                                            .as_ref()
+                                            .map(|r| format!("\nReason: {}", r))
+                                            .unwrap_or_default()
+                                    ))
+                                    .color(ERROR_COLOR)
+                            })
+                        })
+                        .await
+                    }
+                    None => Ok(DmOutcome::Closed),
+                };
+                if let Err(e) = notified {
+                    warn!("Couldn't notify {} of modmail close: {}", thread.user_id, e);
+                }
+
+                send_success(ctx.ctx, ctx.msg, "Conversation closed.").await?;
+
+                // Post the transcript next to the thread
+                let config = guild_config(ctx.ctx, guild_id).await;
+                if let Some(channel_id) = config.modmail_channel {
+                    let transcript = OutgoingFile::new(
+                        format!("modmail-{}.txt", thread.user_id),
+                        thread.transcript_text(),
+                    );
+                    let mut embed = CreateEmbed::default();
+                    embed
+                        .title("Modmail closed")
+                        .color(DEFAULT_COLOR)
+                        .field("User", format!("<@{}>", thread.user_id), true)
+                        .field("Closed by", format!("<@{}>", ctx.msg.author.id), true)
+                        .field("Thread", format!("<#{}>", thread.thread_id), true)
+                        .field("Messages", thread.transcript.len(), true)
+                        .field(
+                            "Reason",
+                            reason.as_deref().unwrap_or("No reason given"),
+                            false,
+                        );
+                    send_file(
+                        ctx.ctx,
+                        ChannelId(channel_id),
+                        Some(guild_id),
+                        transcript,
+                        Some(embed),
+                    )
+                    .await?;
+                }
+
+                ctx.msg
+                    .channel_id
+                    .edit_thread(&ctx.ctx.http, |t| t.archived(true).locked(true))
+                    .await?;
+            }
+            (Some(action @ ("block" | "unblock")), Some(user_id)) => {
+                let block = action == "block";
+                store
+                    .update(|data| {
+                        let blocked = data.blocked.entry(guild_id.0).or_default();
+                        if block {
+                            blocked.insert(user_id.0);
+                        } else {
+                            blocked.remove(&user_id.0);
+                        }
+                    })
+                    .await?;
+
+                let message = if block {
+                    format!(
+                        "<@{}> can no longer contact staff through modmail.",
+                        user_id
+                    )
+                } else {
+                    format!("<@{}> can contact staff through modmail again.", user_id)
+                };
+                send_success(ctx.ctx, ctx.msg, message).await?;
+            }
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", self.usage())).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
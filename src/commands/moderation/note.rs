@@ -0,0 +1,106 @@
+//! Note command for leaving private moderator notes about users.
+
+use async_trait::async_trait;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::moderation::{ModerationData, ModerationKey};
+use crate::storage::JsonStore;
+use crate::utils::helpers::{parse_user, send_error, send_success};
+
+/// Adds or removes moderator notes about a user.
+pub struct NoteCommand {
+    store: Arc<JsonStore<ModerationData>>,
+}
+
+impl NoteCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<ModerationKey>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Command for NoteCommand {
+    fn name(&self) -> &str {
+        "note"
+    }
+
+    fn description(&self) -> &str {
+        "Add or remove a private moderator note about a user"
+    }
+
+    fn usage(&self) -> &str {
+        "note add <user> <text> | note remove <user> <id>"
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::MODERATE_MEMBERS
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Notes can only be used in a server")?;
+        let store = &self.store;
+
+        let action = ctx.args.first().map(String::as_str);
+        let user_id = ctx.args.get(1).and_then(|arg| parse_user(arg));
+
+        match (action, user_id) {
+            (Some("add"), Some(user_id)) if ctx.args.len() > 2 => {
+                let content = ctx.args[2..].join(" ");
+                let author_id = ctx.msg.author.id;
+                let id = store
+                    .update(|data| {
+                        data.guild_mut(guild_id)
+                            .add_note(user_id, author_id, content)
+                    })
+                    .await?;
+
+                send_success(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!("Added note #{} to <@{}>.", id, user_id),
+                )
+                .await?;
+            }
+            (Some("remove"), Some(user_id)) => {
+                let note_id = match ctx.args.get(2).and_then(|arg| arg.parse().ok()) {
+                    Some(id) => id,
+                    None => {
+                        send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", self.usage())).await?;
+                        return Ok(());
+                    }
+                };
+
+                let removed = store
+                    .update(|data| data.guild_mut(guild_id).remove_note(user_id, note_id))
+                    .await?;
+
+                if removed {
+                    send_success(ctx.ctx, ctx.msg, format!("Removed note #{}.", note_id)).await?;
+                } else {
+                    send_error(
+                        ctx.ctx,
+                        ctx.msg,
+                        format!("<@{}> has no note #{}.", user_id, note_id),
+                    )
+                    .await?;
+                }
+            }
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", self.usage())).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
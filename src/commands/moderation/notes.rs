@@ -0,0 +1,161 @@
+//! Notes command for viewing a user's moderation history.
+
+use async_trait::async_trait;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::params::usage_of;
+use crate::framework::state::Inject;
+use crate::framework::{Param, ParamKind};
+use crate::models::moderation::{HistoryEntry, ModerationData, ModerationKey};
+use crate::storage::JsonStore;
+use crate::utils::constants::PAGINATION_MAX_ITEMS;
+use crate::utils::duration::relative;
+use crate::utils::helpers::{parse_user, send_error, send_info, truncate};
+
+/// Shows a user's notes, warnings, and bans, newest first.
+pub struct NotesCommand {
+    store: Arc<JsonStore<ModerationData>>,
+}
+
+impl NotesCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<ModerationKey>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Command for NotesCommand {
+    fn name(&self) -> &str {
+        "notes"
+    }
+
+    fn description(&self) -> &str {
+        "Show a user's moderator notes and past punishments"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            Param::required("user", ParamKind::User, "The member whose history to show"),
+            Param::optional(
+                "page",
+                ParamKind::Integer,
+                "Which page of the history to show",
+            )
+            .range(1, 1000),
+        ]
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["history"]
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::MODERATE_MEMBERS
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Notes can only be used in a server")?;
+        let store = &self.store;
+
+        let user_id = match ctx.args.first().and_then(|arg| parse_user(arg)) {
+            Some(user_id) => user_id,
+            None => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", usage_of(self))).await?;
+                return Ok(());
+            }
+        };
+        let page = ctx
+            .args
+            .get(1)
+            .and_then(|arg| arg.parse::<usize>().ok())
+            .unwrap_or(1)
+            .max(1);
+
+        let (lines, total) = {
+            let data = store.read().await;
+            let history = data
+                .guild(guild_id)
+                .map(|guild| guild.history(user_id))
+                .unwrap_or_default();
+
+            let lines: Vec<String> = history
+                .iter()
+                .skip((page - 1) * PAGINATION_MAX_ITEMS)
+                .take(PAGINATION_MAX_ITEMS)
+                .map(format_entry)
+                .collect();
+
+            (lines, history.len())
+        };
+
+        if total == 0 {
+            send_info(
+                ctx.ctx,
+                ctx.msg,
+                "Moderation history",
+                format!("<@{}> has a clean record.", user_id),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let pages = total.div_ceil(PAGINATION_MAX_ITEMS);
+        if lines.is_empty() {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!("Page {} doesn't exist. There are {} page(s).", page, pages),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        send_info(
+            ctx.ctx,
+            ctx.msg,
+            format!("Moderation history (page {}/{})", page, pages),
+            truncate(
+                &format!("History for <@{}>\n\n{}", user_id, lines.join("\n\n")),
+                4000,
+            ),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Format a history entry as a short block of text.
+fn format_entry(entry: &HistoryEntry<'_>) -> String {
+    match entry {
+        HistoryEntry::Note(note) => format!(
+            "📝 **Note #{}** by <@{}> {}\n{}",
+            note.id,
+            note.author_id,
+            relative(note.created_at),
+            note.content
+        ),
+        HistoryEntry::Case(case) => format!(
+            "🔨 **{} #{}** by {} {}\n{}",
+            case.kind,
+            case.id,
+            case.moderator_id
+                .map_or("an unknown moderator".to_string(), |id| format!(
+                    "<@{}>",
+                    id
+                )),
+            relative(case.created_at),
+            case.reason.as_deref().unwrap_or("No reason given")
+        ),
+    }
+}
@@ -0,0 +1,102 @@
+//! Report command for reporting a message to the server's staff.
+
+use async_trait::async_trait;
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::permissions::Permissions;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::utils::constants::{DEFAULT_COLOR, REPORT_MENU_NAME};
+use crate::utils::helpers::{parse_message_ref, send_error};
+use crate::utils::rest;
+
+/// Starts a report of a message, given by link or ID or by replying to it.
+///
+/// Sends a button that opens the same modal as the "Report Message" context menu,
+/// since only interactions can open modals.
+pub struct ReportCommand;
+
+#[async_trait]
+impl Command for ReportCommand {
+    fn name(&self) -> &str {
+        "report"
+    }
+
+    fn description(&self) -> &str {
+        "Report a message to the server's staff"
+    }
+
+    fn usage(&self) -> &str {
+        "report <message link or ID> (or reply to the message)"
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::EMBED_LINKS
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Reports can only be made in a server")?;
+
+        let target = match &ctx.msg.referenced_message {
+            Some(message) => Some((message.channel_id, message.id)),
+            None => match ctx.args.first().and_then(|arg| parse_message_ref(arg)) {
+                Some(target) if target.guild_id.is_some_and(|id| id != guild_id) => {
+                    send_error(
+                        ctx.ctx,
+                        ctx.msg,
+                        "Only messages from this server can be reported here.",
+                    )
+                    .await?;
+                    return Ok(());
+                }
+                Some(target) => Some((
+                    target.channel_id.unwrap_or(ctx.msg.channel_id),
+                    target.message_id,
+                )),
+                None => None,
+            },
+        };
+        let (channel_id, message_id) = match target {
+            Some(target) => target,
+            None => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", self.usage())).await?;
+                return Ok(());
+            }
+        };
+
+        let author_id = ctx.msg.author.id;
+        rest::call(ctx.ctx, "send_message", || {
+            ctx.msg.channel_id.send_message(&ctx.ctx.http, |m| {
+                m.embed(|e| {
+                    e.title("Report a message")
+                        .description(format!(
+                            "<@{}>, press the button to tell the staff what's wrong. You can \
+                             also right-click a message and choose **Apps → {}**.",
+                            author_id, REPORT_MENU_NAME
+                        ))
+                        .color(DEFAULT_COLOR)
+                })
+                .components(|c| {
+                    c.create_action_row(|row| {
+                        row.create_button(|b| {
+                            b.custom_id(format!(
+                                "report:start:{}:{}:{}",
+                                author_id, channel_id, message_id
+                            ))
+                            .label("Report")
+                            .style(ButtonStyle::Danger)
+                        })
+                    })
+                })
+            })
+        })
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+//! Snipe command for showing the last deleted message in a channel.
+
+use async_trait::async_trait;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::params::usage_of;
+use crate::framework::state::Inject;
+use crate::framework::{Param, ParamKind};
+use crate::models::message_cache::{MessageCache, MessageCacheKey};
+use crate::utils::duration::relative;
+use crate::utils::helpers::{parse_channel, send_error, send_info, truncate};
+
+/// Shows the last deleted message in a channel, from the message cache.
+pub struct SnipeCommand {
+    cache: Arc<MessageCache>,
+}
+
+impl SnipeCommand {
+    /// Create the command with the message cache.
+    pub fn new(Inject(cache): Inject<MessageCacheKey>) -> Self {
+        Self { cache }
+    }
+}
+
+#[async_trait]
+impl Command for SnipeCommand {
+    fn name(&self) -> &str {
+        "snipe"
+    }
+
+    fn description(&self) -> &str {
+        "Show the last deleted message in a channel"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![Param::optional(
+            "channel",
+            ParamKind::Channel,
+            "The channel to look in, this one if left out",
+        )]
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::MANAGE_MESSAGES
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("Snipe can only be used in a server")?;
+        let channel_id = match ctx.args.first() {
+            Some(arg) => match parse_channel(arg) {
+                Some(channel_id) => channel_id,
+                None => {
+                    send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", usage_of(self))).await?;
+                    return Ok(());
+                }
+            },
+            None => ctx.msg.channel_id,
+        };
+
+        // Only messages from this server, so a channel ID can't reach into another
+        let message = match self
+            .cache
+            .last_deleted(channel_id)
+            .filter(|message| message.guild_id == Some(guild_id))
+        {
+            Some(message) => message,
+            None => {
+                send_error(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!("I haven't seen a message deleted in <#{}>.", channel_id),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let mut description = format!(
+            "<@{}> in <#{}>, sent {}\n\n{}",
+            message.author_id,
+            channel_id,
+            relative(message.created_at),
+            truncate(&message.content, 3500)
+        );
+        for attachment in &message.attachments {
+            description.push_str(&format!("\n📎 {}", attachment));
+        }
+        send_info(
+            ctx.ctx,
+            ctx.msg,
+            format!("Deleted message by {}", message.author_tag),
+            truncate(&description, 4000),
+        )
+        .await?;
+        Ok(())
+    }
+}
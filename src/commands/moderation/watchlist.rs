@@ -0,0 +1,179 @@
+//! Watchlist command for flagging users that staff should keep an eye on.
+
+use async_trait::async_trait;
+use serenity::model::id::GuildId;
+use serenity::model::permissions::Permissions;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::moderation::{ModerationData, ModerationKey, WatchEntry};
+use crate::storage::JsonStore;
+use crate::utils::constants::PAGINATION_MAX_ITEMS;
+use crate::utils::duration::relative;
+use crate::utils::helpers::{parse_user, send_error, send_info, send_success, unix_timestamp};
+
+/// Manages the guild's watchlist. Staff are alerted when a watched user joins or chats.
+pub struct WatchlistCommand {
+    store: Arc<JsonStore<ModerationData>>,
+}
+
+impl WatchlistCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<ModerationKey>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Command for WatchlistCommand {
+    fn name(&self) -> &str {
+        "watchlist"
+    }
+
+    fn description(&self) -> &str {
+        "Alert staff when specific users join or become active"
+    }
+
+    fn usage(&self) -> &str {
+        "watchlist [page] | watchlist add <user> [reason] | watchlist remove <user>"
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["watch"]
+    }
+
+    fn required_permissions(&self) -> Permissions {
+        Permissions::MODERATE_MEMBERS
+    }
+
+    fn guild_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let guild_id = ctx
+            .msg
+            .guild_id
+            .ok_or("The watchlist can only be used in a server")?;
+        let store = &self.store;
+
+        let action = ctx.args.first().map(String::as_str);
+        let user_id = ctx.args.get(1).and_then(|arg| parse_user(arg));
+
+        match (action, user_id) {
+            (Some("add"), Some(user_id)) => {
+                let reason = Some(ctx.args[2..].join(" ")).filter(|r| !r.is_empty());
+                let entry = WatchEntry {
+                    added_by: ctx.msg.author.id.0,
+                    reason,
+                    created_at: unix_timestamp(),
+                };
+
+                store
+                    .update(|data| {
+                        data.guild_mut(guild_id).watchlist.insert(user_id.0, entry);
+                    })
+                    .await?;
+
+                send_success(
+                    ctx.ctx,
+                    ctx.msg,
+                    format!("<@{}> is now on the watchlist.", user_id),
+                )
+                .await?;
+            }
+            (Some("remove"), Some(user_id)) => {
+                let removed = store
+                    .update(|data| {
+                        data.guild_mut(guild_id)
+                            .watchlist
+                            .remove(&user_id.0)
+                            .is_some()
+                    })
+                    .await?;
+
+                if removed {
+                    send_success(
+                        ctx.ctx,
+                        ctx.msg,
+                        format!("<@{}> was removed from the watchlist.", user_id),
+                    )
+                    .await?;
+                } else {
+                    send_error(
+                        ctx.ctx,
+                        ctx.msg,
+                        format!("<@{}> is not on the watchlist.", user_id),
+                    )
+                    .await?;
+                }
+            }
+            (None, _) => self.list(&ctx, guild_id, 1).await?,
+            (Some(page), _) if page.parse::<usize>().is_ok() => {
+                let page = page.parse::<usize>().unwrap_or(1).max(1);
+                self.list(&ctx, guild_id, page).await?;
+            }
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", self.usage())).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl WatchlistCommand {
+    /// Show a page of the guild's watchlist.
+    async fn list(
+        &self,
+        ctx: &CommandContext<'_>,
+        guild_id: GuildId,
+        page: usize,
+    ) -> CommandResult {
+        let store = &self.store;
+
+        let (lines, total) = {
+            let data = store.read().await;
+            let mut entries: Vec<_> = data
+                .guild(guild_id)
+                .map(|guild| guild.watchlist.iter().collect())
+                .unwrap_or_default();
+            entries.sort_by_key(|(_, entry)| std::cmp::Reverse(entry.created_at));
+
+            let lines: Vec<String> = entries
+                .iter()
+                .skip((page - 1) * PAGINATION_MAX_ITEMS)
+                .take(PAGINATION_MAX_ITEMS)
+                .map(|(user_id, entry)| {
+                    format!(
+                        "<@{}> added by <@{}> {}\n{}",
+                        user_id,
+                        entry.added_by,
+                        relative(entry.created_at),
+                        entry.reason.as_deref().unwrap_or("No reason given")
+                    )
+                })
+                .collect();
+
+            (lines, entries.len())
+        };
+
+        if total == 0 {
+            send_info(ctx.ctx, ctx.msg, "Watchlist", "Nobody is being watched.").await?;
+        } else if lines.is_empty() {
+            send_error(ctx.ctx, ctx.msg, format!("Page {} doesn't exist.", page)).await?;
+        } else {
+            let pages = total.div_ceil(PAGINATION_MAX_ITEMS);
+            send_info(
+                ctx.ctx,
+                ctx.msg,
+                format!("Watchlist (page {}/{})", page, pages),
+                lines.join("\n\n"),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+}
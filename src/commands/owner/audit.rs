@@ -0,0 +1,138 @@
+//! Audit command for reviewing the privileged actions the bot performed.
+
+use async_trait::async_trait;
+use serenity::model::id::GuildId;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::audit::{AuditEntry, AuditFilter, AuditKey, AuditLog};
+use crate::storage::JsonStore;
+use crate::utils::constants::PAGINATION_MAX_ITEMS;
+use crate::utils::duration::relative;
+use crate::utils::helpers::{parse_user, send_error, send_info, truncate};
+
+/// Shows the internal audit log, newest first.
+pub struct AuditCommand {
+    store: Arc<JsonStore<AuditLog>>,
+}
+
+impl AuditCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<AuditKey>) -> Self {
+        Self { store }
+    }
+}
+
+/// Parse a filter from its name and value.
+fn parse_filter(kind: &str, value: &str) -> Option<AuditFilter> {
+    match kind {
+        "guild" => value.parse().ok().map(|id| AuditFilter::Guild(GuildId(id))),
+        "user" => parse_user(value).map(AuditFilter::Actor),
+        "source" => value.parse().ok().map(AuditFilter::Source),
+        _ => None,
+    }
+}
+
+#[async_trait]
+impl Command for AuditCommand {
+    fn name(&self) -> &str {
+        "audit"
+    }
+
+    fn description(&self) -> &str {
+        "Show privileged actions the bot performed"
+    }
+
+    fn usage(&self) -> &str {
+        "audit [guild <id>|user <user>|source <command|interaction|api|automod|vote>] [page]"
+    }
+
+    fn owner_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let mut args = ctx.args.iter().map(String::as_str).peekable();
+
+        let filter = match args.peek().map(|arg| arg.to_lowercase()) {
+            Some(kind) if kind.parse::<usize>().is_err() => {
+                args.next();
+                match args.next().and_then(|value| parse_filter(&kind, value)) {
+                    Some(filter) => filter,
+                    None => {
+                        send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", self.usage())).await?;
+                        return Ok(());
+                    }
+                }
+            }
+            _ => AuditFilter::All,
+        };
+        let page = args
+            .next()
+            .and_then(|arg| arg.parse::<usize>().ok())
+            .unwrap_or(1)
+            .max(1);
+
+        let (lines, total) = {
+            let log = self.store.read().await;
+            let entries = log.query(filter);
+            let lines: Vec<String> = entries
+                .iter()
+                .skip((page - 1) * PAGINATION_MAX_ITEMS)
+                .take(PAGINATION_MAX_ITEMS)
+                .map(|entry| format_entry(entry))
+                .collect();
+            (lines, entries.len())
+        };
+
+        if total == 0 {
+            send_info(ctx.ctx, ctx.msg, "Audit log", "No actions recorded.").await?;
+            return Ok(());
+        }
+
+        let pages = total.div_ceil(PAGINATION_MAX_ITEMS);
+        if lines.is_empty() {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                format!("Page {} doesn't exist. There are {} page(s).", page, pages),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        send_info(
+            ctx.ctx,
+            ctx.msg,
+            format!("Audit log (page {}/{})", page, pages),
+            truncate(&lines.join("\n\n"), 4000),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Format an entry as a short block of text.
+fn format_entry(entry: &AuditEntry) -> String {
+    let actor = entry
+        .actor_id
+        .map_or("the bot".to_string(), |id| format!("<@{}>", id));
+    let guild = entry
+        .guild_id
+        .map_or(String::new(), |id| format!(" in `{}`", id));
+
+    let mut text = format!(
+        "**#{}** {} · {} via {}{} {}",
+        entry.id,
+        entry.action,
+        actor,
+        entry.source,
+        guild,
+        relative(entry.created_at)
+    );
+    if let Some(reason) = &entry.reason {
+        text.push_str(&format!("\nReason: {}", reason));
+    }
+    text
+}
@@ -0,0 +1,52 @@
+//! Diagnose command for re-running the startup self-checks.
+
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::utils::diagnostics::{Diagnostics, DiagnosticsKey, Status};
+use crate::utils::paste::send_long_info;
+
+/// Runs the self-checks and shows the results.
+pub struct DiagnoseCommand {
+    diagnostics: Arc<Diagnostics>,
+}
+
+impl DiagnoseCommand {
+    /// Create the command with the shared diagnostics.
+    pub fn new(Inject(diagnostics): Inject<DiagnosticsKey>) -> Self {
+        Self { diagnostics }
+    }
+}
+
+#[async_trait]
+impl Command for DiagnoseCommand {
+    fn name(&self) -> &str {
+        "diagnose"
+    }
+
+    fn description(&self) -> &str {
+        "Check the token, intents, storage and server settings"
+    }
+
+    fn usage(&self) -> &str {
+        "diagnose"
+    }
+
+    fn owner_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let report = self.diagnostics.run(&ctx.ctx.http).await;
+        report.log();
+
+        let title = match report.status() {
+            Status::Ok => "🩺 Diagnostics: all good",
+            Status::Warning => "🩺 Diagnostics: warnings",
+            Status::Failed => "🩺 Diagnostics: problems found",
+        };
+        send_long_info(ctx.ctx, ctx.msg, title, &report.describe()).await
+    }
+}
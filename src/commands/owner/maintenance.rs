@@ -0,0 +1,132 @@
+//! Maintenance command for taking the bot or single commands offline.
+
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::maintenance::{MaintenanceKey, MaintenanceState};
+use crate::storage::JsonStore;
+use crate::utils::helpers::{send_error, send_info, send_success};
+
+/// Toggles global and per-command maintenance mode.
+pub struct MaintenanceCommand {
+    store: Arc<JsonStore<MaintenanceState>>,
+}
+
+impl MaintenanceCommand {
+    /// Create the command with its store.
+    pub fn new(Inject(store): Inject<MaintenanceKey>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Command for MaintenanceCommand {
+    fn name(&self) -> &str {
+        "maintenance"
+    }
+
+    fn description(&self) -> &str {
+        "Put the bot or individual commands into maintenance mode"
+    }
+
+    fn usage(&self) -> &str {
+        "maintenance [on [message] | off | owners <on|off> | command <name> <on [message]|off>]"
+    }
+
+    fn owner_only(&self) -> bool {
+        true
+    }
+
+    /// Turning maintenance on locks everyone else out, so changes need confirming.
+    fn critical(&self, args: &[String]) -> bool {
+        !args.is_empty()
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let store = &self.store;
+
+        let args: Vec<&str> = ctx.args.iter().map(String::as_str).collect();
+        let message_from = |start: usize| Some(args[start..].join(" ")).filter(|m| !m.is_empty());
+
+        let response = match args.as_slice() {
+            [] => {
+                let state = store.read().await;
+                let mut commands: Vec<_> =
+                    state.commands.keys().map(|c| format!("`{}`", c)).collect();
+                commands.sort();
+
+                let status = format!(
+                    "Global: **{}**\nOwners allowed: **{}**\nNotice: {}\nCommands: {}",
+                    if state.enabled { "on" } else { "off" },
+                    if state.allow_owners { "yes" } else { "no" },
+                    state.message.as_deref().unwrap_or("default"),
+                    if commands.is_empty() {
+                        "none".to_string()
+                    } else {
+                        commands.join(", ")
+                    }
+                );
+                send_info(ctx.ctx, ctx.msg, "Maintenance status", status).await?;
+                return Ok(());
+            }
+            ["on", ..] => {
+                let message = message_from(1);
+                store
+                    .update(|state| {
+                        state.enabled = true;
+                        state.message = message;
+                    })
+                    .await?;
+                "Maintenance mode is on.".to_string()
+            }
+            ["off"] => {
+                store.update(|state| state.enabled = false).await?;
+                "Maintenance mode is off.".to_string()
+            }
+            ["owners", toggle @ ("on" | "off")] => {
+                let allow = *toggle == "on";
+                store.update(|state| state.allow_owners = allow).await?;
+                format!(
+                    "Owners {} use commands during maintenance.",
+                    if allow { "can" } else { "can't" }
+                )
+            }
+            ["command", name, "on", ..] => {
+                let name = name.to_lowercase();
+                let message = message_from(3);
+                store
+                    .update(|state| {
+                        state.commands.insert(name.clone(), message);
+                    })
+                    .await?;
+                format!("`{}` is now in maintenance.", name)
+            }
+            ["command", name, "off"] => {
+                let name = name.to_lowercase();
+                let removed = store
+                    .update(|state| state.commands.remove(&name).is_some())
+                    .await?;
+                if !removed {
+                    send_error(
+                        ctx.ctx,
+                        ctx.msg,
+                        format!("`{}` isn't in maintenance.", name),
+                    )
+                    .await?;
+                    return Ok(());
+                }
+                format!("`{}` is out of maintenance.", name)
+            }
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", self.usage())).await?;
+                return Ok(());
+            }
+        };
+
+        send_success(ctx.ctx, ctx.msg, response).await?;
+
+        Ok(())
+    }
+}
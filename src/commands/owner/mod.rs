@@ -0,0 +1,20 @@
+//! Owner-only commands for operating the bot.
+
+pub mod audit;
+pub mod diagnose;
+pub mod maintenance;
+pub mod premium;
+pub mod scripts;
+pub mod shards;
+
+use crate::framework::command_handler::CommandHandler;
+
+/// Register all owner commands with the command handler.
+pub fn register_commands(handler: &mut CommandHandler) {
+    handler.register_with_state(audit::AuditCommand::new);
+    handler.register_with_state(diagnose::DiagnoseCommand::new);
+    handler.register_with_state(maintenance::MaintenanceCommand::new);
+    handler.register_with_state(premium::PremiumCommand::new);
+    handler.register_with_state(shards::ShardsCommand::new);
+    handler.register_with_state(scripts::ReloadScriptsCommand::new);
+}
@@ -0,0 +1,213 @@
+//! Premium command for granting tiers and syncing entitlements.
+
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::premium::{Grant, GrantSource, PremiumData, PremiumKey, Tier};
+use crate::storage::JsonStore;
+use crate::utils::duration::{self, relative};
+use crate::utils::entitlements::{EntitlementSync, EntitlementSyncKey};
+use crate::utils::helpers::{parse_user, send_error, send_info, send_success, unix_timestamp};
+
+const USAGE: &str = "premium <user|guild> <id> [<free|plus|pro> [duration]] | premium sync";
+
+/// Whether a grant is for a user or a guild.
+#[derive(Clone, Copy)]
+enum Target {
+    User(u64),
+    Guild(u64),
+}
+
+impl Target {
+    fn describe(self) -> String {
+        match self {
+            Target::User(id) => format!("<@{}>", id),
+            Target::Guild(id) => format!("server `{}`", id),
+        }
+    }
+}
+
+/// Shows and sets the premium tier of users and guilds.
+pub struct PremiumCommand {
+    store: Arc<JsonStore<PremiumData>>,
+    entitlements: Arc<EntitlementSync>,
+}
+
+impl PremiumCommand {
+    /// Create the command with the premium store and the entitlement sync.
+    pub fn new(
+        (Inject(store), Inject(entitlements)): (Inject<PremiumKey>, Inject<EntitlementSyncKey>),
+    ) -> Self {
+        Self {
+            store,
+            entitlements,
+        }
+    }
+}
+
+#[async_trait]
+impl Command for PremiumCommand {
+    fn name(&self) -> &str {
+        "premium"
+    }
+
+    fn description(&self) -> &str {
+        "Show or set the premium tier of a user or server"
+    }
+
+    fn usage(&self) -> &str {
+        USAGE
+    }
+
+    fn owner_only(&self) -> bool {
+        true
+    }
+
+    /// Granting or revoking a tier needs confirming, looking one up doesn't.
+    fn critical(&self, args: &[String]) -> bool {
+        args.len() > 2
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let args: Vec<&str> = ctx.args.iter().map(String::as_str).collect();
+
+        let (target, rest) = match args.as_slice() {
+            ["sync"] => return self.sync(&ctx).await,
+            ["user", user, rest @ ..] => match parse_user(user) {
+                Some(user_id) => (Target::User(user_id.0), rest),
+                None => {
+                    send_error(ctx.ctx, ctx.msg, format!("`{}` isn't a user.", user)).await?;
+                    return Ok(());
+                }
+            },
+            ["guild" | "server", guild, rest @ ..] => match guild.parse() {
+                Ok(guild_id) => (Target::Guild(guild_id), rest),
+                Err(_) => {
+                    send_error(ctx.ctx, ctx.msg, format!("`{}` isn't a server ID.", guild)).await?;
+                    return Ok(());
+                }
+            },
+            _ => {
+                send_error(ctx.ctx, ctx.msg, format!("Usage: `{}`", USAGE)).await?;
+                return Ok(());
+            }
+        };
+
+        let (tier, length) = match rest {
+            [] => return self.show(&ctx, target).await,
+            [tier] => (tier.parse::<Tier>(), None),
+            [tier, length @ ..] => (tier.parse::<Tier>(), Some(length.join(" "))),
+        };
+        let tier = match tier {
+            Ok(tier) => tier,
+            Err(e) => {
+                send_error(ctx.ctx, ctx.msg, e).await?;
+                return Ok(());
+            }
+        };
+        let expires_at = match length.as_deref().map(duration::parse) {
+            None => None,
+            Some(Ok(length)) => Some(unix_timestamp() + length.as_secs()),
+            Some(Err(e)) => {
+                send_error(ctx.ctx, ctx.msg, e.to_string()).await?;
+                return Ok(());
+            }
+        };
+
+        let grant = tier.is_premium().then_some(Grant {
+            tier,
+            source: GrantSource::Owner {
+                by: ctx.msg.author.id.0,
+            },
+            expires_at,
+        });
+        let until = grant
+            .as_ref()
+            .and_then(|grant| grant.expires_at)
+            .map(|expires_at| format!(" until {}", relative(expires_at)))
+            .unwrap_or_default();
+        self.store
+            .update(|data| {
+                let (grants, id) = match target {
+                    Target::User(id) => (&mut data.users, id),
+                    Target::Guild(id) => (&mut data.guilds, id),
+                };
+                match grant {
+                    Some(grant) => grants.insert(id, grant),
+                    None => grants.remove(&id),
+                }
+            })
+            .await?;
+
+        send_success(
+            ctx.ctx,
+            ctx.msg,
+            format!("{} is now on **{}**{}.", target.describe(), tier, until),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+impl PremiumCommand {
+    /// Show a user's or guild's tier and where it came from.
+    async fn show(&self, ctx: &CommandContext<'_>, target: Target) -> CommandResult {
+        let grant = {
+            let data = self.store.read().await;
+            match target {
+                Target::User(id) => data.users.get(&id).cloned(),
+                Target::Guild(id) => data.guilds.get(&id).cloned(),
+            }
+        };
+
+        let now = unix_timestamp();
+        let description = match grant.filter(|grant| grant.is_active(now)) {
+            None => format!("{} is on **{}**.", target.describe(), Tier::Free),
+            Some(grant) => {
+                let source = match grant.source {
+                    GrantSource::Owner { by } => format!("granted by <@{}>", by),
+                    GrantSource::Entitlement { sku_id } => format!("from SKU `{}`", sku_id),
+                    GrantSource::Vote => "vote trial".to_string(),
+                };
+                let until = grant
+                    .expires_at
+                    .map(|expires_at| format!(", ends {}", relative(expires_at)))
+                    .unwrap_or_default();
+                format!(
+                    "{} is on **{}** ({}{}).",
+                    target.describe(),
+                    grant.tier,
+                    source,
+                    until
+                )
+            }
+        };
+
+        send_info(ctx.ctx, ctx.msg, "⭐ Premium", description).await?;
+        Ok(())
+    }
+
+    /// Sync entitlements from Discord now.
+    async fn sync(&self, ctx: &CommandContext<'_>) -> CommandResult {
+        if !self.entitlements.is_enabled() {
+            send_error(
+                ctx.ctx,
+                ctx.msg,
+                "No SKUs are set in `[premium] skus`, so there is nothing to sync.",
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let changed = self.entitlements.sync().await?;
+        send_success(
+            ctx.ctx,
+            ctx.msg,
+            format!("Synced entitlements, {} grants changed.", changed),
+        )
+        .await?;
+        Ok(())
+    }
+}
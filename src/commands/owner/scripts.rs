@@ -0,0 +1,68 @@
+//! Reload scripts command for picking up changes to script commands.
+
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::scripts::{Scripts, ScriptsKey};
+use crate::framework::state::Inject;
+use crate::utils::paste::send_long_info;
+
+/// Reads the script commands from disk again.
+pub struct ReloadScriptsCommand {
+    scripts: Arc<Scripts>,
+}
+
+impl ReloadScriptsCommand {
+    /// Create the command with the loaded scripts.
+    pub fn new(Inject(scripts): Inject<ScriptsKey>) -> Self {
+        Self { scripts }
+    }
+}
+
+#[async_trait]
+impl Command for ReloadScriptsCommand {
+    fn name(&self) -> &str {
+        "reloadscripts"
+    }
+
+    fn description(&self) -> &str {
+        "Reload the script commands from disk"
+    }
+
+    fn usage(&self) -> &str {
+        "reloadscripts"
+    }
+
+    fn owner_only(&self) -> bool {
+        true
+    }
+
+    /// Scripts run with the bot's access, so loading new ones needs confirming.
+    fn critical(&self, _args: &[String]) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let scripts = self.scripts.clone();
+        let report = tokio::task::spawn_blocking(move || scripts.load())
+            .await
+            .map_err(|e| format!("script reload failed: {}", e))?;
+
+        let mut lines = vec![if report.loaded.is_empty() {
+            "No scripts loaded.".to_string()
+        } else {
+            let names: Vec<_> = report
+                .loaded
+                .iter()
+                .map(|name| format!("`{}`", name))
+                .collect();
+            format!("Loaded {}: {}", report.loaded.len(), names.join(", "))
+        }];
+        for (name, error) in &report.failed {
+            lines.push(format!("❌ `{}`: {}", name, error));
+        }
+
+        send_long_info(ctx.ctx, ctx.msg, "📜 Scripts reloaded", &lines.join("\n")).await
+    }
+}
@@ -0,0 +1,81 @@
+//! Shards command for checking gateway connection health.
+
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::framework::command_handler::{Command, CommandContext, CommandResult};
+use crate::framework::state::Inject;
+use crate::models::shard_health::{ShardHealth, ShardHealthKey, ShardStatus};
+use crate::utils::duration::format_compact;
+use crate::utils::helpers::send_info;
+
+/// Describe a shard's connection in one line.
+fn describe(shard_id: u64, status: &ShardStatus) -> String {
+    let state = if status.is_connected() {
+        format!(
+            "🟢 connected for {}",
+            format_compact(status.since.elapsed())
+        )
+    } else {
+        let down_for = status.downtime().unwrap_or_else(|| status.since.elapsed());
+        format!("🔴 {} for {}", status.stage, format_compact(down_for))
+    };
+
+    format!(
+        "**Shard {}**: {} · {} disconnects · {} resumes",
+        shard_id, state, status.disconnects, status.resumes
+    )
+}
+
+/// Shows the connection state of each shard.
+pub struct ShardsCommand {
+    health: Arc<ShardHealth>,
+}
+
+impl ShardsCommand {
+    /// Create the command with the shard health tracker.
+    pub fn new(Inject(health): Inject<ShardHealthKey>) -> Self {
+        Self { health }
+    }
+}
+
+#[async_trait]
+impl Command for ShardsCommand {
+    fn name(&self) -> &str {
+        "shards"
+    }
+
+    fn description(&self) -> &str {
+        "Show the gateway connection health of each shard"
+    }
+
+    fn usage(&self) -> &str {
+        "shards"
+    }
+
+    fn owner_only(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let statuses = self.health.statuses();
+        let description = if statuses.is_empty() {
+            "No shard has reported its connection yet.".to_string()
+        } else {
+            statuses
+                .iter()
+                .map(|(shard_id, status)| describe(*shard_id, status))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        send_info(
+            ctx.ctx,
+            ctx.msg,
+            format!("Shards (this is shard {})", ctx.ctx.shard_id),
+            description,
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+//! Handlers that count messages, emojis and reactions for the activity stats.
+
+use async_trait::async_trait;
+use serenity::model::channel::{Message, Reaction, ReactionType};
+use serenity::prelude::*;
+use std::sync::Arc;
+
+use crate::framework::event_handler::EventHandler;
+use crate::models::analytics::{Analytics, AnalyticsKey};
+
+async fn analytics(ctx: &Context) -> Option<Arc<Analytics>> {
+    let data = ctx.data.read().await;
+    data.get::<AnalyticsKey>().cloned()
+}
+
+/// Counts messages from members, and the emojis in them, per channel.
+pub struct AnalyticsMessageHandler;
+
+#[async_trait]
+impl EventHandler for AnalyticsMessageHandler {
+    fn event_type(&self) -> &'static str {
+        "message"
+    }
+
+    async fn on_message(&self, ctx: Context, msg: &Message) {
+        let guild_id = match msg.guild_id {
+            Some(guild_id) if !msg.author.bot && msg.webhook_id.is_none() => guild_id,
+            _ => return,
+        };
+        if let Some(analytics) = analytics(&ctx).await {
+            analytics
+                .record_message(guild_id, msg.channel_id, &msg.content)
+                .await;
+        }
+    }
+}
+
+/// Counts reactions added by members.
+pub struct AnalyticsReactionHandler;
+
+#[async_trait]
+impl EventHandler for AnalyticsReactionHandler {
+    fn event_type(&self) -> &'static str {
+        "reaction_add"
+    }
+
+    async fn on_reaction_add(&self, ctx: Context, reaction: &Reaction) {
+        let guild_id = match reaction.guild_id {
+            Some(guild_id) => guild_id,
+            None => return,
+        };
+        if reaction
+            .member
+            .as_ref()
+            .is_some_and(|member| member.user.as_ref().is_some_and(|user| user.bot))
+        {
+            return;
+        }
+        let emoji = match &reaction.emoji {
+            ReactionType::Custom { id, name, .. } => {
+                format!("<:{}:{}>", name.as_deref().unwrap_or("emoji"), id)
+            }
+            ReactionType::Unicode(emoji) => emoji.clone(),
+            _ => return,
+        };
+        if let Some(analytics) = analytics(&ctx).await {
+            analytics.record_reaction(guild_id, emoji).await;
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Stops an actor who trips an anti-nuke limit, see [`crate::models::antinuke`].
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serenity::model::event::Event as GatewayEvent;
+use serenity::model::id::{GuildId, RoleId, UserId};
+use serenity::prelude::*;
+use std::time::Instant;
+use tracing::{debug, error, warn};
+
+use crate::framework::event_handler::EventHandler;
+use crate::models::antinuke::{Action, AntinukeKey, AuditChange};
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::guild_config::guild_config;
+use crate::utils::constants::ERROR_COLOR;
+use crate::utils::dms::dm_service;
+use crate::utils::helpers::send_staff_alert;
+use crate::utils::rest::{self, Priority};
+
+/// The gateway event for a new audit log entry, which serenity doesn't know yet.
+const AUDIT_LOG_ENTRY_CREATE: &str = "GUILD_AUDIT_LOG_ENTRY_CREATE";
+
+/// The parts of an audit log entry that matter here.
+#[derive(Deserialize)]
+struct AuditEntry {
+    guild_id: GuildId,
+    #[serde(default)]
+    user_id: Option<UserId>,
+    action_type: u8,
+    #[serde(default)]
+    changes: Vec<AuditChange>,
+}
+
+/// Counts destructive audit log entries per actor and stops actors who trip a
+/// limit in guilds that turned protection on.
+pub struct AntinukeHandler;
+
+#[async_trait]
+impl EventHandler for AntinukeHandler {
+    fn event_type(&self) -> &'static str {
+        "raw"
+    }
+
+    async fn on_raw_event(&self, ctx: Context, event: &GatewayEvent) {
+        let entry = match event {
+            GatewayEvent::Unknown(event) if event.kind == AUDIT_LOG_ENTRY_CREATE => {
+                match serde_json::from_value::<AuditEntry>(event.value.clone()) {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        debug!("Couldn't read audit log entry: {}", e);
+                        return;
+                    }
+                }
+            }
+            _ => return,
+        };
+        let action = match Action::from_entry(entry.action_type, &entry.changes) {
+            Some(action) => action,
+            None => return,
+        };
+        let actor = match entry.user_id {
+            Some(actor) if actor != ctx.cache.current_user_id() => actor,
+            _ => return,
+        };
+        let guild_id = entry.guild_id;
+
+        let settings = guild_config(&ctx, guild_id).await.antinuke;
+        if !settings.enabled || settings.trusted.contains(&actor.0) {
+            return;
+        }
+        if ctx.cache.guild_field(guild_id, |guild| guild.owner_id) == Some(actor) {
+            return;
+        }
+
+        let tracker = {
+            let data = ctx.data.read().await;
+            data.get::<AntinukeKey>().cloned()
+        };
+        let tracker = match tracker {
+            Some(tracker) => tracker,
+            None => return,
+        };
+        let count = match tracker.record(guild_id.0, actor.0, action, Instant::now()) {
+            Some(count) => count,
+            None => return,
+        };
+        warn!(
+            "Anti-nuke tripped in guild {}: {} {} {} times",
+            guild_id, actor, action, count
+        );
+
+        let stripped = strip_roles(&ctx, guild_id, actor).await;
+        let outcome = match &stripped {
+            Ok(0) => "They had no roles I could remove.".to_string(),
+            Ok(removed) => format!("I removed {} of their roles.", removed),
+            Err(e) => format!("I couldn't remove their roles: {}", e),
+        };
+        let description = format!(
+            "<@{}> {} {} times within {} seconds. {}\n\nCheck the audit log, and add them \
+             to `antinuke trust` if this was expected.",
+            actor,
+            action,
+            count,
+            tracker.window().as_secs(),
+            outcome
+        );
+
+        audit::record(
+            &ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.cache.current_user_id()),
+                source: AuditSource::Automod,
+                action: format!("Anti-nuke: stopped <@{}> after they {}", actor, action),
+                reason: None,
+            },
+        )
+        .await;
+        if let Err(e) = send_staff_alert(&ctx, guild_id, "Anti-nuke triggered", &description).await
+        {
+            error!("Failed to send anti-nuke alert: {}", e);
+        }
+        alert_owner(&ctx, guild_id, &description).await;
+    }
+}
+
+/// Remove every role from a member that the bot can, returning how many.
+async fn strip_roles(ctx: &Context, guild_id: GuildId, user_id: UserId) -> Result<usize, String> {
+    // Large servers don't have every member cached
+    let roles = match ctx
+        .cache
+        .member_field(guild_id, user_id, |m| m.roles.clone())
+    {
+        Some(roles) => roles,
+        None => {
+            rest::call_with(ctx, Priority::Moderation, "get_member", || {
+                guild_id.member(&ctx.http, user_id)
+            })
+            .await
+            .map_err(|e| e.to_string())?
+            .roles
+        }
+    };
+
+    let (keep, removed) = {
+        let guild = ctx.cache.guild(guild_id).ok_or("the server isn't cached")?;
+
+        // Managed roles and roles at or above the bot's highest role can't be
+        // removed by it, and asking to would fail the whole edit
+        let top = guild
+            .members
+            .get(&ctx.cache.current_user_id())
+            .map(|bot| {
+                bot.roles
+                    .iter()
+                    .filter_map(|role_id| guild.roles.get(role_id))
+                    .map(|role| role.position)
+                    .max()
+                    .unwrap_or_default()
+            })
+            .unwrap_or_default();
+        let (keep, removed): (Vec<RoleId>, Vec<RoleId>) = roles.iter().partition(|role_id| {
+            guild
+                .roles
+                .get(role_id)
+                .is_none_or(|role| role.managed || role.position >= top)
+        });
+        (keep, removed.len())
+    };
+    if removed == 0 {
+        return Ok(0);
+    }
+
+    rest::call_with(ctx, Priority::Moderation, "edit_member", || {
+        guild_id.edit_member(&ctx.http, user_id, |m| m.roles(&keep))
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(removed)
+}
+
+/// DM the guild's owner about a tripped limit.
+async fn alert_owner(ctx: &Context, guild_id: GuildId, description: &str) {
+    let (owner_id, name) = match ctx
+        .cache
+        .guild_field(guild_id, |guild| (guild.owner_id, guild.name.clone()))
+    {
+        Some(owner) => owner,
+        None => return,
+    };
+    let dms = match dm_service(ctx).await {
+        Some(dms) => dms,
+        None => return,
+    };
+    let sent = dms
+        .send(&ctx.http, owner_id, None, |m| {
+            m.embed(|e| {
+                e.title(format!("Anti-nuke triggered in {}", name))
+                    .description(description)
+                    .color(ERROR_COLOR)
+            })
+        })
+        .await;
+    if let Err(e) = sent {
+        debug!("Couldn't DM the owner of guild {}: {}", guild_id, e);
+    }
+}
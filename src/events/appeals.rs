@@ -0,0 +1,487 @@
+//! Handler for the button and modal interactions of the appeal flow.
+//!
+//! The flow uses these component IDs:
+//! - `appeal:start:<guild>`: button sent by the `appeal` command, opens the modal.
+//! - `appeal:submit:<guild>`: the modal, posts the appeal to the staff channel.
+//! - `appeal:accept:<guild>:<appeal>` / `appeal:deny:<guild>:<appeal>`: staff buttons.
+
+use async_trait::async_trait;
+use serenity::builder::CreateEmbed;
+use serenity::model::application::component::{ActionRowComponent, ButtonStyle, InputTextStyle};
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::modal::ModalSubmitInteraction;
+use serenity::model::application::interaction::{Interaction, InteractionResponseType};
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, GuildId, UserId};
+use serenity::model::permissions::Permissions;
+use serenity::prelude::*;
+use tracing::{error, warn};
+
+use crate::framework::command_handler::CommandResult;
+use crate::framework::event_handler::EventHandler;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::guild_config::guild_config;
+use crate::models::moderation::{Appeal, AppealStatus, CaseKind, ModerationData, ModerationKey};
+use crate::models::notifications::DmCategory;
+use crate::storage::JsonStore;
+use crate::utils::constants::{DEFAULT_COLOR, ERROR_COLOR, SUCCESS_COLOR};
+use crate::utils::dms::dm_service;
+use crate::utils::helpers::{apply_mentions, mention_policy, reply_ephemeral};
+use crate::utils::rest::{self, Priority};
+
+/// Handles appeal buttons and modals.
+pub struct AppealHandler;
+
+#[async_trait]
+impl EventHandler for AppealHandler {
+    fn event_type(&self) -> &'static str {
+        "interaction"
+    }
+
+    async fn on_interaction(&self, ctx: Context, interaction: &Interaction) {
+        let result = match interaction {
+            Interaction::MessageComponent(component) => {
+                let parts: Vec<&str> = match component.data.custom_id.strip_prefix("appeal:") {
+                    Some(rest) => rest.split(':').collect(),
+                    None => return,
+                };
+
+                match parts.as_slice() {
+                    ["start", guild_id] => match guild_id.parse() {
+                        Ok(guild_id) => start(&ctx, component, GuildId(guild_id)).await,
+                        Err(_) => return,
+                    },
+                    [action @ ("accept" | "deny"), guild_id, appeal_id] => {
+                        match (guild_id.parse(), appeal_id.parse()) {
+                            (Ok(guild_id), Ok(appeal_id)) => {
+                                let accept = *action == "accept";
+                                decide(&ctx, component, GuildId(guild_id), appeal_id, accept).await
+                            }
+                            _ => return,
+                        }
+                    }
+                    _ => return,
+                }
+            }
+            Interaction::ModalSubmit(modal) => {
+                let guild_id = match modal
+                    .data
+                    .custom_id
+                    .strip_prefix("appeal:submit:")
+                    .and_then(|id| id.parse().ok())
+                {
+                    Some(guild_id) => GuildId(guild_id),
+                    None => return,
+                };
+
+                submit(&ctx, modal, guild_id).await
+            }
+            _ => return,
+        };
+
+        if let Err(e) = result {
+            error!("Appeal interaction failed: {:?}", e);
+        }
+    }
+}
+
+/// Check whether a user can appeal in a guild, returning the reason if they can't.
+async fn check_appealable(
+    ctx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Option<&'static str> {
+    if guild_config(ctx, guild_id).await.staff_channel.is_none() {
+        return Some("This server isn't accepting appeals right now.");
+    }
+
+    let store = {
+        let data = ctx.data.read().await;
+        data.get::<ModerationKey>().cloned()
+    }?;
+    let data = store.read().await;
+    let guild = data.guild(guild_id);
+
+    match guild {
+        Some(guild) if guild.pending_appeal(user_id).is_some() => {
+            Some("You already have a pending appeal for this server.")
+        }
+        Some(guild) if guild.active_punishment(user_id).is_some() => None,
+        _ => Some("You have no active punishment in this server."),
+    }
+}
+
+/// Open the appeal modal.
+async fn start(
+    ctx: &Context,
+    component: &MessageComponentInteraction,
+    guild_id: GuildId,
+) -> CommandResult {
+    if let Some(reason) = check_appealable(ctx, guild_id, component.user.id).await {
+        reply_ephemeral(ctx, component, reason).await?;
+        return Ok(());
+    }
+
+    component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::Modal)
+                .interaction_response_data(|d| {
+                    d.custom_id(format!("appeal:submit:{}", guild_id))
+                        .title("Appeal your punishment")
+                        .components(|c| {
+                            c.create_action_row(|row| {
+                                row.create_input_text(|t| {
+                                    t.custom_id("appeal")
+                                        .label("Why should your punishment be lifted?")
+                                        .style(InputTextStyle::Paragraph)
+                                        .min_length(20)
+                                        .max_length(1500)
+                                        .required(true)
+                                })
+                            })
+                        })
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Record a submitted appeal and post it to the staff channel.
+async fn submit(ctx: &Context, modal: &ModalSubmitInteraction, guild_id: GuildId) -> CommandResult {
+    let user_id = modal.user.id;
+    let content = modal
+        .data
+        .components
+        .iter()
+        .flat_map(|row| &row.components)
+        .find_map(|component| match component {
+            ActionRowComponent::InputText(input) if input.custom_id == "appeal" => {
+                Some(input.value.clone())
+            }
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    let response = match check_appealable(ctx, guild_id, user_id).await {
+        Some(reason) => reason,
+        None => {
+            let store = {
+                let data = ctx.data.read().await;
+                data.get::<ModerationKey>().cloned()
+            }
+            .ok_or("Moderation store is not loaded")?;
+
+            let config = guild_config(ctx, guild_id).await;
+            let staff_channel = ChannelId(config.staff_channel.ok_or("No staff channel")?);
+
+            // Checked again while recording, in case the punishment was lifted or
+            // another appeal went in since the check above
+            let appeal = store
+                .update(|data| {
+                    let guild = data.guild_mut(guild_id);
+                    if guild.pending_appeal(user_id).is_some() {
+                        return Err("You already have a pending appeal for this server.");
+                    }
+                    let case = guild
+                        .active_punishment(user_id)
+                        .cloned()
+                        .ok_or("You have no active punishment in this server.")?;
+                    let id = guild.add_appeal(user_id, &case, content.clone());
+                    Ok(guild.appeals[&id].clone())
+                })
+                .await?;
+
+            let appeal = match appeal {
+                Ok(appeal) => appeal,
+                Err(reason) => return respond(ctx, modal, reason).await,
+            };
+            match post_appeal(ctx, guild_id, staff_channel, config.staff_role, &appeal).await {
+                Ok(_) => {
+                    "Your appeal was sent to the server's staff. You'll get a DM once it's decided."
+                }
+                // Staff never saw it, so it mustn't keep the user from appealing again
+                Err(e) => {
+                    warn!(
+                        "Couldn't post appeal #{} in guild {}: {}",
+                        appeal.id, guild_id, e
+                    );
+                    store
+                        .update(|data| data.guild_mut(guild_id).appeals.remove(&appeal.id))
+                        .await?;
+                    "I couldn't send your appeal to the server's staff. Please try again later."
+                }
+            }
+        }
+    };
+
+    respond(ctx, modal, response).await
+}
+
+/// Reply to the appeal modal with a message only the user can see.
+async fn respond(ctx: &Context, modal: &ModalSubmitInteraction, content: &str) -> CommandResult {
+    modal
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|d| d.content(content).ephemeral(true))
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Post an appeal to the staff channel with buttons to decide it.
+async fn post_appeal(
+    ctx: &Context,
+    guild_id: GuildId,
+    staff_channel: ChannelId,
+    staff_role: Option<u64>,
+    appeal: &Appeal,
+) -> Result<Message, SerenityError> {
+    let policy = &mention_policy(ctx).await;
+    let staff_roles: &[u64] = &staff_role.into_iter().collect::<Vec<_>>();
+    rest::call(ctx, "send_message", || {
+        staff_channel.send_message(&ctx.http, |m| {
+            m.allowed_mentions(|am| apply_mentions(am, policy, staff_roles));
+            if let Some(role_id) = staff_role {
+                m.content(format!("<@&{}>", role_id));
+            }
+
+            m.embed(|e| {
+                e.title(format!("Appeal #{}", appeal.id))
+                    .description(&appeal.content)
+                    .color(DEFAULT_COLOR)
+                    .field(
+                        "User",
+                        format!("<@{}> (`{}`)", appeal.user_id, appeal.user_id),
+                        true,
+                    )
+                    .field(
+                        "Punishment",
+                        format!("{} (case #{})", appeal.kind, appeal.case_id),
+                        true,
+                    )
+            })
+            .components(|c| {
+                c.create_action_row(|row| {
+                    row.create_button(|b| {
+                        b.custom_id(format!("appeal:accept:{}:{}", guild_id, appeal.id))
+                            .label("Accept")
+                            .style(ButtonStyle::Success)
+                    })
+                    .create_button(|b| {
+                        b.custom_id(format!("appeal:deny:{}:{}", guild_id, appeal.id))
+                            .label("Deny")
+                            .style(ButtonStyle::Danger)
+                    })
+                })
+            })
+        })
+    })
+    .await
+}
+
+/// Lift the punishment an accepted appeal was against, recording it once
+/// Discord has done so.
+///
+/// The unban event may arrive before the lift is recorded; it then records the
+/// unban itself and there's nothing left here to lift.
+async fn lift(
+    ctx: &Context,
+    store: &JsonStore<ModerationData>,
+    guild_id: GuildId,
+    appeal: &Appeal,
+    staff_id: UserId,
+) -> CommandResult {
+    let user_id = UserId(appeal.user_id);
+    match appeal.kind {
+        CaseKind::Ban => {
+            rest::call_with(ctx, Priority::Moderation, "unban", || {
+                guild_id.unban(&ctx.http, user_id)
+            })
+            .await?
+        }
+        _ => {
+            rest::call_with(ctx, Priority::Moderation, "edit_member", || {
+                guild_id.edit_member(&ctx.http, user_id, |m| m.enable_communication())
+            })
+            .await?;
+        }
+    }
+
+    let reason = format!("Appeal #{} accepted", appeal.id);
+    store
+        .update(|data| {
+            data.guild_mut(guild_id)
+                .lift_punishment(user_id, Some(staff_id), Some(reason.clone()))
+        })
+        .await?;
+
+    let action = match appeal.kind {
+        CaseKind::Ban => format!("Unban <@{}>", user_id),
+        _ => format!("Remove the timeout of <@{}>", user_id),
+    };
+    let event = AuditEvent {
+        guild_id: Some(guild_id),
+        actor_id: Some(staff_id),
+        source: AuditSource::Interaction,
+        action,
+        reason: Some(reason),
+    };
+    audit::record(ctx, event).await;
+    Ok(())
+}
+
+/// Accept or deny an appeal from the staff channel.
+async fn decide(
+    ctx: &Context,
+    component: &MessageComponentInteraction,
+    guild_id: GuildId,
+    appeal_id: u64,
+    accept: bool,
+) -> CommandResult {
+    let store = {
+        let data = ctx.data.read().await;
+        data.get::<ModerationKey>().cloned()
+    }
+    .ok_or("Moderation store is not loaded")?;
+
+    let appeal = {
+        let data = store.read().await;
+        data.guild(guild_id)
+            .and_then(|guild| guild.appeals.get(&appeal_id))
+            .cloned()
+    };
+    let appeal = match appeal {
+        Some(appeal) if appeal.status == AppealStatus::Pending => appeal,
+        Some(_) => {
+            reply_ephemeral(ctx, component, "This appeal was already decided.").await?;
+            return Ok(());
+        }
+        None => {
+            reply_ephemeral(ctx, component, "This appeal no longer exists.").await?;
+            return Ok(());
+        }
+    };
+
+    // Deciding requires the permission needed to lift the punishment
+    let required = match appeal.kind {
+        CaseKind::Ban => Permissions::BAN_MEMBERS,
+        _ => Permissions::MODERATE_MEMBERS,
+    };
+    let permissions = component
+        .member
+        .as_ref()
+        .and_then(|member| member.permissions)
+        .unwrap_or_else(Permissions::empty);
+    if !permissions.contains(required) {
+        let content = format!(
+            "You need the {} permission to decide this appeal.",
+            required
+        );
+        reply_ephemeral(ctx, component, &content).await?;
+        return Ok(());
+    }
+
+    let staff_id = component.user.id;
+    let user_id = UserId(appeal.user_id);
+    let status = if accept {
+        AppealStatus::Accepted
+    } else {
+        AppealStatus::Denied
+    };
+
+    // Claim the decision before acting, checking again that it's pending in case
+    // staff decided it meanwhile
+    let decided = store
+        .update(
+            |data| match data.guild_mut(guild_id).appeals.get_mut(&appeal_id) {
+                Some(appeal) if appeal.status == AppealStatus::Pending => {
+                    appeal.status = status;
+                    appeal.decided_by = Some(staff_id.0);
+                    true
+                }
+                _ => false,
+            },
+        )
+        .await?;
+    if !decided {
+        reply_ephemeral(ctx, component, "This appeal was already decided.").await?;
+        return Ok(());
+    }
+
+    let lift_error = if accept {
+        lift(ctx, &store, guild_id, &appeal, staff_id).await.err()
+    } else {
+        None
+    };
+
+    let mut embed = component
+        .message
+        .embeds
+        .first()
+        .cloned()
+        .map(CreateEmbed::from)
+        .unwrap_or_default();
+    let decision = if accept { "Accepted" } else { "Denied" };
+    embed
+        .color(if accept { SUCCESS_COLOR } else { ERROR_COLOR })
+        .field(
+            "Decision",
+            format!("{} by <@{}>", decision, staff_id),
+            false,
+        );
+    if let Some(e) = &lift_error {
+        warn!("Failed to lift punishment for appeal #{}: {}", appeal_id, e);
+        embed.field(
+            "⚠️ Couldn't lift the punishment",
+            format!("{}. Please lift it manually.", e),
+            false,
+        );
+    }
+
+    component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| d.set_embed(embed).components(|c| c))
+        })
+        .await?;
+
+    // Let the user know unless they turned moderation DMs off, ignoring closed DMs
+    let guild_name = guild_id
+        .name(&ctx.cache)
+        .unwrap_or_else(|| "the server".to_string());
+    let punishment = appeal.kind.to_string().to_lowercase();
+    let description = if accept && lift_error.is_none() {
+        format!(
+            "Your appeal in **{}** was accepted and your {} has been lifted.",
+            guild_name, punishment
+        )
+    } else if accept {
+        format!(
+            "Your appeal in **{}** was accepted. Staff will follow up to lift your {}.",
+            guild_name, punishment
+        )
+    } else {
+        format!("Your appeal in **{}** was denied.", guild_name)
+    };
+
+    let dms = match dm_service(ctx).await {
+        Some(dms) => dms,
+        None => return Ok(()),
+    };
+    let dm = dms
+        .send(&ctx.http, user_id, Some(DmCategory::Moderation), |m| {
+            m.embed(|e| {
+                e.title(format!("Appeal {}", decision.to_lowercase()))
+                    .description(description)
+                    .color(if accept { SUCCESS_COLOR } else { ERROR_COLOR })
+            })
+        })
+        .await;
+    if let Err(e) = dm {
+        warn!("Couldn't DM appeal decision to {}: {}", user_id, e);
+    }
+
+    Ok(())
+}
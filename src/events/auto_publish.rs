@@ -0,0 +1,149 @@
+//! Handler that publishes messages posted in auto-publish announcement channels.
+
+use async_trait::async_trait;
+use serenity::model::channel::{ChannelType, Message, MessageFlags, MessageType};
+use serenity::model::id::ChannelId;
+use serenity::prelude::*;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::{debug, error, warn};
+
+use crate::framework::event_handler::{EventHandler, Recover};
+use crate::models::guild_config::{guild_config, AutoPublish};
+use crate::utils::constants::PUBLISH_LIMIT_PER_HOUR;
+use crate::utils::rest;
+
+/// Window Discord applies the publish limit to.
+const PUBLISH_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// Counts publishes per channel within the last hour.
+#[derive(Debug, Default)]
+struct PublishLimiter {
+    recent: HashMap<ChannelId, VecDeque<Instant>>,
+}
+
+impl PublishLimiter {
+    /// Take a publish slot for a channel. Returns `false` if the channel has used up
+    /// its publishes for the hour.
+    fn try_acquire(&mut self, channel_id: ChannelId, now: Instant) -> bool {
+        let recent = self.recent.entry(channel_id).or_default();
+        while recent
+            .front()
+            .is_some_and(|&at| now.duration_since(at) >= PUBLISH_WINDOW)
+        {
+            recent.pop_front();
+        }
+
+        if recent.len() >= PUBLISH_LIMIT_PER_HOUR {
+            return false;
+        }
+        recent.push_back(now);
+        true
+    }
+}
+
+/// Crossposts messages in announcement channels that have auto-publish enabled.
+///
+/// Discord allows 10 publishes per channel per hour; messages past that are left
+/// unpublished instead of queueing behind the rate limit.
+pub struct AutoPublishHandler {
+    limiter: Mutex<PublishLimiter>,
+}
+
+impl AutoPublishHandler {
+    /// Create a new AutoPublishHandler.
+    pub fn new() -> Self {
+        Self {
+            limiter: Mutex::new(PublishLimiter::default()),
+        }
+    }
+}
+
+impl Default for AutoPublishHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Recover for AutoPublishHandler {
+    fn recover(&self) -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl EventHandler for AutoPublishHandler {
+    fn event_type(&self) -> &'static str {
+        "message"
+    }
+
+    async fn on_message(&self, ctx: Context, msg: &Message) {
+        let guild_id = match msg.guild_id {
+            Some(guild_id) => guild_id,
+            None => return,
+        };
+        if !matches!(msg.kind, MessageType::Regular | MessageType::InlineReply)
+            || msg
+                .flags
+                .is_some_and(|flags| flags.contains(MessageFlags::CROSSPOSTED))
+        {
+            return;
+        }
+
+        let mode = match guild_config(&ctx, guild_id)
+            .await
+            .auto_publish(msg.channel_id)
+        {
+            Some(mode) => mode,
+            None => return,
+        };
+        if mode == AutoPublish::Bot && msg.author.id != ctx.cache.current_user_id() {
+            return;
+        }
+        let is_news = ctx
+            .cache
+            .guild_channel(msg.channel_id)
+            .is_some_and(|channel| channel.kind == ChannelType::News);
+        if !is_news {
+            return;
+        }
+
+        if !self
+            .limiter
+            .lock()
+            .await
+            .try_acquire(msg.channel_id, Instant::now())
+        {
+            warn!(
+                "Not publishing {} in {}: the hourly publish limit was reached",
+                msg.id, msg.channel_id
+            );
+            return;
+        }
+
+        match rest::call(&ctx, "crosspost", || msg.crosspost(&ctx.http)).await {
+            Ok(_) => debug!("Published {} in {}", msg.id, msg.channel_id),
+            Err(e) => error!("Failed to publish {} in {}: {}", msg.id, msg.channel_id, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_publishes_per_channel_per_hour() {
+        let mut limiter = PublishLimiter::default();
+        let start = Instant::now();
+
+        for _ in 0..PUBLISH_LIMIT_PER_HOUR {
+            assert!(limiter.try_acquire(ChannelId(1), start));
+        }
+        assert!(!limiter.try_acquire(ChannelId(1), start));
+        assert!(limiter.try_acquire(ChannelId(2), start));
+
+        assert!(limiter.try_acquire(ChannelId(1), start + PUBLISH_WINDOW));
+    }
+}
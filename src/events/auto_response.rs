@@ -0,0 +1,219 @@
+//! Evaluates auto-response rules against messages that weren't commands.
+
+use rand::Rng;
+use regex::Regex;
+use serenity::model::channel::{Message, ReactionType};
+use serenity::model::id::{ChannelId, GuildId};
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::{debug, error};
+
+use crate::models::auto_response::{
+    render_template, AutoResponse, AutoResponseKey, Response, TemplateContext, TriggerKind,
+};
+use crate::models::ignore::ignored;
+use crate::utils::constants::DEFAULT_COLOR;
+use crate::utils::helpers::{apply_mentions, compile_regex, mention_policy};
+use crate::utils::rest;
+
+/// Compiled regexes and cooldowns for auto-response rules.
+#[derive(Default)]
+pub struct AutoResponder {
+    /// Compiled regex triggers by pattern. `None` if the pattern doesn't compile.
+    regexes: Mutex<HashMap<String, Option<Regex>>>,
+    /// When each rule last responded, by guild, rule ID and channel.
+    cooldowns: Mutex<HashMap<(GuildId, u64, ChannelId), Instant>>,
+}
+
+impl AutoResponder {
+    /// Respond to a message with the first matching rule in its guild, if any.
+    pub async fn handle(&self, ctx: &Context, msg: &Message) {
+        let guild_id = match msg.guild_id {
+            Some(guild_id) => guild_id,
+            None => return,
+        };
+        if ignored(ctx, msg).await.passive {
+            return;
+        }
+        let store = {
+            let data = ctx.data.read().await;
+            data.get::<AutoResponseKey>().cloned()
+        };
+        let store = match store {
+            Some(store) => store,
+            None => return,
+        };
+
+        let rules: Vec<AutoResponse> = match store.read().await.guild(guild_id) {
+            Some(guild) => guild
+                .rules
+                .iter()
+                .filter(|rule| rule.applies_in(msg.channel_id))
+                .cloned()
+                .collect(),
+            None => return,
+        };
+
+        for rule in rules {
+            if !self.matches(&rule, &msg.content).await {
+                continue;
+            }
+            if rule.chance < 100 && rand::thread_rng().gen_range(0..100) >= rule.chance {
+                return;
+            }
+            if !self
+                .try_cooldown(guild_id, &rule, msg.channel_id, Instant::now())
+                .await
+            {
+                debug!("Auto-response {} is on cooldown", rule.id);
+                return;
+            }
+
+            if let Err(e) = respond(ctx, msg, guild_id, &rule.response).await {
+                error!("Failed to send auto-response {}: {}", rule.id, e);
+            }
+            return;
+        }
+    }
+
+    /// Whether a message matches a rule's trigger.
+    async fn matches(&self, rule: &AutoResponse, content: &str) -> bool {
+        if rule.trigger != TriggerKind::Regex {
+            return rule.matches_text(content);
+        }
+        let mut regexes = self.regexes.lock().await;
+        regexes
+            .entry(rule.pattern.clone())
+            .or_insert_with(|| compile_regex(&rule.pattern).ok())
+            .as_ref()
+            .is_some_and(|regex| regex.is_match(content))
+    }
+
+    /// Start a rule's cooldown in a channel. Returns `false` if it is still running.
+    async fn try_cooldown(
+        &self,
+        guild_id: GuildId,
+        rule: &AutoResponse,
+        channel_id: ChannelId,
+        now: Instant,
+    ) -> bool {
+        if rule.cooldown == 0 {
+            return true;
+        }
+        let cooldown = Duration::from_secs(rule.cooldown);
+        let mut cooldowns = self.cooldowns.lock().await;
+        let key = (guild_id, rule.id, channel_id);
+        if cooldowns
+            .get(&key)
+            .is_some_and(|&last| now.duration_since(last) < cooldown)
+        {
+            return false;
+        }
+        cooldowns.insert(key, now);
+        true
+    }
+}
+
+/// Send a rule's response to a message.
+async fn respond(
+    ctx: &Context,
+    msg: &Message,
+    guild_id: GuildId,
+    response: &Response,
+) -> Result<(), SerenityError> {
+    let template = match response {
+        Response::Reaction(emoji) => {
+            let reaction = match ReactionType::try_from(emoji.as_str()) {
+                Ok(reaction) => reaction,
+                Err(_) => {
+                    debug!("Skipping auto-response with invalid emoji {}", emoji);
+                    return Ok(());
+                }
+            };
+            rest::call(ctx, "react", || msg.react(&ctx.http, reaction.clone())).await?;
+            return Ok(());
+        }
+        Response::Text(template) | Response::Embed(template) => template,
+    };
+
+    let server_name = ctx
+        .cache
+        .guild_field(guild_id, |guild| guild.name.clone())
+        .unwrap_or_default();
+    let content = render_template(
+        template,
+        &TemplateContext {
+            user_id: msg.author.id.0,
+            user_name: &msg.author.name,
+            channel_id: msg.channel_id.0,
+            server_name: &server_name,
+        },
+    );
+
+    let policy = &mention_policy(ctx).await;
+    let is_embed = matches!(response, Response::Embed(_));
+    let content = &content;
+    rest::call(ctx, "send_message", || {
+        msg.channel_id.send_message(&ctx.http, move |m| {
+            m.allowed_mentions(|am| apply_mentions(am, policy, &[]));
+            if is_embed {
+                m.embed(|e| e.description(content).color(DEFAULT_COLOR))
+            } else {
+                m.content(content)
+            }
+        })
+    })
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::auto_response::GuildAutoResponses;
+
+    #[tokio::test]
+    async fn cooldowns_are_per_rule_and_channel() {
+        let mut guild = GuildAutoResponses::default();
+        guild.add(
+            TriggerKind::Contains,
+            "hi".to_string(),
+            Response::Text("hello".to_string()),
+            1,
+        );
+        let mut rule = guild.rules.remove(0);
+        rule.cooldown = 30;
+
+        let responder = AutoResponder::default();
+        let start = Instant::now();
+        let guild_id = GuildId(1);
+
+        assert!(
+            responder
+                .try_cooldown(guild_id, &rule, ChannelId(1), start)
+                .await
+        );
+        assert!(
+            !responder
+                .try_cooldown(guild_id, &rule, ChannelId(1), start)
+                .await
+        );
+        assert!(
+            responder
+                .try_cooldown(guild_id, &rule, ChannelId(2), start)
+                .await
+        );
+        assert!(
+            responder
+                .try_cooldown(
+                    guild_id,
+                    &rule,
+                    ChannelId(1),
+                    start + Duration::from_secs(30)
+                )
+                .await
+        );
+    }
+}
@@ -0,0 +1,79 @@
+//! Actions shared by the automod middleware.
+
+use serenity::model::id::{GuildId, UserId};
+use serenity::model::timestamp::Timestamp;
+use serenity::prelude::*;
+use tracing::error;
+
+use crate::models::moderation::{CaseKind, ModerationKey};
+use crate::utils::helpers::unix_timestamp;
+use crate::utils::rest::{self, Priority};
+
+/// Record a moderation case with the bot as moderator.
+pub(super) async fn record_case(
+    ctx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    kind: CaseKind,
+    reason: &str,
+    expires_at: Option<u64>,
+) {
+    let store = {
+        let data = ctx.data.read().await;
+        data.get::<ModerationKey>().cloned()
+    };
+    let store = match store {
+        Some(store) => store,
+        None => return,
+    };
+
+    let bot_id = ctx.cache.current_user_id();
+    let result = store
+        .update(|data| {
+            data.guild_mut(guild_id).add_case(
+                user_id,
+                kind,
+                Some(bot_id),
+                Some(reason.to_string()),
+                expires_at,
+            )
+        })
+        .await;
+    if let Err(e) = result {
+        error!("Failed to record {} case: {}", kind, e);
+    }
+}
+
+/// Time a member out for `seconds` and record the case.
+///
+/// The case is recorded with the bot as moderator before the member update arrives,
+/// so the update isn't recorded a second time.
+pub(super) async fn timeout(
+    ctx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    seconds: u64,
+    reason: &str,
+) -> Result<(), SerenityError> {
+    // Callers keep the duration within Discord's 28 day limit
+    let until = unix_timestamp() + seconds;
+    let timestamp =
+        Timestamp::from_unix_timestamp(until as i64).unwrap_or_else(|_| Timestamp::now());
+    rest::call_with(ctx, Priority::Moderation, "timeout", || {
+        guild_id.edit_member(&ctx.http, user_id, |m| {
+            m.disable_communication_until_datetime(timestamp)
+        })
+    })
+    .await?;
+
+    record_case(
+        ctx,
+        guild_id,
+        user_id,
+        CaseKind::Timeout,
+        reason,
+        Some(until),
+    )
+    .await;
+    Ok(())
+}
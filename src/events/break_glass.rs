@@ -0,0 +1,75 @@
+//! Handler for the buttons that confirm critical owner commands, see
+//! [`crate::framework::break_glass`].
+
+use async_trait::async_trait;
+use serenity::model::application::interaction::{Interaction, InteractionResponseType};
+use serenity::prelude::*;
+use tracing::{error, warn};
+
+use crate::framework::break_glass::{BreakGlassKey, CUSTOM_ID_PREFIX};
+use crate::framework::event_handler::EventHandler;
+use crate::utils::helpers::{is_owner, reply_ephemeral};
+
+/// Hands Confirm and Deny presses by bot owners to the command waiting for them.
+pub struct BreakGlassHandler;
+
+#[async_trait]
+impl EventHandler for BreakGlassHandler {
+    fn event_type(&self) -> &'static str {
+        "interaction"
+    }
+
+    async fn on_interaction(&self, ctx: Context, interaction: &Interaction) {
+        let component = match interaction {
+            Interaction::MessageComponent(component) => component,
+            _ => return,
+        };
+        let (approved, id) = match component
+            .data
+            .custom_id
+            .strip_prefix(CUSTOM_ID_PREFIX)
+            .and_then(|rest| rest.split_once(':'))
+        {
+            Some(("confirm", id)) => (true, id),
+            Some(("deny", id)) => (false, id),
+            _ => return,
+        };
+
+        let prompt = (component.channel_id, component.message.id);
+        let reply = if !is_owner(&ctx, component.user.id).await {
+            warn!(
+                "{} pressed a critical command button without being an owner",
+                component.user.id
+            );
+            Some("Only bot owners can answer this.")
+        } else {
+            let break_glass = {
+                let data = ctx.data.read().await;
+                data.get::<BreakGlassKey>().cloned()
+            };
+            match break_glass {
+                Some(break_glass)
+                    if break_glass.resolve(id, prompt, approved, component.user.id) =>
+                {
+                    None
+                }
+                _ => Some("This request has already been answered or expired."),
+            }
+        };
+
+        // The waiting command updates the prompt itself once it's answered
+        let result = match reply {
+            Some(reply) => reply_ephemeral(&ctx, component, reply).await,
+            None => {
+                component
+                    .create_interaction_response(&ctx.http, |r| {
+                        r.kind(InteractionResponseType::DeferredUpdateMessage)
+                    })
+                    .await
+            }
+        };
+        if let Err(e) = result {
+            error!("Failed to answer critical command button: {}", e);
+        }
+    }
+}
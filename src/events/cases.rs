@@ -0,0 +1,121 @@
+//! Handlers that record bans and timeouts as moderation cases.
+
+use async_trait::async_trait;
+use serenity::model::guild::Member;
+use serenity::model::id::GuildId;
+use serenity::model::user::User;
+use serenity::prelude::*;
+use tracing::{debug, error};
+
+use crate::framework::event_handler::EventHandler;
+use crate::models::moderation::{CaseKind, ModerationKey};
+use crate::utils::helpers::unix_timestamp;
+
+/// Record a case unless the user's latest case already describes the same action.
+///
+/// Actions the bot takes itself are recorded with the acting moderator before
+/// the gateway event arrives, so the event must not record them a second time.
+/// Repeated member updates during one timeout carry the same expiry.
+async fn record_case(
+    ctx: &Context,
+    guild_id: GuildId,
+    user: &User,
+    kind: CaseKind,
+    expires_at: Option<u64>,
+) {
+    let store = {
+        let data = ctx.data.read().await;
+        data.get::<ModerationKey>().cloned()
+    };
+    let store = match store {
+        Some(store) => store,
+        None => return,
+    };
+
+    let result = store
+        .update(|data| {
+            let guild = data.guild_mut(guild_id);
+            let duplicate = guild.latest_case(user.id).is_some_and(|case| {
+                case.kind == kind
+                    && ((expires_at.is_some() && case.expires_at == expires_at)
+                        || unix_timestamp().saturating_sub(case.created_at) < 60)
+            });
+
+            if !duplicate {
+                guild.add_case(user.id, kind, None, None, expires_at);
+            }
+        })
+        .await;
+
+    match result {
+        Ok(()) => debug!("Recorded {} for {} in guild {}", kind, user.id, guild_id),
+        Err(e) => error!("Failed to record {} case: {}", kind, e),
+    }
+}
+
+/// Records bans made through Discord.
+pub struct BanRecordHandler;
+
+#[async_trait]
+impl EventHandler for BanRecordHandler {
+    fn event_type(&self) -> &'static str {
+        "guild_ban_add"
+    }
+
+    async fn on_guild_ban_add(&self, ctx: Context, guild_id: GuildId, user: &User) {
+        record_case(&ctx, guild_id, user, CaseKind::Ban, None).await;
+    }
+}
+
+/// Records unbans made through Discord.
+pub struct UnbanRecordHandler;
+
+#[async_trait]
+impl EventHandler for UnbanRecordHandler {
+    fn event_type(&self) -> &'static str {
+        "guild_ban_remove"
+    }
+
+    async fn on_guild_ban_remove(&self, ctx: Context, guild_id: GuildId, user: &User) {
+        record_case(&ctx, guild_id, user, CaseKind::Unban, None).await;
+    }
+}
+
+/// Records timeouts made through Discord.
+pub struct TimeoutRecordHandler;
+
+#[async_trait]
+impl EventHandler for TimeoutRecordHandler {
+    fn event_type(&self) -> &'static str {
+        "guild_member_update"
+    }
+
+    async fn on_guild_member_update(&self, ctx: Context, old: Option<&Member>, new: &Member) {
+        let now = unix_timestamp() as i64;
+        let timed_out_until = |member: &Member| {
+            member
+                .communication_disabled_until
+                .map(|t| t.unix_timestamp())
+                .filter(|t| *t > now)
+        };
+
+        let until = match timed_out_until(new) {
+            Some(until) => until,
+            None => return,
+        };
+
+        // Skip updates that didn't change the timeout
+        if old.and_then(timed_out_until) == Some(until) {
+            return;
+        }
+
+        record_case(
+            &ctx,
+            new.guild_id,
+            &new.user,
+            CaseKind::Timeout,
+            Some(until as u64),
+        )
+        .await;
+    }
+}
@@ -0,0 +1,51 @@
+//! Handler that syncs application commands when the bot connects.
+
+use async_trait::async_trait;
+use serde_json::Value;
+use serenity::model::gateway::Ready;
+use serenity::prelude::*;
+use tracing::{error, info};
+
+use crate::framework::app_commands::{self, SyncScope};
+use crate::framework::event_handler::EventHandler;
+
+/// Brings Discord's slash and context menu commands in line with the local ones.
+pub struct CommandSyncHandler {
+    commands: Vec<Value>,
+    scopes: Vec<SyncScope>,
+}
+
+impl CommandSyncHandler {
+    /// Create the handler with the commands to register and where to register them.
+    pub fn new(commands: Vec<Value>, scopes: Vec<SyncScope>) -> Self {
+        Self { commands, scopes }
+    }
+}
+
+#[async_trait]
+impl EventHandler for CommandSyncHandler {
+    fn event_type(&self) -> &'static str {
+        "ready"
+    }
+
+    async fn on_ready(&self, ctx: Context, _ready: &Ready) {
+        // Commands only need syncing once, not once per shard
+        if ctx.shard_id != 0 {
+            return;
+        }
+
+        for scope in &self.scopes {
+            match app_commands::sync(&ctx.http, *scope, &self.commands, false).await {
+                Ok(changes) if changes.is_empty() => {
+                    info!("Application commands ({}) are up to date", scope)
+                }
+                Ok(changes) => info!(
+                    "Synced application commands ({}) with {} changes",
+                    scope,
+                    changes.len()
+                ),
+                Err(e) => error!("Failed to sync application commands ({}): {}", scope, e),
+            }
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! Handler for the select menus offered by `crate`, `repo` and `docs`.
+
+use async_trait::async_trait;
+use serenity::builder::CreateEmbed;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::{Interaction, InteractionResponseType};
+use serenity::prelude::*;
+use tracing::error;
+
+use crate::framework::command_handler::CommandResult;
+use crate::framework::event_handler::EventHandler;
+use crate::utils::devlookup::{crate_embed, docs_embed, repo_embed, DevLookupKey};
+use crate::utils::helpers::reply_ephemeral;
+
+/// Shows the result picked from a lookup's search results.
+pub struct DevLookupHandler;
+
+#[async_trait]
+impl EventHandler for DevLookupHandler {
+    fn event_type(&self) -> &'static str {
+        "interaction"
+    }
+
+    async fn on_interaction(&self, ctx: Context, interaction: &Interaction) {
+        let component = match interaction {
+            Interaction::MessageComponent(component) => component,
+            _ => return,
+        };
+        // The docs path is last and may contain colons itself
+        let parts: Vec<&str> = match component.data.custom_id.strip_prefix("devlookup:") {
+            Some(rest) => rest.splitn(3, ':').collect(),
+            None => return,
+        };
+        let (kind, user_id, path) = match parts.as_slice() {
+            [kind, user_id] => (*kind, *user_id, None),
+            [kind, user_id, path] => (*kind, *user_id, Some(*path)),
+            _ => return,
+        };
+
+        if user_id.parse::<u64>().ok() != Some(component.user.id.0) {
+            if let Err(e) =
+                reply_ephemeral(&ctx, component, "Only the person who asked can pick.").await
+            {
+                error!("Failed to answer a lookup menu: {}", e);
+            }
+            return;
+        }
+        if let Err(e) = show_choice(&ctx, component, kind, path).await {
+            error!("Lookup menu failed: {:?}", e);
+        }
+    }
+}
+
+/// Replace the menu with the picked crate, repository or docs.
+async fn show_choice(
+    ctx: &Context,
+    component: &MessageComponentInteraction,
+    kind: &str,
+    path: Option<&str>,
+) -> CommandResult {
+    let choice = match component.data.values.first() {
+        Some(choice) => choice,
+        None => return Ok(()),
+    };
+    let lookup = match ctx.data.read().await.get::<DevLookupKey>().cloned() {
+        Some(lookup) => lookup,
+        None => return Ok(()),
+    };
+
+    let embed: Option<CreateEmbed> = match kind {
+        "crate" => lookup.krate(choice).await?.map(|info| crate_embed(&info)),
+        "docs" => lookup
+            .krate(choice)
+            .await?
+            .map(|info| docs_embed(&info, path)),
+        "repo" => lookup.repo(choice).await?.map(|info| repo_embed(&info)),
+        _ => return Ok(()),
+    };
+    let embed = match embed {
+        Some(embed) => embed,
+        None => {
+            reply_ephemeral(
+                ctx,
+                component,
+                "That result is gone now, try searching again.",
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| d.set_embed(embed).components(|c| c))
+        })
+        .await?;
+    Ok(())
+}
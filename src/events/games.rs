@@ -0,0 +1,112 @@
+//! Handler that runs the counting and word chain game channels.
+
+use async_trait::async_trait;
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use tracing::{debug, error, warn};
+
+use crate::framework::event_handler::EventHandler;
+use crate::models::games::{GamesKey, Play};
+use crate::models::ignore::ignored;
+use crate::utils::helpers::{apply_mentions, mention_policy};
+use crate::utils::rest;
+
+/// Checks messages in game channels against the rules.
+///
+/// Wrong entries are deleted; with `reset` on, they also end the streak. Milestones
+/// and new server records are celebrated in the channel.
+pub struct GameHandler;
+
+#[async_trait]
+impl EventHandler for GameHandler {
+    fn event_type(&self) -> &'static str {
+        "message"
+    }
+
+    async fn on_message(&self, ctx: Context, msg: &Message) {
+        let guild_id = match msg.guild_id {
+            Some(guild_id) if !msg.author.bot => guild_id,
+            _ => return,
+        };
+        let store = {
+            let data = ctx.data.read().await;
+            data.get::<GamesKey>().cloned()
+        };
+        let store = match store {
+            Some(store) => store,
+            None => return,
+        };
+
+        // Most channels have no game, so check before taking the write lock
+        let is_game = store
+            .read()
+            .await
+            .guilds
+            .get(&guild_id.0)
+            .is_some_and(|guild| guild.channels.contains_key(&msg.channel_id.0));
+        if is_game && ignored(&ctx, msg).await.passive {
+            return;
+        }
+        if !is_game {
+            return;
+        }
+
+        // Entries are checked under the store's lock, so two players can't both
+        // take the same number
+        let result = store
+            .update(|data| {
+                let guild = data.guilds.get_mut(&guild_id.0)?;
+                let play = guild.play(msg.channel_id, msg.author.id, &msg.content)?;
+                let hint = guild.channels.get(&msg.channel_id.0)?.next_hint();
+                Some((play, hint))
+            })
+            .await;
+        let (play, hint) = match result {
+            Ok(Some(outcome)) => outcome,
+            Ok(None) => return,
+            Err(e) => {
+                error!("Failed to record game entry: {}", e);
+                return;
+            }
+        };
+
+        let announcement = match play {
+            Play::Invalid { reason, ended } => {
+                debug!("Invalid game entry in {}: {}", msg.channel_id, reason);
+                if let Err(e) = rest::call(&ctx, "delete_message", || msg.delete(&ctx.http)).await {
+                    warn!("Failed to delete game entry: {}", e);
+                }
+                ended.map(|streak| {
+                    format!(
+                        "💥 <@{}> ended the streak at **{}**. {} Start over: {}.",
+                        msg.author.id, streak, reason, hint
+                    )
+                })
+            }
+            Play::Valid {
+                streak,
+                milestone,
+                record,
+            } => match (milestone, record) {
+                (true, true) => Some(format!("🎉 **{}**! That's a new server record!", streak)),
+                (true, false) => Some(format!("🎉 **{}**! Keep it going!", streak)),
+                (false, true) => Some(format!("🏆 **{}** beats the server record!", streak)),
+                (false, false) => None,
+            },
+        };
+
+        if let Some(announcement) = announcement {
+            let policy = &mention_policy(&ctx).await;
+            let sent = rest::call(&ctx, "send_message", || {
+                msg.channel_id.send_message(&ctx.http, |m| {
+                    m.content(&announcement)
+                        .allowed_mentions(|am| apply_mentions(am, policy, &[]))
+                })
+            })
+            .await;
+            if let Err(e) = sent {
+                warn!("Failed to announce game streak: {}", e);
+            }
+        }
+    }
+}
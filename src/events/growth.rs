@@ -0,0 +1,105 @@
+//! Handlers that save member counts and announce milestones.
+
+use async_trait::async_trait;
+use serenity::model::guild::Member;
+use serenity::model::id::{ChannelId, GuildId};
+use serenity::model::user::User;
+use serenity::prelude::*;
+use tracing::{error, info, warn};
+
+use crate::framework::event_handler::EventHandler;
+use crate::models::analytics::today;
+use crate::models::growth::GrowthKey;
+use crate::models::guild_config::guild_config;
+use crate::utils::constants::SUCCESS_COLOR;
+use crate::utils::helpers::format_count;
+use crate::utils::rest::{self, Priority};
+
+/// Save a guild's member count from the cache, and announce a milestone if it
+/// reached one.
+async fn record(ctx: &Context, guild_id: GuildId) {
+    let count = match ctx.cache.guild_field(guild_id, |guild| guild.member_count) {
+        Some(count) => count,
+        None => return,
+    };
+    let store = {
+        let data = ctx.data.read().await;
+        data.get::<GrowthKey>().cloned()
+    };
+    let store = match store {
+        Some(store) => store,
+        None => return,
+    };
+
+    let config = guild_config(ctx, guild_id).await;
+    let milestones = config.milestones();
+    let reached = match store
+        .update(|data| data.entry(guild_id).record(count, today(), milestones))
+        .await
+    {
+        Ok(reached) => reached,
+        Err(e) => {
+            error!("Failed to save member count of guild {}: {}", guild_id, e);
+            return;
+        }
+    };
+    let (milestone, channel_id) = match (reached, config.milestone_channel) {
+        (Some(milestone), Some(channel_id)) => (milestone, ChannelId(channel_id)),
+        _ => return,
+    };
+
+    info!("Guild {} reached {} members", guild_id, milestone);
+    let guild_name = guild_id
+        .name(&ctx.cache)
+        .unwrap_or_else(|| "the server".to_string());
+    let result = rest::call_with(ctx, Priority::Analytics, "send_message", || {
+        channel_id.send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.title(format!("🎉 We hit {} members!", format_count(milestone)))
+                    .description(format!("Thank you for being part of {}!", guild_name))
+                    .color(SUCCESS_COLOR)
+            })
+        })
+    })
+    .await;
+    if let Err(e) = result {
+        warn!(
+            "Failed to announce a milestone in guild {}: {}",
+            guild_id, e
+        );
+    }
+}
+
+/// Saves the member count when someone joins.
+pub struct GrowthJoinHandler;
+
+#[async_trait]
+impl EventHandler for GrowthJoinHandler {
+    fn event_type(&self) -> &'static str {
+        "guild_member_add"
+    }
+
+    async fn on_guild_member_add(&self, ctx: Context, guild_id: GuildId, _member: &Member) {
+        record(&ctx, guild_id).await;
+    }
+}
+
+/// Saves the member count when someone leaves.
+pub struct GrowthLeaveHandler;
+
+#[async_trait]
+impl EventHandler for GrowthLeaveHandler {
+    fn event_type(&self) -> &'static str {
+        "guild_member_remove"
+    }
+
+    async fn on_guild_member_remove(
+        &self,
+        ctx: Context,
+        guild_id: GuildId,
+        _user: &User,
+        _member: Option<&Member>,
+    ) {
+        record(&ctx, guild_id).await;
+    }
+}
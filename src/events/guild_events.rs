@@ -0,0 +1,488 @@
+//! Handler for the button and modal interactions of events.
+//!
+//! The flow uses these component IDs:
+//! - `event:start:<user>:<sync>`: button sent by `event create`, opens the modal.
+//! - `event:submit:<sync>`: the modal, posts the event where it was opened.
+//! - `event:rsvp:<event>:<answer>`: the RSVP buttons under an event.
+//!
+//! `<sync>` is `1` when the event should also be created as a Discord
+//! scheduled event.
+
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use serenity::builder::CreateEmbed;
+use serenity::model::application::component::{ActionRowComponent, ButtonStyle, InputTextStyle};
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::modal::ModalSubmitInteraction;
+use serenity::model::application::interaction::{Interaction, InteractionResponseType};
+use serenity::model::channel::ReactionType;
+use serenity::model::guild::ScheduledEventType;
+use serenity::model::Timestamp;
+use serenity::prelude::*;
+use std::collections::BTreeMap;
+use tracing::{error, warn};
+
+use crate::framework::command_handler::CommandResult;
+use crate::framework::event_handler::EventHandler;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::guild_events::{EventKey, GuildEvent, Rsvp, MAX_EVENTS};
+use crate::utils::constants::DEFAULT_COLOR;
+use crate::utils::duration::{self, relative, timestamp, TimestampStyle};
+use crate::utils::helpers::{reply_ephemeral, truncate, unix_timestamp};
+
+/// Furthest ahead an event can start, in seconds.
+const MAX_LEAD: u64 = 365 * 24 * 60 * 60;
+
+/// How long Discord scheduled events last, since external events need an end.
+const SCHEDULED_LENGTH: u64 = 60 * 60;
+
+/// Parse when an event starts: a duration from now such as `in 2h`, or a UTC
+/// date and time such as `2026-10-20 18:00`.
+fn parse_start(input: &str, now: u64) -> Result<u64, String> {
+    let input = input.trim();
+    let lowered = input.to_lowercase();
+    let relative = lowered.strip_prefix("in ").unwrap_or(&lowered);
+    let start = match duration::parse(relative) {
+        Ok(duration) => now + duration.as_secs(),
+        Err(_) => {
+            let date = lowered.trim_end_matches("utc").trim();
+            let parsed = ["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"]
+                .iter()
+                .find_map(|format| NaiveDateTime::parse_from_str(date, format).ok())
+                .ok_or_else(|| {
+                    format!(
+                        "Couldn't read `{}` as a time. Use a duration like `in 2h` or a UTC date like `2026-10-20 18:00`.",
+                        truncate(input, 50)
+                    )
+                })?;
+            u64::try_from(parsed.and_utc().timestamp())
+                .map_err(|_| "Events can't start in the past.".to_string())?
+        }
+    };
+
+    if start <= now {
+        return Err("Events can't start in the past.".to_string());
+    }
+    if start - now > MAX_LEAD {
+        return Err("Events can start at most a year from now.".to_string());
+    }
+    Ok(start)
+}
+
+/// List members as mentions, cutting the list off before it gets too long.
+fn mention_list(users: &[u64]) -> String {
+    if users.is_empty() {
+        return "—".to_string();
+    }
+    let mut out = String::new();
+    for (shown, user_id) in users.iter().enumerate() {
+        let mention = format!("<@{}>", user_id);
+        if out.len() + mention.len() > 950 {
+            out.push_str(&format!(" and {} more", users.len() - shown));
+            break;
+        }
+        if !out.is_empty() {
+            out.push_str(", ");
+        }
+        out.push_str(&mention);
+    }
+    out
+}
+
+/// Build the embed for an event.
+fn event_embed(event: &GuildEvent) -> CreateEmbed {
+    let mut description = String::new();
+    if !event.description.is_empty() {
+        description.push_str(&event.description);
+        description.push_str("\n\n");
+    }
+    description.push_str(&format!(
+        "🕒 {} ({})",
+        timestamp(event.start, TimestampStyle::LongDateTime),
+        relative(event.start)
+    ));
+    if let Some(location) = &event.location {
+        description.push_str(&format!("\n📍 {}", location));
+    }
+
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(format!("📅 {}", event.title))
+        .description(description)
+        .color(DEFAULT_COLOR)
+        .footer(|f| f.text(format!("Event #{}", event.id)));
+    for rsvp in Rsvp::ALL {
+        let attendees = event.attendees(rsvp);
+        embed.field(
+            format!("{} {} ({})", rsvp.emoji(), rsvp, attendees.len()),
+            mention_list(&attendees),
+            true,
+        );
+    }
+    embed
+}
+
+/// Handles event buttons and modals.
+pub struct EventInteractionHandler;
+
+#[async_trait]
+impl EventHandler for EventInteractionHandler {
+    fn event_type(&self) -> &'static str {
+        "interaction"
+    }
+
+    async fn on_interaction(&self, ctx: Context, interaction: &Interaction) {
+        let result = match interaction {
+            Interaction::MessageComponent(component) => {
+                let parts: Vec<&str> = match component.data.custom_id.strip_prefix("event:") {
+                    Some(rest) => rest.split(':').collect(),
+                    None => return,
+                };
+
+                match parts.as_slice() {
+                    ["start", user_id, sync] => {
+                        if user_id.parse::<u64>().ok() != Some(component.user.id.0) {
+                            reply_ephemeral(
+                                &ctx,
+                                component,
+                                "Only the person who asked can fill this in.",
+                            )
+                            .await
+                            .map_err(Into::into)
+                        } else {
+                            start(&ctx, component, *sync == "1").await
+                        }
+                    }
+                    ["rsvp", event_id, answer] => match (event_id.parse(), Rsvp::from_id(answer)) {
+                        (Ok(event_id), Some(rsvp)) => {
+                            answer_event(&ctx, component, event_id, rsvp).await
+                        }
+                        _ => return,
+                    },
+                    _ => return,
+                }
+            }
+            Interaction::ModalSubmit(modal) => {
+                match modal.data.custom_id.strip_prefix("event:submit:") {
+                    Some(sync) => submit(&ctx, modal, sync == "1").await,
+                    None => return,
+                }
+            }
+            _ => return,
+        };
+
+        if let Err(e) = result {
+            error!("Event interaction failed: {:?}", e);
+        }
+    }
+}
+
+/// Open the event form.
+async fn start(
+    ctx: &Context,
+    component: &MessageComponentInteraction,
+    sync: bool,
+) -> CommandResult {
+    component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::Modal)
+                .interaction_response_data(|d| {
+                    d.custom_id(format!("event:submit:{}", u8::from(sync)))
+                        .title("New event")
+                        .components(|c| {
+                            c.create_action_row(|row| {
+                                row.create_input_text(|t| {
+                                    t.custom_id("title")
+                                        .label("Title")
+                                        .style(InputTextStyle::Short)
+                                        .max_length(100)
+                                        .required(true)
+                                })
+                            })
+                            .create_action_row(|row| {
+                                row.create_input_text(|t| {
+                                    t.custom_id("start")
+                                        .label("Starts")
+                                        .placeholder("in 2h, or 2026-10-20 18:00 (UTC)")
+                                        .style(InputTextStyle::Short)
+                                        .max_length(50)
+                                        .required(true)
+                                })
+                            })
+                            .create_action_row(|row| {
+                                row.create_input_text(|t| {
+                                    t.custom_id("location")
+                                        .label("Location")
+                                        .style(InputTextStyle::Short)
+                                        .max_length(100)
+                                        .required(false)
+                                })
+                            })
+                            .create_action_row(|row| {
+                                row.create_input_text(|t| {
+                                    t.custom_id("description")
+                                        .label("Description")
+                                        .style(InputTextStyle::Paragraph)
+                                        .max_length(1000)
+                                        .required(false)
+                                })
+                            })
+                        })
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Post a submitted event with its RSVP buttons.
+async fn submit(ctx: &Context, modal: &ModalSubmitInteraction, sync: bool) -> CommandResult {
+    let guild_id = modal.guild_id.ok_or("Event modal outside a guild")?;
+    let inputs: BTreeMap<&str, String> = modal
+        .data
+        .components
+        .iter()
+        .flat_map(|row| &row.components)
+        .filter_map(|component| match component {
+            ActionRowComponent::InputText(input) => {
+                Some((input.custom_id.as_str(), input.value.trim().to_string()))
+            }
+            _ => None,
+        })
+        .collect();
+    let input = |id: &str| inputs.get(id).cloned().unwrap_or_default();
+
+    let now = unix_timestamp();
+    let store = {
+        let data = ctx.data.read().await;
+        data.get::<EventKey>().cloned()
+    }
+    .ok_or("Event store is not loaded")?;
+
+    let response = match parse_start(&input("start"), now) {
+        Err(reason) => reason,
+        Ok(start) => {
+            let location = Some(input("location")).filter(|location| !location.is_empty());
+            let event = GuildEvent {
+                id: 0,
+                title: input("title"),
+                description: input("description"),
+                location,
+                start,
+                created_by: modal.user.id.0,
+                channel_id: modal.channel_id.0,
+                message_id: None,
+                rsvps: BTreeMap::new(),
+                reminded: false,
+                scheduled_event_id: None,
+            };
+            let added = store
+                .update(|data| {
+                    let guild = data.guild_mut(guild_id);
+                    if guild.upcoming(now).count() >= MAX_EVENTS {
+                        return None;
+                    }
+                    let id = guild.add(event);
+                    guild.events.get(&id).cloned()
+                })
+                .await?;
+            match added {
+                None => format!(
+                    "This server already has {} upcoming events. Cancel one with `event cancel <id>` first.",
+                    MAX_EVENTS
+                ),
+                Some(event) => post(ctx, modal, event, sync).await?,
+            }
+        }
+    };
+
+    modal
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|d| d.content(response).ephemeral(true))
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Post a new event, create its Discord event if asked, and return what to
+/// tell its creator.
+async fn post(
+    ctx: &Context,
+    modal: &ModalSubmitInteraction,
+    event: GuildEvent,
+    sync: bool,
+) -> CommandResult<String> {
+    let guild_id = modal.guild_id.ok_or("Event modal outside a guild")?;
+    let store = {
+        let data = ctx.data.read().await;
+        data.get::<EventKey>().cloned()
+    }
+    .ok_or("Event store is not loaded")?;
+
+    let message = modal
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.set_embed(event_embed(&event)).components(|c| {
+                c.create_action_row(|row| {
+                    for rsvp in Rsvp::ALL {
+                        let style = match rsvp {
+                            Rsvp::Going => ButtonStyle::Success,
+                            Rsvp::Maybe => ButtonStyle::Secondary,
+                            Rsvp::NotGoing => ButtonStyle::Danger,
+                        };
+                        row.create_button(|b| {
+                            b.custom_id(format!("event:rsvp:{}:{}", event.id, rsvp.id()))
+                                .label(rsvp.to_string())
+                                .emoji(ReactionType::Unicode(rsvp.emoji().to_string()))
+                                .style(style)
+                        });
+                    }
+                    row
+                })
+            })
+        })
+        .await;
+    let message = match message {
+        Ok(message) => message,
+        Err(e) => {
+            store
+                .update(|data| data.guild_mut(guild_id).events.remove(&event.id))
+                .await?;
+            warn!("Failed to post event #{}: {}", event.id, e);
+            return Ok("I couldn't post the event in this channel.".to_string());
+        }
+    };
+
+    let mut note = String::new();
+    let mut scheduled_id = None;
+    if sync {
+        let location = match &event.location {
+            Some(location) => location.clone(),
+            None => format!(
+                "#{}",
+                modal.channel_id.name(&ctx.cache).await.unwrap_or_default()
+            ),
+        };
+        let start_time = Timestamp::from_unix_timestamp(event.start as i64)
+            .map_err(|_| "Invalid event start")?;
+        let end_time = Timestamp::from_unix_timestamp((event.start + SCHEDULED_LENGTH) as i64)
+            .map_err(|_| "Invalid event start")?;
+        let created = guild_id
+            .create_scheduled_event(&ctx.http, |e| {
+                e.name(&event.title)
+                    .kind(ScheduledEventType::External)
+                    .location(location)
+                    .start_time(start_time)
+                    .end_time(end_time);
+                if !event.description.is_empty() {
+                    e.description(&event.description);
+                }
+                e
+            })
+            .await;
+        match created {
+            Ok(scheduled) => scheduled_id = Some(scheduled.id.0),
+            Err(e) => {
+                warn!(
+                    "Failed to create a scheduled event for event #{}: {}",
+                    event.id, e
+                );
+                note = " I couldn't add it to the server's events, so check that I have the Manage Events permission.".to_string();
+            }
+        }
+    }
+
+    store
+        .update(|data| {
+            if let Some(saved) = data.guild_mut(guild_id).events.get_mut(&event.id) {
+                saved.message_id = Some(message.id.0);
+                saved.scheduled_event_id = scheduled_id;
+            }
+        })
+        .await?;
+
+    audit::record(
+        ctx,
+        AuditEvent {
+            guild_id: Some(guild_id),
+            actor_id: Some(modal.user.id),
+            source: AuditSource::Command,
+            action: format!("Created event #{} ({})", event.id, event.title),
+            reason: None,
+        },
+    )
+    .await;
+
+    Ok(format!("Posted event #{}.{}", event.id, note))
+}
+
+/// Record an RSVP and refresh the attendee lists.
+async fn answer_event(
+    ctx: &Context,
+    component: &MessageComponentInteraction,
+    event_id: u64,
+    rsvp: Rsvp,
+) -> CommandResult {
+    let guild_id = match component.guild_id {
+        Some(guild_id) => guild_id,
+        None => return Ok(()),
+    };
+    let store = {
+        let data = ctx.data.read().await;
+        data.get::<EventKey>().cloned()
+    }
+    .ok_or("Event store is not loaded")?;
+
+    let user_id = component.user.id.0;
+    let event = store
+        .update(|data| {
+            let event = data.guild_mut(guild_id).events.get_mut(&event_id)?;
+            event.answer(user_id, rsvp);
+            Some(event.clone())
+        })
+        .await?;
+    let event = match event {
+        Some(event) => event,
+        None => {
+            reply_ephemeral(ctx, component, "This event was cancelled.").await?;
+            return Ok(());
+        }
+    };
+
+    component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| d.set_embed(event_embed(&event)))
+        })
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_start_times() {
+        let now = 1_790_000_000;
+        assert_eq!(parse_start("in 2h", now), Ok(now + 7200));
+        assert_eq!(parse_start("90m", now), Ok(now + 5400));
+        assert_eq!(parse_start("2026-10-20 18:00 UTC", now), Ok(1_792_519_200));
+        assert!(parse_start("2020-01-01 00:00", now).is_err());
+        assert!(parse_start("soon", now).is_err());
+        assert!(parse_start("in 2w", now).is_ok());
+        assert!(parse_start("in 60w", now).is_err());
+    }
+
+    #[test]
+    fn cuts_off_long_mention_lists() {
+        assert_eq!(mention_list(&[]), "—");
+        assert_eq!(mention_list(&[1, 2]), "<@1>, <@2>");
+        let many: Vec<u64> = (0..100).map(|n| 100_000_000_000_000_000 + n).collect();
+        let list = mention_list(&many);
+        assert!(list.len() <= 1024);
+        assert!(list.ends_with("more"));
+    }
+}
@@ -0,0 +1,177 @@
+//! Middleware that removes tokens, webhook URLs and API keys from new and edited
+//! messages, see [`crate::utils::leaks`].
+
+use async_trait::async_trait;
+use serenity::model::id::{ChannelId, GuildId, MessageId};
+use serenity::model::user::User;
+use serenity::prelude::*;
+use tracing::{debug, error};
+
+use crate::framework::middleware::{Event, Middleware, Propagation};
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::guild_config::guild_config;
+use crate::models::ignore::ignored_in;
+use crate::utils::constants::WARNING_COLOR;
+use crate::utils::dms::dm_service;
+use crate::utils::helpers::send_staff_alert;
+use crate::utils::leaks::LeakKey;
+use crate::utils::rest::{self, Priority};
+
+/// Scans messages for secrets in guilds that turned `leak_detection` on, deleting
+/// them and telling the author and staff.
+pub struct LeakMiddleware;
+
+#[async_trait]
+impl Middleware for LeakMiddleware {
+    fn name(&self) -> &str {
+        "leaks"
+    }
+
+    fn priority(&self) -> i32 {
+        // Before phishing, so a webhook URL is handled as a leak rather than a link
+        120
+    }
+
+    async fn handle(&self, ctx: &Context, event: &Event<'_>) -> Propagation {
+        let (guild_id, channel_id, message_id, author, content) = match *event {
+            Event::Message(msg) => match msg.guild_id {
+                Some(guild_id) => (guild_id, msg.channel_id, msg.id, &msg.author, &msg.content),
+                None => return Propagation::Continue,
+            },
+            Event::MessageUpdate(update) => {
+                match (update.guild_id, &update.author, &update.content) {
+                    (Some(guild_id), Some(author), Some(content)) => {
+                        (guild_id, update.channel_id, update.id, author, content)
+                    }
+                    _ => return Propagation::Continue,
+                }
+            }
+            _ => return Propagation::Continue,
+        };
+        if author.id == ctx.cache.current_user_id() {
+            return Propagation::Continue;
+        }
+
+        if !guild_config(ctx, guild_id).await.leak_detection {
+            return Propagation::Continue;
+        }
+        let roles = ctx
+            .cache
+            .member(guild_id, author.id)
+            .map(|member| member.roles)
+            .unwrap_or_default();
+        if ignored_in(ctx, guild_id, channel_id, author.id, &roles)
+            .await
+            .passive
+        {
+            return Propagation::Continue;
+        }
+        let scanner = {
+            let data = ctx.data.read().await;
+            data.get::<LeakKey>().cloned()
+        };
+        let kind = match scanner.as_ref().and_then(|scanner| scanner.scan(content)) {
+            Some(kind) => kind.to_string(),
+            None => return Propagation::Continue,
+        };
+        enforce(ctx, guild_id, channel_id, message_id, author, &kind).await
+    }
+}
+
+/// Delete a message with a secret and tell its author and staff.
+///
+/// Stops the event if the message was deleted, so nothing else reacts to it.
+async fn enforce(
+    ctx: &Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    message_id: MessageId,
+    author: &User,
+    kind: &str,
+) -> Propagation {
+    let deleted = match rest::call_with(ctx, Priority::Moderation, "delete_message", || {
+        channel_id.delete_message(&ctx.http, message_id)
+    })
+    .await
+    {
+        Ok(()) => true,
+        Err(e) => {
+            error!("Failed to delete leaked {} in {}: {}", kind, message_id, e);
+            false
+        }
+    };
+
+    // Telling the author matters most, since the secret has to be revoked either way
+    if let Some(dms) = dm_service(ctx).await {
+        let guild_name = ctx
+            .cache
+            .guild_field(guild_id, |guild| guild.name.clone())
+            .unwrap_or_else(|| "a server".to_string());
+        let sent = dms
+            .send(&ctx.http, author.id, None, |m| {
+                m.embed(|e| {
+                    e.title("🔑 Secret detected")
+                        .description(format!(
+                            "Your message in <#{}> ({}) looked like it had a {} in it, so {}.\n\n\
+                             Anyone could have seen it before then, so revoke or regenerate it \
+                             now.",
+                            channel_id,
+                            guild_name,
+                            kind,
+                            if deleted {
+                                "I deleted it"
+                            } else {
+                                "staff have been asked to delete it"
+                            }
+                        ))
+                        .color(WARNING_COLOR)
+                })
+            })
+            .await;
+        if let Err(e) = sent {
+            debug!(
+                "Couldn't tell {} about their leaked {}: {}",
+                author.id, kind, e
+            );
+        }
+    }
+
+    let description = format!(
+        "<@{}> posted what looks like a {} in <#{}>. {}",
+        author.id,
+        kind,
+        channel_id,
+        if deleted {
+            "I deleted it and told them to revoke it."
+        } else {
+            "I couldn't delete it, please remove it."
+        }
+    );
+    if let Err(e) = send_staff_alert(ctx, guild_id, "🔑 Secret leaked", description).await {
+        error!("Failed to send leak alert: {}", e);
+    }
+
+    audit::record(
+        ctx,
+        AuditEvent {
+            guild_id: Some(guild_id),
+            actor_id: Some(ctx.cache.current_user_id()),
+            source: AuditSource::Automod,
+            action: format!(
+                "Catch a leaked {} from <@{}> in <#{}>{}",
+                kind,
+                author.id,
+                channel_id,
+                if deleted { " and delete it" } else { "" }
+            ),
+            reason: None,
+        },
+    )
+    .await;
+
+    if deleted {
+        Propagation::Stop
+    } else {
+        Propagation::Continue
+    }
+}
@@ -0,0 +1,175 @@
+//! Handler for the join and leave buttons of looking-for-group posts.
+//!
+//! Buttons use the IDs `lfg:join:<post>` and `lfg:leave:<post>`. The post's
+//! guild is the guild the button was clicked in.
+
+use async_trait::async_trait;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::{Interaction, InteractionResponseType};
+use serenity::model::id::{ChannelId, GuildId, UserId};
+use serenity::prelude::*;
+use tracing::error;
+
+use crate::framework::command_handler::CommandResult;
+use crate::framework::event_handler::EventHandler;
+use crate::models::lfg::{LfgKey, LfgPost};
+use crate::utils::helpers::{reply_ephemeral, unix_timestamp};
+use crate::utils::lfg::{form_group, post_buttons, post_embed, GROUP_LIFETIME};
+
+/// What clicking a button did.
+enum Outcome {
+    Updated(LfgPost),
+    Filled(LfgPost),
+    Unchanged(&'static str),
+    Gone,
+}
+
+/// Handles LFG join and leave buttons.
+pub struct LfgHandler;
+
+#[async_trait]
+impl EventHandler for LfgHandler {
+    fn event_type(&self) -> &'static str {
+        "interaction"
+    }
+
+    async fn on_interaction(&self, ctx: Context, interaction: &Interaction) {
+        let component = match interaction {
+            Interaction::MessageComponent(component) => component,
+            _ => return,
+        };
+        let (join, post_id) = match component
+            .data
+            .custom_id
+            .strip_prefix("lfg:")
+            .and_then(|rest| rest.split_once(':'))
+        {
+            Some(("join", id)) => (true, id),
+            Some(("leave", id)) => (false, id),
+            _ => return,
+        };
+        let (guild_id, post_id) = match (component.guild_id, post_id.parse::<u64>()) {
+            (Some(guild_id), Ok(post_id)) => (guild_id, post_id),
+            _ => return,
+        };
+
+        if let Err(e) = click(&ctx, component, guild_id, post_id, join).await {
+            error!("LFG button failed: {:?}", e);
+        }
+    }
+}
+
+/// Join or leave a post, forming the group when the last slot is taken.
+async fn click(
+    ctx: &Context,
+    component: &MessageComponentInteraction,
+    guild_id: GuildId,
+    post_id: u64,
+    join: bool,
+) -> CommandResult {
+    let store = {
+        let data = ctx.data.read().await;
+        data.get::<LfgKey>().cloned()
+    }
+    .ok_or("LFG store is not loaded")?;
+
+    let user_id = component.user.id.0;
+    let now = unix_timestamp();
+    let outcome = store
+        .update(|data| {
+            let post = match data.guild_mut(guild_id).posts.get_mut(&post_id) {
+                Some(post) if post.filled_at.is_none() => post,
+                _ => return Outcome::Gone,
+            };
+            if join {
+                if !post.join(user_id) {
+                    return Outcome::Unchanged("You're already in this group.");
+                }
+                if post.is_full() {
+                    post.filled_at = Some(now);
+                    post.expires_at = post.starts_at.unwrap_or(now).max(now) + GROUP_LIFETIME;
+                    return Outcome::Filled(post.clone());
+                }
+            } else if !post.leave(user_id) {
+                let reason = if user_id == post.host {
+                    "You're hosting this group. Cancel it with `lfg cancel` instead."
+                } else {
+                    "You're not in this group."
+                };
+                return Outcome::Unchanged(reason);
+            }
+            Outcome::Updated(post.clone())
+        })
+        .await?;
+
+    match outcome {
+        Outcome::Gone => {
+            reply_ephemeral(
+                ctx,
+                component,
+                "This group is no longer looking for players.",
+            )
+            .await?;
+        }
+        Outcome::Unchanged(reason) => reply_ephemeral(ctx, component, reason).await?,
+        Outcome::Updated(post) => {
+            component
+                .create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::UpdateMessage)
+                        .interaction_response_data(|d| {
+                            d.set_embed(post_embed(&post, false))
+                                .components(|c| post_buttons(c, &post))
+                        })
+                })
+                .await?;
+        }
+        Outcome::Filled(post) => {
+            // Answer first, since creating the role and channel can take a while
+            component
+                .create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::UpdateMessage)
+                        .interaction_response_data(|d| {
+                            d.set_embed(post_embed(&post, false)).components(|c| c)
+                        })
+                })
+                .await?;
+            announce(ctx, guild_id, post).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Set up a full group and ping its members.
+async fn announce(ctx: &Context, guild_id: GuildId, post: LfgPost) -> CommandResult {
+    let (role, channel) = form_group(&ctx.http, guild_id, &post).await;
+    let store = ctx.data.read().await.get::<LfgKey>().cloned();
+    if let Some(store) = store {
+        store
+            .update(|data| {
+                if let Some(saved) = data.guild_mut(guild_id).posts.get_mut(&post.id) {
+                    saved.role_id = role.map(|role| role.0);
+                    saved.voice_channel_id = channel.map(|channel| channel.0);
+                }
+            })
+            .await?;
+    }
+
+    let mentions: Vec<String> = post.members.iter().map(|id| format!("<@{}>", id)).collect();
+    let place = match channel {
+        Some(channel) => format!("Hop into {}!", channel.mention()),
+        None => "Have fun!".to_string(),
+    };
+    let users: Vec<UserId> = post.members.iter().copied().map(UserId).collect();
+    ChannelId(post.channel_id)
+        .send_message(&ctx.http, |m| {
+            m.content(format!(
+                "🎮 {} your **{}** group is full. {}",
+                mentions.join(" "),
+                post.game,
+                place
+            ))
+            .allowed_mentions(|am| am.empty_parse().users(users))
+        })
+        .await?;
+    Ok(())
+}
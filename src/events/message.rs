@@ -5,6 +5,8 @@ use serenity::model::channel::Message;
 use serenity::prelude::*;
 use tracing::{debug, instrument};
 
+use super::auto_response::AutoResponder;
+use super::time_conversion::convert_times;
 use crate::framework::command_handler::CommandHandler;
 use crate::framework::event_handler::EventHandler;
 
@@ -12,12 +14,17 @@ use crate::framework::event_handler::EventHandler;
 pub struct MessageHandler {
     /// The command handler to process commands.
     command_handler: CommandHandler,
+    /// Auto-responses for messages that aren't commands.
+    auto_responder: AutoResponder,
 }
 
 impl MessageHandler {
     /// Create a new MessageHandler with the given CommandHandler.
     pub fn new(command_handler: CommandHandler) -> Self {
-        Self { command_handler }
+        Self {
+            command_handler,
+            auto_responder: AutoResponder::default(),
+        }
     }
 }
 
@@ -36,9 +43,24 @@ impl EventHandler for MessageHandler {
 
         debug!("Received message: {}", msg.content);
 
+        // Answers to a command's questions are only for that command
+        if self.command_handler.take_reply(msg) {
+            return;
+        }
+
         // Process commands
         if let Err(e) = self.command_handler.handle_message(&ctx, msg).await {
             debug!("Error handling command: {:?}", e);
         }
+
+        // Inline commands, auto-responses and time conversion only answer messages that
+        // didn't run a command
+        if !self.command_handler.is_command(&ctx, msg).await {
+            if let Err(e) = self.command_handler.handle_inline(&ctx, msg).await {
+                debug!("Error handling inline commands: {:?}", e);
+            }
+            self.auto_responder.handle(&ctx, msg).await;
+            convert_times(&ctx, msg).await;
+        }
     }
 }
@@ -0,0 +1,167 @@
+//! Handlers that keep the message cache up to date, and log edited and deleted
+//! messages to the `message_log_channel` from what was cached.
+
+use async_trait::async_trait;
+use serenity::model::channel::Message;
+use serenity::model::event::MessageUpdateEvent;
+use serenity::model::id::{ChannelId, GuildId, MessageId};
+use serenity::prelude::*;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::framework::event_handler::EventHandler;
+use crate::models::guild_config::guild_config;
+use crate::models::message_cache::{CachedMessage, MessageCache, MessageCacheKey};
+use crate::utils::constants::{ERROR_COLOR, WARNING_COLOR};
+use crate::utils::helpers::{apply_mentions, mention_policy, truncate};
+use crate::utils::rest::{self, Priority};
+
+/// Longest text an embed field holds.
+const FIELD_LIMIT: usize = 1024;
+
+/// Get the message cache from the client data.
+async fn message_cache(ctx: &Context) -> Option<Arc<MessageCache>> {
+    let data = ctx.data.read().await;
+    data.get::<MessageCacheKey>().cloned()
+}
+
+/// A message's text for a log field.
+fn field_text(content: &str) -> String {
+    match content {
+        "" => "*No text*".to_string(),
+        content => truncate(content, FIELD_LIMIT),
+    }
+}
+
+/// Post an edited or deleted message to its guild's message log channel, if
+/// one is set. Bots' messages aren't logged.
+async fn log_message(
+    ctx: &Context,
+    message: &CachedMessage,
+    title: &str,
+    color: u32,
+    fields: &[(&str, String)],
+) {
+    let guild_id = match message.guild_id {
+        Some(guild_id) if !message.bot => guild_id,
+        _ => return,
+    };
+    let channel_id = match guild_config(ctx, guild_id).await.message_log_channel {
+        Some(channel_id) if channel_id != message.channel_id.0 => ChannelId(channel_id),
+        _ => return,
+    };
+
+    let policy = &mention_policy(ctx).await;
+    let description = &format!(
+        "<@{}> in <#{}> · [Jump to message](https://discord.com/channels/{}/{}/{})",
+        message.author_id, message.channel_id, guild_id, message.channel_id, message.id
+    );
+    let footer = &format!("{} · {}", message.author_tag, message.author_id);
+    let sent = rest::call_with(ctx, Priority::Log, "send_message", || {
+        channel_id.send_message(&ctx.http, move |m| {
+            m.allowed_mentions(|am| apply_mentions(am, policy, &[]));
+            m.embed(|e| {
+                e.title(title)
+                    .description(description)
+                    .color(color)
+                    .footer(|f| f.text(footer));
+                for (name, value) in fields {
+                    e.field(name, value, false);
+                }
+                e
+            })
+        })
+    })
+    .await;
+    if let Err(e) = sent {
+        warn!("Failed to log message {}: {}", message.id, e);
+    }
+}
+
+/// Caches new messages.
+pub struct MessageCacheHandler;
+
+#[async_trait]
+impl EventHandler for MessageCacheHandler {
+    fn event_type(&self) -> &'static str {
+        "message"
+    }
+
+    fn priority(&self) -> i32 {
+        // Cache messages before anything else looks at them
+        100
+    }
+
+    async fn on_message(&self, ctx: Context, msg: &Message) {
+        if let Some(cache) = message_cache(&ctx).await {
+            cache.insert(CachedMessage::from(msg));
+        }
+    }
+}
+
+/// Records message edits in the cache and logs them.
+pub struct MessageCacheUpdateHandler;
+
+#[async_trait]
+impl EventHandler for MessageCacheUpdateHandler {
+    fn event_type(&self) -> &'static str {
+        "message_update"
+    }
+
+    fn priority(&self) -> i32 {
+        100
+    }
+
+    async fn on_message_update(&self, ctx: Context, event: &MessageUpdateEvent) {
+        if let Some(cache) = message_cache(&ctx).await {
+            let attachments = event
+                .attachments
+                .as_ref()
+                .map(|attachments| attachments.iter().map(|a| a.url.clone()).collect());
+            let before = cache.update(event.id, event.content.as_deref(), attachments);
+            // Embeds loading in also count as edits, so only changed text is logged
+            if let (Some(before), Some(after)) = (before, &event.content) {
+                if before.content != *after {
+                    let fields = [
+                        ("Before", field_text(&before.content)),
+                        ("After", field_text(after)),
+                    ];
+                    log_message(&ctx, &before, "✏️ Message edited", WARNING_COLOR, &fields).await;
+                }
+            }
+        }
+    }
+}
+
+/// Marks deleted messages in the cache and logs them.
+pub struct MessageCacheDeleteHandler;
+
+#[async_trait]
+impl EventHandler for MessageCacheDeleteHandler {
+    fn event_type(&self) -> &'static str {
+        "message_delete"
+    }
+
+    fn priority(&self) -> i32 {
+        100
+    }
+
+    async fn on_message_delete(
+        &self,
+        ctx: Context,
+        _channel_id: ChannelId,
+        message_id: MessageId,
+        _guild_id: Option<GuildId>,
+    ) {
+        if let Some(cache) = message_cache(&ctx).await {
+            if let Some(message) = cache.mark_deleted(message_id) {
+                let mut fields = vec![("Content", field_text(&message.content))];
+                if !message.attachments.is_empty() {
+                    let attachments = truncate(&message.attachments.join("\n"), FIELD_LIMIT);
+                    fields.push(("Attachments", attachments));
+                }
+                log_message(&ctx, &message, "🗑️ Message deleted", ERROR_COLOR, &fields).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Handler that relays messages from mirrored channels to their targets.
+
+use async_trait::async_trait;
+use serenity::model::channel::Message;
+use serenity::model::id::ChannelId;
+use serenity::prelude::*;
+use tracing::warn;
+
+use crate::framework::event_handler::EventHandler;
+use crate::models::mirror::MirrorKey;
+use crate::utils::helpers::truncate;
+use crate::utils::webhooks::{webhooks, Persona};
+
+/// Relays messages through a webhook in each target channel, named after the
+/// original author and server.
+pub struct MirrorHandler;
+
+#[async_trait]
+impl EventHandler for MirrorHandler {
+    fn event_type(&self) -> &'static str {
+        "message"
+    }
+
+    async fn on_message(&self, ctx: Context, msg: &Message) {
+        let guild_id = match msg.guild_id {
+            Some(guild_id) => guild_id,
+            None => return,
+        };
+        let store = {
+            let data = ctx.data.read().await;
+            data.get::<MirrorKey>().cloned()
+        };
+        let store = match store {
+            Some(store) => store,
+            None => return,
+        };
+        let mirrors = store.read().await.from_source(msg.channel_id);
+        if mirrors.is_empty() || msg.author.id == ctx.cache.current_user_id() {
+            return;
+        }
+        // Relayed messages are never relayed again, so two-way mirrors don't loop
+        let webhooks = webhooks(&ctx).await;
+        if let Some(webhook_id) = msg.webhook_id {
+            if webhooks.is_own(webhook_id).await {
+                return;
+            }
+        }
+
+        let guild_name = guild_id
+            .name(&ctx.cache)
+            .unwrap_or_else(|| "Unknown server".to_string());
+        let persona = Persona::of(&msg.author).with_suffix(&guild_name);
+        for mirror in mirrors {
+            if mirror.source_guild != guild_id.0 || !mirror.relays(msg) {
+                continue;
+            }
+            let content = truncate(&mirror.content(msg), 2000);
+            let target = ChannelId(mirror.target_channel);
+            if let Err(e) = webhooks
+                .send(&ctx, target, &persona, &content, Vec::new())
+                .await
+            {
+                warn!(
+                    "Failed to relay message through mirror #{}: {}",
+                    mirror.id, e
+                );
+            }
+        }
+    }
+}
@@ -1,21 +1,203 @@
 //! Event handlers for Discord events.
 
+mod analytics;
+#[cfg(feature = "antinuke")]
+mod antinuke;
+mod appeals;
+mod auto_publish;
+mod auto_response;
+#[cfg(feature = "automod")]
+mod automod;
+mod break_glass;
+mod cases;
+mod command_sync;
+mod devlookup;
+#[cfg(feature = "games")]
+mod games;
+mod growth;
+mod guild_events;
+#[cfg(feature = "automod")]
+mod leaks;
+mod lfg;
 mod message;
+mod message_cache;
+mod mirror;
+mod modmail;
+#[cfg(feature = "music")]
+mod music;
+mod onboarding;
+#[cfg(feature = "automod")]
+mod phishing;
+mod pin_archive;
+mod pin_votes;
 mod ready;
+#[cfg(feature = "automod")]
+mod repeats;
+mod reports;
+mod role_persistence;
+mod setup;
+mod shard_health;
+mod steam;
+mod time_conversion;
+mod todo;
+mod voice_text;
+mod watchlist;
+#[cfg(feature = "automod")]
+mod word_filter;
 
+pub use analytics::{AnalyticsMessageHandler, AnalyticsReactionHandler};
+#[cfg(feature = "antinuke")]
+pub use antinuke::AntinukeHandler;
+pub use appeals::AppealHandler;
+pub use auto_publish::AutoPublishHandler;
+pub use break_glass::BreakGlassHandler;
+pub use cases::{BanRecordHandler, TimeoutRecordHandler, UnbanRecordHandler};
+pub use command_sync::CommandSyncHandler;
+pub use devlookup::DevLookupHandler;
+#[cfg(feature = "games")]
+pub use games::GameHandler;
+pub use growth::{GrowthJoinHandler, GrowthLeaveHandler};
+pub use guild_events::EventInteractionHandler;
+#[cfg(feature = "automod")]
+pub use leaks::LeakMiddleware;
+pub use lfg::LfgHandler;
 pub use message::MessageHandler;
+pub use message_cache::{
+    MessageCacheDeleteHandler, MessageCacheHandler, MessageCacheUpdateHandler,
+};
+pub use mirror::MirrorHandler;
+pub use modmail::{ModmailHandler, ModmailInteractionHandler};
+#[cfg(feature = "music")]
+pub use music::{MusicRestoreHandler, MusicVoiceHandler};
+pub use onboarding::{send_onboarding_prompt, OnboardingHandler, OnboardingJoinHandler};
+#[cfg(feature = "automod")]
+pub use phishing::PhishingMiddleware;
+pub use pin_archive::PinArchiveHandler;
+pub use pin_votes::PinVoteHandler;
 pub use ready::ReadyHandler;
+#[cfg(feature = "automod")]
+pub use repeats::RepeatMiddleware;
+pub use reports::ReportHandler;
+pub use role_persistence::{RoleRestoreHandler, RoleSaveHandler};
+pub use setup::{setup_panel, SetupHandler, SetupInviterHandler};
+pub use shard_health::{ShardResumeHandler, ShardStageHandler};
+pub use steam::SteamMenuHandler;
+pub use time_conversion::{TimeConversionReactionHandler, TimestampMenuHandler};
+pub use todo::TodoMenuHandler;
+pub use voice_text::{
+    delete_temporary, revoke_connected, VoiceTextCleanupHandler, VoiceTextHandler,
+};
+pub use watchlist::{WatchlistActivityHandler, WatchlistJoinHandler};
+#[cfg(feature = "automod")]
+pub use word_filter::WordFilterMiddleware;
+
+use serenity::builder::CreateApplicationCommand;
 
 use crate::framework::command_handler::CommandHandler;
-use crate::framework::event_handler::EventDispatcher;
+use crate::framework::event_handler::{DispatchPolicy, EventDispatcher};
+
+/// The slash and context menu commands the built-in handlers respond to.
+pub fn application_commands() -> Vec<CreateApplicationCommand> {
+    vec![reports::report_menu()]
+}
 
 /// Register all event handlers with the event dispatcher.
 pub fn register_events(dispatcher: &mut EventDispatcher, command_handler: CommandHandler) {
     // Register the ready event handler
     dispatcher.register_handler(ReadyHandler);
 
+    // Register the gateway health handlers
+    dispatcher.register_handler(ShardStageHandler);
+    dispatcher.register_handler(ShardResumeHandler);
+
     // Register the message event handler
+    let replies = command_handler.replies();
     dispatcher.register_handler(MessageHandler::new(command_handler));
 
+    // Register the message cache handlers
+    dispatcher.register_handler(MessageCacheHandler);
+    dispatcher.register_handler(MessageCacheUpdateHandler);
+    dispatcher.register_handler(MessageCacheDeleteHandler);
+
+    // Register the channel mirror handler
+    dispatcher.register_handler(MirrorHandler);
+
+    // Register the activity counters
+    dispatcher.register_handler(AnalyticsMessageHandler);
+    dispatcher.register_handler(AnalyticsReactionHandler);
+
+    // Register the member count handlers
+    dispatcher.register_handler(GrowthJoinHandler);
+    dispatcher.register_handler(GrowthLeaveHandler);
+
+    // Register the watchlist handlers
+    dispatcher.register_handler(WatchlistJoinHandler);
+    dispatcher.register_recoverable(WatchlistActivityHandler::new());
+
+    // Register the role persistence handlers
+    dispatcher.register_handler(RoleSaveHandler);
+    dispatcher.register_handler(RoleRestoreHandler);
+
+    // Register the moderation case recorders
+    dispatcher.register_handler(BanRecordHandler);
+    dispatcher.register_handler(UnbanRecordHandler);
+    dispatcher.register_handler(TimeoutRecordHandler);
+
+    // Register the onboarding handlers
+    dispatcher.register_handler(OnboardingJoinHandler);
+    dispatcher.register_handler(OnboardingHandler);
+
+    // Register the critical command confirmation handler
+    dispatcher.register_handler(BreakGlassHandler);
+
+    // Register the voice text channel handlers
+    dispatcher.register_handler(VoiceTextHandler::default());
+    dispatcher.register_handler(VoiceTextCleanupHandler);
+
+    // Register the appeal interaction handler
+    dispatcher.register_handler(AppealHandler);
+
+    // Register the message report handlers
+    dispatcher.register_handler(ReportHandler);
+
+    // Register the modmail relay handlers
+    dispatcher.register_handler(ModmailHandler::new(replies));
+    dispatcher.register_handler(ModmailInteractionHandler);
+
+    // Register the pin archive handler
+    dispatcher.register_handler(PinArchiveHandler);
+
+    // Register the pin voting handler
+    dispatcher.register_handler(PinVoteHandler);
+
+    // Register the announcement auto-publish handler
+    dispatcher.register_recoverable(AutoPublishHandler::new());
+
+    // Register the crate, repo and docs search menu handler
+    dispatcher.register_handler(DevLookupHandler);
+
+    // Register the Steam game menu handler
+    dispatcher.register_handler(SteamMenuHandler);
+
+    // Register the todo done menu handler
+    dispatcher.register_handler(TodoMenuHandler);
+
+    // Register the event RSVP handler
+    dispatcher.register_handler(EventInteractionHandler);
+
+    // Register the LFG button handler
+    dispatcher.register_handler(LfgHandler);
+
+    // Register the setup panel and new server handlers
+    dispatcher.register_handler(SetupHandler);
+    dispatcher.register_handler(SetupInviterHandler);
+
+    // Register the time conversion handlers
+    dispatcher.register_handler(TimeConversionReactionHandler);
+    dispatcher.register_handler(TimestampMenuHandler);
+
+    // Message handlers are independent of each other, so run them side by side
+    dispatcher.set_policy("message", DispatchPolicy::Concurrent { limit: 64 });
+
     // Add more event handlers here as needed
 }
@@ -0,0 +1,495 @@
+//! Handlers that relay modmail between users' DMs and staff threads.
+
+use async_trait::async_trait;
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::application::interaction::{Interaction, InteractionResponseType};
+use serenity::model::channel::{Message, ReactionType};
+use serenity::model::id::{ChannelId, GuildId, UserId};
+use serenity::model::user::User;
+use serenity::prelude::*;
+use std::sync::Arc;
+use tracing::{debug, error, warn};
+
+use crate::framework::command_handler::CommandResult;
+use crate::framework::event_handler::EventHandler;
+use crate::framework::wizard::Replies;
+use crate::models::guild_config::{guild_config, GuildConfigKey};
+use crate::models::modmail::{ModmailData, ModmailKey, ModmailThread, TranscriptEntry};
+use crate::storage::JsonStore;
+use crate::utils::constants::{DEFAULT_COLOR, SUCCESS_COLOR};
+use crate::utils::dms::{dm_service, DmOutcome};
+use crate::utils::duration::relative;
+use crate::utils::helpers::{
+    apply_mentions, mention_policy, truncate, unix_timestamp, BotConfigKey,
+};
+use crate::utils::webhooks::{webhooks, Persona};
+
+/// Get the modmail store from the client data.
+async fn modmail_store(ctx: &Context) -> Option<Arc<JsonStore<ModmailData>>> {
+    let data = ctx.data.read().await;
+    data.get::<ModmailKey>().cloned()
+}
+
+/// Build a transcript entry from a relayed message.
+fn transcript_entry(msg: &Message, from_staff: bool) -> TranscriptEntry {
+    TranscriptEntry {
+        author_id: msg.author.id.0,
+        author_tag: msg.author.tag(),
+        from_staff,
+        content: msg.content.clone(),
+        attachments: msg.attachments.iter().map(|a| a.url.clone()).collect(),
+        sent_at: unix_timestamp(),
+    }
+}
+
+/// Describe a message's text and attachments for a relay embed.
+fn relay_text(msg: &Message) -> String {
+    let mut text = msg.content.clone();
+    for attachment in &msg.attachments {
+        text.push_str(&format!(
+            "\n📎 [{}]({})",
+            attachment.filename, attachment.url
+        ));
+    }
+    if text.is_empty() {
+        text.push_str("*(empty message)*");
+    }
+
+    truncate(&text, 4000)
+}
+
+/// Open a conversation with a guild's staff, relaying the user's first message.
+async fn open_thread(
+    ctx: &Context,
+    guild_id: GuildId,
+    user: &User,
+    first: &Message,
+) -> CommandResult {
+    let store = modmail_store(ctx)
+        .await
+        .ok_or("Modmail store is not loaded")?;
+    let config = guild_config(ctx, guild_id).await;
+    let channel_id = ChannelId(
+        config
+            .modmail_channel
+            .ok_or("Modmail is not enabled in this guild")?,
+    );
+
+    let policy = &mention_policy(ctx).await;
+    let staff_roles: &[u64] = &config.staff_role.into_iter().collect::<Vec<_>>();
+    let header = channel_id
+        .send_message(&ctx.http, |m| {
+            m.allowed_mentions(|am| apply_mentions(am, policy, staff_roles));
+            if let Some(role_id) = config.staff_role {
+                m.content(format!("<@&{}>", role_id));
+            }
+
+            m.embed(|e| {
+                e.title("New modmail")
+                    .color(DEFAULT_COLOR)
+                    .thumbnail(user.face())
+                    .field("User", format!("<@{}> (`{}`)", user.id, user.id), true)
+                    .field(
+                        "Account created",
+                        relative(user.created_at().unix_timestamp() as u64).to_string(),
+                        true,
+                    )
+                    .footer(|f| f.text("Messages in the thread are sent to the user anonymously."))
+            })
+        })
+        .await?;
+
+    let thread = channel_id
+        .create_public_thread(&ctx.http, header.id, |t| {
+            t.name(truncate(&format!("modmail-{}", user.tag()), 90))
+                .auto_archive_duration(1440)
+        })
+        .await?;
+
+    let modmail = ModmailThread {
+        guild_id: guild_id.0,
+        thread_id: thread.id.0,
+        user_id: user.id.0,
+        opened_at: unix_timestamp(),
+        transcript: Vec::new(),
+    };
+    store
+        .update(|data| {
+            data.open.insert(user.id.0, modmail.clone());
+        })
+        .await?;
+
+    relay_to_staff(ctx, &store, &modmail, first).await?;
+
+    let guild_name = guild_id
+        .name(&ctx.cache)
+        .unwrap_or_else(|| "the server".to_string());
+    first
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.title("Message sent")
+                    .description(format!(
+                        "Your message was sent to the staff of **{}**. Their replies will appear here.",
+                        guild_name
+                    ))
+                    .color(SUCCESS_COLOR)
+            })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Relay a user's DM into their staff thread.
+async fn relay_to_staff(
+    ctx: &Context,
+    store: &JsonStore<ModmailData>,
+    thread: &ModmailThread,
+    msg: &Message,
+) -> CommandResult {
+    let persona = Persona {
+        name: msg.author.tag(),
+        avatar_url: Some(msg.author.face()),
+    };
+    webhooks(ctx)
+        .await
+        .send(
+            ctx,
+            ChannelId(thread.thread_id),
+            &persona,
+            &relay_text(msg),
+            Vec::new(),
+        )
+        .await?;
+
+    let entry = transcript_entry(msg, false);
+    store
+        .update(|data| {
+            if let Some(thread) = data.open.get_mut(&msg.author.id.0) {
+                thread.transcript.push(entry);
+            }
+        })
+        .await?;
+
+    msg.react(&ctx.http, ReactionType::Unicode("✅".to_string()))
+        .await?;
+
+    Ok(())
+}
+
+/// Relay a staff message from a modmail thread to the user, hiding who sent it.
+async fn relay_to_user(
+    ctx: &Context,
+    store: &JsonStore<ModmailData>,
+    thread: &ModmailThread,
+    msg: &Message,
+) -> CommandResult {
+    let guild_id = GuildId(thread.guild_id);
+    let guild_name = guild_id
+        .name(&ctx.cache)
+        .unwrap_or_else(|| "Server".to_string());
+
+    let sent = match dm_service(ctx).await {
+        Some(dms) => {
+            dms.send(&ctx.http, UserId(thread.user_id), None, |m| {
+                m.embed(|e| {
+                    e.author(|a| a.name(format!("Staff — {}", guild_name)))
+                        .description(relay_text(msg))
+                        .color(SUCCESS_COLOR)
+                        .timestamp(msg.timestamp)
+                })
+            })
+            .await
+        }
+        None => Ok(DmOutcome::Closed),
+    };
+
+    let failed = match sent {
+        Ok(outcome) if outcome.needs_fallback() => {
+            warn!(
+                "Couldn't relay modmail reply to {}: {:?}",
+                thread.user_id, outcome
+            );
+            true
+        }
+        Ok(_) => false,
+        Err(e) => {
+            warn!("Couldn't relay modmail reply to {}: {}", thread.user_id, e);
+            true
+        }
+    };
+    if failed {
+        msg.reply(
+            &ctx.http,
+            "⚠️ Couldn't deliver this message. The user may have DMs disabled.",
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let entry = transcript_entry(msg, true);
+    store
+        .update(|data| {
+            if let Some(thread) = data.open.get_mut(&thread.user_id) {
+                thread.transcript.push(entry);
+            }
+        })
+        .await?;
+
+    msg.react(&ctx.http, ReactionType::Unicode("✅".to_string()))
+        .await?;
+
+    Ok(())
+}
+
+/// Find the guilds whose modmail the user can reach.
+async fn candidate_guilds(ctx: &Context, user: &User) -> Vec<GuildId> {
+    let configs = {
+        let data = ctx.data.read().await;
+        data.get::<GuildConfigKey>().cloned()
+    };
+    let guild_ids: Vec<GuildId> = match configs {
+        Some(configs) => configs
+            .read()
+            .await
+            .guilds
+            .iter()
+            .filter(|(_, config)| config.modmail_channel.is_some())
+            .map(|(guild_id, _)| GuildId(*guild_id))
+            .collect(),
+        None => return Vec::new(),
+    };
+
+    let blocked = match modmail_store(ctx).await {
+        Some(store) => {
+            let data = store.read().await;
+            guild_ids
+                .iter()
+                .filter(|guild_id| data.is_blocked(**guild_id, user.id))
+                .copied()
+                .collect()
+        }
+        None => Vec::new(),
+    };
+
+    let mut candidates = Vec::new();
+    for guild_id in guild_ids {
+        if blocked.contains(&guild_id) {
+            continue;
+        }
+        // Only ask Discord when the cache can't tell
+        let cached = ctx.cache.guild_field(guild_id, |guild| {
+            let complete = guild.members.len() as u64 >= guild.member_count;
+            (guild.members.contains_key(&user.id), complete)
+        });
+        let member = match cached {
+            Some((true, _)) => true,
+            // Every member is cached, so they aren't one
+            Some((false, true)) => false,
+            _ => guild_id.member(ctx, user.id).await.is_ok(),
+        };
+        if member {
+            candidates.push(guild_id);
+        }
+    }
+
+    candidates
+}
+
+/// Relays DMs to staff threads and staff thread messages back to users.
+pub struct ModmailHandler {
+    /// Answers to command questions, which aren't relayed.
+    replies: Arc<Replies>,
+}
+
+impl ModmailHandler {
+    /// Create the handler, leaving the answers commands wait for alone.
+    pub fn new(replies: Arc<Replies>) -> Self {
+        Self { replies }
+    }
+
+    /// Handle a DM from a user.
+    async fn handle_dm(&self, ctx: &Context, msg: &Message) -> CommandResult {
+        let store = match modmail_store(ctx).await {
+            Some(store) => store,
+            None => return Ok(()),
+        };
+
+        let open = store.read().await.thread_for_user(msg.author.id).cloned();
+        if let Some(thread) = open {
+            if store
+                .read()
+                .await
+                .is_blocked(GuildId(thread.guild_id), msg.author.id)
+            {
+                return Ok(());
+            }
+            return relay_to_staff(ctx, &store, &thread, msg).await;
+        }
+
+        let candidates = candidate_guilds(ctx, &msg.author).await;
+        match candidates.as_slice() {
+            [] => debug!("No modmail guilds for {}", msg.author.id),
+            [guild_id] => open_thread(ctx, *guild_id, &msg.author, msg).await?,
+            _ => {
+                // Ask which server to contact; the prompt replies to the original
+                // message so the button handler can relay it afterwards
+                msg.channel_id
+                    .send_message(&ctx.http, |m| {
+                        m.reference_message(msg)
+                            .embed(|e| {
+                                e.title("Contact staff")
+                                    .description("Which server's staff do you want to contact?")
+                                    .color(DEFAULT_COLOR)
+                            })
+                            .components(|c| {
+                                for row in candidates.chunks(5).take(5) {
+                                    c.create_action_row(|r| {
+                                        for guild_id in row {
+                                            let name = guild_id
+                                                .name(&ctx.cache)
+                                                .unwrap_or_else(|| guild_id.to_string());
+                                            r.create_button(|b| {
+                                                b.custom_id(format!("modmail:open:{}", guild_id))
+                                                    .label(truncate(&name, 70))
+                                                    .style(ButtonStyle::Primary)
+                                            });
+                                        }
+                                        r
+                                    });
+                                }
+                                c
+                            })
+                    })
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle a message in a guild channel, relaying it if it's in a modmail thread.
+    async fn handle_guild_message(&self, ctx: &Context, msg: &Message) -> CommandResult {
+        let store = match modmail_store(ctx).await {
+            Some(store) => store,
+            None => return Ok(()),
+        };
+
+        let thread = store
+            .read()
+            .await
+            .thread_for_channel(msg.channel_id)
+            .cloned();
+        match thread {
+            Some(thread) => relay_to_user(ctx, &store, &thread, msg).await,
+            None => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl EventHandler for ModmailHandler {
+    fn event_type(&self) -> &'static str {
+        "message"
+    }
+
+    async fn on_message(&self, ctx: Context, msg: &Message) {
+        if msg.author.bot {
+            return;
+        }
+
+        // Commands are left to the command handler
+        let is_command = {
+            let data = ctx.data.read().await;
+            data.get::<BotConfigKey>().is_some_and(|config| {
+                msg.content.starts_with(&config.prefix)
+                    || config
+                        .dev_prefix()
+                        .is_some_and(|prefix| msg.content.starts_with(prefix))
+            })
+        };
+        // So are answers to a command's questions and break-glass codes
+        if is_command || self.replies.is_answer(msg) {
+            return;
+        }
+
+        let result = match msg.guild_id {
+            Some(_) => self.handle_guild_message(&ctx, msg).await,
+            None => self.handle_dm(&ctx, msg).await,
+        };
+
+        if let Err(e) = result {
+            error!("Modmail relay failed: {:?}", e);
+        }
+    }
+}
+
+/// Handles the server choice buttons sent to users in several modmail servers.
+pub struct ModmailInteractionHandler;
+
+#[async_trait]
+impl EventHandler for ModmailInteractionHandler {
+    fn event_type(&self) -> &'static str {
+        "interaction"
+    }
+
+    async fn on_interaction(&self, ctx: Context, interaction: &Interaction) {
+        let component = match interaction {
+            Interaction::MessageComponent(component) => component,
+            _ => return,
+        };
+        let guild_id = match component
+            .data
+            .custom_id
+            .strip_prefix("modmail:open:")
+            .and_then(|id| id.parse().ok())
+        {
+            Some(guild_id) => GuildId(guild_id),
+            None => return,
+        };
+
+        let already_open = match modmail_store(&ctx).await {
+            Some(store) => store
+                .read()
+                .await
+                .thread_for_user(component.user.id)
+                .is_some(),
+            None => return,
+        };
+
+        let content = if already_open {
+            "You already have an open conversation.".to_string()
+        } else {
+            let guild_name = guild_id
+                .name(&ctx.cache)
+                .unwrap_or_else(|| "the server".to_string());
+            format!("Contacting the staff of **{}**...", guild_name)
+        };
+
+        let response = component
+            .create_interaction_response(&ctx.http, |r| {
+                r.kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|d| {
+                        d.content(content).set_embeds(Vec::new()).components(|c| c)
+                    })
+            })
+            .await;
+        if let Err(e) = response {
+            error!("Failed to respond to modmail interaction: {}", e);
+            return;
+        }
+
+        if already_open {
+            return;
+        }
+
+        let first = match &component.message.referenced_message {
+            Some(first) => first,
+            None => return,
+        };
+        if let Err(e) = open_thread(&ctx, guild_id, &component.user, first).await {
+            error!("Failed to open modmail thread: {:?}", e);
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! Handlers that keep the music player in step with the bot's voice state.
+
+use async_trait::async_trait;
+use serenity::model::guild::Guild;
+use serenity::model::id::ChannelId;
+use serenity::model::voice::VoiceState;
+use serenity::prelude::*;
+
+use crate::framework::event_handler::EventHandler;
+use crate::models::guild_config::guild_config;
+use crate::utils::player::PlayerKey;
+
+/// Tells the player when the bot was disconnected from voice, such as by a
+/// moderator.
+pub struct MusicVoiceHandler;
+
+#[async_trait]
+impl EventHandler for MusicVoiceHandler {
+    fn event_type(&self) -> &'static str {
+        "voice_state_update"
+    }
+
+    async fn on_voice_state_update(
+        &self,
+        ctx: Context,
+        _old: Option<&VoiceState>,
+        new: &VoiceState,
+    ) {
+        let guild_id = match new.guild_id {
+            Some(guild_id) if new.user_id == ctx.cache.current_user_id() => guild_id,
+            _ => return,
+        };
+        if new.channel_id.is_some() {
+            return;
+        }
+        let player = {
+            let data = ctx.data.read().await;
+            data.get::<PlayerKey>().cloned()
+        };
+        if let Some(player) = player {
+            player.disconnected(guild_id).await;
+        }
+    }
+}
+
+/// Joins a server's 24/7 channel when the server becomes available, such as
+/// after a restart, and resumes its saved queue.
+pub struct MusicRestoreHandler;
+
+#[async_trait]
+impl EventHandler for MusicRestoreHandler {
+    fn event_type(&self) -> &'static str {
+        "guild_create"
+    }
+
+    async fn on_guild_create(&self, ctx: Context, guild: &Guild, _is_new: bool) {
+        let home = match guild_config(&ctx, guild.id).await.music.always_on {
+            Some(home) => ChannelId(home),
+            None => return,
+        };
+        let player = {
+            let data = ctx.data.read().await;
+            data.get::<PlayerKey>().cloned()
+        };
+        if let Some(player) = player {
+            player.restore(&ctx, guild.id, home).await;
+        }
+    }
+}
@@ -0,0 +1,383 @@
+//! Handlers for the onboarding flow new members go through.
+//!
+//! Joining members are sent the server's rules in a DM, or in the flow's fallback
+//! channel when their DMs are closed. The flow uses these component IDs:
+//! - `onboarding:start:<guild>`: button under the rules, shows the first question.
+//! - `onboarding:answer:<guild>:<question>`: select menu, gives the picked roles.
+//! - `onboarding:skip:<guild>:<question>`: button that leaves a question unanswered.
+//!
+//! The step is carried in the component ID, so nothing is stored while a member is
+//! partway through. The access roles are granted after the last question.
+
+use async_trait::async_trait;
+use serenity::builder::{CreateInteractionResponse, CreateMessage};
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::{Interaction, InteractionResponseType};
+use serenity::model::guild::Member;
+use serenity::model::id::{ChannelId, GuildId, RoleId, UserId};
+use serenity::prelude::*;
+use std::collections::HashSet;
+use tracing::{debug, error, warn};
+
+use crate::framework::command_handler::CommandResult;
+use crate::framework::event_handler::EventHandler;
+use crate::models::guild_config::guild_config;
+use crate::models::onboarding::{OnboardingFlow, Question};
+use crate::utils::constants::{DEFAULT_COLOR, SUCCESS_COLOR, WARNING_COLOR};
+use crate::utils::dms::{dm_service, DmOutcome};
+use crate::utils::helpers::{reply_ephemeral, send_staff_alert, unix_timestamp};
+use crate::utils::rest;
+
+/// Sends the onboarding prompt to members who join.
+pub struct OnboardingJoinHandler;
+
+#[async_trait]
+impl EventHandler for OnboardingJoinHandler {
+    fn event_type(&self) -> &'static str {
+        "guild_member_add"
+    }
+
+    async fn on_guild_member_add(&self, ctx: Context, guild_id: GuildId, member: &Member) {
+        if member.user.bot {
+            return;
+        }
+        let flow = guild_config(&ctx, guild_id).await.onboarding;
+        if !flow.is_active() {
+            return;
+        }
+
+        if let Err(e) = send_onboarding_prompt(&ctx, guild_id, member.user.id, &flow).await {
+            warn!(
+                "Couldn't send the onboarding prompt to {} in guild {}: {}",
+                member.user.id, guild_id, e
+            );
+        }
+    }
+}
+
+/// The rules and the start button.
+fn prompt<'a, 'b>(
+    m: &'b mut CreateMessage<'a>,
+    guild_id: GuildId,
+    guild_name: &str,
+    rules: &str,
+) -> &'b mut CreateMessage<'a> {
+    m.embed(|e| {
+        e.title(format!("Welcome to {}", guild_name))
+            .description(rules)
+            .color(DEFAULT_COLOR)
+    })
+    .components(|c| {
+        c.create_action_row(|row| {
+            row.create_button(|b| {
+                b.custom_id(format!("onboarding:start:{}", guild_id))
+                    .label("I accept, get started")
+                    .style(ButtonStyle::Success)
+            })
+        })
+    })
+}
+
+/// Send the rules and the start button, in a DM or else in the fallback channel.
+pub async fn send_onboarding_prompt(
+    ctx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    flow: &OnboardingFlow,
+) -> CommandResult {
+    let guild_name = guild_id
+        .name(&ctx.cache)
+        .unwrap_or_else(|| "the server".to_string());
+    let rules = if flow.rules.is_empty() {
+        "Answer a few questions to get access to the server.".to_string()
+    } else {
+        flow.rules.clone()
+    };
+
+    let dm = match dm_service(ctx).await {
+        Some(dms) => {
+            dms.send(&ctx.http, user_id, None, |m| {
+                prompt(m, guild_id, &guild_name, &rules)
+            })
+            .await
+        }
+        None => Ok(DmOutcome::Closed),
+    };
+    match (dm, flow.channel) {
+        (Ok(outcome), _) if !outcome.needs_fallback() => Ok(()),
+        (dm, Some(channel_id)) => {
+            debug!(
+                "Couldn't DM {} ({:?}), prompting in the server",
+                user_id, dm
+            );
+            let (guild_name, rules) = (&guild_name, &rules);
+            rest::call(ctx, "send_message", move || {
+                ChannelId(channel_id).send_message(&ctx.http, move |m| {
+                    m.content(format!("<@{}>", user_id))
+                        .allowed_mentions(|am| am.users([user_id]));
+                    prompt(m, guild_id, guild_name, rules)
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        (Ok(_), None) => Err("DMs are closed and there's no fallback channel".into()),
+        (Err(e), None) => Err(e.into()),
+    }
+}
+
+/// Handles the onboarding buttons and select menus.
+pub struct OnboardingHandler;
+
+#[async_trait]
+impl EventHandler for OnboardingHandler {
+    fn event_type(&self) -> &'static str {
+        "interaction"
+    }
+
+    async fn on_interaction(&self, ctx: Context, interaction: &Interaction) {
+        let component = match interaction {
+            Interaction::MessageComponent(component) => component,
+            _ => return,
+        };
+        let parts: Vec<&str> = match component.data.custom_id.strip_prefix("onboarding:") {
+            Some(rest) => rest.split(':').collect(),
+            None => return,
+        };
+
+        let result = match parts.as_slice() {
+            ["start", guild_id] => match guild_id.parse() {
+                Ok(guild_id) => step(&ctx, component, GuildId(guild_id), 0).await,
+                Err(_) => return,
+            },
+            [action @ ("answer" | "skip"), guild_id, index] => {
+                match (guild_id.parse(), index.parse::<usize>()) {
+                    (Ok(guild_id), Ok(index)) => {
+                        let picked: Vec<usize> = if *action == "answer" {
+                            component
+                                .data
+                                .values
+                                .iter()
+                                .filter_map(|value| value.parse().ok())
+                                .collect()
+                        } else {
+                            Vec::new()
+                        };
+                        answer(&ctx, component, GuildId(guild_id), index, &picked).await
+                    }
+                    _ => return,
+                }
+            }
+            _ => return,
+        };
+
+        if let Err(e) = result {
+            error!("Onboarding interaction failed: {:?}", e);
+        }
+    }
+}
+
+/// The response kind for a step. Prompts in a server channel are shared, so each
+/// member continues in a message only they can see.
+fn response_kind(component: &MessageComponentInteraction) -> InteractionResponseType {
+    if component.guild_id.is_some() && component.data.custom_id.starts_with("onboarding:start:") {
+        InteractionResponseType::ChannelMessageWithSource
+    } else {
+        InteractionResponseType::UpdateMessage
+    }
+}
+
+/// Give the roles for an answer, then show the next step.
+async fn answer(
+    ctx: &Context,
+    component: &MessageComponentInteraction,
+    guild_id: GuildId,
+    index: usize,
+    picked: &[usize],
+) -> CommandResult {
+    let flow = guild_config(ctx, guild_id).await.onboarding;
+    let question = match flow.questions.get(index) {
+        Some(question) => question,
+        None => {
+            reply_ephemeral(ctx, component, "This question was removed, please rejoin.").await?;
+            return Ok(());
+        }
+    };
+
+    let (add, remove) = question.roles_for(picked);
+    if let Err(e) = edit_roles(ctx, guild_id, component.user.id, &add, &remove).await {
+        reply_ephemeral(ctx, component, "I couldn't give you those roles.").await?;
+        return Err(e);
+    }
+
+    step(ctx, component, guild_id, index + 1).await
+}
+
+/// Show the question at `index`, or finish the flow after the last one.
+async fn step(
+    ctx: &Context,
+    component: &MessageComponentInteraction,
+    guild_id: GuildId,
+    index: usize,
+) -> CommandResult {
+    let flow = guild_config(ctx, guild_id).await.onboarding;
+    if !flow.enabled {
+        reply_ephemeral(ctx, component, "Onboarding is turned off in this server.").await?;
+        return Ok(());
+    }
+    if guild_id.member(ctx, component.user.id).await.is_err() {
+        reply_ephemeral(ctx, component, "You're no longer in this server.").await?;
+        return Ok(());
+    }
+
+    let kind = response_kind(component);
+    match flow.questions.get(index) {
+        Some(question) => {
+            let total = flow.questions.len();
+            component
+                .create_interaction_response(&ctx.http, |r| {
+                    question_response(r, kind, guild_id, index, total, question)
+                })
+                .await?;
+            Ok(())
+        }
+        None => finish(ctx, component, guild_id, &flow, kind).await,
+    }
+}
+
+/// Build the response showing a question.
+fn question_response<'a, 'b>(
+    r: &'b mut CreateInteractionResponse<'a>,
+    kind: InteractionResponseType,
+    guild_id: GuildId,
+    index: usize,
+    total: usize,
+    question: &Question,
+) -> &'b mut CreateInteractionResponse<'a> {
+    r.kind(kind).interaction_response_data(|d| {
+        if kind == InteractionResponseType::ChannelMessageWithSource {
+            d.ephemeral(true);
+        }
+        d.embed(|e| {
+            e.title(format!("Question {} of {}", index + 1, total))
+                .description(&question.prompt)
+                .color(DEFAULT_COLOR)
+        })
+        .components(|c| {
+            c.create_action_row(|row| {
+                row.create_select_menu(|menu| {
+                    menu.custom_id(format!("onboarding:answer:{}:{}", guild_id, index))
+                        .placeholder("Pick an answer")
+                        .min_values(1)
+                        .max_values(if question.multiple {
+                            question.choices.len() as u64
+                        } else {
+                            1
+                        })
+                        .options(|options| {
+                            for (i, choice) in question.choices.iter().enumerate() {
+                                options.create_option(|o| o.label(&choice.label).value(i));
+                            }
+                            options
+                        })
+                })
+            })
+            .create_action_row(|row| {
+                row.create_button(|b| {
+                    b.custom_id(format!("onboarding:skip:{}:{}", guild_id, index))
+                        .label("Skip")
+                        .style(ButtonStyle::Secondary)
+                })
+            })
+        })
+    })
+}
+
+/// Grant the access roles, unless the account is too new.
+async fn finish(
+    ctx: &Context,
+    component: &MessageComponentInteraction,
+    guild_id: GuildId,
+    flow: &OnboardingFlow,
+    kind: InteractionResponseType,
+) -> CommandResult {
+    let user_id = component.user.id;
+    let age_days =
+        unix_timestamp().saturating_sub(user_id.created_at().unix_timestamp() as u64) / 86_400;
+
+    let (title, description, color) = if age_days < flow.min_account_age_days {
+        let alert = send_staff_alert(
+            ctx,
+            guild_id,
+            "🛂 Onboarding held",
+            format!(
+                "<@{}> finished onboarding, but their account is only {} days old. Give them the access roles if they're fine.",
+                user_id, age_days
+            ),
+        )
+        .await;
+        if let Err(e) = alert {
+            error!("Failed to send onboarding alert: {}", e);
+        }
+        (
+            "Almost there",
+            "Your account is quite new, so a staff member will check it and give you access soon.",
+            WARNING_COLOR,
+        )
+    } else {
+        let access: Vec<RoleId> = flow.access_roles.iter().copied().map(RoleId).collect();
+        if let Err(e) = edit_roles(ctx, guild_id, user_id, &access, &[]).await {
+            reply_ephemeral(
+                ctx,
+                component,
+                "I couldn't give you access, please ask the staff.",
+            )
+            .await?;
+            return Err(e);
+        }
+        (
+            "You're all set",
+            "Thanks for answering. Enjoy the server!",
+            SUCCESS_COLOR,
+        )
+    };
+
+    component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(kind).interaction_response_data(|d| {
+                if kind == InteractionResponseType::ChannelMessageWithSource {
+                    d.ephemeral(true);
+                }
+                d.embed(|e| e.title(title).description(description).color(color))
+                    .components(|c| c)
+            })
+        })
+        .await?;
+    Ok(())
+}
+
+/// Add and remove roles of a member in one request.
+async fn edit_roles(
+    ctx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    add: &[RoleId],
+    remove: &[RoleId],
+) -> CommandResult {
+    let member = guild_id.member(ctx, user_id).await?;
+    let mut roles: HashSet<RoleId> = member.roles.iter().copied().collect();
+    let before = roles.clone();
+    roles.extend(add);
+    roles.retain(|role_id| !remove.contains(role_id));
+    if roles == before {
+        return Ok(());
+    }
+
+    let roles: Vec<RoleId> = roles.into_iter().collect();
+    rest::call(ctx, "edit_member", || {
+        guild_id.edit_member(&ctx.http, user_id, |m| m.roles(&roles))
+    })
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,168 @@
+//! Middleware that removes phishing links from new and edited messages.
+
+use async_trait::async_trait;
+use serenity::model::id::{ChannelId, GuildId, MessageId};
+use serenity::model::user::User;
+use serenity::prelude::*;
+use tracing::error;
+
+use super::automod;
+use crate::framework::middleware::{Event, Middleware, Propagation};
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::guild_config::{guild_config, PhishingAction};
+use crate::models::ignore::ignored_in;
+use crate::utils::constants::MAX_TIMEOUT;
+use crate::utils::helpers::{send_staff_alert, BotConfigKey};
+use crate::utils::phishing::PhishingKey;
+use crate::utils::rest::{self, Priority};
+
+/// Scans message links against the phishing blocklist and takes the guild's
+/// configured actions when one matches.
+pub struct PhishingMiddleware;
+
+#[async_trait]
+impl Middleware for PhishingMiddleware {
+    fn name(&self) -> &str {
+        "phishing"
+    }
+
+    fn priority(&self) -> i32 {
+        // Before the word filter, so a phishing link isn't only handled as a bad word
+        110
+    }
+
+    async fn handle(&self, ctx: &Context, event: &Event<'_>) -> Propagation {
+        let (guild_id, channel_id, message_id, author, content) = match *event {
+            Event::Message(msg) => match msg.guild_id {
+                Some(guild_id) => (guild_id, msg.channel_id, msg.id, &msg.author, &msg.content),
+                None => return Propagation::Continue,
+            },
+            Event::MessageUpdate(update) => {
+                match (update.guild_id, &update.author, &update.content) {
+                    (Some(guild_id), Some(author), Some(content)) => {
+                        (guild_id, update.channel_id, update.id, author, content)
+                    }
+                    _ => return Propagation::Continue,
+                }
+            }
+            _ => return Propagation::Continue,
+        };
+        if author.id == ctx.cache.current_user_id() || !content.contains("://") {
+            return Propagation::Continue;
+        }
+
+        let actions = guild_config(ctx, guild_id).await.phishing_actions;
+        if actions.is_empty() {
+            return Propagation::Continue;
+        }
+        let roles = ctx
+            .cache
+            .member(guild_id, author.id)
+            .map(|member| member.roles)
+            .unwrap_or_default();
+        if ignored_in(ctx, guild_id, channel_id, author.id, &roles)
+            .await
+            .passive
+        {
+            return Propagation::Continue;
+        }
+        let list = {
+            let data = ctx.data.read().await;
+            data.get::<PhishingKey>().cloned()
+        };
+        let domain = match list {
+            Some(list) => list.scan(content).await,
+            None => None,
+        };
+        match domain {
+            Some(domain) => {
+                enforce(
+                    ctx, guild_id, channel_id, message_id, author, &domain, &actions,
+                )
+                .await
+            }
+            None => Propagation::Continue,
+        }
+    }
+}
+
+/// Take the guild's phishing actions against a message.
+///
+/// Stops the event if the message was deleted, so nothing else reacts to it.
+async fn enforce(
+    ctx: &Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    message_id: MessageId,
+    author: &User,
+    domain: &str,
+    actions: &[PhishingAction],
+) -> Propagation {
+    let reason = format!("Phishing link to {}", domain);
+    let mut taken = Vec::new();
+    let mut propagation = Propagation::Continue;
+
+    if actions.contains(&PhishingAction::Delete) {
+        match rest::call_with(ctx, Priority::Moderation, "delete_message", || {
+            channel_id.delete_message(&ctx.http, message_id)
+        })
+        .await
+        {
+            Ok(()) => {
+                taken.push("deleted the message");
+                propagation = Propagation::Stop;
+            }
+            Err(e) => error!("Failed to delete phishing message {}: {}", message_id, e),
+        }
+    }
+
+    if actions.contains(&PhishingAction::Timeout) {
+        let seconds = {
+            let data = ctx.data.read().await;
+            data.get::<BotConfigKey>()
+                .map(|config| config.phishing.timeout)
+                .unwrap_or_default()
+        }
+        .min(MAX_TIMEOUT);
+        match automod::timeout(ctx, guild_id, author.id, seconds, &reason).await {
+            Ok(()) => taken.push("timed out the author"),
+            Err(e) => error!("Failed to time out {} for phishing: {}", author.id, e),
+        }
+    }
+
+    if actions.contains(&PhishingAction::Log) {
+        let mut description = format!(
+            "<@{}> posted a link to `{}` in <#{}>.",
+            author.id, domain, channel_id
+        );
+        if !taken.is_empty() {
+            description.push_str(&format!("\nI {}.", taken.join(" and ")));
+        }
+        match send_staff_alert(ctx, guild_id, "🎣 Phishing link", description).await {
+            Ok(Some(_)) => taken.push("alerted staff"),
+            Ok(None) => {}
+            Err(e) => error!("Failed to send phishing alert: {}", e),
+        }
+    }
+
+    if !taken.is_empty() {
+        audit::record(
+            ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: Some(ctx.cache.current_user_id()),
+                source: AuditSource::Automod,
+                action: format!(
+                    "Catch a phishing link from <@{}> in <#{}>: {}",
+                    author.id,
+                    channel_id,
+                    taken.join(", ")
+                ),
+                reason: Some(reason),
+            },
+        )
+        .await;
+    }
+
+    propagation
+}
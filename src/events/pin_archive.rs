@@ -0,0 +1,88 @@
+//! Handler that archives unpinned messages and makes room when pins are full.
+
+use async_trait::async_trait;
+use serenity::model::event::ChannelPinsUpdateEvent;
+use serenity::model::id::MessageId;
+use serenity::prelude::*;
+use tracing::{debug, error};
+
+use crate::framework::command_handler::CommandResult;
+use crate::framework::event_handler::EventHandler;
+use crate::models::guild_config::guild_config;
+use crate::models::pin_archive::PinArchiveKey;
+use crate::utils::constants::PIN_LIMIT;
+use crate::utils::helpers::archive_pin;
+use crate::utils::rest;
+
+/// Archives messages when they are unpinned, and unpins the oldest message once a
+/// channel reaches Discord's pin limit.
+pub struct PinArchiveHandler;
+
+impl PinArchiveHandler {
+    async fn handle(&self, ctx: &Context, event: &ChannelPinsUpdateEvent) -> CommandResult {
+        let guild_id = match event.guild_id {
+            Some(guild_id) => guild_id,
+            None => return Ok(()),
+        };
+        let channel_id = event.channel_id;
+        match guild_config(ctx, guild_id).await.pin_archive_channel {
+            Some(archive_channel) if archive_channel != channel_id.0 => {}
+            _ => return Ok(()),
+        }
+        let store = {
+            let data = ctx.data.read().await;
+            data.get::<PinArchiveKey>().cloned()
+        };
+        let store = match store {
+            Some(store) => store,
+            None => return Ok(()),
+        };
+
+        let pins = rest::call(ctx, "pins", || channel_id.pins(&ctx.http)).await?;
+        let pin_ids: Vec<MessageId> = pins.iter().map(|pin| pin.id).collect();
+        let unpinned = store.update(|data| data.sync(channel_id, &pin_ids)).await?;
+
+        for message_id in unpinned {
+            // Deleting a pinned message also unpins it, and then there's nothing to archive
+            let message = match rest::call(ctx, "get_message", || {
+                channel_id.message(&ctx.http, message_id)
+            })
+            .await
+            {
+                Ok(message) => message,
+                Err(e) => {
+                    debug!("Couldn't fetch unpinned message {}: {}", message_id, e);
+                    continue;
+                }
+            };
+            archive_pin(ctx, guild_id, &message).await?;
+        }
+
+        // Pins are returned newest first, so the last one is the oldest
+        if pins.len() >= PIN_LIMIT {
+            if let Some(oldest) = pins.last() {
+                archive_pin(ctx, guild_id, oldest).await?;
+                rest::call(ctx, "unpin", || oldest.unpin(&ctx.http)).await?;
+                debug!(
+                    "Archived pin {} from {} to make room",
+                    oldest.id, channel_id
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EventHandler for PinArchiveHandler {
+    fn event_type(&self) -> &'static str {
+        "channel_pins_update"
+    }
+
+    async fn on_channel_pins_update(&self, ctx: Context, event: &ChannelPinsUpdateEvent) {
+        if let Err(e) = self.handle(&ctx, event).await {
+            error!("Pin archive in {} failed: {}", event.channel_id, e);
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! Handler that pins messages once they get enough votes.
+
+use async_trait::async_trait;
+use serenity::model::channel::Reaction;
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use crate::framework::command_handler::CommandResult;
+use crate::framework::event_handler::EventHandler;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::guild_config::guild_config;
+use crate::models::message_cache::{CachedMessage, MessageCacheKey};
+use crate::models::pin_votes::{PinVoteKey, VotedPin, MAX_PIN_THRESHOLD};
+use crate::utils::rest;
+
+/// Whether the author's cached messages include a copy of `message` that was
+/// already pinned by vote, so reposting something doesn't get it pinned twice.
+fn is_repost(
+    message: &CachedMessage,
+    recent: &[CachedMessage],
+    pinned: &HashMap<u64, VotedPin>,
+) -> bool {
+    let content = message.content.trim();
+    !content.is_empty()
+        && recent.iter().any(|other| {
+            other.id != message.id
+                && pinned.contains_key(&other.id.0)
+                && other.content.trim().eq_ignore_ascii_case(content)
+        })
+}
+
+/// Pins a message when enough members react with the pin vote emoji. Bots and
+/// the message's author don't count.
+pub struct PinVoteHandler;
+
+impl PinVoteHandler {
+    async fn handle(&self, ctx: &Context, reaction: &Reaction) -> CommandResult {
+        let guild_id = match reaction.guild_id {
+            Some(guild_id) => guild_id,
+            None => return Ok(()),
+        };
+        let from_bot = reaction
+            .member
+            .as_ref()
+            .and_then(|member| member.user.as_ref())
+            .is_some_and(|user| user.bot);
+        if from_bot {
+            return Ok(());
+        }
+        let settings = guild_config(ctx, guild_id).await.pin_votes;
+        let threshold = match settings.threshold(reaction.channel_id) {
+            Some(threshold) if settings.is_vote(&reaction.emoji) => {
+                threshold.min(MAX_PIN_THRESHOLD)
+            }
+            _ => return Ok(()),
+        };
+        let store = {
+            let data = ctx.data.read().await;
+            data.get::<PinVoteKey>().cloned()
+        };
+        let store = match store {
+            Some(store) => store,
+            None => return Ok(()),
+        };
+        if store
+            .read()
+            .await
+            .pinned
+            .contains_key(&reaction.message_id.0)
+        {
+            return Ok(());
+        }
+
+        // The message cache knows about deleted messages and reposts without
+        // asking Discord
+        let cache = {
+            let data = ctx.data.read().await;
+            data.get::<MessageCacheKey>().cloned()
+        };
+        let cached = cache.and_then(|cache| Some((cache.get(reaction.message_id)?, cache)));
+        if let Some((cached, cache)) = cached {
+            if cached.deleted_at.is_some() {
+                return Ok(());
+            }
+            let recent = cache.recent_by_author(guild_id, cached.author_id, Duration::MAX);
+            if is_repost(&cached, &recent, &store.read().await.pinned) {
+                debug!(
+                    "Not pinning {}, a copy of it was already pinned",
+                    reaction.message_id
+                );
+                return Ok(());
+            }
+        }
+
+        let (channel_id, message_id) = (reaction.channel_id, reaction.message_id);
+        let message = rest::call(ctx, "get_message", || {
+            channel_id.message(&ctx.http, message_id)
+        })
+        .await?;
+        let count = message
+            .reactions
+            .iter()
+            .find(|r| r.reaction_type == reaction.emoji)
+            .map_or(0, |r| r.count);
+        if message.pinned || count < threshold as u64 {
+            return Ok(());
+        }
+
+        // The reaction count includes bots and the author, so count the voters
+        let voters = rest::call(ctx, "reaction_users", || {
+            message.reaction_users(&ctx.http, reaction.emoji.clone(), Some(100), None)
+        })
+        .await?;
+        let votes = voters
+            .iter()
+            .filter(|user| !user.bot && user.id != message.author.id)
+            .count() as u32;
+        if votes < threshold {
+            return Ok(());
+        }
+
+        if !store
+            .update(|data| data.claim(channel_id, message_id))
+            .await?
+        {
+            return Ok(());
+        }
+        if let Err(e) = rest::call(ctx, "pin", || channel_id.pin(&ctx.http, message_id)).await {
+            store.update(|data| data.release(message_id)).await?;
+            return Err(e.into());
+        }
+
+        info!(
+            "Pinned message {} in channel {} with {} votes",
+            message_id, channel_id, votes
+        );
+        audit::record(
+            ctx,
+            AuditEvent {
+                guild_id: Some(guild_id),
+                actor_id: None,
+                source: AuditSource::Vote,
+                action: format!(
+                    "Pin {} in <#{}> with {} {} votes",
+                    message.link(),
+                    channel_id,
+                    votes,
+                    settings.emoji()
+                ),
+                reason: None,
+            },
+        )
+        .await;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EventHandler for PinVoteHandler {
+    fn event_type(&self) -> &'static str {
+        "reaction_add"
+    }
+
+    async fn on_reaction_add(&self, ctx: Context, reaction: &Reaction) {
+        if let Err(e) = self.handle(&ctx, reaction).await {
+            warn!(
+                "Failed to pin message {} by vote: {}",
+                reaction.message_id, e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serenity::model::id::{ChannelId, GuildId, MessageId, UserId};
+
+    fn message(id: u64, content: &str) -> CachedMessage {
+        CachedMessage {
+            id: MessageId(id),
+            channel_id: ChannelId(1),
+            guild_id: Some(GuildId(2)),
+            author_id: UserId(3),
+            author_tag: "user#0001".to_string(),
+            bot: false,
+            content: content.to_string(),
+            attachments: Vec::new(),
+            created_at: 0,
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn skips_copies_of_pinned_messages() {
+        let mut pinned = HashMap::new();
+        pinned.insert(
+            1,
+            VotedPin {
+                channel_id: 1,
+                pinned_at: 0,
+            },
+        );
+        let recent = [message(1, "Great news "), message(2, "other")];
+
+        assert!(is_repost(&message(3, "great news"), &recent, &pinned));
+        assert!(!is_repost(&message(3, "other"), &recent, &pinned));
+        assert!(!is_repost(&message(1, "Great news"), &recent, &pinned));
+        assert!(!is_repost(&message(3, ""), &recent, &pinned));
+    }
+}
@@ -3,7 +3,7 @@
 use async_trait::async_trait;
 use serenity::model::gateway::Ready;
 use serenity::prelude::*;
-use tracing::{error, info};
+use tracing::info;
 
 use crate::framework::event_handler::EventHandler;
 use crate::utils::helpers::BotConfigKey;
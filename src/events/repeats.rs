@@ -0,0 +1,185 @@
+//! Middleware that removes messages members keep posting over and over, going
+//! by what they sent recently in the message cache.
+
+use async_trait::async_trait;
+use serenity::model::id::{GuildId, UserId};
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::error;
+
+use crate::framework::middleware::{Event, Middleware, Propagation};
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::guild_config::guild_config;
+use crate::models::ignore::ignored_in;
+use crate::models::message_cache::{CachedMessage, MessageCacheKey};
+use crate::utils::helpers::{author_permissions, send_staff_alert};
+use crate::utils::rest::{self, Priority};
+
+/// How far back identical messages count.
+const REPEAT_WINDOW: Duration = Duration::from_secs(60);
+
+/// How many of a member's earlier messages in the window say the same as
+/// `content`, ignoring case and surrounding whitespace.
+fn count_repeats(recent: &[CachedMessage], content: &str) -> usize {
+    let content = content.trim();
+    recent
+        .iter()
+        .filter(|message| message.deleted_at.is_none())
+        .filter(|message| message.content.trim().eq_ignore_ascii_case(content))
+        .count()
+}
+
+/// Deletes a member's message once they've sent the same text more than the
+/// guild's `repeat_limit` times within a minute, alerting staff once per burst.
+///
+/// Members who can manage messages aren't checked.
+#[derive(Default)]
+pub struct RepeatMiddleware {
+    /// When staff were last alerted about a member.
+    alerted: Mutex<HashMap<(GuildId, UserId), Instant>>,
+}
+
+impl RepeatMiddleware {
+    /// Whether staff should hear about a member's repeats, which they do once
+    /// per [`REPEAT_WINDOW`].
+    fn should_alert(&self, guild_id: GuildId, user_id: UserId) -> bool {
+        let mut alerted = self.alerted.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        alerted.retain(|_, at| now.duration_since(*at) < REPEAT_WINDOW);
+        alerted.insert((guild_id, user_id), now).is_none()
+    }
+}
+
+#[async_trait]
+impl Middleware for RepeatMiddleware {
+    fn name(&self) -> &str {
+        "repeats"
+    }
+
+    fn priority(&self) -> i32 {
+        // After the word filter, which handles messages that break its rules
+        90
+    }
+
+    async fn handle(&self, ctx: &Context, event: &Event<'_>) -> Propagation {
+        let (msg, guild_id) = match *event {
+            Event::Message(msg) => match msg.guild_id {
+                Some(guild_id) if !msg.author.bot && !msg.content.trim().is_empty() => {
+                    (msg, guild_id)
+                }
+                _ => return Propagation::Continue,
+            },
+            _ => return Propagation::Continue,
+        };
+
+        let limit = guild_config(ctx, guild_id).await.repeat_limit;
+        if limit == 0 {
+            return Propagation::Continue;
+        }
+        if author_permissions(ctx, msg)
+            .await
+            .is_some_and(|permissions| permissions.manage_messages())
+        {
+            return Propagation::Continue;
+        }
+        let roles = msg
+            .member
+            .as_ref()
+            .map(|member| member.roles.clone())
+            .unwrap_or_default();
+        if ignored_in(ctx, guild_id, msg.channel_id, msg.author.id, &roles)
+            .await
+            .passive
+        {
+            return Propagation::Continue;
+        }
+
+        let cache = {
+            let data = ctx.data.read().await;
+            data.get::<MessageCacheKey>().cloned()
+        };
+        let recent = match cache {
+            Some(cache) => cache.recent_by_author(guild_id, msg.author.id, REPEAT_WINDOW),
+            None => return Propagation::Continue,
+        };
+        if count_repeats(&recent, &msg.content) < limit as usize {
+            return Propagation::Continue;
+        }
+
+        let (channel_id, message_id) = (msg.channel_id, msg.id);
+        if let Err(e) = rest::call_with(ctx, Priority::Moderation, "delete_message", || {
+            channel_id.delete_message(&ctx.http, message_id)
+        })
+        .await
+        {
+            error!("Failed to delete repeated message {}: {}", message_id, e);
+            return Propagation::Continue;
+        }
+
+        if self.should_alert(guild_id, msg.author.id) {
+            let description = format!(
+                "<@{}> sent the same message more than {} times in a minute in <#{}>, so I'm \
+                 deleting the repeats.",
+                msg.author.id, limit, channel_id
+            );
+            if let Err(e) =
+                send_staff_alert(ctx, guild_id, "🔁 Repeated messages", description).await
+            {
+                error!("Failed to send repeated message alert: {}", e);
+            }
+            audit::record(
+                ctx,
+                AuditEvent {
+                    guild_id: Some(guild_id),
+                    actor_id: Some(ctx.cache.current_user_id()),
+                    source: AuditSource::Automod,
+                    action: format!(
+                        "Delete repeated messages from <@{}> in <#{}>",
+                        msg.author.id, channel_id
+                    ),
+                    reason: None,
+                },
+            )
+            .await;
+        }
+        Propagation::Stop
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serenity::model::id::{ChannelId, MessageId};
+
+    fn message(id: u64, content: &str) -> CachedMessage {
+        CachedMessage {
+            id: MessageId(id),
+            channel_id: ChannelId(1),
+            guild_id: Some(GuildId(2)),
+            author_id: UserId(3),
+            author_tag: "user#0001".to_string(),
+            bot: false,
+            content: content.to_string(),
+            attachments: Vec::new(),
+            created_at: 0,
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn counts_identical_messages() {
+        let mut deleted = message(3, "buy now");
+        deleted.deleted_at = Some(Instant::now());
+        let recent = [message(1, "Buy now "), message(2, "hello"), deleted];
+
+        assert_eq!(count_repeats(&recent, "buy NOW"), 1);
+        assert_eq!(count_repeats(&recent, "bye"), 0);
+
+        let middleware = RepeatMiddleware::default();
+        assert!(middleware.should_alert(GuildId(2), UserId(3)));
+        assert!(!middleware.should_alert(GuildId(2), UserId(3)));
+        assert!(middleware.should_alert(GuildId(2), UserId(4)));
+    }
+}
@@ -0,0 +1,569 @@
+//! Handlers for the message report flow.
+//!
+//! Members report a message from the "Report Message" context menu, or with the
+//! button the `report` command sends. Both open a modal asking what is wrong.
+//! The flow uses these component IDs:
+//! - `report:start:<user>:<channel>:<message>`: button sent by the `report` command.
+//! - `report:submit:<channel>:<message>`: the modal, posts the report to the staff channel.
+//! - `report:<action>:<guild>:<report>`: staff buttons, where the action is `delete`,
+//!   `warn`, `timeout` or `dismiss`.
+
+use async_trait::async_trait;
+use serenity::builder::{
+    CreateApplicationCommand, CreateComponents, CreateEmbed, CreateInteractionResponse,
+};
+use serenity::model::application::command::CommandType;
+use serenity::model::application::component::{ActionRowComponent, ButtonStyle, InputTextStyle};
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::modal::ModalSubmitInteraction;
+use serenity::model::application::interaction::{Interaction, InteractionResponseType};
+use serenity::model::id::{ChannelId, GuildId, MessageId, UserId};
+use serenity::model::permissions::Permissions;
+use serenity::model::timestamp::Timestamp;
+use serenity::prelude::*;
+use tracing::{error, warn};
+
+use crate::framework::command_handler::CommandResult;
+use crate::framework::event_handler::EventHandler;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::guild_config::guild_config;
+use crate::models::moderation::{CaseKind, ModerationKey, Report, ReportAction, ReportStatus};
+use crate::models::notifications::DmCategory;
+use crate::utils::constants::{
+    DEFAULT_COLOR, REPORT_MENU_NAME, REPORT_TIMEOUT, SUCCESS_COLOR, WARNING_COLOR,
+};
+use crate::utils::dms::{dm_service, DmOutcome};
+use crate::utils::helpers::{quote_embed, reply_ephemeral, truncate, unix_timestamp};
+use crate::utils::rest;
+
+/// Staff buttons on a report: the action ID, label and style.
+const STAFF_BUTTONS: [(&str, &str, ButtonStyle); 4] = [
+    ("delete", "Delete message", ButtonStyle::Secondary),
+    ("warn", "Warn", ButtonStyle::Primary),
+    ("timeout", "Timeout", ButtonStyle::Danger),
+    ("dismiss", "Dismiss", ButtonStyle::Secondary),
+];
+
+/// The "Report Message" context menu command.
+pub fn report_menu() -> CreateApplicationCommand {
+    let mut command = CreateApplicationCommand::default();
+    command
+        .name(REPORT_MENU_NAME)
+        .kind(CommandType::Message)
+        .dm_permission(false);
+    command
+}
+
+/// Handles the report context menu, modal and staff buttons.
+pub struct ReportHandler;
+
+#[async_trait]
+impl EventHandler for ReportHandler {
+    fn event_type(&self) -> &'static str {
+        "interaction"
+    }
+
+    async fn on_interaction(&self, ctx: Context, interaction: &Interaction) {
+        let result = match interaction {
+            Interaction::ApplicationCommand(command) if command.data.name == REPORT_MENU_NAME => {
+                match command.data.target_id {
+                    Some(target) => open_from_menu(&ctx, command, target.to_message_id()).await,
+                    None => return,
+                }
+            }
+            Interaction::MessageComponent(component) => {
+                let parts: Vec<&str> = match component.data.custom_id.strip_prefix("report:") {
+                    Some(rest) => rest.split(':').collect(),
+                    None => return,
+                };
+
+                match parts.as_slice() {
+                    ["start", user_id, channel_id, message_id] => {
+                        match (user_id.parse(), channel_id.parse(), message_id.parse()) {
+                            (Ok(user_id), Ok(channel_id), Ok(message_id)) => {
+                                let message = (ChannelId(channel_id), MessageId(message_id));
+                                start(&ctx, component, UserId(user_id), message).await
+                            }
+                            _ => return,
+                        }
+                    }
+                    [action, guild_id, report_id] => {
+                        let action = match *action {
+                            "delete" => Some(ReportAction::Delete),
+                            "warn" => Some(ReportAction::Warn),
+                            "timeout" => Some(ReportAction::Timeout),
+                            "dismiss" => None,
+                            _ => return,
+                        };
+                        match (guild_id.parse(), report_id.parse()) {
+                            (Ok(guild_id), Ok(report_id)) => {
+                                act(&ctx, component, GuildId(guild_id), report_id, action).await
+                            }
+                            _ => return,
+                        }
+                    }
+                    _ => return,
+                }
+            }
+            Interaction::ModalSubmit(modal) => {
+                let ids = modal
+                    .data
+                    .custom_id
+                    .strip_prefix("report:submit:")
+                    .and_then(|ids| ids.split_once(':'))
+                    .and_then(|(channel_id, message_id)| {
+                        Some((channel_id.parse().ok()?, message_id.parse().ok()?))
+                    });
+                match ids {
+                    Some((channel_id, message_id)) => {
+                        submit(&ctx, modal, ChannelId(channel_id), MessageId(message_id)).await
+                    }
+                    None => return,
+                }
+            }
+            _ => return,
+        };
+
+        if let Err(e) = result {
+            error!("Report interaction failed: {:?}", e);
+        }
+    }
+}
+
+/// Check whether a guild takes reports, returning the reason if it doesn't.
+async fn check_reportable(ctx: &Context, guild_id: GuildId) -> Option<&'static str> {
+    if guild_config(ctx, guild_id).await.staff_channel.is_none() {
+        return Some("This server isn't accepting reports right now.");
+    }
+    None
+}
+
+/// Build the modal asking the reporter what is wrong with a message.
+fn report_modal<'a, 'b>(
+    r: &'b mut CreateInteractionResponse<'a>,
+    channel_id: ChannelId,
+    message_id: MessageId,
+) -> &'b mut CreateInteractionResponse<'a> {
+    r.kind(InteractionResponseType::Modal)
+        .interaction_response_data(|d| {
+            d.custom_id(format!("report:submit:{}:{}", channel_id, message_id))
+                .title("Report message")
+                .components(|c| {
+                    c.create_action_row(|row| {
+                        row.create_input_text(|t| {
+                            t.custom_id("reason")
+                                .label("What's wrong with this message?")
+                                .style(InputTextStyle::Paragraph)
+                                .min_length(5)
+                                .max_length(1000)
+                                .required(true)
+                        })
+                    })
+                })
+        })
+}
+
+/// Add the staff buttons of an open report, leaving out actions already taken.
+fn staff_buttons<'a>(
+    c: &'a mut CreateComponents,
+    guild_id: GuildId,
+    report: &Report,
+) -> &'a mut CreateComponents {
+    if report.status != ReportStatus::Open {
+        return c;
+    }
+
+    c.create_action_row(|row| {
+        for (action, label, style) in STAFF_BUTTONS {
+            if action == "delete" && report.actions.contains(&ReportAction::Delete) {
+                continue;
+            }
+            row.create_button(|b| {
+                b.custom_id(format!("report:{}:{}:{}", action, guild_id, report.id))
+                    .label(label)
+                    .style(style)
+            });
+        }
+        row
+    })
+}
+
+/// Open the report modal from the message context menu.
+async fn open_from_menu(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    message_id: MessageId,
+) -> CommandResult {
+    let refusal = match command.guild_id {
+        Some(guild_id) => check_reportable(ctx, guild_id).await,
+        None => Some("Messages can only be reported in servers."),
+    };
+
+    command
+        .create_interaction_response(&ctx.http, |r| match refusal {
+            Some(refusal) => r
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|d| d.content(refusal).ephemeral(true)),
+            None => report_modal(r, command.channel_id, message_id),
+        })
+        .await?;
+    Ok(())
+}
+
+/// Open the report modal from the button sent by the `report` command.
+async fn start(
+    ctx: &Context,
+    component: &MessageComponentInteraction,
+    reporter_id: UserId,
+    (channel_id, message_id): (ChannelId, MessageId),
+) -> CommandResult {
+    if component.user.id != reporter_id {
+        let content = "Only the member who used `report` can fill in this report.";
+        reply_ephemeral(ctx, component, content).await?;
+        return Ok(());
+    }
+    let refusal = match component.guild_id {
+        Some(guild_id) => check_reportable(ctx, guild_id).await,
+        None => Some("Messages can only be reported in servers."),
+    };
+    if let Some(refusal) = refusal {
+        reply_ephemeral(ctx, component, refusal).await?;
+        return Ok(());
+    }
+
+    component
+        .create_interaction_response(&ctx.http, |r| report_modal(r, channel_id, message_id))
+        .await?;
+    Ok(())
+}
+
+/// Record a submitted report and post it to the staff channel.
+async fn submit(
+    ctx: &Context,
+    modal: &ModalSubmitInteraction,
+    channel_id: ChannelId,
+    message_id: MessageId,
+) -> CommandResult {
+    let guild_id = match modal.guild_id {
+        Some(guild_id) => guild_id,
+        None => return Ok(()),
+    };
+    let reason = modal
+        .data
+        .components
+        .iter()
+        .flat_map(|row| &row.components)
+        .find_map(|component| match component {
+            ActionRowComponent::InputText(input) if input.custom_id == "reason" => {
+                Some(input.value.clone())
+            }
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    let response =
+        file_report(ctx, guild_id, modal.user.id, channel_id, message_id, reason).await?;
+
+    modal
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|d| d.content(response).ephemeral(true))
+        })
+        .await?;
+    Ok(())
+}
+
+/// Snapshot the reported message, record the report and alert staff.
+///
+/// Returns the reply for the reporter.
+async fn file_report(
+    ctx: &Context,
+    guild_id: GuildId,
+    reporter_id: UserId,
+    channel_id: ChannelId,
+    message_id: MessageId,
+    reason: String,
+) -> CommandResult<&'static str> {
+    if let Some(refusal) = check_reportable(ctx, guild_id).await {
+        return Ok(refusal);
+    }
+
+    let message = match rest::call(ctx, "get_message", || {
+        channel_id.message(&ctx.http, message_id)
+    })
+    .await
+    {
+        Ok(message) => message,
+        Err(_) => return Ok("I couldn't find that message. It may have been deleted."),
+    };
+    if message.author.id == reporter_id {
+        return Ok("You can't report your own message.");
+    }
+
+    let store = {
+        let data = ctx.data.read().await;
+        data.get::<ModerationKey>().cloned()
+    }
+    .ok_or("Moderation store is not loaded")?;
+
+    let report = store
+        .update(|data| {
+            let guild = data.guild_mut(guild_id);
+            if guild.open_report(message_id).is_some() {
+                return None;
+            }
+            let id = guild.add_report(reporter_id, &message, reason);
+            guild.reports.get(&id).cloned()
+        })
+        .await?;
+    let report = match report {
+        Some(report) => report,
+        None => return Ok("That message was already reported. The staff will look into it."),
+    };
+
+    let config = guild_config(ctx, guild_id).await;
+    let staff_channel = ChannelId(config.staff_channel.ok_or("No staff channel")?);
+
+    let mut embed = quote_embed(&message, guild_id);
+    embed
+        .title(format!("Report #{}", report.id))
+        .color(WARNING_COLOR)
+        .field(
+            "Reported by",
+            format!("<@{}> (`{}`)", reporter_id, reporter_id),
+            true,
+        )
+        .field(
+            "Author",
+            format!("<@{}> (`{}`)", report.author_id, report.author_id),
+            true,
+        )
+        .field("Reason", truncate(&report.reason, 1024), false);
+
+    staff_channel
+        .send_message(&ctx.http, |m| {
+            if let Some(role_id) = config.staff_role {
+                m.content(format!("<@&{}>", role_id));
+            }
+
+            m.set_embed(embed)
+                .components(|c| staff_buttons(c, guild_id, &report))
+        })
+        .await?;
+
+    Ok("Thanks, your report was sent to the server's staff.")
+}
+
+/// Take a staff action on a report, or dismiss it with `None`.
+///
+/// Deleting the message keeps the report open; any other action closes it.
+async fn act(
+    ctx: &Context,
+    component: &MessageComponentInteraction,
+    guild_id: GuildId,
+    report_id: u64,
+    action: Option<ReportAction>,
+) -> CommandResult {
+    let store = {
+        let data = ctx.data.read().await;
+        data.get::<ModerationKey>().cloned()
+    }
+    .ok_or("Moderation store is not loaded")?;
+
+    let report = {
+        let data = store.read().await;
+        data.guild(guild_id)
+            .and_then(|guild| guild.reports.get(&report_id))
+            .cloned()
+    };
+    let report = match report {
+        Some(report) if report.status == ReportStatus::Open => report,
+        Some(_) => {
+            reply_ephemeral(ctx, component, "This report was already closed.").await?;
+            return Ok(());
+        }
+        None => {
+            reply_ephemeral(ctx, component, "This report no longer exists.").await?;
+            return Ok(());
+        }
+    };
+
+    let required = match action {
+        Some(ReportAction::Delete) => Permissions::MANAGE_MESSAGES,
+        _ => Permissions::MODERATE_MEMBERS,
+    };
+    let permissions = component
+        .member
+        .as_ref()
+        .and_then(|member| member.permissions)
+        .unwrap_or_else(Permissions::empty);
+    if !permissions.contains(required) {
+        let content = format!("You need the {} permission to do that.", required);
+        reply_ephemeral(ctx, component, &content).await?;
+        return Ok(());
+    }
+
+    let staff_id = component.user.id;
+    let author_id = UserId(report.author_id);
+    let reason = format!("Reported message (report #{})", report.id);
+
+    // Act first, so nothing is recorded if Discord refuses
+    let mut expires_at = None;
+    let result = match action {
+        Some(ReportAction::Delete) => {
+            let channel_id = ChannelId(report.channel_id);
+            rest::call(ctx, "delete_message", || {
+                channel_id.delete_message(&ctx.http, report.message_id)
+            })
+            .await
+        }
+        Some(ReportAction::Timeout) => {
+            let until = unix_timestamp() + REPORT_TIMEOUT;
+            expires_at = Some(until);
+            let timestamp =
+                Timestamp::from_unix_timestamp(until as i64).unwrap_or_else(|_| Timestamp::now());
+            rest::call(ctx, "timeout", || {
+                guild_id.edit_member(&ctx.http, author_id, |m| {
+                    m.disable_communication_until_datetime(timestamp)
+                })
+            })
+            .await
+            .map(|_| ())
+        }
+        Some(ReportAction::Warn) | None => Ok(()),
+    };
+    if let Err(e) = result {
+        let content = format!("That didn't work: {}", e);
+        reply_ephemeral(ctx, component, &content).await?;
+        return Ok(());
+    }
+
+    let case_kind = match action {
+        Some(ReportAction::Warn) => Some(CaseKind::Warning),
+        Some(ReportAction::Timeout) => Some(CaseKind::Timeout),
+        _ => None,
+    };
+    let status = match action {
+        Some(ReportAction::Delete) => ReportStatus::Open,
+        Some(_) => ReportStatus::Resolved,
+        None => ReportStatus::Dismissed,
+    };
+    let report = store
+        .update(|data| {
+            let guild = data.guild_mut(guild_id);
+            if let Some(kind) = case_kind {
+                guild.add_case(
+                    author_id,
+                    kind,
+                    Some(staff_id),
+                    Some(reason.clone()),
+                    expires_at,
+                );
+            }
+
+            let report = guild.reports.get_mut(&report_id)?;
+            report.actions.extend(action);
+            report.status = status;
+            if status != ReportStatus::Open {
+                report.resolved_by = Some(staff_id.0);
+            }
+            Some(report.clone())
+        })
+        .await?
+        .ok_or("Report was deleted while acting on it")?;
+
+    let audit_action = match action {
+        Some(ReportAction::Delete) => {
+            format!("Delete the message reported in report #{}", report_id)
+        }
+        Some(ReportAction::Warn) => format!("Warn <@{}>", author_id),
+        Some(ReportAction::Timeout) => format!("Time out <@{}>", author_id),
+        None => format!("Dismiss report #{}", report_id),
+    };
+    let event = AuditEvent {
+        guild_id: Some(guild_id),
+        actor_id: Some(staff_id),
+        source: AuditSource::Interaction,
+        action: audit_action,
+        reason: Some(reason),
+    };
+    audit::record(ctx, event).await;
+
+    let mut embed = component
+        .message
+        .embeds
+        .first()
+        .cloned()
+        .map(CreateEmbed::from)
+        .unwrap_or_default();
+    let outcome = match action {
+        Some(action) => action.to_string(),
+        None => "Dismissed".to_string(),
+    };
+    embed.field("Action", format!("{} by <@{}>", outcome, staff_id), false);
+    match report.status {
+        ReportStatus::Open => {}
+        ReportStatus::Resolved => {
+            embed.color(SUCCESS_COLOR);
+        }
+        ReportStatus::Dismissed => {
+            embed.color(DEFAULT_COLOR);
+        }
+    }
+
+    component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| {
+                    d.set_embed(embed)
+                        .components(|c| staff_buttons(c, guild_id, &report))
+                })
+        })
+        .await?;
+
+    // Let a warned author know unless they turned moderation DMs off, mentioning
+    // them where they sent the message if their DMs are closed
+    if action == Some(ReportAction::Warn) {
+        let guild_name = guild_id
+            .name(&ctx.cache)
+            .unwrap_or_else(|| "the server".to_string());
+        let description = format!(
+            "You were warned in **{}** for a message you sent in <#{}>.",
+            guild_name, report.channel_id
+        );
+        let dm = match dm_service(ctx).await {
+            Some(dms) => {
+                dms.send(&ctx.http, author_id, Some(DmCategory::Moderation), |m| {
+                    m.embed(|e| {
+                        e.title("Warning")
+                            .description(&description)
+                            .color(WARNING_COLOR)
+                    })
+                })
+                .await
+            }
+            None => Ok(DmOutcome::Closed),
+        };
+        match dm {
+            Ok(outcome) if outcome.needs_fallback() => {
+                let notice = ChannelId(report.channel_id)
+                    .send_message(&ctx.http, |m| {
+                        m.content(format!(
+                            "<@{}>, you were warned by staff for a message you sent here.",
+                            author_id
+                        ))
+                        .allowed_mentions(|am| am.empty_parse().users([author_id]))
+                    })
+                    .await;
+                if let Err(e) = notice {
+                    warn!("Couldn't notify {} of a report warning: {}", author_id, e);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Couldn't DM report warning to {}: {}", author_id, e),
+        }
+    }
+
+    Ok(())
+}
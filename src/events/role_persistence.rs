@@ -0,0 +1,185 @@
+//! Handlers that save members' roles when they leave and restore them on rejoin.
+
+use async_trait::async_trait;
+use serenity::model::guild::Member;
+use serenity::model::id::{GuildId, RoleId};
+use serenity::model::user::User;
+use serenity::prelude::*;
+use std::sync::Arc;
+use tracing::{debug, error, info};
+
+use crate::framework::event_handler::EventHandler;
+use crate::models::guild_config::guild_config;
+use crate::models::role_persistence::{
+    should_restore, RolePersistenceData, RolePersistenceKey, SavedMember,
+};
+use crate::storage::JsonStore;
+use crate::utils::helpers::{send_staff_alert, unix_timestamp};
+use crate::utils::rest::{self, Priority};
+
+/// Get the role persistence store from the client data.
+async fn role_store(ctx: &Context) -> Option<Arc<JsonStore<RolePersistenceData>>> {
+    let data = ctx.data.read().await;
+    data.get::<RolePersistenceKey>().cloned()
+}
+
+/// Saves the roles and nickname of members who leave.
+///
+/// Everything is saved regardless of the guild's settings, so persistent roles
+/// can be restored even if restoring is turned on later.
+pub struct RoleSaveHandler;
+
+#[async_trait]
+impl EventHandler for RoleSaveHandler {
+    fn event_type(&self) -> &'static str {
+        "guild_member_remove"
+    }
+
+    async fn on_guild_member_remove(
+        &self,
+        ctx: Context,
+        guild_id: GuildId,
+        user: &User,
+        member: Option<&Member>,
+    ) {
+        if user.bot {
+            return;
+        }
+        let member = match member {
+            Some(member) => member,
+            None => {
+                debug!("No cached member to save roles of {}", user.id);
+                return;
+            }
+        };
+        let store = match role_store(&ctx).await {
+            Some(store) => store,
+            None => return,
+        };
+
+        let saved = SavedMember {
+            roles: member.roles.iter().map(|role_id| role_id.0).collect(),
+            nickname: member.nick.clone(),
+            left_at: unix_timestamp(),
+        };
+        if let Err(e) = store
+            .update(|data| data.save(guild_id, user.id, saved))
+            .await
+        {
+            error!("Failed to save roles of {}: {}", user.id, e);
+        }
+    }
+}
+
+/// Gives rejoining members back their saved roles and nickname.
+pub struct RoleRestoreHandler;
+
+#[async_trait]
+impl EventHandler for RoleRestoreHandler {
+    fn event_type(&self) -> &'static str {
+        "guild_member_add"
+    }
+
+    async fn on_guild_member_add(&self, ctx: Context, guild_id: GuildId, member: &Member) {
+        let store = match role_store(&ctx).await {
+            Some(store) => store,
+            None => return,
+        };
+        let user_id = member.user.id;
+        let saved = match store.read().await.guilds.get(&guild_id.0) {
+            Some(guild) => guild.get(&user_id.0).cloned(),
+            None => None,
+        };
+        let saved = match saved {
+            Some(saved) => saved,
+            None => return,
+        };
+
+        let config = guild_config(&ctx, guild_id).await;
+        let restore: Vec<RoleId> = {
+            let guild = match ctx.cache.guild(guild_id) {
+                Some(guild) => guild,
+                None => return,
+            };
+
+            // Roles at or above the bot's highest role can't be assigned by it
+            let bot = match guild.members.get(&ctx.cache.current_user_id()) {
+                Some(bot) => bot,
+                None => return,
+            };
+            let top = bot
+                .roles
+                .iter()
+                .filter_map(|role_id| guild.roles.get(role_id))
+                .map(|role| role.position)
+                .max()
+                .unwrap_or_default();
+
+            saved
+                .roles
+                .iter()
+                .map(|&role_id| RoleId(role_id))
+                .filter(|role_id| !member.roles.contains(role_id))
+                .filter(|role_id| {
+                    guild.roles.get(role_id).is_some_and(|role| {
+                        !role.managed
+                            && role.position < top
+                            && should_restore(&config, *role_id, role.permissions)
+                    })
+                })
+                .collect()
+        };
+        let nickname = saved.nickname.filter(|_| config.restore_roles);
+
+        if restore.is_empty() && nickname.is_none() {
+            if let Err(e) = store.update(|data| data.take(guild_id, user_id)).await {
+                error!("Failed to clear saved roles of {}: {}", user_id, e);
+            }
+            return;
+        }
+
+        let mut roles = member.roles.clone();
+        roles.extend(&restore);
+        let result = rest::call_with(&ctx, Priority::Moderation, "edit_member", || {
+            guild_id.edit_member(&ctx.http, user_id, |m| {
+                m.roles(&roles);
+                if let Some(nickname) = &nickname {
+                    m.nickname(nickname);
+                }
+                m
+            })
+        })
+        .await;
+
+        // Saved roles are kept when restoring fails, so punishments survive another rejoin
+        match result {
+            Ok(_) => {
+                info!(
+                    "Restored {} roles of {} in guild {}",
+                    restore.len(),
+                    user_id,
+                    guild_id
+                );
+                if let Err(e) = store.update(|data| data.take(guild_id, user_id)).await {
+                    error!("Failed to clear saved roles of {}: {}", user_id, e);
+                }
+            }
+            Err(e) => {
+                error!("Failed to restore roles of {}: {}", user_id, e);
+                let alert = send_staff_alert(
+                    &ctx,
+                    guild_id,
+                    "⚠️ Couldn't restore roles",
+                    format!(
+                        "<@{}> rejoined, but I couldn't give back their roles: {}",
+                        user_id, e
+                    ),
+                )
+                .await;
+                if let Err(e) = alert {
+                    error!("Failed to send role restore alert: {}", e);
+                }
+            }
+        }
+    }
+}
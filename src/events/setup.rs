@@ -0,0 +1,750 @@
+//! Handlers for the setup panel, and the welcome DM to whoever adds the bot.
+//!
+//! `setup` posts a panel whose select menus write straight to the guild's
+//! settings. The panel uses these component IDs, where `<user>` is the admin
+//! who opened it and the only one who can use it:
+//! - `setup:<step>:<user>`: select menu for a step, see [`Step`].
+//! - `setup:mute_role:<user>`: button that creates the mute role.
+//! - `setup:retention:<user>`: button that opens the retention modal.
+//! - `setup:retention_submit:<user>`: the retention modal.
+//! - `setup:done:<user>`: button that closes the panel.
+
+use async_trait::async_trait;
+use serenity::builder::{CreateComponents, CreateEmbed};
+use serenity::model::application::component::{ActionRowComponent, ButtonStyle, InputTextStyle};
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::modal::ModalSubmitInteraction;
+use serenity::model::application::interaction::{Interaction, InteractionResponseType};
+use serenity::model::channel::{ChannelType, PermissionOverwrite, PermissionOverwriteType};
+use serenity::model::guild::audit_log::{Action, MemberAction};
+use serenity::model::guild::Guild;
+use serenity::model::id::{ChannelId, GuildId, RoleId, UserId};
+use serenity::model::permissions::Permissions;
+use serenity::prelude::*;
+use std::collections::BTreeMap;
+use tracing::{debug, error};
+
+use crate::framework::command_handler::CommandResult;
+use crate::framework::event_handler::EventHandler;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::guild_config::{GuildConfig, GuildConfigKey, PhishingAction};
+use crate::utils::constants::{DEFAULT_COLOR, SUCCESS_COLOR};
+use crate::utils::dms::dm_service;
+use crate::utils::helpers::{reply_ephemeral, truncate, BotConfigKey};
+use crate::utils::rest;
+
+/// Most channels or roles a select menu lists, leaving room for "None".
+const MAX_OPTIONS: usize = 24;
+
+/// What the mute role is denied in every channel.
+const MUTED_DENY: Permissions = Permissions::SEND_MESSAGES
+    .union(Permissions::SEND_MESSAGES_IN_THREADS)
+    .union(Permissions::CREATE_PUBLIC_THREADS)
+    .union(Permissions::CREATE_PRIVATE_THREADS)
+    .union(Permissions::ADD_REACTIONS)
+    .union(Permissions::SPEAK);
+
+/// A setting the panel has a select menu for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Step {
+    /// Where staff alerts and moderation logs go.
+    StaffChannel,
+    /// Where new members are greeted when their DMs are closed.
+    WelcomeChannel,
+    /// Who gets pinged for staff alerts.
+    StaffRole,
+    /// What happens to messages with phishing links.
+    Automod,
+}
+
+impl Step {
+    const ALL: [Step; 4] = [
+        Step::StaffChannel,
+        Step::WelcomeChannel,
+        Step::StaffRole,
+        Step::Automod,
+    ];
+
+    fn id(self) -> &'static str {
+        match self {
+            Self::StaffChannel => "staff_channel",
+            Self::WelcomeChannel => "welcome_channel",
+            Self::StaffRole => "staff_role",
+            Self::Automod => "automod",
+        }
+    }
+
+    fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|step| step.id() == id)
+    }
+
+    fn placeholder(self) -> &'static str {
+        match self {
+            Self::StaffChannel => "Staff log channel",
+            Self::WelcomeChannel => "Welcome channel",
+            Self::StaffRole => "Staff role",
+            Self::Automod => "Phishing protection",
+        }
+    }
+}
+
+/// Phishing protection levels offered by the panel.
+const AUTOMOD_PRESETS: [(&str, &str, &[PhishingAction]); 3] = [
+    ("off", "Off", &[]),
+    (
+        "standard",
+        "Standard: delete and alert staff",
+        &[PhishingAction::Delete, PhishingAction::Log],
+    ),
+    (
+        "strict",
+        "Strict: also time the author out",
+        &[
+            PhishingAction::Delete,
+            PhishingAction::Log,
+            PhishingAction::Timeout,
+        ],
+    ),
+];
+
+/// The preset matching a guild's phishing actions, if one does.
+fn automod_preset(actions: &[PhishingAction]) -> Option<&'static str> {
+    AUTOMOD_PRESETS
+        .iter()
+        .find(|(_, _, preset)| *preset == actions)
+        .map(|(id, _, _)| *id)
+}
+
+/// Apply a step's picked value to the settings.
+fn apply(config: &mut GuildConfig, step: Step, value: &str) -> Result<(), String> {
+    match step {
+        Step::StaffChannel => config.set("staff_channel", value),
+        Step::StaffRole => config.set("staff_role", value),
+        Step::WelcomeChannel => {
+            config.onboarding.channel = match value {
+                "none" => None,
+                id => Some(id.parse().map_err(|_| "Expected a channel ID.")?),
+            };
+            Ok(())
+        }
+        Step::Automod => {
+            let actions = AUTOMOD_PRESETS
+                .iter()
+                .find(|(id, _, _)| *id == value)
+                .map(|(_, _, actions)| actions.to_vec())
+                .ok_or_else(|| format!("Unknown preset `{}`.", value))?;
+            config.phishing_actions = actions;
+            Ok(())
+        }
+    }
+}
+
+/// Build the panel showing a guild's settings, for the admin who opened it.
+pub fn setup_panel(
+    ctx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    config: &GuildConfig,
+) -> (CreateEmbed, CreateComponents) {
+    let channel = |id: Option<u64>| id.map_or("not set".to_string(), |id| format!("<#{}>", id));
+    let days = |days: Option<u64>| match days {
+        None => "default".to_string(),
+        Some(0) => "forever".to_string(),
+        Some(days) => format!("{} days", days),
+    };
+    let preset = automod_preset(&config.phishing_actions).unwrap_or("custom");
+
+    let mut embed = CreateEmbed::default();
+    embed
+        .title("⚙️ Server setup")
+        .description(
+            "Pick the basics below; every choice is saved right away. Moderation uses \
+             Discord timeouts, but you can also create a mute role to give members by \
+             hand. Fine-tune anything later with `settings`.",
+        )
+        .field("Staff log channel", channel(config.staff_channel), true)
+        .field("Welcome channel", channel(config.onboarding.channel), true)
+        .field(
+            "Staff role",
+            config
+                .staff_role
+                .map_or("not set".to_string(), |id| format!("<@&{}>", id)),
+            true,
+        )
+        .field("Phishing protection", preset, true)
+        .field(
+            "Mute role",
+            config
+                .mute_role
+                .map_or("not set".to_string(), |id| format!("<@&{}>", id)),
+            true,
+        )
+        .field(
+            "Retention",
+            format!(
+                "Cases: {}\nModmail: {}",
+                days(config.case_retention),
+                days(config.message_log_retention)
+            ),
+            true,
+        )
+        .color(DEFAULT_COLOR);
+
+    let (channels, roles) = ctx
+        .cache
+        .guild_field(guild_id, |guild| {
+            let mut channels: Vec<_> = guild
+                .channels
+                .values()
+                .filter_map(|channel| channel.clone().guild())
+                .filter(|channel| matches!(channel.kind, ChannelType::Text | ChannelType::News))
+                .collect();
+            channels.sort_by_key(|channel| channel.position);
+            let channels: Vec<(u64, String)> = channels
+                .into_iter()
+                .map(|channel| (channel.id.0, format!("#{}", channel.name)))
+                .collect();
+
+            let mut roles: Vec<_> = guild
+                .roles
+                .values()
+                .filter(|role| role.id.0 != guild_id.0 && !role.managed)
+                .collect();
+            roles.sort_by_key(|role| std::cmp::Reverse(role.position));
+            let roles: Vec<(u64, String)> = roles
+                .into_iter()
+                .map(|role| (role.id.0, format!("@{}", role.name)))
+                .collect();
+            (channels, roles)
+        })
+        .unwrap_or_default();
+
+    let mut components = CreateComponents::default();
+    for step in Step::ALL {
+        let (options, current): (Vec<(String, String)>, Option<String>) = match step {
+            Step::StaffChannel | Step::WelcomeChannel => {
+                let current = if step == Step::StaffChannel {
+                    config.staff_channel
+                } else {
+                    config.onboarding.channel
+                };
+                (
+                    channels
+                        .iter()
+                        .take(MAX_OPTIONS)
+                        .map(|(id, name)| (id.to_string(), name.clone()))
+                        .collect(),
+                    current.map(|id| id.to_string()),
+                )
+            }
+            Step::StaffRole => (
+                roles
+                    .iter()
+                    .take(MAX_OPTIONS)
+                    .map(|(id, name)| (id.to_string(), name.clone()))
+                    .collect(),
+                config.staff_role.map(|id| id.to_string()),
+            ),
+            Step::Automod => (
+                AUTOMOD_PRESETS
+                    .iter()
+                    .map(|(id, label, _)| (id.to_string(), label.to_string()))
+                    .collect(),
+                automod_preset(&config.phishing_actions).map(String::from),
+            ),
+        };
+
+        components.create_action_row(|row| {
+            row.create_select_menu(|menu| {
+                menu.custom_id(format!("setup:{}:{}", step.id(), user_id))
+                    .placeholder(step.placeholder())
+                    .options(|o| {
+                        if step != Step::Automod {
+                            o.create_option(|option| {
+                                option
+                                    .label("None")
+                                    .value("none")
+                                    .default_selection(current.is_none())
+                            });
+                        }
+                        for (value, label) in &options {
+                            o.create_option(|option| {
+                                option
+                                    .label(truncate(label, 100))
+                                    .value(value)
+                                    .default_selection(current.as_ref() == Some(value))
+                            });
+                        }
+                        o
+                    })
+            })
+        });
+    }
+    components.create_action_row(|row| {
+        row.create_button(|b| {
+            b.custom_id(format!("setup:mute_role:{}", user_id))
+                .label(if config.mute_role.is_some() {
+                    "Update mute role"
+                } else {
+                    "Create mute role"
+                })
+                .style(ButtonStyle::Secondary)
+        })
+        .create_button(|b| {
+            b.custom_id(format!("setup:retention:{}", user_id))
+                .label("Data retention…")
+                .style(ButtonStyle::Secondary)
+        })
+        .create_button(|b| {
+            b.custom_id(format!("setup:done:{}", user_id))
+                .label("Done")
+                .style(ButtonStyle::Success)
+        })
+    });
+
+    (embed, components)
+}
+
+/// Handles the setup panel's menus, buttons and modal.
+pub struct SetupHandler;
+
+#[async_trait]
+impl EventHandler for SetupHandler {
+    fn event_type(&self) -> &'static str {
+        "interaction"
+    }
+
+    async fn on_interaction(&self, ctx: Context, interaction: &Interaction) {
+        let result = match interaction {
+            Interaction::MessageComponent(component) => {
+                let (action, user_id) = match component
+                    .data
+                    .custom_id
+                    .strip_prefix("setup:")
+                    .and_then(|rest| rest.split_once(':'))
+                {
+                    Some(parts) => parts,
+                    None => return,
+                };
+                if user_id.parse::<u64>().ok() != Some(component.user.id.0) {
+                    reply_ephemeral(
+                        &ctx,
+                        component,
+                        "Only the person who ran `setup` can use this.",
+                    )
+                    .await
+                    .map_err(Into::into)
+                } else {
+                    match (action, Step::from_id(action)) {
+                        (_, Some(step)) => pick(&ctx, component, step).await,
+                        ("mute_role", None) => create_mute_role(&ctx, component).await,
+                        ("retention", None) => open_retention(&ctx, component).await,
+                        ("done", None) => done(&ctx, component).await,
+                        _ => return,
+                    }
+                }
+            }
+            Interaction::ModalSubmit(modal)
+                if modal.data.custom_id.starts_with("setup:retention_submit:") =>
+            {
+                submit_retention(&ctx, modal).await
+            }
+            _ => return,
+        };
+
+        if let Err(e) = result {
+            error!("Setup interaction failed: {:?}", e);
+        }
+    }
+}
+
+/// Change a guild's settings and record who did it.
+async fn update(
+    ctx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    action: String,
+    f: impl FnOnce(&mut GuildConfig) -> Result<(), String>,
+) -> CommandResult<Result<GuildConfig, String>> {
+    let store = {
+        let data = ctx.data.read().await;
+        data.get::<GuildConfigKey>().cloned()
+    }
+    .ok_or("Guild config store is not loaded")?;
+
+    // Validate against a copy so a bad value doesn't touch the store
+    let mut config = store.read().await.get(guild_id);
+    if let Err(e) = f(&mut config) {
+        return Ok(Err(e));
+    }
+    store
+        .update(|configs| {
+            configs.guilds.insert(guild_id.0, config.clone());
+        })
+        .await?;
+    audit::record(
+        ctx,
+        AuditEvent {
+            guild_id: Some(guild_id),
+            actor_id: Some(user_id),
+            source: AuditSource::Command,
+            action,
+            reason: None,
+        },
+    )
+    .await;
+    Ok(Ok(config))
+}
+
+/// Save a step's pick and redraw the panel.
+async fn pick(ctx: &Context, component: &MessageComponentInteraction, step: Step) -> CommandResult {
+    let guild_id = component.guild_id.ok_or("Setup panel outside a guild")?;
+    let value = match component.data.values.first() {
+        Some(value) => value.clone(),
+        None => return Ok(()),
+    };
+
+    let action = format!("Setup: set {} to {}", step.id(), value);
+    let config = match update(ctx, guild_id, component.user.id, action, |config| {
+        apply(config, step, &value)
+    })
+    .await?
+    {
+        Ok(config) => config,
+        Err(e) => {
+            reply_ephemeral(ctx, component, &e).await?;
+            return Ok(());
+        }
+    };
+
+    let (embed, components) = setup_panel(ctx, guild_id, component.user.id, &config);
+    component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| d.set_embed(embed).set_components(components))
+        })
+        .await?;
+    Ok(())
+}
+
+/// Create the mute role, or reuse the configured one, deny it talking in every
+/// channel and redraw the panel.
+async fn create_mute_role(ctx: &Context, component: &MessageComponentInteraction) -> CommandResult {
+    let guild_id = component.guild_id.ok_or("Setup panel outside a guild")?;
+    // Going through every channel can take longer than an interaction may wait
+    component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::DeferredUpdateMessage)
+        })
+        .await?;
+
+    let configured = {
+        let data = ctx.data.read().await;
+        data.get::<GuildConfigKey>().cloned()
+    }
+    .ok_or("Guild config store is not loaded")?
+    .read()
+    .await
+    .get(guild_id)
+    .mute_role
+    .map(RoleId)
+    .filter(|role_id| ctx.cache.role(guild_id, *role_id).is_some());
+    let role_id = match configured {
+        Some(role_id) => role_id,
+        None => {
+            let created = rest::call(ctx, "create_role", || {
+                guild_id.create_role(&ctx.http, |r| {
+                    r.name("Muted")
+                        .permissions(Permissions::empty())
+                        .mentionable(false)
+                })
+            })
+            .await;
+            match created {
+                Ok(role) => role.id,
+                Err(e) => {
+                    let content = format!("I couldn't create the mute role: {}", e);
+                    component
+                        .create_followup_message(&ctx.http, |m| m.content(content).ephemeral(true))
+                        .await?;
+                    return Ok(());
+                }
+            }
+        }
+    };
+
+    let channels: Vec<ChannelId> = ctx
+        .cache
+        .guild_field(guild_id, |guild| {
+            guild
+                .channels
+                .values()
+                .filter_map(|channel| channel.clone().guild())
+                .filter(|channel| {
+                    matches!(
+                        channel.kind,
+                        ChannelType::Text
+                            | ChannelType::News
+                            | ChannelType::Voice
+                            | ChannelType::Stage
+                            | ChannelType::Category
+                    )
+                })
+                .map(|channel| channel.id)
+                .collect()
+        })
+        .unwrap_or_default();
+    let overwrite = PermissionOverwrite {
+        allow: Permissions::empty(),
+        deny: MUTED_DENY,
+        kind: PermissionOverwriteType::Role(role_id),
+    };
+    let mut failed = 0;
+    for channel_id in channels {
+        if let Err(e) = rest::call(ctx, "create_permission", || {
+            channel_id.create_permission(&ctx.http, &overwrite)
+        })
+        .await
+        {
+            debug!("Couldn't mute {} in {}: {}", role_id, channel_id, e);
+            failed += 1;
+        }
+    }
+
+    let action = format!("Setup: set mute_role to {}", role_id);
+    let config = update(ctx, guild_id, component.user.id, action, |config| {
+        config.mute_role = Some(role_id.0);
+        Ok(())
+    })
+    .await?
+    .map_err(|e| e.to_string())?;
+    let (embed, components) = setup_panel(ctx, guild_id, component.user.id, &config);
+    component
+        .edit_original_interaction_response(&ctx.http, |r| {
+            r.set_embed(embed).set_components(components)
+        })
+        .await?;
+
+    if failed > 0 {
+        let content = format!(
+            "I couldn't stop <@&{}> talking in {} channels. Check that I can manage \
+             permissions there.",
+            role_id, failed
+        );
+        component
+            .create_followup_message(&ctx.http, |m| m.content(content).ephemeral(true))
+            .await?;
+    }
+    Ok(())
+}
+
+/// Open the retention modal, filled in with the current values.
+async fn open_retention(ctx: &Context, component: &MessageComponentInteraction) -> CommandResult {
+    let guild_id = component.guild_id.ok_or("Setup panel outside a guild")?;
+    let config = {
+        let data = ctx.data.read().await;
+        data.get::<GuildConfigKey>().cloned()
+    }
+    .ok_or("Guild config store is not loaded")?
+    .read()
+    .await
+    .get(guild_id);
+    let current = |days: Option<u64>| days.map(|days| days.to_string()).unwrap_or_default();
+
+    component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::Modal)
+                .interaction_response_data(|d| {
+                    d.custom_id(format!("setup:retention_submit:{}", component.user.id))
+                        .title("Data retention")
+                        .components(|c| {
+                            c.create_action_row(|row| {
+                                row.create_input_text(|t| {
+                                    t.custom_id("case_retention")
+                                        .label("Days to keep moderation cases")
+                                        .placeholder("Blank for the default, 0 keeps forever")
+                                        .value(current(config.case_retention))
+                                        .style(InputTextStyle::Short)
+                                        .max_length(6)
+                                        .required(false)
+                                })
+                            })
+                            .create_action_row(|row| {
+                                row.create_input_text(|t| {
+                                    t.custom_id("message_log_retention")
+                                        .label("Days to keep modmail transcripts")
+                                        .placeholder("Blank for the default, 0 keeps forever")
+                                        .value(current(config.message_log_retention))
+                                        .style(InputTextStyle::Short)
+                                        .max_length(6)
+                                        .required(false)
+                                })
+                            })
+                        })
+                })
+        })
+        .await?;
+    Ok(())
+}
+
+/// Save the retention periods from the modal and redraw the panel.
+async fn submit_retention(ctx: &Context, modal: &ModalSubmitInteraction) -> CommandResult {
+    let guild_id = modal.guild_id.ok_or("Setup modal outside a guild")?;
+    let inputs: BTreeMap<String, String> = modal
+        .data
+        .components
+        .iter()
+        .flat_map(|row| &row.components)
+        .filter_map(|component| match component {
+            ActionRowComponent::InputText(input) => {
+                Some((input.custom_id.clone(), input.value.trim().to_string()))
+            }
+            _ => None,
+        })
+        .collect();
+
+    let result = update(
+        ctx,
+        guild_id,
+        modal.user.id,
+        "Setup: set retention".to_string(),
+        |config| {
+            for key in ["case_retention", "message_log_retention"] {
+                let value = inputs.get(key).map(String::as_str).unwrap_or_default();
+                config.set(key, if value.is_empty() { "none" } else { value })?;
+            }
+            Ok(())
+        },
+    )
+    .await?;
+
+    match result {
+        Ok(config) => {
+            let (embed, components) = setup_panel(ctx, guild_id, modal.user.id, &config);
+            modal
+                .create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::UpdateMessage)
+                        .interaction_response_data(|d| {
+                            d.set_embed(embed).set_components(components)
+                        })
+                })
+                .await?;
+        }
+        Err(e) => {
+            modal
+                .create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|d| d.content(e).ephemeral(true))
+                })
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Close the panel.
+async fn done(ctx: &Context, component: &MessageComponentInteraction) -> CommandResult {
+    component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| {
+                    d.embed(|e| {
+                        e.title("✅ Setup finished")
+                            .description("Change anything later with `setup` or `settings`.")
+                            .color(SUCCESS_COLOR)
+                    })
+                    .components(|c| c)
+                })
+        })
+        .await?;
+    Ok(())
+}
+
+/// DMs whoever added the bot to a server, pointing them at `setup`.
+pub struct SetupInviterHandler;
+
+#[async_trait]
+impl EventHandler for SetupInviterHandler {
+    fn event_type(&self) -> &'static str {
+        "guild_create"
+    }
+
+    async fn on_guild_create(&self, ctx: Context, guild: &Guild, is_new: bool) {
+        if !is_new {
+            return;
+        }
+        let prefix = {
+            let data = ctx.data.read().await;
+            match data.get::<BotConfigKey>() {
+                Some(config) if config.setup.dm_inviter => config.prefix.clone(),
+                _ => return,
+            }
+        };
+
+        // The bot add entry names who invited the bot, if the bot can see the log
+        let bot_id = ctx.cache.current_user_id();
+        let action = Action::Member(MemberAction::BotAdd).num();
+        let inviter = match guild
+            .id
+            .audit_logs(&ctx.http, Some(action), None, None, Some(5))
+            .await
+        {
+            Ok(logs) => logs
+                .entries
+                .into_iter()
+                .find(|entry| entry.target_id == Some(bot_id.0))
+                .map(|entry| entry.user_id),
+            Err(e) => {
+                debug!("Couldn't read the audit log of guild {}: {}", guild.id, e);
+                None
+            }
+        };
+        let inviter = match inviter {
+            Some(inviter) => inviter,
+            None => return,
+        };
+
+        let dms = match dm_service(&ctx).await {
+            Some(dms) => dms,
+            None => return,
+        };
+        let sent = dms
+            .send(&ctx.http, inviter, None, |m| {
+                m.embed(|e| {
+                    e.title(format!("Thanks for adding me to {}!", guild.name))
+                        .description(format!(
+                            "Run `{}setup` in the server to pick a staff log channel, a welcome \
+                             channel, a staff role and phishing protection, and create a mute \
+                             role, in a few clicks.",
+                            prefix
+                        ))
+                        .color(DEFAULT_COLOR)
+                })
+            })
+            .await;
+        if let Err(e) = sent {
+            debug!("Couldn't DM the inviter of guild {}: {}", guild.id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_steps() {
+        let mut config = GuildConfig::default();
+        apply(&mut config, Step::StaffChannel, "123").unwrap();
+        apply(&mut config, Step::WelcomeChannel, "456").unwrap();
+        apply(&mut config, Step::Automod, "strict").unwrap();
+        assert_eq!(config.staff_channel, Some(123));
+        assert_eq!(config.onboarding.channel, Some(456));
+        assert_eq!(automod_preset(&config.phishing_actions), Some("strict"));
+
+        apply(&mut config, Step::WelcomeChannel, "none").unwrap();
+        assert_eq!(config.onboarding.channel, None);
+        assert!(apply(&mut config, Step::Automod, "paranoid").is_err());
+        assert_eq!(Step::from_id("staff_role"), Some(Step::StaffRole));
+    }
+}
@@ -0,0 +1,147 @@
+//! Handlers that track gateway connection health.
+
+use async_trait::async_trait;
+use serenity::client::bridge::gateway::event::ShardStageUpdateEvent;
+use serenity::model::event::ResumedEvent;
+use serenity::model::id::ChannelId;
+use serenity::prelude::*;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::framework::event_handler::EventHandler;
+use crate::models::config::ShardHealthConfig;
+use crate::models::shard_health::{ShardHealth, ShardHealthKey};
+use crate::utils::constants::{SUCCESS_COLOR, WARNING_COLOR};
+use crate::utils::duration::format_compact;
+use crate::utils::helpers::BotConfigKey;
+use crate::utils::rest::{self, Priority};
+
+/// Get the shard health tracker and its configuration from the client data.
+async fn shard_health(ctx: &Context) -> Option<(Arc<ShardHealth>, ShardHealthConfig)> {
+    let data = ctx.data.read().await;
+    let health = data.get::<ShardHealthKey>().cloned()?;
+    let config = data
+        .get::<BotConfigKey>()
+        .map(|config| config.shard_health.clone())
+        .unwrap_or_default();
+    Some((health, config))
+}
+
+/// Post a notice to the owner alert channel.
+async fn post_alert(
+    ctx: &Context,
+    channel_id: ChannelId,
+    title: &str,
+    description: &str,
+    color: u32,
+) {
+    let result = rest::call_with(ctx, Priority::Log, "send_message", || {
+        channel_id.send_message(&ctx.http, |m| {
+            m.embed(|e| e.title(title).description(description).color(color))
+        })
+    })
+    .await;
+
+    if let Err(e) = result {
+        error!("Failed to send shard health alert: {}", e);
+    }
+}
+
+/// Records shard stage changes, logs reconnect storms, and alerts owners about long outages.
+pub struct ShardStageHandler;
+
+#[async_trait]
+impl EventHandler for ShardStageHandler {
+    fn event_type(&self) -> &'static str {
+        "shard_stage_update"
+    }
+
+    async fn on_shard_stage_update(&self, ctx: Context, event: &ShardStageUpdateEvent) {
+        let (health, config) = match shard_health(&ctx).await {
+            Some(found) => found,
+            None => return,
+        };
+
+        let shard_id = event.shard_id.0;
+        let change = health.record_stage(shard_id, event.new);
+        info!(
+            "Shard {} is now {} (was {})",
+            shard_id, event.new, event.old
+        );
+
+        if let Some(disconnects) = change.storm {
+            warn!(
+                "Shard {} reconnect storm: {} disconnects in the last {}",
+                shard_id,
+                disconnects,
+                format_compact(Duration::from_secs(config.storm_window))
+            );
+        }
+
+        let channel_id = match config.alert_channel {
+            Some(channel_id) => ChannelId(channel_id),
+            None => return,
+        };
+
+        if change.recovered_after_alert {
+            post_alert(
+                &ctx,
+                channel_id,
+                "✅ Shard reconnected",
+                &format!("Shard {} is connected again.", shard_id),
+                SUCCESS_COLOR,
+            )
+            .await;
+        }
+
+        let down_since = match health.status(shard_id).and_then(|s| s.down_since) {
+            Some(down_since) if change.disconnected => down_since,
+            _ => return,
+        };
+
+        // Wait in the background so the dispatcher isn't held up by the outage
+        tokio::spawn(async move {
+            let alert_after = Duration::from_secs(config.alert_after);
+            tokio::time::sleep(alert_after).await;
+
+            if !health.mark_alerted(shard_id, down_since) {
+                return;
+            }
+
+            warn!(
+                "Shard {} has been down for {}",
+                shard_id,
+                format_compact(alert_after)
+            );
+            post_alert(
+                &ctx,
+                channel_id,
+                "⚠️ Shard down",
+                &format!(
+                    "Shard {} has been disconnected for {}.",
+                    shard_id,
+                    format_compact(alert_after)
+                ),
+                WARNING_COLOR,
+            )
+            .await;
+        });
+    }
+}
+
+/// Counts session resumes per shard.
+pub struct ShardResumeHandler;
+
+#[async_trait]
+impl EventHandler for ShardResumeHandler {
+    fn event_type(&self) -> &'static str {
+        "resume"
+    }
+
+    async fn on_resume(&self, ctx: Context, _event: &ResumedEvent) {
+        if let Some((health, _)) = shard_health(&ctx).await {
+            health.record_resume(ctx.shard_id);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! Handler for the game menus offered by `gameinfo` and `gameprice`.
+
+use async_trait::async_trait;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::{Interaction, InteractionResponseType};
+use serenity::prelude::*;
+use tracing::error;
+
+use crate::framework::command_handler::CommandResult;
+use crate::framework::event_handler::EventHandler;
+use crate::utils::helpers::reply_ephemeral;
+use crate::utils::steam::{game_embed, price_embed, SteamKey};
+
+/// Shows the game picked from a search.
+pub struct SteamMenuHandler;
+
+#[async_trait]
+impl EventHandler for SteamMenuHandler {
+    fn event_type(&self) -> &'static str {
+        "interaction"
+    }
+
+    async fn on_interaction(&self, ctx: Context, interaction: &Interaction) {
+        let component = match interaction {
+            Interaction::MessageComponent(component) => component,
+            _ => return,
+        };
+        let (kind, user_id) = match component
+            .data
+            .custom_id
+            .strip_prefix("steam:")
+            .and_then(|rest| rest.split_once(':'))
+        {
+            Some(parts) => parts,
+            None => return,
+        };
+
+        if user_id.parse::<u64>().ok() != Some(component.user.id.0) {
+            if let Err(e) =
+                reply_ephemeral(&ctx, component, "Only the person who asked can pick.").await
+            {
+                error!("Failed to answer a Steam menu: {}", e);
+            }
+            return;
+        }
+        if let Err(e) = show_game(&ctx, component, kind).await {
+            error!("Steam menu failed: {:?}", e);
+        }
+    }
+}
+
+/// Replace the menu with the picked game.
+async fn show_game(
+    ctx: &Context,
+    component: &MessageComponentInteraction,
+    kind: &str,
+) -> CommandResult {
+    let id = match component.data.values.first().and_then(|id| id.parse().ok()) {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+    let steam = match ctx.data.read().await.get::<SteamKey>().cloned() {
+        Some(steam) => steam,
+        None => return Ok(()),
+    };
+
+    let app = match steam.app(id).await? {
+        Some(app) => app,
+        None => {
+            reply_ephemeral(ctx, component, "Steam couldn't find that game's page.").await?;
+            return Ok(());
+        }
+    };
+    let embed = match kind {
+        "price" => price_embed(&app),
+        _ => game_embed(&app),
+    };
+    component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| d.set_embed(embed).components(|c| c))
+        })
+        .await?;
+    Ok(())
+}
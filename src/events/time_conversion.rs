@@ -0,0 +1,189 @@
+//! Converts times like `8pm EST` in messages to Discord timestamps, which every
+//! reader sees in their own time zone. Servers opt in with the
+//! `time_conversion` setting.
+
+use async_trait::async_trait;
+use serenity::model::application::interaction::Interaction;
+use serenity::model::channel::{Message, Reaction, ReactionType};
+use serenity::prelude::*;
+use tracing::debug;
+
+use crate::framework::command_handler::CommandResult;
+use crate::framework::event_handler::EventHandler;
+use crate::models::guild_config::{guild_config, TimeConversion};
+use crate::models::ignore::ignored;
+use crate::utils::duration::{relative, timestamp, TimestampStyle};
+use crate::utils::helpers::reply_ephemeral;
+use crate::utils::timezones::{find_times, FoundTime};
+
+/// The reaction offered in react mode.
+const CLOCK: &str = "🕒";
+
+/// The times found in a message, resolved around when it was sent.
+fn times_in(msg: &Message) -> Vec<FoundTime> {
+    let sent = msg.timestamp.unix_timestamp().max(0) as u64;
+    find_times(&msg.content, sent)
+}
+
+/// One line per converted time.
+fn conversion(times: &[FoundTime]) -> String {
+    times
+        .iter()
+        .map(|time| {
+            format!(
+                "🕒 {} → {} ({})",
+                time.text,
+                timestamp(time.unix, TimestampStyle::ShortDateTime),
+                relative(time.unix)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Reply to a message with its converted times.
+async fn reply(ctx: &Context, msg: &Message, times: &[FoundTime]) -> serenity::Result<()> {
+    msg.channel_id
+        .send_message(&ctx.http, |m| {
+            m.reference_message(msg)
+                .content(conversion(times))
+                .allowed_mentions(|am| am.empty_parse().replied_user(false))
+        })
+        .await?;
+    Ok(())
+}
+
+/// Convert the times in a message that didn't run a command, if the server
+/// has opted in.
+pub async fn convert_times(ctx: &Context, msg: &Message) {
+    let guild_id = match msg.guild_id {
+        Some(guild_id) => guild_id,
+        None => return,
+    };
+    let mode = guild_config(ctx, guild_id).await.time_conversion;
+    if mode == TimeConversion::Off || ignored(ctx, msg).await.passive {
+        return;
+    }
+    let times = times_in(msg);
+    if times.is_empty() {
+        return;
+    }
+
+    let result = match mode {
+        TimeConversion::Off => Ok(()),
+        TimeConversion::Reply => reply(ctx, msg, &times).await,
+        TimeConversion::React => msg
+            .react(&ctx.http, ReactionType::Unicode(CLOCK.to_string()))
+            .await
+            .map(|_| ()),
+    };
+    if let Err(e) = result {
+        debug!("Failed to convert times in {}: {}", msg.id, e);
+    }
+}
+
+/// In react mode, replies with the conversion when a member adds the clock the
+/// bot offered. The bot's own reaction is removed so it only replies once.
+pub struct TimeConversionReactionHandler;
+
+impl TimeConversionReactionHandler {
+    async fn handle(&self, ctx: &Context, reaction: &Reaction) -> CommandResult {
+        let clock = ReactionType::Unicode(CLOCK.to_string());
+        if reaction.emoji != clock || reaction.guild_id.is_none() {
+            return Ok(());
+        }
+        if reaction.user_id == Some(ctx.cache.current_user_id()) {
+            return Ok(());
+        }
+
+        let msg = reaction.message(&ctx.http).await?;
+        let offered = msg
+            .reactions
+            .iter()
+            .any(|r| r.reaction_type == clock && r.me);
+        if !offered {
+            return Ok(());
+        }
+        let times = times_in(&msg);
+        if times.is_empty() {
+            return Ok(());
+        }
+
+        msg.delete_reaction(&ctx.http, None, clock).await?;
+        reply(ctx, &msg, &times).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EventHandler for TimeConversionReactionHandler {
+    fn event_type(&self) -> &'static str {
+        "reaction_add"
+    }
+
+    async fn on_reaction_add(&self, ctx: Context, reaction: &Reaction) {
+        if let Err(e) = self.handle(&ctx, reaction).await {
+            debug!("Failed to convert times on request: {:?}", e);
+        }
+    }
+}
+
+/// Answers the style menu of the `timestamp` command with the picked style,
+/// ready to copy.
+pub struct TimestampMenuHandler;
+
+#[async_trait]
+impl EventHandler for TimestampMenuHandler {
+    fn event_type(&self) -> &'static str {
+        "interaction"
+    }
+
+    async fn on_interaction(&self, ctx: Context, interaction: &Interaction) {
+        let component = match interaction {
+            Interaction::MessageComponent(component) => component,
+            _ => return,
+        };
+        let unix = match component
+            .data
+            .custom_id
+            .strip_prefix("timestamp:")
+            .and_then(|unix| unix.parse::<u64>().ok())
+        {
+            Some(unix) => unix,
+            None => return,
+        };
+        let style = component
+            .data
+            .values
+            .first()
+            .and_then(|value| value.chars().next())
+            .and_then(TimestampStyle::from_flag);
+        let style = match style {
+            Some(style) => style,
+            None => return,
+        };
+
+        let tag = timestamp(unix, style);
+        let content = format!("{} shows as {}\n```\n{}\n```", style.name(), tag, tag);
+        if let Err(e) = reply_ephemeral(&ctx, component, &content).await {
+            debug!("Failed to answer a timestamp menu: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_each_time() {
+        let times = [FoundTime {
+            text: "8pm EST".to_string(),
+            unix: 1_792_285_200,
+        }];
+        assert_eq!(
+            conversion(&times),
+            "🕒 8pm EST → <t:1792285200:f> (<t:1792285200:R>)"
+        );
+    }
+}
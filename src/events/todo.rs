@@ -0,0 +1,97 @@
+//! Handler for the menu offered by `todo done`.
+
+use async_trait::async_trait;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::{Interaction, InteractionResponseType};
+use serenity::prelude::*;
+use tracing::error;
+
+use crate::framework::command_handler::CommandResult;
+use crate::framework::event_handler::EventHandler;
+use crate::models::todos::{TodoKey, TodoOwner};
+use crate::utils::constants::SUCCESS_COLOR;
+use crate::utils::helpers::{reply_ephemeral, truncate};
+
+/// Marks the items picked from a todo menu as done.
+pub struct TodoMenuHandler;
+
+#[async_trait]
+impl EventHandler for TodoMenuHandler {
+    fn event_type(&self) -> &'static str {
+        "interaction"
+    }
+
+    async fn on_interaction(&self, ctx: Context, interaction: &Interaction) {
+        let component = match interaction {
+            Interaction::MessageComponent(component) => component,
+            _ => return,
+        };
+        // The owner is written as `user:<id>` or `channel:<id>`
+        let (owner, user_id) = match component
+            .data
+            .custom_id
+            .strip_prefix("todo:")
+            .and_then(|rest| rest.rsplit_once(':'))
+        {
+            Some(parts) => parts,
+            None => return,
+        };
+        let owner = match owner.parse::<TodoOwner>() {
+            Ok(owner) => owner,
+            Err(()) => return,
+        };
+
+        if user_id.parse::<u64>().ok() != Some(component.user.id.0) {
+            if let Err(e) =
+                reply_ephemeral(&ctx, component, "Only the person who asked can pick.").await
+            {
+                error!("Failed to answer a todo menu: {}", e);
+            }
+            return;
+        }
+        if let Err(e) = mark_done(&ctx, component, owner).await {
+            error!("Todo menu failed: {:?}", e);
+        }
+    }
+}
+
+/// Mark the picked items done and replace the menu with what was finished.
+async fn mark_done(
+    ctx: &Context,
+    component: &MessageComponentInteraction,
+    owner: TodoOwner,
+) -> CommandResult {
+    let ids: Vec<u64> = component
+        .data
+        .values
+        .iter()
+        .filter_map(|id| id.parse().ok())
+        .collect();
+    let store = match ctx.data.read().await.get::<TodoKey>().cloned() {
+        Some(store) => store,
+        None => return Ok(()),
+    };
+
+    let done = store
+        .update(|data| data.list_mut(owner).complete(&ids))
+        .await?;
+    if done.is_empty() {
+        reply_ephemeral(ctx, component, "Those items were already done.").await?;
+        return Ok(());
+    }
+    let lines: Vec<String> = done.iter().map(|item| item.line()).collect();
+    component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| {
+                    d.embed(|e| {
+                        e.title("✅ Marked done")
+                            .description(truncate(&lines.join("\n"), 4000))
+                            .color(SUCCESS_COLOR)
+                    })
+                    .components(|c| c)
+                })
+        })
+        .await?;
+    Ok(())
+}
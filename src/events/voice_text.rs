@@ -0,0 +1,301 @@
+//! Gives members in linked voice channels access to their text channel, see
+//! [`crate::models::voice_text`].
+
+use async_trait::async_trait;
+use serenity::model::channel::{ChannelType, PermissionOverwrite, PermissionOverwriteType};
+use serenity::model::guild::Guild;
+use serenity::model::id::{ChannelId, GuildId, RoleId, UserId};
+use serenity::model::permissions::Permissions;
+use serenity::model::voice::VoiceState;
+use serenity::prelude::*;
+use tokio::sync::Mutex;
+use tracing::{debug, error};
+
+use crate::framework::event_handler::EventHandler;
+use crate::models::guild_config::{guild_config, GuildConfigKey};
+use crate::models::voice_text::{temporary_name, VoiceText, VoiceTextSettings};
+use crate::utils::rest;
+
+/// What members of a voice channel can do in its text channel.
+const ACCESS: Permissions = Permissions::VIEW_CHANNEL
+    .union(Permissions::SEND_MESSAGES)
+    .union(Permissions::READ_MESSAGE_HISTORY);
+
+/// Grants and revokes text channel access as members move between voice channels.
+#[derive(Default)]
+pub struct VoiceTextHandler {
+    /// Held while handling a change, so members joining at once don't each
+    /// create a temporary channel.
+    lock: Mutex<()>,
+}
+
+#[async_trait]
+impl EventHandler for VoiceTextHandler {
+    fn event_type(&self) -> &'static str {
+        "voice_state_update"
+    }
+
+    async fn on_voice_state_update(
+        &self,
+        ctx: Context,
+        old: Option<&VoiceState>,
+        new: &VoiceState,
+    ) {
+        let guild_id = match new.guild_id {
+            Some(guild_id) => guild_id,
+            None => return,
+        };
+        let left = old.and_then(|old| old.channel_id);
+        if left == new.channel_id || new.member.as_ref().is_some_and(|m| m.user.bot) {
+            return;
+        }
+        let settings = guild_config(&ctx, guild_id).await.voice_text;
+        let linked = |channel_id: Option<ChannelId>| {
+            channel_id.filter(|channel_id| settings.links.contains_key(&channel_id.0))
+        };
+        let (left, joined) = (linked(left), linked(new.channel_id));
+        if left.is_none() && joined.is_none() {
+            return;
+        }
+
+        let _guard = self.lock.lock().await;
+        if let Some(voice_channel) = left {
+            leave(&ctx, guild_id, voice_channel, new.user_id).await;
+        }
+        if let Some(voice_channel) = joined {
+            join(&ctx, guild_id, voice_channel, new.user_id).await;
+        }
+    }
+}
+
+/// Deletes temporary text channels whose voice channel emptied while the bot was
+/// offline.
+pub struct VoiceTextCleanupHandler;
+
+#[async_trait]
+impl EventHandler for VoiceTextCleanupHandler {
+    fn event_type(&self) -> &'static str {
+        "guild_create"
+    }
+
+    async fn on_guild_create(&self, ctx: Context, guild: &Guild, _is_new: bool) {
+        let temporary = guild_config(&ctx, guild.id).await.voice_text.temporary;
+        for voice_channel in temporary.into_keys() {
+            if connected(guild, ChannelId(voice_channel)) == 0 {
+                delete_temporary(&ctx, guild.id, ChannelId(voice_channel)).await;
+            }
+        }
+    }
+}
+
+/// Members connected to a voice channel, not counting bots.
+fn connected(guild: &Guild, voice_channel: ChannelId) -> usize {
+    guild
+        .voice_states
+        .values()
+        .filter(|state| state.channel_id == Some(voice_channel))
+        .filter(|state| {
+            !guild
+                .members
+                .get(&state.user_id)
+                .is_some_and(|member| member.user.bot)
+        })
+        .count()
+}
+
+/// The guild's current voice text settings.
+async fn settings(ctx: &Context, guild_id: GuildId) -> VoiceTextSettings {
+    guild_config(ctx, guild_id).await.voice_text
+}
+
+/// Give a member access to the text channel of the voice channel they joined,
+/// creating it first if it's temporary.
+async fn join(ctx: &Context, guild_id: GuildId, voice_channel: ChannelId, user_id: UserId) {
+    let settings = settings(ctx, guild_id).await;
+    let text_channel = match (
+        settings.links.get(&voice_channel.0),
+        settings.text_channel(voice_channel.0),
+    ) {
+        (_, Some(text_channel)) => ChannelId(text_channel),
+        (Some(VoiceText::Temporary), None) => {
+            match create_temporary(ctx, guild_id, voice_channel).await {
+                Some(text_channel) => text_channel,
+                None => return,
+            }
+        }
+        (_, None) => return,
+    };
+
+    let overwrite = PermissionOverwrite {
+        allow: ACCESS,
+        deny: Permissions::empty(),
+        kind: PermissionOverwriteType::Member(user_id),
+    };
+    if let Err(e) = rest::call(ctx, "create_permission", || {
+        text_channel.create_permission(&ctx.http, &overwrite)
+    })
+    .await
+    {
+        error!(
+            "Failed to give {} access to voice text channel {}: {}",
+            user_id, text_channel, e
+        );
+    }
+}
+
+/// Take a member's access to the text channel of the voice channel they left,
+/// deleting it if it's temporary and nobody is left.
+async fn leave(ctx: &Context, guild_id: GuildId, voice_channel: ChannelId, user_id: UserId) {
+    let settings = settings(ctx, guild_id).await;
+    let text_channel = match settings.text_channel(voice_channel.0) {
+        Some(text_channel) => ChannelId(text_channel),
+        None => return,
+    };
+    let empty = ctx
+        .cache
+        .guild(guild_id)
+        .is_some_and(|guild| connected(&guild, voice_channel) == 0);
+    if empty && settings.links.get(&voice_channel.0) == Some(&VoiceText::Temporary) {
+        delete_temporary(ctx, guild_id, voice_channel).await;
+        return;
+    }
+    revoke(ctx, text_channel, user_id).await;
+}
+
+/// Remove a member's overwrite from a text channel.
+async fn revoke(ctx: &Context, text_channel: ChannelId, user_id: UserId) {
+    if let Err(e) = rest::call(ctx, "delete_permission", || {
+        text_channel.delete_permission(&ctx.http, PermissionOverwriteType::Member(user_id))
+    })
+    .await
+    {
+        debug!(
+            "Failed to take {}'s access to voice text channel {}: {}",
+            user_id, text_channel, e
+        );
+    }
+}
+
+/// Create the temporary text channel for a voice channel, next to it and hidden
+/// from everyone who isn't connected.
+async fn create_temporary(
+    ctx: &Context,
+    guild_id: GuildId,
+    voice_channel: ChannelId,
+) -> Option<ChannelId> {
+    let (name, category) = ctx
+        .cache
+        .guild_channel(voice_channel)
+        .map(|channel| (temporary_name(&channel.name), channel.parent_id))?;
+    let overwrites = vec![
+        PermissionOverwrite {
+            allow: Permissions::empty(),
+            deny: Permissions::VIEW_CHANNEL,
+            kind: PermissionOverwriteType::Role(RoleId(guild_id.0)),
+        },
+        PermissionOverwrite {
+            allow: ACCESS | Permissions::MANAGE_CHANNELS,
+            deny: Permissions::empty(),
+            kind: PermissionOverwriteType::Member(ctx.cache.current_user_id()),
+        },
+    ];
+    let created = rest::call(ctx, "create_channel", || {
+        let (name, overwrites) = (name.clone(), overwrites.clone());
+        guild_id.create_channel(&ctx.http, move |c| {
+            c.name(name).kind(ChannelType::Text).permissions(overwrites);
+            if let Some(category) = category {
+                c.category(category);
+            }
+            c
+        })
+    })
+    .await;
+    let text_channel = match created {
+        Ok(channel) => channel.id,
+        Err(e) => {
+            error!(
+                "Failed to create the text channel for voice channel {}: {}",
+                voice_channel, e
+            );
+            return None;
+        }
+    };
+
+    let store = {
+        let data = ctx.data.read().await;
+        data.get::<GuildConfigKey>().cloned()
+    }?;
+    let saved = store
+        .update(|configs| {
+            configs
+                .entry(guild_id)
+                .voice_text
+                .temporary
+                .insert(voice_channel.0, text_channel.0);
+        })
+        .await;
+    if let Err(e) = saved {
+        error!("Failed to save voice text channel {}: {}", text_channel, e);
+    }
+    Some(text_channel)
+}
+
+/// Delete the temporary text channel of a voice channel, if it has one.
+pub async fn delete_temporary(ctx: &Context, guild_id: GuildId, voice_channel: ChannelId) {
+    let store = {
+        let data = ctx.data.read().await;
+        data.get::<GuildConfigKey>().cloned()
+    };
+    let store = match store {
+        Some(store) => store,
+        None => return,
+    };
+    let removed = store
+        .update(|configs| {
+            configs
+                .entry(guild_id)
+                .voice_text
+                .temporary
+                .remove(&voice_channel.0)
+        })
+        .await;
+    let text_channel = match removed {
+        Ok(Some(text_channel)) => ChannelId(text_channel),
+        Ok(None) => return,
+        Err(e) => {
+            error!("Failed to forget voice text channel: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = rest::call(ctx, "delete_channel", || text_channel.delete(&ctx.http)).await {
+        debug!(
+            "Failed to delete voice text channel {}: {}",
+            text_channel, e
+        );
+    }
+}
+
+/// Take access from everyone still connected to a voice channel whose link to a
+/// text channel is being removed.
+pub async fn revoke_connected(
+    ctx: &Context,
+    guild_id: GuildId,
+    voice_channel: ChannelId,
+    text_channel: ChannelId,
+) {
+    let connected: Vec<UserId> = ctx
+        .cache
+        .guild(guild_id)
+        .map(|guild| {
+            guild
+                .voice_states
+                .values()
+                .filter(|state| state.channel_id == Some(voice_channel))
+                .map(|state| state.user_id)
+                .collect()
+        })
+        .unwrap_or_default();
+    for user_id in connected {
+        revoke(ctx, text_channel, user_id).await;
+    }
+}
@@ -0,0 +1,144 @@
+//! Handlers that alert staff about watched users.
+
+use async_trait::async_trait;
+use serenity::model::channel::Message;
+use serenity::model::guild::Member;
+use serenity::model::id::{GuildId, UserId};
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::{debug, error};
+
+use crate::framework::event_handler::{EventHandler, Recover};
+use crate::models::moderation::{ModerationKey, WatchEntry};
+use crate::utils::constants::WATCHLIST_ALERT_COOLDOWN;
+use crate::utils::helpers::send_staff_alert;
+
+/// Look up a user's watchlist entry from the client data.
+async fn watch_entry(ctx: &Context, guild_id: GuildId, user_id: UserId) -> Option<WatchEntry> {
+    let store = {
+        let data = ctx.data.read().await;
+        data.get::<ModerationKey>().cloned()
+    }?;
+
+    let data = store.read().await;
+    data.watch_entry(guild_id, user_id).cloned()
+}
+
+/// Describe why a user is being watched.
+fn describe_entry(user_id: UserId, entry: &WatchEntry) -> String {
+    format!(
+        "User: <@{}> (`{}`)\nAdded by: <@{}>\nReason: {}",
+        user_id,
+        user_id,
+        entry.added_by,
+        entry.reason.as_deref().unwrap_or("No reason given")
+    )
+}
+
+/// Alerts staff when a watched user joins the server.
+pub struct WatchlistJoinHandler;
+
+#[async_trait]
+impl EventHandler for WatchlistJoinHandler {
+    fn event_type(&self) -> &'static str {
+        "guild_member_add"
+    }
+
+    async fn on_guild_member_add(&self, ctx: Context, guild_id: GuildId, member: &Member) {
+        let entry = match watch_entry(&ctx, guild_id, member.user.id).await {
+            Some(entry) => entry,
+            None => return,
+        };
+
+        debug!("Watched user {} joined guild {}", member.user.id, guild_id);
+
+        if let Err(e) = send_staff_alert(
+            &ctx,
+            guild_id,
+            "👀 Watched user joined",
+            describe_entry(member.user.id, &entry),
+        )
+        .await
+        {
+            error!("Failed to send watchlist alert: {}", e);
+        }
+    }
+}
+
+/// Alerts staff when a watched user becomes active, at most once per cooldown.
+pub struct WatchlistActivityHandler {
+    /// When each user was last reported.
+    last_alerts: Mutex<HashMap<(GuildId, UserId), Instant>>,
+}
+
+impl WatchlistActivityHandler {
+    /// Create a new WatchlistActivityHandler.
+    pub fn new() -> Self {
+        Self {
+            last_alerts: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl Default for WatchlistActivityHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Recover for WatchlistActivityHandler {
+    fn recover(&self) -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl EventHandler for WatchlistActivityHandler {
+    fn event_type(&self) -> &'static str {
+        "message"
+    }
+
+    async fn on_message(&self, ctx: Context, msg: &Message) {
+        let guild_id = match msg.guild_id {
+            Some(guild_id) if !msg.author.bot => guild_id,
+            _ => return,
+        };
+
+        let entry = match watch_entry(&ctx, guild_id, msg.author.id).await {
+            Some(entry) => entry,
+            None => return,
+        };
+
+        // Only alert once per cooldown so an active user doesn't flood the staff channel
+        {
+            let mut last_alerts = self.last_alerts.lock().await;
+            let key = (guild_id, msg.author.id);
+            let cooldown = Duration::from_secs(WATCHLIST_ALERT_COOLDOWN);
+
+            if let Some(last) = last_alerts.get(&key) {
+                if last.elapsed() < cooldown {
+                    return;
+                }
+            }
+            // Forget users whose cooldown ran out, so the map doesn't keep every
+            // watched user who ever spoke
+            last_alerts.retain(|_, last| last.elapsed() < cooldown);
+            last_alerts.insert(key, Instant::now());
+        }
+
+        let description = format!(
+            "{}\nChannel: <#{}>\n[Jump to message]({})",
+            describe_entry(msg.author.id, &entry),
+            msg.channel_id,
+            msg.link()
+        );
+
+        if let Err(e) =
+            send_staff_alert(&ctx, guild_id, "👀 Watched user is active", description).await
+        {
+            error!("Failed to send watchlist alert: {}", e);
+        }
+    }
+}
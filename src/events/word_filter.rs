@@ -0,0 +1,268 @@
+//! Middleware that enforces each guild's word filter on new and edited messages.
+
+use async_trait::async_trait;
+use regex::Regex;
+use serenity::model::id::{ChannelId, GuildId, MessageId, RoleId};
+use serenity::model::permissions::Permissions;
+use serenity::model::user::User;
+use serenity::prelude::*;
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+use tracing::{debug, error};
+
+use super::automod;
+use crate::framework::middleware::{Event, Middleware, Propagation};
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::ignore::ignored_in;
+use crate::models::moderation::CaseKind;
+use crate::models::word_filter::{
+    FilterAction, FilterPattern, FilterRule, PatternKind, WordFilterKey,
+};
+use crate::utils::helpers::{apply_mentions, author_permissions, mention_policy};
+use crate::utils::rest::{self, Priority};
+
+/// A message being checked against the filter.
+struct Checked<'a> {
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    message_id: MessageId,
+    author: &'a User,
+    content: &'a str,
+    roles: Vec<RoleId>,
+    permissions: Option<Permissions>,
+}
+
+/// Deletes, warns or times out for messages matching the guild's filter list.
+///
+/// Members who can manage messages are never filtered, so staff can manage the
+/// filter without their commands being removed.
+#[derive(Default)]
+pub struct WordFilterMiddleware {
+    /// Compiled patterns. `None` if a pattern doesn't compile.
+    regexes: Mutex<HashMap<(PatternKind, String), Option<Regex>>>,
+}
+
+#[async_trait]
+impl Middleware for WordFilterMiddleware {
+    fn name(&self) -> &str {
+        "word_filter"
+    }
+
+    fn priority(&self) -> i32 {
+        100
+    }
+
+    async fn handle(&self, ctx: &Context, event: &Event<'_>) -> Propagation {
+        let checked = match *event {
+            Event::Message(msg) => match msg.guild_id {
+                Some(guild_id) => Checked {
+                    guild_id,
+                    channel_id: msg.channel_id,
+                    message_id: msg.id,
+                    author: &msg.author,
+                    content: &msg.content,
+                    roles: msg
+                        .member
+                        .as_ref()
+                        .map(|member| member.roles.clone())
+                        .unwrap_or_default(),
+                    permissions: author_permissions(ctx, msg).await,
+                },
+                None => return Propagation::Continue,
+            },
+            Event::MessageUpdate(update) => {
+                match (update.guild_id, &update.author, &update.content) {
+                    (Some(guild_id), Some(author), Some(content)) => {
+                        let member = ctx.cache.member(guild_id, author.id);
+                        let permissions = member.as_ref().and_then(|member| {
+                            let channel = ctx.cache.guild_channel(update.channel_id)?;
+                            let guild = ctx.cache.guild(guild_id)?;
+                            guild.user_permissions_in(&channel, member).ok()
+                        });
+                        Checked {
+                            guild_id,
+                            channel_id: update.channel_id,
+                            message_id: update.id,
+                            author,
+                            content,
+                            roles: member.map(|member| member.roles).unwrap_or_default(),
+                            permissions,
+                        }
+                    }
+                    _ => return Propagation::Continue,
+                }
+            }
+            _ => return Propagation::Continue,
+        };
+
+        if checked.author.bot
+            || checked
+                .permissions
+                .is_some_and(|permissions| permissions.manage_messages())
+        {
+            return Propagation::Continue;
+        }
+        let ignored = ignored_in(
+            ctx,
+            checked.guild_id,
+            checked.channel_id,
+            checked.author.id,
+            &checked.roles,
+        )
+        .await;
+        if ignored.passive {
+            return Propagation::Continue;
+        }
+
+        match self.check(ctx, &checked).await {
+            Some((rule, actions, timeout)) => {
+                enforce(ctx, &checked, &rule, &actions, timeout).await
+            }
+            None => Propagation::Continue,
+        }
+    }
+}
+
+impl WordFilterMiddleware {
+    /// Find the most severe rule a message matches, with the actions for its severity
+    /// and the timeout duration.
+    async fn check(
+        &self,
+        ctx: &Context,
+        checked: &Checked<'_>,
+    ) -> Option<(FilterRule, Vec<FilterAction>, u64)> {
+        let store = {
+            let data = ctx.data.read().await;
+            data.get::<WordFilterKey>().cloned()
+        }?;
+        let data = store.read().await;
+        let filter = data.guild(checked.guild_id)?;
+        if filter.rules.is_empty() || filter.is_whitelisted(checked.channel_id, &checked.roles) {
+            return None;
+        }
+
+        let mut regexes = self.regexes.lock().await;
+        let mut matched: Option<&FilterRule> = None;
+        for rule in &filter.rules {
+            if matched.is_some_and(|m| m.pattern.severity >= rule.pattern.severity) {
+                continue;
+            }
+            if is_match(&mut regexes, &rule.pattern, checked.content) {
+                matched = Some(rule);
+            }
+        }
+
+        let rule = matched?;
+        let actions = filter.actions_for(rule.pattern.severity).to_vec();
+        Some((rule.clone(), actions, filter.timeout))
+    }
+}
+
+/// Whether a message matches a pattern, compiling and caching it if needed.
+fn is_match(
+    regexes: &mut HashMap<(PatternKind, String), Option<Regex>>,
+    pattern: &FilterPattern,
+    content: &str,
+) -> bool {
+    regexes
+        .entry((pattern.kind, pattern.pattern.clone()))
+        .or_insert_with(|| pattern.compile().ok())
+        .as_ref()
+        .is_some_and(|regex| regex.is_match(content))
+}
+
+/// Take a rule's actions against a message.
+///
+/// Stops the event if the message was deleted, so nothing else reacts to it.
+async fn enforce(
+    ctx: &Context,
+    checked: &Checked<'_>,
+    rule: &FilterRule,
+    actions: &[FilterAction],
+    timeout: u64,
+) -> Propagation {
+    let bot_id = ctx.cache.current_user_id();
+    let user_id = checked.author.id;
+    let reason = format!(
+        "Word filter rule #{} ({} severity)",
+        rule.id, rule.pattern.severity
+    );
+    let mut taken = Vec::new();
+    let mut propagation = Propagation::Continue;
+
+    for action in actions {
+        let result = match action {
+            FilterAction::Delete => {
+                let result = rest::call_with(ctx, Priority::Moderation, "delete_message", || {
+                    checked
+                        .channel_id
+                        .delete_message(&ctx.http, checked.message_id)
+                })
+                .await;
+                if result.is_ok() {
+                    propagation = Propagation::Stop;
+                }
+                result
+            }
+            FilterAction::Warn => {
+                automod::record_case(
+                    ctx,
+                    checked.guild_id,
+                    user_id,
+                    CaseKind::Warning,
+                    &reason,
+                    None,
+                )
+                .await;
+                let policy = &mention_policy(ctx).await;
+                rest::call_with(ctx, Priority::Moderation, "send_message", || {
+                    checked.channel_id.send_message(&ctx.http, |m| {
+                        m.allowed_mentions(|am| apply_mentions(am, policy, &[]).users([user_id]))
+                            .content(format!(
+                                "<@{}>, that message isn't allowed here. This is a warning.",
+                                user_id
+                            ))
+                    })
+                })
+                .await
+                .map(|_| ())
+            }
+            FilterAction::Timeout => {
+                automod::timeout(ctx, checked.guild_id, user_id, timeout, &reason).await
+            }
+        };
+
+        match result {
+            Ok(()) => taken.push(action.to_string()),
+            Err(e) => error!(
+                "Word filter failed to {} for {} in {}: {}",
+                action, user_id, checked.guild_id, e
+            ),
+        }
+    }
+
+    debug!(
+        "Word filter rule {} matched {} in {}",
+        rule.id, checked.message_id, checked.guild_id
+    );
+    if !taken.is_empty() {
+        audit::record(
+            ctx,
+            AuditEvent {
+                guild_id: Some(checked.guild_id),
+                actor_id: Some(bot_id),
+                source: AuditSource::Automod,
+                action: format!(
+                    "Filter a message from <@{}> in <#{}>: {}",
+                    user_id,
+                    checked.channel_id,
+                    taken.join(", ")
+                ),
+                reason: Some(reason),
+            },
+        )
+        .await;
+    }
+
+    propagation
+}
@@ -0,0 +1,383 @@
+//! Syncing slash and context menu commands with Discord.
+//!
+//! Discord rate limits command registration, so instead of re-registering every
+//! command on each start, the local definitions are compared with what Discord has
+//! and only new, changed and removed commands are sent. Commands are registered
+//! globally, or only in the `[dev] guilds` in development mode.
+
+use serde_json::{Map, Value};
+use serenity::builder::CreateApplicationCommand;
+use serenity::http::Http;
+use serenity::model::application::command::Command;
+use serenity::model::id::{CommandId, GuildId};
+use serenity::prelude::SerenityError;
+use std::fmt;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use super::plugin::Plugin;
+use crate::models::config::BotConfig;
+
+/// Where commands are registered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyncScope {
+    /// Everywhere the bot is. Changes can take a while to show up.
+    Global,
+    /// A single guild. Changes show up instantly.
+    Guild(GuildId),
+}
+
+impl SyncScope {
+    /// The scopes to sync. Development mode never touches the global commands.
+    pub fn for_config(config: &BotConfig) -> Vec<Self> {
+        if !config.dev.enabled {
+            return vec![Self::Global];
+        }
+        if config.dev.guilds.is_empty() {
+            warn!("Development mode is on but no [dev] guilds are set, so no commands are synced");
+        }
+        config
+            .dev
+            .guilds
+            .iter()
+            .map(|guild_id| Self::Guild(GuildId(*guild_id)))
+            .collect()
+    }
+}
+
+impl fmt::Display for SyncScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Global => f.write_str("global"),
+            Self::Guild(guild_id) => write!(f, "guild {}", guild_id),
+        }
+    }
+}
+
+/// A change needed to bring Discord's commands in line with the local ones.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    /// Register a command Discord doesn't have.
+    Create { name: String, data: Value },
+    /// Replace a command that differs from the local definition.
+    Update {
+        id: CommandId,
+        name: String,
+        data: Value,
+    },
+    /// Remove a command that is no longer defined locally.
+    Delete { id: CommandId, name: String },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Create { name, .. } => write!(f, "create `{}`", name),
+            Self::Update { name, .. } => write!(f, "update `{}`", name),
+            Self::Delete { name, .. } => write!(f, "delete `{}`", name),
+        }
+    }
+}
+
+/// The slash and context menu commands defined by the bot and its plugins, as the
+/// JSON sent to Discord.
+pub fn local_commands(plugins: &[Arc<dyn Plugin>]) -> Vec<Value> {
+    crate::events::application_commands()
+        .into_iter()
+        .chain(
+            plugins
+                .iter()
+                .flat_map(|plugin| plugin.application_commands()),
+        )
+        .map(|command| to_value(&command))
+        .collect()
+}
+
+/// Convert a command builder to the JSON sent to Discord.
+pub fn to_value(command: &CreateApplicationCommand) -> Value {
+    Value::Object(
+        command
+            .0
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.clone()))
+            .collect(),
+    )
+}
+
+/// Work out what has to change for Discord's commands to match the local ones.
+///
+/// Commands are matched by name and type, and compared on the fields the bot sets.
+pub fn diff(local: &[Value], remote: &[Command]) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    for data in local {
+        let shape = local_shape(data);
+        let name = shape["name"].as_str().unwrap_or_default().to_string();
+        let existing = remote
+            .iter()
+            .find(|command| command.name == name && command.kind as u8 as u64 == shape["type"]);
+        match existing {
+            None => changes.push(Change::Create {
+                name,
+                data: data.clone(),
+            }),
+            Some(command) if remote_shape(command) != shape => changes.push(Change::Update {
+                id: command.id,
+                name,
+                data: data.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+
+    for command in remote {
+        let defined = local.iter().any(|data| {
+            let shape = local_shape(data);
+            shape["name"] == command.name.as_str() && shape["type"] == command.kind as u8 as u64
+        });
+        if !defined {
+            changes.push(Change::Delete {
+                id: command.id,
+                name: command.name.clone(),
+            });
+        }
+    }
+
+    changes
+}
+
+/// Bring Discord's commands in a scope in line with the local ones.
+///
+/// Returns the changes, which are only logged and not applied on a dry run.
+pub async fn sync(
+    http: &Http,
+    scope: SyncScope,
+    local: &[Value],
+    dry_run: bool,
+) -> Result<Vec<Change>, SerenityError> {
+    let remote = match scope {
+        SyncScope::Global => http.get_global_application_commands().await?,
+        SyncScope::Guild(guild_id) => http.get_guild_application_commands(guild_id.0).await?,
+    };
+
+    let changes = diff(local, &remote);
+    for change in &changes {
+        if dry_run {
+            info!("Would {} ({} commands)", change, scope);
+            continue;
+        }
+
+        info!("Syncing commands ({}): {}", scope, change);
+        match (scope, change) {
+            (SyncScope::Global, Change::Create { data, .. }) => {
+                http.create_global_application_command(data).await?;
+            }
+            (SyncScope::Global, Change::Update { id, data, .. }) => {
+                http.edit_global_application_command(id.0, data).await?;
+            }
+            (SyncScope::Global, Change::Delete { id, .. }) => {
+                http.delete_global_application_command(id.0).await?;
+            }
+            (SyncScope::Guild(guild_id), Change::Create { data, .. }) => {
+                http.create_guild_application_command(guild_id.0, data)
+                    .await?;
+            }
+            (SyncScope::Guild(guild_id), Change::Update { id, data, .. }) => {
+                http.edit_guild_application_command(guild_id.0, id.0, data)
+                    .await?;
+            }
+            (SyncScope::Guild(guild_id), Change::Delete { id, .. }) => {
+                http.delete_guild_application_command(guild_id.0, id.0)
+                    .await?;
+            }
+        }
+    }
+
+    Ok(changes)
+}
+
+/// The fields of a local definition that are compared, with Discord's defaults
+/// filled in.
+fn local_shape(data: &Value) -> Value {
+    let field = |key: &str| data.get(key).cloned().unwrap_or(Value::Null);
+    shape(
+        field("type").as_u64().unwrap_or(1),
+        field("name"),
+        field("description"),
+        field("options"),
+        field("default_member_permissions"),
+        field("dm_permission").as_bool().unwrap_or(true),
+    )
+}
+
+/// The fields of a registered command that are compared.
+fn remote_shape(command: &Command) -> Value {
+    shape(
+        command.kind as u8 as u64,
+        Value::from(command.name.as_str()),
+        Value::from(command.description.as_str()),
+        serde_json::to_value(&command.options).unwrap_or_default(),
+        command
+            .default_member_permissions
+            .map_or(Value::Null, |permissions| {
+                Value::from(permissions.bits().to_string())
+            }),
+        command.dm_permission.unwrap_or(true),
+    )
+}
+
+/// Build a comparable command from its fields.
+fn shape(
+    kind: u64,
+    name: Value,
+    description: Value,
+    options: Value,
+    permissions: Value,
+    dm_permission: bool,
+) -> Value {
+    let mut shape = Map::new();
+    shape.insert("type".to_string(), Value::from(kind));
+    shape.insert("name".to_string(), name);
+    shape.insert(
+        "description".to_string(),
+        match description {
+            Value::Null => Value::from(""),
+            description => description,
+        },
+    );
+    shape.insert("options".to_string(), normalize(options));
+    shape.insert("default_member_permissions".to_string(), permissions);
+    shape.insert("dm_permission".to_string(), Value::from(dm_permission));
+    Value::Object(shape)
+}
+
+/// Drop unset fields, which Discord and the builders represent differently.
+fn normalize(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(_, value)| !is_unset(value))
+                .map(|(key, value)| (key, normalize(value)))
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(values.into_iter().map(normalize).collect()),
+        Value::Null => Value::Array(Vec::new()),
+        value => value,
+    }
+}
+
+/// Whether a field is null, false or empty.
+fn is_unset(value: &Value) -> bool {
+    match value {
+        Value::Null | Value::Bool(false) => true,
+        Value::Array(values) => values.is_empty(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use serenity::model::application::command::{CommandOptionType, CommandType};
+
+    fn registered(id: u64, name: &str, kind: u8, dm_permission: bool) -> Command {
+        serde_json::from_value(json!({
+            "id": id.to_string(),
+            "application_id": "1",
+            "version": "1",
+            "type": kind,
+            "name": name,
+            "description": "",
+            "dm_permission": dm_permission,
+            "default_member_permissions": null,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn diffs_only_what_changed() {
+        let mut menu = CreateApplicationCommand::default();
+        menu.name("Report Message")
+            .kind(CommandType::Message)
+            .dm_permission(false);
+        let mut slash = CreateApplicationCommand::default();
+        slash
+            .name("ping")
+            .description("Check the bot is alive")
+            .create_option(|option| {
+                option
+                    .name("loud")
+                    .description("Reply in capitals")
+                    .kind(CommandOptionType::Boolean)
+            });
+        let local = [to_value(&menu), to_value(&slash)];
+
+        // An identical context menu needs nothing
+        let remote = [registered(10, "Report Message", 3, false)];
+        let changes = diff(&local, &remote);
+        assert_eq!(changes.len(), 1);
+        assert!(matches!(&changes[0], Change::Create { name, .. } if name == "ping"));
+
+        // A changed context menu is updated and unknown commands are removed
+        let remote = [
+            registered(10, "Report Message", 3, true),
+            registered(11, "old", 1, true),
+        ];
+        let changes: Vec<_> = diff(&local, &remote)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            changes,
+            ["update `Report Message`", "create `ping`", "delete `old`"]
+        );
+    }
+
+    #[test]
+    fn dev_mode_only_syncs_dev_guilds() {
+        let mut config = BotConfig::default();
+        config.dev.guilds = vec![7];
+        assert_eq!(SyncScope::for_config(&config), [SyncScope::Global]);
+
+        config.dev.enabled = true;
+        assert_eq!(
+            SyncScope::for_config(&config),
+            [SyncScope::Guild(GuildId(7))]
+        );
+
+        config.dev.guilds.clear();
+        assert!(SyncScope::for_config(&config).is_empty());
+    }
+
+    #[test]
+    fn compares_options_ignoring_unset_fields() {
+        let mut slash = CreateApplicationCommand::default();
+        slash
+            .name("ping")
+            .description("Pong")
+            .create_option(|option| {
+                option
+                    .name("loud")
+                    .description("Reply in capitals")
+                    .kind(CommandOptionType::Boolean)
+            });
+
+        let remote: Command = serde_json::from_value(json!({
+            "id": "10",
+            "application_id": "1",
+            "version": "1",
+            "type": 1,
+            "name": "ping",
+            "description": "Pong",
+            "options": [{
+                "type": 5,
+                "name": "loud",
+                "description": "Reply in capitals",
+                "required": false,
+            }],
+        }))
+        .unwrap();
+        assert!(diff(&[to_value(&slash)], &[remote]).is_empty());
+    }
+}
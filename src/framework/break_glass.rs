@@ -0,0 +1,352 @@
+//! A second factor for critical owner commands.
+//!
+//! A leaked session or a stolen owner account shouldn't be enough to take the bot
+//! down or hand out entitlements. Commands that say they're
+//! [`critical`](super::command_handler::Command::critical) only run once an owner
+//! confirms them, as set by `[security]` in the configuration:
+//! - With a code, the invoking owner is DMed a one-time code, which is also written
+//!   to the logs for whoever runs the bot, and types it in the channel.
+//! - With buttons, Confirm and Deny buttons are posted in the security channel,
+//!   where only owners can press them. Their component IDs are
+//!   `breakglass:confirm:<id>` and `breakglass:deny:<id>`.
+
+use rand::Rng;
+use serenity::builder::CreateEmbed;
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, MessageId, UserId};
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+use super::wizard::Replies;
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::models::config::{ConfirmMethod, SecurityConfig};
+use crate::utils::constants::{ERROR_COLOR, SUCCESS_COLOR, WARNING_COLOR};
+use crate::utils::dms::dm_service;
+use crate::utils::helpers::{
+    apply_mentions, mention_policy, send_error, send_info, send_success, BotConfigKey,
+};
+
+/// Prefix of the confirmation buttons' component IDs.
+pub const CUSTOM_ID_PREFIX: &str = "breakglass:";
+
+/// Length of a one-time code.
+const CODE_LENGTH: usize = 8;
+
+/// Characters codes are made of, leaving out ones that are easy to mix up.
+const CODE_CHARS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+/// A confirmation waiting for a button press.
+struct Waiting {
+    /// The prompt with the buttons, as its channel and message.
+    prompt: (ChannelId, MessageId),
+    sender: oneshot::Sender<(bool, UserId)>,
+}
+
+/// Confirmations waiting for a button press, by ID.
+#[derive(Default)]
+pub struct BreakGlass {
+    waiting: Mutex<HashMap<String, Waiting>>,
+}
+
+impl BreakGlass {
+    /// Hand a button press on `prompt` to the confirmation waiting for it.
+    /// Presses on any other message, like a copy of the buttons, are ignored.
+    ///
+    /// Returns whether one was waiting.
+    pub fn resolve(
+        &self,
+        id: &str,
+        prompt: (ChannelId, MessageId),
+        approved: bool,
+        owner_id: UserId,
+    ) -> bool {
+        let waiting = {
+            let mut waiting = self.waiting.lock().unwrap_or_else(|e| e.into_inner());
+            match waiting.get(id) {
+                Some(w) if w.prompt == prompt => waiting.remove(id),
+                _ => None,
+            }
+        };
+        waiting.is_some_and(|waiting| waiting.sender.send((approved, owner_id)).is_ok())
+    }
+
+    /// Wait for a button press on `prompt`, returning whether it approved and
+    /// who pressed it.
+    async fn wait(
+        &self,
+        id: &str,
+        prompt: (ChannelId, MessageId),
+        timeout: Duration,
+    ) -> Option<(bool, UserId)> {
+        let (sender, receiver) = oneshot::channel();
+        self.waiting
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(id.to_string(), Waiting { prompt, sender });
+
+        let pressed = tokio::time::timeout(timeout, receiver)
+            .await
+            .ok()
+            .and_then(Result::ok);
+        self.waiting
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(id);
+        pressed
+    }
+}
+
+/// TypeMap key for the confirmations waiting for a button press.
+pub struct BreakGlassKey;
+
+impl TypeMapKey for BreakGlassKey {
+    type Value = Arc<BreakGlass>;
+}
+
+/// Make a one-time code.
+fn code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..CODE_LENGTH)
+        .map(|_| CODE_CHARS[rng.gen_range(0..CODE_CHARS.len())] as char)
+        .collect()
+}
+
+/// Whether a reply matches a code, ignoring case and surrounding spaces.
+fn matches(reply: &str, code: &str) -> bool {
+    reply.trim().eq_ignore_ascii_case(code)
+}
+
+/// Ask an owner to confirm a critical command.
+///
+/// Returns whether it was confirmed. When it wasn't, the invoker has been told.
+pub(super) async fn confirm(
+    replies: &Replies,
+    ctx: &Context,
+    msg: &Message,
+    invocation: &str,
+) -> serenity::Result<bool> {
+    let config = {
+        let data = ctx.data.read().await;
+        data.get::<BotConfigKey>()
+            .map(|config| config.security.clone())
+    }
+    .unwrap_or_default();
+
+    let confirmed = match (config.confirm, config.channel) {
+        (ConfirmMethod::Button, Some(channel_id)) => {
+            with_buttons(ctx, msg, invocation, &config, ChannelId(channel_id)).await?
+        }
+        (ConfirmMethod::Button, None) => {
+            warn!("security.confirm is button but no security.channel is set, using a code");
+            with_code(replies, ctx, msg, invocation, &config).await?
+        }
+        (ConfirmMethod::Code, _) => with_code(replies, ctx, msg, invocation, &config).await?,
+    };
+
+    audit::record(
+        ctx,
+        AuditEvent {
+            guild_id: msg.guild_id,
+            actor_id: Some(msg.author.id),
+            source: AuditSource::Command,
+            action: format!(
+                "{} critical command `{}`",
+                if confirmed { "Confirmed" } else { "Refused" },
+                invocation
+            ),
+            reason: None,
+        },
+    )
+    .await;
+    Ok(confirmed)
+}
+
+/// Confirm with a code DMed to the invoker and typed in the channel.
+async fn with_code(
+    replies: &Replies,
+    ctx: &Context,
+    msg: &Message,
+    invocation: &str,
+    config: &SecurityConfig,
+) -> serenity::Result<bool> {
+    let code = code();
+    let timeout = Duration::from_secs(config.timeout);
+    // Whoever runs the bot can read the code from the logs when DMs don't work
+    warn!(
+        "Confirmation code for `{}` by {}: {}",
+        invocation, msg.author.id, code
+    );
+
+    let sent = match dm_service(ctx).await {
+        Some(dms) => dms
+            .send(&ctx.http, msg.author.id, None, |m| {
+                m.embed(|e| {
+                    e.title("🔐 Confirmation code")
+                        .description(format!(
+                            "Your code for `{}` is `{}`. It expires in {} seconds.\n\n\
+                             If you didn't run this, someone else may have access to your \
+                             account.",
+                            invocation,
+                            code,
+                            timeout.as_secs()
+                        ))
+                        .color(WARNING_COLOR)
+                })
+            })
+            .await
+            .is_ok(),
+        None => false,
+    };
+    let location = if sent { "your DMs" } else { "the bot's logs" };
+    send_info(
+        ctx,
+        msg,
+        "🔐 Confirmation needed",
+        format!(
+            "`{}` is a critical command. Reply with the code from {} within {} seconds.",
+            invocation,
+            location,
+            timeout.as_secs()
+        ),
+    )
+    .await?;
+
+    let confirmed = replies
+        .next(msg.channel_id, msg.author.id, timeout)
+        .await
+        .is_some_and(|reply| matches(&reply, &code));
+    if !confirmed {
+        info!("Critical command `{}` wasn't confirmed", invocation);
+        send_error(ctx, msg, "Wrong or missing code, the command wasn't run.").await?;
+    }
+    Ok(confirmed)
+}
+
+/// Confirm with buttons in the security channel that only owners can press.
+async fn with_buttons(
+    ctx: &Context,
+    msg: &Message,
+    invocation: &str,
+    config: &SecurityConfig,
+    channel_id: ChannelId,
+) -> serenity::Result<bool> {
+    let break_glass = {
+        let data = ctx.data.read().await;
+        data.get::<BreakGlassKey>().cloned()
+    };
+    let break_glass = match break_glass {
+        Some(break_glass) => break_glass,
+        None => {
+            send_error(ctx, msg, "Critical commands can't be confirmed right now.").await?;
+            return Ok(false);
+        }
+    };
+    let id = msg.id.to_string();
+    let timeout = Duration::from_secs(config.timeout);
+
+    let request = format!(
+        "<@{}> wants to run `{}` in <#{}>.",
+        msg.author.id, invocation, msg.channel_id
+    );
+    let policy = &mention_policy(ctx).await;
+    let mut prompt = channel_id
+        .send_message(&ctx.http, |m| {
+            m.allowed_mentions(|am| apply_mentions(am, policy, &[]));
+            m.embed(|e| {
+                e.title("🔐 Confirm critical command")
+                    .description(format!(
+                        "{}\nOnly bot owners can answer. This expires in {} seconds.",
+                        request,
+                        timeout.as_secs()
+                    ))
+                    .color(WARNING_COLOR)
+            })
+            .components(|c| {
+                c.create_action_row(|row| {
+                    row.create_button(|b| {
+                        b.custom_id(format!("{}confirm:{}", CUSTOM_ID_PREFIX, id))
+                            .label("Confirm")
+                            .style(ButtonStyle::Danger)
+                    })
+                    .create_button(|b| {
+                        b.custom_id(format!("{}deny:{}", CUSTOM_ID_PREFIX, id))
+                            .label("Deny")
+                            .style(ButtonStyle::Secondary)
+                    })
+                })
+            })
+        })
+        .await?;
+    send_info(
+        ctx,
+        msg,
+        "🔐 Confirmation needed",
+        format!(
+            "`{}` is a critical command. An owner has to confirm it in <#{}>.",
+            invocation, channel_id
+        ),
+    )
+    .await?;
+
+    let pressed = break_glass
+        .wait(&id, (prompt.channel_id, prompt.id), timeout)
+        .await;
+    let (outcome, color) = match pressed {
+        Some((true, owner_id)) => (format!("Confirmed by <@{}>.", owner_id), SUCCESS_COLOR),
+        Some((false, owner_id)) => (format!("Denied by <@{}>.", owner_id), ERROR_COLOR),
+        None => ("Expired.".to_string(), ERROR_COLOR),
+    };
+    let mut embed = CreateEmbed::default();
+    embed
+        .title("🔐 Critical command")
+        .description(format!("{}\n{}", request, outcome))
+        .color(color);
+    if let Err(e) = prompt
+        .edit(&ctx.http, |m| m.set_embed(embed).components(|c| c))
+        .await
+    {
+        warn!("Failed to close confirmation prompt: {}", e);
+    }
+
+    let confirmed = pressed.is_some_and(|(approved, _)| approved);
+    if confirmed {
+        send_success(ctx, msg, "Confirmed, running the command.").await?;
+    } else {
+        send_error(ctx, msg, "The command wasn't confirmed, so it wasn't run.").await?;
+    }
+    Ok(confirmed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn codes_and_buttons() {
+        let first = code();
+        assert_eq!(first.len(), CODE_LENGTH);
+        assert!(first.bytes().all(|c| CODE_CHARS.contains(&c)));
+        assert_ne!(first, code());
+        assert!(matches(&format!(" {} \n", first.to_lowercase()), &first));
+        assert!(!matches("", &first));
+
+        let break_glass = Arc::new(BreakGlass::default());
+        let prompt = (ChannelId(3), MessageId(4));
+        assert!(!break_glass.resolve("1", prompt, true, UserId(2)));
+        let waiting = break_glass.clone();
+        let pressed =
+            tokio::spawn(async move { waiting.wait("1", prompt, Duration::from_secs(5)).await });
+        while !break_glass.resolve("1", prompt, false, UserId(2)) {
+            // Buttons on another message don't answer it
+            assert!(!break_glass.resolve("1", (ChannelId(5), MessageId(4)), true, UserId(2)));
+            assert!(!break_glass.resolve("1", (ChannelId(3), MessageId(6)), true, UserId(2)));
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(pressed.await.unwrap(), Some((false, UserId(2))));
+    }
+}
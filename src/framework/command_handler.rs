@@ -2,15 +2,40 @@
 
 use async_trait::async_trait;
 use serenity::model::channel::Message;
+use serenity::model::id::UserId;
+use serenity::model::permissions::Permissions;
 use serenity::prelude::*;
-use std::collections::HashMap;
-use std::sync::Arc;
-use tracing::{debug, error, instrument};
+use std::collections::{HashMap, HashSet};
+use std::str::SplitWhitespace;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, field, info, info_span, warn, Instrument, Span};
 
-use crate::utils::constants::DEFAULT_PREFIX;
+use super::break_glass;
+use super::error::{self, KurumiError};
+use super::inline;
+use super::params::{self, Param};
+use super::plugin::{Modules, Plugin};
+use super::queue::{ConcurrencyGuard, ExecutionQueue, MaxConcurrency};
+use super::scripts::ScriptsKey;
+use super::state::{FromState, State};
+use super::wizard::{self, Replies};
+use crate::models::aliases;
+use crate::models::guild_config::guild_config;
+use crate::models::ignore::ignored;
+use crate::models::maintenance::MaintenanceKey;
+use crate::utils::constants::{DEFAULT_MAX_CONCURRENT_COMMANDS, DEFAULT_PREFIX};
+use crate::utils::duration::format_compact;
+use crate::utils::helpers::{
+    author_permissions, is_nsfw_channel, is_owner, permissions_in, send_error, send_info,
+    send_warning,
+};
+use crate::utils::limits::LimitsKey;
+use crate::utils::paste::send_long_info;
 
 /// Result type for command functions.
-pub type CommandResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
+pub type CommandResult<T = ()> = Result<T, KurumiError>;
 
 /// Context passed to command execution functions.
 pub struct CommandContext<'a> {
@@ -20,8 +45,70 @@ pub struct CommandContext<'a> {
     pub msg: &'a Message,
     /// Command arguments (space-separated words after the command).
     pub args: Vec<String>,
-    /// Data passed from the framework.
-    pub data: &'a TypeMap,
+    /// Shared state passed from the framework.
+    pub data: State,
+    /// Whether destructive actions should only be logged, see [`DryRun`].
+    pub dry_run: &'a DryRun,
+}
+
+/// Dry-run mode for a command invocation.
+///
+/// When enabled, the helpers in [`crate::utils::actions`] record what they would
+/// have done instead of doing it. Owners enable it with `simulate <command>`.
+///
+/// Only those helpers, and commands that check [`DryRun::record`] themselves
+/// like `pinarchive` and `mydata delete`, are skipped: anything else a command
+/// does, like sending messages or changing stored settings, still happens.
+#[derive(Debug, Default)]
+pub struct DryRun {
+    enabled: bool,
+    actions: std::sync::Mutex<Vec<String>>,
+}
+
+impl DryRun {
+    /// Create a disabled dry run, where actions are performed normally.
+    pub const fn disabled() -> Self {
+        Self {
+            enabled: false,
+            actions: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Create an enabled dry run.
+    pub const fn enabled() -> Self {
+        Self {
+            enabled: true,
+            actions: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Whether destructive actions are being skipped.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Record an action. Returns `true` if the action should be skipped.
+    pub fn record(&self, action: impl Into<String>) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        let action = action.into();
+        info!("Dry run: {}", action);
+        self.actions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(action);
+        true
+    }
+
+    /// The actions recorded so far.
+    pub fn actions(&self) -> Vec<String> {
+        self.actions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
 }
 
 /// Trait for implementing commands.
@@ -35,20 +122,157 @@ pub trait Command: Send + Sync {
         ""
     }
 
-    /// Optional usage information.
+    /// Freeform usage information, for commands without [`Command::params`].
     fn usage(&self) -> &str {
         ""
     }
 
+    /// The command's arguments, in order.
+    ///
+    /// Commands that list them get their usage string, slash command options and
+    /// missing argument errors generated, see [`super::params`].
+    fn params(&self) -> Vec<Param> {
+        Vec::new()
+    }
+
+    /// Whether to ask for missing required [`Command::params`] one at a time
+    /// instead of refusing to run, see [`super::wizard`].
+    fn wizard(&self) -> bool {
+        false
+    }
+
     /// Optional list of aliases for the command.
     fn aliases(&self) -> Vec<&str> {
         vec![]
     }
 
+    /// Permissions the invoking member needs in the channel.
+    ///
+    /// Commands that require any permissions can only be used in servers.
+    fn required_permissions(&self) -> Permissions {
+        Permissions::empty()
+    }
+
+    /// Permissions the bot needs in the channel to run the command.
+    ///
+    /// Checked before the command runs, so users are told what is missing instead
+    /// of the command failing partway through.
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::empty()
+    }
+
+    /// Whether only bot owners can use the command.
+    ///
+    /// Owner-only commands are never blocked by maintenance mode.
+    fn owner_only(&self) -> bool {
+        false
+    }
+
+    /// Whether an owner has to confirm this invocation with a second factor
+    /// before it runs, see [`super::break_glass`].
+    fn critical(&self, _args: &[String]) -> bool {
+        false
+    }
+
+    /// Whether the command can only be used in servers.
+    ///
+    /// Commands that require any permissions are always server-only.
+    fn guild_only(&self) -> bool {
+        false
+    }
+
+    /// Whether the command can only be used in direct messages with the bot.
+    fn dm_only(&self) -> bool {
+        false
+    }
+
+    /// Whether the command can only be used in age-restricted channels.
+    fn nsfw_only(&self) -> bool {
+        false
+    }
+
+    /// Whether the command needs a premium tier, either the user's own or the
+    /// server's.
+    fn premium_only(&self) -> bool {
+        false
+    }
+
+    /// How many invocations may run at once, such as one `purge` per server.
+    ///
+    /// Invocations over the limit are refused. Without a limit, only the ordering
+    /// within a channel applies.
+    fn max_concurrency(&self) -> Option<MaxConcurrency> {
+        None
+    }
+
+    /// How long the command may run before it is stopped, overriding
+    /// `commands.timeout`.
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+
     /// Execute the command.
     async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult;
 }
 
+/// Creates a short ID that ties a command invocation to its log trace.
+///
+/// IDs combine the current time with a counter, so they are unique enough to search
+/// logs for without pulling in a random number generator.
+fn correlation_id() -> String {
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("{:06x}{:04x}", seconds & 0xff_ffff, count & 0xffff)
+}
+
+/// Finds a script command, see [`super::scripts`].
+async fn script_command(ctx: &Context, name: &str) -> Option<Arc<dyn Command>> {
+    let scripts = {
+        let data = ctx.data.read().await;
+        data.get::<ScriptsKey>().cloned()
+    }?;
+    let command: Arc<dyn Command> = scripts.get(name)?;
+    Some(command)
+}
+
+/// A command invocation that passed its checks and is ready to run.
+struct Invocation<'h> {
+    command: Arc<dyn Command>,
+    name: String,
+    dry_run: bool,
+    arguments: Vec<String>,
+    /// Holds the invocation's slot under the command's concurrency limit.
+    _running: Option<ConcurrencyGuard<'h>>,
+}
+
+impl Invocation<'_> {
+    /// Whether the command asks for arguments it is missing, see [`super::wizard`].
+    fn needs_wizard(&self) -> bool {
+        self.command.wizard() && params::missing(&self.command.params(), &self.arguments).is_some()
+    }
+
+    /// Whether the invoker has to answer prompts before the command can run.
+    fn needs_answers(&self) -> bool {
+        self.needs_wizard() || self.command.critical(&self.arguments)
+    }
+}
+
+/// Key for the names and aliases of the registered commands, which guild aliases
+/// can't take.
+pub struct CommandNamesKey;
+
+impl TypeMapKey for CommandNamesKey {
+    type Value = Arc<HashSet<String>>;
+}
+
+/// Builds a command once the shared state is available.
+type PendingCommand = Box<dyn FnOnce(&TypeMap) -> Result<Arc<dyn Command>, String> + Send + Sync>;
+
 /// Handles command registration and execution.
 pub struct CommandHandler {
     /// Maps command names to command implementations.
@@ -57,6 +281,33 @@ pub struct CommandHandler {
     aliases: HashMap<String, String>,
     /// Command prefix.
     prefix: String,
+    /// A second prefix answered in development mode.
+    dev_prefix: Option<String>,
+    /// Commands registered with [`Self::register_with_state`] that haven't been built yet,
+    /// with the plugin that registered them.
+    pending: Vec<(Option<&'static str>, PendingCommand)>,
+    /// Maps command names to the plugin that registered them.
+    plugins: HashMap<String, &'static str>,
+    /// The plugin whose commands are being registered, see [`Self::register_plugin`].
+    registering: Option<&'static str>,
+    /// Which guilds turned which plugins off, see [`Self::set_modules`].
+    modules: Option<Arc<Modules>>,
+    /// How long a user has to wait between uses of the same command.
+    cooldown: Duration,
+    /// How long a command may run before it is stopped. Zero means no limit.
+    timeout: Duration,
+    /// When each user last ran each command.
+    cooldowns: Mutex<HashMap<(UserId, String), Instant>>,
+    /// Keeps commands in per-channel order and limits how many run at once.
+    queue: ExecutionQueue,
+    /// Wizards waiting for answers to their questions.
+    replies: Arc<Replies>,
+}
+
+impl Default for CommandHandler {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CommandHandler {
@@ -66,6 +317,16 @@ impl CommandHandler {
             commands: HashMap::new(),
             aliases: HashMap::new(),
             prefix: DEFAULT_PREFIX.to_string(),
+            dev_prefix: None,
+            pending: Vec::new(),
+            plugins: HashMap::new(),
+            registering: None,
+            modules: None,
+            cooldown: Duration::ZERO,
+            timeout: Duration::ZERO,
+            cooldowns: Mutex::new(HashMap::new()),
+            queue: ExecutionQueue::new(DEFAULT_MAX_CONCURRENT_COMMANDS),
+            replies: Arc::default(),
         }
     }
 
@@ -75,10 +336,88 @@ impl CommandHandler {
         self
     }
 
+    /// Sets a second prefix that commands are also answered on, for development.
+    pub fn with_dev_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.dev_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Sets how long a user has to wait between uses of the same command.
+    ///
+    /// Owners are never rate limited. A zero cooldown disables the check.
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Sets how long a command may run before it is stopped and the user told.
+    ///
+    /// Commands can override it with [`Command::timeout`]. A zero timeout disables
+    /// the limit.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets how many commands may run at once across all channels.
+    pub fn with_max_concurrent(mut self, max: usize) -> Self {
+        self.queue = ExecutionQueue::new(max);
+        self
+    }
+
     /// Registers a command.
     pub fn register_command(&mut self, command: impl Command + 'static) {
-        let command = Arc::new(command);
+        self.insert_command(Arc::new(command), self.registering);
+    }
+
+    /// Registers a plugin's commands, so they are hidden in guilds that turned it off.
+    pub fn register_plugin(&mut self, plugin: &dyn Plugin) {
+        self.registering = Some(plugin.name());
+        plugin.register_commands(self);
+        self.registering = None;
+    }
+
+    /// Sets the plugin modules checked before running plugin commands.
+    ///
+    /// Without them, every plugin's commands run everywhere.
+    pub fn set_modules(&mut self, modules: Arc<Modules>) {
+        self.modules = Some(modules);
+    }
+
+    /// Registers a command built from the shared state.
+    ///
+    /// The constructor runs when the bot starts, after the stores are opened, and
+    /// receives its dependencies through [`FromState`].
+    pub fn register_with_state<D, C>(
+        &mut self,
+        constructor: impl FnOnce(D) -> C + Send + Sync + 'static,
+    ) where
+        D: FromState,
+        C: Command + 'static,
+    {
+        self.pending.push((
+            self.registering,
+            Box::new(move |state| {
+                let command: Arc<dyn Command> = Arc::new(constructor(D::from_state(state)?));
+                Ok(command)
+            }),
+        ));
+    }
+
+    /// Builds the commands registered with [`Self::register_with_state`].
+    pub fn resolve_state(&mut self, state: &TypeMap) -> Result<(), String> {
+        for (plugin, pending) in std::mem::take(&mut self.pending) {
+            self.insert_command(pending(state)?, plugin);
+        }
+        Ok(())
+    }
+
+    /// Adds a command and its aliases to the lookup tables.
+    fn insert_command(&mut self, command: Arc<dyn Command>, plugin: Option<&'static str>) {
         let name = command.name().to_lowercase();
+        if let Some(plugin) = plugin {
+            self.plugins.insert(name.clone(), plugin);
+        }
 
         // Register main command
         self.commands.insert(name.clone(), command.clone()); // Fixed: Using clone() directly
@@ -97,66 +436,626 @@ impl CommandHandler {
     }
 
     /// Checks if a message is a command and executes it.
-    #[instrument(skip(self, ctx, msg), fields(command))]
+    ///
+    /// Each invocation gets a correlation ID that is attached to every log line of the
+    /// pipeline and shown to the user if the command fails, so owners can find the trace.
+    /// Commands wait for the earlier commands in their channel to finish.
     pub async fn handle_message(&self, ctx: &Context, msg: &Message) -> CommandResult {
-        // Skip messages from bots
-        if msg.author.bot {
+        // Skip messages from bots and anything that isn't a command
+        if msg.author.bot || self.strip_prefix(&msg.content).is_none() {
             return Ok(());
         }
 
-        // Check if message starts with prefix
-        if !msg.content.starts_with(&self.prefix) {
+        let correlation_id = correlation_id();
+        let span = info_span!(
+            "command",
+            correlation_id = %correlation_id,
+            shard = ctx.shard_id,
+            user_id = msg.author.id.0,
+            guild_id = field::Empty,
+            command = field::Empty,
+        );
+        if let Some(guild_id) = msg.guild_id {
+            span.record("guild_id", guild_id.0);
+        }
+
+        let pipeline = self.run_pipeline(ctx, msg, &correlation_id);
+        let waiting = self
+            .queue
+            .run(msg.channel_id, pipeline)
+            .instrument(span.clone())
+            .await?;
+
+        // Commands that wait for their invoker's answers do so outside the queue, so
+        // they don't hold up the channel or a global slot while nobody types, and
+        // then queue again to run
+        let invocation = match waiting {
+            Some(invocation) => invocation,
+            None => return Ok(()),
+        };
+        let invocation = match self
+            .ask(ctx, msg, invocation)
+            .instrument(span.clone())
+            .await?
+        {
+            Some(invocation) => invocation,
+            None => return Ok(()),
+        };
+        let execution = self.execute(ctx, msg, invocation, &correlation_id);
+        self.queue
+            .run(msg.channel_id, execution)
+            .instrument(span)
+            .await
+    }
+
+    /// Runs the commands written inside a message that isn't a command itself,
+    /// if the server turned on `inline_commands`, see [`super::inline`].
+    pub async fn handle_inline(&self, ctx: &Context, msg: &Message) -> CommandResult {
+        let guild_id = match msg.guild_id {
+            Some(guild_id) if !msg.author.bot => guild_id,
+            _ => return Ok(()),
+        };
+        if !msg.content.contains("{{") && !msg.content.contains("[[") {
             return Ok(());
         }
 
+        let style = guild_config(ctx, guild_id).await.inline_commands;
+        for invocation in inline::find(&msg.content, style) {
+            let mut inline = msg.clone();
+            inline.content = format!("{}{}", self.prefix, invocation);
+            self.handle_message(ctx, &inline).await?;
+        }
+        Ok(())
+    }
+
+    /// Runs the command pipeline stages inside the invocation span.
+    ///
+    /// Returns the invocation instead of executing it if it needs answers from the
+    /// invoker first, see [`Self::ask`].
+    async fn run_pipeline(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        correlation_id: &str,
+    ) -> CommandResult<Option<Invocation<'_>>> {
+        let owner = is_owner(ctx, msg.author.id).await;
+
         // Parse command name and arguments
-        let content = msg.content.trim_start_matches(&self.prefix);
-        let mut args = content.split_whitespace();
+        let (command_name, dry_run, args) = {
+            let _stage = info_span!("prefix_match").entered();
+            match self.match_prefix(&msg.content) {
+                Some(matched) => matched,
+                None => return Ok(None),
+            }
+        };
 
-        let cmd_name = match args.next() {
-            Some(name) => name.to_lowercase(),
-            None => return Ok(()),
+        // Ignored channels, roles and users get no reply, except from members who
+        // can manage the server and so can lift the ignore
+        if !owner && ignored(ctx, msg).await.commands {
+            let manager = author_permissions(ctx, msg)
+                .await
+                .is_some_and(|permissions| permissions.manage_guild());
+            if !manager {
+                debug!("Message ignored by the guild's ignore list");
+                return Ok(None);
+            }
+        }
+
+        // `simulate <command>` runs a command in dry-run mode
+        if dry_run && !owner {
+            send_error(ctx, msg, "This command can only be used by the bot owners.").await?;
+            return Ok(None);
+        }
+        let command_name = match command_name {
+            Some(name) => name,
+            None => {
+                send_error(ctx, msg, "Usage: `simulate <command> [args...]`").await?;
+                return Ok(None);
+            }
+        };
+
+        // Aliases the guild defined stand for a command and arguments that go first
+        let (command_name, preset) = match msg.guild_id {
+            Some(guild_id) if !self.is_reserved(&command_name) => {
+                match aliases::resolve(ctx, guild_id, &command_name).await {
+                    Some(expanded) => expanded,
+                    None => (command_name, Vec::new()),
+                }
+            }
+            _ => (command_name, Vec::new()),
         };
 
-        // Find command by name or alias
-        let command_name = self.aliases.get(&cmd_name).unwrap_or(&cmd_name);
+        // Find command by name or alias, then among the script commands
+        let command_name = self.aliases.get(&command_name).unwrap_or(&command_name);
         let command = match self.commands.get(command_name) {
-            Some(cmd) => cmd,
-            None => return Ok(()), // Command not found
+            Some(cmd) => cmd.clone(),
+            None => match script_command(ctx, command_name).await {
+                Some(cmd) => cmd,
+                None if dry_run => {
+                    send_error(ctx, msg, format!("Unknown command `{}`.", command_name)).await?;
+                    return Ok(None);
+                }
+                None => return Ok(None), // Command not found
+            },
+        };
+        Span::current().record("command", command_name.as_str());
+
+        // Commands from plugins the guild turned off don't run there
+        if let (Some(plugin), Some(modules)) = (self.command_plugin(command_name), &self.modules) {
+            if !modules.allows(modules.mask(msg.guild_id), Some(plugin)) {
+                debug!("Command {} refused, plugin {} is off", command_name, plugin);
+                send_error(
+                    ctx,
+                    msg,
+                    format!("The {} module is turned off in this server.", plugin),
+                )
+                .await?;
+                return Ok(None);
+            }
+        }
+
+        let allowed = self
+            .check_permissions(ctx, msg, command_name, command.as_ref(), owner)
+            .instrument(info_span!("permission_check"))
+            .await?;
+        if !allowed {
+            return Ok(None);
+        }
+
+        // Simulated runs don't do anything, so they don't count towards the cooldown
+        let remaining = {
+            let _stage = info_span!("cooldown_check").entered();
+            if owner || dry_run {
+                None
+            } else {
+                self.check_cooldown(msg.author.id, command_name)
+            }
+        };
+        if let Some(remaining) = remaining {
+            debug!("Command {} on cooldown for {:?}", command_name, remaining);
+            send_warning(
+                ctx,
+                msg,
+                format!(
+                    "Slow down! You can use `{}` again in {}.",
+                    command_name,
+                    format_compact(remaining.max(Duration::from_secs(1)))
+                ),
+            )
+            .await?;
+            return Ok(None);
+        }
+
+        // Refuse invocations over the command's concurrency limit
+        let _running = match command.max_concurrency() {
+            Some(limit) => match self.queue.try_start(command_name, limit, msg) {
+                Some(guard) => Some(guard),
+                None => {
+                    debug!("Command {} at its concurrency limit", command_name);
+                    send_warning(
+                        ctx,
+                        msg,
+                        format!(
+                            "`{}` is already running {}. Try again when it finishes.",
+                            command_name, limit.scope
+                        ),
+                    )
+                    .await?;
+                    return Ok(None);
+                }
+            },
+            None => None,
         };
 
         // Collect remaining arguments
-        let arguments: Vec<String> = args.map(String::from).collect();
+        let arguments: Vec<String> = {
+            let _stage = info_span!("argument_parse").entered();
+            preset.into_iter().chain(args.map(String::from)).collect()
+        };
+
+        let invocation = Invocation {
+            command,
+            name: command_name.clone(),
+            dry_run,
+            arguments,
+            _running,
+        };
+        if invocation.needs_answers() {
+            return Ok(Some(invocation));
+        }
+        self.execute(ctx, msg, invocation, correlation_id).await?;
+        Ok(None)
+    }
+
+    /// Asks the invoker for the arguments a wizard command is missing, and an owner
+    /// to confirm a critical command, see [`super::break_glass`].
+    ///
+    /// Returns `None` if they didn't answer or the command wasn't confirmed, which
+    /// they've been told about.
+    async fn ask<'h>(
+        &'h self,
+        ctx: &Context,
+        msg: &Message,
+        mut invocation: Invocation<'h>,
+    ) -> CommandResult<Option<Invocation<'h>>> {
+        if invocation.needs_wizard() {
+            let params = invocation.command.params();
+            let arguments = std::mem::take(&mut invocation.arguments);
+            match wizard::complete(&self.replies, ctx, msg, &params, arguments).await? {
+                Some(arguments) => invocation.arguments = arguments,
+                None => return Ok(None),
+            }
+        }
+
+        // Ask for a second factor before critical commands run, simulated or not,
+        // since not every critical command leaves its changes to the dry run
+        if invocation.command.critical(&invocation.arguments) {
+            // Arguments that don't check out are refused when the command runs
+            let params = invocation.command.params();
+            if params::check(&invocation.name, &params, &invocation.arguments).is_ok() {
+                let command_line = std::iter::once(invocation.name.as_str())
+                    .chain(invocation.arguments.iter().map(String::as_str))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                if !break_glass::confirm(&self.replies, ctx, msg, &command_line).await? {
+                    return Ok(None);
+                }
+            }
+        }
+        Ok(Some(invocation))
+    }
+
+    /// Checks an invocation's arguments and runs it, reporting what a simulated
+    /// command would have done.
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        invocation: Invocation<'_>,
+        correlation_id: &str,
+    ) -> CommandResult {
+        let Invocation {
+            command,
+            name: command_name,
+            dry_run,
+            arguments,
+            _running,
+        } = invocation;
+
+        // Refuse invocations with missing or invalid arguments
+        let params = command.params();
+        if let Err(e) = params::check(&command_name, &params, &arguments) {
+            debug!("Command {} got bad arguments: {}", command_name, e);
+            send_error(ctx, msg, e).await?;
+            return Ok(());
+        }
 
         // Create command context
-        let data = ctx.data.read().await;
+        let dry_run_log = if dry_run {
+            DryRun::enabled()
+        } else {
+            DryRun::disabled()
+        };
         let cmd_ctx = CommandContext {
             ctx,
             msg,
             args: arguments,
-            data: &data,
+            data: State::from(ctx),
+            dry_run: &dry_run_log,
         };
 
         // Execute command
         debug!("Executing command: {}", command_name);
-        match command.execute(cmd_ctx).await {
+        let execution = command.execute(cmd_ctx).instrument(info_span!("execute"));
+        let timeout = command.timeout().unwrap_or(self.timeout);
+        let result = if timeout.is_zero() {
+            execution.await
+        } else {
+            // Dropping the future on timeout cancels whatever the command was waiting on
+            match tokio::time::timeout(timeout, execution).await {
+                Ok(result) => result,
+                Err(_) => {
+                    warn!("Command {} timed out after {:?}", command_name, timeout);
+                    Err(KurumiError::Timeout(timeout))
+                }
+            }
+        };
+        match &result {
             Ok(()) => {
                 debug!("Command {} executed successfully", command_name);
             }
+            Err(e) if dry_run => {
+                debug!("Simulated command {} failed: {}", command_name, e);
+            }
             Err(e) => {
-                error!("Command {} failed with error: {:?}", command_name, e);
-                // You could send an error message to the channel here
+                error::report(ctx, msg, &command_name, e, correlation_id).await?;
+            }
+        }
+
+        // Report what a simulated command would have done
+        if dry_run {
+            let actions = dry_run_log.actions();
+            let mut report = if actions.is_empty() {
+                "Nothing that simulating skips would have been done.".to_string()
+            } else {
+                actions
+                    .iter()
+                    .map(|action| format!("• {}", action))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+            report.push_str(
+                "\n\nSimulating only skips bans, unbans, kicks, timeouts, role changes, deleting \
+                 messages, channels, roles and server events, unpins, pin archiving, permission \
+                 overwrites and deleting stored data; anything else the command does happened \
+                 as usual.",
+            );
+            if let Err(e) = &result {
+                report.push_str(&format!(
+                    "\n\nThe command failed: {}\nReference: `{}`",
+                    e, correlation_id
+                ));
             }
+
+            send_long_info(
+                ctx,
+                msg,
+                format!("🧪 Simulated `{}`", command_name),
+                &report,
+            )
+            .await?;
         }
 
         Ok(())
     }
 
+    /// Splits a prefixed message into its command name, whether it is simulated, and its
+    /// arguments.
+    ///
+    /// The name is `None` for a bare `simulate` with no command to run.
+    fn match_prefix<'m>(
+        &self,
+        content: &'m str,
+    ) -> Option<(Option<String>, bool, SplitWhitespace<'m>)> {
+        let content = self.strip_prefix(content)?;
+        let mut args = content.split_whitespace();
+
+        let cmd_name = args.next()?.to_lowercase();
+        if cmd_name == "simulate" {
+            let target = args.next().map(str::to_lowercase);
+            return Some((target, true, args));
+        }
+        Some((Some(cmd_name), false, args))
+    }
+
+    /// Strips the command prefix, or the development prefix, from a message.
+    fn strip_prefix<'m>(&self, content: &'m str) -> Option<&'m str> {
+        content.strip_prefix(&self.prefix).or_else(|| {
+            self.dev_prefix
+                .as_deref()
+                .and_then(|prefix| content.strip_prefix(prefix))
+        })
+    }
+
+    /// Checks owner-only commands, maintenance mode, server and DM-only commands,
+    /// age-restricted and premium commands, the invoking member's permissions and the
+    /// bot's own permissions.
+    ///
+    /// Returns `false` after telling the user why the command can't run.
+    async fn check_permissions(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        command_name: &str,
+        command: &dyn Command,
+        owner: bool,
+    ) -> CommandResult<bool> {
+        // Check owner-only commands and maintenance mode
+        if command.owner_only() {
+            if !owner {
+                send_error(ctx, msg, "This command can only be used by the bot owners.").await?;
+                return Ok(false);
+            }
+        } else {
+            let maintenance = {
+                let data = ctx.data.read().await;
+                data.get::<MaintenanceKey>().cloned()
+            };
+            if let Some(maintenance) = maintenance {
+                let notice = maintenance.read().await.notice_for(command_name, owner);
+                if let Some(notice) = notice {
+                    debug!("Command {} blocked by maintenance mode", command_name);
+                    send_info(ctx, msg, "🛠️ Maintenance", notice).await?;
+                    return Ok(false);
+                }
+            }
+        }
+
+        // Keep commands in the kind of channel they were made for
+        let in_guild = msg.guild_id.is_some();
+        if command.guild_only() && !in_guild {
+            debug!("Command {} refused outside a server", command_name);
+            send_error(ctx, msg, "This command can only be used in a server.").await?;
+            return Ok(false);
+        }
+        if command.dm_only() && in_guild {
+            debug!("Command {} refused outside direct messages", command_name);
+            send_error(
+                ctx,
+                msg,
+                format!(
+                    "`{}` can only be used in direct messages with me.",
+                    command_name
+                ),
+            )
+            .await?;
+            return Ok(false);
+        }
+
+        // Keep age-restricted commands out of other channels
+        if command.nsfw_only() && !is_nsfw_channel(ctx, msg.channel_id) {
+            debug!(
+                "Command {} refused outside an age-restricted channel",
+                command_name
+            );
+            send_info(
+                ctx,
+                msg,
+                "🔞 Age-restricted command",
+                format!(
+                    "Sorry, `{}` can only be used in age-restricted channels.",
+                    command_name
+                ),
+            )
+            .await?;
+            return Ok(false);
+        }
+
+        // Keep premium commands to premium users and servers
+        if command.premium_only() && !owner {
+            let limits = {
+                let data = ctx.data.read().await;
+                data.get::<LimitsKey>().cloned()
+            };
+            let premium = match limits {
+                Some(limits) => limits.tier(msg.author.id, msg.guild_id).await.is_premium(),
+                None => false,
+            };
+            if !premium {
+                debug!("Command {} refused without premium", command_name);
+                send_info(
+                    ctx,
+                    msg,
+                    "⭐ Premium command",
+                    format!("Sorry, `{}` is only available with premium.", command_name),
+                )
+                .await?;
+                return Ok(false);
+            }
+        }
+
+        // Check the invoking member's permissions
+        let required = command.required_permissions();
+        if !required.is_empty() {
+            match author_permissions(ctx, msg).await {
+                Some(permissions) if permissions.contains(required) => {}
+                Some(permissions) => {
+                    let missing = required - permissions;
+                    send_error(
+                        ctx,
+                        msg,
+                        format!("You need the following permissions: {}", missing),
+                    )
+                    .await?;
+                    return Ok(false);
+                }
+                None => {
+                    send_error(ctx, msg, "This command can only be used in a server.").await?;
+                    return Ok(false);
+                }
+            }
+        }
+
+        // Check the bot's own permissions, which only apply in servers
+        let required = command.required_bot_permissions();
+        if let Some(guild_id) = msg.guild_id.filter(|_| !required.is_empty()) {
+            let bot_id = ctx.cache.current_user_id();
+            let permissions = permissions_in(ctx, guild_id, msg.channel_id, bot_id).await;
+            if let Some(permissions) = permissions.filter(|p| !p.contains(required)) {
+                let missing = required - permissions;
+                debug!(
+                    "Command {} missing bot permissions: {}",
+                    command_name, missing
+                );
+                send_error(
+                    ctx,
+                    msg,
+                    format!("I need the following permissions here: {}", missing),
+                )
+                .await?;
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Starts the user's cooldown for a command, or returns how long is left on it.
+    fn check_cooldown(&self, user: UserId, command_name: &str) -> Option<Duration> {
+        if self.cooldown.is_zero() {
+            return None;
+        }
+
+        let mut cooldowns = self.cooldowns.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        // Forget expired entries so the map doesn't grow with every user who ever ran a command
+        cooldowns.retain(|_, last| now.duration_since(*last) < self.cooldown);
+
+        let key = (user, command_name.to_string());
+        if let Some(last) = cooldowns.get(&key) {
+            return Some(self.cooldown - now.duration_since(*last));
+        }
+        cooldowns.insert(key, now);
+        None
+    }
+
+    /// The plugin that registered a command, if any.
+    pub fn command_plugin(&self, name: &str) -> Option<&'static str> {
+        self.plugins.get(name).copied()
+    }
+
     /// Get a list of all registered command names.
     pub fn command_names(&self) -> Vec<String> {
         self.commands.keys().cloned().collect()
     }
 
+    /// Whether a name belongs to a registered command or one of its aliases.
+    pub fn is_reserved(&self, name: &str) -> bool {
+        name == "simulate" || self.commands.contains_key(name) || self.aliases.contains_key(name)
+    }
+
+    /// The names and aliases of all registered commands, see [`CommandNamesKey`].
+    pub fn reserved_names(&self) -> HashSet<String> {
+        self.commands
+            .keys()
+            .chain(self.aliases.keys())
+            .cloned()
+            .chain(std::iter::once("simulate".to_string()))
+            .collect()
+    }
+
+    /// Give a message to the wizard waiting for its author's answer, see
+    /// [`Command::wizard`]. Returns whether it was an answer.
+    pub fn take_reply(&self, msg: &Message) -> bool {
+        self.replies.deliver(msg)
+    }
+
+    /// The answers commands are waiting for, so other handlers of a message can
+    /// leave answers alone.
+    pub fn replies(&self) -> Arc<Replies> {
+        self.replies.clone()
+    }
+
+    /// Whether a message invokes a command, including `simulate`, the guild's
+    /// aliases and script commands.
+    pub async fn is_command(&self, ctx: &Context, msg: &Message) -> bool {
+        let name = match self.match_prefix(&msg.content) {
+            Some((_, true, _)) => return true,
+            Some((Some(name), false, _)) => name,
+            _ => return false,
+        };
+        let name = match msg.guild_id {
+            Some(guild_id) if !self.is_reserved(&name) => {
+                match aliases::resolve(ctx, guild_id, &name).await {
+                    Some((expanded, _)) => expanded,
+                    None => name,
+                }
+            }
+            _ => name,
+        };
+        self.get_command(&name).is_some() || script_command(ctx, &name).await.is_some()
+    }
+
     /// Get the current command prefix.
     pub fn prefix(&self) -> &str {
         &self.prefix
@@ -181,3 +1080,93 @@ impl CommandHandler {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_prefix_splits_simulated_commands() {
+        let handler = CommandHandler::new().with_prefix("!");
+
+        let (name, dry_run, args) = handler.match_prefix("!Ban someone spam").unwrap();
+        assert_eq!(name.as_deref(), Some("ban"));
+        assert!(!dry_run);
+        assert_eq!(args.collect::<Vec<_>>(), ["someone", "spam"]);
+
+        let (name, dry_run, _) = handler.match_prefix("!simulate purge 10").unwrap();
+        assert_eq!(name.as_deref(), Some("purge"));
+        assert!(dry_run);
+
+        let (name, dry_run, _) = handler.match_prefix("!simulate").unwrap();
+        assert_eq!(name, None);
+        assert!(dry_run);
+
+        assert!(handler.match_prefix("!").is_none());
+        assert!(handler.match_prefix("ping").is_none());
+
+        let handler = handler.with_dev_prefix("?");
+        let (name, _, _) = handler.match_prefix("?ping").unwrap();
+        assert_eq!(name.as_deref(), Some("ping"));
+        assert!(handler.match_prefix("!ping").is_some());
+    }
+
+    #[test]
+    fn cooldown_is_per_user_and_command() {
+        let handler = CommandHandler::new().with_cooldown(Duration::from_secs(60));
+        let (alice, bob) = (UserId(1), UserId(2));
+
+        assert!(handler.check_cooldown(alice, "ping").is_none());
+        assert!(handler.check_cooldown(alice, "ping").is_some());
+        assert!(handler.check_cooldown(alice, "help").is_none());
+        assert!(handler.check_cooldown(bob, "ping").is_none());
+
+        let disabled = CommandHandler::new();
+        assert!(disabled.check_cooldown(alice, "ping").is_none());
+        assert!(disabled.check_cooldown(alice, "ping").is_none());
+    }
+
+    #[test]
+    fn plugin_commands_are_tagged() {
+        struct Echo(&'static str);
+
+        #[async_trait]
+        impl Command for Echo {
+            fn name(&self) -> &str {
+                self.0
+            }
+
+            async fn execute(&self, _ctx: CommandContext<'_>) -> CommandResult {
+                Ok(())
+            }
+        }
+
+        struct EchoPlugin;
+
+        impl Plugin for EchoPlugin {
+            fn name(&self) -> &'static str {
+                "echo"
+            }
+
+            fn description(&self) -> &'static str {
+                "Echoes"
+            }
+
+            fn register_commands(&self, handler: &mut CommandHandler) {
+                handler.register_command(Echo("echo"));
+            }
+        }
+
+        let mut handler = CommandHandler::new();
+        handler.register_plugin(&EchoPlugin);
+        handler.register_command(Echo("ping"));
+        assert_eq!(handler.command_plugin("echo"), Some("echo"));
+        assert_eq!(handler.command_plugin("ping"), None);
+    }
+
+    #[test]
+    fn correlation_ids_are_unique() {
+        assert_ne!(correlation_id(), correlation_id());
+        assert_eq!(correlation_id().len(), 10);
+    }
+}
@@ -1,11 +1,13 @@
 //! Extended context with additional functionality.
 
-use serenity::builder::{CreateEmbed, CreateMessage};
 use serenity::model::channel::{Channel, Message};
 use serenity::model::id::ChannelId;
 use serenity::prelude::*;
 use std::fmt::Display;
 
+use crate::utils::helpers::{apply_mentions, mention_policy};
+use crate::utils::rest;
+
 /// Extended context for the bot with additional helper methods.
 pub struct BotContext<'a> {
     /// The underlying Serenity context.
@@ -24,7 +26,15 @@ impl<'a> BotContext<'a> {
         msg: &Message,
         content: impl Display,
     ) -> Result<Message, SerenityError> {
-        msg.channel_id.say(&self.ctx.http, content).await
+        let policy = &mention_policy(self.ctx).await;
+        let content = &content;
+        rest::call(self.ctx, "send_message", || {
+            msg.channel_id.send_message(&self.ctx.http, move |m| {
+                m.content(content)
+                    .allowed_mentions(|am| apply_mentions(am, policy, &[]))
+            })
+        })
+        .await
     }
 
     /// Sends a simple embed message to the specified channel.
@@ -35,8 +45,11 @@ impl<'a> BotContext<'a> {
         description: impl ToString + Display,
         color: Option<u32>,
     ) -> Result<Message, SerenityError> {
-        channel_id
-            .send_message(&self.ctx.http, |m| {
+        let policy = &mention_policy(self.ctx).await;
+        let (title, description) = (&title, &description);
+        rest::call(self.ctx, "send_message", || {
+            channel_id.send_message(&self.ctx.http, move |m| {
+                m.allowed_mentions(|am| apply_mentions(am, policy, &[]));
                 m.embed(|e| {
                     e.title(title).description(description);
 
@@ -47,12 +60,13 @@ impl<'a> BotContext<'a> {
                     e
                 })
             })
-            .await
+        })
+        .await
     }
 
     /// Gets a channel by ID from the cache or API.
     pub async fn get_channel(&self, channel_id: ChannelId) -> Result<Channel, SerenityError> {
-        channel_id.to_channel(&self.ctx).await
+        rest::call(self.ctx, "get_channel", || channel_id.to_channel(self.ctx)).await
     }
 
     /// Gets the name of the bot.
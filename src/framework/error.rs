@@ -0,0 +1,194 @@
+//! The error type shared by commands, handlers and helpers.
+
+use serenity::http::error::Error as HttpError;
+use serenity::model::channel::Message;
+use serenity::model::error::Error as ModelError;
+use serenity::model::permissions::Permissions;
+use serenity::prelude::*;
+use std::io;
+use std::time::Duration;
+use thiserror::Error;
+use tracing::{debug, error};
+
+use crate::storage::health;
+use crate::utils::duration::{format_compact, DurationError};
+use crate::utils::helpers::send_error;
+use crate::utils::rest;
+
+/// Any error that can happen while running a command.
+#[derive(Debug, Error)]
+pub enum KurumiError {
+    /// The command was used incorrectly. The message is shown to the user as is.
+    #[error("{0}")]
+    Usage(String),
+    /// The invoking member is missing permissions.
+    #[error("missing permissions: {0}")]
+    MissingPermissions(Permissions),
+    /// The bot is missing permissions.
+    #[error("bot is missing permissions: {0}")]
+    BotMissingPermissions(Permissions),
+    /// A Discord API or gateway error.
+    #[error("Discord error: {0}")]
+    Discord(#[from] serenity::Error),
+    /// An error talking to a third-party HTTP service, like the paste service.
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+    /// An error reading or writing persisted data.
+    #[error("storage error: {0}")]
+    Storage(#[from] io::Error),
+    /// The command ran longer than its timeout and was stopped.
+    #[error("timed out after {}", format_compact(*.0))]
+    Timeout(Duration),
+    /// A bug or misconfiguration in the bot itself.
+    #[error("{0}")]
+    Framework(String),
+}
+
+impl KurumiError {
+    /// Create a usage error shown to the user as is.
+    pub fn usage(message: impl Into<String>) -> Self {
+        Self::Usage(message.into())
+    }
+
+    /// Whether the error was caused by the user rather than the bot.
+    ///
+    /// User errors are expected, so they are only logged at debug level.
+    pub fn is_user_error(&self) -> bool {
+        matches!(self, Self::Usage(_) | Self::MissingPermissions(_))
+    }
+
+    /// A message that is safe to show to the user.
+    ///
+    /// Internal details like URLs, paths and API responses are left out; they are logged
+    /// instead.
+    pub fn user_message(&self) -> String {
+        match self {
+            Self::Usage(message) => message.clone(),
+            Self::MissingPermissions(permissions) => {
+                format!("You need the following permissions: {}", permissions)
+            }
+            Self::BotMissingPermissions(permissions) => {
+                format!("I need the following permissions: {}", permissions)
+            }
+            Self::Discord(serenity::Error::Model(ModelError::InvalidPermissions(permissions))) => {
+                format!("I need the following permissions: {}", permissions)
+            }
+            Self::Discord(serenity::Error::Http(e)) => match e.as_ref() {
+                HttpError::UnsuccessfulRequest(response) => match response.status_code.as_u16() {
+                    403 => "I don't have permission to do that.".to_string(),
+                    404 => "That no longer exists on Discord.".to_string(),
+                    status if status >= 500 => {
+                        "Discord is having trouble right now. Try again later.".to_string()
+                    }
+                    _ => "Discord rejected that request.".to_string(),
+                },
+                _ => "Couldn't reach Discord. Try again later.".to_string(),
+            },
+            Self::Discord(serenity::Error::Other(rest::TIMED_OUT | rest::UNAVAILABLE)) => {
+                "Discord is having trouble right now. Try again later.".to_string()
+            }
+            Self::Http(_) => "An external service didn't respond. Try again later.".to_string(),
+            Self::Storage(e) if health::is_unavailable(e) => {
+                "Saving is paused while the database is unreachable, so this can't be done right now. Try again in a few minutes.".to_string()
+            }
+            Self::Timeout(timeout) => format!(
+                "This command took longer than {} and was stopped.",
+                format_compact(*timeout)
+            ),
+            Self::Discord(_) | Self::Storage(_) | Self::Framework(_) => {
+                "Something went wrong while running this command.".to_string()
+            }
+        }
+    }
+}
+
+impl From<DurationError> for KurumiError {
+    fn from(e: DurationError) -> Self {
+        Self::Usage(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for KurumiError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Storage(e.into())
+    }
+}
+
+impl From<String> for KurumiError {
+    fn from(message: String) -> Self {
+        Self::Framework(message)
+    }
+}
+
+impl From<&str> for KurumiError {
+    fn from(message: &str) -> Self {
+        Self::Framework(message.to_string())
+    }
+}
+
+/// Log a failed command and tell the user what went wrong.
+///
+/// Errors caused by the bot include the correlation ID so owners can find the log trace.
+pub async fn report(
+    ctx: &Context,
+    msg: &Message,
+    command_name: &str,
+    err: &KurumiError,
+    correlation_id: &str,
+) -> Result<(), KurumiError> {
+    let message = if err.is_user_error() {
+        debug!("Command {} rejected: {}", command_name, err);
+        err.user_message()
+    } else {
+        error!("Command {} failed with error: {:?}", command_name, err);
+        format!("{}\nReference: `{}`", err.user_message(), correlation_id)
+    };
+
+    send_error(ctx, msg, message).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_errors_are_shown_as_is() {
+        let err = KurumiError::usage("Expected a user mention.");
+        assert!(err.is_user_error());
+        assert_eq!(err.user_message(), "Expected a user mention.");
+
+        let err = KurumiError::from(DurationError::Empty);
+        assert!(err.is_user_error());
+        assert_eq!(err.user_message(), "No duration given.");
+    }
+
+    #[test]
+    fn internal_errors_hide_details() {
+        let err = KurumiError::from(io::Error::other("/data/notes.json"));
+        assert!(!err.is_user_error());
+        assert!(!err.user_message().contains("notes.json"));
+
+        let err = KurumiError::from("token missing from state");
+        assert!(!err.user_message().contains("token"));
+    }
+
+    #[test]
+    fn bot_permission_errors_name_the_permissions() {
+        let err = KurumiError::Discord(serenity::Error::Model(ModelError::InvalidPermissions(
+            Permissions::BAN_MEMBERS,
+        )));
+        assert!(!err.is_user_error());
+        assert!(err.user_message().contains("Ban Members"));
+    }
+
+    #[test]
+    fn timeouts_say_how_long_the_command_ran() {
+        let err = KurumiError::Timeout(Duration::from_secs(90));
+        assert!(!err.is_user_error());
+        assert_eq!(
+            err.user_message(),
+            "This command took longer than 1m30s and was stopped."
+        );
+    }
+}
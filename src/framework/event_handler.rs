@@ -1,12 +1,34 @@
 //! Event dispatching system for handling Discord events.
 
 use async_trait::async_trait;
+use serenity::client::bridge::gateway::event::ShardStageUpdateEvent;
+use serenity::model::event::Event as GatewayEvent;
 use serenity::model::gateway::Ready;
 use serenity::model::prelude::*;
 use serenity::prelude::*;
 use std::collections::HashMap;
-use std::sync::Arc;
-use tracing::{debug, error};
+use std::future::Future;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, RwLock};
+use tokio::sync::Semaphore;
+use tokio::task::JoinError;
+use tracing::{debug, error, field, info_span, warn, Instrument, Span};
+
+use super::middleware::{Event, Middleware, Propagation};
+use super::plugin::{Modules, Plugin};
+use crate::utils::constants::{DEFAULT_HANDLER_PANIC_THRESHOLD, DEFAULT_MAX_IN_FLIGHT_HANDLERS};
+use crate::utils::dms::{dm_service, DmOutcome};
+use crate::utils::helpers::{apply_mentions, mention_policy, BotConfigKey};
+
+/// How to run the handlers of an event that passed the middleware chain.
+struct Dispatch {
+    /// Plugins turned off where the event happened, whose handlers are skipped.
+    mask: u64,
+    /// Span the handlers run in, carrying the event's shard and guild for the logs.
+    span: Span,
+    /// Context for alerting owners about panicking handlers.
+    ctx: Context,
+}
 
 /// A trait for event handlers.
 #[async_trait]
@@ -14,111 +36,586 @@ pub trait EventHandler: Send + Sync {
     /// The event type this handler responds to.
     fn event_type(&self) -> &'static str;
 
+    /// Ordering among handlers for the same event. Higher priorities run first.
+    fn priority(&self) -> i32 {
+        0
+    }
+
     /// Handle the ready event.
     async fn on_ready(&self, _ctx: Context, _ready: &Ready) {}
 
     /// Handle message creation.
     async fn on_message(&self, _ctx: Context, _msg: &Message) {}
 
+    /// Handle message edits.
+    async fn on_message_update(&self, _ctx: Context, _event: &MessageUpdateEvent) {}
+
+    /// Handle message deletion. Bulk deletes are dispatched once per message.
+    async fn on_message_delete(
+        &self,
+        _ctx: Context,
+        _channel_id: ChannelId,
+        _message_id: MessageId,
+        _guild_id: Option<GuildId>,
+    ) {
+    }
+
     /// Handle reaction addition.
     async fn on_reaction_add(&self, _ctx: Context, _reaction: &Reaction) {}
 
     /// Handle guild member join.
     async fn on_guild_member_add(&self, _ctx: Context, _guild_id: GuildId, _member: &Member) {}
 
+    /// Handle a member leaving a guild, or being kicked or banned.
+    ///
+    /// `member` is the member as cached before they left, if the cache had them.
+    async fn on_guild_member_remove(
+        &self,
+        _ctx: Context,
+        _guild_id: GuildId,
+        _user: &User,
+        _member: Option<&Member>,
+    ) {
+    }
+
+    /// Handle guild member updates, such as role or timeout changes.
+    async fn on_guild_member_update(&self, _ctx: Context, _old: Option<&Member>, _new: &Member) {}
+
+    /// Handle a user being banned from a guild.
+    async fn on_guild_ban_add(&self, _ctx: Context, _guild_id: GuildId, _user: &User) {}
+
+    /// Handle a user being unbanned from a guild.
+    async fn on_guild_ban_remove(&self, _ctx: Context, _guild_id: GuildId, _user: &User) {}
+
+    /// Handle a guild becoming available at startup, or the bot joining one when
+    /// `is_new` is set.
+    async fn on_guild_create(&self, _ctx: Context, _guild: &Guild, _is_new: bool) {}
+
+    /// Handle a message being pinned or unpinned in a channel.
+    async fn on_channel_pins_update(&self, _ctx: Context, _event: &ChannelPinsUpdateEvent) {}
+
+    /// Handle a member joining, leaving or moving between voice channels.
+    ///
+    /// `old` is their state before, if the cache had it.
+    async fn on_voice_state_update(
+        &self,
+        _ctx: Context,
+        _old: Option<&VoiceState>,
+        _new: &VoiceState,
+    ) {
+    }
+
     /// Handle an interaction.
     async fn on_interaction(&self, _ctx: Context, _interaction: &Interaction) {}
 
+    /// Handle a shard changing its connection stage, such as disconnecting or resuming.
+    async fn on_shard_stage_update(&self, _ctx: Context, _event: &ShardStageUpdateEvent) {}
+
+    /// Handle a shard resuming its gateway session. The shard is `ctx.shard_id`.
+    async fn on_resume(&self, _ctx: Context, _event: &ResumedEvent) {}
+
+    /// Handle any gateway event, before it is parsed into the typed events above.
+    ///
+    /// Register with the `raw` event type to react to events that don't have a
+    /// typed method yet.
+    async fn on_raw_event(&self, _ctx: Context, _event: &GatewayEvent) {}
+
     // Add more event handlers as needed
 }
 
+/// Event handlers that can be rebuilt after a panic, since a panic partway through
+/// can leave their in-memory state inconsistent.
+pub trait Recover: EventHandler + Sized + 'static {
+    /// Build a fresh handler with the same settings, dropping any state.
+    fn recover(&self) -> Self;
+}
+
+/// A middleware and the plugin that registered it, if any.
+type Tagged<T> = (Option<&'static str>, Arc<T>);
+
+/// Builds a replacement for a handler that panicked.
+type Rebuild = Box<dyn Fn() -> Arc<dyn EventHandler> + Send + Sync>;
+
+/// A registered handler and what is known about its panics.
+struct Supervised {
+    /// The handler's type name, for logs and alerts.
+    name: &'static str,
+    /// The plugin that registered the handler, if any.
+    plugin: Option<&'static str>,
+    priority: i32,
+    /// The handler, replaced when it is rebuilt.
+    handler: RwLock<Arc<dyn EventHandler>>,
+    /// Set for handlers that implement [`Recover`].
+    rebuild: Option<Rebuild>,
+    /// How many times the handler has panicked.
+    panics: AtomicU32,
+}
+
+impl Supervised {
+    /// The current handler instance.
+    fn current(&self) -> Arc<dyn EventHandler> {
+        self.handler
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Count a panic and rebuild the handler if it can be. Returns the panic count.
+    fn record_panic(&self) -> u32 {
+        let panics = self.panics.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Some(rebuild) = &self.rebuild {
+            *self.handler.write().unwrap_or_else(|e| e.into_inner()) = rebuild();
+            warn!("Rebuilt {} after it panicked", self.name);
+        }
+        panics
+    }
+}
+
 /// Dispatches events to registered handlers.
 pub struct EventDispatcher {
-    /// Maps event types to their handlers.
-    handlers: HashMap<&'static str, Vec<Arc<dyn EventHandler>>>,
+    /// Maps event types to their handlers, ordered by priority.
+    handlers: HashMap<&'static str, Vec<Arc<Supervised>>>,
+    /// Middleware run before handlers and the plugin that registered them, ordered
+    /// by priority.
+    middleware: Vec<Tagged<dyn Middleware>>,
+    /// The plugin whose handlers are being registered, see [`Self::register_plugin`].
+    registering: Option<&'static str>,
+    /// Which guilds turned which plugins off, see [`Self::set_modules`].
+    modules: Option<Arc<Modules>>,
+    /// Dispatch policies by event type. Unlisted event types run sequentially.
+    policies: HashMap<&'static str, DispatchPolicy>,
+    /// Per-event-type limits for concurrent dispatch.
+    limits: HashMap<&'static str, Arc<Semaphore>>,
+    /// Limits the number of handler tasks in flight across all events.
+    in_flight: Arc<Semaphore>,
+    /// Panics after which owners are alerted about a handler.
+    panic_threshold: u32,
+}
+
+/// How the handlers for an event type are run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DispatchPolicy {
+    /// Run handlers one after another in priority order.
+    #[default]
+    Sequential,
+    /// Run all handlers at once, with at most `limit` running for this event type
+    /// across all events.
+    Concurrent { limit: usize },
+    /// Start handlers and return without waiting for them.
+    FireAndForget,
+}
+
+/// Log how a handler task ended, and count panics against the handler.
+///
+/// Owners are alerted once when a handler reaches the panic threshold.
+fn supervise(
+    event_type: &str,
+    handler: &Supervised,
+    result: Result<(), JoinError>,
+    ctx: &Context,
+    threshold: u32,
+) {
+    let e = match result {
+        Ok(_) => {
+            debug!("{} event handler completed", event_type);
+            return;
+        }
+        Err(e) => e,
+    };
+    error!(
+        "{} event handler {} panicked: {}",
+        event_type, handler.name, e
+    );
+    if !e.is_panic() {
+        return;
+    }
+
+    let panics = handler.record_panic();
+    if panics == threshold {
+        let ctx = ctx.clone();
+        let alert = format!(
+            "⚠️ The `{}` handler for `{}` events has panicked {} times.{}",
+            handler.name,
+            event_type,
+            panics,
+            if handler.rebuild.is_some() {
+                " It was rebuilt after each panic."
+            } else {
+                ""
+            }
+        );
+        tokio::spawn(async move { alert_owners(&ctx, &alert).await });
+    }
+}
+
+/// Send a message to every bot owner.
+async fn alert_owners(ctx: &Context, alert: &str) {
+    let owners = {
+        let data = ctx.data.read().await;
+        data.get::<BotConfigKey>()
+            .map(|config| config.owners.clone())
+            .unwrap_or_default()
+    };
+
+    let dms = match dm_service(ctx).await {
+        Some(dms) => dms,
+        None => {
+            warn!("Couldn't alert owners about a handler panic: no DM service");
+            return;
+        }
+    };
+    let policy = &mention_policy(ctx).await;
+    for owner in owners {
+        match dms
+            .send(&ctx.http, UserId(owner), None, |m| {
+                m.content(alert)
+                    .allowed_mentions(|am| apply_mentions(am, policy, &[]))
+            })
+            .await
+        {
+            Ok(DmOutcome::Sent) => {}
+            Ok(outcome) => warn!(
+                "Couldn't alert owner {} about a handler panic: {:?}",
+                owner, outcome
+            ),
+            Err(e) => warn!(
+                "Failed to alert owner {} about a handler panic: {}",
+                owner, e
+            ),
+        }
+    }
+}
+
+impl Default for EventDispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl EventDispatcher {
     /// Creates a new EventDispatcher.
     pub fn new() -> Self {
+        Self::with_max_in_flight(DEFAULT_MAX_IN_FLIGHT_HANDLERS)
+    }
+
+    /// Creates a new EventDispatcher that runs at most `max` handler tasks at once.
+    pub fn with_max_in_flight(max: usize) -> Self {
         Self {
             handlers: HashMap::new(),
+            middleware: Vec::new(),
+            registering: None,
+            modules: None,
+            policies: HashMap::new(),
+            limits: HashMap::new(),
+            in_flight: Arc::new(Semaphore::new(max.max(1))),
+            panic_threshold: DEFAULT_HANDLER_PANIC_THRESHOLD,
         }
     }
 
+    /// Sets how many times a handler may panic before owners are alerted.
+    pub fn set_panic_threshold(&mut self, threshold: u32) {
+        self.panic_threshold = threshold.max(1);
+    }
+
     /// Registers an event handler.
-    pub fn register_handler(&mut self, handler: impl EventHandler + 'static) {
+    pub fn register_handler<H: EventHandler + 'static>(&mut self, handler: H) {
+        self.insert_handler(std::any::type_name::<H>(), Arc::new(handler), None);
+    }
+
+    /// Registers an event handler that is rebuilt with [`Recover::recover`] each time
+    /// it panics.
+    pub fn register_recoverable<H: Recover>(&mut self, handler: H) {
         let handler = Arc::new(handler);
+        let template = handler.clone();
+        let rebuild: Rebuild = Box::new(move || Arc::new(template.recover()));
+        self.insert_handler(std::any::type_name::<H>(), handler, Some(rebuild));
+    }
+
+    /// Adds a handler to its event type's list.
+    fn insert_handler(
+        &mut self,
+        name: &'static str,
+        handler: Arc<dyn EventHandler>,
+        rebuild: Option<Rebuild>,
+    ) {
         let event_type = handler.event_type();
+        let priority = handler.priority();
+        let supervised = Arc::new(Supervised {
+            name: name.rsplit("::").next().unwrap_or(name),
+            plugin: self.registering,
+            priority,
+            handler: RwLock::new(handler),
+            rebuild,
+            panics: AtomicU32::new(0),
+        });
 
-        self.handlers
-            .entry(event_type)
-            .or_insert_with(Vec::new)
-            .push(handler);
+        // Keep handlers sorted by priority, preserving registration order for ties
+        let handlers = self.handlers.entry(event_type).or_default();
+        let index = handlers.partition_point(|h| h.priority >= priority);
+        handlers.insert(index, supervised);
 
         debug!("Registered handler for event type: {}", event_type);
     }
 
-    /// Dispatches the ready event to registered handlers.
-    pub async fn dispatch_ready(&self, ctx: Context, ready: &Ready) {
-        if let Some(handlers) = self.handlers.get("ready") {
-            for handler in handlers {
-                let handler_clone = handler.clone(); // Clone the Arc to move it into the task
-                let ctx_clone = ctx.clone();
-                let ready_owned = ready.clone(); // Clone to owned Ready
-
-                match tokio::spawn(
-                    async move { handler_clone.on_ready(ctx_clone, &ready_owned).await },
-                )
-                .await
-                {
-                    Ok(_) => debug!("Ready event handler completed"),
-                    Err(e) => error!("Ready event handler panicked: {}", e),
+    /// Registers a middleware.
+    pub fn register_middleware(&mut self, middleware: impl Middleware + 'static) {
+        let middleware = Arc::new(middleware);
+
+        let index = self
+            .middleware
+            .partition_point(|(_, m)| m.priority() >= middleware.priority());
+        debug!("Registered middleware: {}", middleware.name());
+        self.middleware
+            .insert(index, (self.registering, middleware));
+    }
+
+    /// Registers a plugin's handlers and middleware, so they don't run in guilds that
+    /// turned it off.
+    pub fn register_plugin(&mut self, plugin: &dyn Plugin) {
+        self.registering = Some(plugin.name());
+        plugin.register_events(self);
+        self.registering = None;
+    }
+
+    /// Sets the plugin modules checked before running plugin handlers and middleware.
+    ///
+    /// Without them, every plugin's handlers run everywhere.
+    pub fn set_modules(&mut self, modules: Arc<Modules>) {
+        self.modules = Some(modules);
+    }
+
+    /// The gateway intents needed to receive the events that have handlers.
+    pub fn intents(&self) -> GatewayIntents {
+        self.handlers
+            .keys()
+            .fold(GatewayIntents::empty(), |intents, event_type| {
+                intents | super::intents::for_event(event_type)
+            })
+    }
+
+    /// Whether something registered by `plugin` should run under a guild's mask.
+    fn allows(&self, mask: u64, plugin: Option<&str>) -> bool {
+        self.modules
+            .as_ref()
+            .is_none_or(|modules| modules.allows(mask, plugin))
+    }
+
+    /// Sets how handlers for an event type are run.
+    pub fn set_policy(&mut self, event_type: &'static str, policy: DispatchPolicy) {
+        match policy {
+            DispatchPolicy::Concurrent { limit } => {
+                self.limits
+                    .insert(event_type, Arc::new(Semaphore::new(limit.max(1))));
+            }
+            _ => {
+                self.limits.remove(event_type);
+            }
+        }
+
+        debug!("Dispatch policy for {}: {:?}", event_type, policy);
+        self.policies.insert(event_type, policy);
+    }
+
+    /// Runs the handlers for an event type according to its dispatch policy.
+    ///
+    /// `call` builds the future that runs one handler. Every handler task holds a
+    /// permit from its event type's limit, if it has one, and from the global
+    /// limit, so a flood of events waits here instead of spawning unbounded tasks.
+    async fn run_handlers<F, Fut>(&self, event_type: &'static str, dispatch: Dispatch, call: F)
+    where
+        F: Fn(Arc<dyn EventHandler>) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let handlers = match self.handlers.get(event_type) {
+            Some(handlers) => handlers,
+            None => return,
+        };
+        let policy = self.policies.get(event_type).copied().unwrap_or_default();
+
+        let threshold = self.panic_threshold;
+        let mut tasks = Vec::new();
+        for supervised in handlers {
+            if !self.allows(dispatch.mask, supervised.plugin) {
+                continue;
+            }
+            // The event type's own limit comes first, so handlers waiting on it don't
+            // sit on global permits that other event types could use
+            let limit = match self.limits.get(event_type).cloned() {
+                Some(limit) => match limit.acquire_owned().await {
+                    Ok(limit) => Some(limit),
+                    Err(_) => return,
+                },
+                None => None,
+            };
+            let permit = match self.in_flight.clone().acquire_owned().await {
+                Ok(permit) => permit,
+                Err(_) => return,
+            };
+            let future = call(supervised.current());
+
+            let task = tokio::spawn(
+                async move {
+                    let _permit = permit;
+                    let _limit = limit;
+                    future.await
                 }
+                .instrument(dispatch.span.clone()),
+            );
+
+            match policy {
+                DispatchPolicy::Sequential => {
+                    supervise(event_type, supervised, task.await, &dispatch.ctx, threshold)
+                }
+                DispatchPolicy::Concurrent { .. } => tasks.push((supervised, task)),
+                DispatchPolicy::FireAndForget => {
+                    let supervised = supervised.clone();
+                    let ctx = dispatch.ctx.clone();
+                    tokio::spawn(async move {
+                        supervise(event_type, &supervised, task.await, &ctx, threshold)
+                    });
+                }
+            }
+        }
+
+        for (supervised, task) in tasks {
+            supervise(event_type, supervised, task.await, &dispatch.ctx, threshold);
+        }
+    }
+
+    /// Runs an event through the middleware chain.
+    ///
+    /// Returns how to run the event's handlers, or `None` if a middleware stopped the
+    /// event.
+    async fn run_middleware(&self, ctx: &Context, event: Event<'_>) -> Option<Dispatch> {
+        let guild_id = event.guild_id();
+        let mask = self
+            .modules
+            .as_ref()
+            .map_or(0, |modules| modules.mask(guild_id));
+        let span = info_span!(
+            "event",
+            event = event.event_type(),
+            shard = ctx.shard_id,
+            guild_id = field::Empty,
+        );
+        if let Some(guild_id) = guild_id {
+            span.record("guild_id", guild_id.0);
+        }
+
+        for (plugin, middleware) in &self.middleware {
+            if !self.allows(mask, *plugin) {
+                continue;
+            }
+            let propagation = middleware
+                .handle(ctx, &event)
+                .instrument(span.clone())
+                .await;
+            if propagation == Propagation::Stop {
+                debug!(
+                    parent: &span,
+                    "Middleware {} stopped {} event",
+                    middleware.name(),
+                    event.event_type()
+                );
+                return None;
             }
         }
+
+        Some(Dispatch {
+            mask,
+            span,
+            ctx: ctx.clone(),
+        })
+    }
+
+    /// Dispatches the ready event to registered handlers.
+    pub async fn dispatch_ready(&self, ctx: Context, ready: &Ready) {
+        let dispatch = match self.run_middleware(&ctx, Event::Ready(ready)).await {
+            Some(dispatch) => dispatch,
+            None => return,
+        };
+
+        self.run_handlers("ready", dispatch, |handler| {
+            let ctx = ctx.clone();
+            let ready = ready.clone();
+            async move { handler.on_ready(ctx, &ready).await }
+        })
+        .await;
     }
 
     /// Dispatches message events to registered handlers.
     pub async fn dispatch_message(&self, ctx: Context, msg: &Message) {
-        if let Some(handlers) = self.handlers.get("message") {
-            for handler in handlers {
-                let handler_clone = handler.clone();
-                let ctx_clone = ctx.clone();
-                let msg_clone = msg.clone();
-
-                match tokio::spawn(
-                    async move { handler_clone.on_message(ctx_clone, &msg_clone).await },
-                )
-                .await
-                {
-                    Ok(_) => debug!("Message event handler completed"),
-                    Err(e) => error!("Message event handler panicked: {}", e),
-                }
+        let dispatch = match self.run_middleware(&ctx, Event::Message(msg)).await {
+            Some(dispatch) => dispatch,
+            None => return,
+        };
+
+        self.run_handlers("message", dispatch, |handler| {
+            let ctx = ctx.clone();
+            let msg = msg.clone();
+            async move { handler.on_message(ctx, &msg).await }
+        })
+        .await;
+    }
+
+    /// Dispatches message update events to registered handlers.
+    pub async fn dispatch_message_update(&self, ctx: Context, event: &MessageUpdateEvent) {
+        let dispatch = match self.run_middleware(&ctx, Event::MessageUpdate(event)).await {
+            Some(dispatch) => dispatch,
+            None => return,
+        };
+
+        self.run_handlers("message_update", dispatch, |handler| {
+            let ctx = ctx.clone();
+            let event = event.clone();
+            async move { handler.on_message_update(ctx, &event).await }
+        })
+        .await;
+    }
+
+    /// Dispatches message delete events to registered handlers.
+    pub async fn dispatch_message_delete(
+        &self,
+        ctx: Context,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        guild_id: Option<GuildId>,
+    ) {
+        let dispatch = match self
+            .run_middleware(&ctx, Event::MessageDelete(channel_id, message_id, guild_id))
+            .await
+        {
+            Some(dispatch) => dispatch,
+            None => return,
+        };
+
+        self.run_handlers("message_delete", dispatch, |handler| {
+            let ctx = ctx.clone();
+            async move {
+                handler
+                    .on_message_delete(ctx, channel_id, message_id, guild_id)
+                    .await
             }
-        }
+        })
+        .await;
     }
 
     /// Dispatches reaction events to registered handlers.
     pub async fn dispatch_reaction_add(&self, ctx: Context, reaction: &Reaction) {
-        if let Some(handlers) = self.handlers.get("reaction_add") {
-            for handler in handlers {
-                let handler_clone = handler.clone();
-                let ctx_clone = ctx.clone();
-                let reaction_clone = reaction.clone();
-
-                match tokio::spawn(async move {
-                    handler_clone
-                        .on_reaction_add(ctx_clone, &reaction_clone)
-                        .await
-                })
-                .await
-                {
-                    Ok(_) => debug!("Reaction add event handler completed"),
-                    Err(e) => error!("Reaction add event handler panicked: {}", e),
-                }
-            }
-        }
+        let dispatch = match self
+            .run_middleware(&ctx, Event::ReactionAdd(reaction))
+            .await
+        {
+            Some(dispatch) => dispatch,
+            None => return,
+        };
+
+        self.run_handlers("reaction_add", dispatch, |handler| {
+            let ctx = ctx.clone();
+            let reaction = reaction.clone();
+            async move { handler.on_reaction_add(ctx, &reaction).await }
+        })
+        .await;
     }
 
     /// Dispatches guild member add events to registered handlers.
@@ -128,48 +625,302 @@ impl EventDispatcher {
         guild_id: GuildId,
         member: &Member,
     ) {
-        if let Some(handlers) = self.handlers.get("guild_member_add") {
-            for handler in handlers {
-                let handler_clone = handler.clone();
-                let ctx_clone = ctx.clone();
-                let guild_id_clone = guild_id;
-                let member_clone = member.clone();
-
-                match tokio::spawn(async move {
-                    handler_clone
-                        .on_guild_member_add(ctx_clone, guild_id_clone, &member_clone)
-                        .await
-                })
-                .await
-                {
-                    Ok(_) => debug!("Guild member add event handler completed"),
-                    Err(e) => error!("Guild member add event handler panicked: {}", e),
-                }
+        let dispatch = match self
+            .run_middleware(&ctx, Event::GuildMemberAdd(guild_id, member))
+            .await
+        {
+            Some(dispatch) => dispatch,
+            None => return,
+        };
+
+        self.run_handlers("guild_member_add", dispatch, |handler| {
+            let ctx = ctx.clone();
+            let member = member.clone();
+            async move { handler.on_guild_member_add(ctx, guild_id, &member).await }
+        })
+        .await;
+    }
+
+    /// Dispatches guild member removal events to registered handlers.
+    pub async fn dispatch_guild_member_remove(
+        &self,
+        ctx: Context,
+        guild_id: GuildId,
+        user: &User,
+        member: Option<&Member>,
+    ) {
+        let dispatch = match self
+            .run_middleware(&ctx, Event::GuildMemberRemove(guild_id, user, member))
+            .await
+        {
+            Some(dispatch) => dispatch,
+            None => return,
+        };
+
+        self.run_handlers("guild_member_remove", dispatch, |handler| {
+            let ctx = ctx.clone();
+            let user = user.clone();
+            let member = member.cloned();
+            async move {
+                handler
+                    .on_guild_member_remove(ctx, guild_id, &user, member.as_ref())
+                    .await
             }
-        }
+        })
+        .await;
+    }
+
+    /// Dispatches guild member update events to registered handlers.
+    pub async fn dispatch_guild_member_update(
+        &self,
+        ctx: Context,
+        old: Option<&Member>,
+        new: &Member,
+    ) {
+        let dispatch = match self
+            .run_middleware(&ctx, Event::GuildMemberUpdate(old, new))
+            .await
+        {
+            Some(dispatch) => dispatch,
+            None => return,
+        };
+
+        self.run_handlers("guild_member_update", dispatch, |handler| {
+            let ctx = ctx.clone();
+            let old = old.cloned();
+            let new = new.clone();
+            async move {
+                handler
+                    .on_guild_member_update(ctx, old.as_ref(), &new)
+                    .await
+            }
+        })
+        .await;
+    }
+
+    /// Dispatches guild ban add events to registered handlers.
+    pub async fn dispatch_guild_ban_add(&self, ctx: Context, guild_id: GuildId, user: &User) {
+        let dispatch = match self
+            .run_middleware(&ctx, Event::GuildBanAdd(guild_id, user))
+            .await
+        {
+            Some(dispatch) => dispatch,
+            None => return,
+        };
+
+        self.run_handlers("guild_ban_add", dispatch, |handler| {
+            let ctx = ctx.clone();
+            let user = user.clone();
+            async move { handler.on_guild_ban_add(ctx, guild_id, &user).await }
+        })
+        .await;
+    }
+
+    /// Dispatches guild ban remove events to registered handlers.
+    pub async fn dispatch_guild_ban_remove(&self, ctx: Context, guild_id: GuildId, user: &User) {
+        let dispatch = match self
+            .run_middleware(&ctx, Event::GuildBanRemove(guild_id, user))
+            .await
+        {
+            Some(dispatch) => dispatch,
+            None => return,
+        };
+
+        self.run_handlers("guild_ban_remove", dispatch, |handler| {
+            let ctx = ctx.clone();
+            let user = user.clone();
+            async move { handler.on_guild_ban_remove(ctx, guild_id, &user).await }
+        })
+        .await;
+    }
+
+    /// Dispatches guild create events to registered handlers.
+    pub async fn dispatch_guild_create(&self, ctx: Context, guild: &Guild, is_new: bool) {
+        let dispatch = match self
+            .run_middleware(&ctx, Event::GuildCreate(guild, is_new))
+            .await
+        {
+            Some(dispatch) => dispatch,
+            None => return,
+        };
+
+        self.run_handlers("guild_create", dispatch, |handler| {
+            let ctx = ctx.clone();
+            let guild = guild.clone();
+            async move { handler.on_guild_create(ctx, &guild, is_new).await }
+        })
+        .await;
     }
 
     /// Dispatches interaction events to registered handlers.
     pub async fn dispatch_interaction(&self, ctx: Context, interaction: &Interaction) {
-        if let Some(handlers) = self.handlers.get("interaction") {
-            for handler in handlers {
-                let handler_clone = handler.clone();
-                let ctx_clone = ctx.clone();
-                let interaction_clone = interaction.clone();
-
-                match tokio::spawn(async move {
-                    handler_clone
-                        .on_interaction(ctx_clone, &interaction_clone)
-                        .await
-                })
-                .await
-                {
-                    Ok(_) => debug!("Interaction event handler completed"),
-                    Err(e) => error!("Interaction event handler panicked: {}", e),
-                }
-            }
+        let dispatch = match self
+            .run_middleware(&ctx, Event::Interaction(interaction))
+            .await
+        {
+            Some(dispatch) => dispatch,
+            None => return,
+        };
+
+        self.run_handlers("interaction", dispatch, |handler| {
+            let ctx = ctx.clone();
+            let interaction = interaction.clone();
+            async move { handler.on_interaction(ctx, &interaction).await }
+        })
+        .await;
+    }
+
+    /// Dispatches pin changes to registered handlers.
+    pub async fn dispatch_channel_pins_update(&self, ctx: Context, event: &ChannelPinsUpdateEvent) {
+        let dispatch = match self
+            .run_middleware(&ctx, Event::ChannelPinsUpdate(event))
+            .await
+        {
+            Some(dispatch) => dispatch,
+            None => return,
+        };
+
+        self.run_handlers("channel_pins_update", dispatch, |handler| {
+            let ctx = ctx.clone();
+            let event = event.clone();
+            async move { handler.on_channel_pins_update(ctx, &event).await }
+        })
+        .await;
+    }
+
+    /// Dispatches voice state changes to registered handlers.
+    pub async fn dispatch_voice_state_update(
+        &self,
+        ctx: Context,
+        old: Option<&VoiceState>,
+        new: &VoiceState,
+    ) {
+        let dispatch = match self
+            .run_middleware(&ctx, Event::VoiceStateUpdate(old, new))
+            .await
+        {
+            Some(dispatch) => dispatch,
+            None => return,
+        };
+
+        self.run_handlers("voice_state_update", dispatch, |handler| {
+            let ctx = ctx.clone();
+            let old = old.cloned();
+            let new = new.clone();
+            async move { handler.on_voice_state_update(ctx, old.as_ref(), &new).await }
+        })
+        .await;
+    }
+
+    /// Dispatches shard stage updates to registered handlers.
+    pub async fn dispatch_shard_stage_update(&self, ctx: Context, event: &ShardStageUpdateEvent) {
+        let dispatch = match self
+            .run_middleware(&ctx, Event::ShardStageUpdate(event))
+            .await
+        {
+            Some(dispatch) => dispatch,
+            None => return,
+        };
+
+        self.run_handlers("shard_stage_update", dispatch, |handler| {
+            let ctx = ctx.clone();
+            let event = event.clone();
+            async move { handler.on_shard_stage_update(ctx, &event).await }
+        })
+        .await;
+    }
+
+    /// Dispatches session resumes to registered handlers.
+    pub async fn dispatch_resume(&self, ctx: Context, event: &ResumedEvent) {
+        let dispatch = match self.run_middleware(&ctx, Event::Resume(event)).await {
+            Some(dispatch) => dispatch,
+            None => return,
+        };
+
+        self.run_handlers("resume", dispatch, |handler| {
+            let ctx = ctx.clone();
+            let event = event.clone();
+            async move { handler.on_resume(ctx, &event).await }
+        })
+        .await;
+    }
+
+    /// Dispatches raw gateway events to registered handlers.
+    pub async fn dispatch_raw(&self, ctx: Context, event: &GatewayEvent) {
+        if !self.handlers.contains_key("raw") {
+            return;
         }
+
+        let dispatch = match self.run_middleware(&ctx, Event::Raw(event)).await {
+            Some(dispatch) => dispatch,
+            None => return,
+        };
+
+        self.run_handlers("raw", dispatch, |handler| {
+            let ctx = ctx.clone();
+            let event = event.clone();
+            async move { handler.on_raw_event(ctx, &event).await }
+        })
+        .await;
     }
 
     // Add more dispatch methods as needed
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{MockDiscord, TestMessage};
+    use std::sync::Mutex;
+
+    /// Panics until it has been rebuilt, recording which instance saw each message.
+    struct Flaky {
+        generation: u32,
+        seen: Arc<Mutex<Vec<u32>>>,
+    }
+
+    #[async_trait]
+    impl EventHandler for Flaky {
+        fn event_type(&self) -> &'static str {
+            "message"
+        }
+
+        async fn on_message(&self, _ctx: Context, _msg: &Message) {
+            self.seen.lock().unwrap().push(self.generation);
+            if self.generation == 0 {
+                panic!("corrupted state");
+            }
+        }
+    }
+
+    impl Recover for Flaky {
+        fn recover(&self) -> Self {
+            Self {
+                generation: self.generation + 1,
+                seen: self.seen.clone(),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn rebuilds_recoverable_handlers_after_a_panic() {
+        let discord = MockDiscord::start().await;
+        let ctx = discord.context();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+
+        let mut dispatcher = EventDispatcher::new();
+        dispatcher.register_recoverable(Flaky {
+            generation: 0,
+            seen: seen.clone(),
+        });
+
+        let msg = TestMessage::new("hello").build();
+        dispatcher.dispatch_message(ctx.clone(), &msg).await;
+        dispatcher.dispatch_message(ctx, &msg).await;
+
+        assert_eq!(*seen.lock().unwrap(), [0, 1]);
+        let handler = &dispatcher.handlers["message"][0];
+        assert_eq!(handler.panics.load(Ordering::Relaxed), 1);
+        assert_eq!(handler.name, "Flaky");
+    }
+}
@@ -0,0 +1,76 @@
+//! Commands written inside ordinary messages.
+//!
+//! Servers that turn on the `inline_commands` setting can run a command anywhere
+//! in a message by wrapping it in delimiters, like `what's {{calc 2^10}} again?`.
+//! Each one runs as if it had been sent on its own with the prefix, so the usual
+//! checks apply, and names that aren't commands are left alone.
+
+use crate::models::guild_config::InlineCommands;
+
+/// Most inline commands run for one message.
+pub const MAX_INLINE: usize = 3;
+
+/// Longest inline command, in characters.
+const MAX_LENGTH: usize = 200;
+
+/// The commands wrapped in the style's delimiters, in the order they appear.
+///
+/// Code is skipped, so examples in backticks don't run.
+pub fn find(content: &str, style: InlineCommands) -> Vec<&str> {
+    let mut found: Vec<(usize, &str)> = Vec::new();
+    for (open, close) in style.delimiters() {
+        let mut rest = content;
+        let mut offset = 0;
+        while let Some(start) = rest.find(open) {
+            let inner_start = start + open.len();
+            let length = match rest[inner_start..].find(close) {
+                Some(length) => length,
+                None => break,
+            };
+            let inner = rest[inner_start..inner_start + length].trim();
+            let position = offset + start;
+            if !inner.is_empty()
+                && inner.chars().count() <= MAX_LENGTH
+                && !inner.contains('\n')
+                && !in_code(content, position)
+            {
+                found.push((position, inner));
+            }
+            let next = inner_start + length + close.len();
+            offset += next;
+            rest = &rest[next..];
+        }
+    }
+    found.sort_by_key(|(position, _)| *position);
+    found
+        .into_iter()
+        .map(|(_, inner)| inner)
+        .take(MAX_INLINE)
+        .collect()
+}
+
+/// Whether a position in a message is inside inline code or a code block.
+fn in_code(content: &str, position: usize) -> bool {
+    content[..position].matches('`').count() % 2 == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_wrapped_commands() {
+        let content = "try [[wiki Rust]] or {{ roll 2d6 }} but not `{{ping}}` {{}}";
+        assert_eq!(
+            find(content, InlineCommands::Both),
+            ["wiki Rust", "roll 2d6"]
+        );
+        assert_eq!(find(content, InlineCommands::Braces), ["roll 2d6"]);
+        assert!(find(content, InlineCommands::Off).is_empty());
+        assert!(find("{{unclosed", InlineCommands::Braces).is_empty());
+        assert_eq!(
+            find("{{a}}{{b}}{{c}}{{d}}", InlineCommands::Braces).len(),
+            MAX_INLINE
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! Gateway intents derived from the registered handlers and plugins.
+//!
+//! The bot asks Discord only for the events something is listening to. Each event
+//! type maps to the intents that deliver it, and `[intents]` in the configuration
+//! can add or remove intents on top of that.
+
+use serenity::model::gateway::GatewayIntents;
+
+use crate::models::config::IntentsConfig;
+
+/// Intents every bot connects with, since the cache and permission checks rely on
+/// guild and channel data.
+pub const BASE: GatewayIntents = GatewayIntents::GUILDS;
+
+/// The intents that deliver an event type, see
+/// [`super::event_handler::EventHandler::event_type`].
+///
+/// Event types that arrive without any intent, such as `ready` and `interaction`,
+/// need none.
+pub fn for_event(event_type: &str) -> GatewayIntents {
+    match event_type {
+        "message" | "message_update" => {
+            GatewayIntents::GUILD_MESSAGES
+                | GatewayIntents::DIRECT_MESSAGES
+                | GatewayIntents::MESSAGE_CONTENT
+        }
+        "message_delete" => GatewayIntents::GUILD_MESSAGES | GatewayIntents::DIRECT_MESSAGES,
+        "reaction_add" => {
+            GatewayIntents::GUILD_MESSAGE_REACTIONS | GatewayIntents::DIRECT_MESSAGE_REACTIONS
+        }
+        "guild_member_add" | "guild_member_update" | "guild_member_remove" => {
+            GatewayIntents::GUILD_MEMBERS
+        }
+        "guild_ban_add" | "guild_ban_remove" => GatewayIntents::GUILD_BANS,
+        "channel_pins_update" => GatewayIntents::GUILDS | GatewayIntents::DIRECT_MESSAGES,
+        "voice_state_update" => GatewayIntents::GUILD_VOICE_STATES,
+        _ => GatewayIntents::empty(),
+    }
+}
+
+/// Parse an intent name, such as `guild_members` or `GUILD_MEMBERS`.
+pub fn parse(name: &str) -> Option<GatewayIntents> {
+    let intent = match name.trim().to_lowercase().as_str() {
+        "guilds" => GatewayIntents::GUILDS,
+        "guild_members" => GatewayIntents::GUILD_MEMBERS,
+        "guild_bans" => GatewayIntents::GUILD_BANS,
+        "guild_emojis_and_stickers" => GatewayIntents::GUILD_EMOJIS_AND_STICKERS,
+        "guild_integrations" => GatewayIntents::GUILD_INTEGRATIONS,
+        "guild_webhooks" => GatewayIntents::GUILD_WEBHOOKS,
+        "guild_invites" => GatewayIntents::GUILD_INVITES,
+        "guild_voice_states" => GatewayIntents::GUILD_VOICE_STATES,
+        "guild_presences" => GatewayIntents::GUILD_PRESENCES,
+        "guild_messages" => GatewayIntents::GUILD_MESSAGES,
+        "guild_message_reactions" => GatewayIntents::GUILD_MESSAGE_REACTIONS,
+        "guild_message_typing" => GatewayIntents::GUILD_MESSAGE_TYPING,
+        "direct_messages" => GatewayIntents::DIRECT_MESSAGES,
+        "direct_message_reactions" => GatewayIntents::DIRECT_MESSAGE_REACTIONS,
+        "direct_message_typing" => GatewayIntents::DIRECT_MESSAGE_TYPING,
+        "message_content" => GatewayIntents::MESSAGE_CONTENT,
+        "guild_scheduled_events" => GatewayIntents::GUILD_SCHEDULED_EVENTS,
+        "auto_moderation_configuration" => GatewayIntents::AUTO_MODERATION_CONFIGURATION,
+        "auto_moderation_execution" => GatewayIntents::AUTO_MODERATION_EXECUTION,
+        _ => return None,
+    };
+    Some(intent)
+}
+
+/// Parse a list of intent names into one set.
+fn parse_all(names: &[String]) -> Result<GatewayIntents, String> {
+    names
+        .iter()
+        .try_fold(GatewayIntents::empty(), |intents, name| {
+            parse(name)
+                .map(|intent| intents | intent)
+                .ok_or_else(|| format!("Unknown gateway intent `{}`", name))
+        })
+}
+
+/// Apply the configured additions and removals to the derived intents.
+///
+/// Removals win over additions, so an intent can be turned off even when a handler
+/// needs it.
+pub fn resolve(derived: GatewayIntents, config: &IntentsConfig) -> Result<GatewayIntents, String> {
+    let extra = parse_all(&config.extra)?;
+    let disabled = parse_all(&config.disabled)?;
+    Ok((BASE | derived | extra) - disabled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_intents_from_event_types() {
+        assert!(for_event("message").contains(GatewayIntents::MESSAGE_CONTENT));
+        assert_eq!(for_event("guild_member_add"), GatewayIntents::GUILD_MEMBERS);
+        assert!(for_event("interaction").is_empty());
+        assert!(for_event("ready").is_empty());
+    }
+
+    #[test]
+    fn config_adds_and_removes_intents() {
+        let config = IntentsConfig {
+            extra: vec!["GUILD_VOICE_STATES".to_string()],
+            disabled: vec!["message_content".to_string()],
+        };
+        let intents = resolve(for_event("message"), &config).unwrap();
+        assert!(intents.contains(GatewayIntents::GUILDS | GatewayIntents::GUILD_VOICE_STATES));
+        assert!(!intents.contains(GatewayIntents::MESSAGE_CONTENT));
+
+        let config = IntentsConfig {
+            extra: vec!["voice".to_string()],
+            disabled: Vec::new(),
+        };
+        assert!(resolve(GatewayIntents::empty(), &config).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+//! Middleware that runs before event handlers and can stop an event.
+
+use async_trait::async_trait;
+use serenity::client::bridge::gateway::event::ShardStageUpdateEvent;
+use serenity::model::event::Event as GatewayEvent;
+use serenity::model::gateway::Ready;
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+/// An event passing through the middleware chain.
+pub enum Event<'a> {
+    Ready(&'a Ready),
+    Message(&'a Message),
+    MessageUpdate(&'a MessageUpdateEvent),
+    MessageDelete(ChannelId, MessageId, Option<GuildId>),
+    ReactionAdd(&'a Reaction),
+    GuildMemberAdd(GuildId, &'a Member),
+    GuildMemberUpdate(Option<&'a Member>, &'a Member),
+    GuildMemberRemove(GuildId, &'a User, Option<&'a Member>),
+    GuildBanAdd(GuildId, &'a User),
+    GuildBanRemove(GuildId, &'a User),
+    /// A guild becoming available, `true` when the bot just joined it.
+    GuildCreate(&'a Guild, bool),
+    ChannelPinsUpdate(&'a ChannelPinsUpdateEvent),
+    /// A member joining, leaving or moving between voice channels, with their
+    /// state before if it was cached.
+    VoiceStateUpdate(Option<&'a VoiceState>, &'a VoiceState),
+    Interaction(&'a Interaction),
+    ShardStageUpdate(&'a ShardStageUpdateEvent),
+    Resume(&'a ResumedEvent),
+    /// A raw gateway event, only dispatched when a `raw` handler is registered.
+    Raw(&'a GatewayEvent),
+}
+
+impl Event<'_> {
+    /// The event type name, matching [`super::event_handler::EventHandler::event_type`].
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            Event::Ready(_) => "ready",
+            Event::Message(_) => "message",
+            Event::MessageUpdate(_) => "message_update",
+            Event::MessageDelete(..) => "message_delete",
+            Event::ReactionAdd(_) => "reaction_add",
+            Event::GuildMemberAdd(..) => "guild_member_add",
+            Event::GuildMemberUpdate(..) => "guild_member_update",
+            Event::GuildMemberRemove(..) => "guild_member_remove",
+            Event::GuildBanAdd(..) => "guild_ban_add",
+            Event::GuildBanRemove(..) => "guild_ban_remove",
+            Event::GuildCreate(..) => "guild_create",
+            Event::ChannelPinsUpdate(_) => "channel_pins_update",
+            Event::VoiceStateUpdate(..) => "voice_state_update",
+            Event::Interaction(_) => "interaction",
+            Event::ShardStageUpdate(_) => "shard_stage_update",
+            Event::Resume(_) => "resume",
+            Event::Raw(_) => "raw",
+        }
+    }
+
+    /// The guild the event happened in, if any.
+    pub fn guild_id(&self) -> Option<GuildId> {
+        match self {
+            Event::Message(msg) => msg.guild_id,
+            Event::MessageUpdate(event) => event.guild_id,
+            Event::MessageDelete(_, _, guild_id) => *guild_id,
+            Event::ReactionAdd(reaction) => reaction.guild_id,
+            Event::GuildMemberAdd(guild_id, _)
+            | Event::GuildMemberRemove(guild_id, ..)
+            | Event::GuildBanAdd(guild_id, _)
+            | Event::GuildBanRemove(guild_id, _) => Some(*guild_id),
+            Event::GuildMemberUpdate(_, member) => Some(member.guild_id),
+            Event::GuildCreate(guild, _) => Some(guild.id),
+            Event::ChannelPinsUpdate(event) => event.guild_id,
+            Event::VoiceStateUpdate(_, state) => state.guild_id,
+            Event::Interaction(interaction) => match interaction {
+                Interaction::ApplicationCommand(command) => command.guild_id,
+                Interaction::MessageComponent(component) => component.guild_id,
+                Interaction::Autocomplete(autocomplete) => autocomplete.guild_id,
+                Interaction::ModalSubmit(modal) => modal.guild_id,
+                Interaction::Ping(_) => None,
+            },
+            // Events serenity doesn't know, such as audit log entries, are only
+            // looked at for a `guild_id` field
+            Event::Raw(GatewayEvent::Unknown(event)) => event
+                .value
+                .get("guild_id")
+                .and_then(|id| id.as_str()?.parse().ok())
+                .map(GuildId),
+            Event::Ready(_) | Event::ShardStageUpdate(_) | Event::Resume(_) | Event::Raw(_) => None,
+        }
+    }
+}
+
+/// Whether an event should continue to later middleware and handlers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Propagation {
+    /// Pass the event on.
+    Continue,
+    /// Consume the event; no later middleware or handler sees it.
+    Stop,
+}
+
+/// A step in the middleware chain.
+///
+/// Middleware runs in order of descending priority before any event handler,
+/// one at a time, so a middleware such as automod can delete a message and
+/// stop it before commands or XP tracking see it.
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    /// A name used in logs.
+    fn name(&self) -> &str;
+
+    /// Ordering within the chain. Higher priorities run first.
+    fn priority(&self) -> i32 {
+        0
+    }
+
+    /// Process an event.
+    async fn handle(&self, ctx: &Context, event: &Event<'_>) -> Propagation;
+}
@@ -1,11 +1,28 @@
 //! Core bot framework components for handling commands and events.
 
+pub mod app_commands;
+pub mod break_glass;
 pub mod command_handler;
 pub mod context;
+pub mod error;
 pub mod event_handler;
+pub mod inline;
+pub mod intents;
+pub mod middleware;
+pub mod params;
+pub mod plugin;
+pub mod queue;
+pub mod scripts;
+pub mod state;
+pub mod wizard;
 
 pub use command_handler::CommandHandler;
-pub use event_handler::EventDispatcher;
+pub use error::KurumiError;
+pub use event_handler::{DispatchPolicy, EventDispatcher};
+pub use middleware::{Event, Middleware, Propagation};
+pub use params::{Param, ParamKind};
+pub use plugin::{Plugin, PluginContext};
+pub use state::{FromState, Inject, State};
 
 use std::sync::Arc;
 
@@ -17,6 +34,12 @@ pub struct Framework {
     pub event_dispatcher: EventDispatcher,
 }
 
+impl Default for Framework {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Framework {
     /// Creates a new Framework instance.
     pub fn new() -> Self {
@@ -32,7 +55,7 @@ impl Framework {
         crate::commands::register_commands(&mut self.command_handler);
 
         // Register event handlers from the events module
-        let command_handler = std::mem::replace(&mut self.command_handler, CommandHandler::new());
+        let command_handler = std::mem::take(&mut self.command_handler);
         crate::events::register_events(&mut self.event_dispatcher, command_handler);
     }
 
@@ -0,0 +1,454 @@
+//! Structured command parameters.
+//!
+//! Commands describe their arguments with [`Param`]s instead of a freeform usage
+//! string. The same definition gives the usage string shown in errors, the options
+//! of the matching slash command, and the error for a missing argument, which the
+//! command handler sends before the command runs.
+//!
+//! Parameters can also carry [`Validator`]s. The command handler checks prefix
+//! command arguments against them, and slash commands get them as native option
+//! constraints where Discord has one.
+
+use regex::Regex;
+use serenity::builder::{CreateApplicationCommand, CreateApplicationCommandOption};
+use serenity::model::application::command::CommandOptionType;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+
+use super::command_handler::Command;
+use crate::utils::helpers::{parse_channel, parse_role, parse_user};
+
+/// Longest description Discord accepts for a slash command or option.
+const MAX_DESCRIPTION: usize = 100;
+
+/// Most choices Discord accepts for a slash command option.
+const MAX_CHOICES: usize = 25;
+
+/// Compile a [`Validator::Pattern`] regex, once per pattern, since commands
+/// build their parameters again for every call.
+fn compiled(pattern: &'static str) -> Result<Regex, regex::Error> {
+    static COMPILED: OnceLock<Mutex<HashMap<&'static str, Regex>>> = OnceLock::new();
+    let mut compiled = COMPILED
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if let Some(regex) = compiled.get(pattern) {
+        return Ok(regex.clone());
+    }
+    let regex = Regex::new(pattern)?;
+    compiled.insert(pattern, regex.clone());
+    Ok(regex)
+}
+
+/// What a parameter takes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParamKind {
+    /// A single word.
+    Word,
+    /// The rest of the message. Only the last parameter can take text.
+    Text,
+    /// A whole number.
+    Integer,
+    /// Any number.
+    Number,
+    /// Yes or no.
+    Boolean,
+    /// A user mention or ID.
+    User,
+    /// A channel mention or ID.
+    Channel,
+    /// A role mention or ID.
+    Role,
+}
+
+impl ParamKind {
+    /// The slash command option type for the kind.
+    pub fn option_type(self) -> CommandOptionType {
+        match self {
+            Self::Word | Self::Text => CommandOptionType::String,
+            Self::Integer => CommandOptionType::Integer,
+            Self::Number => CommandOptionType::Number,
+            Self::Boolean => CommandOptionType::Boolean,
+            Self::User => CommandOptionType::User,
+            Self::Channel => CommandOptionType::Channel,
+            Self::Role => CommandOptionType::Role,
+        }
+    }
+}
+
+/// A rule an argument has to follow.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Validator {
+    /// A number between the bounds, inclusive.
+    Range { min: i64, max: i64 },
+    /// Text between the bounds in length, in characters, inclusive.
+    Length { min: usize, max: usize },
+    /// One of a fixed set of words, in any case.
+    Choices(&'static [&'static str]),
+    /// Text matching a regular expression, with what it looks like for the
+    /// error, such as `a hex color like #ff8800`. Slash commands have no
+    /// equivalent, so it's only checked for prefix commands.
+    Pattern {
+        regex: &'static str,
+        hint: &'static str,
+    },
+}
+
+impl Validator {
+    /// Check a value, returning what's wrong with it.
+    fn check(&self, name: &str, value: &str) -> Result<(), String> {
+        match self {
+            Self::Range { min, max } => {
+                let number: f64 = value
+                    .parse()
+                    .map_err(|_| format!("`{}` must be a number.", name))?;
+                if number < *min as f64 || number > *max as f64 {
+                    return Err(format!("`{}` must be between {} and {}.", name, min, max));
+                }
+            }
+            Self::Length { min, max } => {
+                let length = value.chars().count();
+                if length < *min || length > *max {
+                    return Err(format!(
+                        "`{}` must be {} to {} characters long.",
+                        name, min, max
+                    ));
+                }
+            }
+            Self::Choices(choices) => {
+                if !choices
+                    .iter()
+                    .any(|choice| choice.eq_ignore_ascii_case(value))
+                {
+                    return Err(format!(
+                        "`{}` must be one of: {}.",
+                        name,
+                        choices.join(", ")
+                    ));
+                }
+            }
+            Self::Pattern { regex, hint } => {
+                let regex = compiled(regex)
+                    .map_err(|e| format!("Invalid pattern for `{}`: {}", name, e))?;
+                if !regex.is_match(value) {
+                    return Err(format!("`{}` must be {}.", name, hint));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the native constraint for the rule to a slash command option.
+    fn constrain(&self, kind: ParamKind, option: &mut CreateApplicationCommandOption) {
+        match (self, kind) {
+            (Self::Range { min, max }, ParamKind::Integer) => {
+                option.min_int_value(*min).max_int_value(*max);
+            }
+            (Self::Range { min, max }, ParamKind::Number) => {
+                option
+                    .min_number_value(*min as f64)
+                    .max_number_value(*max as f64);
+            }
+            (Self::Length { min, max }, ParamKind::Word | ParamKind::Text) => {
+                let clamp = |length: usize| length.min(u16::MAX as usize) as u16;
+                option.min_length(clamp(*min)).max_length(clamp(*max));
+            }
+            (Self::Choices(choices), ParamKind::Word | ParamKind::Text) => {
+                for choice in choices.iter().take(MAX_CHOICES) {
+                    option.add_string_choice(choice, choice);
+                }
+            }
+            (Self::Choices(choices), ParamKind::Integer) => {
+                for (choice, value) in choices
+                    .iter()
+                    .filter_map(|choice| Some((choice, choice.parse::<i32>().ok()?)))
+                    .take(MAX_CHOICES)
+                {
+                    option.add_int_choice(choice, value);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// One argument of a command.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Param {
+    /// The name shown in the usage string, like `user`.
+    pub name: &'static str,
+    /// What the parameter takes.
+    pub kind: ParamKind,
+    /// Whether the command refuses to run without it.
+    pub required: bool,
+    /// What the parameter is for, shown when it's missing.
+    pub description: &'static str,
+    /// Rules the argument has to follow.
+    pub validators: Vec<Validator>,
+}
+
+impl Param {
+    /// A parameter the command needs.
+    pub const fn required(name: &'static str, kind: ParamKind, description: &'static str) -> Self {
+        Self {
+            name,
+            kind,
+            required: true,
+            description,
+            validators: Vec::new(),
+        }
+    }
+
+    /// A parameter the command can do without.
+    pub const fn optional(name: &'static str, kind: ParamKind, description: &'static str) -> Self {
+        Self {
+            name,
+            kind,
+            required: false,
+            description,
+            validators: Vec::new(),
+        }
+    }
+
+    /// Only accept numbers between `min` and `max`, inclusive.
+    pub fn range(mut self, min: i64, max: i64) -> Self {
+        self.validators.push(Validator::Range { min, max });
+        self
+    }
+
+    /// Only accept text between `min` and `max` characters long.
+    pub fn length(mut self, min: usize, max: usize) -> Self {
+        self.validators.push(Validator::Length { min, max });
+        self
+    }
+
+    /// Only accept one of `choices`, in any case.
+    pub fn choices(mut self, choices: &'static [&'static str]) -> Self {
+        self.validators.push(Validator::Choices(choices));
+        self
+    }
+
+    /// Only accept text matching `regex`, described by `hint` in errors.
+    pub fn pattern(mut self, regex: &'static str, hint: &'static str) -> Self {
+        self.validators.push(Validator::Pattern { regex, hint });
+        self
+    }
+
+    /// Check that an argument has the parameter's type and follows its rules.
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        let name = self.name;
+        let valid = match self.kind {
+            ParamKind::Word | ParamKind::Text => true,
+            ParamKind::Integer => value.parse::<i64>().is_ok(),
+            ParamKind::Number => value.parse::<f64>().is_ok_and(f64::is_finite),
+            ParamKind::Boolean => matches!(
+                value.to_lowercase().as_str(),
+                "yes" | "no" | "true" | "false" | "on" | "off"
+            ),
+            ParamKind::User => parse_user(value).is_some(),
+            ParamKind::Channel => parse_channel(value).is_some(),
+            ParamKind::Role => parse_role(value).is_some(),
+        };
+        if !valid {
+            let expected = match self.kind {
+                ParamKind::Integer => "a whole number",
+                ParamKind::Number => "a number",
+                ParamKind::Boolean => "yes or no",
+                ParamKind::User => "a user mention or ID",
+                ParamKind::Channel => "a channel mention or ID",
+                ParamKind::Role => "a role mention or ID",
+                ParamKind::Word | ParamKind::Text => "text",
+            };
+            return Err(format!("`{}` must be {}.", name, expected));
+        }
+        self.validators
+            .iter()
+            .try_for_each(|validator| validator.check(name, value))
+    }
+}
+
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rest = if self.kind == ParamKind::Text {
+            "..."
+        } else {
+            ""
+        };
+        if self.required {
+            write!(f, "<{}{}>", self.name, rest)
+        } else {
+            write!(f, "[{}{}]", self.name, rest)
+        }
+    }
+}
+
+/// The usage string for a command with parameters, like `mimic <user> <message...>`.
+pub fn usage(name: &str, params: &[Param]) -> String {
+    params.iter().fold(name.to_string(), |mut usage, param| {
+        usage.push(' ');
+        usage.push_str(&param.to_string());
+        usage
+    })
+}
+
+/// A command's usage string, generated from its parameters if it has any.
+pub fn usage_of(command: &dyn Command) -> String {
+    let params = command.params();
+    if params.is_empty() {
+        command.usage().to_string()
+    } else {
+        usage(command.name(), &params)
+    }
+}
+
+/// The first required parameter the arguments don't cover.
+pub fn missing<'p>(params: &'p [Param], args: &[String]) -> Option<&'p Param> {
+    params
+        .iter()
+        .enumerate()
+        .find(|(index, param)| param.required && args.get(*index).is_none())
+        .map(|(_, param)| param)
+}
+
+/// Check arguments against the parameters: required ones have to be there and
+/// every given one has to be valid. Returns the error to show.
+pub fn check(command_name: &str, params: &[Param], args: &[String]) -> Result<(), String> {
+    let result = match missing(params, args) {
+        Some(param) => Err(format!("Missing {}: {}", param, param.description)),
+        None => params.iter().enumerate().try_for_each(|(index, param)| {
+            let value = match param.kind {
+                ParamKind::Text => args.get(index..).map(|rest| rest.join(" ")),
+                _ => args.get(index).cloned(),
+            };
+            match value {
+                Some(value) => param.check(&value),
+                None => Ok(()),
+            }
+        }),
+    };
+    result.map_err(|e| format!("{}\nUsage: `{}`", e, usage(command_name, params)))
+}
+
+/// Shorten a description to what Discord accepts.
+fn slash_description(description: &str) -> String {
+    if description.chars().count() <= MAX_DESCRIPTION {
+        return description.to_string();
+    }
+    let mut short: String = description.chars().take(MAX_DESCRIPTION - 1).collect();
+    short.push('…');
+    short
+}
+
+/// A slash command with the same name, description and parameters as a prefix
+/// command.
+pub fn slash_command(command: &dyn Command) -> CreateApplicationCommand {
+    let description = match command.description() {
+        "" => command.name(),
+        description => description,
+    };
+    let mut slash = CreateApplicationCommand::default();
+    slash
+        .name(command.name())
+        .description(slash_description(description))
+        .dm_permission(!command.guild_only() && command.required_permissions().is_empty());
+    if !command.required_permissions().is_empty() {
+        slash.default_member_permissions(command.required_permissions());
+    }
+    for param in command.params() {
+        slash.create_option(|option| {
+            option
+                .name(param.name)
+                .description(slash_description(param.description))
+                .kind(param.kind.option_type())
+                .required(param.required);
+            for validator in &param.validators {
+                validator.constrain(param.kind, option);
+            }
+            option
+        });
+    }
+    slash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framework::app_commands::to_value;
+    use crate::framework::command_handler::{CommandContext, CommandResult};
+    use async_trait::async_trait;
+
+    struct Mimic;
+
+    #[async_trait]
+    impl Command for Mimic {
+        fn name(&self) -> &str {
+            "mimic"
+        }
+
+        fn params(&self) -> Vec<Param> {
+            vec![
+                Param::required("user", ParamKind::User, "Who to mimic"),
+                Param::required("message", ParamKind::Text, "What they say"),
+            ]
+        }
+
+        async fn execute(&self, _ctx: CommandContext<'_>) -> CommandResult {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn generates_usage_and_missing_errors() {
+        let params = Mimic.params();
+        assert_eq!(usage_of(&Mimic), "mimic <user> <message...>");
+
+        let args = vec!["<@1>".to_string()];
+        assert_eq!(missing(&params, &args).unwrap().name, "message");
+        assert_eq!(
+            check("mimic", &params, &args),
+            Err("Missing <message...>: What they say\nUsage: `mimic <user> <message...>`".into())
+        );
+        let args = vec!["<@1>".to_string(), "hi".to_string()];
+        assert!(check("mimic", &params, &args).is_ok());
+
+        let slash = to_value(&slash_command(&Mimic));
+        assert_eq!(slash["description"], "mimic");
+        assert_eq!(slash["options"][0]["type"], 6);
+        assert_eq!(slash["options"][1]["required"], true);
+    }
+
+    #[test]
+    fn validates_arguments() {
+        let slots = Param::required("slots", ParamKind::Integer, "Group size").range(2, 25);
+        assert!(slots.check("5").is_ok());
+        assert_eq!(
+            slots.check("1"),
+            Err("`slots` must be between 2 and 25.".into())
+        );
+        assert_eq!(
+            slots.check("five"),
+            Err("`slots` must be a whole number.".into())
+        );
+
+        let mode = Param::required("mode", ParamKind::Word, "Mode").choices(&["on", "off"]);
+        assert!(mode.check("ON").is_ok());
+        assert_eq!(
+            mode.check("maybe"),
+            Err("`mode` must be one of: on, off.".into())
+        );
+
+        let color = Param::optional("color", ParamKind::Word, "Color")
+            .pattern(r"^#[0-9a-fA-F]{6}$", "a hex color like #ff8800");
+        assert!(color.check("#FF8800").is_ok());
+        assert!(color.check("red").is_err());
+
+        let name = Param::required("name", ParamKind::Text, "Name").length(1, 5);
+        assert!(check("x", &[name], &["too".into(), "long".into()]).is_err());
+
+        let mut option = CreateApplicationCommandOption::default();
+        Validator::Range { min: 2, max: 25 }.constrain(ParamKind::Integer, &mut option);
+        assert_eq!(option.0["min_value"], 2);
+        assert_eq!(option.0["max_value"], 25);
+    }
+}
@@ -0,0 +1,233 @@
+//! Optional feature areas that bring their own commands, handlers and data.
+//!
+//! A plugin is compiled in with its Cargo feature, see [`crate::plugins`]. Guild
+//! admins can turn a compiled-in plugin off for their server with `modules`, which
+//! hides its commands and stops its event handlers and middleware for that guild.
+
+use async_trait::async_trait;
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::gateway::GatewayIntents;
+use serenity::model::id::GuildId;
+use serenity::model::permissions::Permissions;
+use serenity::prelude::{TypeMap, TypeMapKey};
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, RwLock};
+
+use super::command_handler::CommandHandler;
+use super::event_handler::EventDispatcher;
+use crate::models::config::BotConfig;
+use crate::models::guild_config::GuildConfigs;
+use crate::storage::migrations::Migration;
+use crate::storage::Storage;
+
+/// What a plugin gets when the bot starts.
+pub struct PluginContext<'a> {
+    /// The bot's configuration.
+    pub config: &'a BotConfig,
+    /// Storage for opening the plugin's stores.
+    pub storage: &'a Storage,
+    /// Shared state that the plugin's stores are inserted into.
+    pub state: &'a mut TypeMap,
+}
+
+/// A feature area that can be left out of a build or turned off per guild.
+#[async_trait]
+pub trait Plugin: Send + Sync {
+    /// A short lowercase name, used in settings and logs.
+    fn name(&self) -> &'static str;
+
+    /// What the plugin does.
+    fn description(&self) -> &'static str;
+
+    /// The `config.toml` table the plugin reads its settings from, if any.
+    fn config_section(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Gateway intents the plugin needs beyond the events its handlers listen to,
+    /// such as voice states for music.
+    fn intents(&self) -> GatewayIntents {
+        GatewayIntents::empty()
+    }
+
+    /// Permissions the bot needs in a guild for the plugin to work, checked by
+    /// `permaudit`.
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::empty()
+    }
+
+    /// Migrations for the plugin's stores, versioned separately from the core data.
+    ///
+    /// Versions start at 1 and increase by one.
+    fn migrations(&self) -> &'static [Migration] {
+        &[]
+    }
+
+    /// Open the plugin's stores and insert its shared state. Runs after its
+    /// migrations and before its commands are built.
+    async fn init(&self, _ctx: PluginContext<'_>) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Register the plugin's commands.
+    fn register_commands(&self, _handler: &mut CommandHandler) {}
+
+    /// Register the plugin's event handlers and middleware.
+    fn register_events(&self, _dispatcher: &mut EventDispatcher) {}
+
+    /// Slash and context menu commands the plugin's handlers respond to. They are
+    /// synced with Discord by [`crate::framework::app_commands`].
+    fn application_commands(&self) -> Vec<CreateApplicationCommand> {
+        Vec::new()
+    }
+}
+
+/// The compiled-in plugins and which guilds turned them off.
+///
+/// Guild settings are persisted in [`crate::models::GuildConfig::disabled_plugins`]; this keeps a
+/// bit per plugin in memory so the dispatcher can check every event without
+/// reading the guild configuration.
+pub struct Modules {
+    plugins: Vec<Arc<dyn Plugin>>,
+    /// Disabled plugins by guild ID, one bit per plugin in `plugins` order.
+    disabled: RwLock<HashMap<u64, u64>>,
+}
+
+impl Modules {
+    /// Track the given plugins, with every plugin on in every guild.
+    pub fn new(plugins: Vec<Arc<dyn Plugin>>) -> Self {
+        assert!(plugins.len() <= 64, "at most 64 plugins are supported");
+        Self {
+            plugins,
+            disabled: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// The tracked plugins, in registration order.
+    pub fn plugins(&self) -> &[Arc<dyn Plugin>] {
+        &self.plugins
+    }
+
+    /// Find a plugin by name.
+    pub fn get(&self, name: &str) -> Option<&Arc<dyn Plugin>> {
+        self.plugins.iter().find(|plugin| plugin.name() == name)
+    }
+
+    /// The bit for a plugin in a guild's mask.
+    fn bit(&self, name: &str) -> Option<u64> {
+        let index = self
+            .plugins
+            .iter()
+            .position(|plugin| plugin.name() == name)?;
+        Some(1 << index)
+    }
+
+    /// Load the disabled plugins from the stored guild configuration. Unknown plugin
+    /// names, such as ones left out of this build, are ignored.
+    pub fn load(&self, configs: &GuildConfigs) {
+        let masks = configs
+            .guilds
+            .iter()
+            .map(|(guild_id, config)| {
+                let mask = config
+                    .disabled_plugins
+                    .iter()
+                    .filter_map(|name| self.bit(name))
+                    .fold(0, |mask, bit| mask | bit);
+                (*guild_id, mask)
+            })
+            .filter(|(_, mask)| *mask != 0)
+            .collect();
+        *self.disabled.write().unwrap_or_else(|e| e.into_inner()) = masks;
+    }
+
+    /// The plugins turned off in a guild, as a mask for [`Modules::allows`].
+    /// Nothing is turned off outside guilds.
+    pub fn mask(&self, guild_id: Option<GuildId>) -> u64 {
+        let guild_id = match guild_id {
+            Some(guild_id) => guild_id,
+            None => return 0,
+        };
+        let disabled = self.disabled.read().unwrap_or_else(|e| e.into_inner());
+        disabled.get(&guild_id.0).copied().unwrap_or_default()
+    }
+
+    /// Whether something registered by `plugin` should run under a guild's mask.
+    pub fn allows(&self, mask: u64, plugin: Option<&str>) -> bool {
+        match plugin.and_then(|plugin| self.bit(plugin)) {
+            Some(bit) => mask & bit == 0,
+            None => true,
+        }
+    }
+
+    /// Whether a plugin is on in a guild.
+    pub fn is_enabled(&self, guild_id: GuildId, plugin: &str) -> bool {
+        self.allows(self.mask(Some(guild_id)), Some(plugin))
+    }
+
+    /// Turn a plugin on or off in a guild. Callers persist the change in the guild's
+    /// configuration.
+    pub fn set_enabled(&self, guild_id: GuildId, plugin: &str, enabled: bool) {
+        let bit = match self.bit(plugin) {
+            Some(bit) => bit,
+            None => return,
+        };
+
+        let mut disabled = self.disabled.write().unwrap_or_else(|e| e.into_inner());
+        let mask = disabled.entry(guild_id.0).or_default();
+        if enabled {
+            *mask &= !bit;
+        } else {
+            *mask |= bit;
+        }
+        if *mask == 0 {
+            disabled.remove(&guild_id.0);
+        }
+    }
+}
+
+/// Key for storing the plugin modules in the client data.
+pub struct ModulesKey;
+
+impl TypeMapKey for ModulesKey {
+    type Value = Arc<Modules>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Named(&'static str);
+
+    impl Plugin for Named {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        fn description(&self) -> &'static str {
+            ""
+        }
+    }
+
+    #[test]
+    fn masks_plugins_per_guild() {
+        let modules = Modules::new(vec![Arc::new(Named("games")), Arc::new(Named("automod"))]);
+        let mut configs = GuildConfigs::default();
+        configs.entry(GuildId(1)).disabled_plugins =
+            vec!["automod".to_string(), "gone".to_string()];
+        modules.load(&configs);
+
+        let mask = modules.mask(Some(GuildId(1)));
+        assert!(modules.allows(mask, Some("games")));
+        assert!(!modules.allows(mask, Some("automod")));
+        assert!(modules.allows(mask, None));
+        assert_eq!(modules.mask(None), 0);
+
+        modules.set_enabled(GuildId(1), "automod", true);
+        modules.set_enabled(GuildId(2), "games", false);
+        assert!(modules.is_enabled(GuildId(1), "automod"));
+        assert!(!modules.is_enabled(GuildId(2), "games"));
+        assert!(modules.is_enabled(GuildId(2), "automod"));
+    }
+}
@@ -0,0 +1,219 @@
+//! Ordering and concurrency limits for command execution.
+//!
+//! Commands in one channel run one after another in the order they were sent, so a
+//! `purge` followed by a message doesn't interleave with it. Commands in different
+//! channels run side by side, up to a global limit. Commands can also cap how many
+//! of their invocations run at once with [`MaxConcurrency`].
+
+use serenity::model::channel::Message;
+use serenity::model::id::ChannelId;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{Mutex as AsyncMutex, Semaphore};
+
+/// Where a command's concurrency limit applies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ConcurrencyScope {
+    /// Per invoking user.
+    User,
+    /// Per channel.
+    Channel,
+    /// Per server, or per channel in direct messages.
+    Guild,
+    /// Across the whole bot.
+    Global,
+}
+
+impl ConcurrencyScope {
+    /// The ID that invocations sharing a limit have in common.
+    fn key(self, msg: &Message) -> u64 {
+        match self {
+            ConcurrencyScope::User => msg.author.id.0,
+            ConcurrencyScope::Channel => msg.channel_id.0,
+            ConcurrencyScope::Guild => msg.guild_id.map_or(msg.channel_id.0, |id| id.0),
+            ConcurrencyScope::Global => 0,
+        }
+    }
+}
+
+impl fmt::Display for ConcurrencyScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let place = match self {
+            ConcurrencyScope::User => "for you",
+            ConcurrencyScope::Channel => "in this channel",
+            ConcurrencyScope::Guild => "in this server",
+            ConcurrencyScope::Global => "right now",
+        };
+        f.write_str(place)
+    }
+}
+
+/// How many invocations of a command may run at once, see
+/// [`super::command_handler::Command::max_concurrency`].
+///
+/// Invocations over the limit are refused rather than queued.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaxConcurrency {
+    pub limit: usize,
+    pub scope: ConcurrencyScope,
+}
+
+impl MaxConcurrency {
+    /// Allow one invocation at a time in the given scope.
+    pub const fn one(scope: ConcurrencyScope) -> Self {
+        Self { limit: 1, scope }
+    }
+}
+
+/// Runs commands in per-channel order with a global concurrency limit.
+pub struct ExecutionQueue {
+    /// One lock per channel with queued commands, taken in arrival order.
+    channels: Mutex<HashMap<ChannelId, Arc<AsyncMutex<()>>>>,
+    /// Limits the number of commands running across all channels.
+    permits: Semaphore,
+    /// Running invocations by command, scope and scope ID.
+    running: Mutex<HashMap<(String, ConcurrencyScope, u64), usize>>,
+}
+
+impl ExecutionQueue {
+    /// Create a queue that runs at most `max_concurrent` commands at once.
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            channels: Mutex::new(HashMap::new()),
+            permits: Semaphore::new(max_concurrent.max(1)),
+            running: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Run a command once the earlier commands in its channel are done and a
+    /// global slot is free.
+    pub async fn run<F: Future>(&self, channel_id: ChannelId, future: F) -> F::Output {
+        let lock = {
+            let mut channels = self.channels.lock().unwrap_or_else(|e| e.into_inner());
+            channels.entry(channel_id).or_default().clone()
+        };
+
+        let output = {
+            // Tokio's mutex is fair, so waiters get their turn in arrival order
+            let _turn = lock.lock().await;
+            let _permit = self.permits.acquire().await;
+            future.await
+        };
+
+        // Forget channels with nothing left queued
+        let mut channels = self.channels.lock().unwrap_or_else(|e| e.into_inner());
+        drop(lock);
+        if channels
+            .get(&channel_id)
+            .is_some_and(|lock| Arc::strong_count(lock) == 1)
+        {
+            channels.remove(&channel_id);
+        }
+        output
+    }
+
+    /// Start an invocation under a command's concurrency limit.
+    ///
+    /// Returns `None` if the limit is reached. The slot is freed when the guard is
+    /// dropped.
+    pub fn try_start(
+        &self,
+        command_name: &str,
+        limit: MaxConcurrency,
+        msg: &Message,
+    ) -> Option<ConcurrencyGuard<'_>> {
+        let key = (command_name.to_string(), limit.scope, limit.scope.key(msg));
+        let mut running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+        let count = running.entry(key.clone()).or_default();
+        if *count >= limit.limit {
+            return None;
+        }
+        *count += 1;
+        Some(ConcurrencyGuard { queue: self, key })
+    }
+
+    /// The number of channels with commands running or waiting.
+    pub fn queued_channels(&self) -> usize {
+        self.channels
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .len()
+    }
+}
+
+/// A running invocation counted against its command's concurrency limit.
+pub struct ConcurrencyGuard<'a> {
+    queue: &'a ExecutionQueue,
+    key: (String, ConcurrencyScope, u64),
+}
+
+impl Drop for ConcurrencyGuard<'_> {
+    fn drop(&mut self) {
+        let mut running = self.queue.running.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(count) = running.get_mut(&self.key) {
+            *count -= 1;
+            if *count == 0 {
+                running.remove(&self.key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestMessage;
+    use serenity::model::id::GuildId;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn runs_one_channel_in_order() {
+        let queue = Arc::new(ExecutionQueue::new(8));
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let mut tasks = Vec::new();
+        for i in 0..4u64 {
+            let (queue, order) = (queue.clone(), order.clone());
+            tasks.push(tokio::spawn(async move {
+                queue
+                    .run(ChannelId(1), async move {
+                        // Earlier commands take longer, so only the queue keeps them in order
+                        tokio::time::sleep(Duration::from_millis(40 - i * 10)).await;
+                        order.lock().unwrap().push(i);
+                    })
+                    .await
+            }));
+            tokio::task::yield_now().await;
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(*order.lock().unwrap(), [0, 1, 2, 3]);
+        assert_eq!(queue.queued_channels(), 0);
+    }
+
+    #[test]
+    fn limits_concurrent_invocations_per_scope() {
+        let queue = ExecutionQueue::new(8);
+        let limit = MaxConcurrency::one(ConcurrencyScope::Guild);
+        let here = TestMessage::new("!purge")
+            .guild(GuildId(1))
+            .channel(ChannelId(10))
+            .build();
+        let elsewhere = TestMessage::new("!purge")
+            .guild(GuildId(2))
+            .channel(ChannelId(20))
+            .build();
+
+        let guard = queue.try_start("purge", limit, &here).unwrap();
+        assert!(queue.try_start("purge", limit, &here).is_none());
+        assert!(queue.try_start("purge", limit, &elsewhere).is_some());
+        assert!(queue.try_start("ban", limit, &here).is_some());
+
+        drop(guard);
+        assert!(queue.try_start("purge", limit, &here).is_some());
+    }
+}
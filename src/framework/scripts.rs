@@ -0,0 +1,351 @@
+//! Owner-defined commands written in Rhai.
+//!
+//! Every `<name>.rhai` file in `[scripts] dir` becomes a command called `<name>`.
+//! Comment lines at the top of a file become the command's description. Scripts are
+//! loaded when the bot starts and again with `reloadscripts`; built-in commands
+//! always take priority over a script with the same name.
+//!
+//! Scripts run in a sandbox without access to files, modules, the network or the
+//! bot's data, and are stopped after `max_operations`. They can use:
+//!
+//! - `args`: the command's arguments, as an array of strings
+//! - `author` and `author_id`: the invoking user's name and ID
+//! - `channel_id` and `guild_id`: where the command was used (`guild_id` is empty in DMs)
+//! - `reply(text)`: send a message once the script finishes
+//!
+//! If a script evaluates to a value and never calls `reply`, the value is sent instead.
+
+use async_trait::async_trait;
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Scope, AST};
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+use tracing::{debug, info, warn};
+
+use super::command_handler::{Command, CommandContext, CommandResult};
+use crate::models::config::ScriptsConfig;
+use crate::utils::helpers::{apply_mentions, mention_policy, send_error, truncate};
+use crate::utils::rest;
+
+/// Most messages a script may send per run.
+const MAX_REPLIES: usize = 5;
+
+/// Longest message a script may send, in characters.
+const MAX_REPLY_LENGTH: usize = 2000;
+
+/// Key for storing the loaded scripts in the client data.
+pub struct ScriptsKey;
+
+impl TypeMapKey for ScriptsKey {
+    type Value = Arc<Scripts>;
+}
+
+/// What happened when the scripts were loaded.
+#[derive(Debug, Default)]
+pub struct ScriptReport {
+    /// Names of the scripts that compiled.
+    pub loaded: Vec<String>,
+    /// Scripts that failed to compile, with the error. A script that was loaded
+    /// before keeps its previous version.
+    pub failed: Vec<(String, String)>,
+}
+
+/// The script commands loaded from the scripts directory.
+pub struct Scripts {
+    config: ScriptsConfig,
+    commands: RwLock<HashMap<String, Arc<ScriptCommand>>>,
+}
+
+impl Scripts {
+    /// Create an empty set of scripts. Call [`Scripts::load`] to read the directory.
+    pub fn new(config: ScriptsConfig) -> Self {
+        Self {
+            config,
+            commands: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Get a script command by name.
+    pub fn get(&self, name: &str) -> Option<Arc<ScriptCommand>> {
+        let commands = self.commands.read().unwrap_or_else(|e| e.into_inner());
+        commands.get(name).cloned()
+    }
+
+    /// Names of the loaded scripts, sorted.
+    pub fn names(&self) -> Vec<String> {
+        let commands = self.commands.read().unwrap_or_else(|e| e.into_inner());
+        let mut names: Vec<_> = commands.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Read and compile every script in the directory, replacing the loaded ones.
+    ///
+    /// A missing directory just means there are no scripts.
+    pub fn load(&self) -> ScriptReport {
+        let mut report = ScriptReport::default();
+        let dir = Path::new(&self.config.dir);
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                debug!("No scripts loaded from {}: {}", dir.display(), e);
+                self.commands
+                    .write()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .clear();
+                return report;
+            }
+        };
+
+        let engine = sandboxed_engine(self.config.max_operations);
+        let mut commands = HashMap::new();
+        for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+            if path.extension().and_then(|ext| ext.to_str()) != Some("rhai") {
+                continue;
+            }
+            let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(stem) if is_valid_name(stem) => stem.to_lowercase(),
+                _ => {
+                    warn!("Skipping script with an invalid name: {}", path.display());
+                    continue;
+                }
+            };
+
+            let compiled = fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|source| {
+                    let ast = engine.compile(&source).map_err(|e| e.to_string())?;
+                    Ok((source, ast))
+                });
+            match compiled {
+                Ok((source, ast)) => {
+                    let command = ScriptCommand {
+                        description: description(&source),
+                        usage: format!("{} [args...]", name),
+                        name: name.clone(),
+                        ast: Arc::new(ast),
+                        max_operations: self.config.max_operations,
+                    };
+                    commands.insert(name.clone(), Arc::new(command));
+                    report.loaded.push(name);
+                }
+                Err(e) => {
+                    warn!("Failed to compile script {}: {}", name, e);
+                    if let Some(previous) = self.get(&name) {
+                        commands.insert(name.clone(), previous);
+                    }
+                    report.failed.push((name, e));
+                }
+            }
+        }
+
+        report.loaded.sort();
+        report.failed.sort();
+        info!("Loaded {} script commands", report.loaded.len());
+        *self.commands.write().unwrap_or_else(|e| e.into_inner()) = commands;
+        report
+    }
+}
+
+/// A command defined by a script.
+pub struct ScriptCommand {
+    name: String,
+    description: String,
+    usage: String,
+    ast: Arc<AST>,
+    max_operations: u64,
+}
+
+/// What a script can see about the command invocation.
+#[derive(Clone, Debug, Default)]
+pub struct ScriptInput {
+    pub args: Vec<String>,
+    pub author: String,
+    pub author_id: String,
+    pub channel_id: String,
+    pub guild_id: String,
+}
+
+#[async_trait]
+impl Command for ScriptCommand {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn usage(&self) -> &str {
+        &self.usage
+    }
+
+    async fn execute(&self, ctx: CommandContext<'_>) -> CommandResult {
+        let input = ScriptInput {
+            args: ctx.args.clone(),
+            author: ctx.msg.author.name.clone(),
+            author_id: ctx.msg.author.id.to_string(),
+            channel_id: ctx.msg.channel_id.to_string(),
+            guild_id: ctx
+                .msg
+                .guild_id
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+        };
+
+        // Scripts are CPU-bound, so keep them off the async workers
+        let ast = self.ast.clone();
+        let max_operations = self.max_operations;
+        let result = tokio::task::spawn_blocking(move || run(&ast, max_operations, input))
+            .await
+            .map_err(|e| format!("script task failed: {}", e))?;
+
+        let replies = match result {
+            Ok(replies) => replies,
+            Err(e) => {
+                debug!("Script {} failed: {}", self.name, e);
+                send_error(ctx.ctx, ctx.msg, format!("This script failed: {}", e)).await?;
+                return Ok(());
+            }
+        };
+
+        let policy = &mention_policy(ctx.ctx).await;
+        for reply in replies {
+            rest::call(ctx.ctx, "send_message", || {
+                ctx.msg.channel_id.send_message(&ctx.ctx.http, |m| {
+                    m.content(&reply)
+                        .allowed_mentions(|am| apply_mentions(am, policy, &[]))
+                })
+            })
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Build an engine that can't reach outside the script.
+fn sandboxed_engine(max_operations: u64) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .set_max_operations(max_operations)
+        .set_max_call_levels(32)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(10_000)
+        .set_max_array_size(1_000)
+        .set_max_map_size(1_000)
+        .on_print(|text| debug!("Script printed: {}", text))
+        .on_debug(|text, _, _| debug!("Script debug: {}", text));
+    engine.disable_symbol("eval");
+    engine
+}
+
+/// Run a script and return the messages it wants to send.
+fn run(ast: &AST, max_operations: u64, input: ScriptInput) -> Result<Vec<String>, String> {
+    let replies = Arc::new(Mutex::new(Vec::new()));
+
+    let mut engine = sandboxed_engine(max_operations);
+    let sink = replies.clone();
+    engine.register_fn(
+        "reply",
+        move |text: Dynamic| -> Result<(), Box<EvalAltResult>> {
+            let mut replies = sink.lock().unwrap_or_else(|e| e.into_inner());
+            if replies.len() >= MAX_REPLIES {
+                return Err(format!("scripts can send at most {} replies", MAX_REPLIES).into());
+            }
+            replies.push(text.to_string());
+            Ok(())
+        },
+    );
+
+    let args: Array = input.args.into_iter().map(Dynamic::from).collect();
+    let mut scope = Scope::new();
+    scope
+        .push_constant("args", args)
+        .push_constant("author", input.author)
+        .push_constant("author_id", input.author_id)
+        .push_constant("channel_id", input.channel_id)
+        .push_constant("guild_id", input.guild_id);
+
+    let value = engine
+        .eval_ast_with_scope::<Dynamic>(&mut scope, ast)
+        .map_err(|e| e.to_string())?;
+
+    let mut replies = std::mem::take(&mut *replies.lock().unwrap_or_else(|e| e.into_inner()));
+    if replies.is_empty() && !value.is_unit() {
+        replies.push(value.to_string());
+    }
+    Ok(replies
+        .iter()
+        .filter(|reply| !reply.trim().is_empty())
+        .map(|reply| truncate(reply, MAX_REPLY_LENGTH))
+        .collect())
+}
+
+/// Whether a file name can be used as a command name.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The comment lines at the top of a script, joined into a description.
+fn description(source: &str) -> String {
+    source
+        .lines()
+        .map(str::trim)
+        .take_while(|line| line.starts_with("//"))
+        .map(|line| line.trim_start_matches('/').trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_source(source: &str, args: &[&str]) -> Result<Vec<String>, String> {
+        let ast = sandboxed_engine(10_000).compile(source).unwrap();
+        let input = ScriptInput {
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            author: "kurumi".to_string(),
+            ..ScriptInput::default()
+        };
+        run(&ast, 10_000, input)
+    }
+
+    #[test]
+    fn replies_with_arguments_and_values() {
+        assert_eq!(
+            run_source(r#"reply("Hi " + author); reply(args.len());"#, &["a", "b"]).unwrap(),
+            ["Hi kurumi", "2"]
+        );
+        assert_eq!(
+            run_source(r#"args.reduce(|sum, arg| sum + arg, "")"#, &["a", "b"]).unwrap(),
+            ["ab"]
+        );
+        assert!(run_source("let x = 1;", &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn scripts_are_sandboxed() {
+        assert!(run_source("loop {}", &[]).is_err());
+        assert!(run_source(r#"for i in 0..10 { reply(i) }"#, &[]).is_err());
+        assert!(sandboxed_engine(10).compile(r#"eval("1")"#).is_err());
+        assert!(run_source(r#"import "secrets" as s;"#, &[]).is_err());
+    }
+
+    #[test]
+    fn describes_scripts_from_leading_comments() {
+        let source = "// Rolls a die.\n/// Usage: roll\n\nlet x = 1; // not this";
+        assert_eq!(description(source), "Rolls a die. Usage: roll");
+        assert!(is_valid_name("8ball"));
+        assert!(!is_valid_name("bad name"));
+    }
+}
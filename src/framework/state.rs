@@ -0,0 +1,123 @@
+//! Typed access to the bot's shared state.
+
+use serenity::prelude::*;
+use std::sync::Arc;
+
+/// Shared state stored in the client's `TypeMap`.
+///
+/// Every method takes the lock only for the duration of the call, so holding a
+/// `State` never blocks other tasks.
+#[derive(Clone)]
+pub struct State {
+    data: Arc<RwLock<TypeMap>>,
+}
+
+impl State {
+    /// Wrap the client's `TypeMap`.
+    pub fn new(data: Arc<RwLock<TypeMap>>) -> Self {
+        Self { data }
+    }
+
+    /// Get a copy of a value. Values are usually `Arc`s, so this is cheap.
+    pub async fn get<K>(&self) -> Option<K::Value>
+    where
+        K: TypeMapKey,
+        K::Value: Clone,
+    {
+        self.data.read().await.get::<K>().cloned()
+    }
+
+    /// Get a value, inserting the result of `init` if it isn't set yet.
+    pub async fn get_or_init<K>(&self, init: impl FnOnce() -> K::Value) -> K::Value
+    where
+        K: TypeMapKey,
+        K::Value: Clone,
+    {
+        if let Some(value) = self.get::<K>().await {
+            return value;
+        }
+
+        self.data
+            .write()
+            .await
+            .entry::<K>()
+            .or_insert_with(init)
+            .clone()
+    }
+
+    /// Replace a value.
+    pub async fn insert<K: TypeMapKey>(&self, value: K::Value) {
+        self.data.write().await.insert::<K>(value);
+    }
+
+    /// Read a value in place, without cloning it.
+    pub async fn read<K: TypeMapKey, R>(&self, f: impl FnOnce(&K::Value) -> R) -> Option<R> {
+        self.data.read().await.get::<K>().map(f)
+    }
+
+    /// Change a value in place, holding the write lock only while `f` runs.
+    pub async fn write<K: TypeMapKey, R>(&self, f: impl FnOnce(&mut K::Value) -> R) -> Option<R> {
+        self.data.write().await.get_mut::<K>().map(f)
+    }
+}
+
+impl From<&Context> for State {
+    fn from(ctx: &Context) -> Self {
+        Self::new(ctx.data.clone())
+    }
+}
+
+/// Dependencies a command can ask for when it is registered.
+///
+/// Implemented for [`Inject`] and tuples of dependencies, so a constructor like
+/// `fn new(Inject(store): Inject<ModerationKey>) -> Self` can be passed straight
+/// to [`super::CommandHandler::register_with_state`].
+pub trait FromState: Sized {
+    /// Build the dependencies from the shared state.
+    fn from_state(state: &TypeMap) -> Result<Self, String>;
+}
+
+/// A value taken from the shared state by its key.
+pub struct Inject<K: TypeMapKey>(pub K::Value);
+
+impl<K> FromState for Inject<K>
+where
+    K: TypeMapKey,
+    K::Value: Clone,
+{
+    fn from_state(state: &TypeMap) -> Result<Self, String> {
+        state
+            .get::<K>()
+            .cloned()
+            .map(Inject)
+            .ok_or_else(|| format!("{} is not in the shared state", std::any::type_name::<K>()))
+    }
+}
+
+impl FromState for () {
+    fn from_state(_state: &TypeMap) -> Result<Self, String> {
+        Ok(())
+    }
+}
+
+macro_rules! impl_from_state_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: FromState),+> FromState for ($($name,)+) {
+            fn from_state(state: &TypeMap) -> Result<Self, String> {
+                Ok(($($name::from_state(state)?,)+))
+            }
+        }
+    };
+}
+
+impl_from_state_tuple!(A, B);
+impl_from_state_tuple!(A, B, C);
+impl_from_state_tuple!(A, B, C, D);
+impl_from_state_tuple!(A, B, C, D, E);
+impl_from_state_tuple!(A, B, C, D, E, F);
+impl_from_state_tuple!(A, B, C, D, E, F, G);
+impl_from_state_tuple!(A, B, C, D, E, F, G, H);
+impl_from_state_tuple!(A, B, C, D, E, F, G, H, I);
+impl_from_state_tuple!(A, B, C, D, E, F, G, H, I, J);
+impl_from_state_tuple!(A, B, C, D, E, F, G, H, I, J, K);
+impl_from_state_tuple!(A, B, C, D, E, F, G, H, I, J, K, L);
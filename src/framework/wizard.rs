@@ -0,0 +1,218 @@
+//! Asking for missing arguments.
+//!
+//! Commands that opt in with [`Command::wizard`](super::command_handler::Command::wizard)
+//! don't fail when they're invoked without their required arguments. The bot asks
+//! for each missing one instead and takes the author's next message in the channel
+//! as the answer.
+
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, MessageId, UserId};
+use serenity::prelude::*;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+use super::params::{Param, ParamKind};
+use crate::utils::helpers::{send_error, send_info};
+
+/// How long the bot waits for each answer.
+pub const REPLY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How many invalid answers the bot takes for one argument before giving up.
+const MAX_ATTEMPTS: usize = 3;
+
+/// The answer that stops the wizard.
+const CANCEL: &str = "cancel";
+
+/// How many recent answers are remembered for [`Replies::is_answer`].
+const ANSWERS_KEPT: usize = 64;
+
+/// Wizards waiting for an answer, by channel and user.
+#[derive(Default)]
+pub struct Replies {
+    waiting: Mutex<HashMap<(ChannelId, UserId), oneshot::Sender<String>>>,
+    /// The latest messages taken as answers, oldest first.
+    answered: Mutex<VecDeque<MessageId>>,
+}
+
+impl Replies {
+    /// Hand a message to the wizard waiting on its author in its channel.
+    ///
+    /// Returns whether the message was an answer, in which case nothing else
+    /// should handle it.
+    pub fn deliver(&self, msg: &Message) -> bool {
+        let mut waiting = self.waiting.lock().unwrap_or_else(|e| e.into_inner());
+        let sender = waiting.remove(&(msg.channel_id, msg.author.id));
+        let delivered = sender.is_some_and(|sender| sender.send(msg.content.clone()).is_ok());
+
+        // Remembered before the waiter's lock is released, so `is_answer` never
+        // sees the message as neither awaited nor answered
+        if delivered {
+            let mut answered = self.answered.lock().unwrap_or_else(|e| e.into_inner());
+            answered.push_back(msg.id);
+            if answered.len() > ANSWERS_KEPT {
+                answered.pop_front();
+            }
+        }
+        delivered
+    }
+
+    /// Whether a message is an answer, or will be taken as one, for handlers of
+    /// the same message that run beside the command handler.
+    pub fn is_answer(&self, msg: &Message) -> bool {
+        let waiting = self.waiting.lock().unwrap_or_else(|e| e.into_inner());
+        waiting.contains_key(&(msg.channel_id, msg.author.id))
+            || self
+                .answered
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .contains(&msg.id)
+    }
+
+    /// Wait up to `timeout` for the user's next message in the channel.
+    pub(super) async fn next(
+        &self,
+        channel_id: ChannelId,
+        user_id: UserId,
+        timeout: Duration,
+    ) -> Option<String> {
+        let (sender, receiver) = oneshot::channel();
+        self.waiting
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert((channel_id, user_id), sender);
+
+        let reply = tokio::time::timeout(timeout, receiver)
+            .await
+            .ok()
+            .and_then(Result::ok);
+
+        // Our sender is closed now that the receiver is gone, so this only drops
+        // ours and never one a newer wizard registered
+        self.waiting
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|_, sender| !sender.is_closed());
+        reply
+    }
+}
+
+/// Turn an answer into the argument for a parameter.
+fn answer(param: &Param, reply: &str) -> Result<String, String> {
+    let reply = reply.trim();
+    if param.kind != ParamKind::Text && reply.split_whitespace().nth(1).is_some() {
+        return Err(format!("`{}` must be a single word.", param.name));
+    }
+    param.check(reply)?;
+    Ok(reply.to_string())
+}
+
+/// Ask for the required parameters the arguments don't cover, one at a time.
+///
+/// Returns the completed arguments, or `None` if the user cancelled, stopped
+/// answering or kept giving invalid answers, which they've been told about.
+pub async fn complete(
+    replies: &Replies,
+    ctx: &Context,
+    msg: &Message,
+    params: &[Param],
+    mut args: Vec<String>,
+) -> serenity::Result<Option<Vec<String>>> {
+    let missing: Vec<&Param> = params
+        .iter()
+        .skip(args.len())
+        .take_while(|param| param.required)
+        .collect();
+
+    for param in missing {
+        let mut attempts = 0;
+        let value = loop {
+            let prompt = format!(
+                "{}\n\nReply with it here within {} seconds, or `{}` to stop.",
+                param.description,
+                REPLY_TIMEOUT.as_secs(),
+                CANCEL
+            );
+            send_info(ctx, msg, format!("📝 {}", param), prompt).await?;
+
+            let reply = match replies
+                .next(msg.channel_id, msg.author.id, REPLY_TIMEOUT)
+                .await
+            {
+                Some(reply) => reply,
+                None => {
+                    send_error(
+                        ctx,
+                        msg,
+                        format!("No answer for `{}`, stopped.", param.name),
+                    )
+                    .await?;
+                    return Ok(None);
+                }
+            };
+            if reply.trim().eq_ignore_ascii_case(CANCEL) {
+                send_info(ctx, msg, "Cancelled", "The command wasn't run.").await?;
+                return Ok(None);
+            }
+            match answer(param, &reply) {
+                Ok(value) => break value,
+                Err(e) => {
+                    attempts += 1;
+                    if attempts >= MAX_ATTEMPTS {
+                        send_error(
+                            ctx,
+                            msg,
+                            format!("{}\nToo many invalid answers, stopped.", e),
+                        )
+                        .await?;
+                        return Ok(None);
+                    }
+                    send_error(ctx, msg, e).await?;
+                }
+            }
+        };
+        args.push(value);
+    }
+    Ok(Some(args))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestMessage;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn tells_answers_apart() {
+        let replies = Arc::new(Replies::default());
+        let msg = TestMessage::new("5").build();
+        assert!(!replies.is_answer(&msg));
+
+        let waiter = replies.clone();
+        let (channel_id, user_id) = (msg.channel_id, msg.author.id);
+        let answer = tokio::spawn(async move {
+            waiter
+                .next(channel_id, user_id, Duration::from_secs(5))
+                .await
+        });
+        while !replies.is_answer(&msg) {
+            tokio::task::yield_now().await;
+        }
+        assert!(replies.deliver(&msg));
+        assert_eq!(answer.await.unwrap().as_deref(), Some("5"));
+        assert!(replies.is_answer(&msg));
+        assert!(!replies.deliver(&msg));
+    }
+
+    #[test]
+    fn checks_answers() {
+        let count = Param::required("count", ParamKind::Integer, "How many").range(1, 10);
+        assert_eq!(answer(&count, " 5 \n").unwrap(), "5");
+        assert!(answer(&count, "50").is_err());
+        assert!(answer(&count, "5 6").is_err());
+
+        let text = Param::required("message", ParamKind::Text, "What to say");
+        assert_eq!(answer(&text, "hello there").unwrap(), "hello there");
+    }
+}
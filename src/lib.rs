@@ -0,0 +1,13 @@
+//! A modular Discord bot framework built on Serenity.
+
+pub mod bot;
+pub mod commands;
+pub mod events;
+pub mod framework;
+pub mod models;
+pub mod plugins;
+pub mod storage;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod utils;
+pub mod web;
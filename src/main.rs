@@ -1,37 +1,57 @@
-mod bot;
-mod commands;
-mod events;
-mod framework;
-mod models;
-mod utils;
-
-use std::env;
-
 use dotenv::dotenv;
-use tracing::{debug, error, info, Level};
-use tracing_subscriber::FmtSubscriber;
+use tracing::{debug, error, info};
 
-use crate::bot::{load_config, load_token, Bot};
-use crate::commands::general::ping::PingCommand;
+use rust_discord_bot_hander::bot::{
+    load_config, load_token, run_migrate_command, run_register_commands, Bot,
+};
+use rust_discord_bot_hander::commands;
+use rust_discord_bot_hander::models::config::LoggingConfig;
+use rust_discord_bot_hander::utils::logging;
 
 #[tokio::main]
 async fn main() {
     // Load environment variables from .env file
-    if let Ok(_) = dotenv() {
+    let dotenv_loaded = dotenv().is_ok();
+
+    // Load bot configuration first, since it decides how logs are written
+    let config = load_config();
+    match &config {
+        Ok(config) => logging::init(&config.logging),
+        Err(_) => logging::init(&LoggingConfig::default()),
+    }
+
+    info!("Starting Discord Bot...");
+    if dotenv_loaded {
         debug!("Loaded .env file");
     } else {
         debug!("No .env file found, using environment variables");
     }
 
-    // Initialize more detailed logging
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(Level::DEBUG)
-        .finish();
-
-    tracing::subscriber::set_global_default(subscriber).expect("Failed to set tracing subscriber");
+    let config = match config {
+        Ok(config) => {
+            info!("Successfully loaded configuration");
+            debug!(
+                "Config: prefix={}, owner count={}",
+                config.prefix,
+                config.owners.len()
+            );
+            config
+        }
+        Err(e) => {
+            error!("Failed to load configuration: {}", e);
+            return;
+        }
+    };
 
-    info!("Starting Discord Bot...");
-    debug!("Initializing bot with debug logging enabled");
+    // `migrate [status]` manages the data directory without starting the bot
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("migrate") {
+        if let Err(e) = run_migrate_command(&config, &args[1..]).await {
+            error!("Migration failed: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
 
     // Load the Discord token
     let token = match load_token() {
@@ -50,26 +70,18 @@ async fn main() {
         }
     };
 
-    // Load bot configuration
-    let config = match load_config() {
-        Ok(config) => {
-            info!("Successfully loaded configuration");
-            debug!(
-                "Config: prefix={}, owner count={}",
-                config.prefix,
-                config.owners.len()
-            );
-            config
+    // `register-commands [--dry-run]` syncs application commands and exits
+    if args.first().map(String::as_str) == Some("register-commands") {
+        if let Err(e) = run_register_commands(&token, &config, &args[1..]).await {
+            error!("Registering commands failed: {}", e);
+            std::process::exit(1);
         }
-        Err(e) => {
-            error!("Failed to load configuration: {}", e);
-            return;
-        }
-    };
+        return;
+    }
 
     // Create and register commands with the bot
     info!("Registering commands...");
-    let bot = Bot::new(token, config).register_command(PingCommand);
+    let bot = Bot::new(token, config).with_commands(commands::register_commands);
 
     // Start the bot
     info!("Attempting to connect to Discord...");
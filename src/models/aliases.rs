@@ -0,0 +1,81 @@
+//! Command aliases that guilds define for themselves.
+//!
+//! An alias stands for a command and, optionally, arguments that go before the
+//! ones it's used with, so `slap` can stand for `tag slap`. Aliases never shadow
+//! built-in commands or their aliases.
+
+use serenity::model::id::GuildId;
+use serenity::prelude::*;
+
+use super::guild_config::guild_config;
+
+/// Most aliases a guild can have.
+pub const MAX_ALIASES: usize = 50;
+
+/// Longest alias name, in characters.
+const MAX_NAME_LENGTH: usize = 32;
+
+/// Longest command an alias can stand for, in characters.
+pub const MAX_EXPANSION_LENGTH: usize = 200;
+
+/// Check that an alias name is a single short word without the characters that
+/// mentions and formatting use.
+pub fn check_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return Err(format!(
+            "Alias names are 1 to {} characters long.",
+            MAX_NAME_LENGTH
+        ));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err("Alias names can only use letters, numbers, `-` and `_`.".to_string());
+    }
+    Ok(())
+}
+
+/// Split what an alias stands for into the command name and its preset arguments.
+pub fn expand(expansion: &str) -> Option<(String, Vec<String>)> {
+    let mut words = expansion.split_whitespace();
+    let name = words.next()?.to_lowercase();
+    Some((name, words.map(String::from).collect()))
+}
+
+/// The command and preset arguments a guild's alias stands for, if it has one
+/// with that name.
+pub async fn resolve(
+    ctx: &Context,
+    guild_id: GuildId,
+    name: &str,
+) -> Option<(String, Vec<String>)> {
+    let config = guild_config(ctx, guild_id).await;
+    config
+        .aliases
+        .get(name)
+        .and_then(|expansion| expand(expansion))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_names_and_expands() {
+        assert!(check_name("slap").is_ok());
+        assert!(check_name("hug_2").is_ok());
+        assert!(check_name("").is_err());
+        assert!(check_name("<@1>").is_err());
+        assert!(check_name(&"a".repeat(33)).is_err());
+
+        assert_eq!(
+            expand("Tag slap  hard"),
+            Some((
+                "tag".to_string(),
+                vec!["slap".to_string(), "hard".to_string()]
+            ))
+        );
+        assert_eq!(expand("  "), None);
+    }
+}
@@ -0,0 +1,336 @@
+//! Emoji usage and channel activity, counted per day.
+//!
+//! Counting happens in memory and is written to storage every
+//! `analytics.flush_interval` seconds, so busy servers don't cause a write per
+//! message. Days older than `analytics.retention_days` are dropped when flushing.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serenity::model::id::{ChannelId, GuildId};
+use serenity::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::error;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::models::config::AnalyticsConfig;
+use crate::storage::JsonStore;
+use crate::utils::helpers::unix_timestamp;
+
+/// Seconds in a day.
+const DAY: u64 = 86_400;
+
+/// The day number of a Unix timestamp.
+pub fn day_of(timestamp: u64) -> u64 {
+    timestamp / DAY
+}
+
+/// Today's day number.
+pub fn today() -> u64 {
+    day_of(unix_timestamp())
+}
+
+/// How often an emoji was used.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmojiCount {
+    /// Uses in message content.
+    #[serde(default)]
+    pub messages: u64,
+    /// Reactions added.
+    #[serde(default)]
+    pub reactions: u64,
+}
+
+impl EmojiCount {
+    /// Uses of either kind.
+    pub fn total(&self) -> u64 {
+        self.messages + self.reactions
+    }
+}
+
+/// One day's counts in a guild.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DayStats {
+    /// Messages sent, by channel ID.
+    #[serde(default)]
+    pub channels: HashMap<u64, u64>,
+    /// Emoji uses, by emoji. Custom emojis are stored as `<:name:id>`.
+    #[serde(default)]
+    pub emojis: HashMap<String, EmojiCount>,
+}
+
+impl DayStats {
+    fn merge(&mut self, other: DayStats) {
+        for (channel, count) in other.channels {
+            *self.channels.entry(channel).or_default() += count;
+        }
+        for (emoji, count) in other.emojis {
+            let entry = self.emojis.entry(emoji).or_default();
+            entry.messages += count.messages;
+            entry.reactions += count.reactions;
+        }
+    }
+}
+
+/// Counts of every guild, by day number.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AnalyticsData {
+    #[serde(default)]
+    pub guilds: HashMap<u64, BTreeMap<u64, DayStats>>,
+}
+
+impl AnalyticsData {
+    fn day_mut(&mut self, guild_id: GuildId, day: u64) -> &mut DayStats {
+        self.guilds
+            .entry(guild_id.0)
+            .or_default()
+            .entry(day)
+            .or_default()
+    }
+
+    /// Count a message and the emojis in it.
+    pub fn record_message(
+        &mut self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        emojis: Vec<String>,
+        day: u64,
+    ) {
+        let stats = self.day_mut(guild_id, day);
+        *stats.channels.entry(channel_id.0).or_default() += 1;
+        for emoji in emojis {
+            stats.emojis.entry(emoji).or_default().messages += 1;
+        }
+    }
+
+    /// Count a reaction.
+    pub fn record_reaction(&mut self, guild_id: GuildId, emoji: String, day: u64) {
+        self.day_mut(guild_id, day)
+            .emojis
+            .entry(emoji)
+            .or_default()
+            .reactions += 1;
+    }
+
+    /// Add another set of counts to these.
+    pub fn merge(&mut self, other: AnalyticsData) {
+        for (guild_id, days) in other.guilds {
+            for (day, stats) in days {
+                self.day_mut(GuildId(guild_id), day).merge(stats);
+            }
+        }
+    }
+
+    /// Drop days before `oldest`.
+    pub fn prune(&mut self, oldest: u64) {
+        for days in self.guilds.values_mut() {
+            days.retain(|day, _| *day >= oldest);
+        }
+        self.guilds.retain(|_, days| !days.is_empty());
+    }
+
+    /// A guild's days from `since` on.
+    fn days(&self, guild_id: GuildId, since: u64) -> impl Iterator<Item = (&u64, &DayStats)> {
+        self.guilds
+            .get(&guild_id.0)
+            .into_iter()
+            .flat_map(move |days| days.range(since..))
+    }
+
+    /// Messages per channel since a day, busiest first.
+    pub fn channel_totals(&self, guild_id: GuildId, since: u64) -> Vec<(u64, u64)> {
+        let mut totals: HashMap<u64, u64> = HashMap::new();
+        for (_, stats) in self.days(guild_id, since) {
+            for (channel, count) in &stats.channels {
+                *totals.entry(*channel).or_default() += count;
+            }
+        }
+        sorted(totals.into_iter().collect())
+    }
+
+    /// Messages per day since a day, oldest first, including days without any.
+    pub fn daily_messages(&self, guild_id: GuildId, since: u64, until: u64) -> Vec<(u64, u64)> {
+        let counts: HashMap<u64, u64> = self
+            .days(guild_id, since)
+            .map(|(day, stats)| (*day, stats.channels.values().sum()))
+            .collect();
+        (since..=until)
+            .map(|day| (day, counts.get(&day).copied().unwrap_or_default()))
+            .collect()
+    }
+
+    /// Uses per emoji since a day, most used first.
+    pub fn emoji_totals(&self, guild_id: GuildId, since: u64) -> Vec<(String, EmojiCount)> {
+        let mut totals: HashMap<String, EmojiCount> = HashMap::new();
+        for (_, stats) in self.days(guild_id, since) {
+            for (emoji, count) in &stats.emojis {
+                let entry = totals.entry(emoji.clone()).or_default();
+                entry.messages += count.messages;
+                entry.reactions += count.reactions;
+            }
+        }
+        let mut totals: Vec<_> = totals.into_iter().collect();
+        totals.sort_by(|a, b| b.1.total().cmp(&a.1.total()).then_with(|| a.0.cmp(&b.0)));
+        totals
+    }
+}
+
+/// Sort counts by count, highest first, then by key.
+fn sorted(mut counts: Vec<(u64, u64)>) -> Vec<(u64, u64)> {
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+/// Whether a character starts an emoji, going by the main emoji blocks.
+fn is_emoji_start(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2B00..=0x2BFF | 0x2300..=0x23FF
+    )
+}
+
+/// The emojis in a message, custom ones as `<:name:id>` and Unicode ones as they are.
+pub fn extract_emojis(content: &str) -> Vec<String> {
+    static CUSTOM: OnceLock<Regex> = OnceLock::new();
+    let custom = CUSTOM.get_or_init(|| Regex::new(r"<a?:(\w+):(\d+)>").unwrap());
+
+    let mut emojis: Vec<String> = custom
+        .captures_iter(content)
+        .map(|caps| format!("<:{}:{}>", &caps[1], &caps[2]))
+        .collect();
+    let rest = custom.replace_all(content, " ");
+    emojis.extend(
+        rest.graphemes(true)
+            .filter(|grapheme| grapheme.chars().next().is_some_and(is_emoji_start))
+            .map(str::to_string),
+    );
+    emojis
+}
+
+/// Counts activity in memory and writes it to storage in batches.
+pub struct Analytics {
+    store: Arc<JsonStore<AnalyticsData>>,
+    pending: Mutex<AnalyticsData>,
+    config: AnalyticsConfig,
+}
+
+impl Analytics {
+    /// Create the counter for a store.
+    pub fn new(config: AnalyticsConfig, store: Arc<JsonStore<AnalyticsData>>) -> Self {
+        Self {
+            store,
+            pending: Mutex::new(AnalyticsData::default()),
+            config,
+        }
+    }
+
+    /// Count a message.
+    pub async fn record_message(&self, guild_id: GuildId, channel_id: ChannelId, content: &str) {
+        let emojis = extract_emojis(content);
+        self.pending
+            .lock()
+            .await
+            .record_message(guild_id, channel_id, emojis, today());
+    }
+
+    /// Count a reaction.
+    pub async fn record_reaction(&self, guild_id: GuildId, emoji: String) {
+        self.pending
+            .lock()
+            .await
+            .record_reaction(guild_id, emoji, today());
+    }
+
+    /// The oldest day kept.
+    fn oldest_day(&self) -> u64 {
+        today().saturating_sub(self.config.retention_days.saturating_sub(1))
+    }
+
+    /// The number of days kept, and so the longest range that can be shown.
+    pub fn retention_days(&self) -> u64 {
+        self.config.retention_days
+    }
+
+    /// Write the pending counts to storage and drop expired days.
+    pub async fn flush(&self) -> std::io::Result<()> {
+        let pending = std::mem::take(&mut *self.pending.lock().await);
+        let oldest = self.oldest_day();
+        self.store
+            .update(|data| {
+                data.merge(pending);
+                data.prune(oldest);
+            })
+            .await
+    }
+
+    /// Read the stored and pending counts together.
+    pub async fn read<R>(&self, f: impl FnOnce(&AnalyticsData) -> R) -> R {
+        let mut data = self.store.read().await.clone();
+        data.merge(self.pending.lock().await.clone());
+        f(&data)
+    }
+
+    /// Flush every `analytics.flush_interval` seconds.
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        let period = Duration::from_secs(self.config.flush_interval.max(10));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.flush().await {
+                    error!("Failed to save analytics: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// TypeMap key for the analytics counter.
+pub struct AnalyticsKey;
+
+impl TypeMapKey for AnalyticsKey {
+    type Value = Arc<Analytics>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_custom_and_unicode_emojis() {
+        let emojis = extract_emojis("hi <:kurumi:123> 👍🏽 and <a:dance:456> ❤️ ok");
+        assert_eq!(emojis, ["<:kurumi:123>", "<:dance:456>", "👍🏽", "❤️"]);
+        assert!(extract_emojis("no emoji here: 123 <#5>").is_empty());
+    }
+
+    #[test]
+    fn totals_ranges_and_prunes_days() {
+        let (guild, general, memes) = (GuildId(1), ChannelId(10), ChannelId(11));
+        let mut data = AnalyticsData::default();
+        data.record_message(guild, general, vec!["👍".to_string()], 100);
+        data.record_message(guild, memes, Vec::new(), 101);
+        data.record_message(guild, memes, Vec::new(), 102);
+        data.record_reaction(guild, "👍".to_string(), 102);
+
+        assert_eq!(data.channel_totals(guild, 100), [(11, 2), (10, 1)]);
+        assert_eq!(data.channel_totals(guild, 102), [(11, 1)]);
+        assert_eq!(
+            data.daily_messages(guild, 101, 103),
+            [(101, 1), (102, 1), (103, 0)]
+        );
+        let emojis = data.emoji_totals(guild, 0);
+        assert_eq!(
+            emojis[0].1,
+            EmojiCount {
+                messages: 1,
+                reactions: 1
+            }
+        );
+
+        data.prune(102);
+        assert_eq!(data.channel_totals(guild, 0), [(11, 1)]);
+    }
+}
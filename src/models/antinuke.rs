@@ -0,0 +1,214 @@
+//! Anti-nuke protection against a compromised admin or rogue bot wrecking a server.
+//!
+//! Destructive actions are read from the audit log entries Discord streams to the
+//! bot. When one actor does too many of them within a short window, the
+//! [`Tracker`] trips, and the handler strips the actor's roles and alerts the
+//! owner. Guilds opt in with `antinuke on` and list admins it should leave alone
+//! with `antinuke trust`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serenity::model::permissions::Permissions;
+use serenity::prelude::TypeMapKey;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::config::AntinukeConfig;
+use crate::utils::permaudit::DANGEROUS;
+
+/// Audit log action types, see Discord's audit log docs.
+const CHANNEL_DELETE: u8 = 12;
+const MEMBER_BAN_ADD: u8 = 22;
+const ROLE_CREATE: u8 = 30;
+const ROLE_UPDATE: u8 = 31;
+const WEBHOOK_CREATE: u8 = 50;
+
+/// A guild's anti-nuke settings. Changed with `antinuke`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AntinukeSettings {
+    /// Whether actors are stopped when they trip a limit.
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Users who are never stopped, besides the owner and the bot.
+    #[serde(default)]
+    pub trusted: Vec<u64>,
+}
+
+/// A destructive action that counts toward an actor's limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    ChannelDelete,
+    Ban,
+    WebhookCreate,
+    /// Creating a role with, or giving a role, a dangerous permission.
+    RoleEscalation,
+}
+
+impl Action {
+    /// The action an audit log entry records, if it's one that counts.
+    pub fn from_entry(action_type: u8, changes: &[AuditChange]) -> Option<Self> {
+        match action_type {
+            CHANNEL_DELETE => Some(Self::ChannelDelete),
+            MEMBER_BAN_ADD => Some(Self::Ban),
+            WEBHOOK_CREATE => Some(Self::WebhookCreate),
+            ROLE_CREATE | ROLE_UPDATE if escalates(changes) => Some(Self::RoleEscalation),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::ChannelDelete => "deleted channels",
+            Self::Ban => "banned members",
+            Self::WebhookCreate => "created webhooks",
+            Self::RoleEscalation => "gave roles dangerous permissions",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A change in an audit log entry.
+#[derive(Clone, Debug, Deserialize)]
+pub struct AuditChange {
+    pub key: String,
+    #[serde(default)]
+    pub old_value: Option<Value>,
+    #[serde(default)]
+    pub new_value: Option<Value>,
+}
+
+/// Permission bits in a change value, which Discord sends as a string.
+fn permission_bits(value: Option<&Value>) -> u64 {
+    match value {
+        Some(Value::String(bits)) => bits.parse().unwrap_or_default(),
+        Some(Value::Number(bits)) => bits.as_u64().unwrap_or_default(),
+        _ => 0,
+    }
+}
+
+/// Whether a role change adds a permission from [`DANGEROUS`].
+fn escalates(changes: &[AuditChange]) -> bool {
+    changes
+        .iter()
+        .filter(|change| change.key == "permissions")
+        .any(|change| {
+            let old = Permissions::from_bits_truncate(permission_bits(change.old_value.as_ref()));
+            let new = Permissions::from_bits_truncate(permission_bits(change.new_value.as_ref()));
+            !((new - old) & DANGEROUS).is_empty()
+        })
+}
+
+/// Actions within the window, by guild and actor.
+type Recent = HashMap<(u64, u64), Vec<(Action, Instant)>>;
+
+/// Recent actions by each actor, for telling a nuke from ordinary moderation.
+pub struct Tracker {
+    config: AntinukeConfig,
+    recent: Mutex<Recent>,
+}
+
+impl Tracker {
+    /// Create a tracker with the limits from the configuration.
+    pub fn new(config: AntinukeConfig) -> Self {
+        Self {
+            config,
+            recent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// How long actions are counted for.
+    pub fn window(&self) -> Duration {
+        Duration::from_secs(self.config.window)
+    }
+
+    /// How many of an action within the window trip the limit.
+    fn limit(&self, action: Action) -> usize {
+        let limit = match action {
+            Action::ChannelDelete => self.config.channel_deletes,
+            Action::Ban => self.config.bans,
+            Action::WebhookCreate => self.config.webhook_creates,
+            Action::RoleEscalation => self.config.role_escalations,
+        };
+        limit.max(1) as usize
+    }
+
+    /// Count an action, returning how many of it the actor did within the window
+    /// if that trips the limit. The actor's count starts over once it trips.
+    pub fn record(&self, guild_id: u64, actor: u64, action: Action, now: Instant) -> Option<usize> {
+        let window = self.window();
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        recent.retain(|_, actions| {
+            actions.retain(|(_, at)| now.saturating_duration_since(*at) < window);
+            !actions.is_empty()
+        });
+
+        let actions = recent.entry((guild_id, actor)).or_default();
+        actions.push((action, now));
+        let count = actions.iter().filter(|(other, _)| *other == action).count();
+        if count < self.limit(action) {
+            return None;
+        }
+        recent.remove(&(guild_id, actor));
+        Some(count)
+    }
+}
+
+/// TypeMap key for the anti-nuke tracker.
+pub struct AntinukeKey;
+
+impl TypeMapKey for AntinukeKey {
+    type Value = Arc<Tracker>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trips_on_bursts() {
+        let tracker = Tracker::new(AntinukeConfig::default());
+        let start = Instant::now();
+        let later = |secs| start + Duration::from_secs(secs);
+
+        // Spread out deletions never trip
+        for i in 0..5 {
+            let at = later(i * tracker.config.window);
+            assert_eq!(tracker.record(1, 2, Action::ChannelDelete, at), None);
+        }
+
+        let burst = later(100 * tracker.config.window);
+        let limit = tracker.config.channel_deletes as usize;
+        for _ in 1..limit {
+            assert_eq!(tracker.record(1, 3, Action::ChannelDelete, burst), None);
+        }
+        assert_eq!(tracker.record(1, 3, Action::Ban, burst), None);
+        assert_eq!(
+            tracker.record(1, 3, Action::ChannelDelete, burst),
+            Some(limit)
+        );
+        assert_eq!(tracker.record(1, 3, Action::ChannelDelete, burst), None);
+
+        let change = |old: Permissions, new: Permissions| AuditChange {
+            key: "permissions".to_string(),
+            old_value: Some(Value::String(old.bits().to_string())),
+            new_value: Some(Value::String(new.bits().to_string())),
+        };
+        let send = Permissions::SEND_MESSAGES;
+        assert_eq!(
+            Action::from_entry(
+                ROLE_UPDATE,
+                &[change(send, send | Permissions::ADMINISTRATOR)]
+            ),
+            Some(Action::RoleEscalation)
+        );
+        assert_eq!(
+            Action::from_entry(ROLE_UPDATE, &[change(Permissions::BAN_MEMBERS, send)]),
+            None
+        );
+    }
+}
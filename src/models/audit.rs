@@ -0,0 +1,209 @@
+//! Internal audit log of privileged actions the bot performs.
+
+use serde::{Deserialize, Serialize};
+use serenity::model::id::{GuildId, UserId};
+use serenity::prelude::*;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Arc;
+use tracing::error;
+
+use crate::storage::JsonStore;
+use crate::utils::constants::AUDIT_LOG_MAX_ENTRIES;
+use crate::utils::helpers::unix_timestamp;
+
+/// What made the bot perform an action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditSource {
+    /// A text command.
+    Command,
+    /// A button or other message component.
+    Interaction,
+    /// An external API, such as a dashboard.
+    Api,
+    /// Automatic moderation.
+    Automod,
+    /// Votes from members, such as pin voting.
+    Vote,
+}
+
+impl fmt::Display for AuditSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Command => "command",
+            Self::Interaction => "interaction",
+            Self::Api => "api",
+            Self::Automod => "automod",
+            Self::Vote => "vote",
+        };
+        f.write_str(name)
+    }
+}
+
+impl std::str::FromStr for AuditSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "command" => Ok(Self::Command),
+            "interaction" => Ok(Self::Interaction),
+            "api" => Ok(Self::Api),
+            "automod" => Ok(Self::Automod),
+            "vote" => Ok(Self::Vote),
+            _ => Err(format!("Unknown source `{}`.", s)),
+        }
+    }
+}
+
+/// A privileged action the bot performed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Entry ID, increasing with each action.
+    pub id: u64,
+    /// The guild the action happened in, if any.
+    pub guild_id: Option<u64>,
+    /// The user the bot acted for, if any.
+    pub actor_id: Option<u64>,
+    pub source: AuditSource,
+    /// What the bot did, such as "Ban <@123>".
+    pub action: String,
+    pub reason: Option<String>,
+    /// When the action happened (seconds since the Unix epoch).
+    pub created_at: u64,
+}
+
+/// An action to record, see [`record`].
+#[derive(Clone, Debug)]
+pub struct AuditEvent {
+    pub guild_id: Option<GuildId>,
+    pub actor_id: Option<UserId>,
+    pub source: AuditSource,
+    pub action: String,
+    pub reason: Option<String>,
+}
+
+/// Which entries to list.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditFilter {
+    All,
+    Guild(GuildId),
+    Actor(UserId),
+    Source(AuditSource),
+}
+
+impl AuditFilter {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        match self {
+            Self::All => true,
+            Self::Guild(guild_id) => entry.guild_id == Some(guild_id.0),
+            Self::Actor(user_id) => entry.actor_id == Some(user_id.0),
+            Self::Source(source) => entry.source == *source,
+        }
+    }
+}
+
+/// The most recent audit entries, oldest first.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AuditLog {
+    /// The last ID handed out to an entry.
+    #[serde(default)]
+    pub last_id: u64,
+    #[serde(default)]
+    pub entries: VecDeque<AuditEntry>,
+}
+
+impl AuditLog {
+    /// Add an entry and return its ID, dropping the oldest entries past the limit.
+    pub fn push(&mut self, event: AuditEvent) -> u64 {
+        self.last_id += 1;
+        self.entries.push_back(AuditEntry {
+            id: self.last_id,
+            guild_id: event.guild_id.map(|id| id.0),
+            actor_id: event.actor_id.map(|id| id.0),
+            source: event.source,
+            action: event.action,
+            reason: event.reason,
+            created_at: unix_timestamp(),
+        });
+        while self.entries.len() > AUDIT_LOG_MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.last_id
+    }
+
+    /// Get the entries matching a filter, newest first.
+    pub fn query(&self, filter: AuditFilter) -> Vec<&AuditEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(|entry| filter.matches(entry))
+            .collect()
+    }
+}
+
+/// TypeMap key for the audit log store.
+pub struct AuditKey;
+
+impl TypeMapKey for AuditKey {
+    type Value = Arc<JsonStore<AuditLog>>;
+}
+
+/// Record an action in the audit log.
+///
+/// Failures are logged rather than returned, so that auditing never undoes an
+/// action that already happened.
+pub async fn record(ctx: &Context, event: AuditEvent) {
+    let store = {
+        let data = ctx.data.read().await;
+        data.get::<AuditKey>().cloned()
+    };
+    let store = match store {
+        Some(store) => store,
+        None => return,
+    };
+
+    if let Err(e) = store.update(|log| log.push(event)).await {
+        error!("Failed to record audit entry: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(guild: u64, actor: u64, source: AuditSource) -> AuditEvent {
+        AuditEvent {
+            guild_id: Some(GuildId(guild)),
+            actor_id: Some(UserId(actor)),
+            source,
+            action: "Ban <@1>".to_string(),
+            reason: None,
+        }
+    }
+
+    #[test]
+    fn queries_newest_first() {
+        let mut log = AuditLog::default();
+        log.push(event(1, 10, AuditSource::Command));
+        log.push(event(2, 10, AuditSource::Interaction));
+        log.push(event(1, 11, AuditSource::Command));
+
+        let ids = |filter| -> Vec<u64> { log.query(filter).iter().map(|e| e.id).collect() };
+        assert_eq!(ids(AuditFilter::All), [3, 2, 1]);
+        assert_eq!(ids(AuditFilter::Guild(GuildId(1))), [3, 1]);
+        assert_eq!(ids(AuditFilter::Actor(UserId(10))), [2, 1]);
+        assert_eq!(ids(AuditFilter::Source(AuditSource::Interaction)), [2]);
+    }
+
+    #[test]
+    fn drops_the_oldest_entries() {
+        let mut log = AuditLog::default();
+        for _ in 0..AUDIT_LOG_MAX_ENTRIES + 5 {
+            log.push(event(1, 10, AuditSource::Command));
+        }
+
+        assert_eq!(log.entries.len(), AUDIT_LOG_MAX_ENTRIES);
+        assert_eq!(log.entries.front().unwrap().id, 6);
+    }
+}
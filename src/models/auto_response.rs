@@ -0,0 +1,279 @@
+//! Auto-response rules: triggers matched against messages and what to reply with.
+
+use serde::{Deserialize, Serialize};
+use serenity::model::id::{ChannelId, GuildId};
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::storage::JsonStore;
+
+/// How a trigger is matched against a message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerKind {
+    /// The whole message equals the pattern, ignoring case.
+    Exact,
+    /// The message contains the pattern, ignoring case.
+    Contains,
+    /// The message matches a regular expression.
+    Regex,
+}
+
+impl fmt::Display for TriggerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Exact => "exact",
+            Self::Contains => "contains",
+            Self::Regex => "regex",
+        };
+        f.write_str(name)
+    }
+}
+
+impl std::str::FromStr for TriggerKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "exact" => Ok(Self::Exact),
+            "contains" => Ok(Self::Contains),
+            "regex" => Ok(Self::Regex),
+            _ => Err(format!(
+                "Unknown trigger type `{}`. Use exact, contains or regex.",
+                s
+            )),
+        }
+    }
+}
+
+/// What the bot does when a rule is triggered.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum Response {
+    /// Reply with a message, see [`render_template`].
+    Text(String),
+    /// Reply with an embed whose description is a template.
+    Embed(String),
+    /// React to the message with an emoji.
+    Reaction(String),
+}
+
+impl Response {
+    /// Parse a response: `react:<emoji>`, `embed:<template>`, or a plain template.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        let response = if let Some(emoji) = input.strip_prefix("react:") {
+            Self::Reaction(emoji.trim().to_string())
+        } else if let Some(template) = input.strip_prefix("embed:") {
+            Self::Embed(template.trim().to_string())
+        } else {
+            Self::Text(input.to_string())
+        };
+
+        match &response {
+            Self::Text(value) | Self::Embed(value) | Self::Reaction(value) if value.is_empty() => {
+                Err("The response can't be empty.".to_string())
+            }
+            _ => Ok(response),
+        }
+    }
+}
+
+impl fmt::Display for Response {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Text(template) => write!(f, "{}", template),
+            Self::Embed(template) => write!(f, "embed: {}", template),
+            Self::Reaction(emoji) => write!(f, "react: {}", emoji),
+        }
+    }
+}
+
+/// Values substituted into response templates.
+pub struct TemplateContext<'a> {
+    pub user_id: u64,
+    pub user_name: &'a str,
+    pub channel_id: u64,
+    pub server_name: &'a str,
+}
+
+/// Fill in a response template.
+///
+/// Supports `{user}` (a mention), `{user.name}`, `{channel}` and `{server}`.
+pub fn render_template(template: &str, ctx: &TemplateContext<'_>) -> String {
+    template
+        .replace("{user.name}", ctx.user_name)
+        .replace("{user}", &format!("<@{}>", ctx.user_id))
+        .replace("{channel}", &format!("<#{}>", ctx.channel_id))
+        .replace("{server}", ctx.server_name)
+}
+
+/// A rule that answers messages matching a trigger.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AutoResponse {
+    /// Rule ID, unique within the guild.
+    pub id: u64,
+    pub trigger: TriggerKind,
+    pub pattern: String,
+    pub response: Response,
+    /// Channels the rule applies in. Empty means every channel.
+    #[serde(default)]
+    pub channels: Vec<u64>,
+    /// Percent chance of responding when triggered.
+    #[serde(default = "default_chance")]
+    pub chance: u8,
+    /// Minimum time between responses in the same channel, in seconds.
+    #[serde(default)]
+    pub cooldown: u64,
+    /// Who created the rule.
+    pub created_by: u64,
+}
+
+fn default_chance() -> u8 {
+    100
+}
+
+impl AutoResponse {
+    /// Whether the rule applies in a channel.
+    pub fn applies_in(&self, channel_id: ChannelId) -> bool {
+        self.channels.is_empty() || self.channels.contains(&channel_id.0)
+    }
+
+    /// Whether a message matches an exact or contains trigger.
+    ///
+    /// Regex triggers are matched by the caller, which keeps the compiled regex.
+    pub fn matches_text(&self, content: &str) -> bool {
+        match self.trigger {
+            TriggerKind::Exact => content.trim().to_lowercase() == self.pattern.to_lowercase(),
+            TriggerKind::Contains => content
+                .to_lowercase()
+                .contains(&self.pattern.to_lowercase()),
+            TriggerKind::Regex => false,
+        }
+    }
+}
+
+/// Auto-response rules for a single guild.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GuildAutoResponses {
+    /// The last ID handed out to a rule.
+    #[serde(default)]
+    pub last_id: u64,
+    #[serde(default)]
+    pub rules: Vec<AutoResponse>,
+}
+
+impl GuildAutoResponses {
+    /// Add a rule and return its ID.
+    pub fn add(
+        &mut self,
+        trigger: TriggerKind,
+        pattern: String,
+        response: Response,
+        created_by: u64,
+    ) -> u64 {
+        self.last_id += 1;
+        self.rules.push(AutoResponse {
+            id: self.last_id,
+            trigger,
+            pattern,
+            response,
+            channels: Vec::new(),
+            chance: default_chance(),
+            cooldown: 0,
+            created_by,
+        });
+        self.last_id
+    }
+
+    /// Get a rule to change it.
+    pub fn get_mut(&mut self, id: u64) -> Option<&mut AutoResponse> {
+        self.rules.iter_mut().find(|rule| rule.id == id)
+    }
+
+    /// Remove a rule. Returns whether it existed.
+    pub fn remove(&mut self, id: u64) -> bool {
+        let count = self.rules.len();
+        self.rules.retain(|rule| rule.id != id);
+        self.rules.len() != count
+    }
+}
+
+/// Auto-response rules for all guilds.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AutoResponseData {
+    /// Rules by guild ID.
+    #[serde(default)]
+    pub guilds: HashMap<u64, GuildAutoResponses>,
+}
+
+impl AutoResponseData {
+    /// Get a guild's rules, if it has any.
+    pub fn guild(&self, guild_id: GuildId) -> Option<&GuildAutoResponses> {
+        self.guilds.get(&guild_id.0)
+    }
+
+    /// Get a mutable reference to a guild's rules, creating them if needed.
+    pub fn guild_mut(&mut self, guild_id: GuildId) -> &mut GuildAutoResponses {
+        self.guilds.entry(guild_id.0).or_default()
+    }
+}
+
+/// TypeMap key for the auto-response store.
+pub struct AutoResponseKey;
+
+impl TypeMapKey for AutoResponseKey {
+    type Value = Arc<JsonStore<AutoResponseData>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::helpers::compile_regex;
+
+    fn rule(trigger: TriggerKind, pattern: &str) -> AutoResponse {
+        let mut rules = GuildAutoResponses::default();
+        rules.add(
+            trigger,
+            pattern.to_string(),
+            Response::Text("hi".to_string()),
+            1,
+        );
+        rules.rules.remove(0)
+    }
+
+    #[test]
+    fn matches_text_triggers_ignoring_case() {
+        assert!(rule(TriggerKind::Exact, "Hello").matches_text(" hello "));
+        assert!(!rule(TriggerKind::Exact, "hello").matches_text("hello there"));
+        assert!(rule(TriggerKind::Contains, "rust").matches_text("I like RUST"));
+        assert!(compile_regex("^gm+$").unwrap().is_match("GMMM"));
+        assert!(compile_regex("(").is_err());
+    }
+
+    #[test]
+    fn parses_responses_and_renders_templates() {
+        assert_eq!(
+            Response::parse("react: 🎉"),
+            Ok(Response::Reaction("🎉".to_string()))
+        );
+        assert_eq!(
+            Response::parse("embed:Welcome {user}"),
+            Ok(Response::Embed("Welcome {user}".to_string()))
+        );
+        assert!(Response::parse("react:").is_err());
+
+        let ctx = TemplateContext {
+            user_id: 5,
+            user_name: "kurumi",
+            channel_id: 6,
+            server_name: "Tokisaki",
+        };
+        assert_eq!(
+            render_template("Hi {user} ({user.name}) in {channel} on {server}", &ctx),
+            "Hi <@5> (kurumi) in <#6> on Tokisaki"
+        );
+    }
+}
@@ -0,0 +1,194 @@
+//! Moving a guild's settings between servers as a file.
+//!
+//! `settings export` writes the whole [`GuildConfig`] as JSON or TOML, and
+//! `settings import` reads such a file back, showing what would change before
+//! anything is applied.
+
+use serde_json::{Map, Value};
+
+use super::guild_config::GuildConfig;
+use crate::utils::helpers::truncate;
+
+/// Largest file `settings import` reads, in bytes.
+pub const MAX_IMPORT_SIZE: u64 = 256 * 1024;
+
+/// Longest value shown in a diff line, in characters.
+const MAX_DIFF_VALUE: usize = 80;
+
+/// A file format settings can be exported in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    /// Pretty-printed JSON.
+    #[default]
+    Json,
+    /// TOML, like the bot's own config file.
+    Toml,
+}
+
+impl Format {
+    /// The file extension for the format, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Toml => "toml",
+        }
+    }
+
+    /// Guess the format of a file from its name.
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        let extension = name.rsplit_once('.')?.1;
+        extension.parse().ok()
+    }
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "toml" => Ok(Self::Toml),
+            _ => Err(format!("Unknown format `{}`. Use json or toml.", s)),
+        }
+    }
+}
+
+/// Leave unset values out of nested objects, which TOML has no way to write.
+fn strip_nulls(object: &mut Map<String, Value>) {
+    object.retain(|_, value| !value.is_null());
+    for value in object.values_mut() {
+        if let Value::Object(inner) = value {
+            strip_nulls(inner);
+        }
+    }
+}
+
+/// The settings as a JSON object with unset values left out.
+fn to_object(config: &GuildConfig) -> Map<String, Value> {
+    match serde_json::to_value(config) {
+        Ok(Value::Object(mut object)) => {
+            strip_nulls(&mut object);
+            object
+        }
+        _ => Map::new(),
+    }
+}
+
+/// Write the settings in the given format.
+pub fn export(config: &GuildConfig, format: Format) -> Result<String, String> {
+    let object = to_object(config);
+    match format {
+        Format::Json => serde_json::to_string_pretty(&object).map_err(|e| e.to_string()),
+        // TOML only has string keys, so go through JSON, which turns channel IDs
+        // into strings too
+        Format::Toml => toml::to_string_pretty(&object).map_err(|e| e.to_string()),
+    }
+}
+
+/// Read settings written by [`export`], rejecting unknown settings.
+pub fn import(content: &str, format: Format) -> Result<GuildConfig, String> {
+    let value = match format {
+        Format::Json => serde_json::from_str::<Value>(content)
+            .map_err(|e| format!("That isn't valid JSON: {}", e))?,
+        Format::Toml => {
+            let table = toml::from_str::<toml::Table>(content)
+                .map_err(|e| format!("That isn't valid TOML: {}", e.message()))?;
+            serde_json::to_value(table).map_err(|e| e.to_string())?
+        }
+    };
+
+    let object = match &value {
+        Value::Object(object) => object,
+        _ => return Err("Expected a table of settings.".to_string()),
+    };
+    let known = serde_json::to_value(GuildConfig::default()).map_err(|e| e.to_string())?;
+    let mut unknown: Vec<&str> = object
+        .keys()
+        .filter(|key| known.get(key.as_str()).is_none())
+        .map(String::as_str)
+        .collect();
+    if !unknown.is_empty() {
+        unknown.sort_unstable();
+        return Err(format!("Unknown settings: `{}`.", unknown.join("`, `")));
+    }
+
+    serde_json::from_value(value).map_err(|e| format!("Invalid settings: {}", e))
+}
+
+/// Keep the current settings that only the server owner can change, for imports
+/// by anyone else.
+pub fn keep_owner_settings(imported: &mut GuildConfig, current: &GuildConfig) {
+    imported.antinuke = current.antinuke.clone();
+}
+
+/// Describe each setting that differs, as `` `key`: old → new ``.
+pub fn diff(old: &GuildConfig, new: &GuildConfig) -> Vec<String> {
+    let old = to_object(old);
+    let new = to_object(new);
+    let describe = |value: Option<&Value>| match value {
+        Some(value) => truncate(&value.to_string(), MAX_DIFF_VALUE),
+        None => "not set".to_string(),
+    };
+
+    let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+    keys.sort_unstable();
+    keys.dedup();
+    keys.into_iter()
+        .filter(|key| old.get(*key) != new.get(*key))
+        .map(|key| {
+            format!(
+                "`{}`: {} → {}",
+                key,
+                describe(old.get(key)),
+                describe(new.get(key))
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::guild_config::AutoPublish;
+
+    #[test]
+    fn round_trips_and_diffs() {
+        let mut config = GuildConfig::default();
+        config.set("staff_channel", "123").unwrap();
+        config.set("inline_commands", "both").unwrap();
+        config.auto_publish.insert(456, AutoPublish::Everyone);
+
+        for format in [Format::Json, Format::Toml] {
+            let exported = export(&config, format).unwrap();
+            let imported = import(&exported, format).unwrap();
+            assert!(diff(&config, &imported).is_empty(), "{:?}", format);
+        }
+
+        let changes = diff(&GuildConfig::default(), &config);
+        assert!(changes.contains(&"`staff_channel`: not set → 123".to_string()));
+        assert_eq!(changes.len(), 3);
+
+        assert!(import("prefix = \"!\"", Format::Toml)
+            .unwrap_err()
+            .contains("`prefix`"));
+        assert!(import("[1, 2]", Format::Json).is_err());
+        assert_eq!(Format::from_file_name("kurumi.TOML"), Some(Format::Toml));
+    }
+
+    #[test]
+    fn keeps_owner_settings() {
+        let mut current = GuildConfig::default();
+        current.antinuke.enabled = true;
+        current.antinuke.trusted.push(1);
+
+        let mut imported = import(
+            r#"{"antinuke": {"enabled": false, "trusted": [2]}}"#,
+            Format::Json,
+        )
+        .unwrap();
+        keep_owner_settings(&mut imported, &current);
+        assert!(imported.antinuke.enabled);
+        assert_eq!(imported.antinuke.trusted, [1]);
+        assert!(diff(&current, &imported).is_empty());
+    }
+}
@@ -0,0 +1,115 @@
+//! Subreddit feeds announced in channels.
+
+use serde::{Deserialize, Serialize};
+use serenity::model::id::{ChannelId, GuildId};
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::storage::JsonStore;
+
+/// A subreddit whose new posts are announced in a channel.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Feed {
+    pub subreddit: String,
+    pub channel_id: u64,
+    pub added_by: u64,
+    pub added_at: u64,
+    /// Creation time of the newest post announced (seconds since the Unix
+    /// epoch). Starts at the time the feed was added, so old posts aren't posted.
+    pub last_created: f64,
+}
+
+/// Every guild's feeds.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FeedData {
+    #[serde(default)]
+    pub guilds: HashMap<u64, Vec<Feed>>,
+}
+
+impl FeedData {
+    /// A guild's feeds.
+    pub fn list(&self, guild_id: GuildId) -> &[Feed] {
+        self.guilds.get(&guild_id.0).map_or(&[], Vec::as_slice)
+    }
+
+    /// Add a feed, returning false if the channel already has the subreddit.
+    pub fn add(&mut self, guild_id: GuildId, feed: Feed) -> bool {
+        let feeds = self.guilds.entry(guild_id.0).or_default();
+        if feeds
+            .iter()
+            .any(|f| f.subreddit == feed.subreddit && f.channel_id == feed.channel_id)
+        {
+            return false;
+        }
+        feeds.push(feed);
+        true
+    }
+
+    /// Remove a subreddit from a channel, returning whether it was there.
+    pub fn remove(&mut self, guild_id: GuildId, subreddit: &str, channel_id: ChannelId) -> bool {
+        let feeds = match self.guilds.get_mut(&guild_id.0) {
+            Some(feeds) => feeds,
+            None => return false,
+        };
+        let before = feeds.len();
+        feeds.retain(|f| !(f.subreddit == subreddit && f.channel_id == channel_id.0));
+        let removed = feeds.len() != before;
+        if feeds.is_empty() {
+            self.guilds.remove(&guild_id.0);
+        }
+        removed
+    }
+
+    /// Every subreddit with at least one feed.
+    pub fn subreddits(&self) -> Vec<String> {
+        let mut subreddits: Vec<String> = self
+            .guilds
+            .values()
+            .flatten()
+            .map(|f| f.subreddit.clone())
+            .collect();
+        subreddits.sort();
+        subreddits.dedup();
+        subreddits
+    }
+}
+
+/// TypeMap key for the feed store.
+pub struct FeedKey;
+
+impl TypeMapKey for FeedKey {
+    type Value = Arc<JsonStore<FeedData>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(subreddit: &str, channel_id: u64) -> Feed {
+        Feed {
+            subreddit: subreddit.to_string(),
+            channel_id,
+            added_by: 1,
+            added_at: 0,
+            last_created: 0.0,
+        }
+    }
+
+    #[test]
+    fn adds_and_removes_feeds() {
+        let guild = GuildId(1);
+        let mut data = FeedData::default();
+        assert!(data.add(guild, feed("rust", 10)));
+        assert!(!data.add(guild, feed("rust", 10)));
+        assert!(data.add(guild, feed("rust", 11)));
+        assert!(data.add(GuildId(2), feed("memes", 12)));
+        assert_eq!(data.subreddits(), ["memes", "rust"]);
+
+        assert!(data.remove(guild, "rust", ChannelId(10)));
+        assert!(!data.remove(guild, "rust", ChannelId(10)));
+        assert_eq!(data.list(guild).len(), 1);
+        assert!(data.remove(guild, "rust", ChannelId(11)));
+        assert!(data.list(guild).is_empty());
+    }
+}
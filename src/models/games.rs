@@ -0,0 +1,283 @@
+//! Counting and word chain game channels.
+
+use serde::{Deserialize, Serialize};
+use serenity::model::id::{ChannelId, UserId};
+use serenity::prelude::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+
+use crate::storage::JsonStore;
+
+/// The game played in a channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameKind {
+    /// Members count up from 1, one number per message.
+    Counting,
+    /// Each word starts with the last letter of the word before it.
+    WordChain,
+}
+
+impl GameKind {
+    /// How often a streak is celebrated.
+    pub fn milestone(self) -> u64 {
+        match self {
+            Self::Counting => 100,
+            Self::WordChain => 50,
+        }
+    }
+}
+
+impl fmt::Display for GameKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Counting => "counting",
+            Self::WordChain => "wordchain",
+        };
+        f.write_str(name)
+    }
+}
+
+impl std::str::FromStr for GameKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "counting" | "count" => Ok(Self::Counting),
+            "wordchain" | "words" => Ok(Self::WordChain),
+            _ => Err(format!("Unknown game `{}`. Use counting or wordchain.", s)),
+        }
+    }
+}
+
+/// The state of a game in one channel.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameChannel {
+    pub kind: GameKind,
+    /// Whether a wrong entry ends the streak, rather than only being deleted.
+    #[serde(default)]
+    pub reset_on_fail: bool,
+    /// Valid entries since the game last started.
+    #[serde(default)]
+    pub streak: u64,
+    /// Who made the last valid entry; nobody may go twice in a row.
+    #[serde(default)]
+    pub last_user: Option<u64>,
+    /// The last word of a word chain.
+    #[serde(default)]
+    pub last_word: Option<String>,
+    /// Words already used in the current word chain.
+    #[serde(default)]
+    pub used_words: HashSet<String>,
+    /// The guild's record for this game when the current streak started.
+    #[serde(default)]
+    pub record_to_beat: u64,
+}
+
+/// What happened to a message posted in a game channel.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Play {
+    /// The entry was valid.
+    Valid {
+        streak: u64,
+        /// The streak reached a multiple of the game's milestone.
+        milestone: bool,
+        /// The streak just beat the guild's previous record.
+        record: bool,
+    },
+    /// The entry broke the rules and should be deleted.
+    Invalid {
+        reason: &'static str,
+        /// The streak that ended, if the channel resets on wrong entries.
+        ended: Option<u64>,
+    },
+}
+
+impl GameChannel {
+    /// Start a new game in a channel.
+    pub fn new(kind: GameKind, reset_on_fail: bool) -> Self {
+        Self {
+            kind,
+            reset_on_fail,
+            streak: 0,
+            last_user: None,
+            last_word: None,
+            used_words: HashSet::new(),
+            record_to_beat: 0,
+        }
+    }
+
+    /// Start the game over.
+    pub fn restart(&mut self) {
+        *self = Self::new(self.kind, self.reset_on_fail);
+    }
+
+    /// What the next entry must be, for telling players.
+    pub fn next_hint(&self) -> String {
+        match (self.kind, &self.last_word) {
+            (GameKind::Counting, _) => format!("the next number is **{}**", self.streak + 1),
+            (GameKind::WordChain, Some(word)) => match word.chars().last() {
+                Some(letter) => format!("the next word starts with **{}**", letter),
+                None => "any word can start the chain".to_string(),
+            },
+            (GameKind::WordChain, None) => "any word can start the chain".to_string(),
+        }
+    }
+
+    /// Check an entry against the rules, returning the normalized word or number if valid.
+    fn check(&self, user_id: UserId, content: &str) -> Result<Option<String>, &'static str> {
+        if self.last_user == Some(user_id.0) {
+            return Err("Wait for someone else to go first.");
+        }
+
+        match self.kind {
+            GameKind::Counting => {
+                let number = content.split_whitespace().next().unwrap_or_default();
+                match number.parse::<u64>() {
+                    Ok(number) if number == self.streak + 1 => Ok(None),
+                    _ => Err("That isn't the next number."),
+                }
+            }
+            GameKind::WordChain => {
+                let word = content.trim().to_lowercase();
+                if word.chars().count() < 2 || !word.chars().all(char::is_alphabetic) {
+                    return Err("Entries must be a single word.");
+                }
+                let last_letter = self.last_word.as_ref().and_then(|last| last.chars().last());
+                if last_letter.is_some_and(|letter| !word.starts_with(letter)) {
+                    return Err("That word doesn't start with the last letter.");
+                }
+                if self.used_words.contains(&word) {
+                    return Err("That word was already used.");
+                }
+                Ok(Some(word))
+            }
+        }
+    }
+}
+
+/// Game channels and records for a single guild.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GuildGames {
+    /// Games by channel ID.
+    #[serde(default)]
+    pub channels: HashMap<u64, GameChannel>,
+    /// The longest streak reached in each game.
+    #[serde(default)]
+    pub records: BTreeMap<GameKind, u64>,
+}
+
+impl GuildGames {
+    /// Play an entry in a channel. Returns `None` if the channel has no game.
+    pub fn play(&mut self, channel_id: ChannelId, user_id: UserId, content: &str) -> Option<Play> {
+        let game = self.channels.get_mut(&channel_id.0)?;
+
+        let word = match game.check(user_id, content) {
+            Ok(word) => word,
+            Err(reason) => {
+                let ended = (game.reset_on_fail && game.streak > 0).then_some(game.streak);
+                if ended.is_some() {
+                    game.restart();
+                }
+                return Some(Play::Invalid { reason, ended });
+            }
+        };
+
+        let record = self.records.entry(game.kind).or_default();
+        if game.streak == 0 {
+            game.record_to_beat = *record;
+        }
+        game.streak += 1;
+        *record = (*record).max(game.streak);
+        game.last_user = Some(user_id.0);
+        if let Some(word) = word {
+            game.used_words.insert(word.clone());
+            game.last_word = Some(word);
+        }
+
+        Some(Play::Valid {
+            streak: game.streak,
+            milestone: game.streak % game.kind.milestone() == 0,
+            record: game.record_to_beat > 0 && game.streak == game.record_to_beat + 1,
+        })
+    }
+}
+
+/// Game data for all guilds.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GamesData {
+    /// Game data by guild ID.
+    #[serde(default)]
+    pub guilds: HashMap<u64, GuildGames>,
+}
+
+/// TypeMap key for the games store.
+pub struct GamesKey;
+
+impl TypeMapKey for GamesKey {
+    type Value = Arc<JsonStore<GamesData>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guild(kind: GameKind, reset_on_fail: bool) -> GuildGames {
+        let mut games = GuildGames::default();
+        games
+            .channels
+            .insert(1, GameChannel::new(kind, reset_on_fail));
+        games
+    }
+
+    #[test]
+    fn counts_in_turns_and_resets_on_failure() {
+        let mut games = guild(GameKind::Counting, true);
+        let play = |games: &mut GuildGames, user, content| {
+            games.play(ChannelId(1), UserId(user), content).unwrap()
+        };
+
+        assert!(matches!(
+            play(&mut games, 10, "1"),
+            Play::Valid { streak: 1, .. }
+        ));
+        assert!(matches!(
+            play(&mut games, 10, "2"),
+            Play::Invalid { ended: Some(1), .. }
+        ));
+        assert!(matches!(
+            play(&mut games, 11, "1 nice"),
+            Play::Valid { streak: 1, .. }
+        ));
+        assert!(matches!(
+            play(&mut games, 10, "2"),
+            Play::Valid {
+                streak: 2,
+                record: true,
+                ..
+            }
+        ));
+        assert!(matches!(
+            play(&mut games, 11, "3"),
+            Play::Valid { record: false, .. }
+        ));
+        assert_eq!(games.records[&GameKind::Counting], 3);
+        assert!(games.play(ChannelId(2), UserId(10), "1").is_none());
+    }
+
+    #[test]
+    fn chains_words_by_last_letter() {
+        let mut games = guild(GameKind::WordChain, false);
+        let mut play = |user, content| games.play(ChannelId(1), UserId(user), content).unwrap();
+
+        assert!(matches!(play(10, "Apple"), Play::Valid { streak: 1, .. }));
+        assert!(matches!(
+            play(11, "banana"),
+            Play::Invalid { ended: None, .. }
+        ));
+        assert!(matches!(play(11, "two words"), Play::Invalid { .. }));
+        assert!(matches!(play(11, "eagle"), Play::Valid { streak: 2, .. }));
+        assert!(matches!(play(10, "apple"), Play::Invalid { .. }));
+    }
+}
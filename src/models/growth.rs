@@ -0,0 +1,116 @@
+//! Daily member counts and milestone tracking.
+//!
+//! The count is saved whenever someone joins or leaves, so each day keeps the
+//! last count seen that day. Days without joins or leaves carry the previous
+//! count forward when shown.
+
+use serde::{Deserialize, Serialize};
+use serenity::model::id::GuildId;
+use serenity::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use crate::storage::JsonStore;
+
+/// Member counts that are announced when no milestones are configured.
+pub const DEFAULT_MILESTONES: [u64; 14] = [
+    50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 500_000,
+    1_000_000,
+];
+
+/// A guild's member counts, by day number.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GuildGrowth {
+    #[serde(default)]
+    pub counts: BTreeMap<u64, u64>,
+    /// The highest milestone reached, which isn't announced again if the count
+    /// drops below it and climbs back.
+    #[serde(default)]
+    pub last_milestone: u64,
+}
+
+impl GuildGrowth {
+    /// Save the member count for a day and return the milestone it newly reaches,
+    /// if any. The first count seen only sets the starting point, so adding the
+    /// bot to a large server doesn't announce every milestone below its size.
+    pub fn record(&mut self, count: u64, day: u64, milestones: &[u64]) -> Option<u64> {
+        let first = self.counts.is_empty();
+        self.counts.insert(day, count);
+
+        let reached = milestones
+            .iter()
+            .copied()
+            .filter(|milestone| count >= *milestone)
+            .max()?;
+        if reached <= self.last_milestone {
+            return None;
+        }
+        self.last_milestone = reached;
+        (!first).then_some(reached)
+    }
+
+    /// The member count on each day from `since` to `until`, carrying counts
+    /// forward over days without one. Days before the first count are left out.
+    pub fn series(&self, since: u64, until: u64) -> Vec<(u64, u64)> {
+        let mut current = self.counts.range(..since).next_back().map(|(_, c)| *c);
+        (since..=until)
+            .filter_map(|day| {
+                if let Some(count) = self.counts.get(&day) {
+                    current = Some(*count);
+                }
+                current.map(|count| (day, count))
+            })
+            .collect()
+    }
+}
+
+/// Every guild's member counts.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GrowthData {
+    #[serde(default)]
+    pub guilds: HashMap<u64, GuildGrowth>,
+}
+
+impl GrowthData {
+    /// A guild's counts, creating them if needed.
+    pub fn entry(&mut self, guild_id: GuildId) -> &mut GuildGrowth {
+        self.guilds.entry(guild_id.0).or_default()
+    }
+}
+
+/// TypeMap key for the member count store.
+pub struct GrowthKey;
+
+impl TypeMapKey for GrowthKey {
+    type Value = Arc<JsonStore<GrowthData>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn announces_each_milestone_once() {
+        let milestones = [100, 500, 1_000];
+        let mut growth = GuildGrowth::default();
+        assert_eq!(growth.record(600, 1, &milestones), None);
+        assert_eq!(growth.last_milestone, 500);
+
+        assert_eq!(growth.record(990, 2, &milestones), None);
+        assert_eq!(growth.record(1_001, 3, &milestones), Some(1_000));
+        assert_eq!(growth.record(999, 3, &milestones), None);
+        assert_eq!(growth.record(1_002, 4, &milestones), None);
+    }
+
+    #[test]
+    fn carries_counts_over_missing_days() {
+        let mut growth = GuildGrowth::default();
+        growth.record(10, 5, &[]);
+        growth.record(12, 8, &[]);
+        assert_eq!(
+            growth.series(4, 9),
+            [(5, 10), (6, 10), (7, 10), (8, 12), (9, 12)]
+        );
+        assert_eq!(growth.series(7, 7), [(7, 10)]);
+    }
+}
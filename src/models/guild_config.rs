@@ -0,0 +1,604 @@
+//! Per-guild configuration models.
+
+use serde::{Deserialize, Serialize};
+use serenity::model::id::{ChannelId, GuildId};
+use serenity::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+use super::antinuke::AntinukeSettings;
+use super::growth::DEFAULT_MILESTONES;
+use super::ignore::IgnoreList;
+use super::music::MusicSettings;
+use super::onboarding::OnboardingFlow;
+use super::permission_templates::PermissionTemplate;
+use super::pin_votes::PinVoteSettings;
+use super::voice_text::VoiceTextSettings;
+use crate::storage::JsonStore;
+use crate::utils::helpers::{parse_channel, parse_role};
+
+/// Settings that guild admins can change for their own server.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GuildConfig {
+    /// Channel where staff alerts are posted.
+    #[serde(default)]
+    pub staff_channel: Option<u64>,
+
+    /// Role that is pinged for staff alerts.
+    #[serde(default)]
+    pub staff_role: Option<u64>,
+
+    /// Role staff can give members to stop them talking, created by `setup`.
+    #[serde(default)]
+    pub mute_role: Option<u64>,
+
+    /// Channel where modmail conversations are opened as threads.
+    #[serde(default)]
+    pub modmail_channel: Option<u64>,
+
+    /// Channel where member count milestones are announced.
+    #[serde(default)]
+    pub milestone_channel: Option<u64>,
+
+    /// Member counts to announce. Empty uses [`DEFAULT_MILESTONES`].
+    #[serde(default)]
+    pub milestones: Vec<u64>,
+
+    /// Channel where unpinned messages and overflowing pins are archived.
+    #[serde(default)]
+    pub pin_archive_channel: Option<u64>,
+
+    /// Channel where edited and deleted messages are logged.
+    #[serde(default)]
+    pub message_log_channel: Option<u64>,
+
+    /// How many reactions pin a message, per channel. Changed with `pinvote`.
+    #[serde(default)]
+    pub pin_votes: PinVoteSettings,
+
+    /// Days to keep moderation cases, overriding `retention.cases`.
+    #[serde(default)]
+    pub case_retention: Option<u64>,
+
+    /// Days to keep modmail transcripts, overriding `retention.message_logs`.
+    #[serde(default)]
+    pub message_log_retention: Option<u64>,
+
+    /// Announcement channels whose messages are published automatically, by channel ID.
+    #[serde(default)]
+    pub auto_publish: HashMap<u64, AutoPublish>,
+
+    /// What happens to messages with phishing links. Empty turns link scanning off.
+    #[serde(default)]
+    pub phishing_actions: Vec<PhishingAction>,
+
+    /// Whether messages with tokens, webhook URLs or API keys are removed.
+    #[serde(default)]
+    pub leak_detection: bool,
+
+    /// How many times a member can send the same message within a minute before
+    /// the repeats are deleted. 0 turns it off.
+    #[serde(default)]
+    pub repeat_limit: u32,
+
+    /// Whether members get their roles and nickname back when they rejoin.
+    #[serde(default)]
+    pub restore_roles: bool,
+
+    /// Roles restored on rejoin. Empty restores every role that is safe to give back.
+    #[serde(default)]
+    pub restore_role_allowlist: Vec<u64>,
+
+    /// Mute or quarantine roles, always restored on rejoin so leaving doesn't lift them.
+    #[serde(default)]
+    pub persistent_roles: Vec<u64>,
+
+    /// Plugins turned off in this server, by name. Changed with `modules`.
+    #[serde(default)]
+    pub disabled_plugins: Vec<String>,
+
+    /// Channels, roles and users the bot ignores. Changed with `ignore`.
+    #[serde(default)]
+    pub ignore: IgnoreList,
+
+    /// The flow new members go through before getting access. Changed with
+    /// `onboarding`.
+    #[serde(default)]
+    pub onboarding: OnboardingFlow,
+
+    /// How times like `8pm EST` in messages are converted to Discord timestamps.
+    #[serde(default)]
+    pub time_conversion: TimeConversion,
+
+    /// Which delimiters run commands written inside ordinary messages, like
+    /// `{{calc 2+2}}`.
+    #[serde(default)]
+    pub inline_commands: InlineCommands,
+
+    /// Command aliases, by name, with the command and arguments they stand for.
+    /// Changed with `alias`.
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+
+    /// Channel permission templates, by name. Changed with `permsync template`.
+    #[serde(default)]
+    pub permission_templates: BTreeMap<String, PermissionTemplate>,
+
+    /// Anti-nuke protection. Changed with `antinuke`.
+    #[serde(default)]
+    pub antinuke: AntinukeSettings,
+
+    /// Text channels members of voice channels get access to. Changed with
+    /// `voicetext`.
+    #[serde(default)]
+    pub voice_text: VoiceTextSettings,
+
+    /// Music settings: the server's own radio presets, the DJ role and the
+    /// queue limits. Changed with `radio` and `music`.
+    #[serde(default)]
+    pub music: MusicSettings,
+}
+
+/// How times written in messages are converted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeConversion {
+    /// Times aren't converted.
+    #[default]
+    Off,
+    /// Reply with the conversion right away.
+    Reply,
+    /// React with a clock, and reply with the conversion when someone else
+    /// adds the same reaction.
+    React,
+}
+
+impl fmt::Display for TimeConversion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Off => "off",
+            Self::Reply => "reply",
+            Self::React => "react",
+        };
+        f.write_str(name)
+    }
+}
+
+impl std::str::FromStr for TimeConversion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "off" | "none" => Ok(Self::Off),
+            "reply" => Ok(Self::Reply),
+            "react" => Ok(Self::React),
+            _ => Err(format!("Unknown mode `{}`. Use off, reply or react.", s)),
+        }
+    }
+}
+
+/// Which delimiters mark commands inside messages.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InlineCommands {
+    /// Commands only run with the prefix.
+    #[default]
+    Off,
+    /// `{{command}}`.
+    Braces,
+    /// `[[command]]`.
+    Brackets,
+    /// Both `{{command}}` and `[[command]]`.
+    Both,
+}
+
+impl InlineCommands {
+    /// The opening and closing delimiters to look for.
+    pub fn delimiters(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::Off => &[],
+            Self::Braces => &[("{{", "}}")],
+            Self::Brackets => &[("[[", "]]")],
+            Self::Both => &[("{{", "}}"), ("[[", "]]")],
+        }
+    }
+}
+
+impl fmt::Display for InlineCommands {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Off => "off",
+            Self::Braces => "braces",
+            Self::Brackets => "brackets",
+            Self::Both => "both",
+        };
+        f.write_str(name)
+    }
+}
+
+impl std::str::FromStr for InlineCommands {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "off" | "none" => Ok(Self::Off),
+            "braces" | "{{}}" => Ok(Self::Braces),
+            "brackets" | "[[]]" => Ok(Self::Brackets),
+            "both" => Ok(Self::Both),
+            _ => Err(format!(
+                "Unknown style `{}`. Use off, braces, brackets or both.",
+                s
+            )),
+        }
+    }
+}
+
+/// Whose messages are published in an auto-publish channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoPublish {
+    /// Only the bot's own messages.
+    Bot,
+    /// Every message.
+    Everyone,
+}
+
+/// Something done when a message contains a phishing link.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PhishingAction {
+    /// Delete the message.
+    Delete,
+    /// Alert staff in the staff channel.
+    Log,
+    /// Time the author out for `phishing.timeout` seconds.
+    Timeout,
+}
+
+impl fmt::Display for PhishingAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Delete => "delete",
+            Self::Log => "log",
+            Self::Timeout => "timeout",
+        };
+        f.write_str(name)
+    }
+}
+
+impl std::str::FromStr for PhishingAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "delete" => Ok(Self::Delete),
+            "log" => Ok(Self::Log),
+            "timeout" => Ok(Self::Timeout),
+            _ => Err(format!(
+                "Unknown action `{}`. Use delete, log or timeout.",
+                s
+            )),
+        }
+    }
+}
+
+impl GuildConfig {
+    /// Change a setting by name, parsing the value from user input.
+    ///
+    /// Passing `none` clears an optional setting.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let clear = value.eq_ignore_ascii_case("none");
+
+        match key {
+            "staff_channel" => {
+                self.staff_channel = match parse_channel(value) {
+                    _ if clear => None,
+                    Some(channel_id) => Some(channel_id.0),
+                    None => return Err("Expected a channel mention or ID.".to_string()),
+                };
+            }
+            "modmail_channel" => {
+                self.modmail_channel = match parse_channel(value) {
+                    _ if clear => None,
+                    Some(channel_id) => Some(channel_id.0),
+                    None => return Err("Expected a channel mention or ID.".to_string()),
+                };
+            }
+            "milestone_channel" => {
+                self.milestone_channel = match parse_channel(value) {
+                    _ if clear => None,
+                    Some(channel_id) => Some(channel_id.0),
+                    None => return Err("Expected a channel mention or ID.".to_string()),
+                };
+            }
+            "milestones" => {
+                let mut milestones = if clear {
+                    Vec::new()
+                } else {
+                    value
+                        .split(',')
+                        .map(|count| count.trim().replace('_', "").parse::<u64>())
+                        .collect::<Result<Vec<u64>, _>>()
+                        .map_err(|_| "Expected member counts, separated by commas.")?
+                };
+                milestones.retain(|count| *count > 0);
+                milestones.sort_unstable();
+                milestones.dedup();
+                self.milestones = milestones;
+            }
+            "pin_archive_channel" => {
+                self.pin_archive_channel = match parse_channel(value) {
+                    _ if clear => None,
+                    Some(channel_id) => Some(channel_id.0),
+                    None => return Err("Expected a channel mention or ID.".to_string()),
+                };
+            }
+            "message_log_channel" => {
+                self.message_log_channel = match parse_channel(value) {
+                    _ if clear => None,
+                    Some(channel_id) => Some(channel_id.0),
+                    None => return Err("Expected a channel mention or ID.".to_string()),
+                };
+            }
+            "staff_role" => {
+                self.staff_role = match parse_role(value) {
+                    _ if clear => None,
+                    Some(role_id) => Some(role_id.0),
+                    None => return Err("Expected a role mention or ID.".to_string()),
+                };
+            }
+            "mute_role" => {
+                self.mute_role = match parse_role(value) {
+                    _ if clear => None,
+                    Some(role_id) => Some(role_id.0),
+                    None => return Err("Expected a role mention or ID.".to_string()),
+                };
+            }
+            "case_retention" | "message_log_retention" => {
+                let days = match value.parse::<u64>() {
+                    _ if clear => None,
+                    Ok(days) => Some(days),
+                    Err(_) => {
+                        return Err("Expected a number of days (0 keeps forever).".to_string())
+                    }
+                };
+                if key == "case_retention" {
+                    self.case_retention = days;
+                } else {
+                    self.message_log_retention = days;
+                }
+            }
+            "phishing_actions" => {
+                let mut actions = if clear {
+                    Vec::new()
+                } else {
+                    value
+                        .split(',')
+                        .map(|action| action.trim().parse())
+                        .collect::<Result<Vec<PhishingAction>, String>>()?
+                };
+                actions.sort();
+                actions.dedup();
+                self.phishing_actions = actions;
+            }
+            "leak_detection" | "restore_roles" => {
+                let on = match value.to_lowercase().as_str() {
+                    "on" | "true" | "yes" => true,
+                    "off" | "false" | "no" | "none" => false,
+                    _ => return Err("Expected `on` or `off`.".to_string()),
+                };
+                if key == "leak_detection" {
+                    self.leak_detection = on;
+                } else {
+                    self.restore_roles = on;
+                }
+            }
+            "restore_role_allowlist" | "persistent_roles" => {
+                let roles = if clear {
+                    Vec::new()
+                } else {
+                    let mut roles = value
+                        .split(',')
+                        .map(|role| parse_role(role.trim()).map(|role_id| role_id.0))
+                        .collect::<Option<Vec<u64>>>()
+                        .ok_or("Expected role mentions or IDs, separated by commas.")?;
+                    roles.sort_unstable();
+                    roles.dedup();
+                    roles
+                };
+                if key == "restore_role_allowlist" {
+                    self.restore_role_allowlist = roles;
+                } else {
+                    self.persistent_roles = roles;
+                }
+            }
+            "repeat_limit" => {
+                self.repeat_limit = match value.parse::<u32>() {
+                    _ if clear => 0,
+                    Ok(limit) => limit,
+                    Err(_) => {
+                        return Err("Expected a number of messages (0 turns it off).".to_string())
+                    }
+                };
+            }
+            "time_conversion" => self.time_conversion = value.parse()?,
+            "inline_commands" => self.inline_commands = value.parse()?,
+            _ => return Err(format!("Unknown setting `{}`.", key)),
+        }
+
+        Ok(())
+    }
+
+    /// Describe all settings, one per line.
+    pub fn describe(&self) -> String {
+        let channel = |id: Option<u64>| id.map_or("not set".to_string(), |id| format!("<#{}>", id));
+        let role = |id: Option<u64>| id.map_or("not set".to_string(), |id| format!("<@&{}>", id));
+        let days = |days: Option<u64>| match days {
+            None => "default".to_string(),
+            Some(0) => "forever".to_string(),
+            Some(days) => format!("{} days", days),
+        };
+
+        [
+            format!("`staff_channel`: {}", channel(self.staff_channel)),
+            format!("`staff_role`: {}", role(self.staff_role)),
+            format!("`mute_role`: {}", role(self.mute_role)),
+            format!("`modmail_channel`: {}", channel(self.modmail_channel)),
+            format!("`milestone_channel`: {}", channel(self.milestone_channel)),
+            format!("`milestones`: {}", describe_milestones(&self.milestones)),
+            format!(
+                "`pin_archive_channel`: {}",
+                channel(self.pin_archive_channel)
+            ),
+            format!(
+                "`message_log_channel`: {}",
+                channel(self.message_log_channel)
+            ),
+            format!("`case_retention`: {}", days(self.case_retention)),
+            format!(
+                "`message_log_retention`: {}",
+                days(self.message_log_retention)
+            ),
+            format!("`auto_publish`: {}", self.describe_auto_publish()),
+            format!(
+                "`phishing_actions`: {}",
+                describe_phishing_actions(&self.phishing_actions)
+            ),
+            format!(
+                "`leak_detection`: {}",
+                if self.leak_detection { "on" } else { "off" }
+            ),
+            format!(
+                "`repeat_limit`: {}",
+                match self.repeat_limit {
+                    0 => "off".to_string(),
+                    limit => format!("{} a minute", limit),
+                }
+            ),
+            format!(
+                "`restore_roles`: {}",
+                if self.restore_roles { "on" } else { "off" }
+            ),
+            format!(
+                "`restore_role_allowlist`: {}",
+                describe_roles(&self.restore_role_allowlist, "all safe roles")
+            ),
+            format!(
+                "`persistent_roles`: {}",
+                describe_roles(&self.persistent_roles, "not set")
+            ),
+            format!("`time_conversion`: {}", self.time_conversion),
+            format!("`inline_commands`: {}", self.inline_commands),
+        ]
+        .join("\n")
+    }
+
+    /// List the auto-publish channels, such as `<#1> (bot), <#2> (everyone)`.
+    fn describe_auto_publish(&self) -> String {
+        let mut channels: Vec<_> = self.auto_publish.iter().collect();
+        if channels.is_empty() {
+            return "not set".to_string();
+        }
+
+        channels.sort_by_key(|(channel_id, _)| **channel_id);
+        channels
+            .iter()
+            .map(|(channel_id, mode)| match mode {
+                AutoPublish::Bot => format!("<#{}> (bot)", channel_id),
+                AutoPublish::Everyone => format!("<#{}> (everyone)", channel_id),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// The member counts announced as milestones.
+    pub fn milestones(&self) -> &[u64] {
+        if self.milestones.is_empty() {
+            &DEFAULT_MILESTONES
+        } else {
+            &self.milestones
+        }
+    }
+
+    /// Whose messages are published automatically in a channel, if anyone's.
+    pub fn auto_publish(&self, channel_id: ChannelId) -> Option<AutoPublish> {
+        self.auto_publish.get(&channel_id.0).copied()
+    }
+}
+
+/// List phishing actions, such as `delete, log`.
+fn describe_phishing_actions(actions: &[PhishingAction]) -> String {
+    if actions.is_empty() {
+        return "off".to_string();
+    }
+    actions
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// List milestones, or `default` when the defaults are used.
+fn describe_milestones(milestones: &[u64]) -> String {
+    if milestones.is_empty() {
+        return "default".to_string();
+    }
+    milestones
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// List roles as mentions, or `empty` when there are none.
+fn describe_roles(roles: &[u64], empty: &str) -> String {
+    if roles.is_empty() {
+        return empty.to_string();
+    }
+    roles
+        .iter()
+        .map(|role_id| format!("<@&{}>", role_id))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// All guild configurations, keyed by guild ID.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GuildConfigs {
+    /// Configuration for each guild.
+    #[serde(default)]
+    pub guilds: HashMap<u64, GuildConfig>,
+}
+
+impl GuildConfigs {
+    /// Get a guild's configuration, falling back to the defaults.
+    pub fn get(&self, guild_id: GuildId) -> GuildConfig {
+        self.guilds.get(&guild_id.0).cloned().unwrap_or_default()
+    }
+
+    /// Get a mutable reference to a guild's configuration, creating it if needed.
+    pub fn entry(&mut self, guild_id: GuildId) -> &mut GuildConfig {
+        self.guilds.entry(guild_id.0).or_default()
+    }
+}
+
+/// TypeMap key for the guild configuration store.
+pub struct GuildConfigKey;
+
+impl TypeMapKey for GuildConfigKey {
+    type Value = Arc<JsonStore<GuildConfigs>>;
+}
+
+/// Get a guild's configuration from the client data.
+pub async fn guild_config(ctx: &Context, guild_id: GuildId) -> GuildConfig {
+    let store = {
+        let data = ctx.data.read().await;
+        data.get::<GuildConfigKey>().cloned()
+    };
+
+    match store {
+        Some(store) => store.read().await.get(guild_id),
+        None => GuildConfig::default(),
+    }
+}
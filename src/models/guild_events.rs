@@ -0,0 +1,249 @@
+//! Events members can RSVP to.
+
+use serde::{Deserialize, Serialize};
+use serenity::model::id::GuildId;
+use serenity::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+use crate::storage::JsonStore;
+
+/// Most upcoming events a guild can have.
+pub const MAX_EVENTS: usize = 25;
+
+/// How long after starting an event is kept, in seconds.
+pub const EVENT_RETENTION: u64 = 7 * 24 * 60 * 60;
+
+/// A member's answer to an event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rsvp {
+    Going,
+    Maybe,
+    NotGoing,
+}
+
+impl Rsvp {
+    /// Every answer, in the order they're shown.
+    pub const ALL: [Rsvp; 3] = [Rsvp::Going, Rsvp::Maybe, Rsvp::NotGoing];
+
+    /// The answer's ID in button IDs.
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::Going => "going",
+            Self::Maybe => "maybe",
+            Self::NotGoing => "no",
+        }
+    }
+
+    /// Parse an answer from its ID.
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|rsvp| rsvp.id() == id)
+    }
+
+    /// The emoji shown with the answer.
+    pub fn emoji(self) -> &'static str {
+        match self {
+            Self::Going => "✅",
+            Self::Maybe => "❔",
+            Self::NotGoing => "❌",
+        }
+    }
+}
+
+impl fmt::Display for Rsvp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Going => "Going",
+            Self::Maybe => "Maybe",
+            Self::NotGoing => "Can't go",
+        };
+        f.write_str(name)
+    }
+}
+
+/// An event.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GuildEvent {
+    /// Event ID, unique within the guild.
+    pub id: u64,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub location: Option<String>,
+    /// When the event starts (seconds since the Unix epoch).
+    pub start: u64,
+    pub created_by: u64,
+    pub channel_id: u64,
+    /// The message with the RSVP buttons, once it's posted.
+    #[serde(default)]
+    pub message_id: Option<u64>,
+    /// Answers by user ID.
+    #[serde(default)]
+    pub rsvps: BTreeMap<u64, Rsvp>,
+    /// Whether the members who answered were reminded before the start.
+    #[serde(default)]
+    pub reminded: bool,
+    /// The matching Discord scheduled event, if one was created.
+    #[serde(default)]
+    pub scheduled_event_id: Option<u64>,
+}
+
+impl GuildEvent {
+    /// Set a member's answer. Giving the same answer again takes it back.
+    /// Returns the member's answer afterwards.
+    pub fn answer(&mut self, user_id: u64, rsvp: Rsvp) -> Option<Rsvp> {
+        if self.rsvps.get(&user_id) == Some(&rsvp) {
+            self.rsvps.remove(&user_id);
+            None
+        } else {
+            self.rsvps.insert(user_id, rsvp);
+            Some(rsvp)
+        }
+    }
+
+    /// The members who gave an answer.
+    pub fn attendees(&self, rsvp: Rsvp) -> Vec<u64> {
+        self.rsvps
+            .iter()
+            .filter(|(_, answer)| **answer == rsvp)
+            .map(|(user_id, _)| *user_id)
+            .collect()
+    }
+}
+
+/// A guild's events.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GuildEvents {
+    /// The last ID handed out to an event.
+    #[serde(default)]
+    pub last_id: u64,
+    #[serde(default)]
+    pub events: BTreeMap<u64, GuildEvent>,
+}
+
+impl GuildEvents {
+    /// Add an event, giving it the next ID, and return the ID.
+    pub fn add(&mut self, mut event: GuildEvent) -> u64 {
+        self.last_id += 1;
+        event.id = self.last_id;
+        self.events.insert(event.id, event);
+        self.last_id
+    }
+
+    /// Events that haven't started yet.
+    pub fn upcoming(&self, now: u64) -> impl Iterator<Item = &GuildEvent> {
+        self.events.values().filter(move |event| event.start > now)
+    }
+}
+
+/// Events for all guilds.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct EventData {
+    /// Events by guild ID.
+    #[serde(default)]
+    pub guilds: HashMap<u64, GuildEvents>,
+}
+
+impl EventData {
+    /// Get a guild's events, if it has any.
+    pub fn guild(&self, guild_id: GuildId) -> Option<&GuildEvents> {
+        self.guilds.get(&guild_id.0)
+    }
+
+    /// Get a mutable reference to a guild's events, creating them if needed.
+    pub fn guild_mut(&mut self, guild_id: GuildId) -> &mut GuildEvents {
+        self.guilds.entry(guild_id.0).or_default()
+    }
+
+    /// Whether an event starts within `lead` seconds and hasn't been reminded
+    /// about, or one ended long enough ago to be dropped.
+    pub fn has_due(&self, now: u64, lead: u64) -> bool {
+        self.guilds
+            .values()
+            .flat_map(|guild| guild.events.values())
+            .any(|event| {
+                (!event.reminded && event.start <= now + lead)
+                    || event.start + EVENT_RETENTION < now
+            })
+    }
+
+    /// Events starting within `lead` seconds that haven't been reminded about,
+    /// marking them reminded. Events past their retention are dropped.
+    pub fn take_due(&mut self, now: u64, lead: u64) -> Vec<GuildEvent> {
+        let mut due = Vec::new();
+        for guild in self.guilds.values_mut() {
+            guild
+                .events
+                .retain(|_, event| event.start + EVENT_RETENTION >= now);
+            for event in guild.events.values_mut() {
+                if !event.reminded && event.start <= now + lead {
+                    event.reminded = true;
+                    // Events created too close to their start aren't reminded about
+                    if event.start > now {
+                        due.push(event.clone());
+                    }
+                }
+            }
+        }
+        self.guilds.retain(|_, guild| !guild.events.is_empty());
+        due
+    }
+}
+
+/// TypeMap key for the event store.
+pub struct EventKey;
+
+impl TypeMapKey for EventKey {
+    type Value = Arc<JsonStore<EventData>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(start: u64) -> GuildEvent {
+        GuildEvent {
+            id: 0,
+            title: "Game night".to_string(),
+            description: String::new(),
+            location: None,
+            start,
+            created_by: 1,
+            channel_id: 2,
+            message_id: None,
+            rsvps: BTreeMap::new(),
+            reminded: false,
+            scheduled_event_id: None,
+        }
+    }
+
+    #[test]
+    fn answers_toggle() {
+        let mut event = event(1_000);
+        assert_eq!(event.answer(5, Rsvp::Going), Some(Rsvp::Going));
+        assert_eq!(event.answer(6, Rsvp::Maybe), Some(Rsvp::Maybe));
+        assert_eq!(event.answer(5, Rsvp::Maybe), Some(Rsvp::Maybe));
+        assert_eq!(event.attendees(Rsvp::Maybe), [5, 6]);
+        assert_eq!(event.answer(5, Rsvp::Maybe), None);
+        assert!(event.attendees(Rsvp::Going).is_empty());
+        assert_eq!(Rsvp::from_id("no"), Some(Rsvp::NotGoing));
+    }
+
+    #[test]
+    fn reminds_once_before_the_start() {
+        let mut data = EventData::default();
+        data.guild_mut(GuildId(1)).add(event(1_000));
+        assert!(!data.has_due(100, 600));
+        assert!(data.has_due(500, 600));
+        assert_eq!(data.take_due(500, 600).len(), 1);
+        assert!(data.take_due(500, 600).is_empty());
+
+        // Old events are dropped
+        assert!(data.has_due(1_000 + EVENT_RETENTION + 1, 600));
+        data.take_due(1_000 + EVENT_RETENTION + 1, 600);
+        assert!(data.guilds.is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+//! Channels, roles and users a guild tells the bot to ignore.
+//!
+//! Each entry ignores either commands, passive features (auto-responses, automod
+//! and games), or both. Members who can manage the server still run commands in
+//! ignored places, so an ignore can always be undone.
+
+use serde::{Deserialize, Serialize};
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, GuildId, RoleId, UserId};
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+
+use super::guild_config::guild_config;
+
+/// What an ignore entry covers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IgnoreScope {
+    /// Commands aren't run.
+    Commands,
+    /// Auto-responses, automod and games leave messages alone.
+    Passive,
+    /// Both commands and passive features.
+    All,
+}
+
+impl IgnoreScope {
+    /// Whether commands are ignored.
+    pub fn commands(self) -> bool {
+        matches!(self, Self::Commands | Self::All)
+    }
+
+    /// Whether passive features are ignored.
+    pub fn passive(self) -> bool {
+        matches!(self, Self::Passive | Self::All)
+    }
+}
+
+impl fmt::Display for IgnoreScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Commands => "commands",
+            Self::Passive => "passive",
+            Self::All => "all",
+        };
+        f.write_str(name)
+    }
+}
+
+impl std::str::FromStr for IgnoreScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "commands" => Ok(Self::Commands),
+            "passive" => Ok(Self::Passive),
+            "all" => Ok(Self::All),
+            _ => Err(format!(
+                "Unknown scope `{}`. Use commands, passive or all.",
+                s
+            )),
+        }
+    }
+}
+
+/// What is ignored for a message.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Ignored {
+    pub commands: bool,
+    pub passive: bool,
+}
+
+/// A guild's ignored channels, roles and users, by ID.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct IgnoreList {
+    #[serde(default)]
+    pub channels: HashMap<u64, IgnoreScope>,
+    #[serde(default)]
+    pub roles: HashMap<u64, IgnoreScope>,
+    #[serde(default)]
+    pub users: HashMap<u64, IgnoreScope>,
+}
+
+impl IgnoreList {
+    /// Whether nothing is ignored.
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty() && self.roles.is_empty() && self.users.is_empty()
+    }
+
+    /// What is ignored for a message from `user_id` with `roles` in `channel_id`.
+    pub fn check(&self, channel_id: ChannelId, user_id: UserId, roles: &[RoleId]) -> Ignored {
+        let scopes = self
+            .channels
+            .get(&channel_id.0)
+            .into_iter()
+            .chain(self.users.get(&user_id.0))
+            .chain(roles.iter().filter_map(|role| self.roles.get(&role.0)));
+
+        scopes.fold(Ignored::default(), |ignored, scope| Ignored {
+            commands: ignored.commands || scope.commands(),
+            passive: ignored.passive || scope.passive(),
+        })
+    }
+
+    /// Describe the entries, one per line.
+    pub fn describe(&self) -> String {
+        let mut lines = Vec::new();
+        for (entries, mention) in [
+            (&self.channels, "<#{}>"),
+            (&self.roles, "<@&{}>"),
+            (&self.users, "<@{}>"),
+        ] {
+            let mut entries: Vec<_> = entries.iter().collect();
+            entries.sort_by_key(|(id, _)| **id);
+            for (id, scope) in entries {
+                lines.push(format!(
+                    "{} ({})",
+                    mention.replace("{}", &id.to_string()),
+                    scope
+                ));
+            }
+        }
+
+        if lines.is_empty() {
+            "Nothing is ignored.".to_string()
+        } else {
+            lines.join("\n")
+        }
+    }
+}
+
+/// What the guild's ignore list covers for a message. Nothing is ignored in direct
+/// messages.
+pub async fn ignored(ctx: &Context, msg: &Message) -> Ignored {
+    let guild_id = match msg.guild_id {
+        Some(guild_id) => guild_id,
+        None => return Ignored::default(),
+    };
+    let roles = msg
+        .member
+        .as_ref()
+        .map(|member| member.roles.clone())
+        .unwrap_or_default();
+    ignored_in(ctx, guild_id, msg.channel_id, msg.author.id, &roles).await
+}
+
+/// What the guild's ignore list covers for a user with `roles` in a channel.
+pub async fn ignored_in(
+    ctx: &Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    user_id: UserId,
+    roles: &[RoleId],
+) -> Ignored {
+    guild_config(ctx, guild_id)
+        .await
+        .ignore
+        .check(channel_id, user_id, roles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combines_channel_role_and_user_entries() {
+        let mut list = IgnoreList::default();
+        list.channels.insert(1, IgnoreScope::Commands);
+        list.roles.insert(5, IgnoreScope::Passive);
+        list.users.insert(9, IgnoreScope::All);
+
+        let ignored = list.check(ChannelId(1), UserId(2), &[]);
+        assert!(ignored.commands && !ignored.passive);
+
+        let ignored = list.check(ChannelId(1), UserId(2), &[RoleId(5)]);
+        assert!(ignored.commands && ignored.passive);
+
+        assert_eq!(
+            list.check(ChannelId(3), UserId(2), &[RoleId(6)]),
+            Ignored::default()
+        );
+        assert!(list.check(ChannelId(3), UserId(9), &[]).passive);
+    }
+}
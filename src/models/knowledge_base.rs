@@ -0,0 +1,296 @@
+//! Per-guild knowledge base articles with revision history.
+
+use serde::{Deserialize, Serialize};
+use serenity::model::id::GuildId;
+use serenity::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use crate::storage::JsonStore;
+
+/// Most articles a guild can have.
+pub const MAX_ARTICLES: usize = 200;
+
+/// Revisions kept per article. Older ones are dropped.
+pub const MAX_REVISIONS: usize = 20;
+
+/// Longest topic name.
+pub const MAX_TOPIC_LENGTH: usize = 50;
+
+/// One version of an article.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Revision {
+    pub content: String,
+    /// Whether the article is shown as an embed rather than a plain message.
+    #[serde(default)]
+    pub embed: bool,
+    pub edited_by: u64,
+    pub edited_at: u64,
+}
+
+/// A knowledge base article.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Article {
+    /// The topic as it was first written.
+    pub topic: String,
+    /// Revision numbers start at 1 and keep counting after old ones are dropped.
+    #[serde(default)]
+    pub last_revision: u64,
+    /// Kept revisions, oldest first.
+    pub revisions: Vec<Revision>,
+}
+
+impl Article {
+    /// The current revision.
+    pub fn current(&self) -> Option<&Revision> {
+        self.revisions.last()
+    }
+
+    /// The number of the oldest kept revision.
+    pub fn first_revision(&self) -> u64 {
+        self.last_revision + 1 - self.revisions.len() as u64
+    }
+
+    /// Get a kept revision by number.
+    pub fn revision(&self, number: u64) -> Option<&Revision> {
+        let index = number.checked_sub(self.first_revision())?;
+        self.revisions.get(index as usize)
+    }
+
+    /// Add a revision, dropping the oldest past [`MAX_REVISIONS`], and return its number.
+    fn push(&mut self, revision: Revision) -> u64 {
+        self.revisions.push(revision);
+        if self.revisions.len() > MAX_REVISIONS {
+            self.revisions.remove(0);
+        }
+        self.last_revision += 1;
+        self.last_revision
+    }
+}
+
+/// Why an article couldn't be saved.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SaveError {
+    /// The guild has [`MAX_ARTICLES`] articles.
+    Full,
+    /// The content is the same as the current revision.
+    Unchanged,
+}
+
+/// What a lookup found.
+#[derive(Debug)]
+pub enum Lookup<'a> {
+    /// The article, and whether the topic was matched exactly.
+    Found(&'a Article, bool),
+    /// No close match, with topics that might have been meant.
+    NotFound(Vec<&'a str>),
+}
+
+/// Normalize a topic for lookups, or `None` if it isn't a valid topic.
+pub fn topic_key(topic: &str) -> Option<String> {
+    let key = topic.trim().to_lowercase();
+    let valid = !key.is_empty()
+        && key.chars().count() <= MAX_TOPIC_LENGTH
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    valid.then_some(key)
+}
+
+/// Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                previous
+            } else {
+                1 + previous.min(row[j]).min(row[j + 1])
+            };
+            previous = current;
+        }
+    }
+    row[b.len()]
+}
+
+/// A guild's articles.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GuildKnowledgeBase {
+    /// Articles by topic key.
+    #[serde(default)]
+    pub articles: BTreeMap<String, Article>,
+}
+
+impl GuildKnowledgeBase {
+    /// Save a new revision of a topic, creating the article if needed, and
+    /// return the revision number.
+    pub fn set(&mut self, key: &str, topic: &str, revision: Revision) -> Result<u64, SaveError> {
+        let count = self.articles.len();
+        match self.articles.get_mut(key) {
+            Some(article) => {
+                let unchanged = article.current().is_some_and(|current| {
+                    current.content == revision.content && current.embed == revision.embed
+                });
+                if unchanged {
+                    return Err(SaveError::Unchanged);
+                }
+                Ok(article.push(revision))
+            }
+            None if count >= MAX_ARTICLES => Err(SaveError::Full),
+            None => {
+                let mut article = Article {
+                    topic: topic.to_string(),
+                    last_revision: 0,
+                    revisions: Vec::new(),
+                };
+                let number = article.push(revision);
+                self.articles.insert(key.to_string(), article);
+                Ok(number)
+            }
+        }
+    }
+
+    /// Find an article by topic: an exact match, the only topic starting with
+    /// the query, or the closest topic within a few typos.
+    pub fn lookup(&self, query: &str) -> Lookup<'_> {
+        let query = query.trim().to_lowercase();
+        if let Some(article) = self.articles.get(&query) {
+            return Lookup::Found(article, true);
+        }
+
+        let prefixed: Vec<&Article> = self
+            .articles
+            .iter()
+            .filter(|(key, _)| key.starts_with(&query))
+            .map(|(_, article)| article)
+            .collect();
+        if let [article] = prefixed.as_slice() {
+            return Lookup::Found(article, false);
+        }
+
+        let max_distance = (query.chars().count() / 3).max(1);
+        let mut near: Vec<(usize, &String, &Article)> = self
+            .articles
+            .iter()
+            .map(|(key, article)| (edit_distance(&query, key), key, article))
+            .filter(|(distance, _, _)| *distance <= max_distance * 2)
+            .collect();
+        near.sort_by_key(|(distance, key, _)| (*distance, key.len()));
+        match near.as_slice() {
+            [(best, _, article), rest @ ..]
+                if *best <= max_distance && rest.first().is_none_or(|(d, _, _)| d > best) =>
+            {
+                Lookup::Found(article, false)
+            }
+            _ => {
+                let mut suggestions: Vec<&str> = Vec::new();
+                let candidates = prefixed
+                    .iter()
+                    .copied()
+                    .chain(near.iter().map(|(_, _, article)| *article));
+                for article in candidates {
+                    if suggestions.len() < 5 && !suggestions.contains(&article.topic.as_str()) {
+                        suggestions.push(&article.topic);
+                    }
+                }
+                Lookup::NotFound(suggestions)
+            }
+        }
+    }
+
+    /// Remove an article, returning whether it existed.
+    pub fn remove(&mut self, key: &str) -> bool {
+        self.articles.remove(key).is_some()
+    }
+}
+
+/// Knowledge bases for all guilds.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct KnowledgeBaseData {
+    /// Articles by guild ID.
+    #[serde(default)]
+    pub guilds: HashMap<u64, GuildKnowledgeBase>,
+}
+
+impl KnowledgeBaseData {
+    /// Get a guild's knowledge base, if it has one.
+    pub fn guild(&self, guild_id: GuildId) -> Option<&GuildKnowledgeBase> {
+        self.guilds.get(&guild_id.0)
+    }
+
+    /// Get a mutable reference to a guild's knowledge base, creating it if needed.
+    pub fn guild_mut(&mut self, guild_id: GuildId) -> &mut GuildKnowledgeBase {
+        self.guilds.entry(guild_id.0).or_default()
+    }
+}
+
+/// TypeMap key for the knowledge base store.
+pub struct KnowledgeBaseKey;
+
+impl TypeMapKey for KnowledgeBaseKey {
+    type Value = Arc<JsonStore<KnowledgeBaseData>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn revision(content: &str) -> Revision {
+        Revision {
+            content: content.to_string(),
+            embed: false,
+            edited_by: 1,
+            edited_at: 0,
+        }
+    }
+
+    fn found(kb: &GuildKnowledgeBase, query: &str) -> Option<String> {
+        match kb.lookup(query) {
+            Lookup::Found(article, _) => Some(article.topic.clone()),
+            Lookup::NotFound(_) => None,
+        }
+    }
+
+    #[test]
+    fn keeps_recent_revisions() {
+        let mut kb = GuildKnowledgeBase::default();
+        assert_eq!(kb.set("rules", "Rules", revision("v1")), Ok(1));
+        assert_eq!(
+            kb.set("rules", "Rules", revision("v1")),
+            Err(SaveError::Unchanged)
+        );
+        for n in 2..=25 {
+            kb.set("rules", "Rules", revision(&format!("v{}", n)))
+                .unwrap();
+        }
+
+        let article = &kb.articles["rules"];
+        assert_eq!(article.revisions.len(), MAX_REVISIONS);
+        assert_eq!(article.first_revision(), 6);
+        assert_eq!(article.revision(6).unwrap().content, "v6");
+        assert!(article.revision(5).is_none());
+        assert_eq!(article.current().unwrap().content, "v25");
+    }
+
+    #[test]
+    fn looks_up_topics_fuzzily() {
+        let mut kb = GuildKnowledgeBase::default();
+        for topic in ["Rules", "roles", "faq", "install-guide"] {
+            kb.set(&topic_key(topic).unwrap(), topic, revision(topic))
+                .unwrap();
+        }
+
+        assert_eq!(found(&kb, "RULES").as_deref(), Some("Rules"));
+        assert_eq!(found(&kb, "inst").as_deref(), Some("install-guide"));
+        assert_eq!(found(&kb, "fak").as_deref(), Some("faq"));
+        assert_eq!(found(&kb, "instal-guide").as_deref(), Some("install-guide"));
+        assert!(
+            matches!(kb.lookup("rle"), Lookup::NotFound(suggestions) if !suggestions.is_empty())
+        );
+        assert_eq!(topic_key("two words"), None);
+    }
+}
@@ -0,0 +1,244 @@
+//! Looking-for-group posts and per-guild game presets.
+
+use serde::{Deserialize, Serialize};
+use serenity::model::id::GuildId;
+use serenity::prelude::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::storage::JsonStore;
+
+/// Most open posts a guild can have.
+pub const MAX_OPEN_POSTS: usize = 20;
+
+/// Most presets a guild can have.
+pub const MAX_PRESETS: usize = 50;
+
+/// Smallest and largest group size, counting the host.
+pub const MIN_SLOTS: u32 = 2;
+pub const MAX_SLOTS: u32 = 25;
+
+/// A game preset, so members don't have to give the group size every time.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Preset {
+    /// The game as it was first written.
+    pub game: String,
+    pub slots: u32,
+}
+
+/// A post looking for players.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LfgPost {
+    /// Post ID, unique within the guild.
+    pub id: u64,
+    pub game: String,
+    /// Group size, counting the host.
+    pub slots: u32,
+    pub host: u64,
+    /// Everyone in the group, the host first.
+    pub members: Vec<u64>,
+    pub channel_id: u64,
+    #[serde(default)]
+    pub message_id: Option<u64>,
+    pub created_at: u64,
+    /// When the group wants to play, if not right away.
+    #[serde(default)]
+    pub starts_at: Option<u64>,
+    /// When an open post expires, or a full group's voice channel and role are removed.
+    pub expires_at: u64,
+    /// When the group filled up.
+    #[serde(default)]
+    pub filled_at: Option<u64>,
+    /// The group's temporary voice channel.
+    #[serde(default)]
+    pub voice_channel_id: Option<u64>,
+    /// The group's temporary role.
+    #[serde(default)]
+    pub role_id: Option<u64>,
+}
+
+impl LfgPost {
+    /// Whether every slot is taken.
+    pub fn is_full(&self) -> bool {
+        self.members.len() as u32 >= self.slots
+    }
+
+    /// Add a member, returning whether they joined.
+    pub fn join(&mut self, user_id: u64) -> bool {
+        if self.filled_at.is_some() || self.is_full() || self.members.contains(&user_id) {
+            return false;
+        }
+        self.members.push(user_id);
+        true
+    }
+
+    /// Remove a member other than the host, returning whether they left.
+    pub fn leave(&mut self, user_id: u64) -> bool {
+        if self.filled_at.is_some() || user_id == self.host {
+            return false;
+        }
+        let before = self.members.len();
+        self.members.retain(|member| *member != user_id);
+        self.members.len() != before
+    }
+}
+
+/// A guild's posts and presets.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GuildLfg {
+    /// The last ID handed out to a post.
+    #[serde(default)]
+    pub last_id: u64,
+    #[serde(default)]
+    pub posts: BTreeMap<u64, LfgPost>,
+    /// Presets by lowercased game name.
+    #[serde(default)]
+    pub presets: BTreeMap<String, Preset>,
+}
+
+impl GuildLfg {
+    /// Add a post, giving it the next ID, and return the ID.
+    pub fn add(&mut self, mut post: LfgPost) -> u64 {
+        self.last_id += 1;
+        post.id = self.last_id;
+        self.posts.insert(post.id, post);
+        self.last_id
+    }
+
+    /// Posts still looking for players.
+    pub fn open(&self) -> impl Iterator<Item = &LfgPost> {
+        self.posts.values().filter(|post| post.filled_at.is_none())
+    }
+
+    /// Find a preset by game name, ignoring case.
+    pub fn preset(&self, game: &str) -> Option<&Preset> {
+        self.presets.get(&game.trim().to_lowercase())
+    }
+}
+
+/// Looking-for-group data for all guilds.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LfgData {
+    /// Posts and presets by guild ID.
+    #[serde(default)]
+    pub guilds: HashMap<u64, GuildLfg>,
+}
+
+impl LfgData {
+    /// Get a guild's data, if it has any.
+    pub fn guild(&self, guild_id: GuildId) -> Option<&GuildLfg> {
+        self.guilds.get(&guild_id.0)
+    }
+
+    /// Get a mutable reference to a guild's data, creating it if needed.
+    pub fn guild_mut(&mut self, guild_id: GuildId) -> &mut GuildLfg {
+        self.guilds.entry(guild_id.0).or_default()
+    }
+
+    /// Whether any post has expired.
+    pub fn has_expired(&self, now: u64) -> bool {
+        self.guilds
+            .values()
+            .flat_map(|guild| guild.posts.values())
+            .any(|post| post.expires_at <= now)
+    }
+
+    /// Remove and return expired posts with their guild IDs. Groups still
+    /// using their voice channel get `extension` more seconds instead.
+    pub fn take_expired(
+        &mut self,
+        now: u64,
+        occupied: &HashSet<u64>,
+        extension: u64,
+    ) -> Vec<(GuildId, LfgPost)> {
+        let mut expired = Vec::new();
+        for (guild_id, guild) in &mut self.guilds {
+            let ids: Vec<u64> = guild
+                .posts
+                .values()
+                .filter(|post| post.expires_at <= now)
+                .map(|post| post.id)
+                .collect();
+            for id in ids {
+                let in_use = guild.posts[&id]
+                    .voice_channel_id
+                    .is_some_and(|channel| occupied.contains(&channel));
+                if in_use {
+                    if let Some(post) = guild.posts.get_mut(&id) {
+                        post.expires_at = now + extension;
+                    }
+                } else if let Some(post) = guild.posts.remove(&id) {
+                    expired.push((GuildId(*guild_id), post));
+                }
+            }
+        }
+        expired
+    }
+}
+
+/// TypeMap key for the looking-for-group store.
+pub struct LfgKey;
+
+impl TypeMapKey for LfgKey {
+    type Value = Arc<JsonStore<LfgData>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(slots: u32) -> LfgPost {
+        LfgPost {
+            id: 0,
+            game: "Valorant".to_string(),
+            slots,
+            host: 1,
+            members: vec![1],
+            channel_id: 2,
+            message_id: None,
+            created_at: 0,
+            starts_at: None,
+            expires_at: 100,
+            filled_at: None,
+            voice_channel_id: None,
+            role_id: None,
+        }
+    }
+
+    #[test]
+    fn fills_up() {
+        let mut post = post(3);
+        assert!(post.join(5));
+        assert!(!post.join(5));
+        assert!(!post.leave(1));
+        assert!(post.leave(5));
+        assert!(post.join(5) && post.join(6));
+        assert!(post.is_full());
+        assert!(!post.join(7));
+    }
+
+    #[test]
+    fn takes_expired_posts() {
+        let mut data = LfgData::default();
+        let guild = data.guild_mut(GuildId(1));
+        guild.add(post(2));
+        let mut later = post(2);
+        later.expires_at = 500;
+        guild.add(later);
+        let mut playing = post(2);
+        playing.voice_channel_id = Some(9);
+        guild.add(playing);
+
+        assert!(!data.has_expired(50));
+        assert!(data.has_expired(100));
+        let occupied = HashSet::from([9]);
+        let expired = data.take_expired(100, &occupied, 60);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].1.id, 1);
+
+        // The group in voice is kept a while longer
+        let guild = data.guild(GuildId(1)).unwrap();
+        assert_eq!(guild.posts.len(), 2);
+        assert_eq!(guild.posts[&3].expires_at, 160);
+    }
+}
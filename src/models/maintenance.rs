@@ -0,0 +1,74 @@
+//! Maintenance mode state.
+
+use serde::{Deserialize, Serialize};
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::storage::JsonStore;
+
+/// Global and per-command maintenance flags, controlled by bot owners.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MaintenanceState {
+    /// Whether the whole bot is in maintenance.
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Notice shown to users while the bot is in maintenance.
+    #[serde(default)]
+    pub message: Option<String>,
+
+    /// Whether owners can still use commands during maintenance.
+    #[serde(default = "default_true")]
+    pub allow_owners: bool,
+
+    /// Commands in maintenance, with an optional notice for each.
+    #[serde(default)]
+    pub commands: HashMap<String, Option<String>>,
+}
+
+impl Default for MaintenanceState {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            message: None,
+            allow_owners: true,
+            commands: HashMap::new(),
+        }
+    }
+}
+
+impl MaintenanceState {
+    /// Get the notice to show if a command is unavailable, or `None` if it can run.
+    pub fn notice_for(&self, command: &str, is_owner: bool) -> Option<String> {
+        if is_owner && self.allow_owners {
+            return None;
+        }
+
+        if self.enabled {
+            return Some(self.message.clone().unwrap_or_else(|| {
+                "The bot is undergoing maintenance. Please try again later.".to_string()
+            }));
+        }
+
+        self.commands.get(command).map(|message| {
+            message.clone().unwrap_or_else(|| {
+                format!(
+                    "The `{}` command is undergoing maintenance. Please try again later.",
+                    command
+                )
+            })
+        })
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// TypeMap key for the maintenance store.
+pub struct MaintenanceKey;
+
+impl TypeMapKey for MaintenanceKey {
+    type Value = Arc<JsonStore<MaintenanceState>>;
+}
@@ -0,0 +1,368 @@
+//! Bounded in-memory cache of recent messages.
+//!
+//! Discord doesn't include the old content in edit and delete events, so
+//! features like snipe, edit/delete logs, and repeated-message detection read it
+//! from here instead.
+
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, GuildId, MessageId, UserId};
+use serenity::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A message as it was last seen.
+#[derive(Clone, Debug)]
+pub struct CachedMessage {
+    pub id: MessageId,
+    pub channel_id: ChannelId,
+    pub guild_id: Option<GuildId>,
+    pub author_id: UserId,
+    /// The author's tag, such as `name#0001`.
+    pub author_tag: String,
+    /// Whether the author is a bot.
+    pub bot: bool,
+    pub content: String,
+    /// Attachment URLs.
+    pub attachments: Vec<String>,
+    /// When the message was sent (seconds since the Unix epoch).
+    pub created_at: u64,
+    /// When the message was deleted, if it was.
+    pub deleted_at: Option<Instant>,
+}
+
+impl From<&Message> for CachedMessage {
+    fn from(msg: &Message) -> Self {
+        Self {
+            id: msg.id,
+            channel_id: msg.channel_id,
+            guild_id: msg.guild_id,
+            author_id: msg.author.id,
+            author_tag: msg.author.tag(),
+            bot: msg.author.bot,
+            content: msg.content.clone(),
+            attachments: msg.attachments.iter().map(|a| a.url.clone()).collect(),
+            created_at: msg.timestamp.unix_timestamp() as u64,
+            deleted_at: None,
+        }
+    }
+}
+
+impl CachedMessage {
+    /// Rough memory used by the message's text, for metrics.
+    fn approx_bytes(&self) -> usize {
+        self.content.len()
+            + self.author_tag.len()
+            + self.attachments.iter().map(String::len).sum::<usize>()
+    }
+}
+
+/// Counters describing how the cache is doing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MessageCacheStats {
+    /// Messages currently cached.
+    pub len: usize,
+    /// Maximum number of cached messages.
+    pub capacity: usize,
+    /// Rough bytes of message text held.
+    pub approx_bytes: usize,
+    /// Lookups that found a live message.
+    pub hits: u64,
+    /// Lookups that found nothing, or an expired message.
+    pub misses: u64,
+    /// Messages dropped to make room.
+    pub evictions: u64,
+    /// Messages dropped for being older than the TTL.
+    pub expirations: u64,
+}
+
+struct Entry {
+    message: CachedMessage,
+    inserted: Instant,
+    /// Position in the recency order.
+    tick: u64,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: HashMap<MessageId, Entry>,
+    /// Message IDs by last use, oldest first.
+    order: BTreeMap<u64, MessageId>,
+    tick: u64,
+    stats: MessageCacheStats,
+}
+
+impl Inner {
+    /// Move an entry to the most recently used position.
+    fn touch(&mut self, id: MessageId) {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some(entry) = self.entries.get_mut(&id) {
+            self.order.remove(&entry.tick);
+            entry.tick = tick;
+            self.order.insert(tick, id);
+        }
+    }
+
+    fn remove(&mut self, id: MessageId) -> Option<Entry> {
+        let entry = self.entries.remove(&id)?;
+        self.order.remove(&entry.tick);
+        self.stats.approx_bytes -= entry.message.approx_bytes();
+        Some(entry)
+    }
+
+    /// Get a live entry, dropping it if it has expired.
+    fn live(&mut self, id: MessageId, ttl: Duration, now: Instant) -> Option<&mut Entry> {
+        let expired = now.duration_since(self.entries.get(&id)?.inserted) > ttl;
+        if expired {
+            self.remove(id);
+            self.stats.expirations += 1;
+            return None;
+        }
+
+        self.touch(id);
+        self.entries.get_mut(&id)
+    }
+}
+
+/// An LRU cache of recent messages whose entries expire after a TTL.
+pub struct MessageCache {
+    inner: Mutex<Inner>,
+    capacity: usize,
+    ttl: Duration,
+}
+
+impl MessageCache {
+    /// Create a cache holding up to `capacity` messages for at most `ttl`.
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            inner: Mutex::new(Inner::default()),
+            capacity: capacity.max(1),
+            ttl,
+        }
+    }
+
+    /// Cache a new message, evicting the least recently used one if full.
+    pub fn insert(&self, message: CachedMessage) {
+        self.insert_at(message, Instant::now());
+    }
+
+    fn insert_at(&self, message: CachedMessage, now: Instant) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let id = message.id;
+        inner.remove(id);
+
+        while inner.entries.len() >= self.capacity {
+            let oldest = match inner.order.values().next() {
+                Some(&oldest) => oldest,
+                None => break,
+            };
+            inner.remove(oldest);
+            inner.stats.evictions += 1;
+        }
+
+        inner.stats.approx_bytes += message.approx_bytes();
+        inner.tick += 1;
+        let tick = inner.tick;
+        inner.order.insert(tick, id);
+        inner.entries.insert(
+            id,
+            Entry {
+                message,
+                inserted: now,
+                tick,
+            },
+        );
+    }
+
+    /// Get a cached message.
+    pub fn get(&self, id: MessageId) -> Option<CachedMessage> {
+        self.get_at(id, Instant::now())
+    }
+
+    fn get_at(&self, id: MessageId, now: Instant) -> Option<CachedMessage> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let message = inner
+            .live(id, self.ttl, now)
+            .map(|entry| entry.message.clone());
+
+        match message {
+            Some(_) => inner.stats.hits += 1,
+            None => inner.stats.misses += 1,
+        }
+        message
+    }
+
+    /// Record an edit, returning the message as it was before.
+    pub fn update(
+        &self,
+        id: MessageId,
+        content: Option<&str>,
+        attachments: Option<Vec<String>>,
+    ) -> Option<CachedMessage> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = inner.live(id, self.ttl, Instant::now())?;
+        let before = entry.message.clone();
+
+        if let Some(content) = content {
+            entry.message.content = content.to_string();
+        }
+        if let Some(attachments) = attachments {
+            entry.message.attachments = attachments;
+        }
+
+        let after = entry.message.approx_bytes();
+        inner.stats.approx_bytes = inner.stats.approx_bytes + after - before.approx_bytes();
+        Some(before)
+    }
+
+    /// Mark a message as deleted, returning it.
+    ///
+    /// Deleted messages stay cached until they expire or are evicted.
+    pub fn mark_deleted(&self, id: MessageId) -> Option<CachedMessage> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = inner.live(id, self.ttl, Instant::now())?;
+        entry.message.deleted_at = Some(Instant::now());
+        Some(entry.message.clone())
+    }
+
+    /// Get the most recently deleted message in a channel.
+    pub fn last_deleted(&self, channel_id: ChannelId) -> Option<CachedMessage> {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        inner
+            .entries
+            .values()
+            .filter(|entry| now.duration_since(entry.inserted) <= self.ttl)
+            .map(|entry| &entry.message)
+            .filter(|message| message.channel_id == channel_id && message.deleted_at.is_some())
+            .max_by_key(|message| message.deleted_at)
+            .cloned()
+    }
+
+    /// Get a user's cached messages in a guild sent within `window`, oldest first.
+    pub fn recent_by_author(
+        &self,
+        guild_id: GuildId,
+        author_id: UserId,
+        window: Duration,
+    ) -> Vec<CachedMessage> {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let mut messages: Vec<_> = inner
+            .entries
+            .values()
+            .filter(|entry| now.duration_since(entry.inserted) <= window.min(self.ttl))
+            .map(|entry| &entry.message)
+            .filter(|message| message.guild_id == Some(guild_id) && message.author_id == author_id)
+            .cloned()
+            .collect();
+        messages.sort_by_key(|message| message.id);
+        messages
+    }
+
+    /// Drop every cached message by a user. Returns how many were dropped.
+    pub fn forget_author(&self, author_id: UserId) -> usize {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let ids: Vec<MessageId> = inner
+            .entries
+            .values()
+            .filter(|entry| entry.message.author_id == author_id)
+            .map(|entry| entry.message.id)
+            .collect();
+
+        for &id in &ids {
+            inner.remove(id);
+        }
+        ids.len()
+    }
+
+    /// Get the cache's counters.
+    pub fn stats(&self) -> MessageCacheStats {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        MessageCacheStats {
+            len: inner.entries.len(),
+            capacity: self.capacity,
+            ..inner.stats
+        }
+    }
+}
+
+/// TypeMap key for the message cache.
+pub struct MessageCacheKey;
+
+impl TypeMapKey for MessageCacheKey {
+    type Value = Arc<MessageCache>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: u64, content: &str) -> CachedMessage {
+        CachedMessage {
+            id: MessageId(id),
+            channel_id: ChannelId(1),
+            guild_id: Some(GuildId(2)),
+            author_id: UserId(3),
+            author_tag: "user#0001".to_string(),
+            bot: false,
+            content: content.to_string(),
+            attachments: Vec::new(),
+            created_at: 0,
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let cache = MessageCache::new(2, Duration::from_secs(60));
+        cache.insert(message(1, "a"));
+        cache.insert(message(2, "b"));
+
+        // Reading 1 makes 2 the least recently used
+        assert!(cache.get(MessageId(1)).is_some());
+        cache.insert(message(3, "c"));
+
+        assert!(cache.get(MessageId(2)).is_none());
+        assert!(cache.get(MessageId(1)).is_some());
+        let stats = cache.stats();
+        assert_eq!((stats.len, stats.evictions), (2, 1));
+        assert_eq!(stats.approx_bytes, 2 * (1 + 9));
+    }
+
+    #[test]
+    fn expires_after_ttl() {
+        let cache = MessageCache::new(10, Duration::from_secs(60));
+        let start = Instant::now();
+        cache.insert_at(message(1, "a"), start);
+
+        assert!(cache
+            .get_at(MessageId(1), start + Duration::from_secs(30))
+            .is_some());
+        assert!(cache
+            .get_at(MessageId(1), start + Duration::from_secs(61))
+            .is_none());
+        assert_eq!(cache.stats().expirations, 1);
+        assert_eq!(cache.stats().len, 0);
+    }
+
+    #[test]
+    fn tracks_edits_and_deletes() {
+        let cache = MessageCache::new(10, Duration::from_secs(60));
+        cache.insert(message(1, "before"));
+
+        let before = cache.update(MessageId(1), Some("after"), None).unwrap();
+        assert_eq!(before.content, "before");
+        assert_eq!(cache.get(MessageId(1)).unwrap().content, "after");
+
+        cache.mark_deleted(MessageId(1));
+        assert_eq!(cache.last_deleted(ChannelId(1)).unwrap().content, "after");
+        assert_eq!(
+            cache
+                .recent_by_author(GuildId(2), UserId(3), Duration::from_secs(60))
+                .len(),
+            1
+        );
+    }
+}
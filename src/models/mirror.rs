@@ -0,0 +1,205 @@
+//! Channel mirrors that relay messages from a source channel to a target channel.
+//!
+//! The source can be in another server the bot shares, which lets servers syndicate
+//! announcements. Mirrors are set up from the target server with the `mirror`
+//! command, by members who can also manage the source server.
+
+use serde::{Deserialize, Serialize};
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, GuildId};
+use serenity::prelude::*;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::storage::JsonStore;
+
+/// The most mirrors a server can relay into.
+pub const MAX_MIRRORS_PER_GUILD: usize = 10;
+
+/// Which messages a mirror relays.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MirrorFilter {
+    /// Whether messages from bots and webhooks are relayed.
+    #[serde(default)]
+    pub bots: bool,
+    /// Keywords, one of which a message must contain. Empty relays every message.
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// Whether attachments are relayed as links.
+    #[serde(default = "default_true")]
+    pub attachments: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for MirrorFilter {
+    fn default() -> Self {
+        Self {
+            bots: false,
+            keywords: Vec::new(),
+            attachments: true,
+        }
+    }
+}
+
+impl MirrorFilter {
+    /// Whether a message with this content passes the filter.
+    pub fn allows(&self, content: &str, from_bot: bool) -> bool {
+        if from_bot && !self.bots {
+            return false;
+        }
+        if self.keywords.is_empty() {
+            return true;
+        }
+        let content = content.to_lowercase();
+        self.keywords
+            .iter()
+            .any(|keyword| content.contains(&keyword.to_lowercase()))
+    }
+}
+
+/// A source channel relayed to a target channel.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Mirror {
+    pub id: u64,
+    pub source_guild: u64,
+    pub source_channel: u64,
+    pub target_guild: u64,
+    pub target_channel: u64,
+    /// Who set the mirror up.
+    pub created_by: u64,
+    #[serde(default)]
+    pub filter: MirrorFilter,
+}
+
+impl Mirror {
+    /// Whether the mirror relays a message.
+    pub fn relays(&self, msg: &Message) -> bool {
+        let from_bot = msg.author.bot || msg.webhook_id.is_some();
+        let has_content =
+            !msg.content.is_empty() || (self.filter.attachments && !msg.attachments.is_empty());
+        has_content && self.filter.allows(&msg.content, from_bot)
+    }
+
+    /// The text relayed for a message, with attachment links if they are relayed.
+    pub fn content(&self, msg: &Message) -> String {
+        let mut lines = Vec::new();
+        if !msg.content.is_empty() {
+            lines.push(msg.content.clone());
+        }
+        if self.filter.attachments {
+            lines.extend(msg.attachments.iter().map(|a| a.url.clone()));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Every mirror, by ID.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MirrorData {
+    #[serde(default)]
+    pub mirrors: BTreeMap<u64, Mirror>,
+    #[serde(default)]
+    pub next_id: u64,
+}
+
+impl MirrorData {
+    /// Add a mirror and return its ID.
+    pub fn add(
+        &mut self,
+        source: (GuildId, ChannelId),
+        target: (GuildId, ChannelId),
+        created_by: u64,
+        filter: MirrorFilter,
+    ) -> u64 {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.mirrors.insert(
+            id,
+            Mirror {
+                id,
+                source_guild: source.0 .0,
+                source_channel: source.1 .0,
+                target_guild: target.0 .0,
+                target_channel: target.1 .0,
+                created_by,
+                filter,
+            },
+        );
+        id
+    }
+
+    /// The mirrors relaying from a channel.
+    pub fn from_source(&self, channel_id: ChannelId) -> Vec<Mirror> {
+        self.mirrors
+            .values()
+            .filter(|mirror| mirror.source_channel == channel_id.0)
+            .cloned()
+            .collect()
+    }
+
+    /// The mirrors relaying into a server.
+    pub fn into_guild(&self, guild_id: GuildId) -> Vec<&Mirror> {
+        self.mirrors
+            .values()
+            .filter(|mirror| mirror.target_guild == guild_id.0)
+            .collect()
+    }
+
+    /// A server's mirror by ID, for changing or removing it.
+    pub fn get_mut(&mut self, guild_id: GuildId, id: u64) -> Option<&mut Mirror> {
+        self.mirrors
+            .get_mut(&id)
+            .filter(|mirror| mirror.target_guild == guild_id.0)
+    }
+
+    /// Remove a server's mirror. Returns `false` if it has no mirror with the ID.
+    pub fn remove(&mut self, guild_id: GuildId, id: u64) -> bool {
+        if self.get_mut(guild_id, id).is_none() {
+            return false;
+        }
+        self.mirrors.remove(&id).is_some()
+    }
+}
+
+/// TypeMap key for the mirror store.
+pub struct MirrorKey;
+
+impl TypeMapKey for MirrorKey {
+    type Value = Arc<JsonStore<MirrorData>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_bots_and_keywords() {
+        let filter = MirrorFilter {
+            keywords: vec!["Release".to_string()],
+            ..MirrorFilter::default()
+        };
+        assert!(filter.allows("New release out now", false));
+        assert!(!filter.allows("Just chatting", false));
+        assert!(!filter.allows("New release out now", true));
+        assert!(MirrorFilter::default().allows("anything", false));
+    }
+
+    #[test]
+    fn only_the_target_guild_removes_a_mirror() {
+        let mut data = MirrorData::default();
+        let id = data.add(
+            (GuildId(1), ChannelId(10)),
+            (GuildId(2), ChannelId(20)),
+            5,
+            MirrorFilter::default(),
+        );
+
+        assert_eq!(data.from_source(ChannelId(10)).len(), 1);
+        assert!(!data.remove(GuildId(1), id));
+        assert!(data.remove(GuildId(2), id));
+        assert!(data.from_source(ChannelId(10)).is_empty());
+    }
+}
@@ -1,5 +1,77 @@
 //! Data models and structures used throughout the application.
 
+pub mod aliases;
+pub mod analytics;
+pub mod antinuke;
+pub mod audit;
+pub mod auto_response;
 pub mod config;
+pub mod config_transfer;
+pub mod feeds;
+#[cfg(feature = "games")]
+pub mod games;
+pub mod growth;
+pub mod guild_config;
+pub mod guild_events;
+pub mod ignore;
+pub mod knowledge_base;
+pub mod lfg;
+pub mod maintenance;
+pub mod message_cache;
+pub mod mirror;
+pub mod moderation;
+pub mod modmail;
+pub mod music;
+pub mod notifications;
+pub mod onboarding;
+pub mod permission_templates;
+pub mod pin_archive;
+pub mod pin_votes;
+pub mod premium;
+pub mod quotes;
+pub mod role_persistence;
+pub mod shard_health;
+#[cfg(feature = "music")]
+pub mod sounds;
+pub mod todos;
+pub mod user_data;
+pub mod voice_text;
+pub mod votes;
+#[cfg(feature = "automod")]
+pub mod word_filter;
 
-pub use config::{BotConfig, CommandsConfig, LoggingConfig};
+pub use analytics::{Analytics, AnalyticsData, AnalyticsKey};
+pub use audit::{AuditKey, AuditLog};
+pub use auto_response::{AutoResponseData, AutoResponseKey};
+pub use config::{
+    AnalyticsConfig, BotConfig, CommandsConfig, DatabaseConfig, DispatchConfig, ExchangeConfig,
+    FeedsConfig, LoggingConfig, MentionsConfig, MessageCacheConfig, PasteConfig, PasteService,
+    PhishingConfig, RestConfig, RetentionConfig, RunConfig, ShardHealthConfig, StorageConfig,
+    UploadsConfig,
+};
+pub use feeds::{FeedData, FeedKey};
+#[cfg(feature = "games")]
+pub use games::{GamesData, GamesKey};
+pub use growth::{GrowthData, GrowthKey};
+pub use guild_config::{GuildConfig, GuildConfigKey, GuildConfigs};
+pub use guild_events::{EventData, EventKey};
+pub use ignore::{IgnoreList, IgnoreScope};
+pub use knowledge_base::{KnowledgeBaseData, KnowledgeBaseKey};
+pub use lfg::{LfgData, LfgKey};
+pub use maintenance::{MaintenanceKey, MaintenanceState};
+pub use message_cache::{MessageCache, MessageCacheKey};
+pub use mirror::{MirrorData, MirrorKey};
+pub use moderation::{ModerationData, ModerationKey};
+pub use modmail::{ModmailData, ModmailKey};
+pub use notifications::{NotificationData, NotificationKey};
+pub use onboarding::OnboardingFlow;
+pub use pin_archive::{PinArchiveData, PinArchiveKey};
+pub use pin_votes::{PinVoteData, PinVoteKey};
+pub use premium::{PremiumData, PremiumKey, Tier};
+pub use quotes::{QuoteData, QuoteKey};
+pub use role_persistence::{RolePersistenceData, RolePersistenceKey};
+pub use shard_health::{ShardHealth, ShardHealthKey};
+pub use todos::{TodoData, TodoKey};
+pub use votes::{VoteData, VotesKey};
+#[cfg(feature = "automod")]
+pub use word_filter::{WordFilterData, WordFilterKey};
@@ -0,0 +1,466 @@
+//! Moderation data models: notes, cases, appeals, reports, and the watchlist.
+
+use serde::{Deserialize, Serialize};
+use serenity::model::channel::Message;
+use serenity::model::id::{GuildId, MessageId, UserId};
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::storage::JsonStore;
+use crate::utils::helpers::unix_timestamp;
+
+/// A private note left by a moderator about a user.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Note {
+    /// Note ID, unique within the guild.
+    pub id: u64,
+    /// The moderator who wrote the note.
+    pub author_id: u64,
+    /// The note text.
+    pub content: String,
+    /// When the note was written (seconds since the Unix epoch).
+    pub created_at: u64,
+}
+
+/// The kind of action recorded in a moderation case.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseKind {
+    Warning,
+    Timeout,
+    Kick,
+    Ban,
+    Unban,
+}
+
+impl fmt::Display for CaseKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CaseKind::Warning => "Warning",
+            CaseKind::Timeout => "Timeout",
+            CaseKind::Kick => "Kick",
+            CaseKind::Ban => "Ban",
+            CaseKind::Unban => "Unban",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// A recorded moderation action against a user.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ModCase {
+    /// Case ID, unique within the guild.
+    pub id: u64,
+    /// What kind of action was taken.
+    pub kind: CaseKind,
+    /// The moderator who took the action, if known.
+    pub moderator_id: Option<u64>,
+    /// The reason given for the action.
+    pub reason: Option<String>,
+    /// When the action was taken (seconds since the Unix epoch).
+    pub created_at: u64,
+    /// When the punishment ends, for temporary punishments.
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+impl ModCase {
+    /// Whether this case is a punishment that is still in effect.
+    pub fn is_active(&self) -> bool {
+        match self.kind {
+            CaseKind::Ban => true,
+            CaseKind::Timeout => self.expires_at.is_some_and(|t| t > unix_timestamp()),
+            _ => false,
+        }
+    }
+}
+
+/// The state of a punishment appeal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AppealStatus {
+    Pending,
+    Accepted,
+    Denied,
+}
+
+/// A user's appeal against a ban or timeout.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Appeal {
+    /// Appeal ID, unique within the guild.
+    pub id: u64,
+    /// The user who appealed.
+    pub user_id: u64,
+    /// The case being appealed.
+    pub case_id: u64,
+    /// The kind of punishment being appealed.
+    pub kind: CaseKind,
+    /// The user's explanation.
+    pub content: String,
+    /// Whether the appeal has been decided.
+    pub status: AppealStatus,
+    /// The staff member who decided the appeal.
+    #[serde(default)]
+    pub decided_by: Option<u64>,
+    /// When the appeal was submitted (seconds since the Unix epoch).
+    pub created_at: u64,
+}
+
+/// The state of a message report.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportStatus {
+    Open,
+    Resolved,
+    Dismissed,
+}
+
+/// An action staff took on a report.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportAction {
+    Delete,
+    Warn,
+    Timeout,
+}
+
+impl fmt::Display for ReportAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ReportAction::Delete => "Message deleted",
+            ReportAction::Warn => "Author warned",
+            ReportAction::Timeout => "Author timed out",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// A member's report of a message, with a snapshot of the message.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Report {
+    /// Report ID, unique within the guild.
+    pub id: u64,
+    /// The user who made the report.
+    pub reporter_id: u64,
+    /// The author of the reported message.
+    pub author_id: u64,
+    pub channel_id: u64,
+    pub message_id: u64,
+    /// The reported message's content when it was reported.
+    pub content: String,
+    /// Links to the reported message's attachments.
+    #[serde(default)]
+    pub attachments: Vec<String>,
+    /// The reporter's explanation.
+    pub reason: String,
+    pub status: ReportStatus,
+    /// Actions staff took, in order.
+    #[serde(default)]
+    pub actions: Vec<ReportAction>,
+    /// The staff member who resolved or dismissed the report.
+    #[serde(default)]
+    pub resolved_by: Option<u64>,
+    /// When the report was made (seconds since the Unix epoch).
+    pub created_at: u64,
+}
+
+/// A user on the guild's watchlist.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WatchEntry {
+    /// The moderator who added the user.
+    pub added_by: u64,
+    /// Why the user is being watched.
+    pub reason: Option<String>,
+    /// When the user was added (seconds since the Unix epoch).
+    pub created_at: u64,
+}
+
+/// An item in a user's moderation history.
+pub enum HistoryEntry<'a> {
+    Note(&'a Note),
+    Case(&'a ModCase),
+}
+
+impl HistoryEntry<'_> {
+    /// When the entry was created.
+    pub fn created_at(&self) -> u64 {
+        match self {
+            HistoryEntry::Note(note) => note.created_at,
+            HistoryEntry::Case(case) => case.created_at,
+        }
+    }
+}
+
+/// Moderation data for a single guild.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GuildModeration {
+    /// The last ID handed out to a note or case.
+    #[serde(default)]
+    pub last_id: u64,
+    /// Notes by user ID.
+    #[serde(default)]
+    pub notes: HashMap<u64, Vec<Note>>,
+    /// Cases by user ID.
+    #[serde(default)]
+    pub cases: HashMap<u64, Vec<ModCase>>,
+    /// Watched users by user ID.
+    #[serde(default)]
+    pub watchlist: HashMap<u64, WatchEntry>,
+    /// Punishment appeals by appeal ID.
+    #[serde(default)]
+    pub appeals: HashMap<u64, Appeal>,
+    /// Message reports by report ID.
+    #[serde(default)]
+    pub reports: HashMap<u64, Report>,
+}
+
+impl GuildModeration {
+    /// Hand out the next note or case ID.
+    fn next_id(&mut self) -> u64 {
+        self.last_id += 1;
+        self.last_id
+    }
+
+    /// Add a note about a user and return its ID.
+    pub fn add_note(&mut self, user_id: UserId, author_id: UserId, content: String) -> u64 {
+        let id = self.next_id();
+        self.notes.entry(user_id.0).or_default().push(Note {
+            id,
+            author_id: author_id.0,
+            content,
+            created_at: unix_timestamp(),
+        });
+        id
+    }
+
+    /// Remove a note by ID. Returns whether a note was removed.
+    pub fn remove_note(&mut self, user_id: UserId, note_id: u64) -> bool {
+        match self.notes.get_mut(&user_id.0) {
+            Some(notes) => {
+                let before = notes.len();
+                notes.retain(|note| note.id != note_id);
+                notes.len() != before
+            }
+            None => false,
+        }
+    }
+
+    /// Record a moderation case against a user and return its ID.
+    pub fn add_case(
+        &mut self,
+        user_id: UserId,
+        kind: CaseKind,
+        moderator_id: Option<UserId>,
+        reason: Option<String>,
+        expires_at: Option<u64>,
+    ) -> u64 {
+        let id = self.next_id();
+        self.cases.entry(user_id.0).or_default().push(ModCase {
+            id,
+            kind,
+            moderator_id: moderator_id.map(|id| id.0),
+            reason,
+            created_at: unix_timestamp(),
+            expires_at,
+        });
+        id
+    }
+
+    /// Get the user's most recent case, if any.
+    pub fn latest_case(&self, user_id: UserId) -> Option<&ModCase> {
+        self.cases.get(&user_id.0)?.last()
+    }
+
+    /// Get the punishment currently in effect for a user, if any.
+    ///
+    /// A ban is lifted by a later unban; a timeout ends when it expires.
+    pub fn active_punishment(&self, user_id: UserId) -> Option<&ModCase> {
+        let cases = self.cases.get(&user_id.0)?;
+        let latest = cases.iter().rev().find(|case| {
+            matches!(
+                case.kind,
+                CaseKind::Ban | CaseKind::Unban | CaseKind::Timeout
+            )
+        })?;
+
+        Some(latest).filter(|case| case.is_active())
+    }
+
+    /// Record that a user's active punishment was lifted.
+    ///
+    /// Bans get a matching unban case; timeouts are marked as expired.
+    pub fn lift_punishment(
+        &mut self,
+        user_id: UserId,
+        moderator_id: Option<UserId>,
+        reason: Option<String>,
+    ) -> Option<CaseKind> {
+        let (case_id, kind) = self
+            .active_punishment(user_id)
+            .map(|case| (case.id, case.kind))?;
+
+        match kind {
+            CaseKind::Ban => {
+                self.add_case(user_id, CaseKind::Unban, moderator_id, reason, None);
+            }
+            _ => {
+                let now = unix_timestamp();
+                if let Some(case) = self
+                    .cases
+                    .get_mut(&user_id.0)
+                    .and_then(|cases| cases.iter_mut().find(|case| case.id == case_id))
+                {
+                    case.expires_at = Some(now);
+                }
+            }
+        }
+
+        Some(kind)
+    }
+
+    /// Get the user's pending appeal, if any.
+    pub fn pending_appeal(&self, user_id: UserId) -> Option<&Appeal> {
+        self.appeals
+            .values()
+            .find(|appeal| appeal.user_id == user_id.0 && appeal.status == AppealStatus::Pending)
+    }
+
+    /// Open an appeal against a case and return its ID.
+    pub fn add_appeal(&mut self, user_id: UserId, case: &ModCase, content: String) -> u64 {
+        let id = self.next_id();
+        self.appeals.insert(
+            id,
+            Appeal {
+                id,
+                user_id: user_id.0,
+                case_id: case.id,
+                kind: case.kind,
+                content,
+                status: AppealStatus::Pending,
+                decided_by: None,
+                created_at: unix_timestamp(),
+            },
+        );
+        id
+    }
+
+    /// Get the open report of a message, if any.
+    pub fn open_report(&self, message_id: MessageId) -> Option<&Report> {
+        self.reports
+            .values()
+            .find(|report| report.message_id == message_id.0 && report.status == ReportStatus::Open)
+    }
+
+    /// Record a report of a message and return its ID.
+    pub fn add_report(&mut self, reporter_id: UserId, message: &Message, reason: String) -> u64 {
+        let id = self.next_id();
+        self.reports.insert(
+            id,
+            Report {
+                id,
+                reporter_id: reporter_id.0,
+                author_id: message.author.id.0,
+                channel_id: message.channel_id.0,
+                message_id: message.id.0,
+                content: message.content.clone(),
+                attachments: message
+                    .attachments
+                    .iter()
+                    .map(|attachment| attachment.url.clone())
+                    .collect(),
+                reason,
+                status: ReportStatus::Open,
+                actions: Vec::new(),
+                resolved_by: None,
+                created_at: unix_timestamp(),
+            },
+        );
+        id
+    }
+
+    /// Delete cases, decided appeals and closed reports created before `before` (seconds
+    /// since the Unix epoch). Punishments still in effect are kept. Returns how many were
+    /// deleted.
+    pub fn purge_cases(&mut self, before: u64) -> usize {
+        let mut removed = 0;
+        let users: Vec<u64> = self.cases.keys().copied().collect();
+        for user_id in users {
+            let active = self.active_punishment(UserId(user_id)).map(|case| case.id);
+            if let Some(cases) = self.cases.get_mut(&user_id) {
+                let count = cases.len();
+                cases.retain(|case| case.created_at >= before || Some(case.id) == active);
+                removed += count - cases.len();
+                if cases.is_empty() {
+                    self.cases.remove(&user_id);
+                }
+            }
+        }
+
+        let count = self.appeals.len();
+        self.appeals.retain(|_, appeal| {
+            appeal.created_at >= before || appeal.status == AppealStatus::Pending
+        });
+        removed += count - self.appeals.len();
+
+        let count = self.reports.len();
+        self.reports
+            .retain(|_, report| report.created_at >= before || report.status == ReportStatus::Open);
+        removed + count - self.reports.len()
+    }
+
+    /// Get a user's notes and cases, newest first.
+    pub fn history(&self, user_id: UserId) -> Vec<HistoryEntry<'_>> {
+        let notes = self
+            .notes
+            .get(&user_id.0)
+            .into_iter()
+            .flatten()
+            .map(HistoryEntry::Note);
+        let cases = self
+            .cases
+            .get(&user_id.0)
+            .into_iter()
+            .flatten()
+            .map(HistoryEntry::Case);
+
+        let mut history: Vec<_> = notes.chain(cases).collect();
+        history.sort_by_key(|entry| std::cmp::Reverse(entry.created_at()));
+        history
+    }
+}
+
+/// Moderation data for all guilds.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ModerationData {
+    /// Moderation data by guild ID.
+    #[serde(default)]
+    pub guilds: HashMap<u64, GuildModeration>,
+}
+
+impl ModerationData {
+    /// Get a guild's moderation data, if it has any.
+    pub fn guild(&self, guild_id: GuildId) -> Option<&GuildModeration> {
+        self.guilds.get(&guild_id.0)
+    }
+
+    /// Get a mutable reference to a guild's moderation data, creating it if needed.
+    pub fn guild_mut(&mut self, guild_id: GuildId) -> &mut GuildModeration {
+        self.guilds.entry(guild_id.0).or_default()
+    }
+
+    /// Get a user's watchlist entry, if they are being watched.
+    pub fn watch_entry(&self, guild_id: GuildId, user_id: UserId) -> Option<&WatchEntry> {
+        self.guild(guild_id)?.watchlist.get(&user_id.0)
+    }
+}
+
+/// TypeMap key for the moderation store.
+pub struct ModerationKey;
+
+impl TypeMapKey for ModerationKey {
+    type Value = Arc<JsonStore<ModerationData>>;
+}
@@ -0,0 +1,126 @@
+//! Modmail data models: open conversations, transcripts, and blocked users.
+
+use serde::{Deserialize, Serialize};
+use serenity::model::id::{ChannelId, GuildId, UserId};
+use serenity::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::storage::JsonStore;
+
+/// A single message in a modmail conversation.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TranscriptEntry {
+    /// Who sent the message. For staff replies this is the real staff member.
+    pub author_id: u64,
+    /// The sender's tag at the time of sending.
+    pub author_tag: String,
+    /// Whether the message was a staff reply.
+    pub from_staff: bool,
+    /// The message text.
+    pub content: String,
+    /// URLs of attached files.
+    #[serde(default)]
+    pub attachments: Vec<String>,
+    /// When the message was sent (seconds since the Unix epoch).
+    pub sent_at: u64,
+}
+
+/// An open modmail conversation between a user and a guild's staff.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ModmailThread {
+    /// The guild whose staff the user is talking to.
+    pub guild_id: u64,
+    /// The staff-side thread the conversation is relayed into.
+    pub thread_id: u64,
+    /// The user who opened the conversation.
+    pub user_id: u64,
+    /// When the conversation was opened (seconds since the Unix epoch).
+    pub opened_at: u64,
+    /// Every message relayed in either direction.
+    #[serde(default)]
+    pub transcript: Vec<TranscriptEntry>,
+}
+
+impl ModmailThread {
+    /// Render the conversation as a plain-text transcript.
+    pub fn transcript_text(&self) -> String {
+        let mut text = format!(
+            "Modmail transcript\nUser: {}\nGuild: {}\nOpened: {}\n\n",
+            self.user_id,
+            self.guild_id,
+            format_time(self.opened_at)
+        );
+
+        for entry in &self.transcript {
+            let role = if entry.from_staff { "staff" } else { "user" };
+            text.push_str(&format!(
+                "[{}] {} ({}, {}): {}\n",
+                format_time(entry.sent_at),
+                entry.author_tag,
+                entry.author_id,
+                role,
+                entry.content
+            ));
+            for url in &entry.attachments {
+                text.push_str(&format!("    attachment: {}\n", url));
+            }
+        }
+
+        text
+    }
+
+    /// Drop transcript messages sent before `before` (seconds since the Unix epoch).
+    /// Returns how many were dropped.
+    pub fn purge_transcript(&mut self, before: u64) -> usize {
+        let count = self.transcript.len();
+        self.transcript.retain(|entry| entry.sent_at >= before);
+        count - self.transcript.len()
+    }
+}
+
+/// Format a Unix timestamp as a UTC date and time.
+fn format_time(timestamp: u64) -> String {
+    chrono::DateTime::from_timestamp(timestamp as i64, 0)
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_default()
+}
+
+/// Modmail state for all guilds.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ModmailData {
+    /// Open conversations by user ID. A user has at most one at a time.
+    #[serde(default)]
+    pub open: HashMap<u64, ModmailThread>,
+    /// Users blocked from modmail, by guild ID.
+    #[serde(default)]
+    pub blocked: HashMap<u64, HashSet<u64>>,
+}
+
+impl ModmailData {
+    /// Get a user's open conversation, if any.
+    pub fn thread_for_user(&self, user_id: UserId) -> Option<&ModmailThread> {
+        self.open.get(&user_id.0)
+    }
+
+    /// Get the conversation relayed into a staff thread, if any.
+    pub fn thread_for_channel(&self, channel_id: ChannelId) -> Option<&ModmailThread> {
+        self.open
+            .values()
+            .find(|thread| thread.thread_id == channel_id.0)
+    }
+
+    /// Whether a user is blocked from contacting a guild's staff.
+    pub fn is_blocked(&self, guild_id: GuildId, user_id: UserId) -> bool {
+        self.blocked
+            .get(&guild_id.0)
+            .is_some_and(|users| users.contains(&user_id.0))
+    }
+}
+
+/// TypeMap key for the modmail store.
+pub struct ModmailKey;
+
+impl TypeMapKey for ModmailKey {
+    type Value = Arc<JsonStore<ModmailData>>;
+}
@@ -0,0 +1,341 @@
+//! A guild's music settings: its own radio presets, on top of the ones in the
+//! `[music.radio]` config, who may control playback, how much can be queued,
+//! where the bot stays connected, whether lyrics are shown and the audio
+//! filters tracks are played through. Changed with `radio`, `music` and
+//! `audiofilter`.
+//!
+//! While a member listens alone they control playback. Once others are in the
+//! voice channel, skipping, stopping and clearing the queue need the DJ role or
+//! Manage Server, and everyone else votes to skip instead.
+
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serenity::model::id::RoleId;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use super::config::MusicConfig;
+
+/// Most radio presets a guild can add.
+pub const MAX_RADIO_PRESETS: usize = 25;
+
+/// Longest preset name.
+const MAX_NAME_LENGTH: usize = 32;
+
+/// A guild's music settings.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MusicSettings {
+    /// Radio stream URLs added with `radio add`, by preset name.
+    #[serde(default)]
+    pub radio: BTreeMap<String, String>,
+
+    /// Role that can skip, stop and clear while others are listening.
+    #[serde(default)]
+    pub dj_role: Option<u64>,
+
+    /// Most tracks the queue can hold, below `music.max_queue`.
+    #[serde(default)]
+    pub max_queue: Option<usize>,
+
+    /// Longest track that can be queued in seconds, below
+    /// `music.max_track_length`.
+    #[serde(default)]
+    pub max_track_length: Option<u64>,
+
+    /// Voice channel the bot stays connected to around the clock, rejoining
+    /// after disconnects and restarts. Changed with `music 247`.
+    #[serde(default)]
+    pub always_on: Option<u64>,
+
+    /// Whether the now-playing message shows time-synced lyrics. Changed with
+    /// `music karaoke`.
+    #[serde(default)]
+    pub karaoke: bool,
+
+    /// Filters every track is played through. Changed with `audiofilter`.
+    #[serde(default)]
+    pub filters: AudioFilters,
+}
+
+impl MusicSettings {
+    /// The stream URL for a preset, preferring the guild's own over the built-in
+    /// ones.
+    pub fn radio_stream<'a>(
+        &'a self,
+        builtin: &'a BTreeMap<String, String>,
+        name: &str,
+    ) -> Option<&'a str> {
+        self.radio
+            .get(name)
+            .or_else(|| builtin.get(name))
+            .map(String::as_str)
+    }
+
+    /// Most tracks the queue can hold.
+    pub fn queue_limit(&self, config: &MusicConfig) -> usize {
+        self.max_queue
+            .map_or(config.max_queue, |max| max.min(config.max_queue))
+    }
+
+    /// Longest track that can be queued.
+    pub fn track_limit(&self, config: &MusicConfig) -> Duration {
+        let seconds = self
+            .max_track_length
+            .map_or(config.max_track_length, |max| {
+                max.min(config.max_track_length)
+            });
+        Duration::from_secs(seconds)
+    }
+
+    /// Whether a member is a DJ: they have the DJ role or Manage Server.
+    pub fn is_dj(&self, roles: &[RoleId], manage_guild: bool) -> bool {
+        manage_guild
+            || self
+                .dj_role
+                .is_some_and(|dj_role| roles.contains(&RoleId(dj_role)))
+    }
+
+    /// Whether a member can skip, stop or clear the queue without a vote.
+    /// `listeners` counts the members in the voice channel, bots excluded.
+    pub fn can_control(&self, roles: &[RoleId], manage_guild: bool, listeners: usize) -> bool {
+        listeners <= 1 || self.is_dj(roles, manage_guild)
+    }
+}
+
+/// Sample rate the audio is processed at.
+const SAMPLE_RATE: u32 = 48_000;
+
+/// Slowest and fastest playback speed, in percent.
+pub const SPEED_RANGE: std::ops::RangeInclusive<u32> = 50..=200;
+
+/// Furthest the pitch can be shifted, in semitones.
+pub const PITCH_RANGE: std::ops::RangeInclusive<i32> = -12..=12;
+
+/// An equalizer preset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Equalizer {
+    /// Louder lows.
+    Bassboost,
+    /// Louder highs.
+    Treble,
+    /// Louder mids, where voices are.
+    Vocal,
+}
+
+impl Equalizer {
+    /// Every preset, as named in `audiofilter`.
+    pub const ALL: [(&'static str, Equalizer); 3] = [
+        ("bassboost", Equalizer::Bassboost),
+        ("treble", Equalizer::Treble),
+        ("vocal", Equalizer::Vocal),
+    ];
+
+    fn name(self) -> &'static str {
+        Self::ALL
+            .iter()
+            .find(|(_, preset)| *preset == self)
+            .map_or("", |(name, _)| name)
+    }
+
+    fn filter(self) -> &'static str {
+        match self {
+            Equalizer::Bassboost => "bass=g=8:f=110",
+            Equalizer::Treble => "treble=g=6:f=3000",
+            Equalizer::Vocal => "equalizer=f=2500:t=q:w=1:g=5",
+        }
+    }
+}
+
+/// Audio filters for a guild's tracks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioFilters {
+    /// Equalizer preset.
+    #[serde(default)]
+    pub equalizer: Option<Equalizer>,
+
+    /// Playback speed in percent, without changing the pitch.
+    #[serde(default)]
+    pub speed: Option<u32>,
+
+    /// Pitch shift in semitones, without changing the speed.
+    #[serde(default)]
+    pub pitch: Option<i32>,
+}
+
+impl AudioFilters {
+    /// Faster and higher.
+    pub const NIGHTCORE: Self = Self {
+        equalizer: None,
+        speed: Some(125),
+        pitch: Some(4),
+    };
+
+    /// Slower and lower.
+    pub const VAPORWAVE: Self = Self {
+        equalizer: None,
+        speed: Some(80),
+        pitch: Some(-4),
+    };
+
+    /// Whether no filter is set.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The filters as a list like `bassboost, 125% speed, +4 semitones`.
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(equalizer) = self.equalizer {
+            parts.push(equalizer.name().to_string());
+        }
+        if let Some(speed) = self.speed {
+            parts.push(format!("{}% speed", speed));
+        }
+        if let Some(pitch) = self.pitch {
+            parts.push(format!("{:+} semitones", pitch));
+        }
+        if parts.is_empty() {
+            "none".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+
+    /// How much faster than the source the audio plays. Shifting the pitch
+    /// leaves the speed alone.
+    pub fn tempo(&self) -> f64 {
+        self.speed.map_or(1.0, |speed| speed as f64 / 100.0)
+    }
+
+    /// The ffmpeg filter graph the audio input is run through, if any filter is
+    /// set.
+    pub fn filter_graph(&self) -> Option<String> {
+        let mut filters = Vec::new();
+        if let Some(pitch) = self.pitch.filter(|pitch| *pitch != 0) {
+            // Resampling shifts pitch and speed together; the tempo change undoes
+            // the speed part
+            let ratio = 2f64.powf(pitch as f64 / 12.0);
+            filters.push(format!(
+                "asetrate={:.0},aresample={},atempo={:.4}",
+                SAMPLE_RATE as f64 * ratio,
+                SAMPLE_RATE,
+                1.0 / ratio
+            ));
+        }
+        if let Some(speed) = self.speed.filter(|speed| *speed != 100) {
+            filters.push(format!("atempo={:.2}", speed as f64 / 100.0));
+        }
+        if let Some(equalizer) = self.equalizer {
+            filters.push(equalizer.filter().to_string());
+        }
+        (!filters.is_empty()).then(|| filters.join(","))
+    }
+}
+
+/// How many of the members in a voice channel have to vote to skip: a majority.
+pub fn votes_needed(listeners: usize) -> usize {
+    listeners / 2 + 1
+}
+
+/// Check a radio preset name, returning it normalized.
+pub fn check_preset_name(name: &str) -> Result<String, String> {
+    let name = name.to_lowercase();
+    let valid = !name.is_empty()
+        && name.chars().count() <= MAX_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    // `list`, `add` and `remove` are subcommands of `radio`
+    if !valid || matches!(name.as_str(), "list" | "add" | "remove" | "delete") {
+        return Err(format!(
+            "Preset names are up to {} letters, numbers, `-` and `_`, and can't be `list`, `add` or `remove`.",
+            MAX_NAME_LENGTH
+        ));
+    }
+    Ok(name)
+}
+
+/// Check a stream URL, which has to be `http` or `https`.
+pub fn check_stream_url(url: &str) -> Result<String, String> {
+    let url = url.trim_start_matches('<').trim_end_matches('>');
+    match Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") && parsed.has_host() => {
+            Ok(parsed.to_string())
+        }
+        _ => Err("Give the stream as an `http` or `https` URL.".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_and_urls() {
+        let builtin = BTreeMap::from([
+            ("lofi".to_string(), "https://a.example/lofi".to_string()),
+            ("jazz".to_string(), "https://a.example/jazz".to_string()),
+        ]);
+        let mut settings = MusicSettings::default();
+        settings
+            .radio
+            .insert("jazz".to_string(), "https://b.example/jazz".to_string());
+        assert_eq!(
+            settings.radio_stream(&builtin, "lofi"),
+            Some("https://a.example/lofi")
+        );
+        assert_eq!(
+            settings.radio_stream(&builtin, "jazz"),
+            Some("https://b.example/jazz")
+        );
+        assert_eq!(settings.radio_stream(&builtin, "rock"), None);
+
+        assert_eq!(check_preset_name("Synth-Wave").unwrap(), "synth-wave");
+        assert!(check_preset_name("list").is_err());
+        assert!(check_preset_name("a b").is_err());
+
+        assert!(check_stream_url("<https://radio.example/live.mp3>").is_ok());
+        assert!(check_stream_url("ftp://radio.example/live.mp3").is_err());
+        assert!(check_stream_url("not a url").is_err());
+    }
+
+    #[test]
+    fn control_and_limits() {
+        let config = MusicConfig::default();
+        let mut settings = MusicSettings::default();
+        assert!(settings.can_control(&[], false, 1));
+        assert!(!settings.can_control(&[RoleId(5)], false, 2));
+        assert!(settings.can_control(&[], true, 2));
+        settings.dj_role = Some(5);
+        assert!(settings.can_control(&[RoleId(5)], false, 3));
+
+        assert_eq!(votes_needed(1), 1);
+        assert_eq!(votes_needed(4), 3);
+        assert_eq!(votes_needed(5), 3);
+
+        assert_eq!(settings.queue_limit(&config), config.max_queue);
+        settings.max_queue = Some(config.max_queue + 1);
+        assert_eq!(settings.queue_limit(&config), config.max_queue);
+        settings.max_track_length = Some(60);
+        assert_eq!(settings.track_limit(&config), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn filter_graphs() {
+        assert_eq!(AudioFilters::default().filter_graph(), None);
+        assert_eq!(AudioFilters::default().describe(), "none");
+        assert_eq!(AudioFilters::default().tempo(), 1.0);
+
+        let filters = AudioFilters {
+            equalizer: Some(Equalizer::Bassboost),
+            ..AudioFilters::NIGHTCORE
+        };
+        assert_eq!(filters.describe(), "bassboost, 125% speed, +4 semitones");
+        assert_eq!(filters.tempo(), 1.25);
+        assert_eq!(
+            filters.filter_graph().as_deref(),
+            Some("asetrate=60476,aresample=48000,atempo=0.7937,atempo=1.25,bass=g=8:f=110")
+        );
+    }
+}
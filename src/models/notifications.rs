@@ -0,0 +1,144 @@
+//! Which kinds of DMs each user wants from the bot.
+
+use serde::{Deserialize, Serialize};
+use serenity::model::id::UserId;
+use serenity::prelude::*;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+use crate::storage::JsonStore;
+
+/// A kind of DM the bot sends. Everything is on until a user turns it off.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DmCategory {
+    /// Reaching a new level.
+    LevelUps,
+    /// Due todo items and other reminders.
+    Reminders,
+    /// Winning a giveaway.
+    GiveawayWins,
+    /// Warnings, appeal decisions and other notices from server staff.
+    Moderation,
+}
+
+impl DmCategory {
+    /// Every category, in the order they're listed.
+    pub const ALL: [DmCategory; 4] = [
+        DmCategory::LevelUps,
+        DmCategory::Reminders,
+        DmCategory::GiveawayWins,
+        DmCategory::Moderation,
+    ];
+
+    /// The name used in the `notifications` command.
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::LevelUps => "levelups",
+            Self::Reminders => "reminders",
+            Self::GiveawayWins => "giveaways",
+            Self::Moderation => "moderation",
+        }
+    }
+
+    /// What the category covers.
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::LevelUps => "When you reach a new level",
+            Self::Reminders => "Due todo items and other reminders",
+            Self::GiveawayWins => "When you win a giveaway",
+            Self::Moderation => "Warnings and appeal decisions from server staff",
+        }
+    }
+}
+
+impl fmt::Display for DmCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.key())
+    }
+}
+
+impl std::str::FromStr for DmCategory {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace(['-', '_'], "").as_str() {
+            "levelups" | "levelup" | "levels" => Ok(Self::LevelUps),
+            "reminders" | "reminder" | "todo" | "todos" => Ok(Self::Reminders),
+            "giveaways" | "giveaway" | "giveawaywins" => Ok(Self::GiveawayWins),
+            "moderation" | "mod" | "staff" => Ok(Self::Moderation),
+            _ => Err(format!(
+                "Unknown category `{}`. Use levelups, reminders, giveaways or moderation.",
+                s
+            )),
+        }
+    }
+}
+
+/// A user's notification preferences.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct UserPrefs {
+    /// Categories the user turned off.
+    #[serde(default)]
+    pub muted: BTreeSet<DmCategory>,
+}
+
+/// Notification preferences for all users.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct NotificationData {
+    /// Preferences by user ID. Users with everything on aren't stored.
+    #[serde(default)]
+    pub users: HashMap<u64, UserPrefs>,
+}
+
+impl NotificationData {
+    /// Whether a user wants DMs of a category.
+    pub fn allows(&self, user_id: UserId, category: DmCategory) -> bool {
+        self.users
+            .get(&user_id.0)
+            .is_none_or(|prefs| !prefs.muted.contains(&category))
+    }
+
+    /// Turn categories on or off for a user.
+    pub fn set(&mut self, user_id: UserId, categories: &[DmCategory], enabled: bool) {
+        let prefs = self.users.entry(user_id.0).or_default();
+        for category in categories {
+            if enabled {
+                prefs.muted.remove(category);
+            } else {
+                prefs.muted.insert(*category);
+            }
+        }
+        if prefs.muted.is_empty() {
+            self.users.remove(&user_id.0);
+        }
+    }
+}
+
+/// TypeMap key for the notification preference store.
+pub struct NotificationKey;
+
+impl TypeMapKey for NotificationKey {
+    type Value = Arc<JsonStore<NotificationData>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mutes_and_unmutes_categories() {
+        let mut data = NotificationData::default();
+        let user = UserId(1);
+        assert!(data.allows(user, DmCategory::Reminders));
+
+        data.set(user, &[DmCategory::Reminders, DmCategory::LevelUps], false);
+        assert!(!data.allows(user, DmCategory::Reminders));
+        assert!(data.allows(user, DmCategory::Moderation));
+
+        data.set(user, &DmCategory::ALL, true);
+        assert!(data.users.is_empty());
+        assert_eq!("level-ups".parse(), Ok(DmCategory::LevelUps));
+    }
+}
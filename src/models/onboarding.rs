@@ -0,0 +1,188 @@
+//! Onboarding flows that new members go through before they get access.
+//!
+//! A flow shows the server's rules, asks a few questions whose answers give roles
+//! (pronouns, interests), and finally grants the access roles. Flows are defined per
+//! guild with the `onboarding` command and stored in the guild configuration.
+
+use serde::{Deserialize, Serialize};
+use serenity::model::id::RoleId;
+
+/// The most questions a flow can have.
+pub const MAX_QUESTIONS: usize = 5;
+
+/// The most choices a question can have, the limit of a select menu.
+pub const MAX_CHOICES: usize = 25;
+
+/// A guild's onboarding flow.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct OnboardingFlow {
+    /// Whether new members are sent through the flow.
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Rules shown before the questions, which members accept to continue.
+    #[serde(default)]
+    pub rules: String,
+
+    /// Questions asked in order.
+    #[serde(default)]
+    pub questions: Vec<Question>,
+
+    /// Roles granted once the flow is finished.
+    #[serde(default)]
+    pub access_roles: Vec<u64>,
+
+    /// Channel to prompt in when a member's DMs are closed. Without one, only DMs
+    /// are used.
+    #[serde(default)]
+    pub channel: Option<u64>,
+
+    /// Accounts younger than this many days finish the flow without access, and
+    /// staff are told to check them.
+    #[serde(default)]
+    pub min_account_age_days: u64,
+}
+
+impl OnboardingFlow {
+    /// Whether the flow does anything when a member joins.
+    pub fn is_active(&self) -> bool {
+        self.enabled && (!self.access_roles.is_empty() || !self.questions.is_empty())
+    }
+
+    /// Every role the flow can give.
+    pub fn roles(&self) -> impl Iterator<Item = u64> + '_ {
+        self.access_roles.iter().copied().chain(
+            self.questions
+                .iter()
+                .flat_map(|question| question.choices.iter().map(|choice| choice.role)),
+        )
+    }
+
+    /// Describe the flow for the `onboarding` command.
+    pub fn describe(&self) -> String {
+        let mut lines = vec![format!(
+            "**Enabled:** {}",
+            if self.enabled { "yes" } else { "no" }
+        )];
+        lines.push(format!(
+            "**Rules:** {}",
+            if self.rules.is_empty() {
+                "none"
+            } else {
+                &self.rules
+            }
+        ));
+        for (i, question) in self.questions.iter().enumerate() {
+            let choices: Vec<String> = question
+                .choices
+                .iter()
+                .map(|choice| format!("{} <@&{}>", choice.label, choice.role))
+                .collect();
+            lines.push(format!(
+                "**Question {}{}:** {} ({})",
+                i + 1,
+                if question.multiple { ", pick any" } else { "" },
+                question.prompt,
+                choices.join(", ")
+            ));
+        }
+        let access: Vec<String> = self
+            .access_roles
+            .iter()
+            .map(|role| format!("<@&{}>", role))
+            .collect();
+        lines.push(format!(
+            "**Access roles:** {}",
+            if access.is_empty() {
+                "none".to_string()
+            } else {
+                access.join(", ")
+            }
+        ));
+        lines.push(format!(
+            "**Fallback channel:** {}",
+            self.channel
+                .map_or("none".to_string(), |channel| format!("<#{}>", channel))
+        ));
+        if self.min_account_age_days > 0 {
+            lines.push(format!(
+                "**Minimum account age:** {} days",
+                self.min_account_age_days
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// A question whose answers give roles.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Question {
+    pub prompt: String,
+    pub choices: Vec<Choice>,
+    /// Whether more than one choice can be picked.
+    #[serde(default)]
+    pub multiple: bool,
+}
+
+impl Question {
+    /// The roles to add and remove for the picked choices, given as indexes.
+    ///
+    /// Roles of choices that weren't picked are removed, so answering again replaces
+    /// the earlier answer.
+    pub fn roles_for(&self, picked: &[usize]) -> (Vec<RoleId>, Vec<RoleId>) {
+        let (add, remove): (Vec<_>, Vec<_>) = self
+            .choices
+            .iter()
+            .enumerate()
+            .partition(|(i, _)| picked.contains(i));
+        let roles = |choices: Vec<(usize, &Choice)>| {
+            choices
+                .into_iter()
+                .map(|(_, choice)| RoleId(choice.role))
+                .collect()
+        };
+        (roles(add), roles(remove))
+    }
+}
+
+/// An answer to a question and the role it gives.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Choice {
+    pub label: String,
+    pub role: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answering_replaces_the_earlier_roles() {
+        let question = Question {
+            prompt: "Pronouns?".to_string(),
+            choices: vec![
+                Choice {
+                    label: "she/her".to_string(),
+                    role: 1,
+                },
+                Choice {
+                    label: "he/him".to_string(),
+                    role: 2,
+                },
+                Choice {
+                    label: "they/them".to_string(),
+                    role: 3,
+                },
+            ],
+            multiple: true,
+        };
+
+        let (add, remove) = question.roles_for(&[0, 2]);
+        assert_eq!(add, [RoleId(1), RoleId(3)]);
+        assert_eq!(remove, [RoleId(2)]);
+
+        let (add, remove) = question.roles_for(&[]);
+        assert!(add.is_empty());
+        assert_eq!(remove.len(), 3);
+    }
+}
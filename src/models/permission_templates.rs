@@ -0,0 +1,201 @@
+//! Named sets of channel permission overwrites that guilds apply with `permsync`.
+//!
+//! A template is saved from an existing channel and stored in the guild
+//! configuration. Applying one, like syncing a channel to its category or copying
+//! another channel, replaces the channel's overwrites, so [`diff`] describes what
+//! would change before anything is applied.
+
+use serde::{Deserialize, Serialize};
+use serenity::model::channel::{PermissionOverwrite, PermissionOverwriteType};
+use serenity::model::id::{RoleId, UserId};
+use serenity::model::permissions::Permissions;
+
+/// Most templates a guild can have.
+pub const MAX_TEMPLATES: usize = 25;
+
+/// Longest template name, in characters.
+const MAX_NAME_LENGTH: usize = 32;
+
+/// Who an overwrite applies to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverwriteTarget {
+    /// A role, or `@everyone` when the ID is the guild's.
+    Role(u64),
+    /// A single member.
+    Member(u64),
+}
+
+impl OverwriteTarget {
+    /// Mention the role or member, given the `@everyone` role's ID.
+    pub fn mention(self, everyone: u64) -> String {
+        match self {
+            Self::Role(id) if id == everyone => "@everyone".to_string(),
+            Self::Role(id) => format!("<@&{}>", id),
+            Self::Member(id) => format!("<@{}>", id),
+        }
+    }
+}
+
+/// One overwrite, stored as permission bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateOverwrite {
+    pub target: OverwriteTarget,
+    #[serde(default)]
+    pub allow: u64,
+    #[serde(default)]
+    pub deny: u64,
+}
+
+impl TemplateOverwrite {
+    /// The overwrite to send to Discord.
+    pub fn to_overwrite(self) -> PermissionOverwrite {
+        PermissionOverwrite {
+            allow: Permissions::from_bits_truncate(self.allow),
+            deny: Permissions::from_bits_truncate(self.deny),
+            kind: match self.target {
+                OverwriteTarget::Role(id) => PermissionOverwriteType::Role(RoleId(id)),
+                OverwriteTarget::Member(id) => PermissionOverwriteType::Member(UserId(id)),
+            },
+        }
+    }
+}
+
+/// A named set of overwrites.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PermissionTemplate {
+    #[serde(default)]
+    pub overwrites: Vec<TemplateOverwrite>,
+}
+
+/// Convert a channel's overwrites, sorted by who they apply to.
+pub fn from_overwrites(overwrites: &[PermissionOverwrite]) -> Vec<TemplateOverwrite> {
+    let mut converted: Vec<TemplateOverwrite> = overwrites
+        .iter()
+        .filter_map(|overwrite| {
+            let target = match overwrite.kind {
+                PermissionOverwriteType::Role(role_id) => OverwriteTarget::Role(role_id.0),
+                PermissionOverwriteType::Member(user_id) => OverwriteTarget::Member(user_id.0),
+                _ => return None,
+            };
+            Some(TemplateOverwrite {
+                target,
+                allow: overwrite.allow.bits(),
+                deny: overwrite.deny.bits(),
+            })
+        })
+        .collect();
+    converted.sort_by_key(|overwrite| overwrite.target);
+    converted
+}
+
+/// Check that a template name is a single short word.
+pub fn check_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return Err(format!(
+            "Template names are 1 to {} characters long.",
+            MAX_NAME_LENGTH
+        ));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err("Template names can only use letters, numbers, `-` and `_`.".to_string());
+    }
+    Ok(())
+}
+
+/// Describe an overwrite's permissions, such as `+Send Messages −Attach Files`.
+fn describe_bits(allow: u64, deny: u64) -> String {
+    let mut parts = Vec::new();
+    if allow != 0 {
+        parts.push(format!("+{}", Permissions::from_bits_truncate(allow)));
+    }
+    if deny != 0 {
+        parts.push(format!("−{}", Permissions::from_bits_truncate(deny)));
+    }
+    if parts.is_empty() {
+        "no changes from the defaults".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+/// Describe how replacing `current` with `target` changes a channel, one line
+/// per overwrite added, changed or removed.
+pub fn diff(
+    current: &[TemplateOverwrite],
+    target: &[TemplateOverwrite],
+    everyone: u64,
+) -> Vec<String> {
+    let mut lines = Vec::new();
+    for overwrite in target {
+        let mention = overwrite.target.mention(everyone);
+        match current.iter().find(|old| old.target == overwrite.target) {
+            Some(old) if old == overwrite => {}
+            Some(old) => lines.push(format!(
+                "~ {}: {} → {}",
+                mention,
+                describe_bits(old.allow, old.deny),
+                describe_bits(overwrite.allow, overwrite.deny)
+            )),
+            None => lines.push(format!(
+                "+ {}: {}",
+                mention,
+                describe_bits(overwrite.allow, overwrite.deny)
+            )),
+        }
+    }
+    for old in current {
+        if !target
+            .iter()
+            .any(|overwrite| overwrite.target == old.target)
+        {
+            lines.push(format!("- {}", old.target.mention(everyone)));
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffs_overwrites() {
+        let send = Permissions::SEND_MESSAGES.bits();
+        let view = Permissions::VIEW_CHANNEL.bits();
+        let overwrite = |target, allow, deny| TemplateOverwrite {
+            target,
+            allow,
+            deny,
+        };
+        let current = [
+            overwrite(OverwriteTarget::Role(1), 0, send),
+            overwrite(OverwriteTarget::Role(2), send, 0),
+            overwrite(OverwriteTarget::Member(3), view, 0),
+        ];
+        let target = [
+            overwrite(OverwriteTarget::Role(1), 0, send | view),
+            overwrite(OverwriteTarget::Role(2), send, 0),
+            overwrite(OverwriteTarget::Role(4), view, 0),
+        ];
+
+        assert_eq!(
+            diff(&current, &target, 1),
+            [
+                "~ @everyone: −Send Messages → −Send Messages and View Channel",
+                "+ <@&4>: +View Channel",
+                "- <@3>",
+            ]
+        );
+        assert!(diff(&target, &target, 1).is_empty());
+        assert_eq!(
+            from_overwrites(&[target[2].to_overwrite(), target[0].to_overwrite()]),
+            [target[0], target[2]]
+        );
+        assert!(check_name("staff-only").is_ok());
+        assert!(check_name("two words").is_err());
+    }
+}
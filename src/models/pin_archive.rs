@@ -0,0 +1,79 @@
+//! Pin archive data: the pins last seen in each channel and what was archived.
+
+use serde::{Deserialize, Serialize};
+use serenity::model::id::{ChannelId, MessageId};
+use serenity::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::storage::JsonStore;
+
+/// Pins tracked for archiving.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PinArchiveData {
+    /// Pinned message IDs last seen, by channel ID.
+    #[serde(default)]
+    pub pinned: HashMap<u64, HashSet<u64>>,
+    /// Messages already posted to an archive channel.
+    #[serde(default)]
+    pub archived: HashSet<u64>,
+}
+
+impl PinArchiveData {
+    /// Record a channel's current pins and return the messages that were unpinned
+    /// since they were last seen and haven't been archived yet.
+    ///
+    /// Nothing is returned the first time a channel is seen, since its earlier pins
+    /// are unknown.
+    pub fn sync(&mut self, channel_id: ChannelId, pins: &[MessageId]) -> Vec<MessageId> {
+        let current: HashSet<u64> = pins.iter().map(|id| id.0).collect();
+        let previous = self.pinned.insert(channel_id.0, current.clone());
+
+        let mut unpinned: Vec<MessageId> = previous
+            .unwrap_or_default()
+            .difference(&current)
+            .filter(|id| !self.archived.contains(id))
+            .map(|&id| MessageId(id))
+            .collect();
+        unpinned.sort();
+        unpinned
+    }
+
+    /// Mark a message as archived. Returns `false` if it already was.
+    pub fn mark_archived(&mut self, message_id: MessageId) -> bool {
+        self.archived.insert(message_id.0)
+    }
+
+    /// Whether a message was already archived.
+    pub fn is_archived(&self, message_id: MessageId) -> bool {
+        self.archived.contains(&message_id.0)
+    }
+}
+
+/// TypeMap key for the pin archive store.
+pub struct PinArchiveKey;
+
+impl TypeMapKey for PinArchiveKey {
+    type Value = Arc<JsonStore<PinArchiveData>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_unpinned_messages_once() {
+        let mut data = PinArchiveData::default();
+        let channel = ChannelId(1);
+
+        assert!(data
+            .sync(channel, &[MessageId(10), MessageId(11)])
+            .is_empty());
+        assert_eq!(data.sync(channel, &[MessageId(11)]), [MessageId(10)]);
+        assert!(data.sync(channel, &[MessageId(11)]).is_empty());
+
+        // Archived messages aren't reported again when they are unpinned
+        data.mark_archived(MessageId(11));
+        assert!(data.sync(channel, &[]).is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+//! Pin voting, where enough reactions from members pin a message.
+//!
+//! Each message can only be pinned by vote once. If staff unpin it, voting
+//! can't pin it again, so members and moderators don't end up fighting over it.
+
+use serde::{Deserialize, Serialize};
+use serenity::model::channel::ReactionType;
+use serenity::model::id::{ChannelId, MessageId};
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::storage::JsonStore;
+use crate::utils::helpers::unix_timestamp;
+
+/// The emoji members vote with unless another is set.
+pub const DEFAULT_PIN_EMOJI: &str = "📌";
+
+/// The most votes a message can need, since only 100 reactors are fetched.
+pub const MAX_PIN_THRESHOLD: u32 = 100;
+
+/// A guild's pin voting settings. Changed with `pinvote`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PinVoteSettings {
+    /// The emoji to vote with, such as `📌` or `<:pin:123>`. Defaults to
+    /// [`DEFAULT_PIN_EMOJI`].
+    #[serde(default)]
+    pub emoji: Option<String>,
+    /// Votes needed in channels without their own threshold. 0 turns voting off
+    /// there.
+    #[serde(default)]
+    pub threshold: u32,
+    /// Votes needed in specific channels, by channel ID. 0 turns voting off in
+    /// the channel.
+    #[serde(default)]
+    pub channels: HashMap<u64, u32>,
+}
+
+impl PinVoteSettings {
+    /// The emoji to vote with.
+    pub fn emoji(&self) -> &str {
+        self.emoji.as_deref().unwrap_or(DEFAULT_PIN_EMOJI)
+    }
+
+    /// Whether a reaction is a vote.
+    pub fn is_vote(&self, reaction: &ReactionType) -> bool {
+        match reaction {
+            ReactionType::Unicode(emoji) => emoji == self.emoji(),
+            ReactionType::Custom { id, .. } => self.emoji().ends_with(&format!(":{}>", id)),
+            _ => false,
+        }
+    }
+
+    /// The votes needed to pin a message in a channel, or `None` if voting is off.
+    pub fn threshold(&self, channel_id: ChannelId) -> Option<u32> {
+        let threshold = self
+            .channels
+            .get(&channel_id.0)
+            .copied()
+            .unwrap_or(self.threshold);
+        (threshold > 0).then_some(threshold)
+    }
+
+    /// Describe the settings, one per line.
+    pub fn describe(&self) -> String {
+        let votes = |threshold: u32| match threshold {
+            0 => "off".to_string(),
+            1 => "1 vote".to_string(),
+            n => format!("{} votes", n),
+        };
+        let mut lines = vec![
+            format!("Emoji: {}", self.emoji()),
+            format!("Every channel: {}", votes(self.threshold)),
+        ];
+        let mut channels: Vec<_> = self.channels.iter().collect();
+        channels.sort_by_key(|(channel_id, _)| **channel_id);
+        lines.extend(
+            channels
+                .into_iter()
+                .map(|(channel_id, threshold)| format!("<#{}>: {}", channel_id, votes(*threshold))),
+        );
+        lines.join("\n")
+    }
+}
+
+/// A message pinned by vote.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VotedPin {
+    pub channel_id: u64,
+    /// When the message was pinned (seconds since the Unix epoch).
+    pub pinned_at: u64,
+}
+
+/// Messages pinned by vote, by message ID.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PinVoteData {
+    #[serde(default)]
+    pub pinned: HashMap<u64, VotedPin>,
+}
+
+impl PinVoteData {
+    /// Claim a message for pinning. Returns `false` if it was pinned by vote before.
+    pub fn claim(&mut self, channel_id: ChannelId, message_id: MessageId) -> bool {
+        if self.pinned.contains_key(&message_id.0) {
+            return false;
+        }
+        self.pinned.insert(
+            message_id.0,
+            VotedPin {
+                channel_id: channel_id.0,
+                pinned_at: unix_timestamp(),
+            },
+        );
+        true
+    }
+
+    /// Release a claim after pinning failed, so a later vote can try again.
+    pub fn release(&mut self, message_id: MessageId) {
+        self.pinned.remove(&message_id.0);
+    }
+}
+
+/// TypeMap key for the pin vote store.
+pub struct PinVoteKey;
+
+impl TypeMapKey for PinVoteKey {
+    type Value = Arc<JsonStore<PinVoteData>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serenity::model::id::EmojiId;
+
+    #[test]
+    fn thresholds_and_votes() {
+        let mut settings = PinVoteSettings {
+            threshold: 5,
+            ..PinVoteSettings::default()
+        };
+        settings.channels.insert(1, 0);
+        settings.channels.insert(2, 3);
+        assert_eq!(settings.threshold(ChannelId(1)), None);
+        assert_eq!(settings.threshold(ChannelId(2)), Some(3));
+        assert_eq!(settings.threshold(ChannelId(3)), Some(5));
+
+        assert!(settings.is_vote(&ReactionType::Unicode("📌".to_string())));
+        settings.emoji = Some("<:pin:42>".to_string());
+        assert!(settings.is_vote(&ReactionType::Custom {
+            animated: false,
+            id: EmojiId(42),
+            name: Some("pin".to_string()),
+        }));
+        assert!(!settings.is_vote(&ReactionType::Unicode("📌".to_string())));
+    }
+
+    #[test]
+    fn messages_are_only_pinned_once() {
+        let mut data = PinVoteData::default();
+        assert!(data.claim(ChannelId(1), MessageId(10)));
+        assert!(!data.claim(ChannelId(1), MessageId(10)));
+        data.release(MessageId(10));
+        assert!(data.claim(ChannelId(1), MessageId(10)));
+    }
+}
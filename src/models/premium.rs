@@ -0,0 +1,316 @@
+//! Premium tiers granted to users and guilds.
+
+use serde::{Deserialize, Deserializer, Serialize};
+use serenity::model::id::{GuildId, UserId};
+use serenity::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::storage::JsonStore;
+
+/// A premium tier. Higher tiers get higher limits.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Tier {
+    #[default]
+    Free,
+    Plus,
+    Pro,
+}
+
+impl Tier {
+    /// Every tier, from lowest to highest.
+    pub const ALL: [Tier; 3] = [Tier::Free, Tier::Plus, Tier::Pro];
+
+    /// Whether this is a paid tier.
+    pub fn is_premium(self) -> bool {
+        self > Tier::Free
+    }
+}
+
+impl fmt::Display for Tier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Tier::Free => "free",
+            Tier::Plus => "plus",
+            Tier::Pro => "pro",
+        })
+    }
+}
+
+impl FromStr for Tier {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Tier::ALL
+            .into_iter()
+            .find(|tier| tier.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("Unknown tier `{}`. Tiers are free, plus and pro.", s))
+    }
+}
+
+/// Where a grant came from.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum GrantSource {
+    /// Set by a bot owner with `premium`.
+    Owner { by: u64 },
+    /// Synced from a Discord SKU entitlement.
+    Entitlement { sku_id: u64 },
+    /// A trial for voting on a bot list.
+    Vote,
+}
+
+/// A tier granted to a user or guild.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Grant {
+    pub tier: Tier,
+    pub source: GrantSource,
+    /// When the grant runs out (seconds since the Unix epoch), or `None` if it doesn't.
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+impl Grant {
+    /// Whether the grant still applies at `now`.
+    pub fn is_active(&self, now: u64) -> bool {
+        self.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+}
+
+/// A Discord entitlement, as returned by the entitlements endpoint.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Entitlement {
+    #[serde(deserialize_with = "deserialize_snowflake")]
+    pub id: u64,
+    #[serde(deserialize_with = "deserialize_snowflake")]
+    pub sku_id: u64,
+    #[serde(default)]
+    pub user_id: Option<UserId>,
+    #[serde(default)]
+    pub guild_id: Option<GuildId>,
+    #[serde(default)]
+    pub deleted: bool,
+    /// When the entitlement ends, as an ISO 8601 timestamp.
+    #[serde(default)]
+    pub ends_at: Option<String>,
+}
+
+/// Premium grants by user and guild ID.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PremiumData {
+    #[serde(default)]
+    pub users: HashMap<u64, Grant>,
+    #[serde(default)]
+    pub guilds: HashMap<u64, Grant>,
+}
+
+impl PremiumData {
+    /// A user's tier at `now`.
+    pub fn user_tier(&self, user_id: UserId, now: u64) -> Tier {
+        active_tier(self.users.get(&user_id.0), now)
+    }
+
+    /// A guild's tier at `now`.
+    pub fn guild_tier(&self, guild_id: GuildId, now: u64) -> Tier {
+        active_tier(self.guilds.get(&guild_id.0), now)
+    }
+
+    /// Give a user a tier until `until` for voting, unless they already have it or
+    /// better some other way. A previous vote trial is extended. Returns whether the
+    /// grant was made.
+    pub fn grant_trial(&mut self, user_id: UserId, tier: Tier, until: u64, now: u64) -> bool {
+        let current = self
+            .users
+            .get(&user_id.0)
+            .filter(|grant| grant.is_active(now));
+        if current.is_some_and(|grant| grant.source != GrantSource::Vote && grant.tier >= tier) {
+            return false;
+        }
+        self.users.insert(
+            user_id.0,
+            Grant {
+                tier,
+                source: GrantSource::Vote,
+                expires_at: Some(until),
+            },
+        );
+        true
+    }
+
+    /// Replace the entitlement grants with the given entitlements.
+    ///
+    /// Entitlements for SKUs without a tier are ignored, and owner and vote grants
+    /// are only replaced by a higher entitlement tier. Returns how many grants changed.
+    pub fn apply_entitlements(
+        &mut self,
+        entitlements: &[Entitlement],
+        skus: &HashMap<u64, Tier>,
+        now: u64,
+    ) -> usize {
+        let mut users: HashMap<u64, Grant> = HashMap::new();
+        let mut guilds: HashMap<u64, Grant> = HashMap::new();
+        for entitlement in entitlements.iter().filter(|e| !e.deleted) {
+            let tier = match skus.get(&entitlement.sku_id) {
+                Some(tier) => *tier,
+                None => continue,
+            };
+            let grant = Grant {
+                tier,
+                source: GrantSource::Entitlement {
+                    sku_id: entitlement.sku_id,
+                },
+                expires_at: entitlement.ends_at.as_deref().and_then(parse_timestamp),
+            };
+            if !grant.is_active(now) {
+                continue;
+            }
+            let target = match (entitlement.guild_id, entitlement.user_id) {
+                (Some(guild_id), _) => guilds.entry(guild_id.0),
+                (None, Some(user_id)) => users.entry(user_id.0),
+                (None, None) => continue,
+            };
+            target
+                .and_modify(|existing| {
+                    if grant.tier > existing.tier {
+                        *existing = grant.clone();
+                    }
+                })
+                .or_insert(grant);
+        }
+
+        merge_entitlements(&mut self.users, users, now)
+            + merge_entitlements(&mut self.guilds, guilds, now)
+    }
+}
+
+/// The tier of a grant if it is still active.
+fn active_tier(grant: Option<&Grant>, now: u64) -> Tier {
+    grant
+        .filter(|grant| grant.is_active(now))
+        .map_or(Tier::Free, |grant| grant.tier)
+}
+
+/// Swap the entitlement grants in `current` for `synced`, keeping other grants unless
+/// an entitlement gives a higher tier. Returns how many grants changed.
+fn merge_entitlements(
+    current: &mut HashMap<u64, Grant>,
+    synced: HashMap<u64, Grant>,
+    now: u64,
+) -> usize {
+    let before = current.clone();
+
+    // Drop the previous entitlement grants and lapsed grants
+    current.retain(|_, grant| {
+        !matches!(grant.source, GrantSource::Entitlement { .. }) && grant.is_active(now)
+    });
+    for (id, grant) in synced {
+        match current.get(&id) {
+            Some(other) if other.tier >= grant.tier => {}
+            _ => {
+                current.insert(id, grant);
+            }
+        }
+    }
+
+    let ids: HashSet<_> = before.keys().chain(current.keys()).collect();
+    ids.into_iter()
+        .filter(|id| before.get(id) != current.get(id))
+        .count()
+}
+
+/// Deserialize a snowflake sent as a string.
+fn deserialize_snowflake<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(serde::de::Error::custom)
+}
+
+/// Parse an ISO 8601 timestamp into seconds since the Unix epoch.
+fn parse_timestamp(timestamp: &str) -> Option<u64> {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .and_then(|time| u64::try_from(time.timestamp()).ok())
+}
+
+/// TypeMap key for the premium store.
+pub struct PremiumKey;
+
+impl TypeMapKey for PremiumKey {
+    type Value = Arc<JsonStore<PremiumData>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entitlement(sku_id: u64, user_id: Option<u64>, guild_id: Option<u64>) -> Entitlement {
+        Entitlement {
+            id: sku_id,
+            sku_id,
+            user_id: user_id.map(UserId),
+            guild_id: guild_id.map(GuildId),
+            deleted: false,
+            ends_at: None,
+        }
+    }
+
+    #[test]
+    fn grants_expire() {
+        let mut data = PremiumData::default();
+        data.users.insert(
+            1,
+            Grant {
+                tier: Tier::Pro,
+                source: GrantSource::Owner { by: 9 },
+                expires_at: Some(100),
+            },
+        );
+        assert_eq!(data.user_tier(UserId(1), 99), Tier::Pro);
+        assert_eq!(data.user_tier(UserId(1), 100), Tier::Free);
+        assert_eq!(data.guild_tier(GuildId(1), 0), Tier::Free);
+        assert_eq!("PLUS".parse::<Tier>(), Ok(Tier::Plus));
+
+        // Vote trials don't replace a better grant but do extend each other
+        assert!(!data.grant_trial(UserId(1), Tier::Plus, 200, 50));
+        assert!(data.grant_trial(UserId(1), Tier::Plus, 200, 150));
+        assert!(data.grant_trial(UserId(1), Tier::Plus, 300, 160));
+        assert_eq!(data.user_tier(UserId(1), 250), Tier::Plus);
+    }
+
+    #[test]
+    fn syncs_entitlements_without_dropping_owner_grants() {
+        let skus = HashMap::from([(10, Tier::Plus), (20, Tier::Pro)]);
+        let mut data = PremiumData::default();
+        data.users.insert(
+            1,
+            Grant {
+                tier: Tier::Pro,
+                source: GrantSource::Owner { by: 9 },
+                expires_at: None,
+            },
+        );
+
+        let entitlements = [
+            entitlement(10, Some(1), None),
+            entitlement(10, Some(2), None),
+            entitlement(20, Some(3), Some(5)),
+            entitlement(99, Some(4), None),
+        ];
+        assert_eq!(data.apply_entitlements(&entitlements, &skus, 0), 2);
+        assert_eq!(data.user_tier(UserId(1), 0), Tier::Pro);
+        assert_eq!(data.user_tier(UserId(2), 0), Tier::Plus);
+        assert_eq!(data.guild_tier(GuildId(5), 0), Tier::Pro);
+        assert_eq!(data.user_tier(UserId(4), 0), Tier::Free);
+
+        // Syncing the same entitlements again changes nothing, and lapsed ones go
+        assert_eq!(data.apply_entitlements(&entitlements, &skus, 0), 0);
+        assert_eq!(data.apply_entitlements(&entitlements[..1], &skus, 0), 2);
+        assert_eq!(data.user_tier(UserId(2), 0), Tier::Free);
+        assert_eq!(data.user_tier(UserId(1), 0), Tier::Pro);
+    }
+}
@@ -0,0 +1,144 @@
+//! Quotes saved by members of each guild.
+
+use serde::{Deserialize, Serialize};
+use serenity::model::id::GuildId;
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::storage::JsonStore;
+
+/// A saved quote.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Quote {
+    /// Quote ID, unique within the guild.
+    pub id: u64,
+    pub content: String,
+    /// Who said it, if known.
+    #[serde(default)]
+    pub author_id: Option<u64>,
+    /// The author's name when the quote was saved.
+    pub author_name: String,
+    /// Who saved the quote.
+    pub added_by: u64,
+    pub added_at: u64,
+    /// The message the quote was saved from, if any.
+    #[serde(default)]
+    pub channel_id: Option<u64>,
+    #[serde(default)]
+    pub message_id: Option<u64>,
+}
+
+/// A guild's quotes.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GuildQuotes {
+    /// The last ID handed out to a quote.
+    #[serde(default)]
+    pub last_id: u64,
+    #[serde(default)]
+    pub quotes: Vec<Quote>,
+}
+
+impl GuildQuotes {
+    /// Save a quote, giving it the next ID, and return the ID.
+    pub fn add(&mut self, mut quote: Quote) -> u64 {
+        self.last_id += 1;
+        quote.id = self.last_id;
+        self.quotes.push(quote);
+        self.last_id
+    }
+
+    /// Get a quote by ID.
+    pub fn get(&self, id: u64) -> Option<&Quote> {
+        self.quotes.iter().find(|quote| quote.id == id)
+    }
+
+    /// The quote saved from a message, if any.
+    pub fn from_message(&self, message_id: u64) -> Option<&Quote> {
+        self.quotes
+            .iter()
+            .find(|quote| quote.message_id == Some(message_id))
+    }
+
+    /// Remove a quote, returning it if it existed.
+    pub fn remove(&mut self, id: u64) -> Option<Quote> {
+        let index = self.quotes.iter().position(|quote| quote.id == id)?;
+        Some(self.quotes.remove(index))
+    }
+
+    /// Quotes whose text or author name contains a term, ignoring case, newest first.
+    pub fn search(&self, term: &str) -> Vec<&Quote> {
+        let term = term.to_lowercase();
+        self.quotes
+            .iter()
+            .rev()
+            .filter(|quote| {
+                quote.content.to_lowercase().contains(&term)
+                    || quote.author_name.to_lowercase().contains(&term)
+            })
+            .collect()
+    }
+}
+
+/// Quotes for all guilds.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct QuoteData {
+    /// Quotes by guild ID.
+    #[serde(default)]
+    pub guilds: HashMap<u64, GuildQuotes>,
+}
+
+impl QuoteData {
+    /// Get a guild's quotes, if it has any.
+    pub fn guild(&self, guild_id: GuildId) -> Option<&GuildQuotes> {
+        self.guilds.get(&guild_id.0)
+    }
+
+    /// Get a mutable reference to a guild's quotes, creating them if needed.
+    pub fn guild_mut(&mut self, guild_id: GuildId) -> &mut GuildQuotes {
+        self.guilds.entry(guild_id.0).or_default()
+    }
+}
+
+/// TypeMap key for the quote store.
+pub struct QuoteKey;
+
+impl TypeMapKey for QuoteKey {
+    type Value = Arc<JsonStore<QuoteData>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(content: &str, author_name: &str, message_id: Option<u64>) -> Quote {
+        Quote {
+            id: 0,
+            content: content.to_string(),
+            author_id: None,
+            author_name: author_name.to_string(),
+            added_by: 1,
+            added_at: 0,
+            channel_id: None,
+            message_id,
+        }
+    }
+
+    #[test]
+    fn adds_searches_and_removes_quotes() {
+        let mut quotes = GuildQuotes::default();
+        assert_eq!(quotes.add(quote("Ship it", "Ana", Some(10))), 1);
+        assert_eq!(quotes.add(quote("It works on my machine", "Bo", None)), 2);
+        assert_eq!(quotes.add(quote("Never ship on Friday", "Cy", None)), 3);
+
+        let ids = |found: Vec<&Quote>| found.iter().map(|q| q.id).collect::<Vec<_>>();
+        assert_eq!(ids(quotes.search("SHIP")), [3, 1]);
+        assert_eq!(ids(quotes.search("bo")), [2]);
+        assert_eq!(quotes.from_message(10).map(|q| q.id), Some(1));
+
+        assert!(quotes.remove(1).is_some());
+        assert!(quotes.remove(1).is_none());
+        // IDs aren't reused after a removal
+        assert_eq!(quotes.add(quote("Again", "Ana", None)), 4);
+    }
+}
@@ -0,0 +1,145 @@
+//! Roles and nicknames saved when members leave, for restoring when they rejoin.
+
+use serde::{Deserialize, Serialize};
+use serenity::model::id::{GuildId, RoleId, UserId};
+use serenity::model::permissions::Permissions;
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::models::guild_config::GuildConfig;
+use crate::storage::JsonStore;
+
+/// Permissions that are never handed back automatically, so a role granted by
+/// mistake or since made more powerful isn't restored to someone who left.
+pub const DANGEROUS_PERMISSIONS: Permissions = Permissions::ADMINISTRATOR
+    .union(Permissions::MANAGE_GUILD)
+    .union(Permissions::MANAGE_ROLES)
+    .union(Permissions::MANAGE_CHANNELS)
+    .union(Permissions::MANAGE_WEBHOOKS)
+    .union(Permissions::MANAGE_MESSAGES)
+    .union(Permissions::BAN_MEMBERS)
+    .union(Permissions::KICK_MEMBERS)
+    .union(Permissions::MODERATE_MEMBERS)
+    .union(Permissions::MENTION_EVERYONE);
+
+/// What a member had when they left.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedMember {
+    /// Role IDs the member had.
+    pub roles: Vec<u64>,
+    pub nickname: Option<String>,
+    /// When the member left (seconds since the Unix epoch).
+    pub left_at: u64,
+}
+
+impl SavedMember {
+    /// Whether there is nothing worth restoring.
+    pub fn is_empty(&self) -> bool {
+        self.roles.is_empty() && self.nickname.is_none()
+    }
+}
+
+/// Whether a saved role should be given back to a rejoining member.
+///
+/// Persistent roles always come back. Other roles only come back when restoring is
+/// on, the role is on the allowlist (if there is one) and it has no dangerous
+/// permissions. Callers also check that the bot can assign the role.
+pub fn should_restore(config: &GuildConfig, role_id: RoleId, permissions: Permissions) -> bool {
+    if config.persistent_roles.contains(&role_id.0) {
+        return true;
+    }
+
+    config.restore_roles
+        && (config.restore_role_allowlist.is_empty()
+            || config.restore_role_allowlist.contains(&role_id.0))
+        && !permissions.intersects(DANGEROUS_PERMISSIONS)
+}
+
+/// Saved members for all guilds.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RolePersistenceData {
+    /// Saved members by guild ID, then user ID.
+    #[serde(default)]
+    pub guilds: HashMap<u64, HashMap<u64, SavedMember>>,
+}
+
+impl RolePersistenceData {
+    /// Save what a member had, replacing anything saved before.
+    pub fn save(&mut self, guild_id: GuildId, user_id: UserId, member: SavedMember) {
+        let guild = self.guilds.entry(guild_id.0).or_default();
+        if member.is_empty() {
+            guild.remove(&user_id.0);
+        } else {
+            guild.insert(user_id.0, member);
+        }
+        if guild.is_empty() {
+            self.guilds.remove(&guild_id.0);
+        }
+    }
+
+    /// Remove and return what a member had when they left.
+    pub fn take(&mut self, guild_id: GuildId, user_id: UserId) -> Option<SavedMember> {
+        let guild = self.guilds.get_mut(&guild_id.0)?;
+        let member = guild.remove(&user_id.0);
+        if guild.is_empty() {
+            self.guilds.remove(&guild_id.0);
+        }
+        member
+    }
+}
+
+/// TypeMap key for the role persistence store.
+pub struct RolePersistenceKey;
+
+impl TypeMapKey for RolePersistenceKey {
+    type Value = Arc<JsonStore<RolePersistenceData>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restores_only_safe_allowed_roles() {
+        let mut config = GuildConfig {
+            persistent_roles: vec![1],
+            ..GuildConfig::default()
+        };
+
+        // Persistent roles come back even with restoring off
+        assert!(should_restore(&config, RoleId(1), Permissions::empty()));
+        assert!(!should_restore(&config, RoleId(2), Permissions::empty()));
+
+        config.restore_roles = true;
+        assert!(should_restore(
+            &config,
+            RoleId(2),
+            Permissions::SEND_MESSAGES
+        ));
+        assert!(!should_restore(
+            &config,
+            RoleId(2),
+            Permissions::BAN_MEMBERS
+        ));
+
+        config.restore_role_allowlist = vec![3];
+        assert!(!should_restore(&config, RoleId(2), Permissions::empty()));
+        assert!(should_restore(&config, RoleId(3), Permissions::empty()));
+    }
+
+    #[test]
+    fn saves_and_takes_members() {
+        let mut data = RolePersistenceData::default();
+        let member = SavedMember {
+            roles: vec![5],
+            nickname: None,
+            left_at: 0,
+        };
+        data.save(GuildId(1), UserId(2), member);
+
+        assert_eq!(data.take(GuildId(1), UserId(2)).unwrap().roles, [5]);
+        assert!(data.take(GuildId(1), UserId(2)).is_none());
+        assert!(data.guilds.is_empty());
+    }
+}
@@ -0,0 +1,245 @@
+//! Connection health of the gateway shards.
+//!
+//! Serenity reconnects on its own, so a flaky connection is easy to miss. This
+//! tracks each shard's stage changes and resumes, so reconnect storms and long
+//! outages can be logged, reported to owners, and shown by the `shards` command.
+
+use serenity::gateway::ConnectionStage;
+use serenity::prelude::*;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// What is known about a single shard's connection.
+#[derive(Clone, Debug)]
+pub struct ShardStatus {
+    /// The shard's current connection stage.
+    pub stage: ConnectionStage,
+    /// When the shard entered its current stage.
+    pub since: Instant,
+    /// When the shard lost its connection, while it is down.
+    pub down_since: Option<Instant>,
+    /// How many times the shard lost its connection.
+    pub disconnects: u32,
+    /// How many times the shard resumed its session instead of starting a new one.
+    pub resumes: u32,
+    /// Whether owners were told about the current outage.
+    pub alerted: bool,
+    /// Recent disconnect times, for detecting reconnect storms.
+    recent_disconnects: VecDeque<Instant>,
+}
+
+impl ShardStatus {
+    fn new(stage: ConnectionStage, now: Instant) -> Self {
+        Self {
+            stage,
+            since: now,
+            down_since: None,
+            disconnects: 0,
+            resumes: 0,
+            alerted: false,
+            recent_disconnects: VecDeque::new(),
+        }
+    }
+
+    /// Whether the shard is connected.
+    pub fn is_connected(&self) -> bool {
+        self.stage == ConnectionStage::Connected
+    }
+
+    /// How long the shard has been down, if it is.
+    pub fn downtime(&self) -> Option<Duration> {
+        self.down_since.map(|since| since.elapsed())
+    }
+}
+
+/// The result of recording a stage change.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StageChange {
+    /// The shard lost its connection.
+    pub disconnected: bool,
+    /// The shard is connected again after an outage owners were told about.
+    pub recovered_after_alert: bool,
+    /// Disconnects within the storm window, when that reaches the storm threshold.
+    pub storm: Option<usize>,
+}
+
+/// Tracks the connection health of all shards.
+pub struct ShardHealth {
+    shards: Mutex<HashMap<u64, ShardStatus>>,
+    /// Disconnects within `storm_window` that count as a reconnect storm.
+    storm_threshold: usize,
+    storm_window: Duration,
+}
+
+impl ShardHealth {
+    /// Create a tracker that reports `storm_threshold` disconnects within
+    /// `storm_window` as a reconnect storm.
+    pub fn new(storm_threshold: usize, storm_window: Duration) -> Self {
+        Self {
+            shards: Mutex::new(HashMap::new()),
+            storm_threshold: storm_threshold.max(1),
+            storm_window,
+        }
+    }
+
+    /// Record a shard moving to a new connection stage.
+    pub fn record_stage(&self, shard_id: u64, stage: ConnectionStage) -> StageChange {
+        self.record_stage_at(shard_id, stage, Instant::now())
+    }
+
+    fn record_stage_at(&self, shard_id: u64, stage: ConnectionStage, now: Instant) -> StageChange {
+        let mut shards = self.shards.lock().unwrap_or_else(|e| e.into_inner());
+        let status = shards
+            .entry(shard_id)
+            .or_insert_with(|| ShardStatus::new(stage, now));
+
+        let mut change = StageChange::default();
+        let was_connected = status.is_connected();
+        status.stage = stage;
+        status.since = now;
+
+        if stage == ConnectionStage::Connected {
+            change.recovered_after_alert = status.alerted;
+            status.down_since = None;
+            status.alerted = false;
+        } else if was_connected {
+            change.disconnected = true;
+            status.disconnects += 1;
+            status.down_since = Some(now);
+
+            status.recent_disconnects.push_back(now);
+            while status
+                .recent_disconnects
+                .front()
+                .is_some_and(|at| now.duration_since(*at) > self.storm_window)
+            {
+                status.recent_disconnects.pop_front();
+            }
+            let recent = status.recent_disconnects.len();
+            if recent >= self.storm_threshold {
+                change.storm = Some(recent);
+            }
+        } else if status.down_since.is_none() {
+            // First stage seen for a shard that is still connecting
+            status.down_since = Some(now);
+        }
+
+        change
+    }
+
+    /// Record a shard resuming its session.
+    pub fn record_resume(&self, shard_id: u64) {
+        let mut shards = self.shards.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        shards
+            .entry(shard_id)
+            .or_insert_with(|| ShardStatus::new(ConnectionStage::Connected, now))
+            .resumes += 1;
+    }
+
+    /// Mark the shard's current outage as reported, if it started at `down_since`.
+    ///
+    /// Returns `false` when the shard has reconnected since, or the outage was
+    /// already reported.
+    pub fn mark_alerted(&self, shard_id: u64, down_since: Instant) -> bool {
+        let mut shards = self.shards.lock().unwrap_or_else(|e| e.into_inner());
+        match shards.get_mut(&shard_id) {
+            Some(status) if status.down_since == Some(down_since) && !status.alerted => {
+                status.alerted = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Get a shard's status.
+    pub fn status(&self, shard_id: u64) -> Option<ShardStatus> {
+        let shards = self.shards.lock().unwrap_or_else(|e| e.into_inner());
+        shards.get(&shard_id).cloned()
+    }
+
+    /// Get the status of every shard seen so far, ordered by shard ID.
+    pub fn statuses(&self) -> Vec<(u64, ShardStatus)> {
+        let shards = self.shards.lock().unwrap_or_else(|e| e.into_inner());
+        let mut statuses: Vec<_> = shards
+            .iter()
+            .map(|(id, status)| (*id, status.clone()))
+            .collect();
+        statuses.sort_by_key(|(id, _)| *id);
+        statuses
+    }
+}
+
+/// Key for storing the shard health tracker in the client data.
+pub struct ShardHealthKey;
+
+impl TypeMapKey for ShardHealthKey {
+    type Value = Arc<ShardHealth>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_disconnects_and_recovery() {
+        let health = ShardHealth::new(5, Duration::from_secs(60));
+        let start = Instant::now();
+
+        health.record_stage_at(0, ConnectionStage::Connected, start);
+        let change = health.record_stage_at(0, ConnectionStage::Resuming, start);
+        assert!(change.disconnected);
+        assert_eq!(health.status(0).unwrap().down_since, Some(start));
+
+        // Moving through further connecting stages is the same outage
+        let change = health.record_stage_at(0, ConnectionStage::Handshake, start);
+        assert!(!change.disconnected);
+
+        assert!(health.mark_alerted(0, start));
+        assert!(!health.mark_alerted(0, start));
+
+        let change = health.record_stage_at(0, ConnectionStage::Connected, start);
+        assert!(change.recovered_after_alert);
+        health.record_resume(0);
+
+        let status = health.status(0).unwrap();
+        assert!(status.is_connected());
+        assert_eq!(status.down_since, None);
+        assert_eq!((status.disconnects, status.resumes), (1, 1));
+    }
+
+    #[test]
+    fn alerts_only_for_the_current_outage() {
+        let health = ShardHealth::new(5, Duration::from_secs(60));
+        let start = Instant::now();
+
+        health.record_stage_at(0, ConnectionStage::Connected, start);
+        health.record_stage_at(0, ConnectionStage::Disconnected, start);
+        health.record_stage_at(0, ConnectionStage::Connected, start);
+
+        assert!(!health.mark_alerted(0, start));
+        assert!(!health.mark_alerted(1, start));
+    }
+
+    #[test]
+    fn detects_reconnect_storms() {
+        let health = ShardHealth::new(3, Duration::from_secs(60));
+        let start = Instant::now();
+        let mut storms = Vec::new();
+
+        for step in 0..4 {
+            let now = start + Duration::from_secs(step * 25);
+            health.record_stage_at(0, ConnectionStage::Connected, now);
+            let change = health.record_stage_at(0, ConnectionStage::Resuming, now);
+            storms.push(change.storm);
+        }
+        assert_eq!(storms, [None, None, Some(3), Some(3)]);
+
+        // Disconnects spread out over time are not a storm
+        let later = start + Duration::from_secs(600);
+        health.record_stage_at(0, ConnectionStage::Connected, later);
+        let change = health.record_stage_at(0, ConnectionStage::Resuming, later);
+        assert_eq!(change.storm, None);
+    }
+}
@@ -0,0 +1,197 @@
+//! Soundboard clips uploaded with `sound add`.
+//!
+//! Clip files are kept under `<data_dir>/sounds/<guild>/`, and what's known about
+//! them is kept in the `sounds` store.
+
+use serde::{Deserialize, Serialize};
+use serenity::model::id::GuildId;
+use serenity::prelude::TypeMapKey;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::storage::JsonStore;
+
+/// Longest clip name.
+const MAX_NAME_LENGTH: usize = 32;
+
+/// A stored clip.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Sound {
+    /// File name within the guild's directory.
+    pub file: String,
+    /// Who uploaded it.
+    pub uploaded_by: u64,
+    /// Length in milliseconds.
+    pub length_ms: u64,
+    /// Size in bytes.
+    pub size: u64,
+}
+
+/// Every guild's clips, by name.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SoundData {
+    #[serde(default)]
+    guilds: HashMap<u64, BTreeMap<String, Sound>>,
+}
+
+impl SoundData {
+    /// A guild's clips, by name.
+    pub fn guild(&self, guild_id: GuildId) -> Option<&BTreeMap<String, Sound>> {
+        self.guilds.get(&guild_id.0)
+    }
+}
+
+/// Check a clip name, returning it normalized.
+pub fn check_name(name: &str) -> Result<String, String> {
+    let name = name.to_lowercase();
+    let valid = !name.is_empty()
+        && name.chars().count() <= MAX_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(name)
+    } else {
+        Err(format!(
+            "Clip names are up to {} letters, numbers, `-` and `_`.",
+            MAX_NAME_LENGTH
+        ))
+    }
+}
+
+/// Why a clip couldn't be added.
+#[derive(Debug)]
+pub enum AddError {
+    /// The guild has as many clips as its tier allows.
+    Full(usize),
+    /// Saving the file or the store failed.
+    Io(io::Error),
+}
+
+impl From<io::Error> for AddError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Clips and where their files are kept.
+pub struct Soundboard {
+    store: JsonStore<SoundData>,
+    dir: PathBuf,
+}
+
+impl Soundboard {
+    /// Create the soundboard with its store and the directory clips are kept in.
+    pub fn new(store: JsonStore<SoundData>, dir: PathBuf) -> Self {
+        Self { store, dir }
+    }
+
+    /// A guild's clips, by name.
+    pub async fn list(&self, guild_id: GuildId) -> BTreeMap<String, Sound> {
+        self.store
+            .read()
+            .await
+            .guild(guild_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// A clip and the path of its file.
+    pub async fn get(&self, guild_id: GuildId, name: &str) -> Option<(Sound, PathBuf)> {
+        let sound = self.store.read().await.guild(guild_id)?.get(name)?.clone();
+        let path = self.dir.join(guild_id.to_string()).join(&sound.file);
+        Some((sound, path))
+    }
+
+    /// Save a clip, replacing one with the same name. `max` is how many clips the
+    /// guild may have.
+    pub async fn add(
+        &self,
+        guild_id: GuildId,
+        name: &str,
+        extension: &str,
+        data: &[u8],
+        sound: Sound,
+        max: usize,
+    ) -> Result<(), AddError> {
+        {
+            let data = self.store.read().await;
+            let clips = data.guild(guild_id);
+            let count = clips.map_or(0, BTreeMap::len);
+            if count >= max && !clips.is_some_and(|clips| clips.contains_key(name)) {
+                return Err(AddError::Full(max));
+            }
+        }
+
+        let dir = self.dir.join(guild_id.to_string());
+        tokio::fs::create_dir_all(&dir).await?;
+        let file = format!("{}.{}", name, extension);
+        tokio::fs::write(dir.join(&file), data).await?;
+
+        let replaced = self
+            .store
+            .update(|data| {
+                let sound = Sound { file, ..sound };
+                data.guilds
+                    .entry(guild_id.0)
+                    .or_default()
+                    .insert(name.to_string(), sound)
+            })
+            .await?;
+        // A clip replaced with one of another format leaves its old file behind
+        if let Some(old) = replaced.filter(|old| old.file != format!("{}.{}", name, extension)) {
+            let _ = tokio::fs::remove_file(dir.join(old.file)).await;
+        }
+        Ok(())
+    }
+
+    /// Delete a clip, returning whether it existed.
+    pub async fn remove(&self, guild_id: GuildId, name: &str) -> io::Result<bool> {
+        let removed = self
+            .store
+            .update(|data| {
+                let clips = data.guilds.get_mut(&guild_id.0)?;
+                let removed = clips.remove(name);
+                if clips.is_empty() {
+                    data.guilds.remove(&guild_id.0);
+                }
+                removed
+            })
+            .await?;
+        match removed {
+            Some(sound) => {
+                let path = self.dir.join(guild_id.to_string()).join(sound.file);
+                if let Err(e) = tokio::fs::remove_file(path).await {
+                    if e.kind() != io::ErrorKind::NotFound {
+                        return Err(e);
+                    }
+                }
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// TypeMap key for the soundboard.
+pub struct SoundsKey;
+
+impl TypeMapKey for SoundsKey {
+    type Value = Arc<Soundboard>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clip_names() {
+        assert_eq!(check_name("Airhorn_2").unwrap(), "airhorn_2");
+        assert!(check_name("").is_err());
+        assert!(check_name("../etc").is_err());
+        assert!(check_name("with space").is_err());
+        assert!(check_name(&"a".repeat(MAX_NAME_LENGTH + 1)).is_err());
+    }
+}
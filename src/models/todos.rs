@@ -0,0 +1,248 @@
+//! Todo lists, kept per user and per channel.
+
+use serde::{Deserialize, Serialize};
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::storage::JsonStore;
+use crate::utils::duration::relative;
+
+/// Most items a list can hold.
+pub const MAX_ITEMS: usize = 50;
+
+/// Whose list an item is on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TodoOwner {
+    /// A member's personal list.
+    User(u64),
+    /// A channel's shared list.
+    Channel(u64),
+}
+
+impl fmt::Display for TodoOwner {
+    /// Written as `user:<id>` or `channel:<id>`, as used in menu IDs.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::User(id) => write!(f, "user:{}", id),
+            Self::Channel(id) => write!(f, "channel:{}", id),
+        }
+    }
+}
+
+impl std::str::FromStr for TodoOwner {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, id) = s.split_once(':').ok_or(())?;
+        let id = id.parse().map_err(|_| ())?;
+        match kind {
+            "user" => Ok(Self::User(id)),
+            "channel" => Ok(Self::Channel(id)),
+            _ => Err(()),
+        }
+    }
+}
+
+/// An item on a todo list.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TodoItem {
+    /// Item ID, unique within the list.
+    pub id: u64,
+    pub text: String,
+    pub added_by: u64,
+    pub added_at: u64,
+    /// When the item is due (seconds since the Unix epoch).
+    #[serde(default)]
+    pub due: Option<u64>,
+    /// Whether the reminder for the due date was sent.
+    #[serde(default)]
+    pub reminded: bool,
+    #[serde(default)]
+    pub done: bool,
+}
+
+impl TodoItem {
+    /// The item as a line of a list.
+    pub fn line(&self) -> String {
+        let due = match self.due {
+            Some(due) if !self.done => format!(" · due {}", relative(due)),
+            _ => String::new(),
+        };
+        if self.done {
+            format!("✅ `#{}` ~~{}~~", self.id, self.text)
+        } else {
+            format!("⬜ `#{}` {}{}", self.id, self.text, due)
+        }
+    }
+}
+
+/// A todo list.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TodoList {
+    /// The last ID handed out to an item.
+    #[serde(default)]
+    pub last_id: u64,
+    #[serde(default)]
+    pub items: Vec<TodoItem>,
+}
+
+impl TodoList {
+    /// Add an item and return its ID, or `None` if the list is full.
+    pub fn add(
+        &mut self,
+        text: String,
+        added_by: u64,
+        added_at: u64,
+        due: Option<u64>,
+    ) -> Option<u64> {
+        if self.items.len() >= MAX_ITEMS {
+            return None;
+        }
+        self.last_id += 1;
+        self.items.push(TodoItem {
+            id: self.last_id,
+            text,
+            added_by,
+            added_at,
+            due,
+            reminded: false,
+            done: false,
+        });
+        Some(self.last_id)
+    }
+
+    /// Items that aren't done yet.
+    pub fn open(&self) -> impl Iterator<Item = &TodoItem> {
+        self.items.iter().filter(|item| !item.done)
+    }
+
+    /// Mark items as done and return the ones that weren't already.
+    pub fn complete(&mut self, ids: &[u64]) -> Vec<TodoItem> {
+        self.items
+            .iter_mut()
+            .filter(|item| !item.done && ids.contains(&item.id))
+            .map(|item| {
+                item.done = true;
+                item.clone()
+            })
+            .collect()
+    }
+
+    /// Remove finished items, or every item with `all`. Returns how many were removed.
+    pub fn clear(&mut self, all: bool) -> usize {
+        let count = self.items.len();
+        self.items.retain(|item| !all && !item.done);
+        count - self.items.len()
+    }
+
+    /// Items that are due and haven't been reminded about, marking them reminded.
+    pub fn take_due(&mut self, now: u64) -> Vec<TodoItem> {
+        self.items
+            .iter_mut()
+            .filter(|item| !item.done && !item.reminded && item.due.is_some_and(|due| due <= now))
+            .map(|item| {
+                item.reminded = true;
+                item.clone()
+            })
+            .collect()
+    }
+}
+
+/// Every todo list.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TodoData {
+    /// Personal lists by user ID.
+    #[serde(default)]
+    pub users: HashMap<u64, TodoList>,
+    /// Shared lists by channel ID.
+    #[serde(default)]
+    pub channels: HashMap<u64, TodoList>,
+}
+
+impl TodoData {
+    /// Get a list, if it has any items.
+    pub fn list(&self, owner: TodoOwner) -> Option<&TodoList> {
+        match owner {
+            TodoOwner::User(id) => self.users.get(&id),
+            TodoOwner::Channel(id) => self.channels.get(&id),
+        }
+    }
+
+    /// Get a mutable reference to a list, creating it if needed.
+    pub fn list_mut(&mut self, owner: TodoOwner) -> &mut TodoList {
+        match owner {
+            TodoOwner::User(id) => self.users.entry(id).or_default(),
+            TodoOwner::Channel(id) => self.channels.entry(id).or_default(),
+        }
+    }
+
+    /// Whether any list has an item to remind about.
+    pub fn has_due(&self, now: u64) -> bool {
+        self.users
+            .values()
+            .chain(self.channels.values())
+            .flat_map(|list| list.open())
+            .any(|item| !item.reminded && item.due.is_some_and(|due| due <= now))
+    }
+
+    /// Items on every list that are due, marking them reminded.
+    pub fn take_due(&mut self, now: u64) -> Vec<(TodoOwner, TodoItem)> {
+        let users = self.users.iter_mut().flat_map(|(id, list)| {
+            list.take_due(now)
+                .into_iter()
+                .map(|item| (TodoOwner::User(*id), item))
+        });
+        let channels = self.channels.iter_mut().flat_map(|(id, list)| {
+            list.take_due(now)
+                .into_iter()
+                .map(|item| (TodoOwner::Channel(*id), item))
+        });
+        users.chain(channels).collect::<Vec<_>>()
+    }
+}
+
+/// TypeMap key for the todo store.
+pub struct TodoKey;
+
+impl TypeMapKey for TodoKey {
+    type Value = Arc<JsonStore<TodoData>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completes_and_clears_items() {
+        let mut list = TodoList::default();
+        assert_eq!(list.add("write docs".into(), 1, 0, None), Some(1));
+        assert_eq!(list.add("ship".into(), 1, 0, None), Some(2));
+
+        assert_eq!(list.complete(&[1, 3]).len(), 1);
+        assert!(list.complete(&[1]).is_empty());
+        assert_eq!(list.open().count(), 1);
+
+        assert_eq!(list.clear(false), 1);
+        assert_eq!(list.clear(true), 1);
+        assert!(list.items.is_empty());
+    }
+
+    #[test]
+    fn reminds_about_due_items_once() {
+        let mut data = TodoData::default();
+        let owner = TodoOwner::Channel(5);
+        data.list_mut(owner).add("a".into(), 1, 0, Some(100));
+        data.list_mut(owner).add("b".into(), 1, 0, Some(200));
+
+        assert!(!data.has_due(99));
+        assert!(data.take_due(99).is_empty());
+        let due = data.take_due(150);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].0, owner);
+        assert!(!data.has_due(150));
+        assert!(data.take_due(150).is_empty());
+        assert_eq!("channel:5".parse::<TodoOwner>(), Ok(owner));
+    }
+}
@@ -0,0 +1,630 @@
+//! Collecting and deleting everything the bot stores about a user or a guild.
+
+use serde::Serialize;
+use serenity::model::id::{GuildId, UserId};
+use std::collections::BTreeMap;
+
+use crate::models::guild_config::GuildConfig;
+use crate::models::guild_events::{EventData, Rsvp};
+use crate::models::knowledge_base::{KnowledgeBaseData, Revision};
+use crate::models::lfg::LfgData;
+use crate::models::moderation::{
+    Appeal, GuildModeration, ModCase, ModerationData, Note, Report, WatchEntry,
+};
+use crate::models::modmail::{ModmailData, ModmailThread};
+use crate::models::notifications::{NotificationData, UserPrefs};
+use crate::models::premium::{Grant, GrantSource, PremiumData};
+use crate::models::quotes::{Quote, QuoteData};
+use crate::models::role_persistence::{RolePersistenceData, SavedMember};
+use crate::models::todos::{TodoData, TodoList};
+use crate::models::votes::{VoteData, Voter};
+use crate::utils::helpers::unix_timestamp;
+
+/// The stores that keep data about users.
+#[derive(Clone, Copy)]
+pub struct UserStores<'a> {
+    pub moderation: &'a ModerationData,
+    pub modmail: &'a ModmailData,
+    pub todos: &'a TodoData,
+    pub roles: &'a RolePersistenceData,
+    pub notifications: &'a NotificationData,
+    pub premium: &'a PremiumData,
+    pub votes: &'a VoteData,
+    pub quotes: &'a QuoteData,
+    pub events: &'a EventData,
+    pub lfg: &'a LfgData,
+    pub knowledge_base: &'a KnowledgeBaseData,
+}
+
+/// A user's answer to an event.
+#[derive(Debug, Serialize)]
+pub struct EventAnswer {
+    pub event_id: u64,
+    pub title: String,
+    pub start: u64,
+    pub rsvp: Rsvp,
+}
+
+/// A looking-for-group post the user hosts or joined.
+#[derive(Debug, Serialize)]
+pub struct LfgMembership {
+    pub post_id: u64,
+    pub game: String,
+    pub host: bool,
+}
+
+/// A knowledge base revision the user wrote.
+#[derive(Debug, Serialize)]
+pub struct ArticleRevision {
+    pub topic: String,
+    pub number: u64,
+    pub revision: Revision,
+}
+
+/// What a single guild stores about a user.
+#[derive(Debug, Default, Serialize)]
+pub struct GuildUserData {
+    pub notes: Vec<Note>,
+    pub cases: Vec<ModCase>,
+    pub appeals: Vec<Appeal>,
+    /// Reports the user made.
+    pub reports: Vec<Report>,
+    pub watchlist: Option<WatchEntry>,
+    pub modmail_blocked: bool,
+    /// The roles and nickname kept from when the user left, to give back if they
+    /// rejoin.
+    pub saved_roles: Option<SavedMember>,
+    /// Quotes of the user or saved by them.
+    pub quotes: Vec<Quote>,
+    pub events: Vec<EventAnswer>,
+    pub lfg: Vec<LfgMembership>,
+    pub knowledge_base: Vec<ArticleRevision>,
+}
+
+impl GuildUserData {
+    fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+            && self.cases.is_empty()
+            && self.appeals.is_empty()
+            && self.reports.is_empty()
+            && self.watchlist.is_none()
+            && !self.modmail_blocked
+            && self.saved_roles.is_none()
+            && self.quotes.is_empty()
+            && self.events.is_empty()
+            && self.lfg.is_empty()
+            && self.knowledge_base.is_empty()
+    }
+}
+
+/// Everything the bot stores about a user.
+#[derive(Debug, Serialize)]
+pub struct UserDataExport {
+    pub user_id: u64,
+    /// When the export was made (seconds since the Unix epoch).
+    pub generated_at: u64,
+    /// Data kept by each guild, by guild ID.
+    pub guilds: BTreeMap<u64, GuildUserData>,
+    /// The user's open modmail conversation, if any.
+    pub modmail: Option<ModmailThread>,
+    /// The user's personal todo list.
+    pub todos: Option<TodoList>,
+    /// The DMs the user turned off.
+    pub notifications: Option<UserPrefs>,
+    /// The user's premium tier.
+    pub premium: Option<Grant>,
+    /// The user's votes for the bot.
+    pub votes: Option<Voter>,
+}
+
+impl UserDataExport {
+    /// Collect a user's data from the stores.
+    pub fn collect(stores: UserStores<'_>, user_id: UserId) -> Self {
+        let mut guilds: BTreeMap<u64, GuildUserData> = BTreeMap::new();
+
+        for (guild_id, guild) in &stores.moderation.guilds {
+            let data = GuildUserData {
+                notes: guild.notes.get(&user_id.0).cloned().unwrap_or_default(),
+                cases: guild.cases.get(&user_id.0).cloned().unwrap_or_default(),
+                appeals: guild
+                    .appeals
+                    .values()
+                    .filter(|appeal| appeal.user_id == user_id.0)
+                    .cloned()
+                    .collect(),
+                reports: guild
+                    .reports
+                    .values()
+                    .filter(|report| report.reporter_id == user_id.0)
+                    .cloned()
+                    .collect(),
+                watchlist: guild.watchlist.get(&user_id.0).cloned(),
+                ..GuildUserData::default()
+            };
+            if !data.is_empty() {
+                guilds.insert(*guild_id, data);
+            }
+        }
+
+        for (guild_id, users) in &stores.modmail.blocked {
+            if users.contains(&user_id.0) {
+                guilds.entry(*guild_id).or_default().modmail_blocked = true;
+            }
+        }
+        for (guild_id, members) in &stores.roles.guilds {
+            if let Some(saved) = members.get(&user_id.0) {
+                guilds.entry(*guild_id).or_default().saved_roles = Some(saved.clone());
+            }
+        }
+
+        for (guild_id, guild) in &stores.quotes.guilds {
+            let quotes: Vec<Quote> = guild
+                .quotes
+                .iter()
+                .filter(|quote| quote.author_id == Some(user_id.0) || quote.added_by == user_id.0)
+                .cloned()
+                .collect();
+            if !quotes.is_empty() {
+                guilds.entry(*guild_id).or_default().quotes = quotes;
+            }
+        }
+        for (guild_id, guild) in &stores.events.guilds {
+            let events: Vec<EventAnswer> = guild
+                .events
+                .values()
+                .filter_map(|event| {
+                    event.rsvps.get(&user_id.0).map(|rsvp| EventAnswer {
+                        event_id: event.id,
+                        title: event.title.clone(),
+                        start: event.start,
+                        rsvp: *rsvp,
+                    })
+                })
+                .collect();
+            if !events.is_empty() {
+                guilds.entry(*guild_id).or_default().events = events;
+            }
+        }
+        for (guild_id, guild) in &stores.lfg.guilds {
+            let lfg: Vec<LfgMembership> = guild
+                .posts
+                .values()
+                .filter(|post| post.members.contains(&user_id.0))
+                .map(|post| LfgMembership {
+                    post_id: post.id,
+                    game: post.game.clone(),
+                    host: post.host == user_id.0,
+                })
+                .collect();
+            if !lfg.is_empty() {
+                guilds.entry(*guild_id).or_default().lfg = lfg;
+            }
+        }
+        for (guild_id, guild) in &stores.knowledge_base.guilds {
+            let revisions: Vec<ArticleRevision> = guild
+                .articles
+                .values()
+                .flat_map(|article| {
+                    let first = article.first_revision();
+                    article
+                        .revisions
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, revision)| revision.edited_by == user_id.0)
+                        .map(move |(i, revision)| ArticleRevision {
+                            topic: article.topic.clone(),
+                            number: first + i as u64,
+                            revision: revision.clone(),
+                        })
+                })
+                .collect();
+            if !revisions.is_empty() {
+                guilds.entry(*guild_id).or_default().knowledge_base = revisions;
+            }
+        }
+
+        Self {
+            user_id: user_id.0,
+            generated_at: unix_timestamp(),
+            guilds,
+            modmail: stores.modmail.thread_for_user(user_id).cloned(),
+            todos: stores.todos.users.get(&user_id.0).cloned(),
+            notifications: stores.notifications.users.get(&user_id.0).cloned(),
+            premium: stores.premium.users.get(&user_id.0).cloned(),
+            votes: stores.votes.voter(user_id).cloned(),
+        }
+    }
+
+    /// Whether the bot stores nothing about the user.
+    pub fn is_empty(&self) -> bool {
+        self.guilds.is_empty()
+            && self.modmail.is_none()
+            && self.todos.is_none()
+            && self.notifications.is_none()
+            && self.premium.is_none()
+            && self.votes.is_none()
+    }
+}
+
+impl ModmailData {
+    /// Close a user's open conversation. Returns how many records were deleted.
+    ///
+    /// Modmail blocks are kept, so deleting data can't be used to lift one. The
+    /// staff-side thread is left in Discord, but no longer relays messages.
+    pub fn remove_user(&mut self, user_id: UserId) -> usize {
+        usize::from(self.open.remove(&user_id.0).is_some())
+    }
+}
+
+impl TodoData {
+    /// Delete a user's personal list. Items they added to channel lists stay with
+    /// the channel. Returns how many items were deleted.
+    pub fn remove_user(&mut self, user_id: UserId) -> usize {
+        self.users
+            .remove(&user_id.0)
+            .map_or(0, |list| list.items.len())
+    }
+}
+
+impl RolePersistenceData {
+    /// Delete the roles kept for a user in every guild. Returns how many guilds
+    /// kept some.
+    pub fn remove_user(&mut self, user_id: UserId) -> usize {
+        let mut removed = 0;
+        for members in self.guilds.values_mut() {
+            removed += usize::from(members.remove(&user_id.0).is_some());
+        }
+        self.guilds.retain(|_, members| !members.is_empty());
+        removed
+    }
+}
+
+impl NotificationData {
+    /// Delete a user's preferences, turning every DM back on. Returns how many
+    /// records were deleted.
+    pub fn remove_user(&mut self, user_id: UserId) -> usize {
+        usize::from(self.users.remove(&user_id.0).is_some())
+    }
+}
+
+impl PremiumData {
+    /// Delete a user's voting trial. Tiers granted by an owner or bought are kept,
+    /// since deleting them would take away what the user paid for. Returns how
+    /// many records were deleted.
+    pub fn remove_user(&mut self, user_id: UserId) -> usize {
+        match self.users.get(&user_id.0) {
+            Some(grant) if grant.source == GrantSource::Vote => {
+                self.users.remove(&user_id.0);
+                1
+            }
+            _ => 0,
+        }
+    }
+}
+
+impl VoteData {
+    /// Delete a user's voting history. Returns how many records were deleted.
+    pub fn remove_user(&mut self, user_id: UserId) -> usize {
+        usize::from(self.users.remove(&user_id.0).is_some())
+    }
+}
+
+impl QuoteData {
+    /// Delete the quotes of a user and take their name off the ones they saved,
+    /// which stay with the guild. Returns how many quotes were changed.
+    pub fn remove_user(&mut self, user_id: UserId) -> usize {
+        let mut removed = 0;
+        for guild in self.guilds.values_mut() {
+            let before = guild.quotes.len();
+            guild
+                .quotes
+                .retain(|quote| quote.author_id != Some(user_id.0));
+            removed += before - guild.quotes.len();
+            for quote in &mut guild.quotes {
+                if quote.added_by == user_id.0 {
+                    quote.added_by = 0;
+                    removed += 1;
+                }
+            }
+        }
+        removed
+    }
+}
+
+impl EventData {
+    /// Delete a user's answers to events. Returns how many were deleted.
+    pub fn remove_user(&mut self, user_id: UserId) -> usize {
+        self.guilds
+            .values_mut()
+            .flat_map(|guild| guild.events.values_mut())
+            .map(|event| usize::from(event.rsvps.remove(&user_id.0).is_some()))
+            .sum()
+    }
+}
+
+impl LfgData {
+    /// Take a user out of the groups they joined. Groups they host are kept
+    /// until they expire or are cancelled. Returns how many groups they left.
+    pub fn remove_user(&mut self, user_id: UserId) -> usize {
+        let mut removed = 0;
+        for post in self
+            .guilds
+            .values_mut()
+            .flat_map(|guild| guild.posts.values_mut())
+            .filter(|post| post.host != user_id.0)
+        {
+            let before = post.members.len();
+            post.members.retain(|member| *member != user_id.0);
+            removed += before - post.members.len();
+        }
+        removed
+    }
+}
+
+impl KnowledgeBaseData {
+    /// Take a user's name off the revisions they wrote, which stay with the
+    /// guild. Returns how many revisions were changed.
+    pub fn remove_user(&mut self, user_id: UserId) -> usize {
+        let mut removed = 0;
+        for revision in self
+            .guilds
+            .values_mut()
+            .flat_map(|guild| guild.articles.values_mut())
+            .flat_map(|article| article.revisions.iter_mut())
+            .filter(|revision| revision.edited_by == user_id.0)
+        {
+            revision.edited_by = 0;
+            removed += 1;
+        }
+        removed
+    }
+}
+
+/// Everything the bot stores about a guild.
+#[derive(Debug, Serialize)]
+pub struct GuildDataExport<'a> {
+    pub guild_id: u64,
+    /// When the export was made (seconds since the Unix epoch).
+    pub generated_at: u64,
+    pub config: GuildConfig,
+    pub moderation: Option<&'a GuildModeration>,
+    /// Users blocked from modmail.
+    pub modmail_blocked: Vec<u64>,
+    /// Open modmail conversations with the guild's staff.
+    pub modmail_open: Vec<&'a ModmailThread>,
+    /// Feature data from the key-value store, by namespace and key.
+    pub kv: BTreeMap<String, BTreeMap<String, serde_json::Value>>,
+}
+
+impl<'a> GuildDataExport<'a> {
+    /// Collect a guild's data from the stores.
+    pub fn collect(
+        guild_id: GuildId,
+        config: GuildConfig,
+        moderation: &'a ModerationData,
+        modmail: &'a ModmailData,
+        kv: BTreeMap<String, BTreeMap<String, serde_json::Value>>,
+    ) -> Self {
+        let mut modmail_blocked: Vec<u64> = modmail
+            .blocked
+            .get(&guild_id.0)
+            .map(|users| users.iter().copied().collect())
+            .unwrap_or_default();
+        modmail_blocked.sort_unstable();
+
+        Self {
+            guild_id: guild_id.0,
+            generated_at: unix_timestamp(),
+            config,
+            moderation: moderation.guild(guild_id),
+            modmail_blocked,
+            modmail_open: modmail
+                .open
+                .values()
+                .filter(|thread| thread.guild_id == guild_id.0)
+                .collect(),
+            kv,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::guild_events::GuildEvent;
+    use crate::models::lfg::LfgPost;
+    use crate::models::moderation::CaseKind;
+    use crate::models::notifications::DmCategory;
+    use crate::models::premium::Tier;
+    use crate::models::votes::VoteSite;
+    use crate::testing::TestMessage;
+
+    #[derive(Default)]
+    struct Data {
+        moderation: ModerationData,
+        modmail: ModmailData,
+        todos: TodoData,
+        roles: RolePersistenceData,
+        notifications: NotificationData,
+        premium: PremiumData,
+        votes: VoteData,
+        quotes: QuoteData,
+        events: EventData,
+        lfg: LfgData,
+        knowledge_base: KnowledgeBaseData,
+    }
+
+    impl Data {
+        fn stores(&self) -> UserStores<'_> {
+            UserStores {
+                moderation: &self.moderation,
+                modmail: &self.modmail,
+                todos: &self.todos,
+                roles: &self.roles,
+                notifications: &self.notifications,
+                premium: &self.premium,
+                votes: &self.votes,
+                quotes: &self.quotes,
+                events: &self.events,
+                lfg: &self.lfg,
+                knowledge_base: &self.knowledge_base,
+            }
+        }
+    }
+
+    fn sample() -> Data {
+        let mut data = Data::default();
+        let guild = data.moderation.guild_mut(GuildId(1));
+        guild.add_note(UserId(10), UserId(99), "spams invites".to_string());
+        guild.add_note(UserId(11), UserId(99), "someone else".to_string());
+        let case = guild.add_case(UserId(10), CaseKind::Ban, Some(UserId(99)), None, None);
+        let case = guild.cases[&10]
+            .iter()
+            .find(|c| c.id == case)
+            .unwrap()
+            .clone();
+        guild.add_appeal(UserId(10), &case, "sorry".to_string());
+        let reported = TestMessage::new("buy followers").author(UserId(11)).build();
+        guild.add_report(UserId(10), &reported, "spam".to_string());
+
+        data.modmail.blocked.entry(2).or_default().insert(10);
+
+        let todos = data.todos.users.entry(10).or_default();
+        todos.add("water the plants".to_string(), 10, 0, None);
+        let saved = SavedMember {
+            roles: vec![5],
+            nickname: None,
+            left_at: 0,
+        };
+        data.roles.save(GuildId(3), UserId(10), saved);
+        data.notifications
+            .set(UserId(10), &[DmCategory::LevelUps], false);
+        data.premium.grant_trial(UserId(10), Tier::Plus, 100, 0);
+        data.votes.record(UserId(10), VoteSite::Topgg, 0, 60);
+
+        let quote = |author_id, added_by| Quote {
+            id: 0,
+            content: "hello".to_string(),
+            author_id: Some(author_id),
+            author_name: "someone".to_string(),
+            added_by,
+            added_at: 0,
+            channel_id: None,
+            message_id: None,
+        };
+        let quotes = data.quotes.guild_mut(GuildId(4));
+        quotes.add(quote(10, 11));
+        quotes.add(quote(11, 10));
+        quotes.add(quote(11, 11));
+
+        let mut event = GuildEvent {
+            id: 0,
+            title: "Movie night".to_string(),
+            description: String::new(),
+            location: None,
+            start: 0,
+            created_by: 11,
+            channel_id: 1,
+            message_id: None,
+            rsvps: BTreeMap::new(),
+            reminded: false,
+            scheduled_event_id: None,
+        };
+        event.answer(10, Rsvp::Going);
+        event.answer(11, Rsvp::Maybe);
+        data.events.guild_mut(GuildId(4)).add(event);
+
+        let post = |host| LfgPost {
+            id: 0,
+            game: "chess".to_string(),
+            slots: 4,
+            host,
+            members: vec![host, 10],
+            channel_id: 1,
+            message_id: None,
+            created_at: 0,
+            starts_at: None,
+            expires_at: 0,
+            filled_at: None,
+            voice_channel_id: None,
+            role_id: None,
+        };
+        let lfg = data.lfg.guild_mut(GuildId(4));
+        lfg.add(post(11));
+        lfg.add(post(10));
+
+        let revision = |edited_by| Revision {
+            content: "rules".to_string(),
+            embed: false,
+            edited_by,
+            edited_at: 0,
+        };
+        let kb = data.knowledge_base.guild_mut(GuildId(4));
+        kb.set("rules", "rules", revision(10)).unwrap();
+        kb.set("faq", "faq", revision(11)).unwrap();
+        data
+    }
+
+    #[test]
+    fn exports_only_the_users_data() {
+        let data = sample();
+        let export = UserDataExport::collect(data.stores(), UserId(10));
+
+        let guild = &export.guilds[&1];
+        assert_eq!(guild.notes.len(), 1);
+        assert_eq!(guild.cases.len(), 1);
+        assert_eq!(guild.appeals.len(), 1);
+        assert_eq!(guild.reports.len(), 1);
+        assert!(export.guilds[&2].modmail_blocked);
+        assert!(export.guilds[&3].saved_roles.is_some());
+        assert_eq!(export.todos.as_ref().unwrap().items.len(), 1);
+        assert!(export.notifications.is_some());
+        assert!(export.premium.is_some());
+        assert_eq!(export.votes.as_ref().unwrap().total, 1);
+        let guild = &export.guilds[&4];
+        assert_eq!(guild.quotes.len(), 2);
+        assert_eq!(guild.events[0].rsvp, Rsvp::Going);
+        assert_eq!(guild.lfg.len(), 2);
+        assert_eq!(guild.knowledge_base[0].topic, "rules");
+
+        let json = serde_json::to_string(&export).unwrap();
+        assert!(!json.contains("someone else"));
+
+        assert!(UserDataExport::collect(data.stores(), UserId(12)).is_empty());
+    }
+
+    #[test]
+    fn deletes_only_the_users_data() {
+        let mut data = sample();
+
+        assert_eq!(data.modmail.remove_user(UserId(10)), 0);
+        assert_eq!(data.todos.remove_user(UserId(10)), 1);
+        assert_eq!(data.roles.remove_user(UserId(10)), 1);
+        assert_eq!(data.notifications.remove_user(UserId(10)), 1);
+        assert_eq!(data.premium.remove_user(UserId(10)), 1);
+        assert_eq!(data.votes.remove_user(UserId(10)), 1);
+        assert_eq!(data.quotes.remove_user(UserId(10)), 2);
+        assert_eq!(data.events.remove_user(UserId(10)), 1);
+        assert_eq!(data.lfg.remove_user(UserId(10)), 1);
+        assert_eq!(data.knowledge_base.remove_user(UserId(10)), 1);
+
+        // Moderation records and modmail blocks are kept, as are groups they host
+        let export = UserDataExport::collect(data.stores(), UserId(10));
+        assert_eq!(export.guilds[&1].cases.len(), 1);
+        assert!(export.guilds[&2].modmail_blocked);
+        let guild = &export.guilds[&4];
+        assert!(guild.quotes.is_empty() && guild.events.is_empty());
+        assert!(guild.knowledge_base.is_empty());
+        assert!(guild.lfg.iter().all(|membership| membership.host));
+
+        let quotes = &data.quotes.guild(GuildId(4)).unwrap().quotes;
+        assert_eq!(quotes.len(), 2);
+        assert_eq!(quotes[0].added_by, 0);
+        assert_eq!(
+            data.events.guild(GuildId(4)).unwrap().events[&1]
+                .rsvps
+                .len(),
+            1
+        );
+    }
+}
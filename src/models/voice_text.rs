@@ -0,0 +1,86 @@
+//! Text channels that members in a voice channel get access to.
+//!
+//! A voice channel is linked either to an existing text channel or to a temporary
+//! one, created when the first member joins and deleted when the last one leaves.
+//! Members are given access with a permission overwrite while they're connected.
+//! Changed with `voicetext`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Most voice channels a guild can link.
+pub const MAX_LINKS: usize = 25;
+
+/// Longest channel name Discord allows.
+const MAX_NAME_LENGTH: usize = 100;
+
+/// The text channel a voice channel is linked to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VoiceText {
+    /// An existing text channel, by ID.
+    Channel(u64),
+    /// A channel created while anyone is connected.
+    Temporary,
+}
+
+/// A guild's voice channel links.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct VoiceTextSettings {
+    /// Links by voice channel ID.
+    #[serde(default)]
+    pub links: HashMap<u64, VoiceText>,
+
+    /// Temporary text channels that exist right now, by voice channel ID. Kept so
+    /// ones left behind by a restart can be cleaned up.
+    #[serde(default)]
+    pub temporary: HashMap<u64, u64>,
+}
+
+impl VoiceTextSettings {
+    /// The text channel members of a voice channel get access to, if it exists.
+    pub fn text_channel(&self, voice_channel: u64) -> Option<u64> {
+        match self.links.get(&voice_channel)? {
+            VoiceText::Channel(channel_id) => Some(*channel_id),
+            VoiceText::Temporary => self.temporary.get(&voice_channel).copied(),
+        }
+    }
+}
+
+/// Name a temporary text channel after its voice channel, such as `gaming-text`
+/// for `🎮 Gaming`.
+pub fn temporary_name(voice_name: &str) -> String {
+    let mut name = voice_name
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if name.is_empty() {
+        name.push_str("voice");
+    }
+    let suffix = "-text";
+    let name: String = name.chars().take(MAX_NAME_LENGTH - suffix.len()).collect();
+    format!("{}{}", name, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_and_names() {
+        let mut settings = VoiceTextSettings::default();
+        settings.links.insert(1, VoiceText::Channel(10));
+        settings.links.insert(2, VoiceText::Temporary);
+        assert_eq!(settings.text_channel(1), Some(10));
+        assert_eq!(settings.text_channel(2), None);
+        assert_eq!(settings.text_channel(3), None);
+        settings.temporary.insert(2, 20);
+        assert_eq!(settings.text_channel(2), Some(20));
+
+        assert_eq!(temporary_name("🎮 Gaming Room #2"), "gaming-room-2-text");
+        assert_eq!(temporary_name("🔊"), "voice-text");
+        assert_eq!(temporary_name(&"a".repeat(200)).len(), MAX_NAME_LENGTH);
+    }
+}
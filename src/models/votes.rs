@@ -0,0 +1,125 @@
+//! Votes for the bot on bot lists and the streaks they build.
+
+use serde::{Deserialize, Serialize};
+use serenity::model::id::UserId;
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::storage::JsonStore;
+
+/// A bot list that sends vote webhooks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VoteSite {
+    /// top.gg
+    Topgg,
+    /// discordbotlist.com
+    Dbl,
+}
+
+impl VoteSite {
+    /// Every supported site.
+    pub const ALL: [VoteSite; 2] = [VoteSite::Topgg, VoteSite::Dbl];
+
+    /// The page where users vote for a bot.
+    pub fn vote_url(self, bot_id: UserId) -> String {
+        match self {
+            VoteSite::Topgg => format!("https://top.gg/bot/{}/vote", bot_id),
+            VoteSite::Dbl => format!("https://discordbotlist.com/bots/{}/upvote", bot_id),
+        }
+    }
+}
+
+impl fmt::Display for VoteSite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            VoteSite::Topgg => "top.gg",
+            VoteSite::Dbl => "discordbotlist.com",
+        })
+    }
+}
+
+/// A user's voting history.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Voter {
+    /// Votes ever made.
+    #[serde(default)]
+    pub total: u64,
+    /// Votes in a row, each within the streak window of the last.
+    #[serde(default)]
+    pub streak: u64,
+    /// Longest streak so far.
+    #[serde(default)]
+    pub best_streak: u64,
+    /// When the user last voted on each site (seconds since the Unix epoch).
+    #[serde(default)]
+    pub last_votes: HashMap<String, u64>,
+}
+
+impl Voter {
+    /// When the user last voted on any site.
+    pub fn last_vote(&self) -> Option<u64> {
+        self.last_votes.values().copied().max()
+    }
+}
+
+/// Voting history by user ID.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct VoteData {
+    #[serde(default)]
+    pub users: HashMap<u64, Voter>,
+}
+
+impl VoteData {
+    /// A user's voting history.
+    pub fn voter(&self, user_id: UserId) -> Option<&Voter> {
+        self.users.get(&user_id.0)
+    }
+
+    /// Record a vote and update the user's streak, which carries on if they voted
+    /// within `streak_window` seconds of their last vote.
+    pub fn record(
+        &mut self,
+        user_id: UserId,
+        site: VoteSite,
+        now: u64,
+        streak_window: u64,
+    ) -> &mut Voter {
+        let voter = self.users.entry(user_id.0).or_default();
+        let continues = voter
+            .last_vote()
+            .is_some_and(|last| now.saturating_sub(last) <= streak_window);
+        voter.streak = if continues { voter.streak + 1 } else { 1 };
+        voter.best_streak = voter.best_streak.max(voter.streak);
+        voter.total += 1;
+        voter.last_votes.insert(site.to_string(), now);
+        voter
+    }
+}
+
+/// TypeMap key for the vote store.
+pub struct VotesKey;
+
+impl TypeMapKey for VotesKey {
+    type Value = Arc<JsonStore<VoteData>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streaks_continue_within_the_window() {
+        let mut data = VoteData::default();
+        let user = UserId(1);
+
+        assert_eq!(data.record(user, VoteSite::Topgg, 100, 50).streak, 1);
+        assert_eq!(data.record(user, VoteSite::Dbl, 120, 50).streak, 2);
+        assert_eq!(data.record(user, VoteSite::Topgg, 160, 50).streak, 3);
+
+        let voter = data.record(user, VoteSite::Topgg, 500, 50);
+        assert_eq!((voter.streak, voter.best_streak, voter.total), (1, 3, 4));
+    }
+}
@@ -0,0 +1,354 @@
+//! Word filter lists: wildcard and regex patterns with a severity each, and what to do
+//! at each severity.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serenity::model::id::{ChannelId, GuildId, RoleId};
+use serenity::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+use crate::storage::JsonStore;
+use crate::utils::helpers::compile_regex;
+
+/// How a filter pattern is written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PatternKind {
+    /// A word where `*` matches any characters within the word, like `bad*`.
+    Wildcard,
+    /// A regular expression matched anywhere in the message.
+    Regex,
+}
+
+impl fmt::Display for PatternKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Wildcard => "wildcard",
+            Self::Regex => "regex",
+        };
+        f.write_str(name)
+    }
+}
+
+impl std::str::FromStr for PatternKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "wildcard" | "word" => Ok(Self::Wildcard),
+            "regex" => Ok(Self::Regex),
+            _ => Err(format!(
+                "Unknown pattern type `{}`. Use wildcard or regex.",
+                s
+            )),
+        }
+    }
+}
+
+/// How serious a filtered word is, which decides the actions taken.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+impl Severity {
+    /// Every severity, from lowest to highest.
+    pub const ALL: [Severity; 3] = [Severity::Low, Severity::Medium, Severity::High];
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        };
+        f.write_str(name)
+    }
+}
+
+impl std::str::FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "low" => Ok(Self::Low),
+            "medium" => Ok(Self::Medium),
+            "high" => Ok(Self::High),
+            _ => Err(format!(
+                "Unknown severity `{}`. Use low, medium or high.",
+                s
+            )),
+        }
+    }
+}
+
+/// Something done to a message that matches the filter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterAction {
+    /// Delete the message.
+    Delete,
+    /// Record a warning case and tell the author.
+    Warn,
+    /// Time the author out for the filter's timeout duration.
+    Timeout,
+}
+
+impl fmt::Display for FilterAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Delete => "delete",
+            Self::Warn => "warn",
+            Self::Timeout => "timeout",
+        };
+        f.write_str(name)
+    }
+}
+
+impl std::str::FromStr for FilterAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "delete" => Ok(Self::Delete),
+            "warn" => Ok(Self::Warn),
+            "timeout" => Ok(Self::Timeout),
+            _ => Err(format!(
+                "Unknown action `{}`. Use delete, warn or timeout.",
+                s
+            )),
+        }
+    }
+}
+
+/// Turn a wildcard pattern into a regex matching it as a whole word.
+pub fn wildcard_regex(pattern: &str) -> String {
+    let body = pattern
+        .split('*')
+        .map(regex::escape)
+        .collect::<Vec<_>>()
+        .join(r"\S*");
+    format!(r"(?:^|\W){}(?:$|\W)", body)
+}
+
+/// A pattern in a filter list, as it is imported and exported.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilterPattern {
+    pub kind: PatternKind,
+    pub pattern: String,
+    pub severity: Severity,
+}
+
+impl FilterPattern {
+    /// Compile the pattern, checking that it is valid.
+    pub fn compile(&self) -> Result<Regex, String> {
+        match self.kind {
+            PatternKind::Wildcard => compile_regex(&wildcard_regex(&self.pattern)),
+            PatternKind::Regex => compile_regex(&self.pattern),
+        }
+    }
+}
+
+/// A pattern in a guild's filter.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FilterRule {
+    /// Rule ID, unique within the guild.
+    pub id: u64,
+    #[serde(flatten)]
+    pub pattern: FilterPattern,
+    /// Who added the rule.
+    pub created_by: u64,
+}
+
+/// Default actions: delete at every severity, warn from medium, time out at high.
+fn default_actions() -> BTreeMap<Severity, Vec<FilterAction>> {
+    BTreeMap::from([
+        (Severity::Low, vec![FilterAction::Delete]),
+        (
+            Severity::Medium,
+            vec![FilterAction::Delete, FilterAction::Warn],
+        ),
+        (
+            Severity::High,
+            vec![
+                FilterAction::Delete,
+                FilterAction::Warn,
+                FilterAction::Timeout,
+            ],
+        ),
+    ])
+}
+
+fn default_timeout() -> u64 {
+    600
+}
+
+/// A guild's word filter.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GuildFilter {
+    /// The last ID handed out to a rule.
+    #[serde(default)]
+    pub last_id: u64,
+    #[serde(default)]
+    pub rules: Vec<FilterRule>,
+    /// Actions taken for each severity.
+    #[serde(default = "default_actions")]
+    pub actions: BTreeMap<Severity, Vec<FilterAction>>,
+    /// How long the timeout action lasts, in seconds.
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+    /// Roles whose members aren't filtered.
+    #[serde(default)]
+    pub whitelisted_roles: Vec<u64>,
+    /// Channels that aren't filtered.
+    #[serde(default)]
+    pub whitelisted_channels: Vec<u64>,
+}
+
+impl Default for GuildFilter {
+    fn default() -> Self {
+        Self {
+            last_id: 0,
+            rules: Vec::new(),
+            actions: default_actions(),
+            timeout: default_timeout(),
+            whitelisted_roles: Vec::new(),
+            whitelisted_channels: Vec::new(),
+        }
+    }
+}
+
+impl GuildFilter {
+    /// Add a pattern and return its ID, or `None` if the filter already has it.
+    pub fn add(&mut self, pattern: FilterPattern, created_by: u64) -> Option<u64> {
+        let duplicate = self.rules.iter().any(|rule| {
+            rule.pattern.kind == pattern.kind
+                && rule.pattern.pattern.eq_ignore_ascii_case(&pattern.pattern)
+        });
+        if duplicate {
+            return None;
+        }
+
+        self.last_id += 1;
+        self.rules.push(FilterRule {
+            id: self.last_id,
+            pattern,
+            created_by,
+        });
+        Some(self.last_id)
+    }
+
+    /// Remove a rule. Returns whether it existed.
+    pub fn remove(&mut self, id: u64) -> bool {
+        let count = self.rules.len();
+        self.rules.retain(|rule| rule.id != id);
+        self.rules.len() != count
+    }
+
+    /// The actions taken at a severity.
+    pub fn actions_for(&self, severity: Severity) -> &[FilterAction] {
+        self.actions
+            .get(&severity)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Whether a message in a channel, from a member with the given roles, is exempt.
+    pub fn is_whitelisted(&self, channel_id: ChannelId, roles: &[RoleId]) -> bool {
+        self.whitelisted_channels.contains(&channel_id.0)
+            || roles
+                .iter()
+                .any(|role| self.whitelisted_roles.contains(&role.0))
+    }
+
+    /// The guild's patterns, for exporting.
+    pub fn export(&self) -> Vec<FilterPattern> {
+        self.rules.iter().map(|rule| rule.pattern.clone()).collect()
+    }
+}
+
+/// Word filters for all guilds.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WordFilterData {
+    /// Filters by guild ID.
+    #[serde(default)]
+    pub guilds: HashMap<u64, GuildFilter>,
+}
+
+impl WordFilterData {
+    /// Get a guild's filter, if it has one.
+    pub fn guild(&self, guild_id: GuildId) -> Option<&GuildFilter> {
+        self.guilds.get(&guild_id.0)
+    }
+
+    /// Get a mutable reference to a guild's filter, creating it if needed.
+    pub fn guild_mut(&mut self, guild_id: GuildId) -> &mut GuildFilter {
+        self.guilds.entry(guild_id.0).or_default()
+    }
+}
+
+/// TypeMap key for the word filter store.
+pub struct WordFilterKey;
+
+impl TypeMapKey for WordFilterKey {
+    type Value = Arc<JsonStore<WordFilterData>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(kind: PatternKind, pattern: &str) -> FilterPattern {
+        FilterPattern {
+            kind,
+            pattern: pattern.to_string(),
+            severity: Severity::Low,
+        }
+    }
+
+    #[test]
+    fn wildcards_match_whole_words() {
+        let regex = pattern(PatternKind::Wildcard, "bad*").compile().unwrap();
+        assert!(regex.is_match("that is BADWORD!"));
+        assert!(regex.is_match("bad"));
+        assert!(!regex.is_match("notbad"));
+
+        let regex = pattern(PatternKind::Wildcard, "a.b").compile().unwrap();
+        assert!(regex.is_match("a.b"));
+        assert!(!regex.is_match("axb"));
+
+        assert!(pattern(PatternKind::Regex, "(").compile().is_err());
+    }
+
+    #[test]
+    fn skips_duplicates_and_round_trips_exports() {
+        let mut filter = GuildFilter::default();
+        assert_eq!(
+            filter.add(pattern(PatternKind::Wildcard, "foo"), 1),
+            Some(1)
+        );
+        assert_eq!(filter.add(pattern(PatternKind::Wildcard, "FOO"), 1), None);
+        assert_eq!(filter.add(pattern(PatternKind::Regex, "foo"), 1), Some(2));
+
+        let json = serde_json::to_string(&filter.export()).unwrap();
+        let imported: Vec<FilterPattern> = serde_json::from_str(&json).unwrap();
+        assert_eq!(imported, filter.export());
+
+        assert_eq!(
+            filter.actions_for(Severity::High),
+            [
+                FilterAction::Delete,
+                FilterAction::Warn,
+                FilterAction::Timeout
+            ]
+        );
+        assert!(filter.remove(1));
+        assert!(!filter.remove(1));
+    }
+}
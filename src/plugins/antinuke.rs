@@ -0,0 +1,57 @@
+//! Anti-nuke protection: stopping anyone who mass deletes channels, bans members,
+//! creates webhooks or escalates role permissions.
+
+use async_trait::async_trait;
+use serenity::model::gateway::GatewayIntents;
+use serenity::model::permissions::Permissions;
+use std::io;
+use std::sync::Arc;
+
+use crate::commands::admin::antinuke::AntinukeCommand;
+use crate::events::AntinukeHandler;
+use crate::framework::command_handler::CommandHandler;
+use crate::framework::event_handler::EventDispatcher;
+use crate::framework::plugin::{Plugin, PluginContext};
+use crate::models::antinuke::{AntinukeKey, Tracker};
+
+/// Anti-nuke protection, turned on per server with `antinuke on`.
+pub struct AntinukePlugin;
+
+#[async_trait]
+impl Plugin for AntinukePlugin {
+    fn name(&self) -> &'static str {
+        "antinuke"
+    }
+
+    fn description(&self) -> &'static str {
+        "Stop mass channel deletions, bans, webhooks and permission escalation"
+    }
+
+    fn config_section(&self) -> Option<&'static str> {
+        Some("antinuke")
+    }
+
+    fn intents(&self) -> GatewayIntents {
+        // Audit log entries are delivered with the moderation intent
+        GatewayIntents::GUILD_BANS
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::VIEW_AUDIT_LOG | Permissions::MANAGE_ROLES
+    }
+
+    async fn init(&self, ctx: PluginContext<'_>) -> io::Result<()> {
+        let tracker = Arc::new(Tracker::new(ctx.config.antinuke.clone()));
+        ctx.state.insert::<AntinukeKey>(tracker);
+        Ok(())
+    }
+
+    fn register_commands(&self, handler: &mut CommandHandler) {
+        handler.register_with_state(AntinukeCommand::new);
+    }
+
+    fn register_events(&self, dispatcher: &mut EventDispatcher) {
+        // Register the handler that reads audit log entries as they're created
+        dispatcher.register_handler(AntinukeHandler);
+    }
+}
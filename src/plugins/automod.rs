@@ -0,0 +1,67 @@
+//! Automatic moderation: the word filter, phishing link detection, token leak
+//! detection and repeated message removal.
+
+use async_trait::async_trait;
+use serenity::model::permissions::Permissions;
+use std::io;
+use std::sync::Arc;
+
+use crate::commands::admin::filter::FilterCommand;
+use crate::events::{LeakMiddleware, PhishingMiddleware, RepeatMiddleware, WordFilterMiddleware};
+use crate::framework::command_handler::CommandHandler;
+use crate::framework::event_handler::EventDispatcher;
+use crate::framework::plugin::{Plugin, PluginContext};
+use crate::models::word_filter::WordFilterKey;
+use crate::utils::leaks::{LeakKey, LeakScanner};
+use crate::utils::phishing::{PhishingKey, PhishingList};
+
+/// The word filter, set up with `filter`, phishing link detection, set up with
+/// `settings phishing_actions`, token leak detection, turned on with
+/// `settings leak_detection`, and repeated message removal, set up with
+/// `settings repeat_limit`.
+pub struct AutomodPlugin;
+
+#[async_trait]
+impl Plugin for AutomodPlugin {
+    fn name(&self) -> &'static str {
+        "automod"
+    }
+
+    fn description(&self) -> &'static str {
+        "Word filter, phishing link, token leak and repeated message detection"
+    }
+
+    fn config_section(&self) -> Option<&'static str> {
+        Some("phishing")
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::MANAGE_MESSAGES | Permissions::MODERATE_MEMBERS
+    }
+
+    async fn init(&self, ctx: PluginContext<'_>) -> io::Result<()> {
+        let word_filter = Arc::new(ctx.storage.open("word_filter").await?);
+        ctx.state.insert::<WordFilterKey>(word_filter);
+
+        // Load the phishing blocklists in the background
+        let phishing = Arc::new(PhishingList::new(ctx.config.phishing.clone()));
+        phishing.clone().spawn();
+        ctx.state.insert::<PhishingKey>(phishing);
+
+        let leaks = Arc::new(LeakScanner::new(&ctx.config.leaks));
+        ctx.state.insert::<LeakKey>(leaks);
+        Ok(())
+    }
+
+    fn register_commands(&self, handler: &mut CommandHandler) {
+        handler.register_with_state(FilterCommand::new);
+    }
+
+    fn register_events(&self, dispatcher: &mut EventDispatcher) {
+        // Register the automod middleware, which runs before any message handler
+        dispatcher.register_middleware(LeakMiddleware);
+        dispatcher.register_middleware(PhishingMiddleware);
+        dispatcher.register_middleware(WordFilterMiddleware::default());
+        dispatcher.register_middleware(RepeatMiddleware::default());
+    }
+}
@@ -0,0 +1,46 @@
+//! Counting and word chain game channels.
+
+use async_trait::async_trait;
+use serenity::model::permissions::Permissions;
+use std::io;
+use std::sync::Arc;
+
+use crate::commands::admin::games::GamesCommand;
+use crate::events::GameHandler;
+use crate::framework::command_handler::CommandHandler;
+use crate::framework::event_handler::EventDispatcher;
+use crate::framework::plugin::{Plugin, PluginContext};
+use crate::models::games::GamesKey;
+
+/// Counting and word chain channels, set up with `games`.
+pub struct GamesPlugin;
+
+#[async_trait]
+impl Plugin for GamesPlugin {
+    fn name(&self) -> &'static str {
+        "games"
+    }
+
+    fn description(&self) -> &'static str {
+        "Counting and word chain channels"
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::MANAGE_MESSAGES
+    }
+
+    async fn init(&self, ctx: PluginContext<'_>) -> io::Result<()> {
+        let games = Arc::new(ctx.storage.open("games").await?);
+        ctx.state.insert::<GamesKey>(games);
+        Ok(())
+    }
+
+    fn register_commands(&self, handler: &mut CommandHandler) {
+        handler.register_with_state(GamesCommand::new);
+    }
+
+    fn register_events(&self, dispatcher: &mut EventDispatcher) {
+        // Register the counting and word chain game handler
+        dispatcher.register_handler(GameHandler);
+    }
+}
@@ -0,0 +1,31 @@
+//! Optional feature areas, each compiled in with the Cargo feature of the same name.
+//!
+//! Build with `--no-default-features` and pick the features you need to leave the
+//! others out entirely.
+
+#[cfg(feature = "antinuke")]
+pub mod antinuke;
+#[cfg(feature = "automod")]
+pub mod automod;
+#[cfg(feature = "games")]
+pub mod games;
+#[cfg(feature = "music")]
+pub mod music;
+
+use std::sync::Arc;
+
+use crate::framework::plugin::Plugin;
+
+/// The plugins compiled into this build.
+pub fn builtin() -> Vec<Arc<dyn Plugin>> {
+    vec![
+        #[cfg(feature = "automod")]
+        Arc::new(automod::AutomodPlugin),
+        #[cfg(feature = "antinuke")]
+        Arc::new(antinuke::AntinukePlugin),
+        #[cfg(feature = "games")]
+        Arc::new(games::GamesPlugin),
+        #[cfg(feature = "music")]
+        Arc::new(music::MusicPlugin),
+    ]
+}
@@ -0,0 +1,89 @@
+//! Soundboard clips and music commands.
+
+use async_trait::async_trait;
+use serenity::model::gateway::GatewayIntents;
+use serenity::model::permissions::Permissions;
+use songbird::{Songbird, SongbirdKey};
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::commands::admin::music::MusicCommand;
+use crate::commands::general::audiofilter::AudioFilterCommand;
+use crate::commands::general::play::PlayCommand;
+use crate::commands::general::queue::QueueCommand;
+use crate::commands::general::radio::RadioCommand;
+use crate::commands::general::skip::SkipCommand;
+use crate::commands::general::sound::SoundCommand;
+use crate::commands::general::stop::StopCommand;
+use crate::events::{MusicRestoreHandler, MusicVoiceHandler};
+use crate::framework::command_handler::CommandHandler;
+use crate::framework::event_handler::EventDispatcher;
+use crate::framework::plugin::{Plugin, PluginContext};
+use crate::models::sounds::{Soundboard, SoundsKey};
+use crate::utils::lyrics::{LyricsClient, LyricsKey};
+use crate::utils::player::{Player, PlayerKey};
+use crate::utils::track_links::{TrackLinks, TrackLinksKey};
+
+/// The soundboard, radio presets and music, used with `sound`, `radio`, `play`,
+/// `queue`, `skip`, `stop`, `audiofilter` and `music`.
+pub struct MusicPlugin;
+
+#[async_trait]
+impl Plugin for MusicPlugin {
+    fn name(&self) -> &'static str {
+        "music"
+    }
+
+    fn description(&self) -> &'static str {
+        "Soundboard clips, radio presets and music"
+    }
+
+    fn config_section(&self) -> Option<&'static str> {
+        Some("music")
+    }
+
+    fn intents(&self) -> GatewayIntents {
+        // Commands look up which voice channel the invoker is in, and songbird
+        // needs the bot's own voice state to connect
+        GatewayIntents::GUILD_VOICE_STATES
+    }
+
+    fn required_bot_permissions(&self) -> Permissions {
+        Permissions::CONNECT | Permissions::SPEAK
+    }
+
+    async fn init(&self, ctx: PluginContext<'_>) -> io::Result<()> {
+        let store = ctx.storage.open("sounds").await?;
+        let dir = Path::new(&ctx.config.storage.data_dir).join("sounds");
+        ctx.state
+            .insert::<SoundsKey>(Arc::new(Soundboard::new(store, dir)));
+        ctx.state
+            .insert::<TrackLinksKey>(Arc::new(TrackLinks::new()));
+        ctx.state.insert::<LyricsKey>(Arc::new(LyricsClient::new()));
+        // The client registers songbird as its voice manager, see `Bot::start`
+        let songbird = Songbird::serenity();
+        let saved = ctx.storage.open("music_sessions").await?;
+        let player = Arc::new(Player::new(songbird.clone(), saved));
+        player.clone().spawn();
+        ctx.state.insert::<PlayerKey>(player);
+        ctx.state.insert::<SongbirdKey>(songbird);
+        Ok(())
+    }
+
+    fn register_commands(&self, handler: &mut CommandHandler) {
+        handler.register_with_state(SoundCommand::new);
+        handler.register_with_state(MusicCommand::new);
+        handler.register_with_state(PlayCommand::new);
+        handler.register_with_state(RadioCommand::new);
+        handler.register_with_state(QueueCommand::new);
+        handler.register_with_state(SkipCommand::new);
+        handler.register_with_state(StopCommand::new);
+        handler.register_with_state(AudioFilterCommand::new);
+    }
+
+    fn register_events(&self, dispatcher: &mut EventDispatcher) {
+        dispatcher.register_handler(MusicVoiceHandler);
+        dispatcher.register_handler(MusicRestoreHandler);
+    }
+}
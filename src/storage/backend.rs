@@ -0,0 +1,73 @@
+//! Where stores keep their documents.
+
+use async_trait::async_trait;
+use serde_json::Value;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Persists named JSON documents.
+///
+/// Every store is one document, so a backend only has to load and replace
+/// documents by name.
+#[async_trait]
+pub trait Backend: Send + Sync {
+    /// Load a document, or `None` if it was never saved.
+    async fn load(&self, name: &str) -> io::Result<Option<Value>>;
+
+    /// Replace a document.
+    async fn save(&self, name: &str, document: &Value) -> io::Result<()>;
+
+    /// Describe where documents are kept, for logs.
+    fn describe(&self) -> String;
+}
+
+/// Keeps each document in a JSON file in the data directory.
+#[derive(Clone, Debug)]
+pub struct FileBackend {
+    /// Directory where document files are kept.
+    data_dir: PathBuf,
+}
+
+impl FileBackend {
+    /// Create a backend rooted at the given directory, creating it if needed.
+    pub fn new(data_dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let data_dir = data_dir.into();
+        fs::create_dir_all(&data_dir)?;
+
+        Ok(Self { data_dir })
+    }
+
+    /// Get the path of a document's file (`<data_dir>/<name>.json`).
+    fn path(&self, name: &str) -> PathBuf {
+        self.data_dir.join(format!("{}.json", name))
+    }
+}
+
+#[async_trait]
+impl Backend for FileBackend {
+    async fn load(&self, name: &str) -> io::Result<Option<Value>> {
+        match fs::read_to_string(self.path(name)) {
+            Ok(content) => serde_json::from_str(&content)
+                .map(Some)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Write the document to a temporary file and move it into place.
+    async fn save(&self, name: &str, document: &Value) -> io::Result<()> {
+        let content = serde_json::to_string_pretty(document)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let path = self.path(name);
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, content)?;
+        fs::rename(&tmp_path, &path)
+    }
+
+    fn describe(&self) -> String {
+        format!("JSON files in {}", self.data_dir.display())
+    }
+}
@@ -0,0 +1,224 @@
+//! Keeping the bot running while the backend is unreachable.
+//!
+//! Stores keep their documents in memory, so reads never need the backend. When
+//! a save fails, storage goes degraded: most stores keep taking changes in
+//! memory and queue their latest document in a backlog, while stores opened
+//! with [`Storage::open_durable`](super::Storage::open_durable) refuse changes
+//! they can't save, so users get a notice instead of losing data on a restart.
+//! A background task retries the backlog until it's written, then storage
+//! recovers.
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use super::Backend;
+
+/// How often the backlog is retried while storage is degraded.
+const RETRY_INTERVAL: Duration = Duration::from_secs(15);
+
+/// The error durable stores return while storage is degraded.
+#[derive(Debug)]
+pub struct Unavailable;
+
+impl fmt::Display for Unavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "storage is unavailable, changes can't be saved")
+    }
+}
+
+impl Error for Unavailable {}
+
+/// Create the error durable stores return while storage is degraded.
+pub fn unavailable() -> io::Error {
+    io::Error::other(Unavailable)
+}
+
+/// Whether an error means storage is degraded, rather than something failing.
+pub fn is_unavailable(e: &io::Error) -> bool {
+    e.get_ref().is_some_and(|inner| inner.is::<Unavailable>())
+}
+
+/// A stretch of time the backend was unreachable.
+struct Outage {
+    started: Instant,
+    /// Changes kept in memory meanwhile.
+    deferred: usize,
+}
+
+#[derive(Default)]
+struct State {
+    outage: Option<Outage>,
+    /// The latest unsaved document of each store, by name.
+    backlog: BTreeMap<String, Value>,
+}
+
+/// Whether the backend is reachable, and what couldn't be saved while it wasn't.
+#[derive(Default)]
+pub struct StorageHealth {
+    state: Mutex<State>,
+}
+
+impl StorageHealth {
+    /// Create a tracker for a reachable backend.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Whether saves are being deferred.
+    pub fn is_degraded(&self) -> bool {
+        self.state().outage.is_some()
+    }
+
+    /// How many stores have changes waiting to be saved.
+    pub fn backlog_len(&self) -> usize {
+        self.state().backlog.len()
+    }
+
+    /// Note that a save failed, going degraded if storage wasn't already.
+    pub fn fail(&self, name: &str, e: &io::Error) {
+        let mut state = self.state();
+        if state.outage.is_none() {
+            warn!(
+                "Couldn't save {} ({}), keeping changes in memory until storage is back",
+                name, e
+            );
+            state.outage = Some(Outage {
+                started: Instant::now(),
+                deferred: 0,
+            });
+        }
+    }
+
+    /// Queue a store's latest document to be saved once storage is back.
+    pub fn defer(&self, name: &str, document: Value) {
+        let mut state = self.state();
+        if let Some(outage) = &mut state.outage {
+            outage.deferred += 1;
+        }
+        state.backlog.insert(name.to_string(), document);
+        debug!("Deferred saving {}", name);
+    }
+
+    /// Try to save the backlog, recovering once all of it is written.
+    ///
+    /// Returns whether storage is healthy afterwards.
+    pub async fn flush(&self, backend: &dyn Backend) -> bool {
+        let names: Vec<String> = self.state().backlog.keys().cloned().collect();
+        for name in names {
+            // A newer document may have been deferred since, so only take what's
+            // there now and put it back if saving fails and nothing replaced it
+            let document = match self.state().backlog.remove(&name) {
+                Some(document) => document,
+                None => continue,
+            };
+            if let Err(e) = backend.save(&name, &document).await {
+                debug!("Storage still unavailable: {}", e);
+                self.state().backlog.entry(name).or_insert(document);
+                return false;
+            }
+        }
+
+        let mut state = self.state();
+        if !state.backlog.is_empty() {
+            return false;
+        }
+        if let Some(outage) = state.outage.take() {
+            info!(
+                "Storage is back after {}s, saved {} changes kept in memory meanwhile",
+                outage.started.elapsed().as_secs(),
+                outage.deferred
+            );
+        }
+        true
+    }
+
+    /// Retry the backlog every [`RETRY_INTERVAL`] while storage is degraded.
+    pub fn spawn(self: Arc<Self>, backend: Arc<dyn Backend>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(RETRY_INTERVAL);
+            loop {
+                interval.tick().await;
+                if self.is_degraded() {
+                    self.flush(backend.as_ref()).await;
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{JsonStore, Storage};
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// Keeps documents in memory and fails while it's down.
+    #[derive(Default)]
+    struct FlakyBackend {
+        down: AtomicBool,
+        documents: Mutex<HashMap<String, Value>>,
+    }
+
+    #[async_trait]
+    impl Backend for FlakyBackend {
+        async fn load(&self, name: &str) -> io::Result<Option<Value>> {
+            Ok(self.documents.lock().unwrap().get(name).cloned())
+        }
+
+        async fn save(&self, name: &str, document: &Value) -> io::Result<()> {
+            if self.down.load(Ordering::SeqCst) {
+                return Err(io::Error::new(io::ErrorKind::ConnectionRefused, "down"));
+            }
+            self.documents
+                .lock()
+                .unwrap()
+                .insert(name.to_string(), document.clone());
+            Ok(())
+        }
+
+        fn describe(&self) -> String {
+            "memory".to_string()
+        }
+    }
+
+    #[tokio::test]
+    async fn defers_until_storage_is_back() {
+        let backend = Arc::new(FlakyBackend::default());
+        let storage = Storage::with_backend(backend.clone());
+        let counts: JsonStore<Vec<u32>> = storage.open("counts").await.unwrap();
+        let cases: JsonStore<Vec<u32>> = storage.open_durable("cases").await.unwrap();
+
+        backend.down.store(true, Ordering::SeqCst);
+        counts.update(|c| c.push(1)).await.unwrap();
+        counts.update(|c| c.push(2)).await.unwrap();
+        assert!(storage.health().is_degraded());
+        assert_eq!(*counts.read().await, [1, 2]);
+
+        let e = cases.update(|c| c.push(1)).await.unwrap_err();
+        assert!(is_unavailable(&e));
+        assert!(cases.read().await.is_empty());
+
+        assert!(!storage.health().flush(storage.backend()).await);
+        backend.down.store(false, Ordering::SeqCst);
+        assert!(storage.health().flush(storage.backend()).await);
+        assert!(!storage.health().is_degraded());
+        assert_eq!(
+            backend.load("counts").await.unwrap(),
+            Some(serde_json::json!([1, 2]))
+        );
+        cases.update(|c| c.push(1)).await.unwrap();
+    }
+}
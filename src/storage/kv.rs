@@ -0,0 +1,330 @@
+//! Namespaced key-value storage for small pieces of feature data.
+//!
+//! Features that only need a few values per guild can keep them here instead of
+//! adding a store of their own. Values are stored as JSON under a namespace
+//! (usually the feature name), a guild, and a key, and can expire after a TTL.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serenity::model::id::GuildId;
+use serenity::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::JsonStore;
+use crate::utils::helpers::unix_timestamp;
+
+/// A stored value and when it expires.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct KvEntry {
+    value: serde_json::Value,
+    /// Expiry time (seconds since the Unix epoch), if the value has a TTL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<u64>,
+}
+
+impl KvEntry {
+    fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|at| at <= now)
+    }
+}
+
+/// All stored values, by namespace, guild and key.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct KvData {
+    namespaces: HashMap<String, HashMap<u64, HashMap<String, KvEntry>>>,
+}
+
+impl KvData {
+    fn entry(&self, namespace: &str, guild_id: GuildId, key: &str) -> Option<&KvEntry> {
+        self.namespaces.get(namespace)?.get(&guild_id.0)?.get(key)
+    }
+
+    fn entries_mut(&mut self, namespace: &str, guild_id: GuildId) -> &mut HashMap<String, KvEntry> {
+        self.namespaces
+            .entry(namespace.to_string())
+            .or_default()
+            .entry(guild_id.0)
+            .or_default()
+    }
+
+    fn remove(&mut self, namespace: &str, guild_id: GuildId, key: &str) -> Option<KvEntry> {
+        let guilds = self.namespaces.get_mut(namespace)?;
+        let entries = guilds.get_mut(&guild_id.0)?;
+        let removed = entries.remove(key);
+
+        // Drop empty maps so deleted data doesn't leave traces in the file
+        if entries.is_empty() {
+            guilds.remove(&guild_id.0);
+        }
+        if guilds.is_empty() {
+            self.namespaces.remove(namespace);
+        }
+        removed
+    }
+}
+
+/// Convert a JSON error into the store's error type.
+fn invalid_data(e: serde_json::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// Namespaced key-value store backed by a [`JsonStore`].
+pub struct KvStore {
+    store: JsonStore<KvData>,
+}
+
+impl KvStore {
+    /// Wrap an opened store.
+    pub fn new(store: JsonStore<KvData>) -> Self {
+        Self { store }
+    }
+
+    /// Get a value, or `None` if it isn't set or has expired.
+    pub async fn get<T: DeserializeOwned>(
+        &self,
+        namespace: &str,
+        guild_id: GuildId,
+        key: &str,
+    ) -> Result<Option<T>, io::Error> {
+        let data = self.store.read().await;
+        match data.entry(namespace, guild_id, key) {
+            Some(entry) if !entry.is_expired(unix_timestamp()) => {
+                T::deserialize(&entry.value).map(Some).map_err(invalid_data)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Set a value, replacing any previous one. With a `ttl`, the value expires after it.
+    pub async fn set<T: Serialize>(
+        &self,
+        namespace: &str,
+        guild_id: GuildId,
+        key: &str,
+        value: &T,
+        ttl: Option<Duration>,
+    ) -> Result<(), io::Error> {
+        let value = serde_json::to_value(value).map_err(invalid_data)?;
+        let expires_at = ttl.map(|ttl| unix_timestamp() + ttl.as_secs());
+
+        self.store
+            .update(|data| {
+                data.entries_mut(namespace, guild_id)
+                    .insert(key.to_string(), KvEntry { value, expires_at });
+            })
+            .await
+    }
+
+    /// Delete a value. Returns whether a live value was deleted.
+    pub async fn delete(
+        &self,
+        namespace: &str,
+        guild_id: GuildId,
+        key: &str,
+    ) -> Result<bool, io::Error> {
+        let now = unix_timestamp();
+        self.store
+            .update(|data| {
+                data.remove(namespace, guild_id, key)
+                    .is_some_and(|entry| !entry.is_expired(now))
+            })
+            .await
+    }
+
+    /// Add `by` to an integer value and return the result.
+    ///
+    /// Missing and expired values count as zero. The TTL of an existing value is kept.
+    pub async fn increment(
+        &self,
+        namespace: &str,
+        guild_id: GuildId,
+        key: &str,
+        by: i64,
+    ) -> Result<i64, io::Error> {
+        let now = unix_timestamp();
+        self.store
+            .update(|data| {
+                let entries = data.entries_mut(namespace, guild_id);
+                let entry = entries.entry(key.to_string()).or_insert(KvEntry {
+                    value: 0.into(),
+                    expires_at: None,
+                });
+                if entry.is_expired(now) {
+                    *entry = KvEntry {
+                        value: 0.into(),
+                        expires_at: None,
+                    };
+                }
+
+                let current = entry.value.as_i64().ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("`{}/{}` is not an integer", namespace, key),
+                    )
+                })?;
+                let total = current.saturating_add(by);
+                entry.value = total.into();
+                Ok(total)
+            })
+            .await?
+    }
+
+    /// List the live keys a guild has in a namespace.
+    pub async fn keys(&self, namespace: &str, guild_id: GuildId) -> Vec<String> {
+        let now = unix_timestamp();
+        let data = self.store.read().await;
+        let mut keys: Vec<String> = data
+            .namespaces
+            .get(namespace)
+            .and_then(|guilds| guilds.get(&guild_id.0))
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter(|(_, entry)| !entry.is_expired(now))
+                    .map(|(key, _)| key.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+        keys.sort();
+        keys
+    }
+
+    /// Get every live value a guild has, by namespace and key.
+    pub async fn guild_values(
+        &self,
+        guild_id: GuildId,
+    ) -> BTreeMap<String, BTreeMap<String, serde_json::Value>> {
+        let now = unix_timestamp();
+        let data = self.store.read().await;
+        data.namespaces
+            .iter()
+            .filter_map(|(namespace, guilds)| {
+                let values: BTreeMap<String, serde_json::Value> = guilds
+                    .get(&guild_id.0)?
+                    .iter()
+                    .filter(|(_, entry)| !entry.is_expired(now))
+                    .map(|(key, entry)| (key.clone(), entry.value.clone()))
+                    .collect();
+                (!values.is_empty()).then(|| (namespace.clone(), values))
+            })
+            .collect()
+    }
+
+    /// Remove expired values from disk. Returns how many were removed.
+    pub async fn purge_expired(&self) -> Result<usize, io::Error> {
+        let now = unix_timestamp();
+        self.store
+            .update(|data| {
+                let mut removed = 0;
+                for guilds in data.namespaces.values_mut() {
+                    for entries in guilds.values_mut() {
+                        let before = entries.len();
+                        entries.retain(|_, entry| !entry.is_expired(now));
+                        removed += before - entries.len();
+                    }
+                    guilds.retain(|_, entries| !entries.is_empty());
+                }
+                data.namespaces.retain(|_, guilds| !guilds.is_empty());
+                removed
+            })
+            .await
+    }
+}
+
+/// Key for storing the key-value store in the client data.
+pub struct KvKey;
+
+impl TypeMapKey for KvKey {
+    type Value = Arc<KvStore>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+
+    /// Open an empty store in a fresh temporary directory.
+    async fn temp_store(name: &str) -> KvStore {
+        let dir = std::env::temp_dir().join(format!("kurumi-kv-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let storage = Storage::new(dir).unwrap();
+        KvStore::new(storage.open("kv").await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn stores_values_per_namespace_and_guild() {
+        let kv = temp_store("scopes").await;
+        let guild = GuildId(1);
+
+        kv.set("leveling", guild, "multiplier", &1.5, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            kv.get::<f64>("leveling", guild, "multiplier")
+                .await
+                .unwrap(),
+            Some(1.5)
+        );
+        assert_eq!(
+            kv.get::<f64>("leveling", GuildId(2), "multiplier")
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            kv.get::<f64>("economy", guild, "multiplier").await.unwrap(),
+            None
+        );
+        assert!(kv
+            .get::<String>("leveling", guild, "multiplier")
+            .await
+            .is_err());
+
+        assert!(kv.delete("leveling", guild, "multiplier").await.unwrap());
+        assert!(!kv.delete("leveling", guild, "multiplier").await.unwrap());
+        assert!(kv.keys("leveling", guild).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn expired_values_are_hidden_and_purged() {
+        let kv = temp_store("ttl").await;
+        let guild = GuildId(1);
+
+        kv.set("raid", guild, "lockdown", &true, Some(Duration::ZERO))
+            .await
+            .unwrap();
+        kv.set("raid", guild, "joins", &3, Some(Duration::from_secs(60)))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            kv.get::<bool>("raid", guild, "lockdown").await.unwrap(),
+            None
+        );
+        assert_eq!(kv.keys("raid", guild).await, ["joins"]);
+        assert_eq!(kv.purge_expired().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn increments_atomically() {
+        let kv = temp_store("incr").await;
+        let guild = GuildId(1);
+
+        assert_eq!(
+            kv.increment("stats", guild, "commands", 1).await.unwrap(),
+            1
+        );
+        assert_eq!(
+            kv.increment("stats", guild, "commands", 4).await.unwrap(),
+            5
+        );
+
+        kv.set("stats", guild, "name", &"kurumi", None)
+            .await
+            .unwrap();
+        assert!(kv.increment("stats", guild, "name", 1).await.is_err());
+    }
+}
@@ -0,0 +1,311 @@
+//! Versioned migrations for the stored data.
+//!
+//! The backend records the schema version it was last migrated to in the
+//! `schema` document. Migrations are compiled into the bot and run in order at
+//! startup, or with the `migrate` subcommand, before any store is opened. Data
+//! written by a newer build is never touched, and the bot refuses to start on it.
+//!
+//! Plugins version their own stores separately, see [`migrate_plugin`], so a
+//! build without a plugin doesn't change the core schema version.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io;
+use thiserror::Error;
+use tracing::info;
+
+use super::Storage;
+
+/// Name of the document that records the schema version.
+const SCHEMA_DOCUMENT: &str = "schema";
+
+/// A change to the layout of the stored data.
+pub struct Migration {
+    /// The schema version after this migration has run.
+    pub version: u32,
+    /// What the migration changes, for logs.
+    pub description: &'static str,
+    /// The store document the migration rewrites, if any.
+    pub document: Option<&'static str>,
+    /// Rewrite the document. Not called when the document doesn't exist yet.
+    pub apply: fn(&mut Value) -> io::Result<()>,
+}
+
+/// All migrations, in order. Versions start at 1 and increase by one.
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    version: 1,
+    description: "Start tracking the schema version",
+    document: None,
+    apply: |_| Ok(()),
+}];
+
+/// The schema version this build of the bot reads and writes.
+pub fn current_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+/// The contents of `schema.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SchemaFile {
+    version: u32,
+    /// Schema versions of plugin stores, by plugin name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    plugins: BTreeMap<String, u32>,
+}
+
+/// An error from checking or migrating the data directory.
+#[derive(Debug, Error)]
+pub enum MigrationError {
+    #[error(
+        "The data directory uses schema version {found}, but this build only supports up to \
+         version {supported}. Update the bot or restore a backup."
+    )]
+    NewerSchema { found: u32, supported: u32 },
+    #[error("Migration to version {version} ({description}) failed: {source}")]
+    Failed {
+        version: u32,
+        description: &'static str,
+        source: io::Error,
+    },
+    #[error("Couldn't read the schema version: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// The schema version of a data directory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SchemaStatus {
+    /// The version the data directory is at.
+    pub version: u32,
+    /// The version this build expects.
+    pub latest: u32,
+}
+
+impl SchemaStatus {
+    /// How many migrations have yet to run.
+    pub fn pending(&self) -> usize {
+        MIGRATIONS
+            .iter()
+            .filter(|migration| migration.version > self.version)
+            .count()
+    }
+}
+
+/// Read the `schema` document. Data without one is at version 0.
+async fn read_schema(storage: &Storage) -> io::Result<SchemaFile> {
+    match storage.backend().load(SCHEMA_DOCUMENT).await? {
+        Some(document) => serde_json::from_value(document)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        None => Ok(SchemaFile::default()),
+    }
+}
+
+/// Write the `schema` document.
+async fn write_schema(storage: &Storage, schema: &SchemaFile) -> io::Result<()> {
+    let document =
+        serde_json::to_value(schema).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    storage.backend().save(SCHEMA_DOCUMENT, &document).await
+}
+
+/// Read the schema version of the stored data.
+pub async fn status(storage: &Storage) -> Result<SchemaStatus, MigrationError> {
+    let schema = read_schema(storage).await?;
+
+    Ok(SchemaStatus {
+        version: schema.version,
+        latest: current_version(),
+    })
+}
+
+/// Read the schema version of a plugin's stores. Plugins that never migrated are at 0.
+pub async fn plugin_version(storage: &Storage, plugin: &str) -> Result<u32, MigrationError> {
+    let schema = read_schema(storage).await?;
+    Ok(schema.plugins.get(plugin).copied().unwrap_or_default())
+}
+
+/// Record the schema version of the stored data.
+async fn write_version(storage: &Storage, version: u32) -> io::Result<()> {
+    let mut schema = read_schema(storage).await?;
+    schema.version = version;
+    write_schema(storage, &schema).await
+}
+
+/// Record the schema version of a plugin's stores.
+async fn write_plugin_version(storage: &Storage, plugin: &str, version: u32) -> io::Result<()> {
+    let mut schema = read_schema(storage).await?;
+    schema.plugins.insert(plugin.to_string(), version);
+    write_schema(storage, &schema).await
+}
+
+/// Run a single migration against its document.
+async fn apply(storage: &Storage, migration: &Migration) -> io::Result<()> {
+    let name = match migration.document {
+        Some(name) => name,
+        None => return Ok(()),
+    };
+
+    if let Some(mut document) = storage.backend().load(name).await? {
+        (migration.apply)(&mut document)?;
+        storage.backend().save(name, &document).await?;
+    }
+    Ok(())
+}
+
+/// Bring the stored data up to the current schema version.
+///
+/// Returns the versions that were applied. The version is recorded after each
+/// migration, so a failed run resumes where it stopped.
+pub async fn migrate(storage: &Storage) -> Result<Vec<u32>, MigrationError> {
+    let status = status(storage).await?;
+    if status.version > status.latest {
+        return Err(MigrationError::NewerSchema {
+            found: status.version,
+            supported: status.latest,
+        });
+    }
+
+    let mut applied = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| m.version > status.version) {
+        info!(
+            "Migrating data to version {}: {}",
+            migration.version, migration.description
+        );
+
+        let failed = |source| MigrationError::Failed {
+            version: migration.version,
+            description: migration.description,
+            source,
+        };
+        apply(storage, migration).await.map_err(failed)?;
+        write_version(storage, migration.version)
+            .await
+            .map_err(failed)?;
+        applied.push(migration.version);
+    }
+
+    Ok(applied)
+}
+
+/// Bring a plugin's stores up to its latest migration.
+///
+/// Works like [`migrate`], but records the version under the plugin's name.
+pub async fn migrate_plugin(
+    storage: &Storage,
+    plugin: &str,
+    migrations: &'static [Migration],
+) -> Result<Vec<u32>, MigrationError> {
+    let version = plugin_version(storage, plugin).await?;
+    let latest = migrations.last().map_or(0, |migration| migration.version);
+    if version > latest {
+        return Err(MigrationError::NewerSchema {
+            found: version,
+            supported: latest,
+        });
+    }
+
+    let mut applied = Vec::new();
+    for migration in migrations.iter().filter(|m| m.version > version) {
+        info!(
+            "Migrating {} data to version {}: {}",
+            plugin, migration.version, migration.description
+        );
+
+        let failed = |source| MigrationError::Failed {
+            version: migration.version,
+            description: migration.description,
+            source,
+        };
+        apply(storage, migration).await.map_err(failed)?;
+        write_plugin_version(storage, plugin, migration.version)
+            .await
+            .map_err(failed)?;
+        applied.push(migration.version);
+    }
+
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_storage(name: &str) -> Storage {
+        let dir =
+            std::env::temp_dir().join(format!("kurumi-migrations-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        Storage::new(dir).unwrap()
+    }
+
+    #[test]
+    fn versions_are_sequential() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version as usize, index + 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn migrates_new_data_once() {
+        let storage = temp_storage("fresh");
+        assert_eq!(status(&storage).await.unwrap().version, 0);
+
+        let applied = migrate(&storage).await.unwrap();
+        assert_eq!(applied.len(), MIGRATIONS.len());
+
+        let status = status(&storage).await.unwrap();
+        assert_eq!(status.version, current_version());
+        assert_eq!(status.pending(), 0);
+        assert!(migrate(&storage).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn refuses_newer_schemas() {
+        let storage = temp_storage("newer");
+        write_version(&storage, current_version() + 1)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            migrate(&storage).await,
+            Err(MigrationError::NewerSchema { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn versions_plugins_separately() {
+        const PLUGIN_MIGRATIONS: &[Migration] = &[Migration {
+            version: 1,
+            description: "Rename the score field",
+            document: Some("plugin_doc"),
+            apply: |document| {
+                if let Some(score) = document.get("score").cloned() {
+                    document["points"] = score;
+                }
+                Ok(())
+            },
+        }];
+
+        let storage = temp_storage("plugin");
+        migrate(&storage).await.unwrap();
+        let document = serde_json::json!({ "score": 5 });
+        storage
+            .backend()
+            .save("plugin_doc", &document)
+            .await
+            .unwrap();
+
+        let applied = migrate_plugin(&storage, "games", PLUGIN_MIGRATIONS)
+            .await
+            .unwrap();
+        assert_eq!(applied, [1]);
+        assert_eq!(plugin_version(&storage, "games").await.unwrap(), 1);
+        assert_eq!(status(&storage).await.unwrap().version, current_version());
+
+        let document = storage.backend().load("plugin_doc").await.unwrap().unwrap();
+        assert_eq!(document["points"], 5);
+        assert!(migrate_plugin(&storage, "games", PLUGIN_MIGRATIONS)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,207 @@
+//! Persistent storage for bot data.
+//!
+//! Each feature keeps its data in a [`JsonStore`], a typed document that lives
+//! in memory and is written back to the [`Backend`] after every change: JSON
+//! files in the data directory by default, or PostgreSQL with the `postgres`
+//! feature. Small values that don't need a store of their own can go in
+//! the shared [`KvStore`]. While the backend is unreachable, changes are kept
+//! in memory until it's back (see [`health`]).
+
+pub mod backend;
+pub mod health;
+pub mod kv;
+pub mod migrations;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod retention;
+
+pub use backend::{Backend, FileBackend};
+pub use health::StorageHealth;
+pub use kv::{KvKey, KvStore};
+#[cfg(feature = "postgres")]
+pub use postgres::PostgresBackend;
+pub use retention::Retention;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::{RwLock, RwLockReadGuard};
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+use crate::models::config::BotConfig;
+
+/// Opens stores on the configured backend.
+#[derive(Clone)]
+pub struct Storage {
+    /// Where documents are kept.
+    backend: Arc<dyn Backend>,
+    /// Whether the backend is reachable, shared by every store.
+    health: Arc<StorageHealth>,
+}
+
+impl Storage {
+    /// Create a new Storage keeping JSON files in the given directory, creating it if needed.
+    pub fn new(data_dir: impl Into<PathBuf>) -> Result<Self, io::Error> {
+        Ok(Self::with_backend(Arc::new(FileBackend::new(data_dir)?)))
+    }
+
+    /// Create a new Storage on any backend.
+    pub fn with_backend(backend: Arc<dyn Backend>) -> Self {
+        Self {
+            backend,
+            health: Arc::new(StorageHealth::new()),
+        }
+    }
+
+    /// Create the Storage selected by the configuration.
+    ///
+    /// Uses PostgreSQL when `database.url` is set, and JSON files in
+    /// `storage.data_dir` otherwise.
+    pub async fn from_config(config: &BotConfig) -> Result<Self, io::Error> {
+        match &config.database.url {
+            #[cfg(feature = "postgres")]
+            Some(url) => {
+                let backend =
+                    PostgresBackend::connect(url, config.database.pool_size, config.database.tls)
+                        .await?;
+                Ok(Self::with_backend(Arc::new(backend)))
+            }
+            #[cfg(not(feature = "postgres"))]
+            Some(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "database.url is set, but the bot was built without the `postgres` feature",
+            )),
+            None => Self::new(&config.storage.data_dir),
+        }
+    }
+
+    /// Get the backend the stores are kept on.
+    pub fn backend(&self) -> &dyn Backend {
+        self.backend.as_ref()
+    }
+
+    /// Get whether the backend is reachable and what's waiting to be saved.
+    pub fn health(&self) -> &Arc<StorageHealth> {
+        &self.health
+    }
+
+    /// Retry saving the changes kept in memory while the backend is unreachable.
+    pub fn spawn_recovery(&self) -> JoinHandle<()> {
+        self.health.clone().spawn(self.backend.clone())
+    }
+
+    /// Open the store with the given name.
+    ///
+    /// While the backend is unreachable, changes are kept in memory and saved
+    /// once it's back.
+    pub async fn open<T>(&self, name: &str) -> Result<JsonStore<T>, io::Error>
+    where
+        T: Serialize + DeserializeOwned + Default,
+    {
+        JsonStore::open(self.backend.clone(), self.health.clone(), name, false).await
+    }
+
+    /// Open a store whose changes must not be lost on a restart.
+    ///
+    /// While the backend is unreachable, changes are refused with an error that
+    /// [`health::is_unavailable`] recognizes.
+    pub async fn open_durable<T>(&self, name: &str) -> Result<JsonStore<T>, io::Error>
+    where
+        T: Serialize + DeserializeOwned + Default,
+    {
+        JsonStore::open(self.backend.clone(), self.health.clone(), name, true).await
+    }
+}
+
+/// A typed document persisted by a [`Backend`].
+pub struct JsonStore<T> {
+    /// Where the document is kept.
+    backend: Arc<dyn Backend>,
+    /// Whether the backend is reachable.
+    health: Arc<StorageHealth>,
+    /// Whether changes are refused rather than deferred while it isn't.
+    durable: bool,
+    /// Name of the document.
+    name: String,
+    /// The in-memory copy of the document.
+    data: RwLock<T>,
+}
+
+impl<T> JsonStore<T>
+where
+    T: Serialize + DeserializeOwned + Default,
+{
+    /// Open a store, starting empty if the document doesn't exist yet.
+    pub async fn open(
+        backend: Arc<dyn Backend>,
+        health: Arc<StorageHealth>,
+        name: &str,
+        durable: bool,
+    ) -> Result<Self, io::Error> {
+        let data = match backend.load(name).await? {
+            Some(document) => serde_json::from_value(document)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            None => T::default(),
+        };
+
+        debug!("Opened store {} from {}", name, backend.describe());
+
+        Ok(Self {
+            backend,
+            health,
+            durable,
+            name: name.to_string(),
+            data: RwLock::new(data),
+        })
+    }
+
+    /// Get read access to the document.
+    pub async fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.data.read().await
+    }
+
+    /// Modify the document and write the result back to the backend.
+    ///
+    /// If the backend is unreachable, the change is kept in memory and saved
+    /// later, or refused and undone for durable stores.
+    pub async fn update<R>(&self, f: impl FnOnce(&mut T) -> R) -> Result<R, io::Error> {
+        if self.durable && self.health.is_degraded() {
+            return Err(health::unavailable());
+        }
+
+        let mut data = self.data.write().await;
+        let before = if self.durable {
+            Some(to_document(&*data)?)
+        } else {
+            None
+        };
+        let result = f(&mut data);
+        let document = to_document(&*data)?;
+
+        if self.health.is_degraded() {
+            self.health.defer(&self.name, document);
+            return Ok(result);
+        }
+        if let Err(e) = self.backend.save(&self.name, &document).await {
+            self.health.fail(&self.name, &e);
+            match before {
+                Some(before) => {
+                    *data = serde_json::from_value(before)
+                        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                    return Err(health::unavailable());
+                }
+                None => self.health.defer(&self.name, document),
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// Turn a document into JSON for the backend.
+fn to_document<T: Serialize>(data: &T) -> Result<serde_json::Value, io::Error> {
+    serde_json::to_value(data).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
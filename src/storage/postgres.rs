@@ -0,0 +1,212 @@
+//! PostgreSQL storage backend.
+//!
+//! Documents are kept as JSONB rows in a single table. Enabled with the
+//! `postgres` feature and selected by setting `database.url` in the config.
+//!
+//! Each store keeps its document in memory and saves the whole of it, so the
+//! last save wins. Only one bot process may use a database at a time; a second
+//! one would overwrite the first one's changes with its own copy.
+//!
+//! Connections use TLS when `database.tls` is on, checking the server's
+//! certificate against the Mozilla root certificates.
+
+use async_trait::async_trait;
+use deadpool_postgres::{Config, Pool, PoolConfig, Runtime};
+use serde_json::Value;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio_postgres::tls::{ChannelBinding, MakeTlsConnect, TlsConnect, TlsStream};
+use tokio_postgres::NoTls;
+use tokio_rustls::rustls::{self, ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName};
+use tracing::debug;
+
+use super::backend::Backend;
+
+/// Creates the documents table if it doesn't exist yet.
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS kurumi_documents (
+    name TEXT PRIMARY KEY,
+    data JSONB NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+)";
+
+/// Convert a database error into the store's error type.
+fn db_error(e: impl std::error::Error + Send + Sync + 'static) -> io::Error {
+    io::Error::other(e)
+}
+
+/// Connects to the database over TLS with rustls.
+#[derive(Clone)]
+struct RustlsConnect {
+    config: Arc<ClientConfig>,
+}
+
+impl RustlsConnect {
+    /// Create a connector that trusts the Mozilla root certificates.
+    fn new() -> Self {
+        let mut roots = RootCertStore::empty();
+        roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+            OwnedTrustAnchor::from_subject_spki_name_constraints(
+                anchor.subject,
+                anchor.spki,
+                anchor.name_constraints,
+            )
+        }));
+        let config = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        Self {
+            config: Arc::new(config),
+        }
+    }
+}
+
+/// Connects a single stream for [`RustlsConnect`].
+struct RustlsConnector {
+    config: Arc<ClientConfig>,
+    server_name: ServerName,
+}
+
+/// A TLS connection to the database.
+struct RustlsStream<S>(tokio_rustls::client::TlsStream<S>);
+
+impl<S> MakeTlsConnect<S> for RustlsConnect
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    type Stream = RustlsStream<S>;
+    type TlsConnect = RustlsConnector;
+    type Error = rustls::client::InvalidDnsNameError;
+
+    fn make_tls_connect(&mut self, domain: &str) -> Result<RustlsConnector, Self::Error> {
+        Ok(RustlsConnector {
+            config: self.config.clone(),
+            server_name: ServerName::try_from(domain)?,
+        })
+    }
+}
+
+impl<S> TlsConnect<S> for RustlsConnector
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    type Stream = RustlsStream<S>;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<RustlsStream<S>>> + Send>>;
+
+    fn connect(self, stream: S) -> Self::Future {
+        let connector = tokio_rustls::TlsConnector::from(self.config);
+        Box::pin(async move {
+            let stream = connector.connect(self.server_name, stream).await?;
+            Ok(RustlsStream(stream))
+        })
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> TlsStream for RustlsStream<S> {
+    fn channel_binding(&self) -> ChannelBinding {
+        ChannelBinding::none()
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncRead for RustlsStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncWrite for RustlsStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
+
+/// Keeps documents in a PostgreSQL table.
+pub struct PostgresBackend {
+    pool: Pool,
+    /// The database URL without credentials, for logs.
+    display_url: String,
+}
+
+impl PostgresBackend {
+    /// Connect to the database and create the documents table if needed.
+    pub async fn connect(url: &str, pool_size: usize, tls: bool) -> io::Result<Self> {
+        let config = Config {
+            url: Some(url.to_string()),
+            pool: Some(PoolConfig::new(pool_size.max(1))),
+            ..Config::default()
+        };
+        let pool = if tls {
+            config.create_pool(Some(Runtime::Tokio1), RustlsConnect::new())
+        } else {
+            config.create_pool(Some(Runtime::Tokio1), NoTls)
+        }
+        .map_err(db_error)?;
+
+        let client = pool.get().await.map_err(db_error)?;
+        client.batch_execute(CREATE_TABLE).await.map_err(db_error)?;
+
+        // Keep the host and database, drop the user and password
+        let display_url = match url.rsplit_once('@') {
+            Some((_, host)) => format!("postgres://…@{}", host),
+            None => url.to_string(),
+        };
+        debug!("Connected to {}", display_url);
+
+        Ok(Self { pool, display_url })
+    }
+}
+
+#[async_trait]
+impl Backend for PostgresBackend {
+    async fn load(&self, name: &str) -> io::Result<Option<Value>> {
+        let client = self.pool.get().await.map_err(db_error)?;
+        let row = client
+            .query_opt(
+                "SELECT data FROM kurumi_documents WHERE name = $1",
+                &[&name],
+            )
+            .await
+            .map_err(db_error)?;
+
+        Ok(row.map(|row| row.get(0)))
+    }
+
+    async fn save(&self, name: &str, document: &Value) -> io::Result<()> {
+        let client = self.pool.get().await.map_err(db_error)?;
+        client
+            .execute(
+                "INSERT INTO kurumi_documents (name, data) VALUES ($1, $2)
+                 ON CONFLICT (name) DO UPDATE SET data = EXCLUDED.data, updated_at = now()",
+                &[&name, document],
+            )
+            .await
+            .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        format!("PostgreSQL at {}", self.display_url)
+    }
+}
@@ -0,0 +1,237 @@
+//! Scheduled purging of old data.
+//!
+//! Moderation cases and modmail transcripts are kept for the number of days set
+//! in `[retention]`, which guild admins can override with `settings`. A
+//! background job purges anything older on an interval.
+
+use serenity::model::id::GuildId;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use super::JsonStore;
+use crate::models::config::RetentionConfig;
+use crate::models::guild_config::{GuildConfig, GuildConfigs};
+use crate::models::moderation::ModerationData;
+use crate::models::modmail::ModmailData;
+use crate::utils::helpers::unix_timestamp;
+
+/// Seconds in a day.
+const DAY: u64 = 24 * 60 * 60;
+
+/// How many records a purge deleted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PurgeReport {
+    /// Moderation cases and decided appeals.
+    pub cases: usize,
+    /// Modmail transcript messages.
+    pub message_logs: usize,
+}
+
+impl PurgeReport {
+    /// Whether nothing was deleted.
+    pub fn is_empty(&self) -> bool {
+        self.cases == 0 && self.message_logs == 0
+    }
+}
+
+/// Get the oldest creation time to keep for a retention period, or `None` to keep
+/// everything.
+fn cutoff(days: u64, now: u64) -> Option<u64> {
+    (days > 0).then(|| now.saturating_sub(days.saturating_mul(DAY)))
+}
+
+/// Purges data older than each guild's retention period.
+pub struct Retention {
+    config: RetentionConfig,
+    guild_configs: Arc<JsonStore<GuildConfigs>>,
+    moderation: Arc<JsonStore<ModerationData>>,
+    modmail: Arc<JsonStore<ModmailData>>,
+}
+
+impl Retention {
+    /// Create the job for the given stores.
+    pub fn new(
+        config: RetentionConfig,
+        guild_configs: Arc<JsonStore<GuildConfigs>>,
+        moderation: Arc<JsonStore<ModerationData>>,
+        modmail: Arc<JsonStore<ModmailData>>,
+    ) -> Self {
+        Self {
+            config,
+            guild_configs,
+            moderation,
+            modmail,
+        }
+    }
+
+    /// Days to keep a guild's cases.
+    fn case_days(&self, guild: &GuildConfig) -> u64 {
+        guild.case_retention.unwrap_or(self.config.cases)
+    }
+
+    /// Days to keep a guild's modmail transcripts.
+    fn message_log_days(&self, guild: &GuildConfig) -> u64 {
+        guild
+            .message_log_retention
+            .unwrap_or(self.config.message_logs)
+    }
+
+    /// Delete everything older than its retention period.
+    pub async fn purge(&self) -> Result<PurgeReport, io::Error> {
+        let now = unix_timestamp();
+        let configs = self.guild_configs.read().await;
+
+        let cases = self
+            .moderation
+            .update(|data| {
+                data.guilds
+                    .iter_mut()
+                    .filter_map(|(guild_id, guild)| {
+                        let days = self.case_days(&configs.get(GuildId(*guild_id)));
+                        Some(guild.purge_cases(cutoff(days, now)?))
+                    })
+                    .sum()
+            })
+            .await?;
+
+        let message_logs = self
+            .modmail
+            .update(|data| {
+                data.open
+                    .values_mut()
+                    .filter_map(|thread| {
+                        let days = self.message_log_days(&configs.get(GuildId(thread.guild_id)));
+                        Some(thread.purge_transcript(cutoff(days, now)?))
+                    })
+                    .sum()
+            })
+            .await?;
+
+        Ok(PurgeReport {
+            cases,
+            message_logs,
+        })
+    }
+
+    /// Run [`Retention::purge`] now and then every `retention.interval` seconds.
+    pub fn spawn(self) -> JoinHandle<()> {
+        let period = Duration::from_secs(self.config.interval.max(60));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                match self.purge().await {
+                    Ok(report) if !report.is_empty() => info!(
+                        "Retention purge deleted {} cases and {} modmail messages",
+                        report.cases, report.message_logs
+                    ),
+                    Ok(_) => {}
+                    Err(e) => error!("Retention purge failed: {}", e),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::moderation::CaseKind;
+    use crate::models::modmail::{ModmailThread, TranscriptEntry};
+    use crate::storage::Storage;
+    use serenity::model::id::UserId;
+
+    async fn temp_retention(name: &str, config: RetentionConfig) -> Retention {
+        let dir =
+            std::env::temp_dir().join(format!("kurumi-retention-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let storage = Storage::new(dir).unwrap();
+        Retention::new(
+            config,
+            Arc::new(storage.open("guild_config").await.unwrap()),
+            Arc::new(storage.open("moderation").await.unwrap()),
+            Arc::new(storage.open("modmail").await.unwrap()),
+        )
+    }
+
+    /// Record a case and backdate it by `days`.
+    fn add_old_case(data: &mut ModerationData, guild: u64, user: u64, kind: CaseKind, days: u64) {
+        let guild = data.guild_mut(GuildId(guild));
+        guild.add_case(UserId(user), kind, None, None, None);
+        let case = guild.cases.get_mut(&user).unwrap().last_mut().unwrap();
+        case.created_at -= days * DAY;
+    }
+
+    #[test]
+    fn zero_days_keeps_everything() {
+        assert_eq!(cutoff(0, 10 * DAY), None);
+        assert_eq!(cutoff(3, 10 * DAY), Some(7 * DAY));
+    }
+
+    #[tokio::test]
+    async fn purges_old_cases_but_keeps_active_punishments() {
+        let retention = temp_retention("cases", RetentionConfig::default()).await;
+        retention
+            .moderation
+            .update(|data| {
+                add_old_case(data, 1, 10, CaseKind::Warning, 400);
+                add_old_case(data, 1, 10, CaseKind::Ban, 400);
+                add_old_case(data, 1, 11, CaseKind::Warning, 10);
+                // Guild 2 keeps cases forever
+                add_old_case(data, 2, 10, CaseKind::Warning, 400);
+            })
+            .await
+            .unwrap();
+        retention
+            .guild_configs
+            .update(|configs| configs.entry(GuildId(2)).case_retention = Some(0))
+            .await
+            .unwrap();
+
+        let report = retention.purge().await.unwrap();
+        assert_eq!(report.cases, 1);
+
+        let data = retention.moderation.read().await;
+        let guild = data.guild(GuildId(1)).unwrap();
+        assert_eq!(guild.cases[&10].len(), 1);
+        assert!(guild.active_punishment(UserId(10)).is_some());
+        assert_eq!(guild.cases[&11].len(), 1);
+        assert_eq!(data.guild(GuildId(2)).unwrap().cases[&10].len(), 1);
+    }
+
+    #[tokio::test]
+    async fn trims_modmail_transcripts() {
+        let retention = temp_retention("modmail", RetentionConfig::default()).await;
+        let now = unix_timestamp();
+        let entry = |days: u64| TranscriptEntry {
+            author_id: 10,
+            author_tag: "user#0001".to_string(),
+            from_staff: false,
+            content: "hello".to_string(),
+            attachments: Vec::new(),
+            sent_at: now - days * DAY,
+        };
+        retention
+            .modmail
+            .update(|data| {
+                data.open.insert(
+                    10,
+                    ModmailThread {
+                        guild_id: 1,
+                        thread_id: 5,
+                        user_id: 10,
+                        opened_at: now - 100 * DAY,
+                        transcript: vec![entry(100), entry(1)],
+                    },
+                );
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(retention.purge().await.unwrap().message_logs, 1);
+        assert_eq!(retention.modmail.read().await.open[&10].transcript.len(), 1);
+    }
+}
@@ -0,0 +1,218 @@
+//! Builders for the gateway models that commands and handlers receive.
+
+use serde_json::{json, Value};
+use serenity::model::application::interaction::Interaction;
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, GuildId, UserId};
+use serenity::model::permissions::Permissions;
+
+/// The timestamp used for every built model.
+const TIMESTAMP: &str = "2024-01-01T00:00:00+00:00";
+
+/// Build a user object.
+fn user(id: UserId, bot: bool) -> Value {
+    json!({
+        "id": id.to_string(),
+        "username": format!("user{}", id),
+        "discriminator": "0001",
+        "avatar": null,
+        "bot": bot,
+    })
+}
+
+/// Builds a [`Message`] as if it was received from the gateway.
+pub struct TestMessage {
+    content: String,
+    author_id: UserId,
+    bot: bool,
+    channel_id: ChannelId,
+    guild_id: Option<GuildId>,
+}
+
+impl TestMessage {
+    /// Start a message from a regular user in a DM channel.
+    pub fn new(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            author_id: UserId(100),
+            bot: false,
+            channel_id: ChannelId(10),
+            guild_id: None,
+        }
+    }
+
+    /// Set the author.
+    pub fn author(mut self, author_id: UserId) -> Self {
+        self.author_id = author_id;
+        self
+    }
+
+    /// Mark the author as a bot.
+    pub fn bot(mut self) -> Self {
+        self.bot = true;
+        self
+    }
+
+    /// Set the channel.
+    pub fn channel(mut self, channel_id: ChannelId) -> Self {
+        self.channel_id = channel_id;
+        self
+    }
+
+    /// Send the message in a guild.
+    pub fn guild(mut self, guild_id: GuildId) -> Self {
+        self.guild_id = Some(guild_id);
+        self
+    }
+
+    /// Build the message.
+    pub fn build(self) -> Message {
+        let mut message = json!({
+            "id": "500",
+            "channel_id": self.channel_id.to_string(),
+            "author": user(self.author_id, self.bot),
+            "content": self.content,
+            "timestamp": TIMESTAMP,
+            "edited_timestamp": null,
+            "tts": false,
+            "mention_everyone": false,
+            "mentions": [],
+            "mention_roles": [],
+            "attachments": [],
+            "embeds": [],
+            "pinned": false,
+            "type": 0,
+        });
+        if let Some(guild_id) = self.guild_id {
+            message["guild_id"] = json!(guild_id.to_string());
+        }
+
+        serde_json::from_value(message).expect("Invalid test message")
+    }
+}
+
+/// Builds a button click or modal submission.
+pub struct TestInteraction {
+    modal: bool,
+    custom_id: String,
+    inputs: Vec<(String, String)>,
+    user_id: UserId,
+    channel_id: ChannelId,
+    guild_id: Option<GuildId>,
+    permissions: Permissions,
+}
+
+impl TestInteraction {
+    /// Start a button click with the given custom ID.
+    pub fn component(custom_id: impl Into<String>) -> Self {
+        Self::new(false, custom_id.into())
+    }
+
+    /// Start a modal submission with the given custom ID.
+    pub fn modal(custom_id: impl Into<String>) -> Self {
+        Self::new(true, custom_id.into())
+    }
+
+    fn new(modal: bool, custom_id: String) -> Self {
+        Self {
+            modal,
+            custom_id,
+            inputs: Vec::new(),
+            user_id: UserId(100),
+            channel_id: ChannelId(10),
+            guild_id: None,
+            permissions: Permissions::empty(),
+        }
+    }
+
+    /// Add a text input value to a modal submission.
+    pub fn input(mut self, custom_id: impl Into<String>, value: impl Into<String>) -> Self {
+        self.inputs.push((custom_id.into(), value.into()));
+        self
+    }
+
+    /// Set the user who interacted.
+    pub fn user(mut self, user_id: UserId) -> Self {
+        self.user_id = user_id;
+        self
+    }
+
+    /// Set the channel.
+    pub fn channel(mut self, channel_id: ChannelId) -> Self {
+        self.channel_id = channel_id;
+        self
+    }
+
+    /// Interact in a guild, as a member with the given permissions.
+    pub fn guild(mut self, guild_id: GuildId, permissions: Permissions) -> Self {
+        self.guild_id = Some(guild_id);
+        self.permissions = permissions;
+        self
+    }
+
+    /// Build the interaction.
+    pub fn build(self) -> Interaction {
+        let data = if self.modal {
+            let rows: Vec<Value> = self
+                .inputs
+                .iter()
+                .map(|(custom_id, value)| {
+                    json!({
+                        "type": 1,
+                        "components": [{ "type": 4, "custom_id": custom_id, "value": value }],
+                    })
+                })
+                .collect();
+            json!({ "custom_id": self.custom_id, "components": rows })
+        } else {
+            json!({ "custom_id": self.custom_id, "component_type": 2, "values": [] })
+        };
+
+        let mut interaction = json!({
+            "id": "600",
+            "application_id": "1",
+            "type": if self.modal { 5 } else { 3 },
+            "data": data,
+            "channel_id": self.channel_id.to_string(),
+            "token": "interaction-token",
+            "version": 1,
+            "locale": "en-US",
+        });
+
+        if !self.modal {
+            interaction["message"] = json!({
+                "id": "501",
+                "channel_id": self.channel_id.to_string(),
+                "author": user(UserId(1), true),
+                "content": "",
+                "timestamp": TIMESTAMP,
+                "edited_timestamp": null,
+                "tts": false,
+                "mention_everyone": false,
+                "mentions": [],
+                "mention_roles": [],
+                "attachments": [],
+                "embeds": [],
+                "pinned": false,
+                "type": 0,
+            });
+        }
+
+        match self.guild_id {
+            Some(guild_id) => {
+                interaction["guild_id"] = json!(guild_id.to_string());
+                interaction["member"] = json!({
+                    "user": user(self.user_id, false),
+                    "roles": [],
+                    "joined_at": TIMESTAMP,
+                    "deaf": false,
+                    "mute": false,
+                    "permissions": self.permissions.bits().to_string(),
+                });
+            }
+            None => interaction["user"] = user(self.user_id, false),
+        }
+
+        serde_json::from_value(interaction).expect("Invalid test interaction")
+    }
+}
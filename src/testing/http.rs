@@ -0,0 +1,338 @@
+//! A fake Discord HTTP API that records requests.
+
+use futures::channel::mpsc::{self, UnboundedReceiver};
+use serde_json::{json, Value};
+use serenity::cache::Cache;
+use serenity::client::bridge::gateway::ShardMessenger;
+use serenity::gateway::InterMessage;
+use serenity::http::HttpBuilder;
+use serenity::model::id::ChannelId;
+use serenity::prelude::*;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+/// A request made to the mock API.
+#[derive(Clone, Debug)]
+pub struct RecordedRequest {
+    /// The HTTP method, such as `POST`.
+    pub method: String,
+    /// The path without the API version prefix, such as `/channels/1/messages`.
+    pub path: String,
+    /// The request body, if it was JSON.
+    pub body: Option<Value>,
+}
+
+/// A message sent or edited through the mock API.
+#[derive(Clone, Debug)]
+pub struct SentMessage {
+    /// The channel the message was sent to.
+    pub channel_id: ChannelId,
+    /// The message content, if set.
+    pub content: Option<String>,
+    /// The message embeds as sent to Discord.
+    pub embeds: Vec<Value>,
+    /// Whether this was an edit of an existing message.
+    pub edited: bool,
+}
+
+impl SentMessage {
+    /// Get the titles of the message's embeds.
+    pub fn embed_titles(&self) -> Vec<&str> {
+        self.embeds
+            .iter()
+            .filter_map(|embed| embed["title"].as_str())
+            .collect()
+    }
+
+    /// Whether the message has an embed with the given title.
+    pub fn has_embed_titled(&self, title: &str) -> bool {
+        self.embed_titles().contains(&title)
+    }
+
+    /// Whether the message content or any embed description contains `text`.
+    pub fn contains(&self, text: &str) -> bool {
+        self.content.as_deref().is_some_and(|c| c.contains(text))
+            || self
+                .embeds
+                .iter()
+                .filter_map(|embed| embed["description"].as_str())
+                .any(|d| d.contains(text))
+    }
+}
+
+/// A canned response for requests matching a method and path.
+struct Route {
+    method: String,
+    path: String,
+    status: u16,
+    body: Value,
+}
+
+/// State shared between the mock server and its handle.
+#[derive(Default)]
+struct Shared {
+    requests: Mutex<Vec<RecordedRequest>>,
+    routes: Mutex<Vec<Route>>,
+    next_id: AtomicU64,
+}
+
+/// A fake Discord API served on localhost.
+///
+/// Message creates and edits are echoed back as messages; other requests get a
+/// `204 No Content` unless a response was set with [`Self::respond`].
+pub struct MockDiscord {
+    url: String,
+    shared: Arc<Shared>,
+    /// Keeps the shard channel open so shard messages don't error.
+    _shard_rx: UnboundedReceiver<InterMessage>,
+    shard: ShardMessenger,
+    cache: Arc<Cache>,
+}
+
+impl MockDiscord {
+    /// Start the mock API on a free local port.
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind mock Discord API");
+        let url = format!(
+            "http://{}",
+            listener.local_addr().expect("No local address")
+        );
+        let shared = Arc::new(Shared {
+            next_id: AtomicU64::new(1000),
+            ..Default::default()
+        });
+
+        let server = shared.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, server.clone()));
+            }
+        });
+
+        let (tx, rx) = mpsc::unbounded();
+
+        Self {
+            url,
+            shared,
+            _shard_rx: rx,
+            shard: ShardMessenger::new(tx),
+            cache: Arc::new(Cache::new()),
+        }
+    }
+
+    /// Create a context with empty shared state.
+    pub fn context(&self) -> Context {
+        self.context_with(TypeMap::new())
+    }
+
+    /// Create a context with the given shared state.
+    pub fn context_with(&self, data: TypeMap) -> Context {
+        let http = HttpBuilder::new("mock-token")
+            .proxy(self.url.as_str())
+            .expect("Invalid mock API URL")
+            .ratelimiter_disabled(true)
+            .application_id(1)
+            .build();
+
+        Context {
+            data: Arc::new(RwLock::new(data)),
+            shard: self.shard.clone(),
+            shard_id: 0,
+            http: Arc::new(http),
+            cache: self.cache.clone(),
+        }
+    }
+
+    /// Respond to requests with a method and path with the given JSON.
+    ///
+    /// The path is matched without the API version prefix, such as
+    /// `/users/@me/channels`. Later responses take precedence.
+    pub fn respond(&self, method: &str, path: &str, status: u16, body: Value) {
+        self.shared.routes.lock().unwrap().push(Route {
+            method: method.to_uppercase(),
+            path: path.to_string(),
+            status,
+            body,
+        });
+    }
+
+    /// Get every request made so far.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.shared.requests.lock().unwrap().clone()
+    }
+
+    /// Get the messages sent or edited so far, in order.
+    pub fn sent_messages(&self) -> Vec<SentMessage> {
+        self.requests()
+            .iter()
+            .filter_map(|request| {
+                let (channel_id, rest) = parse_message_path(&request.path)?;
+                let edited = match (request.method.as_str(), rest) {
+                    ("POST", None) => false,
+                    ("PATCH", Some(_)) => true,
+                    _ => return None,
+                };
+                let body = request.body.clone().unwrap_or_default();
+
+                Some(SentMessage {
+                    channel_id: ChannelId(channel_id),
+                    content: body["content"].as_str().map(String::from),
+                    embeds: body["embeds"].as_array().cloned().unwrap_or_default(),
+                    edited,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Split `/channels/<id>/messages[/<id>]` into the channel and message IDs.
+fn parse_message_path(path: &str) -> Option<(u64, Option<u64>)> {
+    let rest = path.strip_prefix("/channels/")?;
+    let (channel_id, rest) = rest.split_once("/messages")?;
+    let channel_id = channel_id.parse().ok()?;
+
+    match rest.strip_prefix('/') {
+        Some(message_id) => Some((channel_id, Some(message_id.parse().ok()?))),
+        None if rest.is_empty() => Some((channel_id, None)),
+        None => None,
+    }
+}
+
+/// Parse a JSON body, or the `payload_json` part of a multipart body.
+fn parse_body(body: &[u8]) -> Option<Value> {
+    if let Ok(value) = serde_json::from_slice(body) {
+        return Some(value);
+    }
+
+    let body = String::from_utf8_lossy(body);
+    let (_, part) = body.split_once("name=\"payload_json\"")?;
+    let (_, part) = part.split_once("\r\n\r\n")?;
+    let (json, _) = part.split_once("\r\n--")?;
+    serde_json::from_str(json).ok()
+}
+
+/// Serve HTTP/1.1 requests on a connection until it closes.
+async fn serve(stream: TcpStream, shared: Arc<Shared>) {
+    let mut reader = BufReader::new(stream);
+
+    loop {
+        let mut request_line = String::new();
+        match reader.read_line(&mut request_line).await {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {}
+        }
+
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_string();
+        let target = parts.next().unwrap_or_default();
+
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header).await.unwrap_or(0) == 0 {
+                return;
+            }
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+
+        let mut body = vec![0; content_length];
+        if reader.read_exact(&mut body).await.is_err() {
+            return;
+        }
+
+        // Strip the query string and the `/api/v10` prefix
+        let path = target.split('?').next().unwrap_or_default();
+        let path = match path.strip_prefix("/api/") {
+            Some(rest) => rest.find('/').map_or("/", |i| &rest[i..]),
+            None => path,
+        };
+
+        let request = RecordedRequest {
+            method,
+            path: path.to_string(),
+            body: parse_body(&body),
+        };
+        let (status, response) = respond(&shared, &request);
+        shared.requests.lock().unwrap().push(request);
+
+        let body = response.map(|v| v.to_string()).unwrap_or_default();
+        let head = format!(
+            "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+            status,
+            body.len()
+        );
+        let stream = reader.get_mut();
+        if stream.write_all(head.as_bytes()).await.is_err()
+            || stream.write_all(body.as_bytes()).await.is_err()
+        {
+            return;
+        }
+    }
+}
+
+/// Pick the response for a request.
+fn respond(shared: &Shared, request: &RecordedRequest) -> (u16, Option<Value>) {
+    let routes = shared.routes.lock().unwrap();
+    if let Some(route) = routes
+        .iter()
+        .rev()
+        .find(|route| route.method == request.method && route.path == request.path)
+    {
+        return (route.status, Some(route.body.clone()));
+    }
+
+    match (request.method.as_str(), parse_message_path(&request.path)) {
+        ("POST", Some((channel_id, None))) => {
+            let id = shared.next_id.fetch_add(1, Ordering::Relaxed);
+            (
+                200,
+                Some(echo_message(id, channel_id, request.body.as_ref())),
+            )
+        }
+        ("PATCH", Some((channel_id, Some(id)))) => (
+            200,
+            Some(echo_message(id, channel_id, request.body.as_ref())),
+        ),
+        _ => (204, None),
+    }
+}
+
+/// Build a message as Discord would return it for a create or edit request.
+fn echo_message(id: u64, channel_id: u64, body: Option<&Value>) -> Value {
+    let field = |name: &str| body.and_then(|b| b.get(name)).cloned();
+
+    json!({
+        "id": id.to_string(),
+        "channel_id": channel_id.to_string(),
+        "author": {
+            "id": "1",
+            "username": "Bot",
+            "discriminator": "0000",
+            "avatar": null,
+            "bot": true,
+        },
+        "content": field("content").unwrap_or_else(|| json!("")),
+        "timestamp": "2024-01-01T00:00:00+00:00",
+        "edited_timestamp": null,
+        "tts": false,
+        "mention_everyone": false,
+        "mentions": [],
+        "mention_roles": [],
+        "attachments": [],
+        "embeds": field("embeds").unwrap_or_else(|| json!([])),
+        "pinned": false,
+        "type": 0,
+    })
+}
@@ -0,0 +1,115 @@
+//! Test helpers for running commands and handlers without connecting to Discord.
+//!
+//! [`MockDiscord`] serves a fake Discord HTTP API on localhost and hands out
+//! `Context`s whose HTTP client talks to it. Requests are recorded so tests can
+//! assert on the messages and embeds a command sent:
+//!
+//! ```ignore
+//! let discord = MockDiscord::start().await;
+//! let ctx = discord.context();
+//! let msg = TestMessage::new("!ping").build();
+//!
+//! PingCommand.execute(command_context(&ctx, &msg)).await?;
+//! assert!(discord.sent_messages()[1].has_embed_titled("🏓 Pong!"));
+//! ```
+//!
+//! Enabled in the crate's own tests and with the `testing` feature.
+
+mod builders;
+mod http;
+
+pub use builders::{TestInteraction, TestMessage};
+pub use http::{MockDiscord, RecordedRequest, SentMessage};
+
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+
+use crate::framework::command_handler::{CommandContext, DryRun};
+use crate::framework::state::State;
+
+/// Dry-run state for contexts built by [`command_context`].
+static NO_DRY_RUN: DryRun = DryRun::disabled();
+
+/// Build a command context for a message, taking every word after the first as an argument.
+pub fn command_context<'a>(ctx: &'a Context, msg: &'a Message) -> CommandContext<'a> {
+    CommandContext {
+        ctx,
+        msg,
+        args: msg
+            .content
+            .split_whitespace()
+            .skip(1)
+            .map(String::from)
+            .collect(),
+        data: State::from(ctx),
+        dry_run: &NO_DRY_RUN,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use serenity::model::application::interaction::Interaction;
+    use serenity::model::id::{ChannelId, GuildId, UserId};
+    use serenity::model::permissions::Permissions;
+
+    #[test]
+    fn builds_guild_message() {
+        let msg = TestMessage::new("!notes 42")
+            .author(UserId(7))
+            .guild(GuildId(3))
+            .build();
+
+        assert_eq!(msg.author.id, UserId(7));
+        assert_eq!(msg.guild_id, Some(GuildId(3)));
+        assert_eq!(msg.content, "!notes 42");
+    }
+
+    #[test]
+    fn builds_component_and_modal_interactions() {
+        let component = TestInteraction::component("appeal:start:3")
+            .guild(GuildId(3), Permissions::BAN_MEMBERS)
+            .build();
+        match component {
+            Interaction::MessageComponent(component) => {
+                assert_eq!(component.data.custom_id, "appeal:start:3");
+                let permissions = component.member.and_then(|m| m.permissions);
+                assert_eq!(permissions, Some(Permissions::BAN_MEMBERS));
+            }
+            _ => panic!("expected a component interaction"),
+        }
+
+        let modal = TestInteraction::modal("appeal:submit:3")
+            .input("appeal", "Please unban me")
+            .build();
+        match modal {
+            Interaction::ModalSubmit(modal) => {
+                assert_eq!(modal.user.id, UserId(100));
+                assert_eq!(modal.data.components.len(), 1);
+            }
+            _ => panic!("expected a modal submission"),
+        }
+    }
+
+    #[tokio::test]
+    async fn records_requests_and_canned_responses() {
+        let discord = MockDiscord::start().await;
+        let ctx = discord.context();
+
+        ChannelId(20)
+            .send_message(&ctx.http, |m| m.embed(|e| e.title("Hello")))
+            .await
+            .unwrap();
+        assert!(discord.sent_messages()[0].has_embed_titled("Hello"));
+
+        discord.respond(
+            "DELETE",
+            "/channels/20/messages/5",
+            404,
+            json!({ "message": "Unknown Message", "code": 10008 }),
+        );
+        assert!(ChannelId(20).delete_message(&ctx.http, 5).await.is_err());
+        assert_eq!(discord.requests().last().unwrap().method, "DELETE");
+    }
+}
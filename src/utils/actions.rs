@@ -0,0 +1,394 @@
+//! Destructive moderation actions that respect dry-run mode.
+//!
+//! Commands should use these instead of calling Serenity directly, so that
+//! `simulate <command>` can show what a command would do without doing it, and
+//! every action that is performed ends up in the audit log.
+
+use serenity::model::channel::{PermissionOverwrite, PermissionOverwriteType};
+use serenity::model::id::{ChannelId, GuildId, MessageId, RoleId, ScheduledEventId, UserId};
+use serenity::model::timestamp::Timestamp;
+
+use crate::framework::command_handler::{CommandContext, CommandResult};
+use crate::models::audit::{self, AuditEvent, AuditSource};
+use crate::utils::duration::{timestamp, TimestampStyle};
+use crate::utils::rest::{self, Priority};
+
+/// Record a performed action in the audit log, with the command's author as the actor.
+async fn audit(
+    ctx: &CommandContext<'_>,
+    guild_id: Option<GuildId>,
+    action: String,
+    reason: Option<&str>,
+) {
+    let event = AuditEvent {
+        guild_id,
+        actor_id: Some(ctx.msg.author.id),
+        source: AuditSource::Command,
+        action,
+        reason: reason.map(str::to_string),
+    };
+    audit::record(ctx.ctx, event).await;
+}
+
+/// Ban a user, deleting their messages from the last `delete_days` days.
+pub async fn ban(
+    ctx: &CommandContext<'_>,
+    guild_id: GuildId,
+    user_id: UserId,
+    delete_days: u8,
+    reason: &str,
+) -> CommandResult {
+    let action = format!(
+        "Ban <@{}>, deleting {} days of messages",
+        user_id, delete_days
+    );
+    if ctx.dry_run.record(format!("{} ({})", action, reason)) {
+        return Ok(());
+    }
+
+    rest::call_with(ctx.ctx, Priority::Moderation, "ban", || {
+        guild_id.ban_with_reason(&ctx.ctx.http, user_id, delete_days, reason)
+    })
+    .await?;
+    audit(ctx, Some(guild_id), action, Some(reason)).await;
+    Ok(())
+}
+
+/// Unban a user.
+pub async fn unban(ctx: &CommandContext<'_>, guild_id: GuildId, user_id: UserId) -> CommandResult {
+    let action = format!("Unban <@{}>", user_id);
+    if ctx.dry_run.record(action.clone()) {
+        return Ok(());
+    }
+
+    rest::call_with(ctx.ctx, Priority::Moderation, "unban", || {
+        guild_id.unban(&ctx.ctx.http, user_id)
+    })
+    .await?;
+    audit(ctx, Some(guild_id), action, None).await;
+    Ok(())
+}
+
+/// Kick a member.
+pub async fn kick(
+    ctx: &CommandContext<'_>,
+    guild_id: GuildId,
+    user_id: UserId,
+    reason: &str,
+) -> CommandResult {
+    let action = format!("Kick <@{}>", user_id);
+    if ctx.dry_run.record(format!("{} ({})", action, reason)) {
+        return Ok(());
+    }
+
+    rest::call_with(ctx.ctx, Priority::Moderation, "kick", || {
+        guild_id.kick_with_reason(&ctx.ctx.http, user_id, reason)
+    })
+    .await?;
+    audit(ctx, Some(guild_id), action, Some(reason)).await;
+    Ok(())
+}
+
+/// Time out a member until the given time, or lift their timeout with `None`.
+pub async fn timeout(
+    ctx: &CommandContext<'_>,
+    guild_id: GuildId,
+    user_id: UserId,
+    until: Option<Timestamp>,
+) -> CommandResult {
+    let action = match until {
+        Some(until) => format!(
+            "Time out <@{}> until {}",
+            user_id,
+            timestamp(until.unix_timestamp() as u64, TimestampStyle::ShortDateTime)
+        ),
+        None => format!("Remove the timeout of <@{}>", user_id),
+    };
+    if ctx.dry_run.record(action.clone()) {
+        return Ok(());
+    }
+
+    rest::call_with(ctx.ctx, Priority::Moderation, "timeout", || {
+        guild_id.edit_member(&ctx.ctx.http, user_id, |m| match until {
+            Some(until) => m.disable_communication_until_datetime(until),
+            None => m.enable_communication(),
+        })
+    })
+    .await?;
+    audit(ctx, Some(guild_id), action, None).await;
+    Ok(())
+}
+
+/// Give a member a role.
+pub async fn add_role(
+    ctx: &CommandContext<'_>,
+    guild_id: GuildId,
+    user_id: UserId,
+    role_id: RoleId,
+    reason: Option<&str>,
+) -> CommandResult {
+    let action = format!("Give <@{}> the <@&{}> role", user_id, role_id);
+    if ctx.dry_run.record(action.clone()) {
+        return Ok(());
+    }
+
+    rest::call_with(ctx.ctx, Priority::Moderation, "add_role", || {
+        ctx.ctx
+            .http
+            .add_member_role(guild_id.0, user_id.0, role_id.0, reason)
+    })
+    .await?;
+    audit(ctx, Some(guild_id), action, reason).await;
+    Ok(())
+}
+
+/// Take a role from a member.
+pub async fn remove_role(
+    ctx: &CommandContext<'_>,
+    guild_id: GuildId,
+    user_id: UserId,
+    role_id: RoleId,
+    reason: Option<&str>,
+) -> CommandResult {
+    let action = format!("Remove the <@&{}> role from <@{}>", role_id, user_id);
+    if ctx.dry_run.record(action.clone()) {
+        return Ok(());
+    }
+
+    rest::call_with(ctx.ctx, Priority::Moderation, "remove_role", || {
+        ctx.ctx
+            .http
+            .remove_member_role(guild_id.0, user_id.0, role_id.0, reason)
+    })
+    .await?;
+    audit(ctx, Some(guild_id), action, reason).await;
+    Ok(())
+}
+
+/// Unpin a message.
+pub async fn unpin(
+    ctx: &CommandContext<'_>,
+    channel_id: ChannelId,
+    message_id: MessageId,
+) -> CommandResult {
+    let action = format!("Unpin message {} in <#{}>", message_id, channel_id);
+    if ctx.dry_run.record(action.clone()) {
+        return Ok(());
+    }
+
+    rest::call_with(ctx.ctx, Priority::Moderation, "unpin", || {
+        channel_id.unpin(&ctx.ctx.http, message_id)
+    })
+    .await?;
+    audit(ctx, ctx.msg.guild_id, action, None).await;
+    Ok(())
+}
+
+/// Delete messages from a channel, in bulk where possible.
+pub async fn purge(
+    ctx: &CommandContext<'_>,
+    channel_id: ChannelId,
+    message_ids: &[MessageId],
+) -> CommandResult {
+    let action = format!("Delete {} messages in <#{}>", message_ids.len(), channel_id);
+    if ctx.dry_run.record(action.clone()) {
+        return Ok(());
+    }
+
+    // Bulk deletes take between 2 and 100 messages at a time
+    for chunk in message_ids.chunks(100) {
+        match chunk {
+            [message_id] => {
+                rest::call_with(ctx.ctx, Priority::Moderation, "delete_message", || {
+                    channel_id.delete_message(&ctx.ctx.http, message_id)
+                })
+                .await?
+            }
+            _ => {
+                rest::call_with(ctx.ctx, Priority::Moderation, "delete_messages", || {
+                    channel_id.delete_messages(&ctx.ctx.http, chunk)
+                })
+                .await?
+            }
+        }
+    }
+    audit(ctx, ctx.msg.guild_id, action, None).await;
+    Ok(())
+}
+
+/// Delete a single message, like a post whose group or event is over.
+///
+/// Cleanup like this isn't audited on its own; callers record the change it's
+/// part of.
+pub async fn delete_message(
+    ctx: &CommandContext<'_>,
+    channel_id: ChannelId,
+    message_id: MessageId,
+) -> CommandResult {
+    if ctx.dry_run.record(format!(
+        "Delete message {} in <#{}>",
+        message_id, channel_id
+    )) {
+        return Ok(());
+    }
+
+    rest::call(ctx.ctx, "delete_message", || {
+        channel_id.delete_message(&ctx.ctx.http, message_id)
+    })
+    .await?;
+    Ok(())
+}
+
+/// Delete a channel. Not audited, like [`delete_message`].
+pub async fn delete_channel(ctx: &CommandContext<'_>, channel_id: ChannelId) -> CommandResult {
+    if ctx
+        .dry_run
+        .record(format!("Delete the <#{}> channel", channel_id))
+    {
+        return Ok(());
+    }
+
+    rest::call(ctx.ctx, "delete_channel", || {
+        channel_id.delete(&ctx.ctx.http)
+    })
+    .await?;
+    Ok(())
+}
+
+/// Delete a role. Not audited, like [`delete_message`].
+pub async fn delete_role(
+    ctx: &CommandContext<'_>,
+    guild_id: GuildId,
+    role_id: RoleId,
+) -> CommandResult {
+    if ctx
+        .dry_run
+        .record(format!("Delete the <@&{}> role", role_id))
+    {
+        return Ok(());
+    }
+
+    rest::call(ctx.ctx, "delete_role", || {
+        guild_id.delete_role(&ctx.ctx.http, role_id)
+    })
+    .await?;
+    Ok(())
+}
+
+/// Delete one of a guild's scheduled events. Not audited, like [`delete_message`].
+pub async fn delete_scheduled_event(
+    ctx: &CommandContext<'_>,
+    guild_id: GuildId,
+    event_id: ScheduledEventId,
+) -> CommandResult {
+    if ctx
+        .dry_run
+        .record(format!("Delete the server event {}", event_id))
+    {
+        return Ok(());
+    }
+
+    rest::call(ctx.ctx, "delete_scheduled_event", || {
+        guild_id.delete_scheduled_event(&ctx.ctx.http, event_id)
+    })
+    .await?;
+    Ok(())
+}
+
+/// How an overwrite's target is written in a recorded action.
+fn overwrite_target(kind: PermissionOverwriteType) -> String {
+    match kind {
+        PermissionOverwriteType::Member(user_id) => format!("<@{}>", user_id),
+        PermissionOverwriteType::Role(role_id) => format!("<@&{}>", role_id),
+        _ => "an unknown target".to_string(),
+    }
+}
+
+/// Set a permission overwrite on a channel.
+///
+/// Overwrites are usually changed many at a time, so they aren't audited one by
+/// one; callers record the change they're part of.
+pub async fn set_overwrite(
+    ctx: &CommandContext<'_>,
+    channel_id: ChannelId,
+    overwrite: &PermissionOverwrite,
+) -> CommandResult {
+    let action = format!(
+        "Set the overwrite for {} in <#{}>",
+        overwrite_target(overwrite.kind),
+        channel_id
+    );
+    if ctx.dry_run.record(action) {
+        return Ok(());
+    }
+
+    rest::call(ctx.ctx, "create_permission", || {
+        channel_id.create_permission(&ctx.ctx.http, overwrite)
+    })
+    .await?;
+    Ok(())
+}
+
+/// Remove a permission overwrite from a channel. Not audited, like
+/// [`set_overwrite`].
+pub async fn delete_overwrite(
+    ctx: &CommandContext<'_>,
+    channel_id: ChannelId,
+    kind: PermissionOverwriteType,
+) -> CommandResult {
+    let action = format!(
+        "Remove the overwrite for {} in <#{}>",
+        overwrite_target(kind),
+        channel_id
+    );
+    if ctx.dry_run.record(action) {
+        return Ok(());
+    }
+
+    rest::call(ctx.ctx, "delete_permission", || {
+        channel_id.delete_permission(&ctx.ctx.http, kind)
+    })
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framework::command_handler::DryRun;
+    use crate::framework::state::State;
+    use crate::testing::{MockDiscord, TestMessage};
+
+    #[tokio::test]
+    async fn dry_run_records_instead_of_acting() {
+        let discord = MockDiscord::start().await;
+        let ctx = discord.context();
+        let msg = TestMessage::new("!simulate ban").guild(GuildId(1)).build();
+        let dry_run = DryRun::enabled();
+        let cmd_ctx = CommandContext {
+            ctx: &ctx,
+            msg: &msg,
+            args: Vec::new(),
+            data: State::from(&ctx),
+            dry_run: &dry_run,
+        };
+
+        ban(&cmd_ctx, GuildId(1), UserId(2), 1, "spam")
+            .await
+            .unwrap();
+        purge(&cmd_ctx, ChannelId(3), &[MessageId(4), MessageId(5)])
+            .await
+            .unwrap();
+        delete_overwrite(
+            &cmd_ctx,
+            ChannelId(3),
+            PermissionOverwriteType::Role(RoleId(6)),
+        )
+        .await
+        .unwrap();
+
+        assert!(discord.requests().is_empty());
+        assert_eq!(dry_run.actions().len(), 3);
+        assert!(dry_run.actions()[0].contains("Ban <@2>"));
+    }
+}
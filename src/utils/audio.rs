@@ -0,0 +1,94 @@
+//! Reading uploaded audio clips.
+//!
+//! Clips are probed before they're stored, so only files that really are audio
+//! in a supported format are kept, and their length is known without trusting
+//! the file name.
+
+use std::io::Cursor;
+use std::time::Duration;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use symphonia::core::units::Time;
+
+/// File extensions of the formats clips can be in.
+pub const EXTENSIONS: &[&str] = &["mp3", "ogg", "wav", "flac"];
+
+/// Convert a symphonia time to a duration.
+fn duration(time: Time) -> Duration {
+    Duration::from_secs(time.seconds) + Duration::from_secs_f64(time.frac)
+}
+
+/// Check that a file is audio in one of [`EXTENSIONS`] and get its length.
+pub fn probe(data: Vec<u8>, extension: &str) -> Result<Duration, String> {
+    let mut hint = Hint::new();
+    hint.with_extension(extension);
+    let source = MediaSourceStream::new(Box::new(Cursor::new(data)), Default::default());
+    let mut probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            source,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|_| "That isn't an audio file I can read.".to_string())?;
+
+    let (track_id, time_base, frames) = match probed.format.default_track() {
+        Some(track) => (
+            track.id,
+            track.codec_params.time_base,
+            track.codec_params.n_frames,
+        ),
+        None => return Err("That file has no audio in it.".to_string()),
+    };
+    let time_base = time_base.ok_or("That file doesn't say how long it is.")?;
+    if let Some(frames) = frames {
+        return Ok(duration(time_base.calc_time(frames)));
+    }
+
+    // Streams without a frame count in their header, like most MP3s, are measured
+    // by adding up their packets
+    let mut frames = 0;
+    while let Ok(packet) = probed.format.next_packet() {
+        if packet.track_id() == track_id {
+            frames += packet.dur;
+        }
+    }
+    if frames == 0 {
+        return Err("That file has no audio in it.".to_string());
+    }
+    Ok(duration(time_base.calc_time(frames)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A silent 16-bit mono WAV file.
+    fn wav(sample_rate: u32, samples: u32) -> Vec<u8> {
+        let data_len = samples * 2;
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&sample_rate.to_le_bytes());
+        wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        wav.resize(wav.len() + data_len as usize, 0);
+        wav
+    }
+
+    #[test]
+    fn measures_clips() {
+        let length = probe(wav(8000, 12000), "wav").unwrap();
+        assert_eq!(length.as_millis(), 1500);
+        assert!(probe(b"definitely not audio".to_vec(), "mp3").is_err());
+    }
+}
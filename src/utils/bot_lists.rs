@@ -0,0 +1,213 @@
+//! Posting server and shard counts to bot lists.
+//!
+//! Each bot list with an API key in the secrets provider (`topgg_token` or
+//! `dbl_token`, see [`crate::utils::secrets`]) gets the counts every
+//! `[bot_lists] interval` seconds. Failed posts are retried with exponential backoff,
+//! honouring `Retry-After` when a list rate limits the bot.
+
+use reqwest::header::{AUTHORIZATION, RETRY_AFTER};
+use reqwest::StatusCode;
+use serde_json::json;
+use serenity::cache::Cache;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::models::config::BotListsConfig;
+use crate::models::votes::VoteSite;
+use crate::utils::rest_usage::RestUsage;
+use crate::utils::secrets;
+
+/// How long to wait after starting before the first post, so the cache has the
+/// guilds.
+const STARTUP_DELAY: Duration = Duration::from_secs(60);
+
+/// First retry delay after a failed post. It doubles with each failure in a row.
+const RETRY_BASE: Duration = Duration::from_secs(30);
+
+/// How long to wait for a bot list to respond.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// The counts posted to bot lists.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BotStats {
+    pub guilds: usize,
+    pub shards: u64,
+}
+
+/// Why a post failed.
+#[derive(Debug)]
+struct PostError {
+    message: String,
+    /// How long the bot list asked to wait, when it rate limited the bot.
+    retry_after: Option<Duration>,
+}
+
+/// Posts the bot's stats to one bot list.
+pub struct StatsPoster {
+    site: VoteSite,
+    token: String,
+    interval: Duration,
+    cache: Arc<Cache>,
+    client: reqwest::Client,
+    usage: Arc<RestUsage>,
+}
+
+impl StatsPoster {
+    /// Create a poster for each bot list with an API key set.
+    pub fn from_secrets(
+        config: &BotListsConfig,
+        cache: Arc<Cache>,
+        usage: Arc<RestUsage>,
+    ) -> Vec<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+
+        VoteSite::ALL
+            .into_iter()
+            .filter_map(|site| {
+                let token = secrets::get(secret_name(site))?;
+                Some(Self {
+                    site,
+                    token,
+                    interval: Duration::from_secs(config.interval.max(60)),
+                    cache: cache.clone(),
+                    client: client.clone(),
+                    usage: usage.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Post the stats now and then on the interval, backing off after failures.
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            tokio::time::sleep(STARTUP_DELAY).await;
+            let mut failures = 0;
+            loop {
+                self.usage.wait_for_headroom("stats posting").await;
+                let delay = match self.post().await {
+                    Ok(stats) => {
+                        if failures > 0 {
+                            info!("Posting stats to {} works again", self.site);
+                        }
+                        debug!(
+                            "Posted {} servers and {} shards to {}",
+                            stats.guilds, stats.shards, self.site
+                        );
+                        failures = 0;
+                        self.interval
+                    }
+                    Err(e) => {
+                        failures += 1;
+                        let delay = e
+                            .retry_after
+                            .unwrap_or_else(|| backoff(failures, self.interval));
+                        warn!(
+                            "Failed to post stats to {} ({} in a row), retrying in {}s: {}",
+                            self.site,
+                            failures,
+                            delay.as_secs(),
+                            e.message
+                        );
+                        delay
+                    }
+                };
+                tokio::time::sleep(delay).await;
+            }
+        })
+    }
+
+    /// The bot's current stats.
+    fn stats(&self) -> BotStats {
+        BotStats {
+            guilds: self.cache.guild_count(),
+            shards: self.cache.shard_count(),
+        }
+    }
+
+    /// Post the current stats once.
+    async fn post(&self) -> Result<BotStats, PostError> {
+        let bot_id = self.cache.current_user_id();
+        if bot_id.0 == 0 {
+            return Err(PostError {
+                message: "not connected to Discord yet".to_string(),
+                retry_after: None,
+            });
+        }
+
+        let stats = self.stats();
+        let (url, body) = match self.site {
+            VoteSite::Topgg => (
+                format!("https://top.gg/api/bots/{}/stats", bot_id),
+                json!({ "server_count": stats.guilds, "shard_count": stats.shards }),
+            ),
+            VoteSite::Dbl => (
+                format!("https://discordbotlist.com/api/v1/bots/{}/stats", bot_id),
+                json!({ "guilds": stats.guilds }),
+            ),
+        };
+
+        let response = self
+            .client
+            .post(url)
+            .header(AUTHORIZATION, &self.token)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| PostError {
+                message: e.without_url().to_string(),
+                retry_after: None,
+            })?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(stats);
+        }
+        let retry_after = (status == StatusCode::TOO_MANY_REQUESTS)
+            .then(|| response.headers().get(RETRY_AFTER))
+            .flatten()
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<f64>().ok())
+            .map(|seconds| Duration::from_secs_f64(seconds.max(1.0)));
+        Err(PostError {
+            message: format!("HTTP {}", status),
+            retry_after,
+        })
+    }
+}
+
+/// The secret holding a bot list's API key.
+fn secret_name(site: VoteSite) -> &'static str {
+    match site {
+        VoteSite::Topgg => "topgg_token",
+        VoteSite::Dbl => "dbl_token",
+    }
+}
+
+/// How long to wait after `failures` failed posts in a row: [`RETRY_BASE`] doubled
+/// for each failure, but never longer than the posting interval.
+fn backoff(failures: u32, interval: Duration) -> Duration {
+    let factor = 2u32.saturating_pow(failures.saturating_sub(1));
+    RETRY_BASE
+        .saturating_mul(factor)
+        .min(interval.max(RETRY_BASE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_off_up_to_the_interval() {
+        let interval = Duration::from_secs(1800);
+        assert_eq!(backoff(1, interval), Duration::from_secs(30));
+        assert_eq!(backoff(2, interval), Duration::from_secs(60));
+        assert_eq!(backoff(4, interval), Duration::from_secs(240));
+        assert_eq!(backoff(10, interval), interval);
+        assert_eq!(backoff(100, interval), interval);
+    }
+}
@@ -0,0 +1,77 @@
+//! A small time-limited cache for results from outside APIs.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Values by key, each kept for a fixed time after it was inserted. Expired
+/// entries are dropped whenever something is inserted, and once the cache is
+/// full the oldest entry makes room for a new one.
+pub struct TtlCache<V> {
+    ttl: Duration,
+    capacity: usize,
+    entries: Mutex<HashMap<String, (Instant, V)>>,
+}
+
+impl<V: Clone> TtlCache<V> {
+    /// Create an empty cache whose entries live for `ttl`, holding at most
+    /// `capacity` of them.
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get a value that hasn't expired yet.
+    pub async fn get(&self, key: &str) -> Option<V> {
+        let entries = self.entries.lock().await;
+        entries
+            .get(key)
+            .filter(|(inserted, _)| inserted.elapsed() < self.ttl)
+            .map(|(_, value)| value.clone())
+    }
+
+    /// Insert a value, replacing any under the same key.
+    pub async fn insert(&self, key: String, value: V) {
+        let mut entries = self.entries.lock().await;
+        entries.retain(|_, (inserted, _)| inserted.elapsed() < self.ttl);
+        while entries.len() >= self.capacity && !entries.contains_key(&key) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, (inserted, _))| *inserted)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(oldest) => entries.remove(&oldest),
+                None => return,
+            };
+        }
+        entries.insert(key, (Instant::now(), value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn full_cache_drops_the_oldest_entry() {
+        let cache = TtlCache::new(Duration::from_secs(60), 2);
+        cache.insert("a".to_string(), 1).await;
+        cache.insert("b".to_string(), 2).await;
+        cache.insert("a".to_string(), 3).await;
+        cache.insert("c".to_string(), 4).await;
+
+        assert_eq!(cache.get("a").await, Some(3));
+        assert_eq!(cache.get("b").await, None);
+        assert_eq!(cache.get("c").await, Some(4));
+    }
+
+    #[tokio::test]
+    async fn zero_capacity_keeps_nothing() {
+        let cache = TtlCache::new(Duration::from_secs(60), 0);
+        cache.insert("a".to_string(), 1).await;
+        assert_eq!(cache.get("a").await, None);
+    }
+}
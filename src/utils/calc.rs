@@ -0,0 +1,434 @@
+//! A small calculator for arithmetic expressions such as `2 * (3 + sqrt(16))`.
+//!
+//! Expressions are tokenized and evaluated by a recursive descent parser, so
+//! nothing is ever run as code. Inputs are limited in length and nesting so a
+//! pathological expression can't use up the stack or CPU.
+
+use std::f64::consts;
+use thiserror::Error;
+
+/// The longest expression accepted, in characters.
+pub const MAX_LENGTH: usize = 500;
+
+/// How deeply parentheses, function calls and signs may nest.
+pub const MAX_DEPTH: usize = 32;
+
+/// The largest number whose factorial can be taken without overflowing.
+const MAX_FACTORIAL: f64 = 170.0;
+
+/// Integers above this can't all be represented exactly, so results past it are
+/// approximate.
+pub const MAX_EXACT: f64 = 9_007_199_254_740_992.0;
+
+/// An error from evaluating an expression.
+#[derive(Debug, Error, PartialEq)]
+pub enum CalcError {
+    #[error("No expression given.")]
+    Empty,
+    #[error(
+        "That expression is too long. Keep it under {} characters.",
+        MAX_LENGTH
+    )]
+    TooLong,
+    #[error("That expression is nested too deeply.")]
+    TooDeep,
+    #[error("Unexpected `{0}`.")]
+    Unexpected(String),
+    #[error("The expression ends too early.")]
+    UnexpectedEnd,
+    #[error("`{0}` isn't a valid number.")]
+    InvalidNumber(String),
+    #[error("Unknown function or constant `{0}`.")]
+    Unknown(String),
+    #[error("`{0}` takes {1}.")]
+    Arguments(String, &'static str),
+    #[error("Can't divide by zero.")]
+    DivideByZero,
+    #[error("Factorials only work on whole numbers from 0 to 170.")]
+    Factorial,
+    #[error("The result is too large.")]
+    Overflow,
+    #[error("The result is undefined.")]
+    Undefined,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Op(char),
+}
+
+/// Split an expression into numbers, names and operators.
+fn tokenize(input: &str) -> Result<Vec<Token>, CalcError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            let digit = |c: char| c.is_ascii_digit() || matches!(c, '.' | '_');
+            while i < chars.len() && digit(chars[i]) {
+                i += 1;
+            }
+            // An exponent, as in `1.5e-3`
+            if i < chars.len() && matches!(chars[i], 'e' | 'E') {
+                let sign = usize::from(matches!(chars.get(i + 1), Some('+' | '-')));
+                if chars.get(i + 1 + sign).is_some_and(char::is_ascii_digit) {
+                    i += 1 + sign;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text
+                .replace('_', "")
+                .parse()
+                .map_err(|_| CalcError::InvalidNumber(text.clone()))?;
+            tokens.push(Token::Number(value));
+        } else if c.is_alphabetic() || c == 'π' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let name: String = chars[start..i].iter().collect();
+            tokens.push(Token::Name(name.to_lowercase()));
+        } else {
+            let op = match c {
+                '×' | '∙' | '·' => '*',
+                '÷' => '/',
+                '−' => '-',
+                '*' if chars.get(i + 1) == Some(&'*') => {
+                    i += 1;
+                    '^'
+                }
+                '+' | '-' | '*' | '/' | '%' | '^' | '(' | ')' | ',' | '!' => c,
+                _ => return Err(CalcError::Unexpected(c.to_string())),
+            };
+            tokens.push(Token::Op(op));
+            i += 1;
+        }
+    }
+    Ok(tokens)
+}
+
+/// The value of a named constant.
+fn constant(name: &str) -> Option<f64> {
+    let value = match name {
+        "pi" | "π" => consts::PI,
+        "tau" => consts::TAU,
+        "e" => consts::E,
+        "phi" => (1.0 + 5f64.sqrt()) / 2.0,
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Call a named function.
+fn call(name: &str, args: &[f64]) -> Result<f64, CalcError> {
+    let one = |f: fn(f64) -> f64| match args {
+        [x] => Ok(f(*x)),
+        _ => Err(CalcError::Arguments(name.to_string(), "one number")),
+    };
+    match name {
+        "sqrt" => one(f64::sqrt),
+        "cbrt" => one(f64::cbrt),
+        "abs" => one(f64::abs),
+        "sin" => one(f64::sin),
+        "cos" => one(f64::cos),
+        "tan" => one(f64::tan),
+        "asin" => one(f64::asin),
+        "acos" => one(f64::acos),
+        "atan" => one(f64::atan),
+        "sinh" => one(f64::sinh),
+        "cosh" => one(f64::cosh),
+        "tanh" => one(f64::tanh),
+        "ln" => one(f64::ln),
+        "log2" => one(f64::log2),
+        "log10" => one(f64::log10),
+        "exp" => one(f64::exp),
+        "floor" => one(f64::floor),
+        "ceil" => one(f64::ceil),
+        "round" => one(f64::round),
+        "deg" => one(f64::to_degrees),
+        "rad" => one(f64::to_radians),
+        "log" => match args {
+            [x] => Ok(x.log10()),
+            [x, base] => Ok(x.log(*base)),
+            _ => Err(CalcError::Arguments(name.to_string(), "one or two numbers")),
+        },
+        "min" | "max" if args.is_empty() => Err(CalcError::Arguments(
+            name.to_string(),
+            "at least one number",
+        )),
+        "min" => Ok(args.iter().copied().fold(f64::INFINITY, f64::min)),
+        "max" => Ok(args.iter().copied().fold(f64::NEG_INFINITY, f64::max)),
+        _ => Err(CalcError::Unknown(name.to_string())),
+    }
+}
+
+fn factorial(value: f64) -> Result<f64, CalcError> {
+    if !(0.0..=MAX_FACTORIAL).contains(&value) || value.fract() != 0.0 {
+        return Err(CalcError::Factorial);
+    }
+    Ok((2..=value as u64).fold(1.0, |product, n| product * n as f64))
+}
+
+/// A recursive descent parser that evaluates as it goes.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, op: char) -> bool {
+        if self.peek() == Some(&Token::Op(op)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, op: char) -> Result<(), CalcError> {
+        match self.next() {
+            Some(Token::Op(found)) if found == op => Ok(()),
+            Some(token) => Err(unexpected(token)),
+            None => Err(CalcError::UnexpectedEnd),
+        }
+    }
+
+    fn enter(&mut self) -> Result<(), CalcError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(CalcError::TooDeep);
+        }
+        Ok(())
+    }
+
+    /// `term (('+' | '-') term)*`
+    fn expression(&mut self) -> Result<f64, CalcError> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value += self.term()?;
+            } else if self.eat('-') {
+                value -= self.term()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// `unary (('*' | '/' | '%')? unary)*`, where two values next to each other
+    /// are multiplied, as in `2pi` or `3(4 + 5)`.
+    fn term(&mut self) -> Result<f64, CalcError> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat('*') {
+                value *= self.unary()?;
+            } else if self.eat('/') {
+                let divisor = self.unary()?;
+                if divisor == 0.0 {
+                    return Err(CalcError::DivideByZero);
+                }
+                value /= divisor;
+            } else if self.eat('%') {
+                let divisor = self.unary()?;
+                if divisor == 0.0 {
+                    return Err(CalcError::DivideByZero);
+                }
+                value %= divisor;
+            } else if matches!(
+                self.peek(),
+                Some(Token::Number(_) | Token::Name(_) | Token::Op('('))
+            ) {
+                value *= self.power()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// `('-' | '+') unary | power`
+    fn unary(&mut self) -> Result<f64, CalcError> {
+        if self.eat('-') {
+            self.enter()?;
+            let value = -self.unary()?;
+            self.depth -= 1;
+            Ok(value)
+        } else if self.eat('+') {
+            self.enter()?;
+            let value = self.unary()?;
+            self.depth -= 1;
+            Ok(value)
+        } else {
+            self.power()
+        }
+    }
+
+    /// `postfix ('^' unary)?`, so `2^3^2` is `2^9` and `-2^2` is `-4`.
+    fn power(&mut self) -> Result<f64, CalcError> {
+        let base = self.postfix()?;
+        if self.eat('^') {
+            self.enter()?;
+            let exponent = self.unary()?;
+            self.depth -= 1;
+            return Ok(base.powf(exponent));
+        }
+        Ok(base)
+    }
+
+    /// `primary '!'*`
+    fn postfix(&mut self) -> Result<f64, CalcError> {
+        let mut value = self.primary()?;
+        while self.eat('!') {
+            value = factorial(value)?;
+        }
+        Ok(value)
+    }
+
+    /// A number, constant, function call or parenthesized expression.
+    fn primary(&mut self) -> Result<f64, CalcError> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(value),
+            Some(Token::Op('(')) => {
+                self.enter()?;
+                let value = self.expression()?;
+                self.expect(')')?;
+                self.depth -= 1;
+                Ok(value)
+            }
+            Some(Token::Name(name)) => {
+                if !self.eat('(') {
+                    return constant(&name).ok_or(CalcError::Unknown(name));
+                }
+                self.enter()?;
+                let mut args = Vec::new();
+                if !self.eat(')') {
+                    loop {
+                        args.push(self.expression()?);
+                        if self.eat(')') {
+                            break;
+                        }
+                        self.expect(',')?;
+                    }
+                }
+                self.depth -= 1;
+                call(&name, &args)
+            }
+            Some(token) => Err(unexpected(token)),
+            None => Err(CalcError::UnexpectedEnd),
+        }
+    }
+}
+
+fn unexpected(token: Token) -> CalcError {
+    CalcError::Unexpected(match token {
+        Token::Number(value) => value.to_string(),
+        Token::Name(name) => name,
+        Token::Op(op) => op.to_string(),
+    })
+}
+
+/// Evaluate an arithmetic expression.
+///
+/// Supports `+ - * / % ^` with the usual precedence, parentheses, factorials,
+/// constants like `pi` and `e`, and functions like `sqrt`, `sin` and `log`.
+/// Angles are in radians.
+pub fn evaluate(input: &str) -> Result<f64, CalcError> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(CalcError::Empty);
+    }
+    if input.chars().count() > MAX_LENGTH {
+        return Err(CalcError::TooLong);
+    }
+
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        pos: 0,
+        depth: 0,
+    };
+    let value = parser.expression()?;
+    if let Some(token) = parser.next() {
+        return Err(unexpected(token));
+    }
+
+    if value.is_nan() {
+        Err(CalcError::Undefined)
+    } else if value.is_infinite() {
+        Err(CalcError::Overflow)
+    } else {
+        // Avoid showing `-0`
+        Ok(value + 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(input: &str, expected: f64) {
+        let value = evaluate(input).unwrap();
+        assert!((value - expected).abs() < 1e-9, "{} = {}", input, value);
+    }
+
+    #[test]
+    fn follows_precedence() {
+        close("1 + 2 * 3", 7.0);
+        close("(1 + 2) * 3", 9.0);
+        close("2^3^2", 512.0);
+        close("-2^2", -4.0);
+        close("2^-1", 0.5);
+        close("10 % 4 - 8 / 4", 0.0);
+        close("2pi", 2.0 * consts::PI);
+        close("3(4 + 5)", 27.0);
+        close("5! / 3!", 20.0);
+        close("1_000 * 1.5e3", 1_500_000.0);
+        close("2 ** 10", 1024.0);
+    }
+
+    #[test]
+    fn calls_functions() {
+        close("sqrt(16) + abs(-3)", 7.0);
+        close("sin(pi / 2)", 1.0);
+        close("log(1000)", 3.0);
+        close("log(8, 2)", 3.0);
+        close("max(1, 5, 3) - min(4, 2)", 3.0);
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert_eq!(evaluate(""), Err(CalcError::Empty));
+        assert_eq!(evaluate("1 / 0"), Err(CalcError::DivideByZero));
+        assert_eq!(evaluate("(1 + 2"), Err(CalcError::UnexpectedEnd));
+        assert_eq!(evaluate("1 + 2)"), Err(CalcError::Unexpected(")".into())));
+        assert_eq!(evaluate("foo(1)"), Err(CalcError::Unknown("foo".into())));
+        assert_eq!(evaluate("sqrt(-1)"), Err(CalcError::Undefined));
+        assert_eq!(evaluate("10^400"), Err(CalcError::Overflow));
+        assert_eq!(evaluate("171!"), Err(CalcError::Factorial));
+        assert_eq!(evaluate("1; 2"), Err(CalcError::Unexpected(";".into())));
+        assert_eq!(
+            evaluate(&format!("{}1{}", "(".repeat(40), ")".repeat(40))),
+            Err(CalcError::TooDeep)
+        );
+        assert_eq!(evaluate(&"-".repeat(100)), Err(CalcError::TooDeep));
+        assert_eq!(evaluate(&"1+".repeat(300)), Err(CalcError::TooLong));
+    }
+}
@@ -0,0 +1,237 @@
+//! Line and bar charts rendered to PNG for statistics commands.
+//!
+//! Charts share a dark theme that matches Discord's, and text is drawn with the
+//! DejaVu Sans font bundled in `assets/fonts` so rendering doesn't depend on the
+//! fonts installed on the host. Without the `charts` feature every chart fails
+//! with [`ChartError::Unavailable`], and commands fall back to text.
+//!
+//! Charts are returned as an [`OutgoingFile`] named [`CHART_FILE`], which an embed
+//! can show with `image(CHART_URL)`.
+
+use thiserror::Error;
+
+use crate::utils::files::OutgoingFile;
+
+/// The file name of a rendered chart.
+pub const CHART_FILE: &str = "chart.png";
+
+/// The URL an embed uses to show a chart sent alongside it.
+pub const CHART_URL: &str = "attachment://chart.png";
+
+/// An error from rendering a chart.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ChartError {
+    #[error("Charts aren't available in this build.")]
+    Unavailable,
+    #[error("There's nothing to chart.")]
+    Empty,
+    #[error("Couldn't draw the chart: {0}")]
+    Draw(String),
+}
+
+/// Render values over time as a filled line, oldest first. Labels are shown
+/// under the line, skipping some when there are too many to fit.
+pub fn line(title: &str, points: &[(String, u64)]) -> Result<OutgoingFile, ChartError> {
+    if points.is_empty() {
+        return Err(ChartError::Empty);
+    }
+    render::line(title, points)
+}
+
+/// Render labelled values as horizontal bars, in the order given from the top.
+pub fn bar(title: &str, bars: &[(String, u64)]) -> Result<OutgoingFile, ChartError> {
+    if bars.is_empty() {
+        return Err(ChartError::Empty);
+    }
+    render::bar(title, bars)
+}
+
+#[cfg(feature = "charts")]
+mod render {
+    use plotters::coord::Shift;
+    use plotters::prelude::*;
+    use plotters::style::text_anchor::{HPos, Pos, VPos};
+    use std::io::Cursor;
+    use std::sync::Once;
+    use tracing::warn;
+
+    use super::{ChartError, CHART_FILE};
+    use crate::utils::constants::DEFAULT_COLOR;
+    use crate::utils::files::OutgoingFile;
+
+    const WIDTH: u32 = 1000;
+    const HEIGHT: u32 = 500;
+
+    /// Discord's dark background.
+    const BACKGROUND: RGBColor = RGBColor(0x31, 0x33, 0x38);
+    const TEXT: RGBColor = RGBColor(0xDB, 0xDE, 0xE1);
+    const GRID: RGBColor = RGBColor(0x4E, 0x50, 0x58);
+    const ACCENT: RGBColor = RGBColor(
+        (DEFAULT_COLOR >> 16) as u8,
+        (DEFAULT_COLOR >> 8) as u8,
+        DEFAULT_COLOR as u8,
+    );
+
+    const FONT: &str = "sans-serif";
+
+    /// Register the bundled font the first time a chart is drawn.
+    fn register_font() {
+        static REGISTER: Once = Once::new();
+        REGISTER.call_once(|| {
+            let bytes = include_bytes!("../../assets/fonts/DejaVuSans.ttf");
+            if plotters::style::register_font(FONT, FontStyle::Normal, bytes).is_err() {
+                warn!("The bundled chart font is invalid, chart text won't render");
+            }
+        });
+    }
+
+    fn draw_error(e: impl std::fmt::Display) -> ChartError {
+        ChartError::Draw(e.to_string())
+    }
+
+    /// Draw on a themed canvas and encode the result as a PNG.
+    fn draw(
+        f: impl FnOnce(&DrawingArea<BitMapBackend, Shift>) -> Result<(), ChartError>,
+    ) -> Result<OutgoingFile, ChartError> {
+        register_font();
+        let mut pixels = vec![0u8; (WIDTH * HEIGHT * 3) as usize];
+        {
+            let root = BitMapBackend::with_buffer(&mut pixels, (WIDTH, HEIGHT)).into_drawing_area();
+            root.fill(&BACKGROUND).map_err(draw_error)?;
+            f(&root)?;
+            root.present().map_err(draw_error)?;
+        }
+
+        let image = image::RgbImage::from_raw(WIDTH, HEIGHT, pixels)
+            .ok_or_else(|| ChartError::Draw("the image buffer is the wrong size".to_string()))?;
+        let mut png = Cursor::new(Vec::new());
+        image
+            .write_to(&mut png, image::ImageOutputFormat::Png)
+            .map_err(draw_error)?;
+        Ok(OutgoingFile::new(CHART_FILE, png.into_inner()))
+    }
+
+    /// The top of the value axis, leaving some room above the highest value.
+    fn axis_max(values: impl Iterator<Item = u64>) -> u64 {
+        let max = values.max().unwrap_or(0).max(1);
+        max + max / 10 + 1
+    }
+
+    pub(super) fn line(title: &str, points: &[(String, u64)]) -> Result<OutgoingFile, ChartError> {
+        draw(|root| {
+            let last = points.len().saturating_sub(1).max(1);
+            let mut chart = ChartBuilder::on(root)
+                .caption(title, (FONT, 28).into_font().color(&TEXT))
+                .margin(20)
+                .x_label_area_size(40)
+                .y_label_area_size(70)
+                .build_cartesian_2d(0..last, 0..axis_max(points.iter().map(|p| p.1)))
+                .map_err(draw_error)?;
+
+            let label = |i: &usize| points.get(*i).map(|p| p.0.clone()).unwrap_or_default();
+            chart
+                .configure_mesh()
+                .bold_line_style(GRID)
+                .light_line_style(BACKGROUND)
+                .axis_style(GRID)
+                .label_style((FONT, 16).into_font().color(&TEXT))
+                .x_labels(points.len().min(8))
+                .x_label_formatter(&label)
+                .draw()
+                .map_err(draw_error)?;
+
+            chart
+                .draw_series(
+                    AreaSeries::new(
+                        points.iter().enumerate().map(|(i, p)| (i, p.1)),
+                        0,
+                        ACCENT.mix(0.25),
+                    )
+                    .border_style(ACCENT.stroke_width(3)),
+                )
+                .map_err(draw_error)?;
+            Ok(())
+        })
+    }
+
+    pub(super) fn bar(title: &str, bars: &[(String, u64)]) -> Result<OutgoingFile, ChartError> {
+        draw(|root| {
+            let rows = bars.len() as f64;
+            let mut chart = ChartBuilder::on(root)
+                .caption(title, (FONT, 28).into_font().color(&TEXT))
+                .margin(20)
+                .x_label_area_size(40)
+                .y_label_area_size(180)
+                .build_cartesian_2d(0..axis_max(bars.iter().map(|b| b.1)), 0.0..rows)
+                .map_err(draw_error)?;
+            chart
+                .configure_mesh()
+                .disable_y_mesh()
+                .y_labels(0)
+                .bold_line_style(GRID)
+                .light_line_style(BACKGROUND)
+                .axis_style(GRID)
+                .label_style((FONT, 16).into_font().color(&TEXT))
+                .draw()
+                .map_err(draw_error)?;
+
+            // The first bar goes at the top, so rows count down from there
+            let top = |i: usize| rows - i as f64;
+            chart
+                .draw_series(bars.iter().enumerate().map(|(i, (_, value))| {
+                    Rectangle::new([(0, top(i) - 0.1), (*value, top(i) - 0.9)], ACCENT.filled())
+                }))
+                .map_err(draw_error)?;
+
+            // Labels are drawn beside the axis, centred on their bars
+            let style = (FONT, 16)
+                .into_font()
+                .color(&TEXT)
+                .pos(Pos::new(HPos::Right, VPos::Center));
+            for (i, (label, _)) in bars.iter().enumerate() {
+                let (x, y) = chart.backend_coord(&(0, top(i) - 0.5));
+                root.draw(&Text::new(label.as_str(), (x - 10, y), style.clone()))
+                    .map_err(draw_error)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(not(feature = "charts"))]
+mod render {
+    use super::ChartError;
+    use crate::utils::files::OutgoingFile;
+
+    pub(super) fn line(
+        _title: &str,
+        _points: &[(String, u64)],
+    ) -> Result<OutgoingFile, ChartError> {
+        Err(ChartError::Unavailable)
+    }
+
+    pub(super) fn bar(_title: &str, _bars: &[(String, u64)]) -> Result<OutgoingFile, ChartError> {
+        Err(ChartError::Unavailable)
+    }
+}
+
+#[cfg(all(test, feature = "charts"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_png_charts() {
+        let points: Vec<(String, u64)> =
+            (1..=30).map(|day| (format!("{}", day), day * 3)).collect();
+        let chart = line("Messages per day", &points).unwrap();
+        assert_eq!(chart.name, CHART_FILE);
+        assert!(chart.data.starts_with(b"\x89PNG"));
+
+        let bars = [("#general".to_string(), 40), ("#memes".to_string(), 12)];
+        assert!(bar("Busiest channels", &bars)
+            .unwrap()
+            .data
+            .starts_with(b"\x89PNG"));
+        assert_eq!(bar("Nothing", &[]).unwrap_err(), ChartError::Empty);
+    }
+}
@@ -32,3 +32,63 @@ pub const PAGINATION_MAX_ITEMS: usize = 10;
 
 /// Default timeout for interactive components (in seconds).
 pub const DEFAULT_COMPONENT_TIMEOUT: u64 = 60;
+
+/// Minimum time between watchlist activity alerts for the same user (in seconds).
+pub const WATCHLIST_ALERT_COOLDOWN: u64 = 3600;
+
+/// Maximum number of entries kept in the internal audit log.
+pub const AUDIT_LOG_MAX_ENTRIES: usize = 10_000;
+
+/// Default maximum number of event handler tasks running at once.
+pub const DEFAULT_MAX_IN_FLIGHT_HANDLERS: usize = 256;
+
+/// Default number of panics after which owners are alerted about an event handler.
+pub const DEFAULT_HANDLER_PANIC_THRESHOLD: u32 = 3;
+
+/// Default maximum number of commands running at once across all channels.
+pub const DEFAULT_MAX_CONCURRENT_COMMANDS: usize = 32;
+
+/// Upload limit for servers without boosts (in bytes).
+pub const UPLOAD_LIMIT_BASE: usize = 25 * 1024 * 1024;
+
+/// Upload limit for servers at boost level 2 (in bytes).
+pub const UPLOAD_LIMIT_TIER_2: usize = 50 * 1024 * 1024;
+
+/// Upload limit for servers at boost level 3 (in bytes).
+pub const UPLOAD_LIMIT_TIER_3: usize = 100 * 1024 * 1024;
+
+/// Maximum number of attachments in a single message.
+pub const MAX_ATTACHMENTS_PER_MESSAGE: usize = 10;
+
+/// Maximum number of messages Discord lets a channel publish per hour.
+pub const PUBLISH_LIMIT_PER_HOUR: usize = 10;
+
+/// Maximum number of pinned messages in a channel.
+pub const PIN_LIMIT: usize = 50;
+
+/// Maximum length of an embed description (in characters).
+pub const EMBED_DESCRIPTION_LIMIT: usize = 4096;
+
+/// Default maximum number of auto-response rules per guild, see `[premium.free]`.
+pub const MAX_AUTO_RESPONSES: usize = 50;
+
+/// Largest compiled size allowed for a user-supplied regex (in bytes).
+pub const REGEX_SIZE_LIMIT: usize = 1024 * 1024;
+
+/// Default maximum number of word filter patterns per guild, see `[premium.free]`.
+pub const MAX_FILTER_RULES: usize = 500;
+
+/// Default maximum number of soundboard clips per guild, see `[premium.free]`.
+pub const MAX_SOUNDS: usize = 25;
+
+/// Longest timeout Discord allows (in seconds).
+pub const MAX_TIMEOUT: u64 = 28 * 24 * 60 * 60;
+
+/// Largest filter list accepted for import (in bytes).
+pub const FILTER_IMPORT_MAX_SIZE: u64 = 1024 * 1024;
+
+/// Name of the message context menu command for reporting messages.
+pub const REPORT_MENU_NAME: &str = "Report Message";
+
+/// How long the report timeout button times members out (in seconds).
+pub const REPORT_TIMEOUT: u64 = 60 * 60;
@@ -0,0 +1,480 @@
+//! Lookups of crates on crates.io, repositories on GitHub and docs on docs.rs.
+//!
+//! Results are cached for ten minutes, including misses, since GitHub only allows
+//! 60 requests an hour without a token. Set the `github_token` secret to raise that.
+//!
+//! When a name doesn't match exactly, commands offer the closest search results
+//! in a select menu with the component ID `devlookup:<kind>:<user>[:<path>]`,
+//! where `kind` is `crate`, `repo` or `docs` and only `user` may pick.
+
+use chrono::DateTime;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serenity::builder::{CreateComponents, CreateEmbed};
+use serenity::model::id::UserId;
+use serenity::prelude::*;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::framework::command_handler::CommandResult;
+use crate::utils::cache::TtlCache;
+use crate::utils::constants::DEFAULT_COLOR;
+use crate::utils::duration::relative;
+use crate::utils::helpers::{format_count, truncate};
+use crate::utils::secrets;
+
+/// How long lookups are cached.
+const CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// How many lookups of each kind are cached at once.
+const CACHE_CAPACITY: usize = 200;
+
+/// How long to wait for crates.io or GitHub.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How many search results are offered when a name doesn't match.
+const MAX_CHOICES: usize = 5;
+
+/// Both crates.io and GitHub reject requests without a user agent.
+const USER_AGENT: &str = concat!("kurumi-rs/", env!("CARGO_PKG_VERSION"));
+
+const CRATES_API: &str = "https://crates.io/api/v1";
+const GITHUB_API: &str = "https://api.github.com";
+
+/// Crates whose docs are on doc.rust-lang.org rather than docs.rs.
+const STD_CRATES: &[&str] = &["std", "core", "alloc", "proc_macro", "test"];
+
+/// Key for storing the lookup client in the client data.
+pub struct DevLookupKey;
+
+impl TypeMapKey for DevLookupKey {
+    type Value = Arc<DevLookup>;
+}
+
+/// A crate on crates.io.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CrateInfo {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub max_stable_version: Option<String>,
+    pub max_version: String,
+    #[serde(default)]
+    pub downloads: u64,
+    #[serde(default)]
+    pub recent_downloads: Option<u64>,
+    #[serde(default)]
+    pub documentation: Option<String>,
+    #[serde(default)]
+    pub repository: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+impl CrateInfo {
+    /// The newest stable version, or the newest version if none is stable.
+    pub fn version(&self) -> &str {
+        self.max_stable_version
+            .as_deref()
+            .unwrap_or(&self.max_version)
+    }
+
+    /// Where the crate's docs are, preferring docs.rs.
+    pub fn docs_url(&self) -> String {
+        match &self.documentation {
+            Some(url) if !url.contains("docs.rs") => url.clone(),
+            _ => format!("https://docs.rs/{}/{}", self.name, self.version()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CrateResponse {
+    #[serde(rename = "crate")]
+    krate: CrateInfo,
+}
+
+#[derive(Deserialize)]
+struct CrateSearch {
+    crates: Vec<CrateInfo>,
+}
+
+/// A repository on GitHub.
+#[derive(Clone, Debug, Deserialize)]
+pub struct RepoInfo {
+    pub full_name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub html_url: String,
+    #[serde(default)]
+    pub stargazers_count: u64,
+    #[serde(default)]
+    pub forks_count: u64,
+    /// Open issues and pull requests, which GitHub counts together.
+    #[serde(default)]
+    pub open_issues_count: u64,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub license: Option<License>,
+    #[serde(default)]
+    pub pushed_at: Option<String>,
+    #[serde(default)]
+    pub archived: bool,
+    /// The latest release, filled in separately.
+    #[serde(skip)]
+    pub release: Option<Release>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct License {
+    #[serde(default)]
+    pub spdx_id: Option<String>,
+    pub name: String,
+}
+
+/// A release of a GitHub repository.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    pub html_url: String,
+    #[serde(default)]
+    pub published_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RepoSearch {
+    items: Vec<RepoInfo>,
+}
+
+/// Seconds since the Unix epoch for an RFC 3339 timestamp from an API.
+fn parse_time(timestamp: &str) -> Option<u64> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .and_then(|time| u64::try_from(time.timestamp()).ok())
+}
+
+/// Split an item path like `tokio::sync::Mutex` into its crate and the rest.
+pub fn split_path(path: &str) -> (String, Option<String>) {
+    let path = path.trim().trim_matches('`');
+    match path.split_once("::") {
+        Some((krate, item)) if !item.is_empty() => (krate.to_lowercase(), Some(item.to_string())),
+        _ => (path.trim_end_matches("::").to_lowercase(), None),
+    }
+}
+
+/// Where docs for an item path are, searching the crate's docs for the item.
+pub fn docs_url(krate: &str, version: &str, item: Option<&str>) -> String {
+    let module = krate.replace('-', "_");
+    let base = if STD_CRATES.contains(&module.as_str()) {
+        format!("https://doc.rust-lang.org/stable/{}/", module)
+    } else {
+        format!("https://docs.rs/{}/{}/{}/", krate, version, module)
+    };
+    match item {
+        Some(item) => format!(
+            "{}?search={}",
+            base,
+            item.replace("::", "%3A%3A").replace(' ', "%20")
+        ),
+        None => base,
+    }
+}
+
+/// Looks up crates and repositories, caching the results.
+pub struct DevLookup {
+    client: reqwest::Client,
+    github_token: Option<String>,
+    crates: TtlCache<Option<CrateInfo>>,
+    repos: TtlCache<Option<RepoInfo>>,
+    crate_searches: TtlCache<Vec<String>>,
+    repo_searches: TtlCache<Vec<String>>,
+}
+
+impl Default for DevLookup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DevLookup {
+    /// Create a client, reading the `github_token` secret if it's set.
+    pub fn new() -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent(USER_AGENT)
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            client,
+            github_token: secrets::get("github_token"),
+            crates: TtlCache::new(CACHE_TTL, CACHE_CAPACITY),
+            repos: TtlCache::new(CACHE_TTL, CACHE_CAPACITY),
+            crate_searches: TtlCache::new(CACHE_TTL, CACHE_CAPACITY),
+            repo_searches: TtlCache::new(CACHE_TTL, CACHE_CAPACITY),
+        }
+    }
+
+    /// GET a JSON API, or `None` on a 404.
+    async fn get<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        query: &[(&str, &str)],
+    ) -> CommandResult<Option<T>> {
+        let mut request = self.client.get(url).query(query);
+        if url.starts_with(GITHUB_API) {
+            request = request.header("Accept", "application/vnd.github+json");
+            if let Some(token) = &self.github_token {
+                request = request.bearer_auth(token);
+            }
+        }
+        let response = request.send().await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(response.error_for_status()?.json().await?))
+    }
+
+    /// Look up a crate by its exact name.
+    pub async fn krate(&self, name: &str) -> CommandResult<Option<CrateInfo>> {
+        let key = name.to_lowercase().replace('_', "-");
+        if let Some(cached) = self.crates.get(&key).await {
+            return Ok(cached);
+        }
+
+        let url = format!("{}/crates/{}", CRATES_API, key);
+        let info = self
+            .get::<CrateResponse>(&url, &[])
+            .await?
+            .map(|response| response.krate);
+        self.crates.insert(key, info.clone()).await;
+        Ok(info)
+    }
+
+    /// Names of the crates that best match a search.
+    pub async fn search_crates(&self, query: &str) -> CommandResult<Vec<String>> {
+        let key = query.to_lowercase();
+        if let Some(cached) = self.crate_searches.get(&key).await {
+            return Ok(cached);
+        }
+
+        let per_page = MAX_CHOICES.to_string();
+        let url = format!("{}/crates", CRATES_API);
+        let names: Vec<String> = self
+            .get::<CrateSearch>(&url, &[("q", query), ("per_page", &per_page)])
+            .await?
+            .map(|search| search.crates.into_iter().map(|c| c.name).collect())
+            .unwrap_or_default();
+        self.crate_searches.insert(key, names.clone()).await;
+        Ok(names)
+    }
+
+    /// Look up a repository by `owner/name`, with its latest release.
+    pub async fn repo(&self, full_name: &str) -> CommandResult<Option<RepoInfo>> {
+        let key = full_name.to_lowercase();
+        if let Some(cached) = self.repos.get(&key).await {
+            return Ok(cached);
+        }
+
+        let url = format!("{}/repos/{}", GITHUB_API, key);
+        let mut info = self.get::<RepoInfo>(&url, &[]).await?;
+        if let Some(info) = &mut info {
+            let url = format!("{}/repos/{}/releases/latest", GITHUB_API, key);
+            info.release = self.get::<Release>(&url, &[]).await?;
+        }
+        self.repos.insert(key, info.clone()).await;
+        Ok(info)
+    }
+
+    /// Full names of the repositories that best match a search.
+    pub async fn search_repos(&self, query: &str) -> CommandResult<Vec<String>> {
+        let key = query.to_lowercase();
+        if let Some(cached) = self.repo_searches.get(&key).await {
+            return Ok(cached);
+        }
+
+        let per_page = MAX_CHOICES.to_string();
+        let url = format!("{}/search/repositories", GITHUB_API);
+        let names: Vec<String> = self
+            .get::<RepoSearch>(&url, &[("q", query), ("per_page", &per_page)])
+            .await?
+            .map(|search| search.items.into_iter().map(|r| r.full_name).collect())
+            .unwrap_or_default();
+        self.repo_searches.insert(key, names.clone()).await;
+        Ok(names)
+    }
+}
+
+/// Build the embed for a crate.
+pub fn crate_embed(info: &CrateInfo) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(format!("📦 {} {}", info.name, info.version()))
+        .url(format!("https://crates.io/crates/{}", info.name))
+        .color(DEFAULT_COLOR)
+        .description(truncate(
+            info.description
+                .as_deref()
+                .unwrap_or("No description.")
+                .trim(),
+            1024,
+        ))
+        .field("Downloads", format_count(info.downloads), true)
+        .field("Docs", format!("[docs.rs]({})", info.docs_url()), true);
+    if let Some(recent) = info.recent_downloads {
+        embed.field("Last 90 days", format_count(recent), true);
+    }
+    if let Some(repository) = &info.repository {
+        embed.field("Repository", repository, false);
+    }
+    if let Some(updated) = info.updated_at.as_deref().and_then(parse_time) {
+        embed.field("Updated", relative(updated), true);
+    }
+    embed
+}
+
+/// Build the embed for a repository.
+pub fn repo_embed(info: &RepoInfo) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    let title = if info.archived {
+        format!("🐙 {} (archived)", info.full_name)
+    } else {
+        format!("🐙 {}", info.full_name)
+    };
+    embed
+        .title(title)
+        .url(&info.html_url)
+        .color(DEFAULT_COLOR)
+        .description(truncate(
+            info.description
+                .as_deref()
+                .unwrap_or("No description.")
+                .trim(),
+            1024,
+        ))
+        .field("Stars", format_count(info.stargazers_count), true)
+        .field("Forks", format_count(info.forks_count), true)
+        .field("Open issues", format_count(info.open_issues_count), true);
+    if let Some(language) = &info.language {
+        embed.field("Language", language, true);
+    }
+    if let Some(license) = &info.license {
+        let name = license
+            .spdx_id
+            .as_deref()
+            .filter(|id| *id != "NOASSERTION")
+            .unwrap_or(&license.name);
+        embed.field("License", name, true);
+    }
+    if let Some(release) = &info.release {
+        let when = release
+            .published_at
+            .as_deref()
+            .and_then(parse_time)
+            .map(|at| format!(", {}", relative(at)))
+            .unwrap_or_default();
+        embed.field(
+            "Latest release",
+            format!("[{}]({}){}", release.tag_name, release.html_url, when),
+            false,
+        );
+    }
+    if let Some(pushed) = info.pushed_at.as_deref().and_then(parse_time) {
+        embed.field("Last push", relative(pushed), true);
+    }
+    embed
+}
+
+/// Build the embed for an item path in a crate's docs.
+pub fn docs_embed(info: &CrateInfo, item: Option<&str>) -> CreateEmbed {
+    let path = match item {
+        Some(item) => format!("{}::{}", info.name.replace('-', "_"), item),
+        None => info.name.replace('-', "_"),
+    };
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(format!("📚 {}", truncate(&path, 200)))
+        .url(docs_url(&info.name, info.version(), item))
+        .color(DEFAULT_COLOR)
+        .description(truncate(
+            info.description
+                .as_deref()
+                .unwrap_or("No description.")
+                .trim(),
+            1024,
+        ))
+        .field("Version", info.version(), true);
+    embed
+}
+
+/// Build the embed for the standard library's docs.
+pub fn std_docs_embed(krate: &str, item: Option<&str>) -> CreateEmbed {
+    let path = match item {
+        Some(item) => format!("{}::{}", krate, item),
+        None => krate.to_string(),
+    };
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(format!("📚 {}", truncate(&path, 200)))
+        .url(docs_url(krate, "stable", item))
+        .color(DEFAULT_COLOR)
+        .description("From the Rust standard library.");
+    embed
+}
+
+/// Whether a crate's docs are on doc.rust-lang.org.
+pub fn is_std(krate: &str) -> bool {
+    STD_CRATES.contains(&krate)
+}
+
+/// Build a select menu offering search results, for when a name doesn't match.
+/// `path` is carried in the component ID for `docs`, so keep it short.
+pub fn choices_menu(
+    components: &mut CreateComponents,
+    kind: &str,
+    user_id: UserId,
+    path: Option<&str>,
+    choices: &[String],
+) {
+    let custom_id = match path {
+        Some(path) => format!("devlookup:{}:{}:{}", kind, user_id, path),
+        None => format!("devlookup:{}:{}", kind, user_id),
+    };
+    components.create_action_row(|row| {
+        row.create_select_menu(|menu| {
+            menu.custom_id(custom_id)
+                .placeholder("Did you mean...")
+                .options(|options| {
+                    for choice in choices.iter().take(MAX_CHOICES) {
+                        options.create_option(|o| o.label(choice).value(choice));
+                    }
+                    options
+                })
+        })
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_and_links_paths() {
+        assert_eq!(
+            split_path("tokio::sync::Mutex"),
+            ("tokio".to_string(), Some("sync::Mutex".to_string()))
+        );
+        assert_eq!(split_path("`Serde`"), ("serde".to_string(), None));
+        assert_eq!(
+            docs_url("serde-json", "1.0.0", Some("Value")),
+            "https://docs.rs/serde-json/1.0.0/serde_json/?search=Value"
+        );
+        assert_eq!(
+            docs_url("std", "stable", Some("vec::Vec")),
+            "https://doc.rust-lang.org/stable/std/?search=vec%3A%3AVec"
+        );
+        assert_eq!(parse_time("1970-01-01T00:01:00Z"), Some(60));
+    }
+}
@@ -0,0 +1,436 @@
+//! Self-checks that catch setup problems before they surface as failing commands.
+//!
+//! The checks run once when the bot starts and again with the owner `diagnose`
+//! command. They confirm the token works, that Discord allows the privileged
+//! intents the bot asks for, that storage can be written and read back, and that
+//! the channels and roles in each guild's configuration still exist. The report
+//! also shows how full the message cache is.
+
+use serde_json::json;
+use serenity::http::Http;
+use serenity::model::application::ApplicationFlags;
+use serenity::model::gateway::GatewayIntents;
+use serenity::prelude::TypeMapKey;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+use tracing::{error, info, warn};
+
+use crate::models::guild_config::{GuildConfig, GuildConfigs};
+use crate::models::message_cache::MessageCache;
+use crate::storage::{JsonStore, Storage};
+use crate::utils::helpers::unix_timestamp;
+
+/// Document written and read back to check storage.
+const PROBE_DOCUMENT: &str = "diagnostics";
+
+/// How a check went.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    /// Nothing to fix.
+    Ok,
+    /// Something is off, but the bot can run.
+    Warning,
+    /// Something the bot needs is broken.
+    Failed,
+}
+
+impl Status {
+    fn icon(self) -> &'static str {
+        match self {
+            Status::Ok => "✅",
+            Status::Warning => "⚠️",
+            Status::Failed => "❌",
+        }
+    }
+}
+
+/// The result of one check.
+#[derive(Clone, Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: &'static str, status: Status, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} **{}**: {}",
+            self.status.icon(),
+            self.name,
+            self.detail
+        )
+    }
+}
+
+/// The results of a diagnostics run.
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    /// The worst status among the checks.
+    pub fn status(&self) -> Status {
+        let statuses = self.checks.iter().map(|check| check.status);
+        if statuses.clone().any(|status| status == Status::Failed) {
+            Status::Failed
+        } else if statuses.clone().any(|status| status == Status::Warning) {
+            Status::Warning
+        } else {
+            Status::Ok
+        }
+    }
+
+    /// Log each check at a level matching its status.
+    pub fn log(&self) {
+        for check in &self.checks {
+            match check.status {
+                Status::Ok => info!("Diagnostics: {}: {}", check.name, check.detail),
+                Status::Warning => warn!("Diagnostics: {}: {}", check.name, check.detail),
+                Status::Failed => error!("Diagnostics: {}: {}", check.name, check.detail),
+            }
+        }
+    }
+
+    /// Describe the checks, one per line.
+    pub fn describe(&self) -> String {
+        self.checks
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// The application flag that allows each privileged intent.
+fn intent_flags() -> [(GatewayIntents, ApplicationFlags, &'static str); 3] {
+    [
+        (
+            GatewayIntents::GUILD_MEMBERS,
+            ApplicationFlags::GATEWAY_GUILD_MEMBERS
+                | ApplicationFlags::GATEWAY_GUILD_MEMBERS_LIMITED,
+            "server members",
+        ),
+        (
+            GatewayIntents::GUILD_PRESENCES,
+            ApplicationFlags::GATEWAY_PRESENCE | ApplicationFlags::GATEWAY_PRESENCE_LIMITED,
+            "presence",
+        ),
+        (
+            GatewayIntents::MESSAGE_CONTENT,
+            ApplicationFlags::GATEWAY_MESSAGE_CONTENT
+                | ApplicationFlags::GATEWAY_MESSAGE_CONTENT_LIMITED,
+            "message content",
+        ),
+    ]
+}
+
+/// The privileged intents requested but not turned on for the application.
+fn missing_intents(intents: GatewayIntents, flags: ApplicationFlags) -> Vec<&'static str> {
+    intent_flags()
+        .into_iter()
+        .filter(|(intent, allowed, _)| intents.contains(*intent) && !flags.intersects(*allowed))
+        .map(|(_, _, name)| name)
+        .collect()
+}
+
+/// The channels and roles a guild's configuration refers to that are gone.
+pub fn missing_references(
+    config: &GuildConfig,
+    channels: &HashSet<u64>,
+    roles: &HashSet<u64>,
+) -> Vec<String> {
+    let mut missing = Vec::new();
+
+    let configured_channels = [
+        ("staff_channel", config.staff_channel),
+        ("modmail_channel", config.modmail_channel),
+        ("pin_archive_channel", config.pin_archive_channel),
+        ("message_log_channel", config.message_log_channel),
+        ("onboarding.channel", config.onboarding.channel),
+    ];
+    for (setting, channel_id) in configured_channels {
+        if let Some(channel_id) = channel_id.filter(|id| !channels.contains(id)) {
+            missing.push(format!("`{}` channel {}", setting, channel_id));
+        }
+    }
+    let mut auto_publish: Vec<_> = config.auto_publish.keys().copied().collect();
+    auto_publish.sort_unstable();
+    for channel_id in auto_publish {
+        if !channels.contains(&channel_id) {
+            missing.push(format!("`auto_publish` channel {}", channel_id));
+        }
+    }
+
+    for (setting, role_id) in [
+        ("staff_role", config.staff_role),
+        ("mute_role", config.mute_role),
+    ] {
+        if let Some(role_id) = role_id.filter(|id| !roles.contains(id)) {
+            missing.push(format!("`{}` role {}", setting, role_id));
+        }
+    }
+    let configured_roles = [
+        ("restore_role_allowlist", &config.restore_role_allowlist),
+        ("persistent_roles", &config.persistent_roles),
+    ];
+    for (setting, role_ids) in configured_roles {
+        for role_id in role_ids.iter().filter(|id| !roles.contains(id)) {
+            missing.push(format!("`{}` role {}", setting, role_id));
+        }
+    }
+    for role_id in config.onboarding.roles().filter(|id| !roles.contains(id)) {
+        missing.push(format!("`onboarding` role {}", role_id));
+    }
+
+    missing
+}
+
+/// Runs the self-checks.
+pub struct Diagnostics {
+    storage: Storage,
+    guild_configs: Arc<JsonStore<GuildConfigs>>,
+    message_cache: Arc<MessageCache>,
+    /// The intents the bot connects with, known once the handlers are registered.
+    intents: OnceLock<GatewayIntents>,
+}
+
+impl Diagnostics {
+    /// Create the checks for the given storage, guild configurations and
+    /// message cache.
+    pub fn new(
+        storage: Storage,
+        guild_configs: Arc<JsonStore<GuildConfigs>>,
+        message_cache: Arc<MessageCache>,
+    ) -> Self {
+        Self {
+            storage,
+            guild_configs,
+            message_cache,
+            intents: OnceLock::new(),
+        }
+    }
+
+    /// Set the intents the bot connects with, which the intent check compares
+    /// against the application's settings.
+    pub fn set_intents(&self, intents: GatewayIntents) {
+        let _ = self.intents.set(intents);
+    }
+
+    /// Run every check.
+    pub async fn run(&self, http: &Http) -> Report {
+        let mut checks = vec![self.check_token(http).await];
+        // The other Discord checks can't work without a valid token
+        if checks[0].status != Status::Failed {
+            checks.push(self.check_intents(http).await);
+        }
+        checks.push(self.check_storage().await);
+        if checks[0].status != Status::Failed {
+            checks.push(self.check_guild_configs(http).await);
+        }
+        checks.push(self.check_message_cache());
+        Report { checks }
+    }
+
+    fn check_message_cache(&self) -> Check {
+        let stats = self.message_cache.stats();
+        let lookups = stats.hits + stats.misses;
+        let hit_rate = match lookups {
+            0 => "no lookups yet".to_string(),
+            n => format!("{}% of {} lookups hit", stats.hits * 100 / n, n),
+        };
+        Check::new(
+            "Message cache",
+            Status::Ok,
+            format!(
+                "{}/{} messages, about {} KiB of text, {}, {} evicted, {} expired",
+                stats.len,
+                stats.capacity,
+                stats.approx_bytes / 1024,
+                hit_rate,
+                stats.evictions,
+                stats.expirations
+            ),
+        )
+    }
+
+    async fn check_token(&self, http: &Http) -> Check {
+        match http.get_current_user().await {
+            Ok(user) => Check::new("Token", Status::Ok, format!("Logged in as {}", user.tag())),
+            Err(e) => Check::new(
+                "Token",
+                Status::Failed,
+                format!("Discord refused it: {}", e),
+            ),
+        }
+    }
+
+    async fn check_intents(&self, http: &Http) -> Check {
+        let intents = match self.intents.get() {
+            Some(intents) => *intents,
+            None => return Check::new("Intents", Status::Warning, "Not resolved yet"),
+        };
+        let flags = match http.get_current_application_info().await {
+            Ok(application) => application.flags.unwrap_or_default(),
+            Err(e) => {
+                return Check::new(
+                    "Intents",
+                    Status::Warning,
+                    format!("Couldn't fetch the application: {}", e),
+                )
+            }
+        };
+
+        let missing = missing_intents(intents, flags);
+        if missing.is_empty() {
+            Check::new("Intents", Status::Ok, format!("{:?}", intents))
+        } else {
+            Check::new(
+                "Intents",
+                Status::Failed,
+                format!(
+                    "Turn on the {} intents in the Discord developer portal",
+                    missing.join(", ")
+                ),
+            )
+        }
+    }
+
+    async fn check_storage(&self) -> Check {
+        let backend = self.storage.backend();
+        let probe = json!({ "checked_at": unix_timestamp() });
+        let result = match backend.save(PROBE_DOCUMENT, &probe).await {
+            Ok(()) => backend.load(PROBE_DOCUMENT).await,
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(Some(document)) if document == probe => {
+                let health = self.storage.health();
+                if health.is_degraded() {
+                    Check::new(
+                        "Storage",
+                        Status::Warning,
+                        format!(
+                            "{} is reachable again, {} stores still waiting to be saved",
+                            backend.describe(),
+                            health.backlog_len()
+                        ),
+                    )
+                } else {
+                    Check::new("Storage", Status::Ok, backend.describe())
+                }
+            }
+            Ok(_) => Check::new(
+                "Storage",
+                Status::Failed,
+                format!("{} didn't return what was written", backend.describe()),
+            ),
+            Err(e) => Check::new(
+                "Storage",
+                Status::Failed,
+                format!("{}: {}", backend.describe(), e),
+            ),
+        }
+    }
+
+    async fn check_guild_configs(&self, http: &Http) -> Check {
+        let configs: Vec<(u64, GuildConfig)> = {
+            let configs = self.guild_configs.read().await;
+            configs
+                .guilds
+                .iter()
+                .map(|(guild_id, config)| (*guild_id, config.clone()))
+                .collect()
+        };
+
+        let mut problems = Vec::new();
+        for (guild_id, config) in &configs {
+            let channels = match http.get_channels(*guild_id).await {
+                Ok(channels) => channels.iter().map(|c| c.id.0).collect(),
+                Err(_) => {
+                    problems.push(format!("server {}: not reachable", guild_id));
+                    continue;
+                }
+            };
+            let roles = match http.get_guild_roles(*guild_id).await {
+                Ok(roles) => roles.iter().map(|r| r.id.0).collect(),
+                Err(_) => {
+                    problems.push(format!("server {}: not reachable", guild_id));
+                    continue;
+                }
+            };
+            for missing in missing_references(config, &channels, &roles) {
+                problems.push(format!("server {}: {} no longer exists", guild_id, missing));
+            }
+        }
+
+        if problems.is_empty() {
+            Check::new(
+                "Server settings",
+                Status::Ok,
+                format!("{} configured servers checked", configs.len()),
+            )
+        } else {
+            Check::new("Server settings", Status::Warning, problems.join("; "))
+        }
+    }
+}
+
+/// Key for storing the diagnostics in the client data.
+pub struct DiagnosticsKey;
+
+impl TypeMapKey for DiagnosticsKey {
+    type Value = Arc<Diagnostics>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_deleted_channels_and_roles() {
+        let config = GuildConfig {
+            staff_channel: Some(1),
+            modmail_channel: Some(2),
+            staff_role: Some(10),
+            mute_role: Some(13),
+            persistent_roles: vec![11, 12],
+            ..GuildConfig::default()
+        };
+        let channels = HashSet::from([1]);
+        let roles = HashSet::from([10, 12]);
+
+        assert_eq!(
+            missing_references(&config, &channels, &roles),
+            [
+                "`modmail_channel` channel 2",
+                "`mute_role` role 13",
+                "`persistent_roles` role 11"
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_privileged_intents_that_are_off() {
+        let intents = GatewayIntents::GUILD_MEMBERS | GatewayIntents::MESSAGE_CONTENT;
+        assert_eq!(
+            missing_intents(intents, ApplicationFlags::GATEWAY_GUILD_MEMBERS_LIMITED),
+            ["message content"]
+        );
+        assert!(missing_intents(GatewayIntents::GUILDS, ApplicationFlags::empty()).is_empty());
+    }
+}
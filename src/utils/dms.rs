@@ -0,0 +1,239 @@
+//! Sending DMs on behalf of features.
+//!
+//! Everything that DMs a user goes through [`DmService`], which skips users who
+//! turned the message's category off, spaces DMs out under one global rate
+//! limit and remembers whose DMs are closed. A feature that gets
+//! [`DmOutcome::Closed`] can mention the user in a channel instead.
+
+use serenity::builder::{CreateEmbed, CreateMessage};
+use serenity::http::{Http, HttpError};
+use serenity::model::id::UserId;
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Mutex as AsyncMutex;
+use tokio::time::Instant;
+
+use crate::framework::command_handler::CommandResult;
+use crate::framework::error::KurumiError;
+use crate::models::notifications::{DmCategory, NotificationData};
+use crate::storage::JsonStore;
+use crate::utils::files::{send_file, OutgoingFile};
+use crate::utils::helpers::unix_timestamp;
+
+/// Discord's error code for "Cannot send messages to this user".
+pub const CLOSED_DMS: isize = 50007;
+
+/// Time between two DMs, across the whole bot.
+const DM_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Longest a DM waits for its turn before it's dropped.
+const MAX_WAIT: Duration = Duration::from_secs(30);
+
+/// How long a user whose DMs were closed isn't tried again, in seconds.
+const CLOSED_RETRY: u64 = 6 * 3600;
+
+/// What happened to a DM.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DmOutcome {
+    /// The DM was delivered.
+    Sent,
+    /// The user turned the category off.
+    Muted,
+    /// The user doesn't accept DMs from the bot.
+    Closed,
+    /// Too many DMs are queued, so this one was dropped.
+    RateLimited,
+}
+
+impl DmOutcome {
+    /// Whether a feature should reach the user another way, such as a mention
+    /// in a channel. Muted users asked not to be notified, so they aren't.
+    pub fn needs_fallback(self) -> bool {
+        matches!(self, Self::Closed | Self::RateLimited)
+    }
+}
+
+/// Whether an error is Discord refusing to deliver a DM.
+pub fn is_closed_dms(error: &SerenityError) -> bool {
+    match error {
+        SerenityError::Http(e) => matches!(
+            e.as_ref(),
+            HttpError::UnsuccessfulRequest(response) if response.error.code == CLOSED_DMS
+        ),
+        _ => false,
+    }
+}
+
+/// Key for storing the DM service in the client data.
+pub struct DmServiceKey;
+
+impl TypeMapKey for DmServiceKey {
+    type Value = Arc<DmService>;
+}
+
+/// Sends DMs while respecting preferences, closed DMs and the rate limit.
+pub struct DmService {
+    notifications: Arc<JsonStore<NotificationData>>,
+    /// When the next DM may be sent.
+    next_slot: AsyncMutex<Instant>,
+    /// Users whose DMs were closed, and when that was found out.
+    closed: Mutex<HashMap<UserId, u64>>,
+}
+
+impl DmService {
+    /// Create the service with the notification preference store.
+    pub fn new(notifications: Arc<JsonStore<NotificationData>>) -> Self {
+        Self {
+            notifications,
+            next_slot: AsyncMutex::new(Instant::now()),
+            closed: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether a recent DM to the user failed because their DMs are closed.
+    pub fn dms_closed(&self, user_id: UserId) -> bool {
+        let now = unix_timestamp();
+        let mut closed = self.closed.lock().unwrap_or_else(|e| e.into_inner());
+        closed.retain(|_, at| now.saturating_sub(*at) < CLOSED_RETRY);
+        closed.contains_key(&user_id)
+    }
+
+    /// Remember whether the user's DMs are closed.
+    fn record(&self, user_id: UserId, closed: bool) {
+        let mut users = self.closed.lock().unwrap_or_else(|e| e.into_inner());
+        if closed {
+            users.insert(user_id, unix_timestamp());
+        } else {
+            users.remove(&user_id);
+        }
+    }
+
+    /// Wait until a DM may be sent. Returns false if the wait would be too long.
+    async fn wait_turn(&self) -> bool {
+        let slot = {
+            let mut next = self.next_slot.lock().await;
+            let now = Instant::now();
+            let slot = (*next).max(now);
+            if slot - now > MAX_WAIT {
+                return false;
+            }
+            *next = slot + DM_INTERVAL;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+        true
+    }
+
+    /// Why a DM to the user can't go out, or `None` once it's its turn.
+    async fn hold(&self, user_id: UserId, category: Option<DmCategory>) -> Option<DmOutcome> {
+        if let Some(category) = category {
+            if !self.notifications.read().await.allows(user_id, category) {
+                return Some(DmOutcome::Muted);
+            }
+        }
+        if self.dms_closed(user_id) {
+            return Some(DmOutcome::Closed);
+        }
+        if !self.wait_turn().await {
+            return Some(DmOutcome::RateLimited);
+        }
+        None
+    }
+
+    /// DM a user. Messages with a category are skipped if the user turned it
+    /// off; messages without one are replies to something the user did.
+    ///
+    /// Closed DMs aren't an error, they're reported as [`DmOutcome::Closed`].
+    pub async fn send<'a, F>(
+        &self,
+        http: impl AsRef<Http>,
+        user_id: UserId,
+        category: Option<DmCategory>,
+        f: F,
+    ) -> serenity::Result<DmOutcome>
+    where
+        for<'b> F: FnOnce(&'b mut CreateMessage<'a>) -> &'b mut CreateMessage<'a>,
+    {
+        if let Some(outcome) = self.hold(user_id, category).await {
+            return Ok(outcome);
+        }
+
+        let http = http.as_ref();
+        let sent = match user_id.create_dm_channel(http).await {
+            Ok(channel) => channel.send_message(http, f).await,
+            Err(e) => Err(e),
+        };
+        match sent {
+            Ok(_) => {
+                self.record(user_id, false);
+                Ok(DmOutcome::Sent)
+            }
+            Err(e) if is_closed_dms(&e) => {
+                self.record(user_id, true);
+                Ok(DmOutcome::Closed)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// DM a user a file with an optional embed, fitting it under the upload
+    /// limit like [`send_file`]. Skipped and closed DMs work like [`Self::send`].
+    pub async fn send_file(
+        &self,
+        ctx: &Context,
+        user_id: UserId,
+        category: Option<DmCategory>,
+        file: OutgoingFile,
+        embed: Option<CreateEmbed>,
+    ) -> CommandResult<DmOutcome> {
+        if let Some(outcome) = self.hold(user_id, category).await {
+            return Ok(outcome);
+        }
+
+        let sent = match user_id.create_dm_channel(&ctx.http).await {
+            Ok(channel) => send_file(ctx, channel.id, None, file, embed).await,
+            Err(e) => Err(e.into()),
+        };
+        match sent {
+            Ok(_) => {
+                self.record(user_id, false);
+                Ok(DmOutcome::Sent)
+            }
+            Err(KurumiError::Discord(e)) if is_closed_dms(&e) => {
+                self.record(user_id, true);
+                Ok(DmOutcome::Closed)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// The DM service from the client data.
+pub async fn dm_service(ctx: &Context) -> Option<Arc<DmService>> {
+    let data = ctx.data.read().await;
+    data.get::<DmServiceKey>().cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+
+    #[tokio::test]
+    async fn remembers_closed_dms() {
+        let dir = std::env::temp_dir().join(format!("kurumi-dms-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let storage = Storage::new(dir).unwrap();
+        let dms = DmService::new(Arc::new(storage.open("notifications").await.unwrap()));
+        let user = UserId(1);
+        assert!(!dms.dms_closed(user));
+        dms.record(user, true);
+        assert!(dms.dms_closed(user));
+        dms.record(user, false);
+        assert!(!dms.dms_closed(user));
+        assert!(DmOutcome::Closed.needs_fallback());
+        assert!(!DmOutcome::Muted.needs_fallback());
+    }
+}
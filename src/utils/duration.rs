@@ -0,0 +1,259 @@
+//! Parsing human-written durations and rendering Discord timestamps.
+
+use std::fmt;
+use std::time::Duration;
+use thiserror::Error;
+
+/// An error from parsing a duration.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum DurationError {
+    #[error("No duration given.")]
+    Empty,
+    #[error("Expected a number before `{0}`.")]
+    MissingNumber(String),
+    #[error("`{0}` is missing a unit, like `{0}m` or `{0}h`.")]
+    MissingUnit(String),
+    #[error("Unknown time unit `{0}`. Use s, m, h, d, or w.")]
+    UnknownUnit(String),
+    #[error("That duration is too long.")]
+    Overflow,
+}
+
+/// Get the number of seconds in a unit, accepting short and long names.
+fn unit_seconds(unit: &str) -> Option<u64> {
+    let seconds = match unit {
+        "s" | "sec" | "secs" | "second" | "seconds" => 1,
+        "m" | "min" | "mins" | "minute" | "minutes" => 60,
+        "h" | "hr" | "hrs" | "hour" | "hours" => 3600,
+        "d" | "day" | "days" => 86400,
+        "w" | "wk" | "wks" | "week" | "weeks" => 604800,
+        _ => return None,
+    };
+
+    Some(seconds)
+}
+
+/// Parse a duration such as `30m`, `1d2h30m`, or `2 hours 15 minutes`.
+///
+/// Units are case-insensitive and whitespace between parts is ignored.
+pub fn parse(input: &str) -> Result<Duration, DurationError> {
+    let input = input.to_lowercase();
+    let mut chars = input.chars().filter(|c| !c.is_whitespace()).peekable();
+    if chars.peek().is_none() {
+        return Err(DurationError::Empty);
+    }
+
+    let mut total: u64 = 0;
+    while chars.peek().is_some() {
+        let number: String = std::iter::from_fn(|| chars.next_if(|c| c.is_ascii_digit())).collect();
+        let unit: String = std::iter::from_fn(|| chars.next_if(|c| !c.is_ascii_digit())).collect();
+
+        if number.is_empty() {
+            return Err(DurationError::MissingNumber(unit));
+        }
+        if unit.is_empty() {
+            return Err(DurationError::MissingUnit(number));
+        }
+
+        let seconds = unit_seconds(&unit).ok_or(DurationError::UnknownUnit(unit))?;
+        let amount: u64 = number.parse().map_err(|_| DurationError::Overflow)?;
+        total = amount
+            .checked_mul(seconds)
+            .and_then(|part| total.checked_add(part))
+            .ok_or(DurationError::Overflow)?;
+    }
+
+    Ok(Duration::from_secs(total))
+}
+
+/// Render a duration compactly in the format [`parse`] accepts, such as `1d2h30m`.
+pub fn format_compact(duration: Duration) -> String {
+    let mut seconds = duration.as_secs();
+    if seconds == 0 {
+        return "0s".to_string();
+    }
+
+    let mut out = String::new();
+    for (unit, size) in [
+        ("w", 604800),
+        ("d", 86400),
+        ("h", 3600),
+        ("m", 60),
+        ("s", 1),
+    ] {
+        if seconds >= size {
+            out.push_str(&format!("{}{}", seconds / size, unit));
+            seconds %= size;
+        }
+    }
+    out
+}
+
+/// Render a duration like a media player's clock, such as `3:05` or `1:02:03`.
+pub fn format_clock(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// How Discord renders a timestamp.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimestampStyle {
+    /// `16:20`
+    ShortTime,
+    /// `16:20:30`
+    LongTime,
+    /// `20/04/2021`
+    ShortDate,
+    /// `20 April 2021`
+    LongDate,
+    /// `20 April 2021 16:20`
+    ShortDateTime,
+    /// `Tuesday, 20 April 2021 16:20`
+    LongDateTime,
+    /// `2 months ago` or `in 3 hours`
+    Relative,
+}
+
+impl TimestampStyle {
+    /// Every style, in the order Discord's docs list them.
+    pub const ALL: [TimestampStyle; 7] = [
+        TimestampStyle::ShortTime,
+        TimestampStyle::LongTime,
+        TimestampStyle::ShortDate,
+        TimestampStyle::LongDate,
+        TimestampStyle::ShortDateTime,
+        TimestampStyle::LongDateTime,
+        TimestampStyle::Relative,
+    ];
+
+    /// The letter Discord uses for the style, like `R` for relative.
+    pub fn flag(self) -> char {
+        match self {
+            TimestampStyle::ShortTime => 't',
+            TimestampStyle::LongTime => 'T',
+            TimestampStyle::ShortDate => 'd',
+            TimestampStyle::LongDate => 'D',
+            TimestampStyle::ShortDateTime => 'f',
+            TimestampStyle::LongDateTime => 'F',
+            TimestampStyle::Relative => 'R',
+        }
+    }
+
+    /// Find a style by its letter.
+    pub fn from_flag(flag: char) -> Option<Self> {
+        Self::ALL.into_iter().find(|style| style.flag() == flag)
+    }
+
+    /// A readable name for the style.
+    pub fn name(self) -> &'static str {
+        match self {
+            TimestampStyle::ShortTime => "Short time",
+            TimestampStyle::LongTime => "Long time",
+            TimestampStyle::ShortDate => "Short date",
+            TimestampStyle::LongDate => "Long date",
+            TimestampStyle::ShortDateTime => "Short date and time",
+            TimestampStyle::LongDateTime => "Long date and time",
+            TimestampStyle::Relative => "Relative",
+        }
+    }
+}
+
+/// A Unix timestamp rendered as a Discord timestamp tag, like `<t:1618953630:R>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DiscordTimestamp {
+    /// Seconds since the Unix epoch.
+    pub unix: u64,
+    /// How Discord should render it.
+    pub style: TimestampStyle,
+}
+
+impl fmt::Display for DiscordTimestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<t:{}:{}>", self.unix, self.style.flag())
+    }
+}
+
+/// Render a Unix timestamp in the given style.
+pub fn timestamp(unix: u64, style: TimestampStyle) -> DiscordTimestamp {
+    DiscordTimestamp { unix, style }
+}
+
+/// Render a Unix timestamp relative to now, like `in 3 hours`.
+pub fn relative(unix: u64) -> DiscordTimestamp {
+    timestamp(unix, TimestampStyle::Relative)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_single_and_combined_units() {
+        assert_eq!(parse("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(
+            parse("1d2h30m"),
+            Ok(Duration::from_secs(86400 + 7200 + 1800))
+        );
+        assert_eq!(parse("2w"), Ok(Duration::from_secs(2 * 604800)));
+    }
+
+    #[test]
+    fn accepts_long_units_spaces_and_case() {
+        assert_eq!(parse("2 Hours 15 min"), Ok(Duration::from_secs(8100)));
+        assert_eq!(parse(" 1D 1M "), Ok(Duration::from_secs(86460)));
+    }
+
+    #[test]
+    fn repeated_units_add_up() {
+        assert_eq!(parse("1m1m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse("0s"), Ok(Duration::ZERO));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert_eq!(parse(""), Err(DurationError::Empty));
+        assert_eq!(parse("   "), Err(DurationError::Empty));
+        assert_eq!(parse("10"), Err(DurationError::MissingUnit("10".into())));
+        assert_eq!(parse("h"), Err(DurationError::MissingNumber("h".into())));
+        assert_eq!(parse("5y"), Err(DurationError::UnknownUnit("y".into())));
+        assert_eq!(parse("1.5h"), Err(DurationError::UnknownUnit(".".into())));
+        assert_eq!(parse("-5m"), Err(DurationError::MissingNumber("-".into())));
+    }
+
+    #[test]
+    fn rejects_overflow() {
+        assert_eq!(parse("99999999999999999999s"), Err(DurationError::Overflow));
+        assert_eq!(parse("18446744073709551615w"), Err(DurationError::Overflow));
+    }
+
+    #[test]
+    fn formats_compactly_and_round_trips() {
+        assert_eq!(format_compact(Duration::ZERO), "0s");
+        assert_eq!(format_compact(Duration::from_secs(95)), "1m35s");
+        assert_eq!(format_compact(Duration::from_secs(694800)), "1w1d1h");
+
+        let duration = Duration::from_secs(1_234_567);
+        assert_eq!(parse(&format_compact(duration)), Ok(duration));
+    }
+
+    #[test]
+    fn formats_clocks() {
+        assert_eq!(format_clock(Duration::ZERO), "0:00");
+        assert_eq!(format_clock(Duration::from_secs(185)), "3:05");
+        assert_eq!(format_clock(Duration::from_secs(3723)), "1:02:03");
+    }
+
+    #[test]
+    fn renders_discord_timestamps() {
+        assert_eq!(relative(1618953630).to_string(), "<t:1618953630:R>");
+        assert_eq!(
+            timestamp(0, TimestampStyle::LongDateTime).to_string(),
+            "<t:0:F>"
+        );
+    }
+}
@@ -0,0 +1,147 @@
+//! Syncing premium tiers from Discord SKU entitlements.
+//!
+//! Entitlements for the SKUs in `[premium] skus` are fetched when the bot starts and
+//! again every `sync_interval` seconds, and stored as premium grants. Tiers granted
+//! by owners with `premium` are left alone.
+
+use serenity::http::Http;
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use crate::framework::KurumiError;
+use crate::models::config::PremiumConfig;
+use crate::models::premium::{Entitlement, PremiumData, Tier};
+use crate::storage::JsonStore;
+use crate::utils::helpers::unix_timestamp;
+
+/// Discord API base for the entitlements endpoint, which Serenity doesn't cover.
+const API_BASE: &str = "https://discord.com/api/v10";
+
+/// Most entitlements Discord returns per page.
+const PAGE_SIZE: usize = 100;
+
+/// How long to wait for Discord to respond.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Key for storing the entitlement sync in the client data.
+pub struct EntitlementSyncKey;
+
+impl TypeMapKey for EntitlementSyncKey {
+    type Value = Arc<EntitlementSync>;
+}
+
+/// Fetches entitlements from Discord and stores them as premium grants.
+pub struct EntitlementSync {
+    token: String,
+    skus: HashMap<u64, Tier>,
+    interval: u64,
+    store: Arc<JsonStore<PremiumData>>,
+    client: reqwest::Client,
+    application_id: OnceCell<u64>,
+}
+
+impl EntitlementSync {
+    /// Create the sync for the bot's token and premium settings.
+    pub fn new(token: String, config: &PremiumConfig, store: Arc<JsonStore<PremiumData>>) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            token,
+            skus: config.skus.iter().map(|sku| (sku.id, sku.tier)).collect(),
+            interval: config.sync_interval,
+            store,
+            client,
+            application_id: OnceCell::new(),
+        }
+    }
+
+    /// Whether any SKUs are configured, so there is something to sync.
+    pub fn is_enabled(&self) -> bool {
+        !self.skus.is_empty()
+    }
+
+    /// Fetch the entitlements and update the stored grants. Returns how many grants
+    /// changed.
+    pub async fn sync(&self) -> Result<usize, KurumiError> {
+        if !self.is_enabled() {
+            return Ok(0);
+        }
+
+        let entitlements = self.fetch().await?;
+        let changed = self
+            .store
+            .update(|data| data.apply_entitlements(&entitlements, &self.skus, unix_timestamp()))
+            .await?;
+        Ok(changed)
+    }
+
+    /// Run [`EntitlementSync::sync`] now and then every `sync_interval` seconds, if
+    /// syncing is on and SKUs are configured.
+    pub fn spawn(self: Arc<Self>) -> Option<JoinHandle<()>> {
+        if self.interval == 0 || !self.is_enabled() {
+            return None;
+        }
+
+        let period = Duration::from_secs(self.interval.max(60));
+        Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                match self.sync().await {
+                    Ok(0) => {}
+                    Ok(changed) => info!("Entitlement sync changed {} premium grants", changed),
+                    Err(e) => error!("Entitlement sync failed: {}", e),
+                }
+            }
+        }))
+    }
+
+    /// Get every active entitlement for the application, a page at a time.
+    async fn fetch(&self) -> Result<Vec<Entitlement>, KurumiError> {
+        let application_id = self
+            .application_id
+            .get_or_try_init(|| async {
+                let info = Http::new(&self.token)
+                    .get_current_application_info()
+                    .await?;
+                Ok::<_, KurumiError>(info.id.0)
+            })
+            .await?;
+
+        let url = format!("{}/applications/{}/entitlements", API_BASE, application_id);
+        let mut entitlements: Vec<Entitlement> = Vec::new();
+        loop {
+            // Paging with `after` returns the oldest entitlements first
+            let after = entitlements.last().map_or(0, |last| last.id);
+            let query = [
+                ("limit", PAGE_SIZE.to_string()),
+                ("exclude_ended", "true".to_string()),
+                ("after", after.to_string()),
+            ];
+
+            let page: Vec<Entitlement> = self
+                .client
+                .get(&url)
+                .header("Authorization", format!("Bot {}", self.token))
+                .query(&query)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            let done = page.len() < PAGE_SIZE;
+            entitlements.extend(page);
+            if done {
+                return Ok(entitlements);
+            }
+        }
+    }
+}
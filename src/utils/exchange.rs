@@ -0,0 +1,257 @@
+//! Cached currency exchange rates and crypto prices.
+//!
+//! Fiat rates are fetched for every currency at once and kept for `rates_ttl`
+//! seconds. Crypto prices are fetched per coin and currency and kept for
+//! `crypto_ttl` seconds, since they move much faster.
+
+use serde::Deserialize;
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use crate::framework::command_handler::CommandResult;
+use crate::models::config::ExchangeConfig;
+use crate::utils::helpers::unix_timestamp;
+
+/// How long to wait for a rate provider to respond.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Coin symbols and the CoinGecko IDs they stand for. Other coins can be looked
+/// up by their CoinGecko ID.
+const COINS: &[(&str, &str)] = &[
+    ("btc", "bitcoin"),
+    ("eth", "ethereum"),
+    ("usdt", "tether"),
+    ("usdc", "usd-coin"),
+    ("bnb", "binancecoin"),
+    ("sol", "solana"),
+    ("xrp", "ripple"),
+    ("ada", "cardano"),
+    ("doge", "dogecoin"),
+    ("trx", "tron"),
+    ("dot", "polkadot"),
+    ("ltc", "litecoin"),
+    ("xmr", "monero"),
+    ("ton", "the-open-network"),
+    ("avax", "avalanche-2"),
+    ("shib", "shiba-inu"),
+    ("link", "chainlink"),
+    ("matic", "matic-network"),
+];
+
+/// Key for storing the exchange rate provider in the client data.
+pub struct ExchangeKey;
+
+impl TypeMapKey for ExchangeKey {
+    type Value = Arc<Exchange>;
+}
+
+/// Fiat exchange rates against a base currency.
+#[derive(Clone, Debug)]
+pub struct Rates {
+    pub base: String,
+    /// Units of each currency per unit of the base, by uppercase code.
+    pub rates: HashMap<String, f64>,
+    /// When the provider last updated the rates (seconds since the Unix epoch).
+    pub updated_at: u64,
+}
+
+impl Rates {
+    /// Whether a currency code is known, ignoring case.
+    pub fn has(&self, code: &str) -> bool {
+        self.rate(code).is_some()
+    }
+
+    fn rate(&self, code: &str) -> Option<f64> {
+        let code = code.to_uppercase();
+        if code == self.base {
+            return Some(1.0);
+        }
+        self.rates.get(&code).copied().filter(|rate| *rate > 0.0)
+    }
+
+    /// Convert an amount between currencies, or `None` if either is unknown.
+    pub fn convert(&self, amount: f64, from: &str, to: &str) -> Option<f64> {
+        Some(amount / self.rate(from)? * self.rate(to)?)
+    }
+}
+
+/// The price of a coin in one currency.
+#[derive(Clone, Debug)]
+pub struct CoinPrice {
+    /// The coin's CoinGecko ID.
+    pub id: String,
+    /// The lowercase currency the price is in.
+    pub currency: String,
+    pub price: f64,
+    /// Change over the last 24 hours, in percent.
+    pub change_24h: Option<f64>,
+    /// When the price was last updated (seconds since the Unix epoch).
+    pub updated_at: u64,
+}
+
+/// A response from the fiat rate API.
+#[derive(Deserialize)]
+struct RatesResponse {
+    base_code: String,
+    #[serde(default)]
+    time_last_update_unix: Option<u64>,
+    rates: HashMap<String, f64>,
+}
+
+/// The CoinGecko ID for a coin symbol or ID, ignoring case.
+pub fn coin_id(name: &str) -> Option<&'static str> {
+    let name = name.trim().to_lowercase();
+    COINS
+        .iter()
+        .find(|(symbol, id)| *symbol == name || *id == name)
+        .map(|(_, id)| *id)
+}
+
+/// The symbol for a known CoinGecko ID, for showing prices.
+pub fn coin_symbol(id: &str) -> Option<&'static str> {
+    COINS
+        .iter()
+        .find(|(_, coin)| *coin == id)
+        .map(|(symbol, _)| *symbol)
+}
+
+/// Fetches and caches exchange rates and crypto prices.
+pub struct Exchange {
+    config: ExchangeConfig,
+    client: reqwest::Client,
+    /// The fiat rates and when they were fetched. Held while fetching, so only one
+    /// request is made when the cache runs out.
+    rates: Mutex<Option<(Instant, Arc<Rates>)>>,
+    /// Crypto prices by coin ID and currency.
+    prices: Mutex<HashMap<(String, String), (Instant, CoinPrice)>>,
+}
+
+impl Exchange {
+    /// Create a provider with empty caches.
+    pub fn new(config: ExchangeConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            config,
+            client,
+            rates: Mutex::new(None),
+            prices: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The latest fiat rates, fetched again once they are older than `rates_ttl`.
+    pub async fn rates(&self) -> CommandResult<Arc<Rates>> {
+        let mut cached = self.rates.lock().await;
+        let ttl = Duration::from_secs(self.config.rates_ttl);
+        if let Some((fetched, rates)) = cached.as_ref() {
+            if fetched.elapsed() < ttl {
+                return Ok(rates.clone());
+            }
+        }
+
+        let response: RatesResponse = self
+            .client
+            .get(&self.config.rates_url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let rates = Arc::new(Rates {
+            base: response.base_code.to_uppercase(),
+            rates: response
+                .rates
+                .into_iter()
+                .map(|(code, rate)| (code.to_uppercase(), rate))
+                .collect(),
+            updated_at: response
+                .time_last_update_unix
+                .unwrap_or_else(unix_timestamp),
+        });
+        *cached = Some((Instant::now(), rates.clone()));
+        Ok(rates)
+    }
+
+    /// The price of a coin in a currency, or `None` if CoinGecko doesn't know the
+    /// coin or currency.
+    pub async fn price(&self, coin: &str, currency: &str) -> CommandResult<Option<CoinPrice>> {
+        let key = (coin.to_lowercase(), currency.to_lowercase());
+        let ttl = Duration::from_secs(self.config.crypto_ttl);
+        if let Some((fetched, price)) = self.prices.lock().await.get(&key) {
+            if fetched.elapsed() < ttl {
+                return Ok(Some(price.clone()));
+            }
+        }
+
+        let response: HashMap<String, HashMap<String, Option<f64>>> = self
+            .client
+            .get(&self.config.crypto_url)
+            .query(&[
+                ("ids", key.0.as_str()),
+                ("vs_currencies", key.1.as_str()),
+                ("include_24hr_change", "true"),
+                ("include_last_updated_at", "true"),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let fields = match response.get(&key.0) {
+            Some(fields) => fields,
+            None => return Ok(None),
+        };
+        let price = match fields.get(&key.1).copied().flatten() {
+            Some(price) => price,
+            None => return Ok(None),
+        };
+        let price = CoinPrice {
+            id: key.0.clone(),
+            currency: key.1.clone(),
+            price,
+            change_24h: fields
+                .get(&format!("{}_24h_change", key.1))
+                .copied()
+                .flatten(),
+            updated_at: fields
+                .get("last_updated_at")
+                .copied()
+                .flatten()
+                .map(|at| at as u64)
+                .unwrap_or_else(unix_timestamp),
+        };
+
+        let mut prices = self.prices.lock().await;
+        prices.retain(|_, (fetched, _)| fetched.elapsed() < ttl);
+        prices.insert(key, (Instant::now(), price.clone()));
+        Ok(Some(price))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_through_the_base_currency() {
+        let rates = Rates {
+            base: "USD".to_string(),
+            rates: HashMap::from([("EUR".to_string(), 0.5), ("GBP".to_string(), 0.25)]),
+            updated_at: 0,
+        };
+        assert_eq!(rates.convert(10.0, "usd", "EUR"), Some(5.0));
+        assert_eq!(rates.convert(10.0, "EUR", "USD"), Some(20.0));
+        assert_eq!(rates.convert(10.0, "eur", "gbp"), Some(5.0));
+        assert_eq!(rates.convert(10.0, "EUR", "XYZ"), None);
+
+        assert_eq!(coin_id("BTC"), Some("bitcoin"));
+        assert_eq!(coin_id("ethereum"), Some("ethereum"));
+        assert_eq!(coin_id("usd"), None);
+    }
+}
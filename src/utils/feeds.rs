@@ -0,0 +1,202 @@
+//! Announces new posts from subreddit feeds.
+//!
+//! Every `[feeds] interval` seconds, the watcher reads the newest posts of each
+//! subreddit with a feed and posts the ones it hasn't announced yet in the feed's
+//! channel. Age-restricted posts only go to age-restricted channels.
+
+use serenity::cache::Cache;
+use serenity::http::Http;
+use serenity::model::id::{ChannelId, GuildId};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::models::config::FeedsConfig;
+use crate::models::feeds::FeedData;
+use crate::storage::JsonStore;
+use crate::utils::helpers::is_nsfw_cached;
+use crate::utils::reddit::{post_embed, Post, Reddit};
+use crate::utils::rest::{Priority, RestPolicy};
+
+/// Most posts announced in a channel per check, so a busy subreddit can't flood it.
+const MAX_PER_CHECK: usize = 5;
+
+/// The posts to announce from a listing, oldest first: those newer than `since`
+/// that aren't pinned, leaving out age-restricted ones unless `nsfw` is set.
+fn fresh_posts(posts: &[Post], since: f64, nsfw: bool) -> Vec<&Post> {
+    let mut fresh: Vec<&Post> = posts
+        .iter()
+        .filter(|post| post.created_utc > since && !post.stickied)
+        .filter(|post| nsfw || !post.over_18)
+        .collect();
+    fresh.sort_by(|a, b| b.created_utc.total_cmp(&a.created_utc));
+    fresh.truncate(MAX_PER_CHECK);
+    fresh.reverse();
+    fresh
+}
+
+/// Checks subreddit feeds in the background.
+pub struct FeedWatcher {
+    reddit: Arc<Reddit>,
+    feeds: Arc<JsonStore<FeedData>>,
+    http: Arc<Http>,
+    cache: Arc<Cache>,
+    rest: Arc<RestPolicy>,
+    interval: Duration,
+}
+
+impl FeedWatcher {
+    /// Create a watcher for the feeds in the store.
+    pub fn new(
+        config: &FeedsConfig,
+        reddit: Arc<Reddit>,
+        feeds: Arc<JsonStore<FeedData>>,
+        http: Arc<Http>,
+        cache: Arc<Cache>,
+        rest: Arc<RestPolicy>,
+    ) -> Self {
+        Self {
+            reddit,
+            feeds,
+            http,
+            cache,
+            rest,
+            interval: Duration::from_secs(config.interval.max(60)),
+        }
+    }
+
+    /// Check the feeds every interval until the bot stops.
+    pub fn spawn(self) -> JoinHandle<()> {
+        info!(
+            "Checking subreddit feeds every {}s",
+            self.interval.as_secs()
+        );
+        tokio::spawn(async move {
+            let start = tokio::time::Instant::now() + self.interval;
+            let mut interval = tokio::time::interval_at(start, self.interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                self.rest.usage().wait_for_headroom("subreddit feeds").await;
+                self.check().await;
+            }
+        })
+    }
+
+    /// Announce new posts for every subreddit with a feed.
+    async fn check(&self) {
+        let subreddits = self.feeds.read().await.subreddits();
+        for subreddit in subreddits {
+            let posts = match self.reddit.newest(&subreddit).await {
+                Ok(Some(posts)) => posts,
+                Ok(None) => {
+                    debug!("Skipping feed for unreadable r/{}", subreddit);
+                    continue;
+                }
+                Err(e) => {
+                    warn!("Failed to check r/{}: {}", subreddit, e);
+                    continue;
+                }
+            };
+            let newest = match posts.iter().map(|post| post.created_utc).reduce(f64::max) {
+                Some(newest) => newest,
+                None => continue,
+            };
+
+            let feeds: Vec<(u64, u64, f64)> = self
+                .feeds
+                .read()
+                .await
+                .guilds
+                .iter()
+                .flat_map(|(guild_id, feeds)| {
+                    feeds
+                        .iter()
+                        .filter(|feed| feed.subreddit == subreddit)
+                        .map(|feed| (*guild_id, feed.channel_id, feed.last_created))
+                })
+                .collect();
+            for (guild_id, channel_id, since) in feeds {
+                if newest <= since {
+                    continue;
+                }
+                let channel_id = ChannelId(channel_id);
+                let nsfw = is_nsfw_cached(&self.cache, channel_id);
+                for post in fresh_posts(&posts, since, nsfw) {
+                    let embed = post_embed(post);
+                    let result = self
+                        .rest
+                        .call_with(Priority::Analytics, "send_message", || {
+                            let embed = embed.clone();
+                            channel_id.send_message(&self.http, |m| m.set_embed(embed))
+                        })
+                        .await;
+                    if let Err(e) = result {
+                        warn!(
+                            "Failed to announce r/{} in {}: {}",
+                            subreddit, channel_id, e
+                        );
+                        break;
+                    }
+                }
+                self.mark_seen(GuildId(guild_id), channel_id, &subreddit, newest)
+                    .await;
+            }
+        }
+    }
+
+    /// Save the newest post seen by a feed.
+    async fn mark_seen(&self, guild_id: GuildId, channel_id: ChannelId, subreddit: &str, at: f64) {
+        let result = self
+            .feeds
+            .update(|data| {
+                let feed = data.guilds.get_mut(&guild_id.0).and_then(|feeds| {
+                    feeds
+                        .iter_mut()
+                        .find(|f| f.subreddit == subreddit && f.channel_id == channel_id.0)
+                });
+                if let Some(feed) = feed {
+                    feed.last_created = feed.last_created.max(at);
+                }
+            })
+            .await;
+        if let Err(e) = result {
+            warn!("Failed to save feed progress for r/{}: {}", subreddit, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(id: &str, created_utc: f64, over_18: bool) -> Post {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "title": id,
+            "subreddit": "rust",
+            "author": "someone",
+            "permalink": format!("/r/rust/comments/{}/", id),
+            "over_18": over_18,
+            "created_utc": created_utc,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn announces_new_posts_oldest_first() {
+        let posts: Vec<Post> = (0..8)
+            .rev()
+            .map(|i| post(&i.to_string(), 100.0 + i as f64, i == 7))
+            .collect();
+        let ids = |posts: Vec<&Post>| posts.iter().map(|p| p.id.clone()).collect::<Vec<_>>();
+
+        assert_eq!(ids(fresh_posts(&posts, 104.0, false)), ["5", "6"]);
+        assert_eq!(ids(fresh_posts(&posts, 104.0, true)), ["5", "6", "7"]);
+        assert_eq!(
+            ids(fresh_posts(&posts, 0.0, true)),
+            ["3", "4", "5", "6", "7"]
+        );
+    }
+}
@@ -0,0 +1,272 @@
+//! Sending generated files while respecting Discord's upload limits.
+//!
+//! Files that are too large are gzipped, then split into parts if they are text.
+//! Text that still doesn't fit goes to the paste service, and anything else is
+//! uploaded to the service in the `[uploads]` config section.
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serenity::builder::CreateEmbed;
+use serenity::model::channel::{AttachmentType, Message};
+use serenity::model::guild::PremiumTier;
+use serenity::model::id::{ChannelId, GuildId};
+use serenity::prelude::*;
+use std::io::Write;
+use tracing::{debug, warn};
+
+use crate::framework::command_handler::CommandResult;
+use crate::utils::constants::{
+    MAX_ATTACHMENTS_PER_MESSAGE, UPLOAD_LIMIT_BASE, UPLOAD_LIMIT_TIER_2, UPLOAD_LIMIT_TIER_3,
+};
+use crate::utils::helpers::BotConfigKey;
+use crate::utils::paste::{paste, paste_config};
+
+/// A file to send.
+#[derive(Clone, Debug)]
+pub struct OutgoingFile {
+    /// The file name shown in Discord.
+    pub name: String,
+    /// The file contents.
+    pub data: Vec<u8>,
+}
+
+impl OutgoingFile {
+    /// Create a file from its name and contents.
+    pub fn new(name: impl Into<String>, data: impl Into<Vec<u8>>) -> Self {
+        Self {
+            name: name.into(),
+            data: data.into(),
+        }
+    }
+}
+
+/// Get the largest file the bot can upload in a guild, in bytes.
+pub fn upload_limit(ctx: &Context, guild_id: Option<GuildId>) -> usize {
+    let tier =
+        guild_id.and_then(|guild_id| ctx.cache.guild_field(guild_id, |guild| guild.premium_tier));
+
+    match tier {
+        Some(PremiumTier::Tier3) => UPLOAD_LIMIT_TIER_3,
+        Some(PremiumTier::Tier2) => UPLOAD_LIMIT_TIER_2,
+        _ => UPLOAD_LIMIT_BASE,
+    }
+}
+
+/// Gzip a file, returning `None` if that doesn't make it smaller.
+fn compress(file: &OutgoingFile) -> Option<OutgoingFile> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(&file.data).ok()?;
+    let data = encoder.finish().ok()?;
+
+    (data.len() < file.data.len()).then(|| OutgoingFile::new(format!("{}.gz", file.name), data))
+}
+
+/// Split a text file on line boundaries into parts of at most `limit` bytes.
+///
+/// Returns `None` for binary files or lines longer than the limit.
+fn split_text(file: &OutgoingFile, limit: usize) -> Option<Vec<OutgoingFile>> {
+    let text = std::str::from_utf8(&file.data).ok()?;
+    let (stem, extension) = match file.name.rsplit_once('.') {
+        Some((stem, extension)) => (stem, format!(".{}", extension)),
+        None => (file.name.as_str(), String::new()),
+    };
+
+    let mut parts: Vec<String> = vec![String::new()];
+    for line in text.split_inclusive('\n') {
+        if line.len() > limit {
+            return None;
+        }
+        let current = parts.last_mut()?;
+        if current.len() + line.len() > limit {
+            parts.push(line.to_string());
+        } else {
+            current.push_str(line);
+        }
+    }
+
+    Some(
+        parts
+            .into_iter()
+            .enumerate()
+            .map(|(i, part)| {
+                OutgoingFile::new(format!("{}.part{}{}", stem, i + 1, extension), part)
+            })
+            .collect(),
+    )
+}
+
+/// Paste a text file to the paste service, returning its URL.
+async fn paste_text(ctx: &Context, file: &OutgoingFile) -> Option<String> {
+    let text = std::str::from_utf8(&file.data).ok()?;
+    let config = paste_config(ctx).await;
+
+    match paste(&config, &file.name, text).await {
+        Ok(url) => Some(url),
+        Err(e) => {
+            warn!("Failed to paste {}: {}", file.name, e);
+            None
+        }
+    }
+}
+
+/// Upload a file to the configured fallback service and return its URL.
+async fn upload_fallback(ctx: &Context, file: OutgoingFile) -> CommandResult<Option<String>> {
+    let config = {
+        let data = ctx.data.read().await;
+        data.get::<BotConfigKey>()
+            .map(|config| config.uploads.clone())
+    };
+    let url = match config.and_then(|config| config.fallback_url) {
+        Some(url) => url,
+        None => return Ok(None),
+    };
+
+    let part = reqwest::multipart::Part::bytes(file.data).file_name(file.name);
+    let form = reqwest::multipart::Form::new().part("file", part);
+    let response = reqwest::Client::new()
+        .post(&url)
+        .multipart(form)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+
+    let link = response.trim();
+    if !link.starts_with("http") {
+        return Err(format!("Unexpected response from upload service: {}", link).into());
+    }
+    Ok(Some(link.to_string()))
+}
+
+/// Send files with an optional embed, fitting them under the channel's upload limit.
+///
+/// Oversized files are compressed, split, pasted, or uploaded to the fallback
+/// service in that order; a pasted or uploaded file is linked in the message instead.
+pub async fn send_files(
+    ctx: &Context,
+    channel_id: ChannelId,
+    guild_id: Option<GuildId>,
+    files: Vec<OutgoingFile>,
+    embed: Option<CreateEmbed>,
+) -> CommandResult<Message> {
+    let limit = upload_limit(ctx, guild_id);
+
+    let mut attachments = Vec::new();
+    let mut links = Vec::new();
+    for file in files {
+        if file.data.len() <= limit {
+            attachments.push(file);
+            continue;
+        }
+
+        debug!(
+            "{} is {} bytes, over the {} byte limit",
+            file.name,
+            file.data.len(),
+            limit
+        );
+        if let Some(compressed) = compress(&file).filter(|f| f.data.len() <= limit) {
+            attachments.push(compressed);
+        } else if let Some(parts) = split_text(&file, limit)
+            .filter(|parts| attachments.len() + parts.len() <= MAX_ATTACHMENTS_PER_MESSAGE)
+        {
+            attachments.extend(parts);
+        } else if let Some(url) = paste_text(ctx, &file).await {
+            links.push(format!("📎 [{}]({})", file.name, url));
+        } else {
+            let name = file.name.clone();
+            match upload_fallback(ctx, file).await {
+                Ok(Some(url)) => links.push(format!("📎 [{}]({})", name, url)),
+                Ok(None) => {
+                    return Err(format!(
+                        "{} is too large to send and no upload service is configured",
+                        name
+                    )
+                    .into())
+                }
+                Err(e) => {
+                    warn!("Failed to upload {}: {}", name, e);
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    if attachments.len() > MAX_ATTACHMENTS_PER_MESSAGE {
+        return Err(format!(
+            "Can't send more than {} files in one message",
+            MAX_ATTACHMENTS_PER_MESSAGE
+        )
+        .into());
+    }
+
+    let message = channel_id
+        .send_message(&ctx.http, |m| {
+            match embed {
+                Some(mut embed) => {
+                    if !links.is_empty() {
+                        embed.field("Files", links.join("\n"), false);
+                    }
+                    m.set_embed(embed);
+                }
+                None if !links.is_empty() => {
+                    m.content(links.join("\n"));
+                }
+                None => {}
+            }
+
+            for file in attachments {
+                m.add_file(AttachmentType::Bytes {
+                    data: file.data.into(),
+                    filename: file.name,
+                });
+            }
+            m
+        })
+        .await?;
+
+    Ok(message)
+}
+
+/// Send a single file with an optional embed, see [`send_files`].
+pub async fn send_file(
+    ctx: &Context,
+    channel_id: ChannelId,
+    guild_id: Option<GuildId>,
+    file: OutgoingFile,
+    embed: Option<CreateEmbed>,
+) -> CommandResult<Message> {
+    send_files(ctx, channel_id, guild_id, vec![file], embed).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_text_on_lines() {
+        let file = OutgoingFile::new("log.txt", "aaa\nbbb\nccc\n");
+        let parts = split_text(&file, 8).unwrap();
+
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].name, "log.part1.txt");
+        assert_eq!(parts[0].data, b"aaa\nbbb\n");
+        assert_eq!(parts[1].data, b"ccc\n");
+    }
+
+    #[test]
+    fn does_not_split_binary_or_long_lines() {
+        assert!(split_text(&OutgoingFile::new("a.bin", vec![0xff, 0xfe]), 8).is_none());
+        assert!(split_text(&OutgoingFile::new("a.txt", "0123456789"), 8).is_none());
+    }
+
+    #[test]
+    fn compresses_repetitive_data() {
+        let file = OutgoingFile::new("a.txt", "spam ".repeat(1000));
+        let compressed = compress(&file).unwrap();
+
+        assert_eq!(compressed.name, "a.txt.gz");
+        assert!(compressed.data.len() < file.data.len());
+    }
+}
@@ -0,0 +1,236 @@
+//! Heartbeat pings to an outside monitor.
+//!
+//! While the bot runs, it pings `[monitoring] heartbeat_url` every
+//! `heartbeat_interval` seconds with the status of each shard. The monitor alerts
+//! operators when the pings stop, so a bot that dies without logging anything is
+//! still noticed. See [`HeartbeatKind`] for the supported monitors.
+
+use reqwest::Method;
+use serde::Serialize;
+use serenity::cache::Cache;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::models::config::{HeartbeatKind, MonitoringConfig};
+use crate::models::shard_health::{ShardHealth, ShardStatus};
+use crate::utils::secrets;
+
+/// How long to wait for the monitor to respond.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Overall health reported with a heartbeat.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Health {
+    /// Every shard is connected.
+    Up,
+    /// Some shards are connected.
+    Degraded,
+    /// No shard is connected.
+    Down,
+}
+
+impl fmt::Display for Health {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Health::Up => write!(f, "up"),
+            Health::Degraded => write!(f, "degraded"),
+            Health::Down => write!(f, "down"),
+        }
+    }
+}
+
+/// A single shard in a heartbeat.
+#[derive(Clone, Debug, Serialize)]
+pub struct ShardReport {
+    pub id: u64,
+    pub stage: String,
+    pub connected: bool,
+    /// Seconds the shard has been down, while it is.
+    pub down_for: Option<u64>,
+    pub disconnects: u32,
+    pub resumes: u32,
+}
+
+/// What a heartbeat tells the monitor.
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    pub status: Health,
+    pub guilds: usize,
+    pub shards: Vec<ShardReport>,
+}
+
+impl Report {
+    /// Build a report from the shard statuses.
+    pub fn new(statuses: &[(u64, ShardStatus)], guilds: usize) -> Self {
+        let connected = statuses.iter().filter(|(_, s)| s.is_connected()).count();
+        let status = if connected == 0 {
+            Health::Down
+        } else if connected < statuses.len() {
+            Health::Degraded
+        } else {
+            Health::Up
+        };
+        let shards = statuses
+            .iter()
+            .map(|(id, s)| ShardReport {
+                id: *id,
+                stage: s.stage.to_string(),
+                connected: s.is_connected(),
+                down_for: s.downtime().map(|d| d.as_secs()),
+                disconnects: s.disconnects,
+                resumes: s.resumes,
+            })
+            .collect();
+        Self {
+            status,
+            guilds,
+            shards,
+        }
+    }
+
+    /// A one line summary, such as `degraded: 3/4 shards connected, 1200 servers`.
+    pub fn summary(&self) -> String {
+        let connected = self.shards.iter().filter(|s| s.connected).count();
+        format!(
+            "{}: {}/{} shards connected, {} servers",
+            self.status,
+            connected,
+            self.shards.len(),
+            self.guilds
+        )
+    }
+}
+
+/// Pings the monitor on an interval.
+pub struct Heartbeat {
+    url: String,
+    kind: HeartbeatKind,
+    interval: Duration,
+    health: Arc<ShardHealth>,
+    cache: Arc<Cache>,
+    client: reqwest::Client,
+}
+
+impl Heartbeat {
+    /// Create the heartbeat, or `None` when no URL is set in the config or the
+    /// `heartbeat_url` secret.
+    pub fn new(
+        config: &MonitoringConfig,
+        health: Arc<ShardHealth>,
+        cache: Arc<Cache>,
+    ) -> Option<Self> {
+        let url = config
+            .heartbeat_url
+            .clone()
+            .filter(|url| !url.is_empty())
+            .or_else(|| secrets::get("heartbeat_url"))?;
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Some(Self {
+            url,
+            kind: config.heartbeat_kind,
+            interval: Duration::from_secs(config.heartbeat_interval.max(10)),
+            health,
+            cache,
+            client,
+        })
+    }
+
+    /// Ping the monitor on the interval in the background.
+    pub fn spawn(self) -> JoinHandle<()> {
+        info!("Sending heartbeats every {}s", self.interval.as_secs());
+        tokio::spawn(async move {
+            // The first heartbeat waits an interval, so shards have time to connect
+            let start = tokio::time::Instant::now() + self.interval;
+            let mut interval = tokio::time::interval_at(start, self.interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let mut failing = false;
+            loop {
+                interval.tick().await;
+                let report = Report::new(&self.health.statuses(), self.cache.guild_count());
+                match self.ping(&report).await {
+                    Ok(()) => {
+                        if failing {
+                            info!("Heartbeats are reaching the monitor again");
+                        }
+                        failing = false;
+                        debug!("Sent heartbeat: {}", report.summary());
+                    }
+                    Err(e) => {
+                        // Only warn once per outage, the monitor will be alerting anyway
+                        if !failing {
+                            warn!("Failed to send heartbeat: {}", e);
+                        }
+                        failing = true;
+                    }
+                }
+            }
+        })
+    }
+
+    /// Send one heartbeat.
+    async fn ping(&self, report: &Report) -> Result<(), String> {
+        let request = match self.kind {
+            HeartbeatKind::Healthchecks => {
+                let url = match report.status {
+                    Health::Down => format!("{}/fail", self.url.trim_end_matches('/')),
+                    _ => self.url.clone(),
+                };
+                self.client.post(url).body(report.summary())
+            }
+            HeartbeatKind::UptimeKuma => {
+                let status = match report.status {
+                    Health::Down => "down",
+                    _ => "up",
+                };
+                self.client
+                    .request(Method::GET, &self.url)
+                    .query(&[("status", status), ("msg", &report.summary())])
+            }
+            HeartbeatKind::Webhook => self.client.post(&self.url).json(report),
+        };
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| e.without_url().to_string())?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("HTTP {}", response.status()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serenity::gateway::ConnectionStage;
+
+    #[test]
+    fn reports_degraded_when_some_shards_are_down() {
+        let health = ShardHealth::new(5, Duration::from_secs(60));
+        health.record_stage(0, ConnectionStage::Connected);
+        health.record_stage(1, ConnectionStage::Connected);
+        assert_eq!(Report::new(&health.statuses(), 10).status, Health::Up);
+
+        health.record_stage(1, ConnectionStage::Disconnected);
+        let report = Report::new(&health.statuses(), 10);
+        assert_eq!(report.status, Health::Degraded);
+        assert!(report.shards[1].down_for.is_some());
+        assert_eq!(
+            report.summary(),
+            "degraded: 1/2 shards connected, 10 servers"
+        );
+
+        health.record_stage(0, ConnectionStage::Disconnected);
+        assert_eq!(Report::new(&health.statuses(), 10).status, Health::Down);
+        assert_eq!(Report::new(&[], 0).status, Health::Down);
+    }
+}
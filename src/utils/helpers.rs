@@ -1,14 +1,27 @@
 //! Helper functions for common operations.
 
 use chrono::Utc;
-use serenity::model::channel::Message;
-use serenity::model::id::UserId;
+use regex::{Regex, RegexBuilder};
+use serenity::builder::{CreateAllowedMentions, CreateEmbed, ParseValue};
+use serenity::cache::Cache;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::channel::{ChannelType, Message};
+use serenity::model::id::{ChannelId, GuildId, MessageId, RoleId, UserId};
+use serenity::model::permissions::Permissions;
 use serenity::model::timestamp::Timestamp;
 use serenity::prelude::*;
 use std::fmt::Display;
 use std::time::{Duration, SystemTime};
+use unicode_segmentation::UnicodeSegmentation;
 
-use crate::utils::constants::{DEFAULT_COLOR, ERROR_COLOR, SUCCESS_COLOR, WARNING_COLOR};
+use crate::models::config::MentionsConfig;
+use crate::models::pin_archive::PinArchiveKey;
+use crate::utils::constants::{
+    DEFAULT_COLOR, EMBED_DESCRIPTION_LIMIT, ERROR_COLOR, REGEX_SIZE_LIMIT, SUCCESS_COLOR,
+    WARNING_COLOR,
+};
+use crate::utils::rest::{self, Priority};
 
 // Create a wrapper struct to implement TypeMapKey for BotConfig
 pub struct BotConfigKey;
@@ -28,6 +41,144 @@ pub async fn is_owner(ctx: &Context, user_id: UserId) -> bool {
     false
 }
 
+/// Get the permissions of a message's author in the channel the message was sent in.
+///
+/// Returns `None` for direct messages or when the guild isn't cached.
+pub async fn author_permissions(ctx: &Context, msg: &Message) -> Option<Permissions> {
+    let guild = msg.guild(&ctx.cache)?;
+    let channel = ctx.cache.guild_channel(msg.channel_id)?;
+    let member = msg.member(ctx).await.ok()?;
+
+    guild.user_permissions_in(&channel, &member).ok()
+}
+
+/// Get a member's permissions in a channel, fetching the member if it isn't cached.
+///
+/// Returns `None` when the guild or channel isn't cached or the member can't be found.
+pub async fn permissions_in(
+    ctx: &Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    user_id: UserId,
+) -> Option<Permissions> {
+    let guild = ctx.cache.guild(guild_id)?;
+    let channel = ctx.cache.guild_channel(channel_id)?;
+    let member = guild_id.member(ctx, user_id).await.ok()?;
+
+    guild.user_permissions_in(&channel, &member).ok()
+}
+
+/// Whether a channel is age-restricted. Threads follow their parent channel.
+///
+/// Direct messages and channels that aren't cached count as not age-restricted.
+pub fn is_nsfw_channel(ctx: &Context, channel_id: ChannelId) -> bool {
+    is_nsfw_cached(&ctx.cache, channel_id)
+}
+
+/// [`is_nsfw_channel`] for tasks that only have the cache.
+pub fn is_nsfw_cached(cache: &Cache, channel_id: ChannelId) -> bool {
+    let channel = match cache.guild_channel(channel_id) {
+        Some(channel) => channel,
+        None => return false,
+    };
+    match channel.kind {
+        ChannelType::PublicThread | ChannelType::PrivateThread | ChannelType::NewsThread => channel
+            .parent_id
+            .and_then(|parent_id| cache.guild_channel(parent_id))
+            .is_some_and(|parent| parent.nsfw),
+        _ => channel.nsfw,
+    }
+}
+
+/// The raw text of a message from its `index`th argument on, keeping the line
+/// breaks and spacing that splitting into `args` loses.
+pub fn raw_args<'a>(content: &'a str, args: &[String], index: usize) -> &'a str {
+    if index >= args.len() {
+        return "";
+    }
+    let tokens: Vec<&str> = content.split_whitespace().collect();
+    let start = match tokens.len().checked_sub(args.len() - index) {
+        Some(start) => start,
+        None => return "",
+    };
+    let offset = tokens[start].as_ptr() as usize - content.as_ptr() as usize;
+    content[offset..].trim_end()
+}
+
+/// Parse a user mention (`<@123>`, `<@!123>`) or a raw user ID.
+pub fn parse_user(arg: &str) -> Option<UserId> {
+    serenity::utils::parse_username(arg)
+        .or_else(|| arg.parse().ok())
+        .map(UserId)
+}
+
+/// Parse a channel mention (`<#123>`) or a raw channel ID.
+pub fn parse_channel(arg: &str) -> Option<ChannelId> {
+    serenity::utils::parse_channel(arg)
+        .or_else(|| arg.parse().ok())
+        .map(ChannelId)
+}
+
+/// Parse a role mention (`<@&123>`) or a raw role ID.
+pub fn parse_role(arg: &str) -> Option<RoleId> {
+    serenity::utils::parse_role(arg)
+        .or_else(|| arg.parse().ok())
+        .map(RoleId)
+}
+
+/// A message referenced by a link or ID.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MessageRef {
+    /// The message's guild, or `None` for DMs and bare IDs.
+    pub guild_id: Option<GuildId>,
+    /// The message's channel, or `None` for a bare message ID.
+    pub channel_id: Option<ChannelId>,
+    pub message_id: MessageId,
+}
+
+/// Parse a message link (`https://discord.com/channels/<guild>/<channel>/<message>`),
+/// a `<channel>-<message>` pair as copied by Discord, or a raw message ID.
+pub fn parse_message_ref(arg: &str) -> Option<MessageRef> {
+    let arg = arg.trim_start_matches('<').trim_end_matches('>');
+
+    if let Some((_, path)) = arg.split_once("/channels/") {
+        let host = arg.split('/').nth(2)?;
+        if !(host.ends_with("discord.com") || host.ends_with("discordapp.com")) {
+            return None;
+        }
+
+        let mut parts = path.split('/');
+        let guild = parts.next()?;
+        let channel_id = parts.next()?.parse().ok()?;
+        let message_id = parts.next()?.parse().ok()?;
+        if parts.next().is_some() {
+            return None;
+        }
+
+        return Some(MessageRef {
+            guild_id: match guild {
+                "@me" => None,
+                id => Some(GuildId(id.parse().ok()?)),
+            },
+            channel_id: Some(ChannelId(channel_id)),
+            message_id: MessageId(message_id),
+        });
+    }
+
+    match arg.split_once('-') {
+        Some((channel_id, message_id)) => Some(MessageRef {
+            guild_id: None,
+            channel_id: Some(ChannelId(channel_id.parse().ok()?)),
+            message_id: MessageId(message_id.parse().ok()?),
+        }),
+        None => Some(MessageRef {
+            guild_id: None,
+            channel_id: None,
+            message_id: MessageId(arg.parse().ok()?),
+        }),
+    }
+}
+
 /// Format a duration into a human-readable string (e.g., "2h 15m 30s").
 pub fn format_duration(duration: Duration) -> String {
     let seconds = duration.as_secs();
@@ -44,6 +195,19 @@ pub fn format_duration(duration: Duration) -> String {
     }
 }
 
+/// Format a count with thousands separators (e.g., "10,000").
+pub fn format_count(count: u64) -> String {
+    let digits = count.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    out
+}
+
 /// Get the current timestamp as seconds since the Unix epoch.
 pub fn unix_timestamp() -> u64 {
     SystemTime::now()
@@ -67,6 +231,31 @@ fn datetime_to_timestamp(dt: chrono::DateTime<Utc>) -> Timestamp {
     }
 }
 
+/// Get the bot's allowed-mentions policy.
+pub async fn mention_policy(ctx: &Context) -> MentionsConfig {
+    let data = ctx.data.read().await;
+    data.get::<BotConfigKey>()
+        .map(|config| config.mentions.clone())
+        .unwrap_or_default()
+}
+
+/// Apply an allowed-mentions policy, additionally allowing pings for `extra_roles`.
+pub fn apply_mentions<'a>(
+    am: &'a mut CreateAllowedMentions,
+    policy: &MentionsConfig,
+    extra_roles: &[u64],
+) -> &'a mut CreateAllowedMentions {
+    am.empty_parse();
+    if policy.everyone {
+        am.parse(ParseValue::Everyone);
+    }
+    if policy.users {
+        am.parse(ParseValue::Users);
+    }
+    am.roles(policy.roles.iter().chain(extra_roles).copied().map(RoleId))
+        .replied_user(false)
+}
+
 /// Send an info embed to a channel.
 pub async fn send_info(
     ctx: &Context,
@@ -74,8 +263,11 @@ pub async fn send_info(
     title: impl Display,
     description: impl Display,
 ) -> Result<Message, SerenityError> {
-    msg.channel_id
-        .send_message(&ctx.http, |m| {
+    let policy = &mention_policy(ctx).await;
+    let (title, description) = (&title, &description);
+    rest::call(ctx, "send_message", || {
+        msg.channel_id.send_message(&ctx.http, move |m| {
+            m.allowed_mentions(|am| apply_mentions(am, policy, &[]));
             m.embed(|e| {
                 e.title(title)
                     .description(description)
@@ -84,7 +276,8 @@ pub async fn send_info(
                     .timestamp(datetime_to_timestamp(Utc::now()))
             })
         })
-        .await
+    })
+    .await
 }
 
 /// Send a success embed to a channel.
@@ -93,8 +286,11 @@ pub async fn send_success(
     msg: &Message,
     description: impl Display,
 ) -> Result<Message, SerenityError> {
-    msg.channel_id
-        .send_message(&ctx.http, |m| {
+    let policy = &mention_policy(ctx).await;
+    let description = &description;
+    rest::call(ctx, "send_message", || {
+        msg.channel_id.send_message(&ctx.http, move |m| {
+            m.allowed_mentions(|am| apply_mentions(am, policy, &[]));
             m.embed(|e| {
                 e.title("Success")
                     .description(description)
@@ -103,7 +299,8 @@ pub async fn send_success(
                     .timestamp(datetime_to_timestamp(Utc::now()))
             })
         })
-        .await
+    })
+    .await
 }
 
 /// Send an error embed to a channel.
@@ -112,8 +309,11 @@ pub async fn send_error(
     msg: &Message,
     description: impl Display,
 ) -> Result<Message, SerenityError> {
-    msg.channel_id
-        .send_message(&ctx.http, |m| {
+    let policy = &mention_policy(ctx).await;
+    let description = &description;
+    rest::call(ctx, "send_message", || {
+        msg.channel_id.send_message(&ctx.http, move |m| {
+            m.allowed_mentions(|am| apply_mentions(am, policy, &[]));
             m.embed(|e| {
                 e.title("Error")
                     .description(description)
@@ -122,7 +322,8 @@ pub async fn send_error(
                     .timestamp(datetime_to_timestamp(Utc::now()))
             })
         })
-        .await
+    })
+    .await
 }
 
 /// Send a warning embed to a channel.
@@ -131,8 +332,11 @@ pub async fn send_warning(
     msg: &Message,
     description: impl Display,
 ) -> Result<Message, SerenityError> {
-    msg.channel_id
-        .send_message(&ctx.http, |m| {
+    let policy = &mention_policy(ctx).await;
+    let description = &description;
+    rest::call(ctx, "send_message", || {
+        msg.channel_id.send_message(&ctx.http, move |m| {
+            m.allowed_mentions(|am| apply_mentions(am, policy, &[]));
             m.embed(|e| {
                 e.title("Warning")
                     .description(description)
@@ -141,22 +345,309 @@ pub async fn send_warning(
                     .timestamp(datetime_to_timestamp(Utc::now()))
             })
         })
+    })
+    .await
+}
+
+/// Reply to a component interaction with a message only the clicker can see.
+pub async fn reply_ephemeral(
+    ctx: &Context,
+    component: &MessageComponentInteraction,
+    content: &str,
+) -> Result<(), SerenityError> {
+    component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|d| d.content(content).ephemeral(true))
+        })
         .await
 }
 
-/// Truncate a string to a maximum length, appending an ellipsis if necessary.
+/// Post an alert to a guild's staff channel, pinging the staff role if one is set.
+///
+/// Returns `Ok(None)` when the guild has no staff channel configured. Alerts are
+/// logs as far as [`rest::Priority`] goes, so they can be dropped while Discord
+/// is congested.
+pub async fn send_staff_alert(
+    ctx: &Context,
+    guild_id: GuildId,
+    title: impl Display,
+    description: impl Display,
+) -> Result<Option<Message>, SerenityError> {
+    let config = crate::models::guild_config::guild_config(ctx, guild_id).await;
+    let channel_id = match config.staff_channel {
+        Some(channel_id) => ChannelId(channel_id),
+        None => return Ok(None),
+    };
+
+    let policy = &mention_policy(ctx).await;
+    let staff_roles: &[u64] = &config.staff_role.into_iter().collect::<Vec<_>>();
+    let (title, description) = (&title, &description);
+    rest::call_with(ctx, Priority::Log, "send_message", || {
+        channel_id.send_message(&ctx.http, move |m| {
+            m.allowed_mentions(|am| apply_mentions(am, policy, staff_roles));
+            if let Some(role_id) = config.staff_role {
+                m.content(format!("<@&{}>", role_id));
+            }
+
+            m.embed(|e| {
+                e.title(title)
+                    .description(description)
+                    .color(WARNING_COLOR)
+                    .timestamp(datetime_to_timestamp(Utc::now()))
+            })
+        })
+    })
+    .await
+    .map(Some)
+}
+
+/// Build an embed that quotes a message, with its author, attachments and a jump link.
+pub fn quote_embed(message: &Message, guild_id: GuildId) -> CreateEmbed {
+    let link = format!(
+        "https://discord.com/channels/{}/{}/{}",
+        guild_id, message.channel_id, message.id
+    );
+    let image = message.attachments.iter().find(|attachment| {
+        attachment
+            .content_type
+            .as_deref()
+            .is_some_and(|kind| kind.starts_with("image/"))
+    });
+    let files: Vec<String> = message
+        .attachments
+        .iter()
+        .filter(|attachment| Some(attachment.id) != image.map(|image| image.id))
+        .map(|attachment| format!("[{}]({})", attachment.filename, attachment.url))
+        .collect();
+
+    let mut embed = CreateEmbed::default();
+    embed
+        .author(|a| a.name(message.author.tag()).icon_url(message.author.face()))
+        .description(truncate(&message.content, EMBED_DESCRIPTION_LIMIT))
+        .color(DEFAULT_COLOR)
+        .field(
+            "Source",
+            format!("<#{}> · [Jump to message]({})", message.channel_id, link),
+            false,
+        )
+        .timestamp(message.timestamp);
+    if let Some(image) = image {
+        embed.image(&image.url);
+    }
+    if !files.is_empty() {
+        embed.field("Attachments", truncate(&files.join("\n"), 1024), false);
+    }
+    embed
+}
+
+/// Archive a pinned message into its guild's pin archive channel.
+///
+/// Returns `Ok(false)` when the guild has no archive channel or the message was
+/// already archived.
+pub async fn archive_pin(
+    ctx: &Context,
+    guild_id: GuildId,
+    message: &Message,
+) -> crate::framework::command_handler::CommandResult<bool> {
+    let store = {
+        let data = ctx.data.read().await;
+        data.get::<PinArchiveKey>().cloned()
+    };
+    let config = crate::models::guild_config::guild_config(ctx, guild_id).await;
+    let (store, channel_id) = match (store, config.pin_archive_channel) {
+        (Some(store), Some(channel_id)) => (store, ChannelId(channel_id)),
+        _ => return Ok(false),
+    };
+    if store.read().await.is_archived(message.id) {
+        return Ok(false);
+    }
+
+    let policy = &mention_policy(ctx).await;
+    let embed = quote_embed(message, guild_id);
+    rest::call_with(ctx, Priority::Log, "send_message", || {
+        let embed = embed.clone();
+        channel_id.send_message(&ctx.http, move |m| {
+            m.allowed_mentions(|am| apply_mentions(am, policy, &[]))
+                .content(format!("📌 Pinned in <#{}>", message.channel_id))
+                .set_embed(embed)
+        })
+    })
+    .await?;
+    store.update(|data| data.mark_archived(message.id)).await?;
+
+    Ok(true)
+}
+
+/// Compile a user-supplied regex, case-insensitively and with a size limit.
+pub fn compile_regex(pattern: &str) -> Result<Regex, String> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| format!("Invalid regex: {}", e))
+}
+
+/// Truncate a string to at most `max_chars` characters, ending with an ellipsis if
+/// anything was cut.
+///
+/// Cuts only between grapheme clusters, so emoji and combining characters are
+/// never split.
 pub fn truncate(s: &str, max_chars: usize) -> String {
     if s.chars().count() <= max_chars {
-        s.to_string()
-    } else {
-        let mut result = String::with_capacity(max_chars + 3);
-        for (i, c) in s.chars().enumerate() {
-            if i >= max_chars {
-                break;
-            }
-            result.push(c);
+        return s.to_string();
+    }
+
+    let budget = max_chars.saturating_sub(3);
+    let mut result = String::with_capacity(max_chars);
+    let mut used = 0;
+    for grapheme in s.graphemes(true) {
+        let len = grapheme.chars().count();
+        if used + len > budget {
+            break;
         }
-        result.push_str("...");
-        result
+        used += len;
+        result.push_str(grapheme);
+    }
+    result.push_str("...");
+    result
+}
+
+/// Escape Discord markdown so the text is shown literally.
+pub fn escape_markdown(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(
+            c,
+            '\\' | '*' | '_' | '~' | '`' | '|' | '>' | '#' | '-' | '[' | ']' | '(' | ')'
+        ) {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// Break `@everyone`, `@here`, and user and role mentions by inserting
+/// a zero-width space after the `@`.
+///
+/// Prefer an allowed-mentions policy when sending; this is for text that ends up
+/// somewhere allowed mentions don't apply, such as a nickname or a topic.
+pub fn escape_mentions(s: &str) -> String {
+    s.replace("@everyone", "@\u{200B}everyone")
+        .replace("@here", "@\u{200B}here")
+        .replace("<@", "<@\u{200B}")
+}
+
+/// Wrap text in a code block, optionally with a language for highlighting.
+///
+/// Backticks that would close the block early are broken up with zero-width spaces.
+pub fn codeblock(content: &str, language: &str) -> String {
+    let mut content = content.replace("```", "`\u{200B}`\u{200B}`");
+    if content.ends_with('`') {
+        content.push('\u{200B}');
+    }
+
+    format!("```{}\n{}\n```", language, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw_args_keep_line_breaks() {
+        let content = "!kb set rules **Be nice**\n\n- No spam";
+        let args: Vec<String> = content
+            .split_whitespace()
+            .skip(1)
+            .map(String::from)
+            .collect();
+        assert_eq!(raw_args(content, &args, 2), "**Be nice**\n\n- No spam");
+        assert_eq!(raw_args(content, &args, 9), "");
+    }
+
+    #[test]
+    fn truncate_fits_limit_without_splitting_graphemes() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("hello world", 8), "hello...");
+
+        // A family emoji is several code points joined into one grapheme
+        let family = "👨‍👩‍👧";
+        let truncated = truncate(&format!("ab{}cd", family), 8);
+        assert_eq!(truncated, "ab...");
+        assert!(truncated.chars().count() <= 8);
+    }
+
+    #[test]
+    fn parses_message_refs() {
+        let link = parse_message_ref("https://ptb.discord.com/channels/1/2/3").unwrap();
+        assert_eq!(link.guild_id, Some(GuildId(1)));
+        assert_eq!(link.channel_id, Some(ChannelId(2)));
+        assert_eq!(link.message_id, MessageId(3));
+
+        let dm = parse_message_ref("<https://discord.com/channels/@me/2/3>").unwrap();
+        assert_eq!(dm.guild_id, None);
+
+        let pair = parse_message_ref("2-3").unwrap();
+        assert_eq!(pair.channel_id, Some(ChannelId(2)));
+        assert_eq!(parse_message_ref("3").unwrap().channel_id, None);
+
+        assert!(parse_message_ref("https://example.com/channels/1/2/3").is_none());
+        assert!(parse_message_ref("https://discord.com/channels/1/2").is_none());
+        assert!(parse_message_ref("hello").is_none());
+    }
+
+    #[test]
+    fn escapes_markdown() {
+        assert_eq!(escape_markdown("**bold** _x_"), "\\*\\*bold\\*\\* \\_x\\_");
+        assert_eq!(escape_markdown("a\\b"), "a\\\\b");
+    }
+
+    #[test]
+    fn formats_counts_with_separators() {
+        assert_eq!(format_count(0), "0");
+        assert_eq!(format_count(999), "999");
+        assert_eq!(format_count(10_000), "10,000");
+        assert_eq!(format_count(1_234_567), "1,234,567");
+    }
+
+    #[test]
+    fn escapes_mentions() {
+        assert_eq!(escape_mentions("hi @everyone"), "hi @\u{200B}everyone");
+        assert_eq!(
+            escape_mentions("<@123> <@&456>"),
+            "<@\u{200B}123> <@\u{200B}&456>"
+        );
+        assert_eq!(escape_mentions("me@example.com"), "me@example.com");
+    }
+
+    #[tokio::test]
+    async fn send_helpers_block_everyone_and_role_pings() {
+        use crate::testing::{MockDiscord, TestMessage};
+
+        let discord = MockDiscord::start().await;
+        let ctx = discord.context();
+        let msg = TestMessage::new("!test").build();
+
+        send_info(&ctx, &msg, "Title", "@everyone <@&5>")
+            .await
+            .unwrap();
+
+        let body = discord.requests()[0].body.clone().unwrap();
+        assert_eq!(
+            body["allowed_mentions"]["parse"],
+            serde_json::json!(["users"])
+        );
+        assert_eq!(body["allowed_mentions"]["roles"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn codeblock_survives_backticks() {
+        assert_eq!(codeblock("let x = 1;", "rs"), "```rs\nlet x = 1;\n```");
+
+        let block = codeblock("a ``` b `", "");
+        assert_eq!(block.matches("```").count(), 2);
+        assert!(block.starts_with("```\n") && block.ends_with("\n```"));
     }
 }
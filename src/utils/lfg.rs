@@ -0,0 +1,284 @@
+//! Looking-for-group posts: their embeds, forming full groups, and expiry.
+//!
+//! A post expires if it doesn't fill up in time. Once a group is full it gets a
+//! temporary role and voice channel, which are removed [`GROUP_LIFETIME`] after
+//! the group's start unless someone is still in the voice channel.
+
+use serenity::builder::{CreateComponents, CreateEmbed};
+use serenity::cache::Cache;
+use serenity::http::Http;
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::channel::{ChannelType, PermissionOverwrite, PermissionOverwriteType};
+use serenity::model::id::{ChannelId, GuildId, MessageId, RoleId};
+use serenity::model::permissions::Permissions;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::models::lfg::{LfgData, LfgPost};
+use crate::storage::JsonStore;
+use crate::utils::constants::{DEFAULT_COLOR, SUCCESS_COLOR, WARNING_COLOR};
+use crate::utils::duration::relative;
+use crate::utils::helpers::{truncate, unix_timestamp};
+use crate::utils::rest_usage::RestUsage;
+
+/// How often expired posts are checked for.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long a post without a start time stays open, in seconds.
+const OPEN_LIFETIME: u64 = 2 * 60 * 60;
+
+/// How long after its start time a post stays open, in seconds.
+const START_GRACE: u64 = 30 * 60;
+
+/// How long a full group keeps its role and voice channel, in seconds.
+pub const GROUP_LIFETIME: u64 = 3 * 60 * 60;
+
+/// How much longer a group still in its voice channel keeps it, in seconds.
+const IN_USE_EXTENSION: u64 = 30 * 60;
+
+/// When an open post expires.
+pub fn open_expiry(created_at: u64, starts_at: Option<u64>) -> u64 {
+    match starts_at {
+        Some(start) => start + START_GRACE,
+        None => created_at + OPEN_LIFETIME,
+    }
+}
+
+/// Build the embed for a post, marked expired if it never filled up.
+pub fn post_embed(post: &LfgPost, expired: bool) -> CreateEmbed {
+    let members: Vec<String> = post.members.iter().map(|id| format!("<@{}>", id)).collect();
+    let (status, color) = if post.filled_at.is_some() {
+        ("✅ Group full".to_string(), SUCCESS_COLOR)
+    } else if expired {
+        ("⌛ Expired".to_string(), WARNING_COLOR)
+    } else {
+        (
+            format!("{} of {} players", post.members.len(), post.slots),
+            DEFAULT_COLOR,
+        )
+    };
+
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(format!("🎮 LFG: {}", truncate(&post.game, 200)))
+        .color(color)
+        .field("Players", truncate(&members.join("\n"), 1024), true)
+        .field("Status", status, true)
+        .footer(|f| f.text(format!("LFG #{}", post.id)));
+    match post.starts_at {
+        Some(start) => embed.description(format!("Starting {}", relative(start))),
+        None => embed.description("Starting as soon as the group is full"),
+    };
+    embed
+}
+
+/// Add the join and leave buttons for an open post.
+pub fn post_buttons<'a>(
+    components: &'a mut CreateComponents,
+    post: &LfgPost,
+) -> &'a mut CreateComponents {
+    components.create_action_row(|row| {
+        row.create_button(|b| {
+            b.custom_id(format!("lfg:join:{}", post.id))
+                .label("Join")
+                .style(ButtonStyle::Success)
+        })
+        .create_button(|b| {
+            b.custom_id(format!("lfg:leave:{}", post.id))
+                .label("Leave")
+                .style(ButtonStyle::Secondary)
+        })
+    })
+}
+
+/// Create a full group's role and voice channel and give the role to its
+/// members. Either may be missing if the bot lacks the permissions.
+pub async fn form_group(
+    http: &Http,
+    guild_id: GuildId,
+    post: &LfgPost,
+) -> (Option<RoleId>, Option<ChannelId>) {
+    let name = truncate(&format!("LFG {}", post.game), 90);
+    let role = match guild_id
+        .create_role(http, |r| r.name(&name).mentionable(false))
+        .await
+    {
+        Ok(role) => Some(role.id),
+        Err(e) => {
+            debug!("Failed to create the role for LFG #{}: {}", post.id, e);
+            None
+        }
+    };
+
+    let category = match ChannelId(post.channel_id).to_channel(http).await {
+        Ok(channel) => channel.guild().and_then(|channel| channel.parent_id),
+        Err(_) => None,
+    };
+    let mut overwrites = Vec::new();
+    if let Some(role) = role {
+        overwrites.push(PermissionOverwrite {
+            allow: Permissions::VIEW_CHANNEL | Permissions::CONNECT | Permissions::SPEAK,
+            deny: Permissions::empty(),
+            kind: PermissionOverwriteType::Role(role),
+        });
+        // Only the group can join while it has a role
+        overwrites.push(PermissionOverwrite {
+            allow: Permissions::empty(),
+            deny: Permissions::CONNECT,
+            kind: PermissionOverwriteType::Role(RoleId(guild_id.0)),
+        });
+    }
+    let channel = match guild_id
+        .create_channel(http, |c| {
+            c.name(format!("🎮 {}", name))
+                .kind(ChannelType::Voice)
+                .user_limit(post.slots)
+                .permissions(overwrites);
+            if let Some(category) = category {
+                c.category(category);
+            }
+            c
+        })
+        .await
+    {
+        Ok(channel) => Some(channel.id),
+        Err(e) => {
+            debug!(
+                "Failed to create the voice channel for LFG #{}: {}",
+                post.id, e
+            );
+            None
+        }
+    };
+
+    if let Some(role) = role {
+        for member in &post.members {
+            if let Err(e) = http
+                .add_member_role(guild_id.0, *member, role.0, Some("LFG group filled"))
+                .await
+            {
+                debug!(
+                    "Failed to give the LFG #{} role to {}: {}",
+                    post.id, member, e
+                );
+            }
+        }
+    }
+    (role, channel)
+}
+
+/// Expires stale posts and removes finished groups' roles and voice channels.
+pub struct LfgSweeper {
+    store: Arc<JsonStore<LfgData>>,
+    http: Arc<Http>,
+    cache: Arc<Cache>,
+    usage: Arc<RestUsage>,
+}
+
+impl LfgSweeper {
+    /// Create the sweeper for the store.
+    pub fn new(
+        store: Arc<JsonStore<LfgData>>,
+        http: Arc<Http>,
+        cache: Arc<Cache>,
+        usage: Arc<RestUsage>,
+    ) -> Self {
+        Self {
+            store,
+            http,
+            cache,
+            usage,
+        }
+    }
+
+    /// Check for expired posts until the bot stops.
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(CHECK_INTERVAL);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                self.usage.wait_for_headroom("LFG cleanup").await;
+                let now = unix_timestamp();
+                // Only write to the store when something expired
+                if !self.store.read().await.has_expired(now) {
+                    continue;
+                }
+                let occupied = self.occupied_channels();
+                let expired = match self
+                    .store
+                    .update(|data| data.take_expired(now, &occupied, IN_USE_EXTENSION))
+                    .await
+                {
+                    Ok(expired) => expired,
+                    Err(e) => {
+                        warn!("Failed to check for expired LFG posts: {}", e);
+                        continue;
+                    }
+                };
+                for (guild_id, post) in expired {
+                    self.expire(guild_id, &post).await;
+                }
+            }
+        })
+    }
+
+    /// Voice channels with someone in them, as far as the cache knows.
+    fn occupied_channels(&self) -> HashSet<u64> {
+        self.cache
+            .guilds()
+            .into_iter()
+            .filter_map(|guild_id| self.cache.guild(guild_id))
+            .flat_map(|guild| {
+                guild
+                    .voice_states
+                    .values()
+                    .filter_map(|state| state.channel_id.map(|id| id.0))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Close an expired post, or clean up after a finished group.
+    async fn expire(&self, guild_id: GuildId, post: &LfgPost) {
+        if post.filled_at.is_some() {
+            if let Some(channel) = post.voice_channel_id {
+                if let Err(e) = self.http.delete_channel(channel).await {
+                    debug!("Failed to delete the LFG #{} voice channel: {}", post.id, e);
+                }
+            }
+            if let Some(role) = post.role_id {
+                if let Err(e) = self.http.delete_role(guild_id.0, role).await {
+                    debug!("Failed to delete the LFG #{} role: {}", post.id, e);
+                }
+            }
+            return;
+        }
+
+        if let Some(message_id) = post.message_id {
+            let embed = post_embed(post, true);
+            let edited = ChannelId(post.channel_id)
+                .edit_message(&self.http, MessageId(message_id), |m| {
+                    m.set_embed(embed).components(|c| c)
+                })
+                .await;
+            if let Err(e) = edited {
+                debug!("Failed to mark LFG #{} expired: {}", post.id, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_posts_expire_after_their_start() {
+        assert_eq!(open_expiry(1_000, None), 1_000 + OPEN_LIFETIME);
+        assert_eq!(open_expiry(1_000, Some(5_000)), 5_000 + START_GRACE);
+    }
+}
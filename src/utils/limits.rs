@@ -0,0 +1,90 @@
+//! Limits that depend on a user's or guild's premium tier.
+//!
+//! Commands ask [`Limits`] how much a guild or user may store instead of using fixed
+//! constants, so premium tiers can raise them in one place. The limits for each tier
+//! are set in `[premium]`.
+
+use serenity::model::id::{GuildId, UserId};
+use serenity::prelude::*;
+use std::sync::Arc;
+
+use crate::models::config::{PremiumConfig, TierLimits};
+use crate::models::premium::{PremiumData, Tier};
+use crate::storage::JsonStore;
+use crate::utils::helpers::unix_timestamp;
+
+/// Something a premium tier raises.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    /// Auto-response rules per guild.
+    AutoResponses,
+    /// Word filter patterns per guild.
+    FilterRules,
+    /// Soundboard clips per guild.
+    Sounds,
+}
+
+/// Key for storing the limits service in the client data.
+pub struct LimitsKey;
+
+impl TypeMapKey for LimitsKey {
+    type Value = Arc<Limits>;
+}
+
+/// Resolves premium tiers and the limits that come with them.
+pub struct Limits {
+    config: PremiumConfig,
+    store: Arc<JsonStore<PremiumData>>,
+}
+
+impl Limits {
+    /// Create the service with the tier limits and the premium store.
+    pub fn new(config: PremiumConfig, store: Arc<JsonStore<PremiumData>>) -> Self {
+        Self { config, store }
+    }
+
+    /// A user's tier.
+    pub async fn user_tier(&self, user_id: UserId) -> Tier {
+        self.store.read().await.user_tier(user_id, unix_timestamp())
+    }
+
+    /// A guild's tier.
+    pub async fn guild_tier(&self, guild_id: GuildId) -> Tier {
+        self.store
+            .read()
+            .await
+            .guild_tier(guild_id, unix_timestamp())
+    }
+
+    /// The higher of a user's tier and the tier of the guild they are in.
+    pub async fn tier(&self, user_id: UserId, guild_id: Option<GuildId>) -> Tier {
+        let data = self.store.read().await;
+        let now = unix_timestamp();
+        let guild_tier = guild_id.map_or(Tier::Free, |guild_id| data.guild_tier(guild_id, now));
+        data.user_tier(user_id, now).max(guild_tier)
+    }
+
+    /// A limit at a tier.
+    pub fn get(&self, tier: Tier, limit: Limit) -> usize {
+        let limits = self.tier_limits(tier);
+        match limit {
+            Limit::AutoResponses => limits.auto_responses,
+            Limit::FilterRules => limits.filter_rules,
+            Limit::Sounds => limits.sounds,
+        }
+    }
+
+    /// A limit for a guild.
+    pub async fn for_guild(&self, guild_id: GuildId, limit: Limit) -> usize {
+        self.get(self.guild_tier(guild_id).await, limit)
+    }
+
+    /// The configured limits for a tier.
+    fn tier_limits(&self, tier: Tier) -> &TierLimits {
+        match tier {
+            Tier::Free => &self.config.free,
+            Tier::Plus => &self.config.plus,
+            Tier::Pro => &self.config.pro,
+        }
+    }
+}
@@ -0,0 +1,388 @@
+//! Time-synced lyrics shown in a now-playing message while a track plays.
+//!
+//! Lyrics come as LRC from lrclib.net, which needs no key. While karaoke is on
+//! for a guild (`music karaoke on`), the player starts [`run`] when a track
+//! starts, which edits the now-playing message to show the line being sung
+//! with the ones around it. Edits are at least [`EDIT_INTERVAL`] apart to stay
+//! clear of Discord's rate limits; lines sung faster than that are skipped
+//! over.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::framework::command_handler::CommandResult;
+use crate::utils::cache::TtlCache;
+use crate::utils::constants::DEFAULT_COLOR;
+use crate::utils::rest;
+
+const LRCLIB_API: &str = "https://lrclib.net/api/search";
+
+/// Furthest a result's length can be from the track's to be taken as the same
+/// recording.
+const LENGTH_TOLERANCE: Duration = Duration::from_secs(3);
+
+/// How long lyrics, and their absence, are cached.
+const LYRICS_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// How many tracks' lyrics are cached at once.
+const LYRICS_CAPACITY: usize = 200;
+
+/// How long to wait for lrclib.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Shortest time between two edits of the now-playing message.
+pub const EDIT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Longest the runner sleeps before checking the track again, so it notices the
+/// track ending or being skipped.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Lyric lines by the time they start.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Lyrics {
+    lines: Vec<(Duration, String)>,
+}
+
+impl Lyrics {
+    /// Parse LRC, where each line starts with one or more `[mm:ss.xx]` times. An
+    /// `[offset:ms]` tag shifts every line; other tags are ignored.
+    pub fn parse(lrc: &str) -> Self {
+        let mut offset_ms: i64 = 0;
+        let mut lines = Vec::new();
+        for line in lrc.lines() {
+            let mut rest = line.trim();
+            let mut times = Vec::new();
+            while let Some(tag) = rest.strip_prefix('[') {
+                let (tag, after) = match tag.split_once(']') {
+                    Some(split) => split,
+                    None => break,
+                };
+                rest = after;
+                match parse_time(tag) {
+                    Some(time) => times.push(time),
+                    None => {
+                        if let Some(offset) = tag.strip_prefix("offset:") {
+                            offset_ms = offset.trim().parse().unwrap_or(0);
+                        }
+                    }
+                }
+            }
+            let text = rest.trim().to_string();
+            lines.extend(times.into_iter().map(|time| (time, text.clone())));
+        }
+
+        // A positive offset shows lines earlier
+        for (time, _) in &mut lines {
+            let shifted = time.as_millis() as i64 - offset_ms;
+            *time = Duration::from_millis(shifted.max(0) as u64);
+        }
+        lines.sort_by_key(|(time, _)| *time);
+        Self { lines }
+    }
+
+    /// Whether there are no timed lines.
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// The index of the line being sung at a point in the track.
+    pub fn line_at(&self, position: Duration) -> Option<usize> {
+        self.lines
+            .partition_point(|(time, _)| *time <= position)
+            .checked_sub(1)
+    }
+
+    /// When the line after `position` starts.
+    pub fn next_change(&self, position: Duration) -> Option<Duration> {
+        let next = self.lines.partition_point(|(time, _)| *time <= position);
+        self.lines.get(next).map(|(time, _)| *time)
+    }
+
+    /// The line being sung in bold, with the one before and after it.
+    pub fn render(&self, line: Option<usize>) -> String {
+        let text = |index: usize| match self.lines.get(index) {
+            Some((_, text)) if !text.is_empty() => text.as_str(),
+            Some(_) => "♪",
+            None => "",
+        };
+        match line {
+            Some(line) => {
+                let previous = line.checked_sub(1).map(text).unwrap_or("");
+                format!("{}\n**{}**\n{}", previous, text(line), text(line + 1))
+            }
+            None => format!("\n**♪**\n{}", text(0)),
+        }
+        .trim_matches('\n')
+        .to_string()
+    }
+}
+
+/// Parse an LRC time like `01:23.45` or `01:23`.
+fn parse_time(tag: &str) -> Option<Duration> {
+    let (minutes, seconds) = tag.split_once(':')?;
+    let minutes: u64 = minutes.parse().ok()?;
+    let seconds: f64 = seconds.parse().ok()?;
+    if !(0.0..60.0).contains(&seconds) {
+        return None;
+    }
+    Some(Duration::from_secs(minutes * 60) + Duration::from_secs_f64(seconds))
+}
+
+/// Which frames of the now-playing message to show, at most one per
+/// [`EDIT_INTERVAL`].
+pub struct Karaoke {
+    lyrics: Lyrics,
+    shown: Option<Option<usize>>,
+    last_edit: Option<Instant>,
+}
+
+impl Karaoke {
+    /// Start with nothing shown.
+    pub fn new(lyrics: Lyrics) -> Self {
+        Self {
+            lyrics,
+            shown: None,
+            last_edit: None,
+        }
+    }
+
+    /// The text to edit the message to at a point in the track, if the line
+    /// changed and the last edit was long enough ago.
+    pub fn frame(&mut self, position: Duration, now: Instant) -> Option<String> {
+        let line = self.lyrics.line_at(position);
+        if self.shown == Some(line) {
+            return None;
+        }
+        if self
+            .last_edit
+            .is_some_and(|last| now.duration_since(last) < EDIT_INTERVAL)
+        {
+            return None;
+        }
+        self.shown = Some(line);
+        self.last_edit = Some(now);
+        Some(self.lyrics.render(line))
+    }
+
+    /// How long to wait before the next frame could be due.
+    pub fn wait(&self, position: Duration, now: Instant) -> Duration {
+        // A line held back by the throttle is due as soon as an edit is allowed
+        let pending = self.shown != Some(self.lyrics.line_at(position));
+        let until_line = match self.lyrics.next_change(position) {
+            _ if pending => Duration::ZERO,
+            Some(next) => next - position,
+            None => POLL_INTERVAL,
+        };
+        let until_allowed = self.last_edit.map_or(Duration::ZERO, |last| {
+            EDIT_INTERVAL.saturating_sub(now.duration_since(last))
+        });
+        until_line.max(until_allowed).min(POLL_INTERVAL)
+    }
+}
+
+/// Where playback of a track is.
+#[async_trait]
+pub trait PlaybackClock: Send + Sync {
+    /// The position in the track, or `None` once it ended or was skipped.
+    async fn position(&self) -> Option<Duration>;
+}
+
+/// Show synced lyrics below `track` in a now-playing message until the track
+/// ends, then leave just `track`.
+pub async fn run(
+    ctx: &Context,
+    message: &mut Message,
+    track: &str,
+    lyrics: Lyrics,
+    clock: &dyn PlaybackClock,
+) -> CommandResult {
+    let mut karaoke = Karaoke::new(lyrics);
+    while let Some(position) = clock.position().await {
+        let now = Instant::now();
+        if let Some(text) = karaoke.frame(position, now) {
+            edit(ctx, message, &format!("{}\n\n{}", track, text)).await?;
+        }
+        tokio::time::sleep(karaoke.wait(position, Instant::now())).await;
+    }
+    edit(ctx, message, track).await
+}
+
+/// Edit the now-playing message.
+async fn edit(ctx: &Context, message: &mut Message, description: &str) -> CommandResult {
+    rest::call(ctx, "edit_message", || {
+        message.channel_id.edit_message(&ctx.http, message.id, |m| {
+            m.allowed_mentions(|am| am.empty_parse()).embed(|e| {
+                e.title("🎵 Now playing")
+                    .description(description)
+                    .color(DEFAULT_COLOR)
+            })
+        })
+    })
+    .await?;
+    Ok(())
+}
+
+/// What to search lrclib for: the artist and track when yt-dlp knows them,
+/// otherwise the title without bracketed parts like `(Official Video)`.
+pub fn search_query(artist: Option<&str>, track: Option<&str>, title: &str) -> String {
+    if let (Some(artist), Some(track)) = (artist, track) {
+        return format!("{} {}", artist, track);
+    }
+    let mut query = String::with_capacity(title.len());
+    let mut depth = 0usize;
+    for c in title.chars() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            _ if depth == 0 => query.push(c),
+            _ => {}
+        }
+    }
+    query.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LrclibResponse {
+    #[serde(default)]
+    synced_lyrics: Option<String>,
+    /// Length in seconds.
+    #[serde(default)]
+    duration: Option<f64>,
+}
+
+impl LrclibResponse {
+    /// Whether the result is probably the recording being played.
+    fn matches(&self, length: Option<Duration>) -> bool {
+        match (length, self.duration) {
+            (Some(length), Some(duration)) if duration.is_finite() && duration >= 0.0 => {
+                let duration = Duration::from_secs_f64(duration);
+                duration.max(length) - duration.min(length) <= LENGTH_TOLERANCE
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Looks up synced lyrics on lrclib.net, caching the results.
+pub struct LyricsClient {
+    client: reqwest::Client,
+    cache: TtlCache<Option<Lyrics>>,
+}
+
+impl Default for LyricsClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LyricsClient {
+    /// Create a client.
+    pub fn new() -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self {
+            client,
+            cache: TtlCache::new(LYRICS_TTL, LYRICS_CAPACITY),
+        }
+    }
+
+    /// Synced lyrics for a search from [`search_query`], or `None` if lrclib
+    /// has none. When the track's length is known, results of a different
+    /// length are passed over.
+    pub async fn synced(
+        &self,
+        query: &str,
+        length: Option<Duration>,
+    ) -> CommandResult<Option<Lyrics>> {
+        let key = format!(
+            "{}\n{}",
+            query.to_lowercase(),
+            length.map_or(0, |length| length.as_secs())
+        );
+        if let Some(cached) = self.cache.get(&key).await {
+            return Ok(cached);
+        }
+        let results: Vec<LrclibResponse> = self
+            .client
+            .get(LRCLIB_API)
+            .query(&[("q", query)])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let lyrics = results
+            .into_iter()
+            .filter(|result| result.matches(length))
+            .filter_map(|result| result.synced_lyrics)
+            .map(|lrc| Lyrics::parse(&lrc))
+            .find(|lyrics| !lyrics.is_empty());
+        self.cache.insert(key, lyrics.clone()).await;
+        Ok(lyrics)
+    }
+}
+
+/// Key for storing the lyrics client in the client data.
+pub struct LyricsKey;
+
+impl TypeMapKey for LyricsKey {
+    type Value = Arc<LyricsClient>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_throttles() {
+        let lyrics = Lyrics::parse(
+            "[ar:Someone]\n[offset:500]\n[00:01.50]First\n[00:04.00][00:20.00]Chorus\n\
+             [00:04.80]Quick\nno time here\n[00:10.00]",
+        );
+        let secs = Duration::from_secs_f64;
+        assert_eq!(lyrics.line_at(secs(0.5)), None);
+        assert_eq!(lyrics.line_at(secs(1.0)), Some(0));
+        assert_eq!(lyrics.next_change(secs(1.0)), Some(secs(3.5)));
+        assert_eq!(lyrics.render(Some(1)), "First\n**Chorus**\nQuick");
+        assert_eq!(lyrics.render(Some(3)), "Quick\n**♪**\nChorus");
+
+        let start = Instant::now();
+        let mut karaoke = Karaoke::new(lyrics);
+        assert!(karaoke.frame(secs(1.0), start).is_some());
+        assert!(karaoke.frame(secs(1.5), start + secs(0.5)).is_none());
+        // The line changed, but too soon after the last edit
+        assert!(karaoke.frame(secs(3.6), start + secs(1.0)).is_none());
+        assert_eq!(karaoke.wait(secs(3.6), start + secs(1.0)), secs(1.0));
+        assert_eq!(
+            karaoke.frame(secs(4.6), start + secs(2.0)).as_deref(),
+            Some("Chorus\n**Quick**\n♪")
+        );
+    }
+
+    #[test]
+    fn search_queries() {
+        assert_eq!(
+            search_query(None, None, "Artist - Song (Official Video) [HD]"),
+            "Artist - Song"
+        );
+        assert_eq!(
+            search_query(Some("Artist"), Some("Song"), "ignored"),
+            "Artist Song"
+        );
+
+        let result = LrclibResponse {
+            synced_lyrics: None,
+            duration: Some(200.0),
+        };
+        assert!(result.matches(None));
+        assert!(result.matches(Some(Duration::from_secs(202))));
+        assert!(!result.matches(Some(Duration::from_secs(210))));
+    }
+}
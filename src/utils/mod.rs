@@ -1,7 +1,50 @@
 //! Utility functions and helpers used throughout the application.
 
+pub mod actions;
+#[cfg(feature = "music")]
+pub mod audio;
+pub mod bot_lists;
+pub mod cache;
+pub mod calc;
+pub mod charts;
 pub mod constants;
+pub mod devlookup;
+pub mod diagnostics;
+pub mod dms;
+pub mod duration;
+pub mod entitlements;
+pub mod exchange;
+pub mod feeds;
+pub mod files;
+pub mod heartbeat;
 pub mod helpers;
+#[cfg(feature = "automod")]
+pub mod leaks;
+pub mod lfg;
+pub mod limits;
+pub mod logging;
+#[cfg(feature = "music")]
+pub mod lyrics;
+pub mod paste;
+pub mod permaudit;
+#[cfg(feature = "automod")]
+pub mod phishing;
+pub mod piston;
+#[cfg(feature = "music")]
+pub mod player;
+pub mod reddit;
+pub mod reminders;
+pub mod rest;
+pub mod rest_usage;
+pub mod secrets;
+pub mod steam;
+pub mod timezones;
+#[cfg(feature = "music")]
+pub mod track_links;
+pub mod units;
+#[cfg(feature = "music")]
+pub mod voice;
+pub mod webhooks;
 
 // Re-export commonly used utilities
 pub use constants::*;
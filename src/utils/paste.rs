@@ -0,0 +1,105 @@
+//! Paste service client for output that doesn't fit in a message.
+
+use serde::Deserialize;
+use serde_json::json;
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use std::fmt::Display;
+use tracing::warn;
+
+use crate::framework::command_handler::CommandResult;
+use crate::models::config::{PasteConfig, PasteService};
+use crate::utils::constants::EMBED_DESCRIPTION_LIMIT;
+use crate::utils::files::{send_file, OutgoingFile};
+use crate::utils::helpers::{send_info, BotConfigKey};
+
+/// Response from a hastebin-compatible server.
+#[derive(Deserialize)]
+struct HasteResponse {
+    key: String,
+}
+
+/// Response from mystb.in.
+#[derive(Deserialize)]
+struct MystbinResponse {
+    id: String,
+}
+
+/// Upload text to the configured paste service and return its URL.
+pub async fn paste(config: &PasteConfig, filename: &str, content: &str) -> CommandResult<String> {
+    let base = config.base_url().trim_end_matches('/');
+    let client = reqwest::Client::new();
+
+    let url = match config.service {
+        PasteService::Hastebin => {
+            let response: HasteResponse = client
+                .post(format!("{}/documents", base))
+                .body(content.to_string())
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            format!("{}/{}", base, response.key)
+        }
+        PasteService::Mystbin => {
+            let body = json!({ "files": [{ "filename": filename, "content": content }] });
+            let response: MystbinResponse = client
+                .post(format!("{}/api/paste", base))
+                .json(&body)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            format!("{}/{}", base, response.id)
+        }
+    };
+
+    Ok(url)
+}
+
+/// Get the paste configuration from the client data.
+pub async fn paste_config(ctx: &Context) -> PasteConfig {
+    let data = ctx.data.read().await;
+    data.get::<BotConfigKey>()
+        .map(|config| config.paste.clone())
+        .unwrap_or_default()
+}
+
+/// Reply with an info embed, pasting the text and linking it if it's too long.
+///
+/// Falls back to attaching the text as a file if the paste service fails.
+pub async fn send_long_info(
+    ctx: &Context,
+    msg: &Message,
+    title: impl Display,
+    text: &str,
+) -> CommandResult {
+    if text.chars().count() <= EMBED_DESCRIPTION_LIMIT {
+        send_info(ctx, msg, title, text).await?;
+        return Ok(());
+    }
+
+    let config = paste_config(ctx).await;
+    match paste(&config, "output.txt", text).await {
+        Ok(url) => {
+            let description = format!(
+                "The output was too long to show here, so it was uploaded:\n{}",
+                url
+            );
+            send_info(ctx, msg, title, description).await?;
+        }
+        Err(e) => {
+            warn!("Failed to paste long output: {}", e);
+            let mut embed = serenity::builder::CreateEmbed::default();
+            embed
+                .title(title)
+                .description("The output was too long to show here, so it's attached.");
+            let file = OutgoingFile::new("output.txt", text);
+            send_file(ctx, msg.channel_id, msg.guild_id, file, Some(embed)).await?;
+        }
+    }
+
+    Ok(())
+}
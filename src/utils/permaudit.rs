@@ -0,0 +1,430 @@
+//! Finding risky permission setups in a guild, for `permaudit`.
+//!
+//! The audit looks at a snapshot of the guild's roles and channel overwrites:
+//! roles that grant Administrator or other dangerous permissions to everyone,
+//! channels where anyone can ping `@everyone`, overwrites left behind for deleted
+//! roles or departed members, and permissions the bot lacks for the features a
+//! guild has turned on.
+
+use serenity::model::channel::{ChannelType, PermissionOverwriteType};
+use serenity::model::guild::Guild;
+use serenity::model::id::{RoleId, UserId};
+use serenity::model::permissions::Permissions;
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::models::guild_config::{AutoPublish, GuildConfig};
+
+/// Permissions that should never be given to everyone.
+pub const DANGEROUS: Permissions = Permissions::ADMINISTRATOR
+    .union(Permissions::MANAGE_GUILD)
+    .union(Permissions::MANAGE_ROLES)
+    .union(Permissions::MANAGE_CHANNELS)
+    .union(Permissions::MANAGE_WEBHOOKS)
+    .union(Permissions::BAN_MEMBERS)
+    .union(Permissions::KICK_MEMBERS);
+
+/// Most channels named in one finding.
+const MAX_LISTED: usize = 5;
+
+/// How urgent a finding is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Anyone could take the server over or break a feature.
+    Critical,
+    /// Worth a look, but not necessarily wrong.
+    Warning,
+    /// Clutter with no effect.
+    Info,
+}
+
+impl Severity {
+    pub fn icon(self) -> &'static str {
+        match self {
+            Self::Critical => "🔴",
+            Self::Warning => "🟠",
+            Self::Info => "🔵",
+        }
+    }
+}
+
+/// Something the audit found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Finding {
+    fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.severity.icon(), self.message)
+    }
+}
+
+/// A role, as far as the audit cares.
+#[derive(Clone, Debug)]
+pub struct RoleInfo {
+    pub id: u64,
+    pub permissions: Permissions,
+    /// Whether an integration, like a bot, manages the role.
+    pub managed: bool,
+}
+
+/// A channel overwrite, for a role or a member.
+#[derive(Clone, Debug)]
+pub struct OverwriteInfo {
+    pub role: bool,
+    pub target: u64,
+    pub allow: Permissions,
+    pub deny: Permissions,
+}
+
+/// A channel and its overwrites.
+#[derive(Clone, Debug)]
+pub struct ChannelInfo {
+    pub id: u64,
+    /// Whether messages can be sent in the channel, so mentions matter.
+    pub text: bool,
+    pub overwrites: Vec<OverwriteInfo>,
+}
+
+/// What the audit looks at.
+#[derive(Clone, Debug, Default)]
+pub struct Snapshot {
+    /// The `@everyone` role's ID, which is the guild's ID.
+    pub everyone: u64,
+    pub roles: Vec<RoleInfo>,
+    pub channels: Vec<ChannelInfo>,
+    /// Every member's ID, if all of them are cached, to spot overwrites for
+    /// members who left.
+    pub members: Option<HashSet<u64>>,
+    /// The bot's server-wide permissions.
+    pub bot_permissions: Permissions,
+}
+
+impl Snapshot {
+    /// Take a snapshot of a cached guild.
+    pub fn of(guild: &Guild, bot_id: UserId) -> Self {
+        let roles = guild
+            .roles
+            .values()
+            .map(|role| RoleInfo {
+                id: role.id.0,
+                permissions: role.permissions,
+                managed: role.managed,
+            })
+            .collect();
+        let mut channels: Vec<ChannelInfo> = guild
+            .channels
+            .values()
+            .filter_map(|channel| channel.clone().guild())
+            .map(|channel| ChannelInfo {
+                id: channel.id.0,
+                text: matches!(
+                    channel.kind,
+                    ChannelType::Text | ChannelType::News | ChannelType::Voice
+                ),
+                overwrites: channel
+                    .permission_overwrites
+                    .iter()
+                    .filter_map(|overwrite| {
+                        let (role, target) = match overwrite.kind {
+                            PermissionOverwriteType::Role(role_id) => (true, role_id.0),
+                            PermissionOverwriteType::Member(user_id) => (false, user_id.0),
+                            _ => return None,
+                        };
+                        Some(OverwriteInfo {
+                            role,
+                            target,
+                            allow: overwrite.allow,
+                            deny: overwrite.deny,
+                        })
+                    })
+                    .collect(),
+            })
+            .collect();
+        channels.sort_by_key(|channel| channel.id);
+        let members = (guild.members.len() as u64 >= guild.member_count)
+            .then(|| guild.members.keys().map(|id| id.0).collect());
+        // The bot's roles added to @everyone, ignoring channel overwrites
+        let bot_permissions = guild
+            .members
+            .get(&bot_id)
+            .map_or(Permissions::empty(), |member| {
+                member
+                    .roles
+                    .iter()
+                    .chain(std::iter::once(&RoleId(guild.id.0)))
+                    .filter_map(|role_id| guild.roles.get(role_id))
+                    .fold(Permissions::empty(), |all, role| all | role.permissions)
+            });
+
+        Self {
+            everyone: guild.id.0,
+            roles,
+            channels,
+            members,
+            bot_permissions,
+        }
+    }
+
+    fn everyone_permissions(&self) -> Permissions {
+        self.roles
+            .iter()
+            .find(|role| role.id == self.everyone)
+            .map_or(Permissions::empty(), |role| role.permissions)
+    }
+}
+
+/// Core features a guild turned on that need the bot to have certain permissions.
+///
+/// Plugins declare theirs with [`Plugin::required_bot_permissions`](crate::framework::plugin::Plugin::required_bot_permissions).
+pub fn feature_permissions(config: &GuildConfig) -> Vec<(&'static str, Permissions)> {
+    let mut needs = Vec::new();
+    if config.staff_channel.is_some() {
+        needs.push((
+            "staff alerts",
+            Permissions::SEND_MESSAGES | Permissions::EMBED_LINKS,
+        ));
+    }
+    if config.restore_roles || !config.persistent_roles.is_empty() {
+        needs.push(("role persistence", Permissions::MANAGE_ROLES));
+    }
+    if config.onboarding.is_active() {
+        needs.push(("onboarding", Permissions::MANAGE_ROLES));
+    }
+    if config
+        .auto_publish
+        .values()
+        .any(|mode| *mode == AutoPublish::Everyone)
+    {
+        needs.push(("auto-publish", Permissions::MANAGE_MESSAGES));
+    }
+    if !config.voice_text.links.is_empty() {
+        needs.push((
+            "voice text channels",
+            Permissions::MANAGE_CHANNELS | Permissions::MANAGE_ROLES,
+        ));
+    }
+    if config.music.always_on.is_some() {
+        needs.push(("24/7 music", Permissions::CONNECT | Permissions::SPEAK));
+    }
+    needs
+}
+
+/// List channels as mentions, cutting the list off after a few.
+fn channel_list(channels: &[u64]) -> String {
+    let mut listed: Vec<String> = channels
+        .iter()
+        .take(MAX_LISTED)
+        .map(|id| format!("<#{}>", id))
+        .collect();
+    if channels.len() > MAX_LISTED {
+        listed.push(format!("and {} more", channels.len() - MAX_LISTED));
+    }
+    listed.join(", ")
+}
+
+/// Audit a guild, most severe findings first.
+///
+/// `needs` are the features that are on and the permissions each needs.
+pub fn audit(snapshot: &Snapshot, needs: &[(&str, Permissions)]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let everyone = snapshot.everyone_permissions();
+
+    // Roles
+    let dangerous = everyone & DANGEROUS;
+    if !dangerous.is_empty() {
+        findings.push(Finding::new(
+            Severity::Critical,
+            format!("`@everyone` has {}", dangerous),
+        ));
+    }
+    let mut roles: Vec<&RoleInfo> = snapshot
+        .roles
+        .iter()
+        .filter(|role| role.id != snapshot.everyone)
+        .collect();
+    roles.sort_by_key(|role| role.id);
+    for role in roles {
+        if role.permissions.administrator() {
+            let note = if role.managed {
+                ", which its integration asked for"
+            } else {
+                ""
+            };
+            findings.push(Finding::new(
+                Severity::Warning,
+                format!(
+                    "<@&{}> has Administrator{}. Members with it bypass every channel overwrite.",
+                    role.id, note
+                ),
+            ));
+        }
+    }
+
+    // Mentions
+    let mention_channels: Vec<u64> = snapshot
+        .channels
+        .iter()
+        .filter(|channel| channel.text)
+        .filter(|channel| {
+            let overwrite = channel
+                .overwrites
+                .iter()
+                .find(|overwrite| overwrite.role && overwrite.target == snapshot.everyone);
+            let permissions = match overwrite {
+                Some(overwrite) => (everyone & !overwrite.deny) | overwrite.allow,
+                None => everyone,
+            };
+            permissions.mention_everyone()
+        })
+        .map(|channel| channel.id)
+        .collect();
+    if !mention_channels.is_empty() {
+        let severity = if everyone.mention_everyone() {
+            Severity::Critical
+        } else {
+            Severity::Warning
+        };
+        findings.push(Finding::new(
+            severity,
+            format!(
+                "Anyone can mention `@everyone` in {}",
+                channel_list(&mention_channels)
+            ),
+        ));
+    }
+
+    // Overwrites
+    let roles: HashSet<u64> = snapshot.roles.iter().map(|role| role.id).collect();
+    let mut deleted_roles = Vec::new();
+    let mut departed = Vec::new();
+    let mut empty = Vec::new();
+    for channel in &snapshot.channels {
+        for overwrite in &channel.overwrites {
+            if overwrite.role && !roles.contains(&overwrite.target) {
+                deleted_roles.push(channel.id);
+            } else if !overwrite.role
+                && snapshot
+                    .members
+                    .as_ref()
+                    .is_some_and(|members| !members.contains(&overwrite.target))
+            {
+                departed.push(channel.id);
+            } else if overwrite.allow.is_empty() && overwrite.deny.is_empty() {
+                empty.push(channel.id);
+            }
+        }
+    }
+    for (channels, what) in [
+        (&mut deleted_roles, "deleted roles"),
+        (&mut departed, "members who left"),
+    ] {
+        channels.dedup();
+        if !channels.is_empty() {
+            findings.push(Finding::new(
+                Severity::Warning,
+                format!("Overwrites for {} in {}", what, channel_list(channels)),
+            ));
+        }
+    }
+    empty.dedup();
+    if !empty.is_empty() {
+        findings.push(Finding::new(
+            Severity::Info,
+            format!(
+                "Overwrites that neither allow nor deny anything in {}",
+                channel_list(&empty)
+            ),
+        ));
+    }
+
+    // The bot's own permissions
+    if !snapshot.bot_permissions.administrator() {
+        for (feature, needed) in needs {
+            let missing = *needed - snapshot.bot_permissions;
+            if !missing.is_empty() {
+                findings.push(Finding::new(
+                    Severity::Critical,
+                    format!("I need {} for {}", missing, feature),
+                ));
+            }
+        }
+    }
+
+    findings.sort_by_key(|finding| finding.severity);
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn role(id: u64, permissions: Permissions) -> RoleInfo {
+        RoleInfo {
+            id,
+            permissions,
+            managed: false,
+        }
+    }
+
+    #[test]
+    fn finds_risky_setups() {
+        let snapshot = Snapshot {
+            everyone: 1,
+            roles: vec![
+                role(1, Permissions::SEND_MESSAGES),
+                role(2, Permissions::ADMINISTRATOR),
+            ],
+            channels: vec![
+                ChannelInfo {
+                    id: 10,
+                    text: true,
+                    overwrites: vec![OverwriteInfo {
+                        role: true,
+                        target: 1,
+                        allow: Permissions::MENTION_EVERYONE,
+                        deny: Permissions::empty(),
+                    }],
+                },
+                ChannelInfo {
+                    id: 11,
+                    text: true,
+                    overwrites: vec![OverwriteInfo {
+                        role: true,
+                        target: 99,
+                        allow: Permissions::empty(),
+                        deny: Permissions::SEND_MESSAGES,
+                    }],
+                },
+            ],
+            members: None,
+            bot_permissions: Permissions::SEND_MESSAGES,
+        };
+        let findings = audit(
+            &snapshot,
+            &[("role persistence", Permissions::MANAGE_ROLES)],
+        );
+
+        assert_eq!(findings.len(), 4);
+        assert_eq!(findings[0].severity, Severity::Critical);
+        assert!(findings[0].message.contains("Manage Roles"));
+        assert!(findings.iter().any(|f| f.message.contains("<@&2>")));
+        assert!(findings
+            .iter()
+            .any(|f| f.message.contains("`@everyone` in <#10>")));
+        assert!(findings
+            .iter()
+            .any(|f| f.message.contains("deleted roles in <#11>")));
+    }
+}
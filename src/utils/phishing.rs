@@ -0,0 +1,265 @@
+//! Phishing domain blocklists and link scanning.
+//!
+//! Domains are downloaded from the feeds in `[phishing]` when the bot starts and
+//! again every `refresh_interval` seconds. Links to URL shorteners can optionally be
+//! followed, so a shortened phishing link is caught too.
+
+use regex::Regex;
+use reqwest::header::LOCATION;
+use reqwest::redirect::Policy;
+use reqwest::Url;
+use serenity::prelude::*;
+use std::collections::HashSet;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::framework::command_handler::CommandResult;
+use crate::models::config::PhishingConfig;
+
+/// Most redirects followed when unshortening a link.
+const MAX_REDIRECTS: usize = 5;
+
+/// How long to wait for a feed or shortener to respond.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Key for storing the phishing list in the client data.
+pub struct PhishingKey;
+
+impl TypeMapKey for PhishingKey {
+    type Value = Arc<PhishingList>;
+}
+
+/// Known phishing domains and the settings for checking links against them.
+pub struct PhishingList {
+    config: PhishingConfig,
+    domains: RwLock<HashSet<String>>,
+    /// Client that doesn't follow redirects, for unshortening links one hop at a time.
+    client: reqwest::Client,
+}
+
+impl PhishingList {
+    /// Create an empty list. Call [`PhishingList::spawn`] to load the feeds.
+    pub fn new(config: PhishingConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .redirect(Policy::none())
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            config,
+            domains: RwLock::new(HashSet::new()),
+            client,
+        }
+    }
+
+    /// Number of known phishing domains.
+    pub fn len(&self) -> usize {
+        self.domains.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Whether no phishing domains are known.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Replace the known domains.
+    pub fn replace(&self, domains: HashSet<String>) {
+        *self.domains.write().unwrap_or_else(|e| e.into_inner()) = domains;
+    }
+
+    /// Whether a host or any domain it belongs to is a phishing domain.
+    pub fn is_blocked(&self, host: &str) -> bool {
+        let domains = self.domains.read().unwrap_or_else(|e| e.into_inner());
+        parent_domains(&normalize_host(host)).any(|domain| domains.contains(domain))
+    }
+
+    /// Find the first phishing domain linked in a message, following shortened links
+    /// if `unshorten` is on.
+    pub async fn scan(&self, content: &str) -> Option<String> {
+        for url in link_urls(content) {
+            if let Some(domain) = self.check_url(url).await {
+                return Some(domain);
+            }
+        }
+        None
+    }
+
+    /// Check a link, and where it redirects to if it is shortened.
+    async fn check_url(&self, mut url: Url) -> Option<String> {
+        for _ in 0..=MAX_REDIRECTS {
+            let host = normalize_host(url.host_str()?);
+            if self.is_blocked(&host) {
+                return Some(host);
+            }
+            if !self.config.unshorten || !self.is_shortener(&host) {
+                return None;
+            }
+
+            url = self.redirect_target(&url).await?;
+        }
+        None
+    }
+
+    /// Whether a host is a configured URL shortener.
+    fn is_shortener(&self, host: &str) -> bool {
+        self.config
+            .shorteners
+            .iter()
+            .any(|shortener| shortener.eq_ignore_ascii_case(host))
+    }
+
+    /// Where a link redirects to, without following it any further.
+    async fn redirect_target(&self, url: &Url) -> Option<Url> {
+        let response = match self.client.head(url.clone()).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                debug!("Failed to unshorten {}: {}", url, e);
+                return None;
+            }
+        };
+        if !response.status().is_redirection() {
+            return None;
+        }
+
+        let location = response.headers().get(LOCATION)?.to_str().ok()?;
+        url.join(location).ok()
+    }
+
+    /// Download every feed and replace the known domains. Returns how many there are.
+    ///
+    /// Feeds that fail are skipped; the list is only kept as it was if all of them fail.
+    pub async fn refresh(&self) -> CommandResult<usize> {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+
+        let mut domains = HashSet::new();
+        let mut loaded = 0;
+        for feed in &self.config.feeds {
+            let response = client
+                .get(feed)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            let body = match response {
+                Ok(response) => response.text().await,
+                Err(e) => Err(e),
+            };
+            match body {
+                Ok(body) => {
+                    domains.extend(parse_feed(&body));
+                    loaded += 1;
+                }
+                Err(e) => warn!("Failed to download phishing feed {}: {}", feed, e),
+            }
+        }
+
+        if loaded == 0 && !self.config.feeds.is_empty() {
+            return Err("No phishing feed could be downloaded".into());
+        }
+        let count = domains.len();
+        self.replace(domains);
+        Ok(count)
+    }
+
+    /// Run [`PhishingList::refresh`] now and then every `refresh_interval` seconds.
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        let period = Duration::from_secs(self.config.refresh_interval.max(60));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                match self.refresh().await {
+                    Ok(count) => info!("Loaded {} phishing domains", count),
+                    Err(e) => warn!("Failed to refresh phishing domains: {}", e),
+                }
+            }
+        })
+    }
+}
+
+/// Parse a feed serving either a JSON array of domains or one domain per line.
+///
+/// Blank lines and `#` comments are skipped.
+pub fn parse_feed(body: &str) -> Vec<String> {
+    if let Ok(domains) = serde_json::from_str::<Vec<String>>(body) {
+        return domains
+            .iter()
+            .map(|domain| normalize_host(domain))
+            .collect();
+    }
+
+    body.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(normalize_host)
+        .collect()
+}
+
+/// Lowercase a host and strip a leading `www.` and trailing dot.
+fn normalize_host(host: &str) -> String {
+    let host = host.trim().trim_end_matches('.').to_lowercase();
+    match host.strip_prefix("www.") {
+        Some(host) => host.to_string(),
+        None => host,
+    }
+}
+
+/// A host followed by each domain it belongs to, such as `a.b.com`, `b.com`, `com`.
+fn parent_domains(host: &str) -> impl Iterator<Item = &str> {
+    std::iter::successors(Some(host), |domain| {
+        domain.split_once('.').map(|(_, parent)| parent)
+    })
+}
+
+/// Get the web links in a message.
+pub fn link_urls(content: &str) -> Vec<Url> {
+    static LINK: OnceLock<Regex> = OnceLock::new();
+    let link = LINK
+        .get_or_init(|| Regex::new(r#"(?i)https?://[^\s<>"'`|]+"#).expect("link regex is valid"));
+
+    link.find_iter(content)
+        .filter_map(|m| Url::parse(m.as_str()).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_json_and_line_feeds() {
+        assert_eq!(
+            parse_feed(r#"["Steamcommunlty.com", "www.disc0rd.gift"]"#),
+            ["steamcommunlty.com", "disc0rd.gift"]
+        );
+        assert_eq!(
+            parse_feed("# scam links\nfree-nitro.ru\n\n  dlscord.app  \n"),
+            ["free-nitro.ru", "dlscord.app"]
+        );
+    }
+
+    #[test]
+    fn blocks_subdomains_of_listed_domains() {
+        let list = PhishingList::new(PhishingConfig::default());
+        list.replace(HashSet::from(["dlscord.app".to_string()]));
+
+        assert!(list.is_blocked("dlscord.app"));
+        assert!(list.is_blocked("WWW.Login.Dlscord.App."));
+        assert!(!list.is_blocked("discord.app"));
+        assert!(!list.is_blocked("notdlscord.app"));
+    }
+
+    #[test]
+    fn finds_links_in_messages() {
+        let hosts: Vec<String> =
+            link_urls("free nitro <https://dlscord.app/gift> and https://example.com/a?b=c, bye")
+                .iter()
+                .filter_map(|url| url.host_str().map(str::to_string))
+                .collect();
+        assert_eq!(hosts, ["dlscord.app", "example.com"]);
+    }
+}
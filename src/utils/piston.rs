@@ -0,0 +1,253 @@
+//! Client for the Piston code execution API, used by `run`.
+//!
+//! Piston runs code in a sandbox on its own servers, so nothing sent to `run`
+//! executes on the bot's host. The list of languages it supports is cached for
+//! an hour.
+
+use serde::{Deserialize, Serialize};
+use serenity::prelude::*;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use crate::framework::command_handler::CommandResult;
+use crate::framework::KurumiError;
+use crate::models::config::RunConfig;
+
+/// How long the list of languages is cached.
+const RUNTIMES_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// How long to wait for Piston, which includes compiling and running the code.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Key for storing the Piston client in the client data.
+pub struct PistonKey;
+
+impl TypeMapKey for PistonKey {
+    type Value = Arc<Piston>;
+}
+
+/// A language Piston can run.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Runtime {
+    pub language: String,
+    pub version: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+impl Runtime {
+    /// Whether a name is this language or one of its aliases, ignoring case.
+    pub fn matches(&self, name: &str) -> bool {
+        self.language.eq_ignore_ascii_case(name)
+            || self
+                .aliases
+                .iter()
+                .any(|alias| alias.eq_ignore_ascii_case(name))
+    }
+}
+
+#[derive(Serialize)]
+struct ExecuteRequest<'a> {
+    language: &'a str,
+    version: &'a str,
+    files: [SourceFile<'a>; 1],
+}
+
+#[derive(Serialize)]
+struct SourceFile<'a> {
+    content: &'a str,
+}
+
+/// The output of compiling or running code.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Stage {
+    #[serde(default)]
+    pub output: String,
+    /// The exit code, if the process exited normally.
+    #[serde(default)]
+    pub code: Option<i32>,
+    /// The signal the process was killed with, such as `SIGKILL` on a timeout.
+    #[serde(default)]
+    pub signal: Option<String>,
+}
+
+impl Stage {
+    /// Whether the process exited successfully.
+    pub fn succeeded(&self) -> bool {
+        self.code == Some(0) && self.signal.is_none()
+    }
+}
+
+/// The result of a run.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Execution {
+    pub language: String,
+    pub version: String,
+    /// Only present for compiled languages.
+    #[serde(default)]
+    pub compile: Option<Stage>,
+    #[serde(default)]
+    pub run: Stage,
+}
+
+/// An error message from Piston, such as for an unknown language.
+#[derive(Deserialize)]
+struct PistonError {
+    message: String,
+}
+
+/// Code from a message and the language it was tagged with, if any.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CodeBlock {
+    pub language: Option<String>,
+    pub code: String,
+}
+
+/// Find the first fenced code block in a message, like
+/// ```` ```rust\nfn main() {}\n``` ````. An unclosed block runs to the end.
+pub fn extract_code(content: &str) -> Option<CodeBlock> {
+    let start = content.find("```")? + 3;
+    let rest = &content[start..];
+    let block = match rest.find("```") {
+        Some(end) => &rest[..end],
+        None => rest,
+    };
+
+    // The first line is a language tag if there's code after it
+    let (language, code) = match block.split_once('\n') {
+        Some((tag, code)) if !tag.trim().is_empty() && !tag.trim().contains(' ') => {
+            (Some(tag.trim().to_lowercase()), code)
+        }
+        _ => (None, block),
+    };
+    let code = code.trim_matches('\n');
+    if code.trim().is_empty() {
+        return None;
+    }
+    Some(CodeBlock {
+        language,
+        code: code.to_string(),
+    })
+}
+
+/// Runs code through a Piston API.
+pub struct Piston {
+    config: RunConfig,
+    client: reqwest::Client,
+    runtimes: Mutex<Option<(Instant, Arc<Vec<Runtime>>)>>,
+}
+
+impl Piston {
+    /// Create a client for the configured API.
+    pub fn new(config: RunConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            config,
+            client,
+            runtimes: Mutex::new(None),
+        }
+    }
+
+    /// The run settings.
+    pub fn config(&self) -> &RunConfig {
+        &self.config
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.config.piston_url.trim_end_matches('/'), path)
+    }
+
+    /// Every language Piston can run.
+    pub async fn runtimes(&self) -> CommandResult<Arc<Vec<Runtime>>> {
+        let mut cached = self.runtimes.lock().await;
+        if let Some((fetched, runtimes)) = cached.as_ref() {
+            if fetched.elapsed() < RUNTIMES_TTL {
+                return Ok(runtimes.clone());
+            }
+        }
+
+        let runtimes: Vec<Runtime> = self
+            .client
+            .get(self.url("runtimes"))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let runtimes = Arc::new(runtimes);
+        *cached = Some((Instant::now(), runtimes.clone()));
+        Ok(runtimes)
+    }
+
+    /// Find a language by name or alias.
+    pub async fn runtime(&self, name: &str) -> CommandResult<Option<Runtime>> {
+        let runtimes = self.runtimes().await?;
+        Ok(runtimes
+            .iter()
+            .find(|runtime| runtime.matches(name))
+            .cloned())
+    }
+
+    /// Compile and run code.
+    pub async fn execute(&self, runtime: &Runtime, code: &str) -> CommandResult<Execution> {
+        let request = ExecuteRequest {
+            language: &runtime.language,
+            version: &runtime.version,
+            files: [SourceFile { content: code }],
+        };
+        let response = self
+            .client
+            .post(self.url("execute"))
+            .json(&request)
+            .send()
+            .await?;
+
+        // Piston explains rejected code, such as code that is too large
+        if response.status().is_client_error() {
+            let message = match response.json::<PistonError>().await {
+                Ok(error) => error.message,
+                Err(_) => "The code couldn't be run.".to_string(),
+            };
+            return Err(KurumiError::usage(message));
+        }
+        Ok(response.error_for_status()?.json().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_fenced_code() {
+        assert_eq!(
+            extract_code("run ```py\nprint(1)\n```"),
+            Some(CodeBlock {
+                language: Some("py".to_string()),
+                code: "print(1)".to_string(),
+            })
+        );
+        assert_eq!(
+            extract_code("```\nputs 1\nputs 2```"),
+            Some(CodeBlock {
+                language: None,
+                code: "puts 1\nputs 2".to_string(),
+            })
+        );
+        assert_eq!(
+            extract_code("```print(1)```").map(|block| block.code),
+            Some("print(1)".to_string())
+        );
+        assert_eq!(
+            extract_code("```js\nconsole.log(1)").map(|block| block.code),
+            Some("console.log(1)".to_string())
+        );
+        assert_eq!(extract_code("```rust\n```"), None);
+        assert_eq!(extract_code("no code"), None);
+    }
+}
@@ -0,0 +1,1217 @@
+//! Voice sessions: which channel the bot is in in each server and what it's
+//! playing there.
+//!
+//! [`Player`] joins voice channels through songbird and plays each server's
+//! queue one track at a time. A track is only looked up with yt-dlp when it's
+//! about to play, so long playlists queue quickly. Radio streams play until
+//! they're skipped, reconnecting when the stream drops. Soundboard clips are mixed
+//! over whatever else is playing. Once nothing has played for [`IDLE_TIMEOUT`],
+//! the bot leaves the channel.
+//!
+//! Each track is run through the server's audio filters, read when the track
+//! starts, so changing them takes effect from the next track. When a track
+//! starts, its now-playing message is posted and, with karaoke on, shows the
+//! track's synced lyrics until it ends.
+//!
+//! Servers with a 24/7 channel are the exception: the bot stays there, rejoins
+//! it when it's disconnected, and saves the queue in the `music_sessions` store
+//! so it picks up where it left off after a restart.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, GuildId, UserId};
+use serenity::prelude::*;
+use songbird::input::{Input, Metadata};
+use songbird::tracks::TrackHandle;
+use songbird::{
+    Call, Event, EventContext, EventHandler as VoiceEventHandler, Songbird, TrackEvent,
+};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::models::guild_config::guild_config;
+use crate::models::music::AudioFilters;
+use crate::storage::JsonStore;
+use crate::utils::constants::DEFAULT_COLOR;
+use crate::utils::duration::format_clock;
+use crate::utils::lyrics::{self, LyricsKey, PlaybackClock};
+use crate::utils::rest;
+use crate::utils::voice::{self, InputOptions};
+
+/// How long the bot stays in a channel with nothing playing.
+pub const IDLE_TIMEOUT: Duration = Duration::from_secs(2 * 60);
+
+/// How many times in a row a dropped stream is reconnected before it's skipped.
+const MAX_RECONNECTS: u32 = 5;
+
+/// How long to wait before reconnecting, times the attempt.
+const RECONNECT_DELAY: Duration = Duration::from_secs(3);
+
+/// How long a stream has to play before its reconnect attempts start over.
+const STREAM_STABLE: Duration = Duration::from_secs(60);
+
+/// How many times the bot tries to rejoin a 24/7 channel after a disconnect.
+const MAX_REJOINS: u32 = 5;
+
+/// How long to wait before rejoining a 24/7 channel, times the attempt.
+const REJOIN_DELAY: Duration = Duration::from_secs(5);
+
+/// How often 24/7 sessions are saved, so a restart resumes close to where
+/// playback was.
+const SAVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// A track waiting to be played.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QueuedTrack {
+    /// What yt-dlp is given to find the audio: a page URL or a search.
+    pub query: String,
+    pub title: String,
+    /// Length in seconds, if known.
+    #[serde(default)]
+    pub length: Option<u64>,
+    pub requested_by: u64,
+    /// Whether `query` is a radio stream URL, played as it is until skipped.
+    #[serde(default)]
+    pub stream: bool,
+    /// Where to start playing, in seconds, when the track is resumed.
+    #[serde(default)]
+    pub offset: u64,
+}
+
+impl QueuedTrack {
+    /// A track for what yt-dlp found.
+    pub fn from_media(media: &voice::Media, query: &str, requested_by: UserId) -> Self {
+        Self {
+            query: media
+                .webpage_url
+                .clone()
+                .unwrap_or_else(|| query.to_string()),
+            title: media.title.clone(),
+            length: media.length().map(|length| length.as_secs()),
+            requested_by: requested_by.0,
+            stream: false,
+            offset: 0,
+        }
+    }
+
+    /// A track to search for when it's about to play.
+    pub fn search(query: String, length: Duration, requested_by: UserId) -> Self {
+        Self {
+            title: query.clone(),
+            query,
+            length: Some(length.as_secs()),
+            requested_by: requested_by.0,
+            stream: false,
+            offset: 0,
+        }
+    }
+
+    /// A radio stream.
+    pub fn radio(name: &str, url: &str, requested_by: UserId) -> Self {
+        Self {
+            query: url.to_string(),
+            title: format!("📻 {}", name),
+            length: None,
+            requested_by: requested_by.0,
+            stream: true,
+            offset: 0,
+        }
+    }
+
+    /// How long the track is, if known.
+    pub fn length(&self) -> Option<Duration> {
+        self.length.map(Duration::from_secs)
+    }
+
+    /// The track as a line of a list, like `Title (3:45) · @user`.
+    pub fn line(&self) -> String {
+        match self.length() {
+            Some(length) => format!(
+                "{} ({}) · <@{}>",
+                self.title,
+                format_clock(length),
+                self.requested_by
+            ),
+            None => format!("{} · <@{}>", self.title, self.requested_by),
+        }
+    }
+}
+
+/// The track playing in a server.
+struct Current {
+    track: QueuedTrack,
+    /// Set once the track is looked up and started.
+    handle: Option<TrackHandle>,
+    /// The session's activity count when the track started, so events from
+    /// tracks that were since skipped are ignored.
+    generation: u64,
+    /// Members who voted to skip the track.
+    votes: HashSet<UserId>,
+    /// When the track last started playing.
+    started: Option<Instant>,
+    /// Reconnects since a stream last played steadily.
+    reconnects: u32,
+    /// How much faster than the source the track plays, from the audio filters
+    /// it was started with.
+    tempo: f64,
+    /// What to search for the track's lyrics, once it's looked up.
+    lyrics: Option<String>,
+}
+
+/// What's saved of a 24/7 session, so it's picked up again after a restart.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SavedSession {
+    /// Where now-playing messages go.
+    pub text_channel: u64,
+    /// The track that was playing, starting where it was, then the queue.
+    #[serde(default)]
+    pub queue: Vec<QueuedTrack>,
+}
+
+/// Every 24/7 session, by guild.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SavedSessions {
+    #[serde(default)]
+    guilds: HashMap<u64, SavedSession>,
+}
+
+/// What the bot is doing in a server's voice channel.
+struct Session {
+    channel_id: ChannelId,
+    /// The 24/7 channel the bot stays in, if the server set one.
+    home: Option<ChannelId>,
+    /// Where now-playing messages go.
+    text_channel: ChannelId,
+    /// For posting messages from track events.
+    ctx: Context,
+    queue: VecDeque<QueuedTrack>,
+    current: Option<Current>,
+    /// Clips playing right now.
+    clips: usize,
+    /// Bumped whenever something starts, so a pending leave knows to stay.
+    activity: u64,
+}
+
+impl Session {
+    fn is_idle(&self) -> bool {
+        self.clips == 0 && self.current.is_none() && self.queue.is_empty()
+    }
+
+    /// Take the next track off the queue and make it the current one, before
+    /// it's looked up. Returns the track and its generation.
+    fn begin_next(&mut self) -> Option<(QueuedTrack, u64)> {
+        let track = self.queue.pop_front()?;
+        self.activity += 1;
+        self.current = Some(Current {
+            track: track.clone(),
+            handle: None,
+            generation: self.activity,
+            votes: HashSet::new(),
+            started: None,
+            reconnects: 0,
+            tempo: 1.0,
+            lyrics: None,
+        });
+        Some((track, self.activity))
+    }
+}
+
+/// A server's queue, as shown by `queue`.
+pub struct QueueSnapshot {
+    pub channel_id: ChannelId,
+    /// The track playing, and how far into it playback is.
+    pub current: Option<(QueuedTrack, Duration)>,
+    pub upcoming: Vec<QueuedTrack>,
+}
+
+/// Joins voice channels and plays audio in them.
+pub struct Player {
+    songbird: Arc<Songbird>,
+    sessions: Mutex<HashMap<GuildId, Session>>,
+    saved: JsonStore<SavedSessions>,
+}
+
+impl Player {
+    /// Create a player that connects through songbird and saves 24/7 sessions
+    /// in a store.
+    pub fn new(songbird: Arc<Songbird>, saved: JsonStore<SavedSessions>) -> Self {
+        Self {
+            songbird,
+            sessions: Mutex::new(HashMap::new()),
+            saved,
+        }
+    }
+
+    /// Save 24/7 sessions every [`SAVE_INTERVAL`] until the bot stops.
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SAVE_INTERVAL);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                self.save().await;
+            }
+        })
+    }
+
+    /// Save every 24/7 session, with how far into its track playback is.
+    async fn save(&self) {
+        let sessions: Vec<_> = {
+            let sessions = self.sessions();
+            sessions
+                .iter()
+                .filter(|(_, session)| session.home.is_some())
+                .map(|(guild_id, session)| {
+                    let current = session.current.as_ref().map(|current| {
+                        (
+                            current.track.clone(),
+                            current.handle.clone(),
+                            current.started,
+                            current.tempo,
+                        )
+                    });
+                    let queue: Vec<QueuedTrack> = session.queue.iter().cloned().collect();
+                    (*guild_id, session.text_channel, current, queue)
+                })
+                .collect()
+        };
+
+        let mut saved = SavedSessions::default();
+        for (guild_id, text_channel, current, queue) in sessions {
+            let mut tracks = Vec::with_capacity(queue.len() + 1);
+            if let Some((mut track, handle, started, tempo)) = current {
+                if !track.stream {
+                    let played = position(handle.as_ref(), started).await;
+                    track.offset += played.mul_f64(tempo).as_secs();
+                }
+                tracks.push(track);
+            }
+            tracks.extend(queue);
+            saved.guilds.insert(
+                guild_id.0,
+                SavedSession {
+                    text_channel: text_channel.0,
+                    queue: tracks,
+                },
+            );
+        }
+        if *self.saved.read().await == saved {
+            return;
+        }
+        if let Err(e) = self.saved.update(|data| *data = saved).await {
+            warn!("Couldn't save the music sessions: {}", e);
+        }
+    }
+
+    /// Save 24/7 sessions soon, after the queue changed.
+    fn changed(self: &Arc<Self>) {
+        let player = self.clone();
+        tokio::spawn(async move { player.save().await });
+    }
+
+    fn sessions(&self) -> std::sync::MutexGuard<'_, HashMap<GuildId, Session>> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The channel the bot is playing in in a server, if any.
+    pub fn channel(&self, guild_id: GuildId) -> Option<ChannelId> {
+        self.sessions()
+            .get(&guild_id)
+            .map(|session| session.channel_id)
+    }
+
+    /// How many tracks a server has queued, counting the one playing.
+    pub fn queued(&self, guild_id: GuildId) -> usize {
+        self.sessions().get(&guild_id).map_or(0, |session| {
+            session.queue.len() + usize::from(session.current.is_some())
+        })
+    }
+
+    /// A server's queue, if the bot is in voice there.
+    pub async fn snapshot(&self, guild_id: GuildId) -> Option<QueueSnapshot> {
+        let (channel_id, current, handle, upcoming) = {
+            let sessions = self.sessions();
+            let session = sessions.get(&guild_id)?;
+            let current = session.current.as_ref();
+            (
+                session.channel_id,
+                current.map(|current| (current.track.clone(), current.tempo)),
+                current.and_then(|current| current.handle.clone()),
+                session.queue.iter().cloned().collect(),
+            )
+        };
+        let current = match current {
+            Some((track, tempo)) => {
+                let position = match handle {
+                    Some(handle) => handle
+                        .get_info()
+                        .await
+                        .map(|state| state.position)
+                        .unwrap_or_default(),
+                    None => Duration::ZERO,
+                };
+                let offset = Duration::from_secs(track.offset);
+                Some((track, offset + position.mul_f64(tempo)))
+            }
+            None => None,
+        };
+        Some(QueueSnapshot {
+            channel_id,
+            current,
+            upcoming,
+        })
+    }
+
+    /// Join a voice channel, moving from another one in the server only if
+    /// nothing is playing there.
+    async fn join(
+        &self,
+        ctx: &Context,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        text_channel: ChannelId,
+    ) -> Result<Arc<tokio::sync::Mutex<Call>>, String> {
+        if let Some(session) = self.sessions().get(&guild_id) {
+            if session.channel_id != channel_id && !session.is_idle() {
+                return Err(format!("I'm already playing in <#{}>.", session.channel_id));
+            }
+        }
+        let (call, joined) = self.songbird.join(guild_id, channel_id).await;
+        if let Err(e) = joined {
+            debug!("Couldn't join voice channel {}: {}", channel_id, e);
+            return Err(format!("I couldn't join <#{}>.", channel_id));
+        }
+        let mut sessions = self.sessions();
+        let session = sessions.entry(guild_id).or_insert_with(|| Session {
+            channel_id,
+            home: None,
+            text_channel,
+            ctx: ctx.clone(),
+            queue: VecDeque::new(),
+            current: None,
+            clips: 0,
+            activity: 0,
+        });
+        session.channel_id = channel_id;
+        session.text_channel = text_channel;
+        Ok(call)
+    }
+
+    /// Add tracks to a server's queue, joining the voice channel and starting
+    /// playback if nothing is playing. Tracks past `max_queue` are left out.
+    /// Returns the position of the first track, where 0 is playing now, and
+    /// how many were added.
+    pub async fn enqueue(
+        self: &Arc<Self>,
+        ctx: &Context,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        text_channel: ChannelId,
+        mut tracks: Vec<QueuedTrack>,
+        max_queue: usize,
+    ) -> Result<(usize, usize), String> {
+        self.join(ctx, guild_id, channel_id, text_channel).await?;
+        let (position, added) = {
+            let mut sessions = self.sessions();
+            let session = sessions
+                .get_mut(&guild_id)
+                .ok_or("I was disconnected from voice.")?;
+            let position = session.queue.len() + usize::from(session.current.is_some());
+            if position >= max_queue {
+                return Err(format!("The queue is full, it holds {} tracks.", max_queue));
+            }
+            tracks.truncate(max_queue - position);
+            let added = tracks.len();
+            session.queue.extend(tracks);
+            session.activity += 1;
+            self.play_if_idle(guild_id, session);
+            (position, added)
+        };
+        self.changed();
+        Ok((position, added))
+    }
+
+    /// Set or remove a server's 24/7 channel. Setting one joins it unless the
+    /// bot is busy playing elsewhere, in which case it moves there once the
+    /// queue ends.
+    pub async fn set_always_on(
+        self: &Arc<Self>,
+        ctx: &Context,
+        guild_id: GuildId,
+        home: Option<ChannelId>,
+        text_channel: ChannelId,
+    ) -> Result<(), String> {
+        match home {
+            Some(home) => {
+                let stay = self
+                    .sessions()
+                    .get(&guild_id)
+                    .is_some_and(|session| session.channel_id != home && !session.is_idle());
+                if !stay {
+                    self.join(ctx, guild_id, home, text_channel).await?;
+                }
+                if let Some(session) = self.sessions().get_mut(&guild_id) {
+                    session.home = Some(home);
+                }
+            }
+            None => {
+                let idle = match self.sessions().get_mut(&guild_id) {
+                    Some(session) => {
+                        session.home = None;
+                        session.is_idle()
+                    }
+                    None => false,
+                };
+                if idle {
+                    self.leave_when_idle(guild_id);
+                }
+            }
+        }
+        self.changed();
+        Ok(())
+    }
+
+    /// Join a server's 24/7 channel after a restart, resuming the saved queue.
+    pub async fn restore(self: &Arc<Self>, ctx: &Context, guild_id: GuildId, home: ChannelId) {
+        if self.sessions().contains_key(&guild_id) {
+            return;
+        }
+        let saved = self
+            .saved
+            .read()
+            .await
+            .guilds
+            .get(&guild_id.0)
+            .cloned()
+            .unwrap_or_default();
+        // A voice channel's own chat is the fallback for now-playing messages
+        let text_channel = match saved.text_channel {
+            0 => home,
+            id => ChannelId(id),
+        };
+        if let Err(e) = self.join(ctx, guild_id, home, text_channel).await {
+            warn!(
+                "Couldn't rejoin the 24/7 channel in guild {}: {}",
+                guild_id, e
+            );
+            return;
+        }
+        if let Some(session) = self.sessions().get_mut(&guild_id) {
+            session.home = Some(home);
+            session.queue.extend(saved.queue);
+            self.play_if_idle(guild_id, session);
+        }
+        debug!("Rejoined the 24/7 channel in guild {}", guild_id);
+    }
+
+    /// Play the next track in a server's queue, skipping tracks that can't be
+    /// played, or leave once the queue is empty.
+    async fn advance(self: Arc<Self>, guild_id: GuildId) {
+        loop {
+            let next = {
+                let mut sessions = self.sessions();
+                let session = match sessions.get_mut(&guild_id) {
+                    Some(session) => session,
+                    None => return,
+                };
+                match session.begin_next() {
+                    Some((track, generation)) => {
+                        Ok((track, generation, session.ctx.clone(), session.text_channel))
+                    }
+                    None => {
+                        session.current = None;
+                        Err((
+                            session.is_idle(),
+                            session.home.filter(|home| *home != session.channel_id),
+                            session.ctx.clone(),
+                            session.text_channel,
+                        ))
+                    }
+                }
+            };
+            let (track, generation, ctx, text_channel) = match next {
+                Ok(next) => next,
+                Err((idle, home, ctx, text_channel)) => {
+                    self.changed();
+                    // A 24/7 session goes back to its channel instead of leaving
+                    if let Some(home) = home {
+                        if let Err(e) = self.join(&ctx, guild_id, home, text_channel).await {
+                            debug!("Couldn't return to the 24/7 channel: {}", e);
+                        }
+                    } else if idle {
+                        self.leave_when_idle(guild_id);
+                    }
+                    return;
+                }
+            };
+
+            if !self
+                .play(guild_id, &track, generation, &ctx, text_channel)
+                .await
+            {
+                return;
+            }
+        }
+    }
+
+    /// Start the next track if nothing is playing. The track becomes the
+    /// current one under the caller's lock, so only one caller starts it.
+    fn play_if_idle(self: &Arc<Self>, guild_id: GuildId, session: &mut Session) {
+        if session.current.is_some() {
+            return;
+        }
+        let (track, generation) = match session.begin_next() {
+            Some(next) => next,
+            None => return,
+        };
+        let player = self.clone();
+        let ctx = session.ctx.clone();
+        let text_channel = session.text_channel;
+        tokio::spawn(async move {
+            if player
+                .play(guild_id, &track, generation, &ctx, text_channel)
+                .await
+            {
+                player.advance(guild_id).await;
+            }
+        });
+    }
+
+    /// Start a track that was made the current one. Returns `true` if it
+    /// couldn't be played and the queue should move on.
+    async fn play(
+        self: &Arc<Self>,
+        guild_id: GuildId,
+        track: &QueuedTrack,
+        generation: u64,
+        ctx: &Context,
+        text_channel: ChannelId,
+    ) -> bool {
+        match self.start(guild_id, track, generation).await {
+            Ok(true) => {
+                self.changed();
+                self.track_started(guild_id, generation, ctx, text_channel, track)
+                    .await;
+                return false;
+            }
+            // Skipped or stopped while it was being looked up
+            Ok(false) => return false,
+            Err(e) => {
+                warn!("Couldn't play {} in guild {}: {}", track.query, guild_id, e);
+                let text = format!("I couldn't play **{}**, so I skipped it.", track.title);
+                notify(ctx, text_channel, &text).await;
+            }
+        }
+        // Keep going only if nothing else took over in the meantime
+        self.is_current(guild_id, generation)
+    }
+
+    /// The input for a track, run through audio filters. Tracks are looked up
+    /// with yt-dlp; streams are read as they are.
+    async fn input(track: &QueuedTrack, filters: &AudioFilters) -> Result<Source, String> {
+        if track.stream {
+            let options = InputOptions {
+                network: true,
+                filters: filters.filter_graph(),
+                ..Default::default()
+            };
+            let metadata = Metadata {
+                title: Some(track.title.clone()),
+                source_url: Some(track.query.clone()),
+                ..Default::default()
+            };
+            let input =
+                voice::ffmpeg(&track.query, &options, metadata).map_err(|e| e.to_string())?;
+            return Ok(Source {
+                input,
+                length: None,
+                lyrics: None,
+            });
+        }
+        let media = voice::lookup(&track.query)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or("nothing found")?;
+        let url = media.url.clone().ok_or("no audio URL")?;
+        let metadata = Metadata {
+            title: Some(media.title.clone()),
+            duration: media.length(),
+            source_url: media.webpage_url.clone(),
+            ..Default::default()
+        };
+        let options = InputOptions {
+            start: Duration::from_secs(track.offset),
+            filters: filters.filter_graph(),
+            ..InputOptions::for_media(&media)
+        };
+        let input = voice::ffmpeg(&url, &options, metadata).map_err(|e| e.to_string())?;
+        let lyrics = lyrics::search_query(
+            media.artist.as_deref(),
+            media.track.as_deref(),
+            &media.title,
+        );
+        Ok(Source {
+            input,
+            length: media.length(),
+            lyrics: Some(lyrics),
+        })
+    }
+
+    /// Look up a track and start it. Returns `false` if the track stopped being
+    /// the current one before it could start.
+    async fn start(
+        self: &Arc<Self>,
+        guild_id: GuildId,
+        track: &QueuedTrack,
+        generation: u64,
+    ) -> Result<bool, String> {
+        let filters = match self.text_channel(guild_id) {
+            Some((ctx, _)) => guild_config(&ctx, guild_id).await.music.filters,
+            None => AudioFilters::default(),
+        };
+        let source = Self::input(track, &filters).await?;
+        let call = self.songbird.get(guild_id).ok_or("not in voice")?;
+        let handle = call.lock().await.play_source(source.input);
+
+        let started = match self.sessions().get_mut(&guild_id) {
+            Some(session) => match session.current.as_mut() {
+                Some(current) if current.generation == generation => {
+                    current.handle = Some(handle.clone());
+                    current.started = Some(Instant::now());
+                    current.tempo = filters.tempo();
+                    current.lyrics = source.lyrics;
+                    if current.track.length.is_none() {
+                        current.track.length = source.length.map(|length| length.as_secs());
+                    }
+                    true
+                }
+                _ => false,
+            },
+            None => false,
+        };
+        if !started {
+            let _ = handle.stop();
+            return Ok(false);
+        }
+        let ended = TrackEnded {
+            player: self.clone(),
+            guild_id,
+            generation,
+        };
+        if handle
+            .add_event(Event::Track(TrackEvent::End), ended)
+            .is_err()
+        {
+            // The track already ended
+            self.track_ended(guild_id, generation);
+        }
+        Ok(true)
+    }
+
+    /// Post the now-playing message for a track that just started and, with
+    /// karaoke on, keep it showing the track's synced lyrics until it ends.
+    async fn track_started(
+        self: &Arc<Self>,
+        guild_id: GuildId,
+        generation: u64,
+        ctx: &Context,
+        text_channel: ChannelId,
+        track: &QueuedTrack,
+    ) {
+        let message = match announce(ctx, text_channel, track).await {
+            Some(message) => message,
+            None => return,
+        };
+        if !guild_config(ctx, guild_id).await.music.karaoke {
+            return;
+        }
+        let query = self.sessions().get(&guild_id).and_then(|session| {
+            session
+                .current
+                .as_ref()
+                .filter(|current| current.generation == generation)
+                .and_then(|current| current.lyrics.clone())
+        });
+        let (query, client) = match (query, ctx.data.read().await.get::<LyricsKey>()) {
+            (Some(query), Some(client)) => (query, client.clone()),
+            _ => return,
+        };
+
+        let clock = TrackClock {
+            player: self.clone(),
+            guild_id,
+            generation,
+        };
+        let ctx = ctx.clone();
+        let description = track.line();
+        let length = track.length();
+        tokio::spawn(async move {
+            let lyrics = match client.synced(&query, length).await {
+                Ok(Some(lyrics)) => lyrics,
+                Ok(None) => return,
+                Err(e) => {
+                    debug!("Couldn't look up lyrics for {}: {}", query, e);
+                    return;
+                }
+            };
+            let mut message = message;
+            if let Err(e) = lyrics::run(&ctx, &mut message, &description, lyrics, &clock).await {
+                debug!("Stopped showing lyrics in guild {}: {}", guild_id, e);
+            }
+        });
+    }
+
+    /// Move on once the current track ended. A stream that ended was dropped,
+    /// so it's reconnected until it fails [`MAX_RECONNECTS`] times in a row.
+    fn track_ended(self: &Arc<Self>, guild_id: GuildId, generation: u64) {
+        let reconnect = {
+            let mut sessions = self.sessions();
+            let current = match sessions
+                .get_mut(&guild_id)
+                .and_then(|session| session.current.as_mut())
+            {
+                Some(current) if current.generation == generation => current,
+                _ => return,
+            };
+            current.handle = None;
+            if !current.track.stream {
+                None
+            } else {
+                if current
+                    .started
+                    .is_some_and(|started| started.elapsed() >= STREAM_STABLE)
+                {
+                    current.reconnects = 0;
+                }
+                current.reconnects += 1;
+                Some((current.track.clone(), current.reconnects))
+            }
+        };
+
+        let player = self.clone();
+        match reconnect {
+            Some((track, attempt)) if attempt <= MAX_RECONNECTS => {
+                debug!("Reconnecting to {} in guild {}", track.query, guild_id);
+                tokio::spawn(async move {
+                    tokio::time::sleep(RECONNECT_DELAY * attempt).await;
+                    if !player.is_current(guild_id, generation) {
+                        return;
+                    }
+                    if let Err(e) = player.start(guild_id, &track, generation).await {
+                        debug!("Couldn't reconnect to {}: {}", track.query, e);
+                        player.track_ended(guild_id, generation);
+                    }
+                });
+            }
+            Some((track, _)) => {
+                tokio::spawn(async move {
+                    if let Some((ctx, channel_id)) = player.text_channel(guild_id) {
+                        let text = format!(
+                            "**{}** stopped and I couldn't reconnect, so I skipped it.",
+                            track.title
+                        );
+                        notify(&ctx, channel_id, &text).await;
+                    }
+                    player.advance(guild_id).await;
+                });
+            }
+            None => {
+                tokio::spawn(async move { player.advance(guild_id).await });
+            }
+        }
+    }
+
+    /// Whether a track is still the one playing in a server.
+    fn is_current(&self, guild_id: GuildId, generation: u64) -> bool {
+        self.sessions().get(&guild_id).is_some_and(|session| {
+            session
+                .current
+                .as_ref()
+                .is_some_and(|current| current.generation == generation)
+        })
+    }
+
+    /// Where a server's session posts messages.
+    fn text_channel(&self, guild_id: GuildId) -> Option<(Context, ChannelId)> {
+        self.sessions()
+            .get(&guild_id)
+            .map(|session| (session.ctx.clone(), session.text_channel))
+    }
+
+    /// Skip the track playing in a server, returning it.
+    pub fn skip(self: &Arc<Self>, guild_id: GuildId) -> Option<QueuedTrack> {
+        let (track, handle) = {
+            let mut sessions = self.sessions();
+            let session = sessions.get_mut(&guild_id)?;
+            session.activity += 1;
+            let activity = session.activity;
+            let current = session.current.as_mut()?;
+            // A new generation makes the track's end event, a pending reconnect
+            // or a lookup in progress stale
+            current.generation = activity;
+            (current.track.clone(), current.handle.take())
+        };
+        if let Some(handle) = handle {
+            let _ = handle.stop();
+        }
+        let player = self.clone();
+        tokio::spawn(async move { player.advance(guild_id).await });
+        Some(track)
+    }
+
+    /// Vote to skip the track playing in a server. Returns the track and how
+    /// many of `listeners` have voted to skip it, or `None` if nothing is
+    /// playing.
+    pub fn vote_skip(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        listeners: &[UserId],
+    ) -> Option<(QueuedTrack, usize)> {
+        let mut sessions = self.sessions();
+        let current = sessions.get_mut(&guild_id)?.current.as_mut()?;
+        current.votes.insert(user_id);
+        // Votes from members who left the channel don't count
+        let votes = current
+            .votes
+            .iter()
+            .filter(|voter| listeners.contains(voter))
+            .count();
+        Some((current.track.clone(), votes))
+    }
+
+    /// Remove every track waiting in a server's queue, returning how many there
+    /// were. The track playing keeps playing.
+    pub fn clear(self: &Arc<Self>, guild_id: GuildId) -> usize {
+        let removed = self
+            .sessions()
+            .get_mut(&guild_id)
+            .map_or(0, |session| std::mem::take(&mut session.queue).len());
+        self.changed();
+        removed
+    }
+
+    /// Stop playback and empty a server's queue. The bot leaves the voice
+    /// channel, or goes back to the 24/7 channel if the server has one, which
+    /// is returned.
+    pub async fn stop(self: &Arc<Self>, guild_id: GuildId) -> Option<ChannelId> {
+        let stopped = {
+            let mut sessions = self.sessions();
+            match sessions.get_mut(&guild_id) {
+                Some(session) if session.home.is_some() => {
+                    session.queue.clear();
+                    session.activity += 1;
+                    let handle = session.current.take().and_then(|current| current.handle);
+                    Some((
+                        session.home,
+                        session.channel_id,
+                        session.ctx.clone(),
+                        session.text_channel,
+                        handle,
+                    ))
+                }
+                _ => None,
+            }
+        };
+        let (home, channel_id, ctx, text_channel, handle) = match stopped {
+            Some((Some(home), channel_id, ctx, text_channel, handle)) => {
+                (home, channel_id, ctx, text_channel, handle)
+            }
+            _ => {
+                self.leave(guild_id).await;
+                return None;
+            }
+        };
+        if let Some(handle) = handle {
+            let _ = handle.stop();
+        }
+        if channel_id != home {
+            if let Err(e) = self.join(&ctx, guild_id, home, text_channel).await {
+                debug!("Couldn't return to the 24/7 channel: {}", e);
+            }
+        }
+        self.changed();
+        Some(home)
+    }
+
+    /// Play a soundboard clip in a voice channel, over anything else playing.
+    pub async fn play_clip(
+        self: &Arc<Self>,
+        ctx: &Context,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        text_channel: ChannelId,
+        path: &Path,
+    ) -> Result<(), String> {
+        let call = self.join(ctx, guild_id, channel_id, text_channel).await?;
+        let input = match voice::ffmpeg(path, &InputOptions::default(), Metadata::default()) {
+            Ok(input) => input,
+            Err(e) => {
+                warn!("Couldn't start ffmpeg for {}: {}", path.display(), e);
+                self.leave_when_idle(guild_id);
+                return Err("I couldn't play that clip.".to_string());
+            }
+        };
+        let handle = call.lock().await.play_source(input);
+        if let Some(session) = self.sessions().get_mut(&guild_id) {
+            session.clips += 1;
+            session.activity += 1;
+        }
+        let ended = ClipEnded {
+            player: self.clone(),
+            guild_id,
+        };
+        if handle
+            .add_event(Event::Track(TrackEvent::End), ended)
+            .is_err()
+        {
+            // The clip already ended
+            self.clip_ended(guild_id);
+        }
+        Ok(())
+    }
+
+    fn clip_ended(self: &Arc<Self>, guild_id: GuildId) {
+        let idle = match self.sessions().get_mut(&guild_id) {
+            Some(session) => {
+                session.clips = session.clips.saturating_sub(1);
+                session.is_idle()
+            }
+            None => false,
+        };
+        if idle {
+            self.leave_when_idle(guild_id);
+        }
+    }
+
+    /// Leave a server's channel if nothing starts within [`IDLE_TIMEOUT`].
+    fn leave_when_idle(self: &Arc<Self>, guild_id: GuildId) {
+        let activity = match self.sessions().get(&guild_id) {
+            Some(session) => session.activity,
+            None => return,
+        };
+        let player = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(IDLE_TIMEOUT).await;
+            let idle = player.sessions().get(&guild_id).is_some_and(|session| {
+                session.activity == activity && session.is_idle() && session.home.is_none()
+            });
+            if idle {
+                player.leave(guild_id).await;
+            }
+        });
+    }
+
+    /// Stop everything in a server and leave its voice channel.
+    pub async fn leave(&self, guild_id: GuildId) {
+        self.sessions().remove(&guild_id);
+        if let Err(e) = self.songbird.remove(guild_id).await {
+            debug!("Couldn't leave voice in guild {}: {}", guild_id, e);
+        }
+    }
+
+    /// Handle the bot being disconnected from voice, such as by a moderator.
+    /// A 24/7 session rejoins its channel and resumes the track where it was;
+    /// any other session is forgotten.
+    pub async fn disconnected(self: &Arc<Self>, guild_id: GuildId) {
+        let current = {
+            let mut sessions = self.sessions();
+            let session = match sessions.get_mut(&guild_id) {
+                Some(session) => session,
+                None => return,
+            };
+            match session.home {
+                Some(home) => {
+                    session.channel_id = home;
+                    session.activity += 1;
+                    Some(session.current.take())
+                }
+                None => {
+                    sessions.remove(&guild_id);
+                    None
+                }
+            }
+        };
+        debug!("Disconnected from voice in guild {}", guild_id);
+        if let Some(call) = self.songbird.get(guild_id) {
+            call.lock().await.stop();
+        }
+        let current = match current {
+            Some(current) => current,
+            None => return,
+        };
+
+        // Put the track back at the front of the queue, to resume where it was
+        if let Some(current) = current {
+            let mut track = current.track;
+            if !track.stream {
+                let played = position(current.handle.as_ref(), current.started).await;
+                track.offset += played.mul_f64(current.tempo).as_secs();
+            }
+            if let Some(session) = self.sessions().get_mut(&guild_id) {
+                session.queue.push_front(track);
+            }
+        }
+        self.changed();
+
+        let player = self.clone();
+        tokio::spawn(async move { player.rejoin(guild_id).await });
+    }
+
+    /// Rejoin a server's 24/7 channel after a disconnect, giving up after
+    /// [`MAX_REJOINS`] attempts.
+    async fn rejoin(self: Arc<Self>, guild_id: GuildId) {
+        for attempt in 1..=MAX_REJOINS {
+            tokio::time::sleep(REJOIN_DELAY * attempt).await;
+            let session = self.sessions().get(&guild_id).and_then(|session| {
+                session
+                    .home
+                    .map(|home| (home, session.ctx.clone(), session.text_channel))
+            });
+            // 24/7 was turned off or the bot left in the meantime
+            let (home, ctx, text_channel) = match session {
+                Some(session) => session,
+                None => return,
+            };
+            match self.join(&ctx, guild_id, home, text_channel).await {
+                Ok(_) => {
+                    debug!("Rejoined the 24/7 channel in guild {}", guild_id);
+                    if let Some(session) = self.sessions().get_mut(&guild_id) {
+                        self.play_if_idle(guild_id, session);
+                    }
+                    return;
+                }
+                Err(e) => debug!("Couldn't rejoin the 24/7 channel: {}", e),
+            }
+        }
+        warn!(
+            "Gave up rejoining the 24/7 channel in guild {} after {} attempts",
+            guild_id, MAX_REJOINS
+        );
+        self.sessions().remove(&guild_id);
+    }
+}
+
+/// How long a track has played, from its handle or, if the driver is gone,
+/// from when it started. Multiply by the track's tempo for the position in the
+/// source.
+async fn position(handle: Option<&TrackHandle>, started: Option<Instant>) -> Duration {
+    let state = match handle {
+        Some(handle) => handle.get_info().await.ok(),
+        None => None,
+    };
+    state
+        .map(|state| state.position)
+        .or_else(|| started.map(|started| started.elapsed()))
+        .unwrap_or_default()
+}
+
+/// Post a message about playback.
+async fn notify(ctx: &Context, channel_id: ChannelId, text: &str) {
+    let sent = rest::call(ctx, "send_message", || {
+        channel_id.send_message(&ctx.http, |m| {
+            m.allowed_mentions(|am| am.empty_parse())
+                .embed(|e| e.description(text).color(DEFAULT_COLOR))
+        })
+    })
+    .await;
+    if let Err(e) = sent {
+        debug!("Couldn't post to {}: {}", channel_id, e);
+    }
+}
+
+/// Post what started playing, returning the now-playing message.
+async fn announce(ctx: &Context, channel_id: ChannelId, track: &QueuedTrack) -> Option<Message> {
+    let description = track.line();
+    let sent = rest::call(ctx, "send_message", || {
+        channel_id.send_message(&ctx.http, |m| {
+            m.allowed_mentions(|am| am.empty_parse()).embed(|e| {
+                e.title("🎵 Now playing")
+                    .description(&description)
+                    .color(DEFAULT_COLOR)
+            })
+        })
+    })
+    .await;
+    match sent {
+        Ok(message) => Some(message),
+        Err(e) => {
+            debug!("Couldn't announce the track in {}: {}", channel_id, e);
+            None
+        }
+    }
+}
+
+/// A track's decoded audio, ready to play.
+struct Source {
+    input: Input,
+    /// Length of the track, if known.
+    length: Option<Duration>,
+    /// What to search for the track's lyrics; streams have none.
+    lyrics: Option<String>,
+}
+
+/// Where playback of a server's current track is, for synced lyrics.
+struct TrackClock {
+    player: Arc<Player>,
+    guild_id: GuildId,
+    generation: u64,
+}
+
+#[async_trait]
+impl PlaybackClock for TrackClock {
+    async fn position(&self) -> Option<Duration> {
+        let (handle, offset, tempo) = {
+            let sessions = self.player.sessions();
+            let current = sessions.get(&self.guild_id)?.current.as_ref()?;
+            if current.generation != self.generation {
+                return None;
+            }
+            (current.handle.clone()?, current.track.offset, current.tempo)
+        };
+        // Fails once the track ended
+        let played = handle.get_info().await.ok()?.position;
+        Some(Duration::from_secs(offset) + played.mul_f64(tempo))
+    }
+}
+
+/// Moves on to the next track when one ends.
+struct TrackEnded {
+    player: Arc<Player>,
+    guild_id: GuildId,
+    generation: u64,
+}
+
+#[async_trait]
+impl VoiceEventHandler for TrackEnded {
+    async fn act(&self, _ctx: &EventContext<'_>) -> Option<Event> {
+        self.player.track_ended(self.guild_id, self.generation);
+        None
+    }
+}
+
+/// Counts a clip as done when its track ends.
+struct ClipEnded {
+    player: Arc<Player>,
+    guild_id: GuildId,
+}
+
+#[async_trait]
+impl VoiceEventHandler for ClipEnded {
+    async fn act(&self, _ctx: &EventContext<'_>) -> Option<Event> {
+        self.player.clip_ended(self.guild_id);
+        None
+    }
+}
+
+/// Key for storing the player in the client data.
+pub struct PlayerKey;
+
+impl TypeMapKey for PlayerKey {
+    type Value = Arc<Player>;
+}
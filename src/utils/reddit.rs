@@ -0,0 +1,290 @@
+//! Client for Reddit's public JSON listings, used by `meme` and subreddit feeds.
+
+use reqwest::redirect::Policy;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serenity::builder::CreateEmbed;
+use serenity::prelude::*;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::framework::command_handler::CommandResult;
+use crate::utils::cache::TtlCache;
+use crate::utils::helpers::{format_count, truncate};
+
+const REDDIT_URL: &str = "https://www.reddit.com";
+
+/// Reddit blocks requests with generic user agents.
+const USER_AGENT: &str = concat!(
+    "discord:kurumi-rs:",
+    env!("CARGO_PKG_VERSION"),
+    " (by /u/kurumi-rs)"
+);
+
+/// How long hot posts and subreddit details are cached.
+const CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// How many subreddits' posts and details are cached at once.
+const CACHE_CAPACITY: usize = 100;
+
+/// How long to wait for Reddit.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Posts fetched per listing.
+const LISTING_LIMIT: &str = "50";
+
+/// Reddit's orange, for post embeds.
+const REDDIT_COLOR: u32 = 0xFF4500;
+
+/// Subreddits `meme` picks from when none is given.
+pub const MEME_SUBREDDITS: [&str; 4] = ["memes", "dankmemes", "me_irl", "wholesomememes"];
+
+/// Key for storing the Reddit client in the client data.
+pub struct RedditKey;
+
+impl TypeMapKey for RedditKey {
+    type Value = Arc<Reddit>;
+}
+
+/// A post in a listing.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Post {
+    pub id: String,
+    pub title: String,
+    pub subreddit: String,
+    pub author: String,
+    pub permalink: String,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub post_hint: Option<String>,
+    #[serde(default)]
+    pub over_18: bool,
+    #[serde(default)]
+    pub spoiler: bool,
+    #[serde(default)]
+    pub stickied: bool,
+    #[serde(default)]
+    pub score: i64,
+    #[serde(default)]
+    pub num_comments: u64,
+    /// When the post was made (seconds since the Unix epoch).
+    #[serde(default)]
+    pub created_utc: f64,
+}
+
+impl Post {
+    /// The post's image, if it links straight to one.
+    pub fn image_url(&self) -> Option<&str> {
+        let path = self
+            .url
+            .split('?')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        let is_image = self.post_hint.as_deref() == Some("image")
+            || [".jpg", ".jpeg", ".png", ".gif", ".webp"]
+                .iter()
+                .any(|ext| path.ends_with(ext));
+        is_image.then_some(self.url.as_str())
+    }
+
+    /// The post's page on Reddit.
+    pub fn link(&self) -> String {
+        format!("{}{}", REDDIT_URL, self.permalink)
+    }
+}
+
+/// A subreddit's details.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Subreddit {
+    pub display_name: String,
+    #[serde(default)]
+    pub over18: bool,
+}
+
+#[derive(Deserialize)]
+struct Thing<T> {
+    data: T,
+}
+
+#[derive(Deserialize)]
+struct Listing {
+    children: Vec<Thing<Post>>,
+}
+
+/// Normalize a subreddit given as `memes`, `r/memes` or a link, or `None` if it
+/// isn't a valid name.
+pub fn normalize_subreddit(input: &str) -> Option<String> {
+    let input = input.trim().trim_matches(|c| c == '<' || c == '>');
+    let input = input
+        .trim_start_matches("https://")
+        .trim_start_matches("www.")
+        .trim_start_matches("old.")
+        .trim_start_matches("reddit.com")
+        .trim_start_matches('/');
+    let name = input
+        .strip_prefix("r/")
+        .unwrap_or(input)
+        .trim_end_matches('/');
+    let valid = (2..=21).contains(&name.len())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    valid.then(|| name.to_lowercase())
+}
+
+/// Reads subreddit listings, caching hot posts.
+pub struct Reddit {
+    client: reqwest::Client,
+    hot: TtlCache<Option<Vec<Post>>>,
+    subreddits: TtlCache<Option<Subreddit>>,
+}
+
+impl Default for Reddit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Reddit {
+    /// Create a client. Redirects aren't followed, since Reddit redirects missing
+    /// subreddits to its search page.
+    pub fn new() -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent(USER_AGENT)
+            .redirect(Policy::none())
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            client,
+            hot: TtlCache::new(CACHE_TTL, CACHE_CAPACITY),
+            subreddits: TtlCache::new(CACHE_TTL, CACHE_CAPACITY),
+        }
+    }
+
+    /// GET a Reddit JSON endpoint, or `None` if the subreddit is missing, banned
+    /// or private.
+    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> CommandResult<Option<T>> {
+        let response = self
+            .client
+            .get(format!("{}{}", REDDIT_URL, path))
+            .query(&[("limit", LISTING_LIMIT), ("raw_json", "1")])
+            .send()
+            .await?;
+        let status = response.status();
+        if status.is_redirection()
+            || status == StatusCode::NOT_FOUND
+            || status == StatusCode::FORBIDDEN
+        {
+            return Ok(None);
+        }
+        Ok(Some(response.error_for_status()?.json().await?))
+    }
+
+    async fn listing(&self, subreddit: &str, sort: &str) -> CommandResult<Option<Vec<Post>>> {
+        let listing: Option<Thing<Listing>> =
+            self.get(&format!("/r/{}/{}.json", subreddit, sort)).await?;
+        Ok(listing.map(|listing| {
+            listing
+                .data
+                .children
+                .into_iter()
+                .map(|child| child.data)
+                .collect()
+        }))
+    }
+
+    /// A subreddit's hot posts, or `None` if it can't be read.
+    pub async fn hot(&self, subreddit: &str) -> CommandResult<Option<Vec<Post>>> {
+        if let Some(cached) = self.hot.get(subreddit).await {
+            return Ok(cached);
+        }
+        let posts = self.listing(subreddit, "hot").await?;
+        self.hot.insert(subreddit.to_string(), posts.clone()).await;
+        Ok(posts)
+    }
+
+    /// A subreddit's newest posts, newest first. Never cached, for feeds.
+    pub async fn newest(&self, subreddit: &str) -> CommandResult<Option<Vec<Post>>> {
+        self.listing(subreddit, "new").await
+    }
+
+    /// A subreddit's details, or `None` if it can't be read.
+    pub async fn subreddit(&self, name: &str) -> CommandResult<Option<Subreddit>> {
+        if let Some(cached) = self.subreddits.get(name).await {
+            return Ok(cached);
+        }
+        let about: Option<Thing<Subreddit>> = self.get(&format!("/r/{}/about.json", name)).await?;
+        let about = about.map(|about| about.data);
+        self.subreddits
+            .insert(name.to_string(), about.clone())
+            .await;
+        Ok(about)
+    }
+}
+
+/// Build the embed for a post, showing its image if it has one.
+pub fn post_embed(post: &Post) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(truncate(&post.title, 256))
+        .url(post.link())
+        .color(REDDIT_COLOR)
+        .author(|a| a.name(format!("r/{} · u/{}", post.subreddit, post.author)))
+        .footer(|f| {
+            f.text(format!(
+                "⬆️ {} · 💬 {}",
+                format_count(post.score.max(0) as u64),
+                format_count(post.num_comments)
+            ))
+        });
+    match post.image_url() {
+        Some(image) if !post.spoiler => {
+            embed.image(image);
+        }
+        Some(_) => {
+            embed.description("Spoiler, open the post to see the image.");
+        }
+        None => {}
+    }
+    embed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_subreddits() {
+        assert_eq!(normalize_subreddit("Memes"), Some("memes".to_string()));
+        assert_eq!(normalize_subreddit("r/rust"), Some("rust".to_string()));
+        assert_eq!(
+            normalize_subreddit("https://www.reddit.com/r/rust/"),
+            Some("rust".to_string())
+        );
+        assert_eq!(normalize_subreddit("r/a"), None);
+        assert_eq!(normalize_subreddit("not a sub"), None);
+    }
+
+    #[test]
+    fn finds_images() {
+        let post: Post = serde_json::from_value(serde_json::json!({
+            "id": "abc",
+            "title": "A meme",
+            "subreddit": "memes",
+            "author": "someone",
+            "permalink": "/r/memes/comments/abc/a_meme/",
+            "url": "https://i.redd.it/abc.PNG?width=640",
+        }))
+        .unwrap();
+        assert_eq!(
+            post.image_url(),
+            Some("https://i.redd.it/abc.PNG?width=640")
+        );
+        assert_eq!(
+            post.link(),
+            "https://www.reddit.com/r/memes/comments/abc/a_meme/"
+        );
+    }
+}
@@ -0,0 +1,183 @@
+//! Reminder scheduler for todo items with a due date and upcoming events.
+//!
+//! Every [`CHECK_INTERVAL`], items that came due are taken from the todo store
+//! and announced: personal items by DM, unless the owner turned reminder DMs
+//! off, and shared items in their channel with a mention of whoever added them. Events starting within [`EVENT_LEAD`] are
+//! announced in their channel, mentioning everyone who answered going or maybe.
+//! Each item and event is reminded about once.
+
+use serenity::http::Http;
+use serenity::model::id::{ChannelId, MessageId, UserId};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::models::guild_events::{EventData, GuildEvent, Rsvp};
+use crate::models::notifications::DmCategory;
+use crate::models::todos::{TodoData, TodoItem, TodoOwner};
+use crate::storage::JsonStore;
+use crate::utils::dms::DmService;
+use crate::utils::duration::relative;
+use crate::utils::helpers::{truncate, unix_timestamp};
+
+/// How often due items are checked for.
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long before an event starts its attendees are reminded, in seconds.
+const EVENT_LEAD: u64 = 15 * 60;
+
+/// Most members mentioned in an event reminder, Discord's limit for allowed mentions.
+const MAX_EVENT_MENTIONS: usize = 100;
+
+/// Sends reminders for due todo items and upcoming events.
+pub struct Reminders {
+    todos: Arc<JsonStore<TodoData>>,
+    events: Arc<JsonStore<EventData>>,
+    dms: Arc<DmService>,
+    http: Arc<Http>,
+}
+
+impl Reminders {
+    /// Create the scheduler for the todo and event stores. Personal reminders
+    /// are sent through the DM service.
+    pub fn new(
+        todos: Arc<JsonStore<TodoData>>,
+        events: Arc<JsonStore<EventData>>,
+        dms: Arc<DmService>,
+        http: Arc<Http>,
+    ) -> Self {
+        Self {
+            todos,
+            events,
+            dms,
+            http,
+        }
+    }
+
+    /// Check for due items until the bot stops.
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(CHECK_INTERVAL);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                let now = unix_timestamp();
+                self.check_todos(now).await;
+                self.check_events(now).await;
+            }
+        })
+    }
+
+    /// Remind about todo items that came due.
+    async fn check_todos(&self, now: u64) {
+        // Only write to the store when something came due
+        if !self.todos.read().await.has_due(now) {
+            return;
+        }
+        let due = match self.todos.update(|data| data.take_due(now)).await {
+            Ok(due) => due,
+            Err(e) => {
+                warn!("Failed to check for due todo items: {}", e);
+                return;
+            }
+        };
+        for (owner, item) in due {
+            if let Err(e) = self.remind(owner, &item).await {
+                debug!("Failed to send a reminder for todo #{}: {}", item.id, e);
+            }
+        }
+    }
+
+    /// Remind the attendees of events that are about to start.
+    async fn check_events(&self, now: u64) {
+        if !self.events.read().await.has_due(now, EVENT_LEAD) {
+            return;
+        }
+        let due = match self
+            .events
+            .update(|data| data.take_due(now, EVENT_LEAD))
+            .await
+        {
+            Ok(due) => due,
+            Err(e) => {
+                warn!("Failed to check for upcoming events: {}", e);
+                return;
+            }
+        };
+        for event in due {
+            if let Err(e) = self.remind_event(&event).await {
+                debug!("Failed to send a reminder for event #{}: {}", event.id, e);
+            }
+        }
+    }
+
+    /// Ping the members who answered going or maybe to an event.
+    async fn remind_event(&self, event: &GuildEvent) -> serenity::Result<()> {
+        let attendees: Vec<UserId> = event
+            .attendees(Rsvp::Going)
+            .into_iter()
+            .chain(event.attendees(Rsvp::Maybe))
+            .take(MAX_EVENT_MENTIONS)
+            .map(UserId)
+            .collect();
+        if attendees.is_empty() {
+            return Ok(());
+        }
+        let mentions: Vec<String> = attendees.iter().map(|id| format!("<@{}>", id)).collect();
+        ChannelId(event.channel_id)
+            .send_message(&self.http, |m| {
+                if let Some(message_id) = event.message_id {
+                    m.reference_message((ChannelId(event.channel_id), MessageId(message_id)));
+                }
+                m.content(format!(
+                    "⏰ **{}** starts {}!\n{}",
+                    truncate(&event.title, 100),
+                    relative(event.start),
+                    mentions.join(" ")
+                ))
+                .allowed_mentions(|am| am.empty_parse().users(attendees.clone()))
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Send the reminder for an item.
+    async fn remind(&self, owner: TodoOwner, item: &TodoItem) -> serenity::Result<()> {
+        let text = truncate(&item.text, 1500);
+        match owner {
+            TodoOwner::User(user_id) => {
+                let outcome = self
+                    .dms
+                    .send(
+                        &self.http,
+                        UserId(user_id),
+                        Some(DmCategory::Reminders),
+                        |m| {
+                            m.content(format!("⏰ Todo #{} is due: {}", item.id, text))
+                                .allowed_mentions(|am| am.empty_parse())
+                        },
+                    )
+                    .await?;
+                if outcome.needs_fallback() {
+                    debug!(
+                        "Couldn't DM todo #{} to {}: {:?}",
+                        item.id, user_id, outcome
+                    );
+                }
+            }
+            TodoOwner::Channel(channel_id) => {
+                ChannelId(channel_id)
+                    .send_message(&self.http, |m| {
+                        m.content(format!(
+                            "⏰ <@{}>, todo #{} is due: {}",
+                            item.added_by, item.id, text
+                        ))
+                        .allowed_mentions(|am| am.empty_parse().users([UserId(item.added_by)]))
+                    })
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,405 @@
+//! Timeouts, retries and circuit breaking for Discord REST calls.
+//!
+//! Serenity already waits out rate limits, so this only deals with requests that time
+//! out or fail because Discord is having trouble. After too many of those in a row, the
+//! circuit opens and requests fail straight away for a while instead of piling up.
+//! Responses that count as invalid requests are reported to [`RestUsage`].
+//!
+//! Requests also have a [`Priority`]. While many requests are in flight or REST
+//! usage is high, moderation actions wait briefly, logs wait longer and are
+//! dropped if things don't clear up, and analytics posts are dropped straight
+//! away, so replies to commands aren't stuck behind them.
+
+use serenity::http::error::Error as HttpError;
+use serenity::prelude::*;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+use crate::models::config::RestConfig;
+use crate::utils::rest_usage::RestUsage;
+
+/// Error message for requests that took longer than the configured timeout.
+pub const TIMED_OUT: &str = "Discord request timed out";
+
+/// Error message for requests rejected because the circuit is open.
+pub const UNAVAILABLE: &str = "Discord is unavailable, not sending requests for now";
+
+/// Error message for low-priority requests dropped while Discord is congested.
+pub const DROPPED: &str = "Discord is busy, dropped a low-priority request";
+
+/// Requests in flight at which lower priorities start waiting.
+const CONGESTED_IN_FLIGHT: usize = 20;
+
+/// How often waiting requests check whether the congestion cleared.
+const CONGESTION_POLL: Duration = Duration::from_millis(250);
+
+/// How important a request is when Discord is congested.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// Responses to what a user just did, sent right away.
+    Reply,
+    /// Moderation actions, which wait up to 5 seconds and then go anyway.
+    Moderation,
+    /// Staff alerts and archives, which wait up to 30 seconds or are dropped.
+    Log,
+    /// Stats and announcements nobody is waiting for, dropped while congested.
+    Analytics,
+}
+
+impl Priority {
+    /// How long a request waits for congestion to clear, and whether it's sent
+    /// anyway afterwards.
+    fn patience(self) -> (Duration, bool) {
+        match self {
+            Self::Reply => (Duration::ZERO, true),
+            Self::Moderation => (Duration::from_secs(5), true),
+            Self::Log => (Duration::from_secs(30), false),
+            Self::Analytics => (Duration::ZERO, false),
+        }
+    }
+}
+
+/// Counts a request as in flight until dropped.
+struct InFlight<'a>(&'a AtomicUsize);
+
+impl<'a> InFlight<'a> {
+    fn start(count: &'a AtomicUsize) -> Self {
+        count.fetch_add(1, Ordering::Relaxed);
+        Self(count)
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Key for storing the REST policy in the client data.
+pub struct RestPolicyKey;
+
+impl TypeMapKey for RestPolicyKey {
+    type Value = Arc<RestPolicy>;
+}
+
+/// Failure tracking for the circuit breaker.
+#[derive(Default)]
+struct Breaker {
+    /// Transient failures since the last successful request.
+    failures: u32,
+    /// When requests may be sent again, if the circuit is open.
+    open_until: Option<Instant>,
+}
+
+/// Retry and circuit-breaking policy shared by all REST calls.
+#[derive(Default)]
+pub struct RestPolicy {
+    config: RestConfig,
+    breaker: Mutex<Breaker>,
+    usage: Arc<RestUsage>,
+    /// Requests currently being sent.
+    in_flight: AtomicUsize,
+}
+
+impl RestPolicy {
+    /// Create a policy from the configuration.
+    pub fn new(config: RestConfig) -> Self {
+        Self {
+            config,
+            breaker: Mutex::default(),
+            usage: Arc::default(),
+            in_flight: AtomicUsize::new(0),
+        }
+    }
+
+    /// The REST usage monitor that calls report invalid requests to.
+    pub fn usage(&self) -> Arc<RestUsage> {
+        self.usage.clone()
+    }
+
+    /// Whether requests are currently being rejected.
+    pub fn is_open(&self) -> bool {
+        let breaker = self.breaker.lock().unwrap_or_else(|e| e.into_inner());
+        breaker
+            .open_until
+            .is_some_and(|until| Instant::now() < until)
+    }
+
+    /// Record the outcome of a request.
+    ///
+    /// Only transient failures count towards opening the circuit; any other response
+    /// means Discord is reachable.
+    fn record(&self, transient_failure: bool) {
+        let mut breaker = self.breaker.lock().unwrap_or_else(|e| e.into_inner());
+        if !transient_failure {
+            *breaker = Breaker::default();
+            return;
+        }
+
+        breaker.failures += 1;
+        if breaker.failures >= self.config.failure_threshold.max(1) {
+            // After the circuit closes again, one more failure is enough to reopen it
+            warn!(
+                "{} Discord requests failed in a row, pausing requests for {}s",
+                breaker.failures, self.config.open_duration
+            );
+            breaker.open_until =
+                Some(Instant::now() + Duration::from_secs(self.config.open_duration));
+        }
+    }
+
+    /// Whether so many requests are in flight, or REST usage is so high, that
+    /// lower priorities should hold back.
+    pub fn is_congested(&self) -> bool {
+        self.in_flight.load(Ordering::Relaxed) >= CONGESTED_IN_FLIGHT || self.usage.is_paused()
+    }
+
+    /// Wait for congestion to clear as long as the priority allows. Returns
+    /// whether the request should be sent.
+    async fn admit(&self, priority: Priority) -> bool {
+        let (patience, send_anyway) = priority.patience();
+        let deadline = Instant::now() + patience;
+        while self.is_congested() {
+            if Instant::now() >= deadline {
+                return send_anyway;
+            }
+            tokio::time::sleep(CONGESTION_POLL).await;
+        }
+        true
+    }
+
+    /// Run a request with the timeout, retrying transient failures.
+    ///
+    /// `request` is called once per attempt, so it must build a fresh request each time.
+    pub async fn call<T, F, Fut>(&self, operation: &str, request: F) -> Result<T, SerenityError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, SerenityError>>,
+    {
+        self.call_with(Priority::Reply, operation, request).await
+    }
+
+    /// Run a request like [`Self::call`], holding it back or dropping it while
+    /// Discord is congested as its priority says.
+    pub async fn call_with<T, F, Fut>(
+        &self,
+        priority: Priority,
+        operation: &str,
+        mut request: F,
+    ) -> Result<T, SerenityError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, SerenityError>>,
+    {
+        if !self.admit(priority).await {
+            debug!(
+                "Dropped {} ({:?} priority) while congested",
+                operation, priority
+            );
+            return Err(SerenityError::Other(DROPPED));
+        }
+
+        let timeout = Duration::from_secs(self.config.timeout);
+        let mut delay = Duration::from_millis(self.config.retry_delay);
+        let mut attempt = 0;
+
+        loop {
+            if self.is_open() {
+                return Err(SerenityError::Other(UNAVAILABLE));
+            }
+
+            let result = {
+                let _in_flight = InFlight::start(&self.in_flight);
+                match tokio::time::timeout(timeout, request()).await {
+                    Ok(result) => result,
+                    Err(_) => Err(SerenityError::Other(TIMED_OUT)),
+                }
+            };
+
+            let e = match result {
+                Ok(value) => {
+                    self.record(false);
+                    return Ok(value);
+                }
+                Err(e) => e,
+            };
+
+            if is_invalid_request(&e) {
+                self.usage.record_invalid();
+            }
+            let transient = is_transient(&e);
+            self.record(transient);
+            if !transient || attempt >= self.config.retries || self.is_open() {
+                return Err(e);
+            }
+
+            attempt += 1;
+            warn!(
+                "{} failed ({}), retrying in {}ms (attempt {}/{})",
+                operation,
+                e,
+                delay.as_millis(),
+                attempt,
+                self.config.retries
+            );
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+}
+
+/// Whether an error is likely to go away if the request is retried.
+pub fn is_transient(e: &SerenityError) -> bool {
+    match e {
+        SerenityError::Other(message) => *message == TIMED_OUT,
+        SerenityError::Http(e) => match e.as_ref() {
+            HttpError::UnsuccessfulRequest(response) => response.status_code.is_server_error(),
+            HttpError::Request(e) => e.is_timeout() || e.is_connect(),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Whether Discord counts an error towards the invalid request limit.
+pub fn is_invalid_request(e: &SerenityError) -> bool {
+    match e {
+        SerenityError::Http(e) => match e.as_ref() {
+            HttpError::UnsuccessfulRequest(response) => {
+                matches!(response.status_code.as_u16(), 401 | 403 | 429)
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Get the bot's REST policy, falling back to the defaults.
+pub async fn rest_policy(ctx: &Context) -> Arc<RestPolicy> {
+    let data = ctx.data.read().await;
+    data.get::<RestPolicyKey>().cloned().unwrap_or_default()
+}
+
+/// Run a request with the bot's REST policy.
+///
+/// See [`RestPolicy::call`].
+pub async fn call<T, F, Fut>(ctx: &Context, operation: &str, request: F) -> Result<T, SerenityError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, SerenityError>>,
+{
+    rest_policy(ctx).await.call(operation, request).await
+}
+
+/// Run a request with the bot's REST policy and a priority.
+///
+/// See [`RestPolicy::call_with`].
+pub async fn call_with<T, F, Fut>(
+    ctx: &Context,
+    priority: Priority,
+    operation: &str,
+    request: F,
+) -> Result<T, SerenityError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, SerenityError>>,
+{
+    rest_policy(ctx)
+        .await
+        .call_with(priority, operation, request)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn policy(retries: u32, failure_threshold: u32) -> RestPolicy {
+        RestPolicy::new(RestConfig {
+            timeout: 5,
+            retries,
+            retry_delay: 0,
+            failure_threshold,
+            open_duration: 60,
+        })
+    }
+
+    #[tokio::test]
+    async fn retries_transient_failures() {
+        let policy = policy(2, 10);
+        let attempts = AtomicU32::new(0);
+
+        let result = policy
+            .call("test", || async {
+                if attempts.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Err(SerenityError::Other(TIMED_OUT))
+                } else {
+                    Ok(42)
+                }
+            })
+            .await;
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        assert!(!policy.is_open());
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_other_errors() {
+        let policy = policy(2, 10);
+        let attempts = AtomicU32::new(0);
+
+        let result: Result<(), _> = policy
+            .call("test", || async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(SerenityError::Other("bad request"))
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn opens_after_repeated_failures() {
+        let policy = policy(1, 3);
+        let attempts = AtomicU32::new(0);
+        let failing = || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(SerenityError::Other(TIMED_OUT))
+        };
+
+        assert!(policy.call("test", failing).await.is_err());
+        assert!(!policy.is_open());
+        // The third failure opens the circuit, so the retry is never sent
+        assert!(policy.call("test", failing).await.is_err());
+        assert!(policy.is_open());
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        let result = policy.call("test", || async { Ok(()) }).await;
+        assert!(matches!(result, Err(SerenityError::Other(UNAVAILABLE))));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn drops_analytics_while_congested() {
+        let policy = policy(0, 10);
+        let _busy: Vec<_> = (0..CONGESTED_IN_FLIGHT)
+            .map(|_| InFlight::start(&policy.in_flight))
+            .collect();
+        assert!(policy.is_congested());
+
+        let dropped = policy
+            .call_with(Priority::Analytics, "test", || async { Ok(()) })
+            .await;
+        assert!(matches!(dropped, Err(SerenityError::Other(DROPPED))));
+        let reply = policy
+            .call_with(Priority::Reply, "test", || async { Ok(1) })
+            .await;
+        assert_eq!(reply.unwrap(), 1);
+    }
+}
@@ -0,0 +1,205 @@
+//! Backing off when the bot uses too much of Discord's REST API.
+//!
+//! Serenity reports every rate limit it waits out, and [`super::rest`] reports
+//! responses that count as invalid requests, which Cloudflare bans bots for when
+//! there are too many of them. When either gets close to trouble, background
+//! tasks that can wait (feeds, stats posting, LFG cleanup) pause until things
+//! have been quiet for a while, then resume with some jitter so they don't all
+//! fire at once.
+
+use rand::Rng;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// Window for counting rate limits.
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Rate limits within the window that pause background tasks. Global rate
+/// limits always do.
+const RATE_LIMIT_THRESHOLD: usize = 20;
+
+/// Window Cloudflare counts invalid requests in.
+const INVALID_WINDOW: Duration = Duration::from_secs(600);
+
+/// Invalid requests within the window that pause background tasks, half of the
+/// 10,000 that get the bot banned.
+const INVALID_THRESHOLD: usize = 5_000;
+
+/// How long things have to stay quiet before background tasks resume.
+const QUIET_PERIOD: Duration = Duration::from_secs(30);
+
+/// How often paused tasks check whether they can resume.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Longest random delay before a paused task resumes.
+const MAX_JITTER: Duration = Duration::from_secs(10);
+
+/// A stretch of time with background tasks paused.
+struct Episode {
+    started: Instant,
+    rate_limits: usize,
+    invalid: usize,
+}
+
+/// Recent rate limits and invalid requests.
+#[derive(Default)]
+struct Usage {
+    rate_limits: VecDeque<Instant>,
+    invalid: VecDeque<Instant>,
+    /// When usage was last too high.
+    last_pressure: Option<Instant>,
+    episode: Option<Episode>,
+}
+
+impl Usage {
+    /// Forget events that left their window.
+    fn expire(&mut self, now: Instant) {
+        for (events, window) in [
+            (&mut self.rate_limits, RATE_LIMIT_WINDOW),
+            (&mut self.invalid, INVALID_WINDOW),
+        ] {
+            while events
+                .front()
+                .is_some_and(|at| now.duration_since(*at) >= window)
+            {
+                events.pop_front();
+            }
+        }
+    }
+
+    /// Note that usage is too high, starting an episode if there isn't one.
+    fn pressure(&mut self, now: Instant, reason: &str) {
+        self.last_pressure = Some(now);
+        if self.episode.is_none() {
+            warn!("{}, pausing background tasks", reason);
+            self.episode = Some(Episode {
+                started: now,
+                rate_limits: 0,
+                invalid: 0,
+            });
+        }
+    }
+}
+
+/// Tracks REST usage and pauses background tasks while it's too high.
+#[derive(Default)]
+pub struct RestUsage {
+    usage: Mutex<Usage>,
+}
+
+impl RestUsage {
+    /// Create a monitor with nothing recorded.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a rate limit Serenity waited out.
+    pub fn record_rate_limit(&self, global: bool) {
+        self.record_rate_limit_at(global, Instant::now());
+    }
+
+    fn record_rate_limit_at(&self, global: bool, now: Instant) {
+        let mut usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        usage.expire(now);
+        usage.rate_limits.push_back(now);
+        if let Some(episode) = &mut usage.episode {
+            episode.rate_limits += 1;
+        }
+
+        let count = usage.rate_limits.len();
+        if global {
+            usage.pressure(now, "Hit Discord's global rate limit");
+        } else if count >= RATE_LIMIT_THRESHOLD {
+            let reason = format!("Hit {} rate limits in the last minute", count);
+            usage.pressure(now, &reason);
+        }
+    }
+
+    /// Record a response that counts as an invalid request (401, 403 or 429).
+    pub fn record_invalid(&self) {
+        self.record_invalid_at(Instant::now());
+    }
+
+    fn record_invalid_at(&self, now: Instant) {
+        let mut usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        usage.expire(now);
+        usage.invalid.push_back(now);
+        if let Some(episode) = &mut usage.episode {
+            episode.invalid += 1;
+        }
+
+        let count = usage.invalid.len();
+        if count >= INVALID_THRESHOLD {
+            let reason = format!("{} invalid requests in the last 10 minutes", count);
+            usage.pressure(now, &reason);
+        }
+    }
+
+    /// Whether background tasks should wait, ending the episode once usage has
+    /// been quiet for long enough.
+    pub fn is_paused(&self) -> bool {
+        self.is_paused_at(Instant::now())
+    }
+
+    fn is_paused_at(&self, now: Instant) -> bool {
+        let mut usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        let quiet = usage
+            .last_pressure
+            .is_none_or(|at| now.duration_since(at) >= QUIET_PERIOD);
+        if !quiet {
+            return true;
+        }
+        if let Some(episode) = usage.episode.take() {
+            info!(
+                "REST usage back to normal after {}s ({} rate limits and {} invalid requests meanwhile), resuming background tasks",
+                now.duration_since(episode.started).as_secs(),
+                episode.rate_limits,
+                episode.invalid
+            );
+        }
+        false
+    }
+
+    /// Wait until background tasks may use the REST API, plus some jitter if
+    /// they had to wait at all.
+    pub async fn wait_for_headroom(&self, task: &str) {
+        if !self.is_paused() {
+            return;
+        }
+        debug!("Pausing {} until REST usage drops", task);
+        while self.is_paused() {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+        let jitter = rand::thread_rng().gen_range(Duration::ZERO..=MAX_JITTER);
+        tokio::time::sleep(jitter).await;
+        debug!("Resuming {}", task);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pauses_until_quiet() {
+        let usage = RestUsage::new();
+        let start = Instant::now();
+        assert!(!usage.is_paused_at(start));
+
+        for _ in 0..RATE_LIMIT_THRESHOLD - 1 {
+            usage.record_rate_limit_at(false, start);
+        }
+        assert!(!usage.is_paused_at(start));
+        usage.record_rate_limit_at(false, start);
+        assert!(usage.is_paused_at(start));
+        assert!(usage.is_paused_at(start + QUIET_PERIOD / 2));
+        assert!(!usage.is_paused_at(start + QUIET_PERIOD));
+
+        let later = start + RATE_LIMIT_WINDOW * 2;
+        usage.record_rate_limit_at(true, later);
+        assert!(usage.is_paused_at(later));
+        assert!(!usage.is_paused_at(later + QUIET_PERIOD));
+    }
+}
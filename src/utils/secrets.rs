@@ -0,0 +1,21 @@
+//! API keys and other secrets, kept out of `config.toml`.
+//!
+//! A secret named `topgg_token` is read from the `TOPGG_TOKEN` environment variable,
+//! or else from the file `secrets/topgg_token`. Surrounding whitespace is trimmed and
+//! an empty value counts as unset.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Directory secrets files are read from.
+const SECRETS_DIR: &str = "secrets";
+
+/// Get a secret by name, or `None` if it isn't set.
+pub fn get(name: &str) -> Option<String> {
+    env::var(name.to_uppercase())
+        .ok()
+        .or_else(|| fs::read_to_string(Path::new(SECRETS_DIR).join(name)).ok())
+        .map(|secret| secret.trim().to_string())
+        .filter(|secret| !secret.is_empty())
+}
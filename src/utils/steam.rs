@@ -0,0 +1,585 @@
+//! Lookups of games on the Steam store and profiles on Steam Community.
+//!
+//! Store lookups use Steam's public store API and need no key. Profiles need a
+//! Steam Web API key in the `steam_api_key` secret; without one, `steam` says it
+//! isn't set up.
+//!
+//! When a title matches several games, commands offer them in a select menu with
+//! the component ID `steam:<kind>:<user>`, where `kind` is `game` or `price` and
+//! only `user` may pick. Each option's value is the game's app ID.
+
+use serde::Deserialize;
+use serenity::builder::{CreateComponents, CreateEmbed};
+use serenity::model::id::UserId;
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::framework::command_handler::CommandResult;
+use crate::utils::cache::TtlCache;
+use crate::utils::constants::{DEFAULT_COLOR, SUCCESS_COLOR};
+use crate::utils::duration::relative;
+use crate::utils::helpers::{format_count, truncate};
+use crate::utils::secrets;
+
+const STORE_API: &str = "https://store.steampowered.com/api";
+const WEB_API: &str = "https://api.steampowered.com";
+
+/// The store region prices are shown for.
+const COUNTRY: &str = "us";
+
+/// How long store pages and searches are cached.
+const STORE_TTL: Duration = Duration::from_secs(30 * 60);
+
+/// How long profiles are cached, since they show whether someone is online.
+const PROFILE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// How many store pages, searches and profiles are cached at once, each.
+const CACHE_CAPACITY: usize = 200;
+
+/// How long to wait for Steam.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How many search results are offered when a title matches several games.
+const MAX_CHOICES: usize = 10;
+
+/// The first SteamID64, for telling IDs from custom profile names.
+const STEAM_ID_BASE: u64 = 76_561_197_960_265_728;
+
+/// Key for storing the Steam client in the client data.
+pub struct SteamKey;
+
+impl TypeMapKey for SteamKey {
+    type Value = Arc<Steam>;
+}
+
+/// A game in store search results.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SearchResult {
+    pub id: u64,
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+struct SearchResponse {
+    #[serde(default)]
+    items: Vec<SearchResult>,
+}
+
+/// A game's store page.
+#[derive(Clone, Debug, Deserialize)]
+pub struct App {
+    pub steam_appid: u64,
+    pub name: String,
+    #[serde(default)]
+    pub short_description: String,
+    #[serde(default)]
+    pub header_image: Option<String>,
+    #[serde(default)]
+    pub is_free: bool,
+    #[serde(default)]
+    pub price_overview: Option<Price>,
+    #[serde(default)]
+    pub developers: Vec<String>,
+    #[serde(default)]
+    pub publishers: Vec<String>,
+    #[serde(default)]
+    pub release_date: Option<ReleaseDate>,
+    #[serde(default)]
+    pub genres: Vec<Genre>,
+    #[serde(default)]
+    pub metacritic: Option<Metacritic>,
+    #[serde(default)]
+    pub platforms: Option<Platforms>,
+    #[serde(default)]
+    pub recommendations: Option<Recommendations>,
+}
+
+impl App {
+    /// The game's store page.
+    pub fn store_url(&self) -> String {
+        format!("https://store.steampowered.com/app/{}", self.steam_appid)
+    }
+
+    /// The price as shown on the store, like `$19.99 (-50% from $39.99)`.
+    pub fn describe_price(&self) -> String {
+        match &self.price_overview {
+            Some(price) if price.discount_percent > 0 => format!(
+                "{} (-{}% from {})",
+                price.final_formatted, price.discount_percent, price.initial_formatted
+            ),
+            Some(price) => price.final_formatted.clone(),
+            None if self.is_free => "Free to play".to_string(),
+            None => "Not for sale".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Price {
+    #[serde(default)]
+    pub discount_percent: u32,
+    #[serde(default)]
+    pub initial_formatted: String,
+    pub final_formatted: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ReleaseDate {
+    #[serde(default)]
+    pub coming_soon: bool,
+    #[serde(default)]
+    pub date: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Genre {
+    pub description: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Metacritic {
+    pub score: u32,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Platforms {
+    #[serde(default)]
+    pub windows: bool,
+    #[serde(default)]
+    pub mac: bool,
+    #[serde(default)]
+    pub linux: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Recommendations {
+    pub total: u64,
+}
+
+#[derive(Deserialize)]
+struct AppResponse {
+    success: bool,
+    #[serde(default)]
+    data: Option<App>,
+}
+
+/// A Steam Community profile.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Player {
+    pub steamid: String,
+    pub personaname: String,
+    pub profileurl: String,
+    #[serde(default)]
+    pub avatarfull: Option<String>,
+    #[serde(default)]
+    pub personastate: u32,
+    /// 3 if the profile is public.
+    #[serde(default)]
+    pub communityvisibilitystate: u32,
+    #[serde(default)]
+    pub timecreated: Option<u64>,
+    #[serde(default)]
+    pub loccountrycode: Option<String>,
+    /// The game being played right now.
+    #[serde(default)]
+    pub gameextrainfo: Option<String>,
+    /// Filled in separately, and only for public profiles.
+    #[serde(skip)]
+    pub level: Option<u32>,
+    #[serde(skip)]
+    pub game_count: Option<u64>,
+}
+
+impl Player {
+    /// The player's status, like `Online` or `Playing Portal 2`.
+    pub fn status(&self) -> String {
+        if let Some(game) = &self.gameextrainfo {
+            return format!("Playing {}", game);
+        }
+        match self.personastate {
+            0 => "Offline",
+            1 => "Online",
+            2 => "Busy",
+            3 => "Away",
+            4 => "Snooze",
+            5 => "Looking to trade",
+            6 => "Looking to play",
+            _ => "Unknown",
+        }
+        .to_string()
+    }
+}
+
+#[derive(Deserialize)]
+struct WebResponse<T> {
+    response: T,
+}
+
+#[derive(Deserialize)]
+struct VanityResponse {
+    success: u32,
+    #[serde(default)]
+    steamid: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PlayersResponse {
+    #[serde(default)]
+    players: Vec<Player>,
+}
+
+#[derive(Deserialize)]
+struct LevelResponse {
+    #[serde(default)]
+    player_level: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct OwnedGamesResponse {
+    #[serde(default)]
+    game_count: Option<u64>,
+}
+
+/// How a profile was given: as a SteamID64 or a custom URL name.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ProfileRef {
+    Id(u64),
+    Vanity(String),
+}
+
+/// Parse a profile given as a link, a SteamID64 or a custom URL name.
+pub fn parse_profile(input: &str) -> Option<ProfileRef> {
+    let input = input.trim().trim_matches(|c| c == '<' || c == '>');
+    let input = input
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_start_matches("steamcommunity.com");
+    let input = input.trim_matches('/');
+
+    let (kind, value) = match input.split_once('/') {
+        Some((kind, value)) => (Some(kind), value.trim_end_matches('/')),
+        None => (None, input),
+    };
+    if value.is_empty() || value.contains('/') {
+        return None;
+    }
+    match (kind, value.parse::<u64>()) {
+        (Some("profiles") | None, Ok(id)) if id > STEAM_ID_BASE => Some(ProfileRef::Id(id)),
+        (Some("profiles"), _) => None,
+        (Some("id") | None, _) => value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+            .then(|| ProfileRef::Vanity(value.to_lowercase())),
+        _ => None,
+    }
+}
+
+/// Looks up games and profiles on Steam, caching the results.
+pub struct Steam {
+    client: reqwest::Client,
+    api_key: Option<String>,
+    apps: TtlCache<Option<App>>,
+    searches: TtlCache<Vec<SearchResult>>,
+    players: TtlCache<Option<Player>>,
+}
+
+impl Default for Steam {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Steam {
+    /// Create a client, reading the `steam_api_key` secret if it's set.
+    pub fn new() -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            client,
+            api_key: secrets::get("steam_api_key"),
+            apps: TtlCache::new(STORE_TTL, CACHE_CAPACITY),
+            searches: TtlCache::new(STORE_TTL, CACHE_CAPACITY),
+            players: TtlCache::new(PROFILE_TTL, CACHE_CAPACITY),
+        }
+    }
+
+    /// Whether profiles can be looked up.
+    pub fn has_api_key(&self) -> bool {
+        self.api_key.is_some()
+    }
+
+    /// Search the store for games by title, best matches first.
+    pub async fn search(&self, title: &str) -> CommandResult<Vec<SearchResult>> {
+        let key = title.to_lowercase();
+        if let Some(cached) = self.searches.get(&key).await {
+            return Ok(cached);
+        }
+
+        let response: SearchResponse = self
+            .client
+            .get(format!("{}/storesearch/", STORE_API))
+            .query(&[("term", title), ("l", "english"), ("cc", COUNTRY)])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let mut results = response.items;
+        results.truncate(MAX_CHOICES);
+        self.searches.insert(key, results.clone()).await;
+        Ok(results)
+    }
+
+    /// Get a game's store page by app ID.
+    pub async fn app(&self, id: u64) -> CommandResult<Option<App>> {
+        let key = id.to_string();
+        if let Some(cached) = self.apps.get(&key).await {
+            return Ok(cached);
+        }
+
+        let mut response: HashMap<String, AppResponse> = self
+            .client
+            .get(format!("{}/appdetails", STORE_API))
+            .query(&[("appids", key.as_str()), ("l", "english"), ("cc", COUNTRY)])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let app = response
+            .remove(&key)
+            .filter(|app| app.success)
+            .and_then(|app| app.data);
+        self.apps.insert(key, app.clone()).await;
+        Ok(app)
+    }
+
+    /// Call a Steam Web API method, or `None` if no key is set.
+    async fn web<T: serde::de::DeserializeOwned>(
+        &self,
+        method: &str,
+        query: &[(&str, &str)],
+    ) -> CommandResult<Option<T>> {
+        let key = match &self.api_key {
+            Some(key) => key,
+            None => return Ok(None),
+        };
+        let response: WebResponse<T> = self
+            .client
+            .get(format!("{}/{}", WEB_API, method))
+            .query(&[("key", key.as_str())])
+            .query(query)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(Some(response.response))
+    }
+
+    /// Look up a profile, or `None` if it doesn't exist or no key is set.
+    pub async fn player(&self, profile: &ProfileRef) -> CommandResult<Option<Player>> {
+        let id = match profile {
+            ProfileRef::Id(id) => *id,
+            ProfileRef::Vanity(name) => {
+                let resolved: Option<VanityResponse> = self
+                    .web("ISteamUser/ResolveVanityURL/v1/", &[("vanityurl", name)])
+                    .await?;
+                match resolved
+                    .filter(|resolved| resolved.success == 1)
+                    .and_then(|resolved| resolved.steamid)
+                    .and_then(|id| id.parse().ok())
+                {
+                    Some(id) => id,
+                    None => return Ok(None),
+                }
+            }
+        };
+
+        let key = id.to_string();
+        if let Some(cached) = self.players.get(&key).await {
+            return Ok(cached);
+        }
+        let players: Option<PlayersResponse> = self
+            .web("ISteamUser/GetPlayerSummaries/v2/", &[("steamids", &key)])
+            .await?;
+        let mut player = players.and_then(|players| players.players.into_iter().next());
+        if let Some(player) = player
+            .as_mut()
+            .filter(|player| player.communityvisibilitystate == 3)
+        {
+            let level: Option<LevelResponse> = self
+                .web("IPlayerService/GetSteamLevel/v1/", &[("steamid", &key)])
+                .await?;
+            player.level = level.and_then(|level| level.player_level);
+            let games: Option<OwnedGamesResponse> = self
+                .web("IPlayerService/GetOwnedGames/v1/", &[("steamid", &key)])
+                .await?;
+            player.game_count = games.and_then(|games| games.game_count);
+        }
+        self.players.insert(key, player.clone()).await;
+        Ok(player)
+    }
+}
+
+/// Build the embed for a game.
+pub fn game_embed(app: &App) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(&app.name)
+        .url(app.store_url())
+        .color(DEFAULT_COLOR)
+        .description(truncate(&app.short_description, 1024))
+        .field("Price", app.describe_price(), true);
+    if let Some(image) = &app.header_image {
+        embed.image(image);
+    }
+    if let Some(release) = &app.release_date {
+        let date = if release.coming_soon {
+            format!("Coming {}", release.date)
+        } else {
+            release.date.clone()
+        };
+        if !date.is_empty() {
+            embed.field("Released", date, true);
+        }
+    }
+    if let Some(metacritic) = &app.metacritic {
+        embed.field("Metacritic", metacritic.score, true);
+    }
+    if !app.developers.is_empty() {
+        embed.field("Developer", truncate(&app.developers.join(", "), 256), true);
+    }
+    if !app.publishers.is_empty() && app.publishers != app.developers {
+        embed.field("Publisher", truncate(&app.publishers.join(", "), 256), true);
+    }
+    if !app.genres.is_empty() {
+        let genres: Vec<&str> = app.genres.iter().map(|g| g.description.as_str()).collect();
+        embed.field("Genres", truncate(&genres.join(", "), 256), true);
+    }
+    if let Some(platforms) = &app.platforms {
+        let names: Vec<&str> = [
+            (platforms.windows, "Windows"),
+            (platforms.mac, "macOS"),
+            (platforms.linux, "Linux"),
+        ]
+        .into_iter()
+        .filter_map(|(supported, name)| supported.then_some(name))
+        .collect();
+        if !names.is_empty() {
+            embed.field("Platforms", names.join(", "), true);
+        }
+    }
+    if let Some(recommendations) = &app.recommendations {
+        embed.footer(|f| f.text(format!("{} reviews", format_count(recommendations.total))));
+    }
+    embed
+}
+
+/// Build the embed for a game's price.
+pub fn price_embed(app: &App) -> CreateEmbed {
+    let on_sale = app
+        .price_overview
+        .as_ref()
+        .is_some_and(|price| price.discount_percent > 0);
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(format!("💸 {}", app.name))
+        .url(app.store_url())
+        .color(if on_sale {
+            SUCCESS_COLOR
+        } else {
+            DEFAULT_COLOR
+        })
+        .description(format!("**{}**", app.describe_price()))
+        .footer(|f| f.text(format!("Steam store, {} region", COUNTRY.to_uppercase())));
+    if let Some(image) = &app.header_image {
+        embed.thumbnail(image);
+    }
+    embed
+}
+
+/// Build the embed for a profile.
+pub fn profile_embed(player: &Player) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(&player.personaname)
+        .url(&player.profileurl)
+        .color(DEFAULT_COLOR)
+        .field("Status", player.status(), true);
+    if let Some(avatar) = &player.avatarfull {
+        embed.thumbnail(avatar);
+    }
+    if player.communityvisibilitystate != 3 {
+        embed.description("This profile is private.");
+    }
+    if let Some(level) = player.level {
+        embed.field("Level", level, true);
+    }
+    if let Some(games) = player.game_count {
+        embed.field("Games", format_count(games), true);
+    }
+    if let Some(country) = &player.loccountrycode {
+        embed.field("Country", country, true);
+    }
+    if let Some(created) = player.timecreated {
+        embed.field("Joined", relative(created), true);
+    }
+    embed.footer(|f| f.text(format!("SteamID64 {}", player.steamid)));
+    embed
+}
+
+/// Build a select menu offering games from a search.
+pub fn choices_menu(
+    components: &mut CreateComponents,
+    kind: &str,
+    user_id: UserId,
+    choices: &[SearchResult],
+) {
+    components.create_action_row(|row| {
+        row.create_select_menu(|menu| {
+            menu.custom_id(format!("steam:{}:{}", kind, user_id))
+                .placeholder("Which game?")
+                .options(|options| {
+                    for choice in choices.iter().take(MAX_CHOICES) {
+                        options.create_option(|o| {
+                            o.label(truncate(&choice.name, 100)).value(choice.id)
+                        });
+                    }
+                    options
+                })
+        })
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_profiles() {
+        assert_eq!(
+            parse_profile("https://steamcommunity.com/id/Gabe/"),
+            Some(ProfileRef::Vanity("gabe".to_string()))
+        );
+        assert_eq!(
+            parse_profile("steamcommunity.com/profiles/76561197960287930"),
+            Some(ProfileRef::Id(76561197960287930))
+        );
+        assert_eq!(
+            parse_profile("76561197960287930"),
+            Some(ProfileRef::Id(76561197960287930))
+        );
+        assert_eq!(
+            parse_profile("1234"),
+            Some(ProfileRef::Vanity("1234".to_string()))
+        );
+        assert_eq!(parse_profile("steamcommunity.com/profiles/abc"), None);
+        assert_eq!(parse_profile("bad name!"), None);
+    }
+}
@@ -0,0 +1,305 @@
+//! Finding times like `8pm EST` in text and turning them into Unix timestamps.
+//!
+//! Only fixed abbreviations and `UTC+5:30` style offsets are understood, so a
+//! time is converted exactly as written, without guessing daylight saving.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+use regex::Regex;
+use std::sync::OnceLock;
+
+use crate::utils::duration;
+
+/// Most times converted from one message.
+pub const MAX_TIMES: usize = 5;
+
+/// Time zone abbreviations and their offsets from UTC, in minutes.
+const ZONES: &[(&str, i32)] = &[
+    ("utc", 0),
+    ("gmt", 0),
+    ("z", 0),
+    ("wet", 0),
+    ("bst", 60),
+    ("ist", 330),
+    ("cet", 60),
+    ("cest", 120),
+    ("eet", 120),
+    ("eest", 180),
+    ("msk", 180),
+    ("gst", 240),
+    ("pkt", 300),
+    ("npt", 345),
+    ("wib", 420),
+    ("ict", 420),
+    ("sgt", 480),
+    ("hkt", 480),
+    ("pht", 480),
+    ("awst", 480),
+    ("jst", 540),
+    ("kst", 540),
+    ("acst", 570),
+    ("aest", 600),
+    ("aedt", 660),
+    ("nzst", 720),
+    ("nzdt", 780),
+    ("brt", -180),
+    ("art", -180),
+    ("ast", -240),
+    ("adt", -180),
+    ("est", -300),
+    ("edt", -240),
+    ("cst", -360),
+    ("cdt", -300),
+    ("mst", -420),
+    ("mdt", -360),
+    ("pst", -480),
+    ("pdt", -420),
+    ("akst", -540),
+    ("akdt", -480),
+    ("hst", -600),
+];
+
+/// A time found in a message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FoundTime {
+    /// The time as it was written.
+    pub text: String,
+    /// Seconds since the Unix epoch.
+    pub unix: u64,
+}
+
+/// Times with a zone, such as `8pm EST`, `20:00 utc` or `9:30am UTC+5:30`.
+fn time_regex() -> &'static Regex {
+    static TIME: OnceLock<Regex> = OnceLock::new();
+    TIME.get_or_init(|| {
+        Regex::new(
+            r"(?i)\b(\d{1,2})(?::([0-5]\d))?\s?(am|pm)?\s?\b((?:utc|gmt)\s?[+-]\s?\d{1,2}(?::?[0-5]\d)?|[a-z]{1,4})\b",
+        )
+        .expect("time regex is valid")
+    })
+}
+
+/// Offset from UTC of a zone abbreviation or offset like `UTC+5:30`, in seconds.
+pub fn zone_offset(zone: &str) -> Option<i64> {
+    let zone: String = zone
+        .to_lowercase()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    if let Some((_, minutes)) = ZONES.iter().find(|(name, _)| *name == zone) {
+        return Some(*minutes as i64 * 60);
+    }
+
+    let offset = zone
+        .strip_prefix("utc")
+        .or_else(|| zone.strip_prefix("gmt"))?;
+    let (sign, offset) = match offset.split_at_checked(1)? {
+        ("+", rest) => (1, rest),
+        ("-", rest) => (-1, rest),
+        _ => return None,
+    };
+    let (hours, minutes) = match offset.split_once(':') {
+        Some((hours, minutes)) => (hours, minutes),
+        None if offset.len() > 2 => offset.split_at(offset.len() - 2),
+        None => (offset, "0"),
+    };
+    let hours: i64 = hours.parse().ok()?;
+    let minutes: i64 = minutes.parse().ok()?;
+    if hours > 14 || minutes >= 60 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Turn an hour and minute in a zone into a timestamp. Chat usually talks
+/// about upcoming times, so this is the next occurrence unless the time passed
+/// within the last few hours.
+fn nearest(hour: u32, minute: u32, offset: i64, now: u64) -> Option<u64> {
+    let local_now = DateTime::from_timestamp(now as i64 + offset, 0)?.naive_utc();
+    let time = NaiveTime::from_hms_opt(hour, minute, 0)?;
+    let mut unix = local_now.date().and_time(time).and_utc().timestamp() - offset;
+    let now = now as i64;
+    if unix < now - 6 * 3600 {
+        unix += 24 * 3600;
+    } else if unix >= now + 18 * 3600 {
+        unix -= 24 * 3600;
+    }
+    u64::try_from(unix).ok()
+}
+
+/// Convert a matched hour, minute and am/pm to a 24-hour time.
+fn to_24_hour(hour: u32, minute: Option<u32>, meridiem: Option<&str>) -> Option<(u32, u32)> {
+    let minute = minute.unwrap_or(0);
+    let hour = match meridiem.map(|m| m.to_lowercase()) {
+        Some(m) if (1..=12).contains(&hour) => {
+            let hour = hour % 12;
+            if m == "pm" {
+                hour + 12
+            } else {
+                hour
+            }
+        }
+        Some(_) => return None,
+        None if hour < 24 => hour,
+        None => return None,
+    };
+    Some((hour, minute))
+}
+
+/// Find times with a time zone in text, such as `8pm EST` or `20:00 UTC`.
+///
+/// A bare number followed by a word isn't a time, so each needs either am/pm or
+/// minutes.
+pub fn find_times(text: &str, now: u64) -> Vec<FoundTime> {
+    let mut found: Vec<FoundTime> = Vec::new();
+    for captures in time_regex().captures_iter(text) {
+        let minute = captures.get(2).and_then(|m| m.as_str().parse().ok());
+        let meridiem = captures.get(3).map(|m| m.as_str());
+        if minute.is_none() && meridiem.is_none() {
+            continue;
+        }
+        let offset = match zone_offset(&captures[4]) {
+            Some(offset) => offset,
+            None => continue,
+        };
+        let unix = captures[1]
+            .parse()
+            .ok()
+            .and_then(|hour| to_24_hour(hour, minute, meridiem))
+            .and_then(|(hour, minute)| nearest(hour, minute, offset, now));
+        if let Some(unix) = unix {
+            if !found.iter().any(|time| time.unix == unix) {
+                found.push(FoundTime {
+                    text: captures[0].to_string(),
+                    unix,
+                });
+            }
+        }
+        if found.len() >= MAX_TIMES {
+            break;
+        }
+    }
+    found
+}
+
+/// Split a trailing zone off a lowercased time, returning the rest and the
+/// zone's offset in seconds. Times without a zone are in UTC.
+fn split_zone(input: &str) -> (&str, i64) {
+    if let Some(start) = input.find("utc").or_else(|| input.find("gmt")) {
+        if let Some(offset) = zone_offset(&input[start..]) {
+            return (input[..start].trim(), offset);
+        }
+    }
+    if let Some((rest, zone)) = input.rsplit_once(' ') {
+        if let Some(offset) = zone_offset(zone) {
+            return (rest.trim(), offset);
+        }
+    }
+    (input, 0)
+}
+
+/// Parse a point in time for the `timestamp` command: `now`, a Unix timestamp,
+/// a duration like `in 2h`, a date like `2026-10-20 18:00 EST`, or a time like
+/// `8pm` or `20:00 CET`. Dates and times without a zone are in UTC.
+pub fn parse_when(input: &str, now: u64) -> Result<u64, String> {
+    let input = input.trim();
+    let lowered = input.to_lowercase();
+    if lowered == "now" {
+        return Ok(now);
+    }
+    if lowered.len() >= 9 && lowered.chars().all(|c| c.is_ascii_digit()) {
+        return lowered
+            .parse()
+            .map_err(|_| "That timestamp is too big.".to_string());
+    }
+    if let Some(relative) = lowered.strip_prefix("in ") {
+        return duration::parse(relative)
+            .map(|delay| now + delay.as_secs())
+            .map_err(|e| e.to_string());
+    }
+    if let Some(ago) = lowered.strip_suffix(" ago") {
+        return duration::parse(ago)
+            .map(|delay| now.saturating_sub(delay.as_secs()))
+            .map_err(|e| e.to_string());
+    }
+    if let Ok(delay) = duration::parse(&lowered) {
+        return Ok(now + delay.as_secs());
+    }
+
+    let (rest, offset) = split_zone(&lowered);
+    for format in ["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"] {
+        if let Ok(date) = NaiveDateTime::parse_from_str(rest, format) {
+            return u64::try_from(date.and_utc().timestamp() - offset)
+                .map_err(|_| "Dates before 1970 aren't supported.".to_string());
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(rest, "%Y-%m-%d") {
+        let midnight = date.and_hms_opt(0, 0, 0).ok_or("Invalid date")?;
+        return u64::try_from(midnight.and_utc().timestamp() - offset)
+            .map_err(|_| "Dates before 1970 aren't supported.".to_string());
+    }
+
+    let compact: String = rest.chars().filter(|c| !c.is_whitespace()).collect();
+    let (clock, meridiem) = match compact.strip_suffix("am") {
+        Some(clock) => (clock, Some("am")),
+        None => match compact.strip_suffix("pm") {
+            Some(clock) => (clock, Some("pm")),
+            None => (compact.as_str(), None),
+        },
+    };
+    let (hour, minute) = match clock.split_once(':') {
+        Some((hour, minute)) => (hour, Some(minute)),
+        None => (clock, None),
+    };
+    let time = hour.parse().ok().and_then(|hour| {
+        let minute = match minute {
+            Some(minute) => Some(minute.parse().ok().filter(|m| *m < 60)?),
+            None => None,
+        };
+        to_24_hour(hour, minute, meridiem)
+    });
+    match time {
+        Some((hour, minute)) => nearest(hour, minute, offset, now)
+            .ok_or_else(|| "That time is out of range.".to_string()),
+        None => Err(format!(
+            "Couldn't read `{}` as a time. Try `8pm EST`, `2026-10-20 18:00 CET` or `in 2h`.",
+            input
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2026-10-17 12:00 UTC
+    const NOW: u64 = 1_792_238_400;
+
+    #[test]
+    fn finds_times_in_messages() {
+        let found = find_times("raid at 8pm EST, or 20:00 utc if that's easier", NOW);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].text, "8pm EST");
+        // 8pm EST is 01:00 UTC the next day
+        assert_eq!(found[0].unix, NOW + 13 * 3600);
+        assert_eq!(found[1].unix, NOW + 8 * 3600);
+
+        // 04:00 UTC passed too long ago, so it's tomorrow's
+        assert_eq!(find_times("9:30am UTC+5:30", NOW)[0].unix, NOW + 16 * 3600);
+        assert_eq!(find_times("10:00 UTC", NOW)[0].unix, NOW - 2 * 3600);
+        assert!(find_times("I have 5 cats", NOW).is_empty());
+        assert!(find_times("meet at 5 est", NOW).is_empty());
+        assert!(find_times("13pm est", NOW).is_empty());
+    }
+
+    #[test]
+    fn parses_points_in_time() {
+        assert_eq!(parse_when("now", NOW), Ok(NOW));
+        assert_eq!(parse_when("in 2h", NOW), Ok(NOW + 7200));
+        assert_eq!(parse_when("1h ago", NOW), Ok(NOW - 3600));
+        assert_eq!(parse_when("2026-10-17 15:00 CET", NOW), Ok(NOW + 2 * 3600));
+        assert_eq!(parse_when("2026-10-18", NOW), Ok(NOW + 12 * 3600));
+        assert_eq!(parse_when("6pm", NOW), Ok(NOW + 6 * 3600));
+        assert_eq!(parse_when("14:00 UTC+2", NOW), Ok(NOW));
+        assert!(parse_when("whenever", NOW).is_err());
+    }
+}
@@ -0,0 +1,515 @@
+//! Resolving Spotify and Apple Music links to tracks that can be searched for on
+//! the audio source.
+//!
+//! Spotify needs API credentials in the `spotify_client_id` and
+//! `spotify_client_secret` secrets; without them, Spotify links are refused.
+//! Apple Music links are looked up with the public iTunes API, which has no
+//! playlists.
+//!
+//! Albums and playlists are fetched a page at a time so callers can report
+//! progress and stop once the queue is full. Fully resolved links are cached.
+
+use reqwest::{StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use serenity::prelude::*;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use crate::framework::command_handler::CommandResult;
+use crate::utils::cache::TtlCache;
+use crate::utils::secrets;
+
+const SPOTIFY_API: &str = "https://api.spotify.com/v1";
+const SPOTIFY_TOKEN_URL: &str = "https://accounts.spotify.com/api/token";
+const ITUNES_LOOKUP: &str = "https://itunes.apple.com/lookup";
+
+/// How long resolved links are cached.
+const LINK_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How many resolved links are cached at once.
+const LINK_CAPACITY: usize = 500;
+
+/// How long to wait for either API.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Most tracks Spotify returns per album page.
+const ALBUM_PAGE: usize = 50;
+
+/// Most tracks Spotify returns per playlist page.
+const PLAYLIST_PAGE: usize = 100;
+
+/// Most tracks the iTunes API returns for an album.
+const ITUNES_LIMIT: usize = 200;
+
+/// Where a link points.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Service {
+    Spotify,
+    AppleMusic,
+}
+
+/// What a link points to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkKind {
+    Track,
+    Album,
+    Playlist,
+}
+
+/// A Spotify or Apple Music link.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrackLink {
+    pub service: Service,
+    pub kind: LinkKind,
+    pub id: String,
+}
+
+impl TrackLink {
+    /// A name for the link like `Spotify playlist`.
+    pub fn describe(&self) -> String {
+        let service = match self.service {
+            Service::Spotify => "Spotify",
+            Service::AppleMusic => "Apple Music",
+        };
+        let kind = match self.kind {
+            LinkKind::Track => "track",
+            LinkKind::Album => "album",
+            LinkKind::Playlist => "playlist",
+        };
+        format!("{} {}", service, kind)
+    }
+
+    fn cache_key(&self) -> String {
+        format!("{:?}:{:?}:{}", self.service, self.kind, self.id)
+    }
+}
+
+/// Parse a Spotify link or URI, or an Apple Music link.
+pub fn parse_link(input: &str) -> Option<TrackLink> {
+    let input = input.trim().trim_matches(|c| c == '<' || c == '>');
+    if let Some(uri) = input.strip_prefix("spotify:") {
+        let (kind, id) = uri.split_once(':')?;
+        return spotify_link(kind, id);
+    }
+
+    let url = Url::parse(input).ok()?;
+    let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+    match url.host_str()? {
+        "open.spotify.com" => {
+            // Links can carry a locale first, like `/intl-de/track/<id>`
+            let segments = match segments.first() {
+                Some(first) if first.starts_with("intl-") => &segments[1..],
+                _ => &segments[..],
+            };
+            match segments {
+                [kind, id] => spotify_link(kind, id),
+                _ => None,
+            }
+        }
+        "music.apple.com" | "geo.music.apple.com" => {
+            // `/<country>/<kind>/<slug>/<id>`, or `/<country>/<kind>/<id>`
+            let (kind, id) = match segments.as_slice() {
+                [_, kind, _, id] | [_, kind, id] => (*kind, *id),
+                _ => return None,
+            };
+            let track = url
+                .query_pairs()
+                .find(|(key, _)| key == "i")
+                .map(|(_, id)| id.into_owned());
+            let (kind, id) = match (kind, track) {
+                ("album", Some(track)) => (LinkKind::Track, track),
+                ("album", None) => (LinkKind::Album, id.to_string()),
+                ("song", _) => (LinkKind::Track, id.to_string()),
+                ("playlist", _) => (LinkKind::Playlist, id.to_string()),
+                _ => return None,
+            };
+            let valid = match kind {
+                LinkKind::Playlist => id.starts_with("pl."),
+                _ => id.chars().all(|c| c.is_ascii_digit()),
+            };
+            valid.then_some(TrackLink {
+                service: Service::AppleMusic,
+                kind,
+                id,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn spotify_link(kind: &str, id: &str) -> Option<TrackLink> {
+    let kind = match kind {
+        "track" => LinkKind::Track,
+        "album" => LinkKind::Album,
+        "playlist" => LinkKind::Playlist,
+        _ => return None,
+    };
+    let valid = !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric());
+    valid.then(|| TrackLink {
+        service: Service::Spotify,
+        kind,
+        id: id.to_string(),
+    })
+}
+
+/// A track a link resolved to.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResolvedTrack {
+    pub title: String,
+    pub artist: String,
+    pub length_ms: u64,
+}
+
+impl ResolvedTrack {
+    /// What to search the audio source for.
+    pub fn query(&self) -> String {
+        format!("{} - {}", self.artist, self.title)
+    }
+
+    /// The track's length.
+    pub fn length(&self) -> Duration {
+        Duration::from_millis(self.length_ms)
+    }
+}
+
+/// One page of a link's tracks.
+pub struct Page {
+    pub tracks: Vec<ResolvedTrack>,
+    /// How many tracks the link has in all.
+    pub total: usize,
+    /// Offset of the next page, if there is one.
+    pub next: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct SpotifyToken {
+    access_token: String,
+    expires_in: u64,
+}
+
+#[derive(Deserialize)]
+struct SpotifyArtist {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct SpotifyTrack {
+    name: String,
+    #[serde(default)]
+    artists: Vec<SpotifyArtist>,
+    #[serde(default)]
+    duration_ms: u64,
+}
+
+impl From<SpotifyTrack> for ResolvedTrack {
+    fn from(track: SpotifyTrack) -> Self {
+        let artist = track
+            .artists
+            .into_iter()
+            .map(|artist| artist.name)
+            .collect::<Vec<_>>()
+            .join(", ");
+        Self {
+            title: track.name,
+            artist,
+            length_ms: track.duration_ms,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SpotifyPage<T> {
+    #[serde(default = "Vec::new")]
+    items: Vec<T>,
+    total: usize,
+}
+
+/// A playlist entry, whose track is missing for removed and local tracks.
+#[derive(Deserialize)]
+struct PlaylistItem {
+    #[serde(default)]
+    track: Option<SpotifyTrack>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ItunesResult {
+    wrapper_type: String,
+    #[serde(default)]
+    track_name: Option<String>,
+    #[serde(default)]
+    artist_name: Option<String>,
+    #[serde(default)]
+    track_time_millis: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct ItunesResponse {
+    #[serde(default)]
+    results: Vec<ItunesResult>,
+}
+
+/// Resolves links, keeping a Spotify access token and caching resolved links.
+pub struct TrackLinks {
+    client: reqwest::Client,
+    spotify: Option<(String, String)>,
+    token: Mutex<Option<(String, Instant)>>,
+    resolved: TtlCache<Vec<ResolvedTrack>>,
+}
+
+impl Default for TrackLinks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TrackLinks {
+    /// Create a resolver, reading the Spotify credentials if they're set.
+    pub fn new() -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        let spotify = secrets::get("spotify_client_id").zip(secrets::get("spotify_client_secret"));
+        Self {
+            client,
+            spotify,
+            token: Mutex::new(None),
+            resolved: TtlCache::new(LINK_TTL, LINK_CAPACITY),
+        }
+    }
+
+    /// Whether links to a service can be resolved.
+    pub fn supports(&self, link: &TrackLink) -> Result<(), &'static str> {
+        match link {
+            TrackLink {
+                service: Service::Spotify,
+                ..
+            } if self.spotify.is_none() => Err(
+                "Spotify links aren't set up. The bot owner needs to set Spotify API credentials.",
+            ),
+            TrackLink {
+                service: Service::AppleMusic,
+                kind: LinkKind::Playlist,
+                ..
+            } => Err("Apple Music playlists can't be looked up, only songs and albums."),
+            _ => Ok(()),
+        }
+    }
+
+    /// The tracks of a link resolved earlier.
+    pub async fn cached(&self, link: &TrackLink) -> Option<Vec<ResolvedTrack>> {
+        self.resolved.get(&link.cache_key()).await
+    }
+
+    /// Cache the tracks a link resolved to, once all its pages were fetched.
+    pub async fn remember(&self, link: &TrackLink, tracks: Vec<ResolvedTrack>) {
+        self.resolved.insert(link.cache_key(), tracks).await;
+    }
+
+    /// Fetch a page of a link's tracks from `offset`, or `None` if the link
+    /// doesn't exist.
+    pub async fn page(&self, link: &TrackLink, offset: usize) -> CommandResult<Option<Page>> {
+        match link.service {
+            Service::Spotify => self.spotify_page(link, offset).await,
+            Service::AppleMusic => self.itunes_page(link).await,
+        }
+    }
+
+    /// A Spotify access token, fetched again shortly before it expires.
+    async fn spotify_token(&self) -> CommandResult<String> {
+        let (id, secret) = self
+            .spotify
+            .as_ref()
+            .ok_or("Spotify API credentials aren't set")?;
+        let mut token = self.token.lock().await;
+        if let Some((token, expires)) = token.as_ref() {
+            if Instant::now() < *expires {
+                return Ok(token.clone());
+            }
+        }
+        let fetched: SpotifyToken = self
+            .client
+            .post(SPOTIFY_TOKEN_URL)
+            .basic_auth(id, Some(secret))
+            .form(&[("grant_type", "client_credentials")])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let lifetime = Duration::from_secs(fetched.expires_in.saturating_sub(60));
+        *token = Some((fetched.access_token.clone(), Instant::now() + lifetime));
+        Ok(fetched.access_token)
+    }
+
+    /// Call the Spotify API, or `None` if what was asked for doesn't exist.
+    async fn spotify<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> CommandResult<Option<T>> {
+        let token = self.spotify_token().await?;
+        let response = self
+            .client
+            .get(format!("{}/{}", SPOTIFY_API, path))
+            .bearer_auth(token)
+            .query(query)
+            .send()
+            .await?;
+        if matches!(
+            response.status(),
+            StatusCode::NOT_FOUND | StatusCode::BAD_REQUEST
+        ) {
+            return Ok(None);
+        }
+        Ok(Some(response.error_for_status()?.json().await?))
+    }
+
+    async fn spotify_page(&self, link: &TrackLink, offset: usize) -> CommandResult<Option<Page>> {
+        let (tracks, total, size) = match link.kind {
+            LinkKind::Track => {
+                let track: Option<SpotifyTrack> =
+                    self.spotify(&format!("tracks/{}", link.id), &[]).await?;
+                return Ok(track.map(|track| Page {
+                    tracks: vec![track.into()],
+                    total: 1,
+                    next: None,
+                }));
+            }
+            LinkKind::Album => {
+                let page: Option<SpotifyPage<SpotifyTrack>> = self
+                    .spotify(
+                        &format!("albums/{}/tracks", link.id),
+                        &[
+                            ("limit", ALBUM_PAGE.to_string()),
+                            ("offset", offset.to_string()),
+                        ],
+                    )
+                    .await?;
+                match page {
+                    Some(page) => (
+                        page.items.into_iter().map(Into::into).collect(),
+                        page.total,
+                        ALBUM_PAGE,
+                    ),
+                    None => return Ok(None),
+                }
+            }
+            LinkKind::Playlist => {
+                let page: Option<SpotifyPage<PlaylistItem>> = self
+                    .spotify(
+                        &format!("playlists/{}/tracks", link.id),
+                        &[
+                            ("limit", PLAYLIST_PAGE.to_string()),
+                            ("offset", offset.to_string()),
+                            (
+                                "fields",
+                                "total,items(track(name,duration_ms,artists(name)))".to_string(),
+                            ),
+                        ],
+                    )
+                    .await?;
+                match page {
+                    Some(page) => (
+                        page.items
+                            .into_iter()
+                            .filter_map(|item| item.track)
+                            .map(Into::into)
+                            .collect(),
+                        page.total,
+                        PLAYLIST_PAGE,
+                    ),
+                    None => return Ok(None),
+                }
+            }
+        };
+        let next = Some(offset + size).filter(|next| *next < total);
+        Ok(Some(Page {
+            tracks,
+            total,
+            next,
+        }))
+    }
+
+    async fn itunes_page(&self, link: &TrackLink) -> CommandResult<Option<Page>> {
+        let mut request = self
+            .client
+            .get(ITUNES_LOOKUP)
+            .query(&[("id", link.id.as_str())]);
+        if link.kind == LinkKind::Album {
+            request = request.query(&[("entity", "song"), ("limit", &ITUNES_LIMIT.to_string())]);
+        }
+        let response: ItunesResponse = request.send().await?.error_for_status()?.json().await?;
+        let tracks: Vec<ResolvedTrack> = response
+            .results
+            .into_iter()
+            .filter(|result| result.wrapper_type == "track")
+            .filter_map(|result| {
+                Some(ResolvedTrack {
+                    title: result.track_name?,
+                    artist: result.artist_name.unwrap_or_default(),
+                    length_ms: result.track_time_millis.unwrap_or_default(),
+                })
+            })
+            .collect();
+        if tracks.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Page {
+            total: tracks.len(),
+            tracks,
+            next: None,
+        }))
+    }
+}
+
+/// Key for storing the link resolver in the client data.
+pub struct TrackLinksKey;
+
+impl TypeMapKey for TrackLinksKey {
+    type Value = Arc<TrackLinks>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_links() {
+        let link = |service, kind, id: &str| {
+            Some(TrackLink {
+                service,
+                kind,
+                id: id.to_string(),
+            })
+        };
+        assert_eq!(
+            parse_link("https://open.spotify.com/track/4uLU6hMCjMI75M1A2tKUQC?si=abc"),
+            link(Service::Spotify, LinkKind::Track, "4uLU6hMCjMI75M1A2tKUQC")
+        );
+        assert_eq!(
+            parse_link("<https://open.spotify.com/intl-de/playlist/37i9dQZF1DXcBWIGoYBM5M>"),
+            link(
+                Service::Spotify,
+                LinkKind::Playlist,
+                "37i9dQZF1DXcBWIGoYBM5M"
+            )
+        );
+        assert_eq!(
+            parse_link("spotify:album:1DFixLWuPkv3KT3TnV35m3"),
+            link(Service::Spotify, LinkKind::Album, "1DFixLWuPkv3KT3TnV35m3")
+        );
+        assert_eq!(
+            parse_link("https://music.apple.com/us/album/folklore/1528112358?i=1528112361"),
+            link(Service::AppleMusic, LinkKind::Track, "1528112361")
+        );
+        assert_eq!(
+            parse_link("https://music.apple.com/us/album/folklore/1528112358"),
+            link(Service::AppleMusic, LinkKind::Album, "1528112358")
+        );
+        assert_eq!(parse_link("https://open.spotify.com/artist/123"), None);
+        assert_eq!(parse_link("never gonna give you up"), None);
+    }
+}
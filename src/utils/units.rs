@@ -0,0 +1,368 @@
+//! Unit conversion and parsing of conversion queries such as `5 mi to km`.
+
+use std::fmt;
+
+/// What a unit measures. Only units of the same dimension convert to each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dimension {
+    Length,
+    Mass,
+    Volume,
+    Area,
+    Temperature,
+    Speed,
+    Time,
+    Data,
+}
+
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Length => "length",
+            Self::Mass => "mass",
+            Self::Volume => "volume",
+            Self::Area => "area",
+            Self::Temperature => "temperature",
+            Self::Speed => "speed",
+            Self::Time => "time",
+            Self::Data => "data size",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A unit, converted to its dimension's base unit with `(value + offset) * factor`.
+#[derive(Debug)]
+pub struct Unit {
+    /// The symbol shown in results.
+    pub symbol: &'static str,
+    /// Names and symbols accepted in queries, in lowercase.
+    names: &'static [&'static str],
+    pub dimension: Dimension,
+    factor: f64,
+    offset: f64,
+}
+
+macro_rules! unit {
+    ($symbol:expr, [$($name:expr),*], $dimension:ident, $factor:expr) => {
+        unit!($symbol, [$($name),*], $dimension, $factor, 0.0)
+    };
+    ($symbol:expr, [$($name:expr),*], $dimension:ident, $factor:expr, $offset:expr) => {
+        Unit {
+            symbol: $symbol,
+            names: &[$($name),*],
+            dimension: Dimension::$dimension,
+            factor: $factor,
+            offset: $offset,
+        }
+    };
+}
+
+/// Every unit, by dimension. Base units are metres, kilograms, litres, square
+/// metres, kelvin, metres per second, seconds and bytes.
+const UNITS: &[Unit] = &[
+    unit!(
+        "m",
+        ["m", "meter", "meters", "metre", "metres"],
+        Length,
+        1.0
+    ),
+    unit!(
+        "km",
+        ["km", "kilometer", "kilometers", "kilometre", "kilometres"],
+        Length,
+        1_000.0
+    ),
+    unit!(
+        "cm",
+        [
+            "cm",
+            "centimeter",
+            "centimeters",
+            "centimetre",
+            "centimetres"
+        ],
+        Length,
+        0.01
+    ),
+    unit!(
+        "mm",
+        [
+            "mm",
+            "millimeter",
+            "millimeters",
+            "millimetre",
+            "millimetres"
+        ],
+        Length,
+        0.001
+    ),
+    unit!("mi", ["mi", "mile", "miles"], Length, 1_609.344),
+    unit!("yd", ["yd", "yard", "yards"], Length, 0.9144),
+    unit!("ft", ["ft", "foot", "feet", "'"], Length, 0.3048),
+    unit!("in", ["in", "inch", "inches", "\""], Length, 0.0254),
+    unit!(
+        "nmi",
+        ["nmi", "nautical mile", "nautical miles"],
+        Length,
+        1_852.0
+    ),
+    unit!(
+        "kg",
+        ["kg", "kilogram", "kilograms", "kilo", "kilos"],
+        Mass,
+        1.0
+    ),
+    unit!("g", ["g", "gram", "grams"], Mass, 0.001),
+    unit!("mg", ["mg", "milligram", "milligrams"], Mass, 0.000_001),
+    unit!("t", ["t", "tonne", "tonnes", "ton", "tons"], Mass, 1_000.0),
+    unit!("lb", ["lb", "lbs", "pound", "pounds"], Mass, 0.453_592_37),
+    unit!("oz", ["oz", "ounce", "ounces"], Mass, 0.028_349_523_125),
+    unit!("st", ["st", "stone", "stones"], Mass, 6.350_293_18),
+    unit!(
+        "L",
+        ["l", "liter", "liters", "litre", "litres"],
+        Volume,
+        1.0
+    ),
+    unit!(
+        "mL",
+        [
+            "ml",
+            "milliliter",
+            "milliliters",
+            "millilitre",
+            "millilitres"
+        ],
+        Volume,
+        0.001
+    ),
+    unit!(
+        "m³",
+        ["m3", "m³", "cubic meter", "cubic meters"],
+        Volume,
+        1_000.0
+    ),
+    unit!("gal", ["gal", "gallon", "gallons"], Volume, 3.785_411_784),
+    unit!("qt", ["qt", "quart", "quarts"], Volume, 0.946_352_946),
+    unit!("pt", ["pt", "pint", "pints"], Volume, 0.473_176_473),
+    unit!("cup", ["cup", "cups"], Volume, 0.236_588_236_5),
+    unit!(
+        "fl oz",
+        ["floz", "fl oz", "fluid ounce", "fluid ounces"],
+        Volume,
+        0.029_573_529_562_5
+    ),
+    unit!(
+        "m²",
+        ["m2", "m²", "square meter", "square meters"],
+        Area,
+        1.0
+    ),
+    unit!(
+        "km²",
+        ["km2", "km²", "square kilometer", "square kilometers"],
+        Area,
+        1_000_000.0
+    ),
+    unit!(
+        "ft²",
+        ["ft2", "ft²", "square foot", "square feet"],
+        Area,
+        0.092_903_04
+    ),
+    unit!(
+        "mi²",
+        ["mi2", "mi²", "square mile", "square miles"],
+        Area,
+        2_589_988.110_336
+    ),
+    unit!("ha", ["ha", "hectare", "hectares"], Area, 10_000.0),
+    unit!("acre", ["acre", "acres", "ac"], Area, 4_046.856_422_4),
+    unit!("°C", ["c", "°c", "celsius"], Temperature, 1.0, 273.15),
+    unit!(
+        "°F",
+        ["f", "°f", "fahrenheit"],
+        Temperature,
+        5.0 / 9.0,
+        459.67
+    ),
+    unit!("K", ["k", "kelvin"], Temperature, 1.0),
+    unit!("m/s", ["m/s", "mps"], Speed, 1.0),
+    unit!("km/h", ["km/h", "kmh", "kph"], Speed, 1.0 / 3.6),
+    unit!("mph", ["mph", "mi/h"], Speed, 0.447_04),
+    unit!("kn", ["kn", "knot", "knots"], Speed, 1_852.0 / 3_600.0),
+    unit!("ms", ["ms", "millisecond", "milliseconds"], Time, 0.001),
+    unit!("s", ["s", "sec", "secs", "second", "seconds"], Time, 1.0),
+    unit!("min", ["min", "mins", "minute", "minutes"], Time, 60.0),
+    unit!("h", ["h", "hr", "hrs", "hour", "hours"], Time, 3_600.0),
+    unit!("d", ["d", "day", "days"], Time, 86_400.0),
+    unit!("wk", ["wk", "week", "weeks"], Time, 604_800.0),
+    unit!("yr", ["yr", "year", "years"], Time, 31_557_600.0),
+    unit!("B", ["b", "byte", "bytes"], Data, 1.0),
+    unit!("KB", ["kb", "kilobyte", "kilobytes"], Data, 1e3),
+    unit!("MB", ["mb", "megabyte", "megabytes"], Data, 1e6),
+    unit!("GB", ["gb", "gigabyte", "gigabytes"], Data, 1e9),
+    unit!("TB", ["tb", "terabyte", "terabytes"], Data, 1e12),
+    unit!("KiB", ["kib", "kibibyte", "kibibytes"], Data, 1_024.0),
+    unit!("MiB", ["mib", "mebibyte", "mebibytes"], Data, 1_048_576.0),
+    unit!(
+        "GiB",
+        ["gib", "gibibyte", "gibibytes"],
+        Data,
+        1_073_741_824.0
+    ),
+    unit!(
+        "TiB",
+        ["tib", "tebibyte", "tebibytes"],
+        Data,
+        1_099_511_627_776.0
+    ),
+];
+
+/// Find a unit by name or symbol, ignoring case.
+pub fn find_unit(name: &str) -> Option<&'static Unit> {
+    let name = name.trim().to_lowercase();
+    UNITS
+        .iter()
+        .find(|unit| unit.names.contains(&name.as_str()))
+}
+
+/// Convert a value between units, or `None` if they measure different things.
+pub fn convert(value: f64, from: &Unit, to: &Unit) -> Option<f64> {
+    if from.dimension != to.dimension {
+        return None;
+    }
+    let base = (value + from.offset) * from.factor;
+    Some(base / to.factor - to.offset)
+}
+
+/// A parsed query such as `100 USD to EUR`. The units are left as written, since
+/// they may be units, currencies or coins.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Query {
+    pub amount: f64,
+    pub from: String,
+    pub to: String,
+}
+
+/// Parse a number, allowing thousands separators.
+pub fn parse_amount(input: &str) -> Option<f64> {
+    let value: f64 = input.trim().replace(['_', ','], "").parse().ok()?;
+    value.is_finite().then_some(value)
+}
+
+/// Parse a query of the form `<amount> <from> to|in|-> <to>`. The amount can be
+/// left out to convert 1, and can be written against the unit, as in `5km`.
+pub fn parse_query(input: &str) -> Result<Query, String> {
+    const USAGE: &str = "Write conversions like `100 USD to EUR` or `5 mi in km`.";
+
+    let words: Vec<&str> = input.split_whitespace().collect();
+    // Split on the last separator with something after it, since `in` is also a unit
+    let split = (1..words.len().saturating_sub(1))
+        .rev()
+        .find(|i| matches!(words[*i].to_lowercase().as_str(), "to" | "in" | "->" | "="))
+        .ok_or(USAGE)?;
+    let to = words[split + 1..].join(" ");
+    let left = &words[..split];
+
+    let (amount, from) = match parse_amount(left[0]) {
+        Some(amount) if left.len() > 1 => (amount, left[1..].join(" ")),
+        Some(_) => return Err(USAGE.to_string()),
+        None => {
+            // `5km`, or no amount at all
+            let first = left[0];
+            let digits = first
+                .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | ',' | '-' | '+')))
+                .unwrap_or(first.len());
+            if digits == 0 {
+                (1.0, left.join(" "))
+            } else {
+                let amount = parse_amount(&first[..digits]).ok_or(USAGE)?;
+                let mut from = first[digits..].to_string();
+                for word in &left[1..] {
+                    from.push(' ');
+                    from.push_str(word);
+                }
+                (amount, from)
+            }
+        }
+    };
+    Ok(Query { amount, from, to })
+}
+
+/// Format a result with up to `decimals` decimals, dropping trailing zeros and
+/// switching to scientific notation for very large or small values.
+pub fn format_number(value: f64, decimals: usize) -> String {
+    let magnitude = value.abs();
+    if magnitude != 0.0 && !(1e-4..1e15).contains(&magnitude) {
+        return format!("{:.4e}", value);
+    }
+    let fixed = format!("{:.*}", decimals, value);
+    let fixed = if fixed.contains('.') {
+        fixed.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        &fixed
+    };
+    let (sign, fixed) = match fixed.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", fixed),
+    };
+    let (whole, fraction) = match fixed.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (fixed, None),
+    };
+    let whole = whole
+        .parse::<u64>()
+        .map(crate::utils::helpers::format_count)
+        .unwrap_or_else(|_| whole.to_string());
+    match fraction {
+        Some(fraction) => format!("{}{}.{}", sign, whole, fraction),
+        None => format!("{}{}", sign, whole),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert_named(value: f64, from: &str, to: &str) -> Option<f64> {
+        convert(value, find_unit(from)?, find_unit(to)?)
+    }
+
+    #[test]
+    fn converts_units() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-6;
+        assert!(close(convert_named(5.0, "mi", "km").unwrap(), 8.04672));
+        assert!(close(convert_named(100.0, "C", "F").unwrap(), 212.0));
+        assert!(close(convert_named(0.0, "K", "celsius").unwrap(), -273.15));
+        assert!(close(convert_named(1.0, "GiB", "MB").unwrap(), 1073.741824));
+        assert_eq!(convert_named(1.0, "kg", "km"), None);
+    }
+
+    #[test]
+    fn parses_queries() {
+        let query = |amount, from: &str, to: &str| Query {
+            amount,
+            from: from.to_string(),
+            to: to.to_string(),
+        };
+        assert_eq!(
+            parse_query("100 USD to EUR"),
+            Ok(query(100.0, "USD", "EUR"))
+        );
+        assert_eq!(parse_query("5 ft in in"), Ok(query(5.0, "ft", "in")));
+        assert_eq!(parse_query("5km to mi"), Ok(query(5.0, "km", "mi")));
+        assert_eq!(parse_query("1,500 lb -> kg"), Ok(query(1500.0, "lb", "kg")));
+        assert_eq!(parse_query("btc to usd"), Ok(query(1.0, "btc", "usd")));
+        assert!(parse_query("100 USD").is_err());
+    }
+
+    #[test]
+    fn formats_numbers() {
+        assert_eq!(format_number(1234567.891, 2), "1,234,567.89");
+        assert_eq!(format_number(2.5, 4), "2.5");
+        assert_eq!(format_number(-3.0, 2), "-3");
+        assert_eq!(format_number(0.00001234, 4), "1.2340e-5");
+    }
+}
@@ -0,0 +1,231 @@
+//! Voice channel helpers for the music commands.
+//!
+//! Audio is sent through songbird. Every source is decoded by an ffmpeg process
+//! into the 48 kHz stereo float samples songbird mixes, and tracks are found
+//! with yt-dlp, so both have to be on the `PATH` wherever the bot plays music.
+
+use serde::Deserialize;
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, GuildId, UserId};
+use serenity::prelude::*;
+use songbird::input::{children_to_reader, Codec, Container, Input, Metadata};
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::io;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use crate::models::music::MusicSettings;
+use crate::utils::helpers::author_permissions;
+
+/// The program that finds tracks and where their audio is.
+const YT_DLP: &str = "yt-dlp";
+
+/// How long a yt-dlp lookup may take.
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The voice channel a member is connected to, if any.
+pub fn voice_channel_of(ctx: &Context, guild_id: GuildId, user_id: UserId) -> Option<ChannelId> {
+    ctx.cache
+        .guild_field(guild_id, |guild| {
+            guild
+                .voice_states
+                .get(&user_id)
+                .and_then(|state| state.channel_id)
+        })
+        .flatten()
+}
+
+/// The members connected to a voice channel, bots excluded.
+pub fn listeners(ctx: &Context, guild_id: GuildId, channel_id: ChannelId) -> Vec<UserId> {
+    ctx.cache
+        .guild_field(guild_id, |guild| {
+            guild
+                .voice_states
+                .values()
+                .filter(|state| state.channel_id == Some(channel_id))
+                .filter(|state| {
+                    let bot = state
+                        .member
+                        .as_ref()
+                        .map(|member| member.user.bot)
+                        .or_else(|| guild.members.get(&state.user_id).map(|m| m.user.bot));
+                    !bot.unwrap_or(false)
+                })
+                .map(|state| state.user_id)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Whether a message's author is a DJ: they have the DJ role or Manage Server.
+pub async fn is_dj(ctx: &Context, msg: &Message, settings: &MusicSettings) -> bool {
+    let manage_guild = author_permissions(ctx, msg)
+        .await
+        .is_some_and(|permissions| permissions.manage_guild());
+    let roles = msg
+        .member
+        .as_ref()
+        .map(|member| member.roles.as_slice())
+        .unwrap_or_default();
+    settings.is_dj(roles, manage_guild)
+}
+
+/// Whether a message's author can skip, stop or clear the queue without a vote,
+/// with `listeners` members in the voice channel.
+pub async fn can_control(
+    ctx: &Context,
+    msg: &Message,
+    settings: &MusicSettings,
+    listeners: usize,
+) -> bool {
+    listeners <= 1 || is_dj(ctx, msg, settings).await
+}
+
+/// What yt-dlp found for a search or a page.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Media {
+    pub title: String,
+    /// The song's artist and name, when the site lists them.
+    #[serde(default)]
+    pub artist: Option<String>,
+    #[serde(default)]
+    pub track: Option<String>,
+    /// Length in seconds, missing for live streams.
+    #[serde(default)]
+    pub duration: Option<f64>,
+    /// The page the audio is on.
+    #[serde(default)]
+    pub webpage_url: Option<String>,
+    /// Where the audio itself can be downloaded.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Headers the download needs.
+    #[serde(default)]
+    pub http_headers: BTreeMap<String, String>,
+}
+
+impl Media {
+    /// How long the audio is, if it isn't live.
+    pub fn length(&self) -> Option<Duration> {
+        self.duration
+            .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+            .map(Duration::from_secs_f64)
+    }
+}
+
+/// Look up a page with yt-dlp, or search YouTube for anything that isn't a
+/// URL. Returns `None` if nothing was found.
+pub async fn lookup(query: &str) -> io::Result<Option<Media>> {
+    let target = if query.starts_with("http://") || query.starts_with("https://") {
+        query.to_string()
+    } else {
+        format!("ytsearch1:{}", query)
+    };
+    let output = tokio::process::Command::new(YT_DLP)
+        .args([
+            "-j",
+            "-f",
+            "bestaudio/best",
+            "--no-playlist",
+            "--no-warnings",
+        ])
+        .arg("--")
+        .arg(&target)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(LOOKUP_TIMEOUT, output)
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "yt-dlp took too long"))??;
+
+    // yt-dlp prints nothing for a search without results or a page it can't read
+    match output
+        .stdout
+        .split(|byte| *byte == b'\n')
+        .find(|line| !line.is_empty())
+    {
+        Some(line) => serde_json::from_slice(line)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        None => Ok(None),
+    }
+}
+
+/// How ffmpeg reads a source.
+#[derive(Clone, Debug, Default)]
+pub struct InputOptions {
+    /// HTTP headers for a URL.
+    pub headers: BTreeMap<String, String>,
+    /// Whether the source is a URL, which ffmpeg reconnects to when the
+    /// connection drops.
+    pub network: bool,
+    /// Where in the source to start.
+    pub start: Duration,
+    /// ffmpeg filter graph the audio is run through, see
+    /// [`AudioFilters::filter_graph`](crate::models::music::AudioFilters::filter_graph).
+    pub filters: Option<String>,
+}
+
+impl InputOptions {
+    /// Options for the audio yt-dlp found.
+    pub fn for_media(media: &Media) -> Self {
+        Self {
+            headers: media.http_headers.clone(),
+            network: true,
+            start: Duration::ZERO,
+            filters: None,
+        }
+    }
+}
+
+/// Decode a file or URL with ffmpeg into an input songbird can play.
+pub fn ffmpeg(
+    source: impl AsRef<OsStr>,
+    options: &InputOptions,
+    metadata: Metadata,
+) -> io::Result<Input> {
+    let mut command = Command::new("ffmpeg");
+    command.args(["-hide_banner", "-loglevel", "error"]);
+    if options.network {
+        command.args([
+            "-reconnect",
+            "1",
+            "-reconnect_streamed",
+            "1",
+            "-reconnect_delay_max",
+            "5",
+        ]);
+    }
+    if !options.headers.is_empty() {
+        let headers: String = options
+            .headers
+            .iter()
+            .map(|(name, value)| format!("{}: {}\r\n", name, value))
+            .collect();
+        command.arg("-headers").arg(headers);
+    }
+    if !options.start.is_zero() {
+        command
+            .arg("-ss")
+            .arg(format!("{:.3}", options.start.as_secs_f64()));
+    }
+    command.arg("-i").arg(source).arg("-vn");
+    if let Some(filters) = &options.filters {
+        command.arg("-af").arg(filters);
+    }
+    let child = command
+        .args(["-f", "f32le", "-ac", "2", "-ar", "48000", "-"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    Ok(Input::new(
+        true,
+        children_to_reader::<f32>(vec![child]),
+        Codec::FloatPcm,
+        Container::Raw,
+        Some(metadata),
+    ))
+}
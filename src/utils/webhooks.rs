@@ -0,0 +1,219 @@
+//! Sending messages through bot-owned webhooks, under another name and avatar.
+//!
+//! Each channel gets one webhook, found or created the first time it's needed and
+//! cached after that. Where a webhook can't be used, such as in threads or without
+//! the Manage Webhooks permission, messages are sent by the bot instead, with the
+//! name and avatar shown as the embed author.
+
+use serenity::builder::CreateEmbed;
+use serenity::json::{hashmap_to_json_map, Value};
+use serenity::model::channel::{ChannelType, Message};
+use serenity::model::id::{ChannelId, WebhookId};
+use serenity::model::user::User;
+use serenity::model::webhook::Webhook;
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::utils::constants::DEFAULT_COLOR;
+use crate::utils::helpers::truncate;
+use crate::utils::rest;
+
+/// The name of the webhooks the bot creates.
+const WEBHOOK_NAME: &str = "Kurumi";
+
+/// The longest name a webhook message can have.
+const MAX_USERNAME_LENGTH: usize = 80;
+
+/// Who a webhook message appears to be from.
+#[derive(Clone, Debug)]
+pub struct Persona {
+    pub name: String,
+    pub avatar_url: Option<String>,
+}
+
+impl Persona {
+    /// Appear as a user, with their name and avatar.
+    pub fn of(user: &User) -> Self {
+        Self {
+            name: user.name.clone(),
+            avatar_url: Some(user.face()),
+        }
+    }
+
+    /// Add a note after the name, such as the server a message came from.
+    pub fn with_suffix(mut self, suffix: &str) -> Self {
+        self.name = format!("{} • {}", self.name, suffix);
+        self
+    }
+}
+
+/// The bot's webhooks, by channel.
+#[derive(Default)]
+pub struct Webhooks {
+    cache: Mutex<HashMap<ChannelId, Webhook>>,
+}
+
+impl Webhooks {
+    /// Create an empty webhook cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether a webhook is one of the bot's.
+    pub async fn is_own(&self, webhook_id: WebhookId) -> bool {
+        self.cache
+            .lock()
+            .await
+            .values()
+            .any(|webhook| webhook.id == webhook_id)
+    }
+
+    /// The bot's webhook in a channel, reusing one it made earlier or creating it.
+    pub async fn get(
+        &self,
+        ctx: &Context,
+        channel_id: ChannelId,
+    ) -> Result<Webhook, SerenityError> {
+        // The cache isn't held across the requests, so sends to other channels
+        // don't wait on them
+        if let Some(webhook) = self.cache.lock().await.get(&channel_id) {
+            return Ok(webhook.clone());
+        }
+
+        let bot_id = ctx.cache.current_user_id();
+        let existing = rest::call(ctx, "get_webhooks", || channel_id.webhooks(&ctx.http))
+            .await?
+            .into_iter()
+            .find(|webhook| {
+                webhook.token.is_some() && webhook.user.as_ref().is_some_and(|u| u.id == bot_id)
+            });
+        let webhook = match existing {
+            Some(webhook) => webhook,
+            None => {
+                debug!("Creating webhook in channel {}", channel_id);
+                rest::call(ctx, "create_webhook", || {
+                    channel_id.create_webhook(&ctx.http, WEBHOOK_NAME)
+                })
+                .await?
+            }
+        };
+
+        // Another send may have cached one in the meantime, which is kept so the
+        // channel keeps using a single webhook
+        let webhook = self
+            .cache
+            .lock()
+            .await
+            .entry(channel_id)
+            .or_insert(webhook)
+            .clone();
+        Ok(webhook)
+    }
+
+    /// Send a message as a persona. Mentions in the message don't ping anyone.
+    ///
+    /// Falls back to sending as the bot where a webhook can't be used.
+    pub async fn send(
+        &self,
+        ctx: &Context,
+        channel_id: ChannelId,
+        persona: &Persona,
+        content: &str,
+        embeds: Vec<CreateEmbed>,
+    ) -> Result<(), SerenityError> {
+        let is_thread = ctx.cache.guild_channel(channel_id).is_some_and(|channel| {
+            matches!(
+                channel.kind,
+                ChannelType::PublicThread | ChannelType::PrivateThread | ChannelType::NewsThread
+            )
+        });
+        if is_thread {
+            return send_as_bot(ctx, channel_id, persona, content, embeds).await;
+        }
+        let webhook = match self.get(ctx, channel_id).await {
+            Ok(webhook) => webhook,
+            Err(e) => {
+                debug!(
+                    "No webhook in channel {}, sending as the bot: {}",
+                    channel_id, e
+                );
+                return send_as_bot(ctx, channel_id, persona, content, embeds).await;
+            }
+        };
+
+        let username = truncate(&persona.name, MAX_USERNAME_LENGTH);
+        let embeds: Vec<Value> = embeds
+            .into_iter()
+            .map(|embed| Value::from(hashmap_to_json_map(embed.0)))
+            .collect();
+        let result = rest::call(ctx, "execute_webhook", || {
+            let embeds = embeds.clone();
+            webhook.execute(&ctx.http, false, |w| {
+                w.content(content)
+                    .username(&username)
+                    .embeds(embeds)
+                    .allowed_mentions(|am| am.empty_parse());
+                if let Some(avatar_url) = &persona.avatar_url {
+                    w.avatar_url(avatar_url);
+                }
+                w
+            })
+        })
+        .await;
+        if result.is_err() {
+            // The webhook may have been deleted, so look it up again next time
+            self.cache.lock().await.remove(&channel_id);
+        }
+        result.map(|_| ())
+    }
+}
+
+/// Send a message as the bot, showing the persona as the author of an embed with
+/// the content. Messages with only embeds are sent as they are.
+async fn send_as_bot(
+    ctx: &Context,
+    channel_id: ChannelId,
+    persona: &Persona,
+    content: &str,
+    embeds: Vec<CreateEmbed>,
+) -> Result<(), SerenityError> {
+    let mut all = Vec::with_capacity(embeds.len() + 1);
+    if !content.is_empty() {
+        let mut author = CreateEmbed::default();
+        author.author(|a| {
+            a.name(&persona.name);
+            if let Some(avatar_url) = &persona.avatar_url {
+                a.icon_url(avatar_url);
+            }
+            a
+        });
+        author.description(content).color(DEFAULT_COLOR);
+        all.push(author);
+    }
+    all.extend(embeds);
+
+    rest::call(ctx, "send_message", || {
+        let all = all.clone();
+        channel_id.send_message(&ctx.http, move |m| {
+            m.allowed_mentions(|am| am.empty_parse()).set_embeds(all)
+        })
+    })
+    .await
+    .map(|_: Message| ())
+}
+
+/// Key for storing the webhook cache in the client data.
+pub struct WebhooksKey;
+
+impl TypeMapKey for WebhooksKey {
+    type Value = Arc<Webhooks>;
+}
+
+/// Get the shared webhook cache, or a fresh one if it isn't in the client data.
+pub async fn webhooks(ctx: &Context) -> Arc<Webhooks> {
+    let data = ctx.data.read().await;
+    data.get::<WebhooksKey>().cloned().unwrap_or_default()
+}
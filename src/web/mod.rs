@@ -0,0 +1,116 @@
+//! A small HTTP server for webhooks from outside Discord.
+//!
+//! The server only starts when `[web] bind` is set. It serves:
+//! - `GET /health`: answers `ok` while the bot is running.
+//! - `POST /votes/topgg` and `POST /votes/dbl`: vote webhooks, see [`votes`].
+
+pub mod votes;
+
+use hyper::body::HttpBody;
+use hyper::header::AUTHORIZATION;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+use crate::models::votes::VoteSite;
+use votes::VoteWebhook;
+
+/// Largest request body accepted, in bytes.
+const MAX_BODY_SIZE: usize = 64 * 1024;
+
+/// The webhook server and what its routes need.
+pub struct WebServer {
+    addr: SocketAddr,
+    votes: VoteWebhook,
+}
+
+impl WebServer {
+    /// Create the server for an address such as `0.0.0.0:8080`.
+    pub fn new(bind: &str, votes: VoteWebhook) -> Result<Self, String> {
+        let addr = bind
+            .parse()
+            .map_err(|e| format!("Invalid [web] bind address `{}`: {}", bind, e))?;
+        Ok(Self { addr, votes })
+    }
+
+    /// Bind the address and serve requests in the background.
+    pub fn spawn(self) -> Result<JoinHandle<()>, hyper::Error> {
+        let addr = self.addr;
+        let server = Arc::new(self);
+        let make_service = make_service_fn(move |_| {
+            let server = server.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let server = server.clone();
+                    async move { Ok::<_, Infallible>(server.route(request).await) }
+                }))
+            }
+        });
+
+        let running = Server::try_bind(&addr)?.serve(make_service);
+        info!("Web server listening on {}", addr);
+        Ok(tokio::spawn(async move {
+            if let Err(e) = running.await {
+                error!("Web server stopped: {}", e);
+            }
+        }))
+    }
+
+    /// Answer a request.
+    async fn route(&self, request: Request<Body>) -> Response<Body> {
+        let site = match (request.method(), request.uri().path()) {
+            (&Method::GET, "/health") => return respond(StatusCode::OK, "ok"),
+            (&Method::POST, "/votes/topgg") => VoteSite::Topgg,
+            (&Method::POST, "/votes/dbl") => VoteSite::Dbl,
+            (_, path) => {
+                debug!("Web request to unknown route {} {}", request.method(), path);
+                return respond(StatusCode::NOT_FOUND, "not found");
+            }
+        };
+
+        let authorization = request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok());
+        if !self.votes.is_authorized(site, authorization) {
+            warn!("Refused a {} vote webhook with a bad secret", site);
+            return respond(StatusCode::UNAUTHORIZED, "unauthorized");
+        }
+
+        let body = match read_body(request.into_body()).await {
+            Some(body) => body,
+            None => return respond(StatusCode::PAYLOAD_TOO_LARGE, "body too large"),
+        };
+        match self.votes.handle(site, &body).await {
+            Ok(()) => respond(StatusCode::NO_CONTENT, ""),
+            Err(e) => {
+                warn!("Bad {} vote webhook: {}", site, e);
+                respond(StatusCode::BAD_REQUEST, "bad request")
+            }
+        }
+    }
+}
+
+/// Read a request body, or `None` if it is larger than [`MAX_BODY_SIZE`].
+async fn read_body(mut body: Body) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.ok()?;
+        if bytes.len() + chunk.len() > MAX_BODY_SIZE {
+            return None;
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Some(bytes)
+}
+
+/// A plain text response.
+fn respond(status: StatusCode, text: &'static str) -> Response<Body> {
+    let mut response = Response::new(Body::from(text));
+    *response.status_mut() = status;
+    response
+}
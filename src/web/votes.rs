@@ -0,0 +1,155 @@
+//! Vote webhooks from top.gg and discordbotlist.com.
+//!
+//! Each site signs its webhook with the secret set in `[votes]`. A vote is recorded
+//! towards the user's streak and rewarded with a premium trial for the configured
+//! duration. Test votes from top.gg are accepted but ignored.
+
+use serde::Deserialize;
+use serenity::model::id::UserId;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::models::config::VotesConfig;
+use crate::models::premium::PremiumData;
+use crate::models::votes::{VoteData, VoteSite};
+use crate::storage::JsonStore;
+use crate::utils::helpers::unix_timestamp;
+
+/// A vote webhook body. top.gg sends the voter as `user` and discordbotlist.com as
+/// `id`.
+#[derive(Debug, Deserialize)]
+struct VotePayload {
+    #[serde(alias = "id")]
+    user: UserId,
+    /// `upvote`, or `test` for test votes from top.gg.
+    #[serde(rename = "type", default)]
+    kind: Option<String>,
+}
+
+/// Checks, records and rewards votes.
+pub struct VoteWebhook {
+    config: VotesConfig,
+    votes: Arc<JsonStore<VoteData>>,
+    premium: Arc<JsonStore<PremiumData>>,
+}
+
+impl VoteWebhook {
+    /// Create the webhook with its settings and stores.
+    pub fn new(
+        config: VotesConfig,
+        votes: Arc<JsonStore<VoteData>>,
+        premium: Arc<JsonStore<PremiumData>>,
+    ) -> Self {
+        Self {
+            config,
+            votes,
+            premium,
+        }
+    }
+
+    /// Whether a request's `Authorization` header matches the site's secret. Sites
+    /// without a secret are always refused.
+    pub fn is_authorized(&self, site: VoteSite, authorization: Option<&str>) -> bool {
+        let secret = match site {
+            VoteSite::Topgg => &self.config.topgg_auth,
+            VoteSite::Dbl => &self.config.dbl_auth,
+        };
+        match authorization {
+            Some(authorization) if !secret.is_empty() => {
+                constant_time_eq(authorization.as_bytes(), secret.as_bytes())
+            }
+            _ => false,
+        }
+    }
+
+    /// Record and reward a vote from a webhook body.
+    pub async fn handle(&self, site: VoteSite, body: &[u8]) -> Result<(), String> {
+        let payload: VotePayload = serde_json::from_slice(body).map_err(|e| e.to_string())?;
+        if payload.kind.as_deref() == Some("test") {
+            info!("Received a test vote from {}", site);
+            return Ok(());
+        }
+
+        let now = unix_timestamp();
+        let streak = self
+            .votes
+            .update(|data| {
+                data.record(payload.user, site, now, self.config.streak_window)
+                    .streak
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if self.config.trial_duration > 0 {
+            let until = now + self.config.trial_duration;
+            self.premium
+                .update(|data| data.grant_trial(payload.user, self.config.trial_tier, until, now))
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+
+        info!("{} voted on {} (streak {})", payload.user, site, streak);
+        Ok(())
+    }
+}
+
+/// Compare two secrets without leaking how much of them matched through timing.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+
+    #[tokio::test]
+    async fn records_and_rewards_authorized_votes() {
+        let dir = std::env::temp_dir().join(format!("kurumi-votes-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let storage = Storage::new(dir).unwrap();
+        let config = VotesConfig {
+            topgg_auth: "secret".to_string(),
+            ..VotesConfig::default()
+        };
+        let webhook = VoteWebhook::new(
+            config,
+            Arc::new(storage.open("votes").await.unwrap()),
+            Arc::new(storage.open("premium").await.unwrap()),
+        );
+
+        assert!(webhook.is_authorized(VoteSite::Topgg, Some("secret")));
+        assert!(!webhook.is_authorized(VoteSite::Topgg, Some("secreT")));
+        assert!(!webhook.is_authorized(VoteSite::Topgg, None));
+        assert!(!webhook.is_authorized(VoteSite::Dbl, Some("")));
+
+        let test_vote = br#"{"bot": "1", "user": "5", "type": "test"}"#;
+        webhook.handle(VoteSite::Topgg, test_vote).await.unwrap();
+        assert!(webhook.votes.read().await.voter(UserId(5)).is_none());
+
+        webhook
+            .handle(
+                VoteSite::Topgg,
+                br#"{"bot": "1", "user": "5", "type": "upvote"}"#,
+            )
+            .await
+            .unwrap();
+        webhook
+            .handle(VoteSite::Dbl, br#"{"id": "5", "username": "kurumi"}"#)
+            .await
+            .unwrap();
+        assert!(webhook.handle(VoteSite::Dbl, b"{}").await.is_err());
+
+        let now = unix_timestamp();
+        assert_eq!(
+            webhook.votes.read().await.voter(UserId(5)).unwrap().streak,
+            2
+        );
+        assert!(webhook
+            .premium
+            .read()
+            .await
+            .user_tier(UserId(5), now)
+            .is_premium());
+    }
+}